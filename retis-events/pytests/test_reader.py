@@ -58,3 +58,28 @@ def test_event_File():
 
     with pytest.raises(Exception):
         sf.events()
+
+
+def test_event_reader_merge():
+    """Test EventReader can merge the events of multiple files"""
+    count = sum(1 for _ in EventReader("test_data/test_events.json"))
+
+    r = EventReader.merge(["test_data/test_events.json"] * 2, [1000])
+    verify_event_reader(r)
+
+    # Only the startup event of the first file is kept.
+    r = EventReader.merge(["test_data/test_events.json"] * 2)
+    assert sum(1 for _ in r) == 2 * count - 1
+
+    with pytest.raises(Exception):
+        EventReader.merge(["test_data/test_events.json"], [0, 0])
+
+
+def test_startup():
+    """Test the startup event can be retrieved"""
+    f = EventFile("test_data/test_events.json")
+    startup = f.startup()
+    assert startup.startup
+    assert startup.startup.retis_version
+
+    assert EventReader("test_data/test_events.json").startup().startup
@@ -0,0 +1,70 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// Bridge section.
+#[event_section]
+#[derive(Default)]
+pub struct BridgeEvent {
+    /// Operation: "forward" (the frame is forwarded to a known port), "flood"
+    /// (the frame is flooded to all ports), "local" (the frame is delivered to
+    /// the bridge device itself), "learn" (an address was learned on a port)
+    /// or "stp" (the STP state of a port changed).
+    pub op: String,
+    /// Name of the bridge device, if known.
+    pub bridge: Option<String>,
+    /// Port the frame is forwarded to, the address was learned on, or which
+    /// state changed.
+    pub port: Option<BridgePort>,
+    /// Type of the flooded frame: "unicast" (unknown destination),
+    /// "multicast" or "broadcast".
+    pub pkt_type: Option<String>,
+    /// Learned MAC address.
+    pub mac: Option<String>,
+    /// VLAN id of the learned address, if any.
+    pub vid: Option<u16>,
+}
+
+/// Bridge port.
+#[event_type]
+#[derive(Default)]
+pub struct BridgePort {
+    /// Name of the port device.
+    pub name: String,
+    /// Port number.
+    pub no: u16,
+    /// STP state of the port, e.g. "forwarding". For STP operations this is
+    /// the new state.
+    pub state: String,
+}
+
+impl EventFmt for BridgeEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "bridge")?;
+
+        if let Some(bridge) = &self.bridge {
+            write!(f, " {bridge}")?;
+        }
+
+        write!(f, " {}", self.op)?;
+
+        if let Some(pkt_type) = &self.pkt_type {
+            write!(f, " {pkt_type}")?;
+        }
+
+        if let Some(mac) = &self.mac {
+            write!(f, " {mac}")?;
+        }
+
+        if let Some(vid) = self.vid {
+            write!(f, " vid {vid}")?;
+        }
+
+        if let Some(port) = &self.port {
+            write!(f, " port {} ({}) {}", port.name, port.no, port.state)?;
+        }
+
+        Ok(())
+    }
+}
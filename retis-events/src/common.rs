@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{collections::BTreeMap, fmt};
 
 use crate::{file::rotate::RotationPolicy, helpers::time::*, *};
 
@@ -11,6 +11,126 @@ pub struct MachineInfo {
     pub kernel_version: String,
     /// Hardware name.
     pub hardware_name: String,
+    /// Kernel build id, if available.
+    pub kernel_build_id: Option<String>,
+}
+
+/// Collection information, describing how events were collected.
+#[event_type]
+pub struct CollectionInfo {
+    /// Collection start time (CLOCK_MONOTONIC, in nanoseconds).
+    pub start_time: u64,
+    /// Probes attached when the collection started.
+    pub probes: Vec<String>,
+    /// Filters in effect.
+    pub filters: Vec<String>,
+    /// Containers running when the collection started, if container
+    /// enrichment was enabled.
+    pub containers: Option<Vec<ContainerInfo>>,
+    /// Kubernetes objects known when the collection started, if the
+    /// Kubernetes mode was enabled.
+    pub kube: Option<KubeInfo>,
+}
+
+/// Container information, as reported by its runtime. Used to map the netns
+/// and cgroup ids found in events to containers.
+#[event_type]
+pub struct ContainerInfo {
+    /// Container id.
+    pub id: String,
+    /// Container name.
+    pub name: String,
+    /// Container runtime the container was retrieved from, e.g. "podman",
+    /// "docker" or "crictl".
+    pub runtime: String,
+    /// Name of the pod the container is part of, if any.
+    pub pod: Option<String>,
+    /// Namespace of the pod the container is part of, if any.
+    pub pod_namespace: Option<String>,
+    /// Pid of the container init process, as seen from the host.
+    pub pid: Option<i32>,
+    /// Netns inum of the container.
+    pub netns: Option<u32>,
+    /// Id of the cgroup (v2) of the container.
+    pub cgroup_id: Option<u64>,
+}
+
+impl ContainerInfo {
+    /// Name of the container, including its pod if any (e.g.
+    /// "namespace/pod/container").
+    pub fn full_name(&self) -> String {
+        match (&self.pod_namespace, &self.pod) {
+            (Some(ns), Some(pod)) => format!("{ns}/{pod}/{}", self.name),
+            (None, Some(pod)) => format!("{pod}/{}", self.name),
+            _ => self.name.clone(),
+        }
+    }
+
+    /// Name of the network namespace of the container: containers in a pod
+    /// share the pod netns, which is named after the pod.
+    pub fn netns_name(&self) -> String {
+        match (&self.pod_namespace, &self.pod) {
+            (Some(ns), Some(pod)) => format!("{ns}/{pod}"),
+            (None, Some(pod)) => pod.clone(),
+            _ => self.name.clone(),
+        }
+    }
+}
+
+/// Kubernetes objects, as reported by the API server. Used to map the
+/// addresses and interfaces found in events to pods, services and nodes.
+#[event_type]
+#[derive(Default)]
+pub struct KubeInfo {
+    /// Netns inum of the host, in which the host side of the pod interfaces
+    /// are.
+    pub host_netns: Option<u32>,
+    /// Pods.
+    pub pods: Vec<KubePod>,
+    /// Services.
+    pub services: Vec<KubeService>,
+    /// Nodes.
+    pub nodes: Vec<KubeNode>,
+}
+
+/// Kubernetes pod.
+#[event_type]
+#[derive(Default)]
+pub struct KubePod {
+    /// Pod name.
+    pub name: String,
+    /// Pod namespace.
+    pub namespace: String,
+    /// Node the pod runs on, if scheduled.
+    pub node: Option<String>,
+    /// Pod addresses. Pods using the host network have none, as they use the
+    /// node addresses.
+    pub ips: Vec<String>,
+    /// Ifindexes of the host side of the pod interfaces (e.g. veth peers),
+    /// for pods running on the collecting node.
+    pub host_ifindexes: Vec<u32>,
+}
+
+/// Kubernetes service.
+#[event_type]
+#[derive(Default)]
+pub struct KubeService {
+    /// Service name.
+    pub name: String,
+    /// Service namespace.
+    pub namespace: String,
+    /// Service addresses: cluster, external and load-balancer ones.
+    pub ips: Vec<String>,
+}
+
+/// Kubernetes node.
+#[event_type]
+#[derive(Default)]
+pub struct KubeNode {
+    /// Node name.
+    pub name: String,
+    /// Node addresses.
+    pub ips: Vec<String>,
 }
 
 /// Startup event section. Contains global information about a collection as a
@@ -25,8 +145,13 @@ pub struct StartupEvent {
     pub clock_monotonic_offset: TimeSpec,
     /// Machine information retrieved while collecting events.
     pub machine: MachineInfo,
+    /// Collection information, if any.
+    pub collection: Option<CollectionInfo>,
     /// Information about the split file, if any.
     pub split_file: Option<SplitFile>,
+    /// Versions of the event sections having had breaking changes. Sections
+    /// not listed are at version 1.
+    pub section_versions: Option<BTreeMap<String, u32>>,
 }
 
 impl EventFmt for StartupEvent {
@@ -39,6 +164,30 @@ impl EventFmt for StartupEvent {
             "{sep}on machine {} {} {}",
             self.machine.kernel_release, self.machine.kernel_version, self.machine.hardware_name
         )?;
+        if let Some(build_id) = &self.machine.kernel_build_id {
+            write!(f, "{sep}kernel build id {build_id}")?;
+        }
+
+        if let Some(collection) = &self.collection {
+            write!(
+                f,
+                "{sep}started at {}",
+                format_date_time(
+                    format.time_format,
+                    collection.start_time,
+                    Some(self.clock_monotonic_offset)
+                )
+            )?;
+            if !collection.probes.is_empty() {
+                write!(f, "{sep}probes {}", collection.probes.join(", "))?;
+            }
+            if !collection.filters.is_empty() {
+                write!(f, "{sep}filters {}", collection.filters.join(", "))?;
+            }
+            if let Some(containers) = &collection.containers {
+                write!(f, "{sep}{} container(s) known", containers.len())?;
+            }
+        }
 
         if let Some(split) = &self.split_file {
             write!(f, "{sep}file id {}", split.id)?;
@@ -48,6 +197,20 @@ impl EventFmt for StartupEvent {
     }
 }
 
+/// Lost events section. Reports events which could not be collected, e.g.
+/// because the events buffer was full while the consumer was lagging behind.
+#[event_section]
+pub struct LostEvent {
+    /// Number of events lost since the previous report.
+    pub count: u64,
+}
+
+impl EventFmt for LostEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "lost {} event(s)", self.count)
+    }
+}
+
 /// Split-file information
 ///
 /// Information about a partial event file generated while splitting the full
@@ -82,6 +245,9 @@ pub struct CommonEvent {
     pub smp_id: Option<u32>,
     /// Linux task.
     pub task: Option<TaskEvent>,
+    /// Host the event was collected on, for events collected remotely (see
+    /// `retis collect --remote`).
+    pub host: Option<String>,
 }
 
 impl EventFmt for CommonEvent {
@@ -92,6 +258,10 @@ impl EventFmt for CommonEvent {
             format_date_time(format.time_format, self.timestamp, format.monotonic_offset)
         )?;
 
+        if let Some(host) = &self.host {
+            write!(f, " @{host}")?;
+        }
+
         if let Some(smp_id) = self.smp_id {
             write!(f, " ({smp_id})")?;
         }
@@ -1,5 +1,7 @@
 #![allow(dead_code)] // Not everything is used in all versions.
 
+use std::collections::BTreeMap;
+
 use anyhow::{anyhow, bail, Result};
 use semver::{Version, VersionReq};
 
@@ -39,6 +41,15 @@ const FIXUPS: &[&[CompatFixup]] = &[
     ],
 ];
 
+/// Versions of the event sections, by name. A section version must be bumped
+/// when making breaking changes to it (e.g. changing the type or the meaning of
+/// a field); those are then dropped when reading events collected by a newer
+/// Retis version. Adding fields does not require a bump, as unknown fields are
+/// ignored and missing ones set to their default value.
+///
+/// Sections not listed below are at version 1.
+const SECTION_VERSIONS: &[(&str, u32)] = &[];
+
 enum CompatFixup<'a> {
     Remove(&'a str),
     Add(&'a str, CompatValue),
@@ -65,6 +76,10 @@ pub(crate) enum CompatVersion {
     V1,
     /* v1.7.x.. */
     V2,
+    /* Newer y-stream versions than the current one. No fixup can be applied,
+     * but unknown fields and sections are ignored and sections we can't
+     * unmarshal are dropped. */
+    Future,
 }
 
 // Retis versions to internal compat version table.
@@ -81,6 +96,18 @@ impl CompatVersion {
     pub(crate) fn from_retis_version(retis_version: &str) -> Result<Self> {
         let retis_version = parse_version(retis_version)?;
 
+        // Events generated by a newer y-stream version might include changes
+        // we do not know about. Process what we can; but refuse events from a
+        // newer major version, as anything could have changed.
+        let current = parse_version(env!("CARGO_PKG_VERSION"))?;
+        if retis_version.major > current.major {
+            bail!("Events were collected with a newer Retis version (v{retis_version}), use at least this version to process them (this is v{current})");
+        }
+        if (retis_version.major, retis_version.minor) > (current.major, current.minor) {
+            log::warn!("Events were collected with a newer Retis version (v{retis_version}), data unknown to this version (v{current}) will be ignored");
+            return Ok(CompatVersion::Future);
+        }
+
         for (req, version) in VERSION_MATCHES {
             let req = VersionReq::parse(req)?;
             if req.matches(&retis_version) {
@@ -93,6 +120,31 @@ impl CompatVersion {
     }
 }
 
+/// Versions of the event sections having had breaking changes, to be stored
+/// along events.
+pub(crate) fn section_versions() -> BTreeMap<String, u32> {
+    SECTION_VERSIONS
+        .iter()
+        .map(|(name, version)| (name.to_string(), *version))
+        .collect()
+}
+
+/// Given the section versions of events collected by a newer Retis version,
+/// return the sections we can't unmarshal.
+pub(crate) fn incompatible_sections(versions: &BTreeMap<String, u32>) -> Vec<String> {
+    versions
+        .iter()
+        .filter(|(name, version)| {
+            let known = SECTION_VERSIONS
+                .iter()
+                .find_map(|(n, v)| (n == name).then_some(*v))
+                .unwrap_or(1);
+            **version > known
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
 // Parse a Retis version str into a semver::Version.
 fn parse_version(retis_version: &str) -> Result<Version> {
     // Version should start with 'v'. Let's be flexible.
@@ -239,4 +291,41 @@ mod tests {
         assert!(check_version("1.5.x", "= 1.5.0").is_err());
         assert!(check_version("1.5.0?foo", "= 1.5.0").is_err());
     }
+
+    #[test]
+    fn section_versions() {
+        let versions = std::collections::BTreeMap::from([
+            ("skb".to_string(), 1),
+            ("ct".to_string(), 2),
+            ("unknown".to_string(), 1),
+        ]);
+        assert_eq!(super::incompatible_sections(&versions), ["ct"]);
+    }
+
+    #[test]
+    fn newer_version() {
+        let current = super::parse_version(env!("CARGO_PKG_VERSION")).unwrap();
+
+        assert!(super::CompatVersion::from_retis_version("v1.6.0").is_ok());
+        assert!(super::CompatVersion::from_retis_version(&format!(
+            "v{}.{}.{}-31584d349ace",
+            current.major,
+            current.minor,
+            current.patch + 1
+        ))
+        .is_ok());
+        assert_eq!(
+            super::CompatVersion::from_retis_version(&format!(
+                "v{}.{}.0",
+                current.major,
+                current.minor + 1
+            ))
+            .unwrap(),
+            super::CompatVersion::Future
+        );
+        assert!(
+            super::CompatVersion::from_retis_version(&format!("v{}.0.0", current.major + 1))
+                .is_err()
+        );
+    }
 }
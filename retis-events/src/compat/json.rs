@@ -1,12 +1,27 @@
 //! Providing backward compatibility for events formatted in JSON.
 
 use anyhow::{anyhow, bail, Result};
+use once_cell::sync::Lazy;
 
 use super::*;
+use crate::Event;
+
+/// JSON Schema of events, describing the default value of their fields.
+static EVENT_SCHEMA: Lazy<serde_json::Value> =
+    Lazy::new(|| schemars::schema_for!(Event).to_value());
 
 /// Equivalent of `serde_json::from_str` with an additional and optional
 /// compatibility layer.
 pub fn from_str<T>(input: &str, version: CompatVersion) -> Result<T>
+where
+    T: for<'de> serde::Deserialize<'de>,
+{
+    from_str_with(input, version, &[])
+}
+
+/// Same as `from_str`, also dropping the given `incompatible` sections when
+/// reading events collected by a newer Retis version.
+pub fn from_str_with<T>(input: &str, version: CompatVersion, incompatible: &[String]) -> Result<T>
 where
     T: for<'de> serde::Deserialize<'de>,
 {
@@ -16,22 +31,38 @@ where
         return Ok(serde_json::from_str(input)?);
     }
 
-    // Otherwise we do a two-step deserialization to allow fixing up fields in
-    // between. The first round deserializes the event into a generic
-    // serde_json::Value (which has the advantage of not enforcing any field
-    // presence or type) and the second one generates our Event from the fixed
-    // up serde_json::Value.
+    from_value_with(serde_json::from_str(input)?, version, incompatible)
+}
+
+/// Same as `from_str_with`, from an event already parsed as a generic
+/// `serde_json::Value` (e.g. read from another format).
+pub fn from_value_with<T>(
+    mut event: serde_json::Value,
+    version: CompatVersion,
+    incompatible: &[String],
+) -> Result<T>
+where
+    T: for<'de> serde::Deserialize<'de>,
+{
+    if version == CompatVersion::LATEST {
+        return Ok(serde_json::from_value(event)?);
+    }
 
-    let mut event: serde_json::Value = serde_json::from_str(input)?;
+    // Otherwise fix up the fields of the generic serde_json::Value (which has
+    // the advantage of not enforcing any field presence or type) before
+    // generating our Event from it.
+    let fixup = |event: &mut serde_json::Value| -> Result<()> {
+        super::compatibility_fixup(event, CompatStrategy::Backward(version))?;
+        if version == CompatVersion::Future {
+            forward_fixup(event, incompatible)?;
+        }
+        Ok(())
+    };
     match event {
         // Plain events.
-        serde_json::Value::Object(_) => {
-            super::compatibility_fixup(&mut event, CompatStrategy::Backward(version))?
-        }
+        serde_json::Value::Object(_) => fixup(&mut event)?,
         // Sorted events.
-        serde_json::Value::Array(ref mut vec) => vec.iter_mut().try_for_each(|event| {
-            super::compatibility_fixup(event, CompatStrategy::Backward(version))
-        })?,
+        serde_json::Value::Array(ref mut vec) => vec.iter_mut().try_for_each(fixup)?,
         _ => bail!("Unknown event format (not a map nor an array)"),
     }
 
@@ -39,6 +70,155 @@ where
         .map_err(|e| anyhow!(format!("Event was fixed up but parsing still failed: {e}")))
 }
 
+/// Drop the sections of an event collected by a newer Retis version we can't
+/// unmarshal: sections having an incompatible version and sections failing to
+/// parse (e.g. because of a new enum variant). Missing fields are set to their
+/// default value when possible, unknown fields and sections are already
+/// ignored when unmarshaling.
+fn forward_fixup(event: &mut serde_json::Value, incompatible: &[String]) -> Result<()> {
+    fill_defaults(event, &EVENT_SCHEMA, &EVENT_SCHEMA);
+
+    let serde_json::Value::Object(sections) = event else {
+        bail!("Unknown event format (not a map)");
+    };
+
+    sections.retain(|name, section| {
+        if incompatible.contains(name) {
+            return false;
+        }
+
+        let single = serde_json::Map::from_iter([(name.clone(), section.clone())]);
+        match serde_json::from_value::<Event>(serde_json::Value::Object(single)) {
+            Ok(_) => true,
+            Err(e) => {
+                log::debug!("Dropping section {name} of event: {e}");
+                false
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Set the required fields missing from `val` to their default value, if any,
+/// following its JSON Schema.
+fn fill_defaults(
+    val: &mut serde_json::Value,
+    schema: &serde_json::Value,
+    root: &serde_json::Value,
+) {
+    let (schema, _) = resolve_schema(schema, root);
+
+    match val {
+        serde_json::Value::Object(obj) => match schema.get("properties") {
+            // Structs.
+            Some(serde_json::Value::Object(props)) => {
+                let required: Vec<&str> = schema
+                    .get("required")
+                    .and_then(serde_json::Value::as_array)
+                    .map(|req| req.iter().filter_map(serde_json::Value::as_str).collect())
+                    .unwrap_or_default();
+
+                props
+                    .iter()
+                    .for_each(|(name, prop)| match obj.get_mut(name) {
+                        Some(field) => fill_defaults(field, prop, root),
+                        None if required.contains(&name.as_str()) => {
+                            if let Some(default) = default_value(prop, root) {
+                                obj.insert(name.clone(), default);
+                            }
+                        }
+                        None => (),
+                    });
+            }
+            // Maps.
+            _ => {
+                if let Some(values) = schema.get("additionalProperties") {
+                    obj.values_mut()
+                        .for_each(|val| fill_defaults(val, values, root));
+                }
+            }
+        },
+        serde_json::Value::Array(vals) => {
+            if let Some(items) = schema.get("items") {
+                vals.iter_mut()
+                    .for_each(|val| fill_defaults(val, items, root));
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Default value of a field given its JSON Schema, if any. Nullable fields
+/// and enums do not have one.
+fn default_value(
+    schema: &serde_json::Value,
+    root: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    let (schema, nullable) = resolve_schema(schema, root);
+    if let Some(default) = schema.get("default") {
+        return Some(default.clone());
+    }
+    if nullable || schema.get("const").is_some() || schema.get("enum").is_some() {
+        return None;
+    }
+
+    match schema.get("type").and_then(serde_json::Value::as_str)? {
+        "boolean" => Some(serde_json::Value::Bool(false)),
+        "integer" | "number" => Some(serde_json::Value::from(0)),
+        "string" => Some(serde_json::Value::String(String::new())),
+        "array" => Some(serde_json::Value::Array(Vec::new())),
+        "object" => {
+            let mut val = serde_json::Value::Object(serde_json::Map::new());
+            fill_defaults(&mut val, schema, root);
+            Some(val)
+        }
+        _ => None,
+    }
+}
+
+/// Follow the references of a schema and strip its null alternative, if any.
+/// Also returns whether the schema is nullable.
+fn resolve_schema<'a>(
+    mut schema: &'a serde_json::Value,
+    root: &'a serde_json::Value,
+) -> (&'a serde_json::Value, bool) {
+    let is_null = |schema: &serde_json::Value| schema.get("type").is_some_and(|t| *t == "null");
+    let mut nullable = false;
+
+    loop {
+        if let Some(target) = schema
+            .get("$ref")
+            .and_then(serde_json::Value::as_str)
+            .and_then(|r| r.strip_prefix('#'))
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            schema = target;
+            continue;
+        }
+
+        if let Some([a, b]) = schema
+            .get("anyOf")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+        {
+            if is_null(a) || is_null(b) {
+                schema = if is_null(a) { b } else { a };
+                nullable = true;
+                continue;
+            }
+        }
+
+        break;
+    }
+
+    let types = schema.get("type").and_then(serde_json::Value::as_array);
+    if types.is_some_and(|types| types.iter().any(|t| *t == "null")) {
+        nullable = true;
+    }
+    (schema, nullable)
+}
+
 impl EventCompatibility for serde_json::Value {
     fn remove(&mut self, target: &str) -> Result<()> {
         if let Some(target) = get_mut_ref(self, target)? {
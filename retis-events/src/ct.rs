@@ -115,6 +115,30 @@ pub struct CtTuple {
     pub proto: CtProto,
 }
 
+/// Address (and port) translation of a tuple member.
+#[event_type]
+#[derive(Default)]
+pub struct CtNatMangle {
+    /// Address before translation.
+    pub pre_addr: String,
+    /// Address after translation.
+    pub post_addr: String,
+    /// Port before translation, for protocols using ports.
+    pub pre_port: Option<u16>,
+    /// Port after translation, for protocols using ports.
+    pub post_port: Option<u16>,
+}
+
+/// NAT translations of a connection, as seen from its original direction.
+#[event_type]
+#[derive(Default)]
+pub struct CtNat {
+    /// Source NAT, if the source of the connection is translated.
+    pub src: Option<CtNatMangle>,
+    /// Destination NAT, if the destination of the connection is translated.
+    pub dst: Option<CtNatMangle>,
+}
+
 /// Conntrack state.
 #[event_type]
 #[serde(rename_all = "snake_case")]
@@ -141,6 +165,18 @@ pub struct CtEvent {
     pub parent: Option<CtConnEvent>,
 }
 
+/// Conntrack entry lifecycle event, not linked to a packet.
+#[event_section]
+pub struct CtLifecycleEvent {
+    /// Operation: "create", "confirm", "timeout" or "destroy".
+    pub op: String,
+    /// Connection.
+    #[serde(flatten)]
+    pub conn: CtConnEvent,
+    /// Parent connection.
+    pub parent: Option<CtConnEvent>,
+}
+
 /// Conntrack connection
 #[event_type]
 #[derive(Default)]
@@ -161,19 +197,26 @@ pub struct CtConnEvent {
     pub labels: Option<U128>,
     /// Status. From ct->status.
     pub ct_status: u64,
+    /// NAT translations, if any.
+    pub nat: Option<CtNat>,
 }
 
 impl EventFmt for CtEvent {
     fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         use CtState::*;
-        match self.state {
-            Established => write!(f, "ct_state ESTABLISHED ")?,
-            Related => write!(f, "ct_state RELATED ")?,
-            New => write!(f, "ct_state NEW ")?,
-            Reply => write!(f, "ct_state REPLY ")?,
-            RelatedReply => write!(f, "ct_state RELATED_REPLY ")?,
-            Untracked => write!(f, "ct_state UNTRACKED ")?,
-        }
+        let state = match self.state {
+            Established => "ESTABLISHED",
+            Related => "RELATED",
+            New => "NEW",
+            Reply => "REPLY",
+            RelatedReply => "RELATED_REPLY",
+            Untracked => "UNTRACKED",
+        };
+        write!(
+            f,
+            "ct_state {} ",
+            format.highlight(Highlight::CtState, state)
+        )?;
 
         Self::format_conn(&self.base, f)?;
 
@@ -191,6 +234,25 @@ impl EventFmt for CtEvent {
     }
 }
 
+impl EventFmt for CtLifecycleEvent {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        write!(f, "ct {} ", self.op)?;
+        CtEvent::format_conn(&self.conn, f)?;
+
+        if let Some(parent) = &self.parent {
+            if format.multiline {
+                write!(f, "\n\\")?;
+            }
+
+            write!(f, " parent [")?;
+            CtEvent::format_conn(parent, f)?;
+            write!(f, "]")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl CtEvent {
     fn format_conn(conn: &CtConnEvent, f: &mut Formatter) -> fmt::Result {
         write!(f, "status {:#x} ", conn.ct_status)?;
@@ -273,6 +335,23 @@ impl CtEvent {
             write!(f, " labels {:#x}", labels.bits())?;
         }
 
+        if let Some(nat) = &conn.nat {
+            for (name, mangle) in [("snat", &nat.src), ("dnat", &nat.dst)] {
+                if let Some(mangle) = mangle {
+                    let port =
+                        |port: Option<u16>| port.map(|p| format!(".{p}")).unwrap_or_default();
+                    write!(
+                        f,
+                        " {name} [{}{} > {}{}]",
+                        mangle.pre_addr,
+                        port(mangle.pre_port),
+                        mangle.post_addr,
+                        port(mangle.post_port),
+                    )?;
+                }
+            }
+        }
+
         Ok(())
     }
 }
@@ -15,6 +15,62 @@ pub enum TimeFormat {
     UtcDate,
 }
 
+/// Color themes, used to highlight parts of events.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ColorTheme {
+    /// For terminals with a dark background.
+    #[default]
+    Dark,
+    /// For terminals with a light background.
+    Light,
+}
+
+/// Parts of events that can be highlighted.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Highlight {
+    /// Probe (kernel or userspace symbol).
+    Probe,
+    /// Packet addresses and ports.
+    Address,
+    /// Drop reason.
+    DropReason,
+    /// Conntrack state.
+    CtState,
+}
+
+impl ColorTheme {
+    /// ANSI SGR parameters used for a given part of events.
+    fn sgr(&self, highlight: Highlight) -> &'static str {
+        use Highlight::*;
+        match (self, highlight) {
+            (ColorTheme::Dark, Probe) => "1;36",
+            (ColorTheme::Dark, Address) => "33",
+            (ColorTheme::Dark, DropReason) => "1;31",
+            (ColorTheme::Dark, CtState) => "32",
+            (ColorTheme::Light, Probe) => "1;34",
+            (ColorTheme::Light, Address) => "35",
+            (ColorTheme::Light, DropReason) => "1;31",
+            (ColorTheme::Light, CtState) => "32",
+        }
+    }
+}
+
+/// Value wrapped in ANSI color sequences when displayed, if colors are used.
+/// See `DisplayFormat::highlight`.
+pub struct Highlighted<T> {
+    val: T,
+    sgr: Option<&'static str>,
+}
+
+impl<T: fmt::Display> fmt::Display for Highlighted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.sgr {
+            Some(sgr) => write!(f, "\x1b[{sgr}m{}\x1b[0m", self.val),
+            None => write!(f, "{}", self.val),
+        }
+    }
+}
+
 /// Controls how an event is formatted.
 #[derive(Debug, Default, Clone, Copy)]
 pub struct DisplayFormat {
@@ -26,6 +82,8 @@ pub struct DisplayFormat {
     pub monotonic_offset: Option<TimeSpec>,
     /// Should the link level part be displayed?
     pub print_ll: bool,
+    /// Color theme used to highlight parts of events, if any.
+    pub colors: Option<ColorTheme>,
 }
 
 impl DisplayFormat {
@@ -56,6 +114,20 @@ impl DisplayFormat {
         self.print_ll = enabled;
         self
     }
+
+    /// Configure the color theme, or None to disable colors.
+    pub fn colors(mut self, theme: Option<ColorTheme>) -> Self {
+        self.colors = theme;
+        self
+    }
+
+    /// Highlight a part of an event, if colors are used.
+    pub fn highlight<T: fmt::Display>(&self, highlight: Highlight, val: T) -> Highlighted<T> {
+        Highlighted {
+            val,
+            sgr: self.colors.map(|theme| theme.sgr(highlight)),
+        }
+    }
 }
 
 /// `Formatter` implements `std::fmt::Write` and controls how events are being
@@ -341,3 +413,33 @@ impl DelimWriter {
         !self.first
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SkbDropEvent;
+
+    #[test]
+    fn highlight() {
+        let drop = SkbDropEvent {
+            subsys: None,
+            drop_reason: "NETFILTER_DROP".to_string(),
+        };
+
+        let format = DisplayFormat::new();
+        assert_eq!(
+            format!("{}", drop.display(&format, &FormatterConf::new())),
+            "drop (reason NETFILTER_DROP)"
+        );
+
+        let format = format.colors(Some(ColorTheme::Dark));
+        assert_eq!(
+            format!("{}", drop.display(&format, &FormatterConf::new())),
+            "drop (reason \x1b[1;31mNETFILTER_DROP\x1b[0m)"
+        );
+        assert_eq!(
+            format!("{}", format.highlight(Highlight::Probe, "ip_rcv")),
+            "\x1b[1;36mip_rcv\x1b[0m"
+        );
+    }
+}
@@ -51,14 +51,20 @@ pub struct Event {
     pub kernel: Option<KernelEvent>,
     /// Userspace section.
     pub userspace: Option<UserEvent>,
+    /// Userspace stack trace section.
+    pub user_stack: Option<UserStackEvent>,
     /// Tracking section.
     pub tracking: Option<TrackingInfo>,
     /// Skb tracking section.
     pub skb_tracking: Option<SkbTrackingEvent>,
+    /// Skb relationship section.
+    pub skb_relation: Option<SkbRelationEvent>,
     /// Skb drop section.
     pub skb_drop: Option<SkbDropEvent>,
     /// Packet section.
     pub packet: Option<PacketEvent>,
+    /// Kubernetes section.
+    pub kube: Option<KubeEvent>,
     /// Skb section.
     pub skb: Option<SkbEvent>,
     /// Net namespace section.
@@ -69,12 +75,56 @@ pub struct Event {
     pub ovs: Option<OvsEvent>,
     /// OVS-detrace section.
     pub ovs_detrace: Option<OvsFlowInfoEvent>,
+    /// Netfilter hook section.
+    pub nf: Option<NfEvent>,
     /// Nft section.
     pub nft: Option<NftEvent>,
     /// Ct section.
     pub ct: Option<CtEvent>,
+    /// Ct lifecycle section.
+    pub ct_lifecycle: Option<CtLifecycleEvent>,
+    /// Tc section.
+    pub tc: Option<TcEvent>,
+    /// XDP section.
+    pub xdp: Option<XdpEvent>,
+    /// Xfrm section.
+    pub xfrm: Option<XfrmEvent>,
+    /// Tunnel section.
+    pub tunnel: Option<TunnelEvent>,
+    /// Tcp section.
+    pub tcp: Option<TcpEvent>,
+    /// GRO/GSO section.
+    pub gro_gso: Option<GroGsoEvent>,
+    /// Bridge section.
+    pub bridge: Option<BridgeEvent>,
+    /// Lag section.
+    pub lag: Option<LagEvent>,
+    /// WireGuard section.
+    pub wireguard: Option<WireguardEvent>,
+    /// ICMP error section.
+    pub icmp_error: Option<IcmpErrorEvent>,
+    /// Network namespace lifecycle section.
+    pub ns_lifecycle: Option<NsLifecycleEvent>,
+    /// Socket section.
+    pub sock: Option<SockEvent>,
+    /// Hardware offload section.
+    pub offload: Option<OffloadEvent>,
+    /// Memory accounting section.
+    pub mem: Option<MemEvent>,
+    /// AF_XDP section.
+    pub xsk: Option<XskEvent>,
+    /// MPTCP section.
+    pub mptcp: Option<MptcpEvent>,
+    /// Route section.
+    pub route: Option<RouteEvent>,
+    /// Neighbor section.
+    pub neigh: Option<NeighEvent>,
+    /// External collectors section.
+    pub external: Option<ExternalEvent>,
     /// Startup event.
     pub startup: Option<StartupEvent>,
+    /// Lost events section.
+    pub lost: Option<LostEvent>,
 
     #[cfg(feature = "test-events")]
     pub test: Option<TestEvent>,
@@ -125,6 +175,12 @@ impl EventFmt for Event {
                 f.conf.reset_level();
             }
         }
+        if let Some(user_stack) = &self.user_stack {
+            f.conf.inc_level(4);
+            write!(f, "{sep}")?;
+            user_stack.event_fmt(f, format)?;
+            f.conf.reset_level();
+        }
 
         f.conf.inc_level(2);
 
@@ -134,6 +190,10 @@ impl EventFmt for Event {
             write!(f, "{sep}")?;
             packet.event_fmt(f, format)?;
         }
+        if let Some(kube) = &self.kube {
+            write!(f, "{sep}")?;
+            kube.event_fmt(f, format)?;
+        }
 
         // Special case the netns & dev sections, to make the output more
         // packed. Also the two are quite related and it makes sense to display
@@ -149,12 +209,35 @@ impl EventFmt for Event {
 
         /* Format the rest of the optional fields. */
         [
+            self.skb_relation.as_ref().map(|f| f as &dyn EventDisplay),
             self.skb.as_ref().map(|f| f as &dyn EventDisplay),
             self.ovs.as_ref().map(|f| f as &dyn EventDisplay),
             self.ovs_detrace.as_ref().map(|f| f as &dyn EventDisplay),
+            self.nf.as_ref().map(|f| f as &dyn EventDisplay),
             self.nft.as_ref().map(|f| f as &dyn EventDisplay),
             self.ct.as_ref().map(|f| f as &dyn EventDisplay),
+            self.ct_lifecycle.as_ref().map(|f| f as &dyn EventDisplay),
+            self.tc.as_ref().map(|f| f as &dyn EventDisplay),
+            self.xdp.as_ref().map(|f| f as &dyn EventDisplay),
+            self.xfrm.as_ref().map(|f| f as &dyn EventDisplay),
+            self.tunnel.as_ref().map(|f| f as &dyn EventDisplay),
+            self.tcp.as_ref().map(|f| f as &dyn EventDisplay),
+            self.gro_gso.as_ref().map(|f| f as &dyn EventDisplay),
+            self.bridge.as_ref().map(|f| f as &dyn EventDisplay),
+            self.lag.as_ref().map(|f| f as &dyn EventDisplay),
+            self.wireguard.as_ref().map(|f| f as &dyn EventDisplay),
+            self.icmp_error.as_ref().map(|f| f as &dyn EventDisplay),
+            self.ns_lifecycle.as_ref().map(|f| f as &dyn EventDisplay),
+            self.sock.as_ref().map(|f| f as &dyn EventDisplay),
+            self.offload.as_ref().map(|f| f as &dyn EventDisplay),
+            self.mem.as_ref().map(|f| f as &dyn EventDisplay),
+            self.xsk.as_ref().map(|f| f as &dyn EventDisplay),
+            self.mptcp.as_ref().map(|f| f as &dyn EventDisplay),
+            self.route.as_ref().map(|f| f as &dyn EventDisplay),
+            self.neigh.as_ref().map(|f| f as &dyn EventDisplay),
+            self.external.as_ref().map(|f| f as &dyn EventDisplay),
             self.startup.as_ref().map(|f| f as &dyn EventDisplay),
+            self.lost.as_ref().map(|f| f as &dyn EventDisplay),
         ]
         .iter()
         .try_for_each(|field| match field {
@@ -171,7 +254,7 @@ impl EventFmt for Event {
 }
 
 /// A set of sorted Events with the same tracking id.
-#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[derive(Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 #[serde(transparent)]
 pub struct EventSeries {
     /// Events that comprise the Series.
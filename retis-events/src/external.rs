@@ -0,0 +1,60 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// External collectors section. External collectors are loaded at runtime
+/// and their data is described by their manifest, not by Retis itself.
+#[event_section]
+#[derive(Default)]
+pub struct ExternalEvent {
+    /// Sections reported by external collectors, one per collector having
+    /// data for the event.
+    pub sections: Vec<ExternalSection>,
+}
+
+impl EventFmt for ExternalEvent {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        self.sections
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, section)| {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                section.event_fmt(f, format)
+            })
+    }
+}
+
+/// Data reported by a single external collector.
+#[event_type]
+#[derive(Default)]
+pub struct ExternalSection {
+    /// Name of the external collector, as found in its manifest.
+    pub collector: String,
+    /// Fields of the section, in the order they are defined in the manifest.
+    pub fields: Vec<ExternalField>,
+}
+
+impl EventFmt for ExternalSection {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "[{}]", self.collector)?;
+        self.fields
+            .iter()
+            .try_for_each(|field| write!(f, " {}={}", field.name, field.value))
+    }
+}
+
+/// Single field of an external collector section.
+#[event_type]
+#[derive(Default)]
+pub struct ExternalField {
+    /// Name of the field.
+    pub name: String,
+    /// Type of the field, as found in the manifest (e.g. "u32", "ipv4" or
+    /// "str").
+    pub kind: String,
+    /// Formatted value of the field.
+    pub value: String,
+}
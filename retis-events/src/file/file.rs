@@ -2,15 +2,16 @@
 
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Read, Seek},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
     path::Path,
 };
 
 use anyhow::{anyhow, bail, Result};
 
+use super::proto::{self, PROTO_MAGIC};
 use crate::{
-    compat::{json, CompatVersion},
-    Event, EventSeries,
+    compat::{incompatible_sections, json, CompatVersion},
+    Event, EventSeries, StartupEvent,
 };
 
 // Type of file that is being processed.
@@ -22,15 +23,131 @@ pub enum FileType {
     Series,
 }
 
+/// Serialization format of events in files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EventFormat {
+    /// One JSON event per line.
+    #[default]
+    Json,
+    /// Length-delimited protobuf events, see `proto`.
+    Protobuf,
+}
+
+impl EventFormat {
+    /// Data starting a file, before the events.
+    pub fn header(&self) -> &'static [u8] {
+        match self {
+            EventFormat::Json => b"",
+            EventFormat::Protobuf => PROTO_MAGIC,
+        }
+    }
+
+    /// Serialize an event, including its delimiter.
+    pub fn serialize(&self, event: &Event) -> Result<Vec<u8>> {
+        Ok(match self {
+            EventFormat::Json => {
+                let mut buf = serde_json::to_vec(event)?;
+                buf.push(b'\n');
+                buf
+            }
+            EventFormat::Protobuf => proto::encode(event)?,
+        })
+    }
+}
+
 pub trait ReadSeek: Read + Seek + Send + Sync {}
 impl<T> ReadSeek for T where T: Read + Seek + Send + Sync {}
 
+trait ReadSync: Read + Send + Sync {}
+impl<T> ReadSync for T where T: Read + Send + Sync {}
+
+/// Magic number starting zstd frames.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Is the data (zstd) compressed? The reader is rewound.
+pub(crate) fn is_compressed<R: Read + Seek>(reader: &mut R) -> io::Result<bool> {
+    let mut magic = [0; 4];
+    let res = reader.read_exact(&mut magic);
+    reader.rewind()?;
+
+    match res {
+        Ok(_) => Ok(magic == ZSTD_MAGIC),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Where a `FileEventsFactory` reads events from.
+enum Input {
+    /// A single file.
+    Reader {
+        reader: BufReader<Box<dyn ReadSync>>,
+        // First line of the file, read while detecting its type.
+        first: Option<String>,
+    },
+    /// A single protobuf file.
+    Proto {
+        reader: BufReader<Box<dyn ReadSync>>,
+        // First event of the file, read while retrieving the startup event.
+        first: Option<serde_json::Value>,
+    },
+    /// Multiple files, see `FileEventsFactory::merge`.
+    Merge(Vec<MergeInput>),
+}
+
+/// File being merged with others.
+struct MergeInput {
+    factory: FileEventsFactory,
+    // Value added to the timestamps of the file events, in nanoseconds.
+    adjust: i64,
+    // Next event of the file, if any.
+    next: Option<Event>,
+}
+
+impl MergeInput {
+    /// Get the next event of the file, if not already done. Startup events
+    /// are only kept for the first file.
+    fn fill(&mut self, first: bool) -> Result<()> {
+        while self.next.is_none() {
+            let Some(mut event) = self.factory.next_event()? else {
+                break;
+            };
+            if event.startup.is_some() && !first {
+                continue;
+            }
+
+            if let Some(common) = event.common.as_mut() {
+                common.timestamp = common.timestamp.saturating_add_signed(self.adjust);
+            }
+            self.next = Some(event);
+        }
+        Ok(())
+    }
+
+    /// Ordering key of the next event, startup events coming first.
+    fn key(&self) -> Option<u64> {
+        self.next.as_ref().map(|e| match e.startup {
+            Some(_) => 0,
+            None => e.common.as_ref().map(|c| c.timestamp).unwrap_or_default(),
+        })
+    }
+}
+
 /// File events factory retrieving and unmarshaling events
-/// parts.
+/// parts. Compressed files are detected and handled transparently.
 pub struct FileEventsFactory {
-    reader: BufReader<Box<dyn ReadSeek>>,
+    input: Input,
     filetype: FileType,
     compat_version: CompatVersion,
+    // Sections we can't unmarshal, when reading events collected by a newer
+    // Retis version.
+    incompatible_sections: Vec<String>,
+    // Startup event of the file, describing the collection.
+    startup: Option<StartupEvent>,
+    // Is the file followed while being written, see `follow`?
+    follow: bool,
+    // Partial trailing record, when following the file.
+    partial: Vec<u8>,
 }
 
 impl FileEventsFactory {
@@ -43,21 +160,206 @@ impl FileEventsFactory {
         })?))
     }
 
-    pub fn new(reader: Box<dyn ReadSeek>) -> Result<Self> {
-        let mut reader = BufReader::new(reader);
-        let (filetype, compat_version) = Self::detect_type(&mut reader)?;
+    /// Create a factory only reading events from a part of an uncompressed
+    /// file, given as (start, end) offsets, e.g. as found in its index (see
+    /// `EventIndex`).
+    pub fn from_path_range<P>(file: P, start: u64, end: u64) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut factory = Self::from_path(&file)?;
+        if matches!(factory.input, Input::Proto { .. }) {
+            bail!("Cannot read a part of a protobuf file");
+        }
+
+        let mut reader = File::open(&file)?;
+        if is_compressed(&mut reader)? {
+            bail!("Cannot read a part of a compressed file");
+        }
+        reader.seek(SeekFrom::Start(start))?;
+
+        factory.input = Input::Reader {
+            reader: BufReader::new(Box::new(reader.take(end.saturating_sub(start)))),
+            first: None,
+        };
+        Ok(factory)
+    }
+
+    pub fn new(mut reader: Box<dyn ReadSeek>) -> Result<Self> {
+        let reader: Box<dyn ReadSync> = match is_compressed(&mut reader)? {
+            true => Box::new(zstd::Decoder::new(reader)?),
+            false => Box::new(reader),
+        };
+        Self::from_buf_reader(BufReader::new(reader))
+    }
+
+    /// Create a factory reading events from a stream which can't be rewound,
+    /// e.g. a socket. Streams can't be compressed. Reading blocks until an
+    /// event is available or the stream is closed.
+    pub fn from_stream<R>(reader: R) -> Result<Self>
+    where
+        R: Read + Send + Sync + 'static,
+    {
+        let reader: Box<dyn ReadSync> = Box::new(reader);
+        Self::from_buf_reader(BufReader::new(reader))
+    }
+
+    fn from_buf_reader(mut reader: BufReader<Box<dyn ReadSync>>) -> Result<Self> {
+        if reader.fill_buf()?.starts_with(PROTO_MAGIC) {
+            return Self::from_proto(reader);
+        }
+        let (filetype, compat_version, first) = Self::detect_type(&mut reader)?;
+
+        let startup = match filetype {
+            FileType::Event => json::from_str::<Event>(&first, compat_version)?.startup,
+            FileType::Series => json::from_str::<EventSeries>(&first, compat_version)?
+                .events
+                .into_iter()
+                .find_map(|e| e.startup),
+        };
+
+        let incompatible_sections = Self::incompatible_sections(compat_version, startup.as_ref());
 
         Ok(FileEventsFactory {
-            reader,
+            input: Input::Reader {
+                reader,
+                first: Some(first),
+            },
             filetype,
             compat_version,
+            incompatible_sections,
+            startup,
+            follow: false,
+            partial: Vec::new(),
+        })
+    }
+
+    fn from_proto(mut reader: BufReader<Box<dyn ReadSync>>) -> Result<Self> {
+        reader.consume(PROTO_MAGIC.len());
+
+        let (first, compat_version) =
+            proto::decode_first(&mut reader)?.ok_or_else(|| anyhow!("File is empty"))?;
+        let startup = json::from_value_with::<Event>(first.clone(), compat_version, &[])?.startup;
+        let incompatible_sections = Self::incompatible_sections(compat_version, startup.as_ref());
+
+        Ok(FileEventsFactory {
+            input: Input::Proto {
+                reader,
+                first: Some(first),
+            },
+            filetype: FileType::Event,
+            compat_version,
+            incompatible_sections,
+            startup,
+            follow: false,
+            partial: Vec::new(),
+        })
+    }
+
+    /// Sections of events collected by a newer Retis version which can't be
+    /// unmarshaled, given their startup event.
+    fn incompatible_sections(
+        compat_version: CompatVersion,
+        startup: Option<&StartupEvent>,
+    ) -> Vec<String> {
+        let incompatible = match (compat_version, startup) {
+            (CompatVersion::Future, Some(startup)) => startup
+                .section_versions
+                .as_ref()
+                .map(incompatible_sections)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        if !incompatible.is_empty() {
+            log::warn!(
+                "Sections {} are incompatible with this Retis version and will be ignored",
+                incompatible.join(", ")
+            );
+        }
+        incompatible
+    }
+
+    /// Create a factory merging the events of multiple files (e.g. collected
+    /// on different hosts) into a single stream, ordered by timestamp. Events
+    /// of each file are expected to be ordered.
+    ///
+    /// Timestamps are converted to the clock of the first file, using the
+    /// files monotonic clock offsets, and a per-file delta (in nanoseconds) is
+    /// added to compensate for clock differences between hosts. Only the
+    /// startup event of the first file is kept.
+    pub fn merge(inputs: Vec<(FileEventsFactory, i64)>) -> Result<Self> {
+        let offset = |factory: &FileEventsFactory| {
+            factory
+                .startup()
+                .map(|s| i64::from(s.clock_monotonic_offset))
+                .unwrap_or_default()
+        };
+
+        let Some((first, _)) = inputs.first() else {
+            bail!("No file to merge");
+        };
+        let base = offset(first);
+        let startup = first.startup.clone();
+        let compat_version = first.compat_version;
+
+        let mut merged = Vec::new();
+        let mut incompatible_sections = Vec::new();
+        for (factory, delta) in inputs.into_iter() {
+            if matches!(factory.filetype, FileType::Series) {
+                bail!("Cannot merge sorted files");
+            }
+
+            factory.incompatible_sections.iter().for_each(|section| {
+                if !incompatible_sections.contains(section) {
+                    incompatible_sections.push(section.clone());
+                }
+            });
+
+            merged.push(MergeInput {
+                adjust: offset(&factory) - base + delta,
+                factory,
+                next: None,
+            });
+        }
+
+        Ok(FileEventsFactory {
+            input: Input::Merge(merged),
+            filetype: FileType::Event,
+            compat_version,
+            incompatible_sections,
+            startup,
+            follow: false,
+            partial: Vec::new(),
         })
     }
 
+    /// Returns the startup event found at the beginning of the file, which
+    /// describes the collection (Retis version, machine, probes, etc).
+    pub fn startup(&self) -> Option<&StartupEvent> {
+        self.startup.as_ref()
+    }
+
+    /// Follow the file while it is being written, e.g. by a running
+    /// collection. Reaching the end of the file is reported as no event being
+    /// available and partial trailing records are kept until completed, so
+    /// reading can be retried once more data was written. Compressed files
+    /// cannot be followed.
+    pub fn follow(&mut self) -> Result<()> {
+        if matches!(self.input, Input::Merge(_)) {
+            bail!("Cannot follow merged files");
+        }
+        self.follow = true;
+        Ok(())
+    }
+
     /// Returns true if the events are not from the latest (event format)
     /// version.
     pub fn is_compat(&self) -> bool {
-        self.compat_version != CompatVersion::LATEST
+        match &self.input {
+            Input::Merge(inputs) => inputs.iter().any(|i| i.factory.is_compat()),
+            _ => self.compat_version != CompatVersion::LATEST,
+        }
     }
 
     /// Retrieve the next event or None if we've reached the end of the file.
@@ -67,12 +369,47 @@ impl FileEventsFactory {
             FileType::Event => (),
             FileType::Series => bail!("Cannot read event from sorted file"),
         }
-        let mut line = String::new();
 
-        match self.reader.read_line(&mut line) {
-            Err(e) => Err(e.into()),
-            Ok(0) => Ok(None),
-            Ok(_) => Ok(Some(json::from_str(line.as_str(), self.compat_version)?)),
+        if let Input::Merge(inputs) = &mut self.input {
+            for (i, input) in inputs.iter_mut().enumerate() {
+                input.fill(i == 0)?;
+            }
+
+            return Ok(inputs
+                .iter_mut()
+                .filter(|i| i.next.is_some())
+                .min_by_key(|i| i.key())
+                .and_then(|i| i.next.take()));
+        }
+
+        if let Input::Proto { reader, first } = &mut self.input {
+            let event = match first.take() {
+                Some(first) => Some(first),
+                None if self.follow => Self::next_proto_partial(reader, &mut self.partial)?,
+                None => {
+                    // Rotated files are read as a single stream, each one
+                    // starting with the magic.
+                    if reader.fill_buf()?.starts_with(PROTO_MAGIC) {
+                        reader.consume(PROTO_MAGIC.len());
+                    }
+                    proto::decode_value(reader)?
+                }
+            };
+
+            return event
+                .map(|event| {
+                    json::from_value_with(event, self.compat_version, &self.incompatible_sections)
+                })
+                .transpose();
+        }
+
+        match self.next_line()? {
+            Some(line) => Ok(Some(json::from_str_with(
+                line.as_str(),
+                self.compat_version,
+                &self.incompatible_sections,
+            )?)),
+            None => Ok(None),
         }
     }
 
@@ -83,18 +420,82 @@ impl FileEventsFactory {
             FileType::Event => bail!("Cannot read series from unsorted file"),
             FileType::Series => (),
         }
-        let mut line = String::new();
 
-        match self.reader.read_line(&mut line) {
-            Err(e) => Err(e.into()),
-            Ok(0) => Ok(None),
-            Ok(_) => Ok(Some(json::from_str(line.as_str(), self.compat_version)?)),
+        match self.next_line()? {
+            Some(line) => Ok(Some(json::from_str_with(
+                line.as_str(),
+                self.compat_version,
+                &self.incompatible_sections,
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieve the next line or None if we've reached the end of the file.
+    fn next_line(&mut self) -> Result<Option<String>> {
+        let Input::Reader { reader, first } = &mut self.input else {
+            bail!("Cannot read lines from merged or protobuf files");
+        };
+        if let Some(first) = first.take() {
+            return Ok(Some(first));
+        }
+
+        let mut line = std::mem::take(&mut self.partial);
+        reader.read_until(b'\n', &mut line)?;
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        // Files being written can end with a partial line, which is kept
+        // until completed.
+        if self.follow && !line.ends_with(b"\n") {
+            self.partial = line;
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(line)?))
+    }
+
+    /// Retrieve the next protobuf event, if fully written. Data is buffered
+    /// until a full event is available.
+    fn next_proto_partial<R: BufRead>(
+        reader: &mut R,
+        partial: &mut Vec<u8>,
+    ) -> Result<Option<serde_json::Value>> {
+        loop {
+            if partial.starts_with(PROTO_MAGIC) {
+                partial.drain(..PROTO_MAGIC.len());
+            }
+
+            let mut data = partial.as_slice();
+            match proto::decode_value(&mut data) {
+                Ok(Some(event)) => {
+                    let used = partial.len() - data.len();
+                    partial.drain(..used);
+                    return Ok(Some(event));
+                }
+                // No or partial event, more data is needed.
+                Ok(None) => (),
+                Err(e)
+                    if e.downcast_ref::<io::Error>()
+                        .is_some_and(|e| e.kind() == io::ErrorKind::UnexpectedEof) => {}
+                Err(e) => return Err(e),
+            }
+
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            let len = buf.len();
+            partial.extend_from_slice(buf);
+            reader.consume(len);
         }
     }
 
-    fn detect_type<T>(reader: &mut T) -> Result<(FileType, CompatVersion)>
+    /// Detect the file type and the events version from the first line, which
+    /// is returned as it is consumed from the reader.
+    fn detect_type<T>(reader: &mut T) -> Result<(FileType, CompatVersion, String)>
     where
-        T: BufRead + Seek,
+        T: BufRead,
     {
         let mut line = String::new();
 
@@ -103,19 +504,19 @@ impl FileEventsFactory {
             Ok(0) => return Err(anyhow!("File is empty")),
             Ok(_) => (),
         }
-        reader.rewind()?;
 
         let first: serde_json::Value = serde_json::from_str(line.as_str())
             .map_err(|e| anyhow!("Failed to parse event file: {:?}", e))?;
 
-        Ok(match first {
+        let (filetype, version) = match first {
             serde_json::Value::Object(ref obj) => (FileType::Event, guess_version(obj)?),
             serde_json::Value::Array(mut vec) => match vec.pop() {
                 Some(serde_json::Value::Object(ref map)) => (FileType::Series, guess_version(map)?),
                 _ => bail!("Invalid or missing events"),
             },
             _ => bail!("File contains invalid json data"),
-        })
+        };
+        Ok((filetype, version, line))
     }
 
     pub fn file_type(&self) -> &FileType {
@@ -138,11 +539,15 @@ pub(crate) fn guess_version(
 
 #[cfg(test)]
 mod tests {
+    use std::{collections::BTreeMap, io::Write};
+
     use super::*;
+    use crate::{file::rotate::startup_event, helpers::time::TimeSpec, CommonEvent};
 
     #[test]
     fn read_from_file() {
         let mut fact = FileEventsFactory::from_path("test_data/test_events.json").unwrap();
+        assert_eq!(fact.startup().unwrap().retis_version, "v1.6.0");
 
         let mut events = Vec::new();
         while let Some(event) = fact.next_event().unwrap() {
@@ -151,4 +556,217 @@ mod tests {
         }
         assert!(events.len() == 5);
     }
+
+    #[test]
+    fn read_from_file_range() {
+        let data = std::fs::read_to_string("test_data/test_events.json").unwrap();
+        let lines: Vec<&str> = data.split_inclusive('\n').collect();
+        let start = lines[..2].iter().map(|l| l.len()).sum::<usize>() as u64;
+        let end = start + lines[2].len() as u64 + lines[3].len() as u64;
+
+        let mut fact =
+            FileEventsFactory::from_path_range("test_data/test_events.json", start, end).unwrap();
+        assert!(fact.startup().is_some());
+
+        let mut events = Vec::new();
+        while let Some(event) = fact.next_event().unwrap() {
+            assert!(event.startup.is_none());
+            events.push(event)
+        }
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn merge_files() {
+        let timestamps = |fact: &mut FileEventsFactory| {
+            let mut ts = Vec::new();
+            while let Some(event) = fact.next_event().unwrap() {
+                ts.push((event.startup.is_some(), event.common.unwrap().timestamp));
+            }
+            ts
+        };
+
+        let mut fact = FileEventsFactory::from_path("test_data/test_events.json").unwrap();
+        let single = timestamps(&mut fact);
+
+        let mut fact = FileEventsFactory::merge(vec![
+            (
+                FileEventsFactory::from_path("test_data/test_events.json").unwrap(),
+                0,
+            ),
+            (
+                FileEventsFactory::from_path("test_data/test_events.json").unwrap(),
+                -1,
+            ),
+        ])
+        .unwrap();
+        assert!(fact.startup().is_some());
+
+        // Only the first startup event is kept and events of the second file
+        // are shifted by the delta.
+        let mut expected = vec![single[0]];
+        single[1..].iter().for_each(|(startup, ts)| {
+            expected.push((*startup, ts - 1));
+            expected.push((*startup, *ts));
+        });
+        assert_eq!(timestamps(&mut fact), expected);
+
+        assert!(FileEventsFactory::merge(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn read_newer_file() {
+        let current = semver::Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
+        let mut startup = startup_event("retis", TimeSpec::new(0, 0), None, 0, None).unwrap();
+        if let Some(startup) = startup.startup.as_mut() {
+            startup.retis_version = format!("v{}.{}.0", current.major, current.minor + 1);
+            startup.section_versions = Some(BTreeMap::from([("skb".to_string(), 99)]));
+        }
+
+        let mut event = serde_json::json!(Event {
+            skb: Some(Default::default()),
+            ..Default::default()
+        });
+        // Unknown fields and sections are ignored, sections failing to parse
+        // are dropped and missing fields use their default value.
+        event["common"] = serde_json::json!({ "smp_id": 3, "unknown": 1 });
+        event["unknown"] = serde_json::json!({});
+        event["ct"] = serde_json::json!({ "state": "unknown" });
+
+        let path = std::env::temp_dir().join(format!("retis-events-newer-{}", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "{}", serde_json::to_string(&startup).unwrap()).unwrap();
+        writeln!(file, "{event}").unwrap();
+
+        let mut fact = FileEventsFactory::from_path(&path).unwrap();
+        assert!(fact.is_compat());
+        assert!(fact.next_event().unwrap().unwrap().startup.is_some());
+
+        let event = fact.next_event().unwrap().unwrap();
+        let common = event.common.unwrap();
+        assert_eq!((common.timestamp, common.smp_id), (0, Some(3)));
+        assert!(event.skb.is_none());
+        assert!(event.ct.is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_newer_proto_file() {
+        let current = semver::Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
+        let mut startup = startup_event("retis", TimeSpec::new(0, 0), None, 0, None).unwrap();
+        if let Some(startup) = startup.startup.as_mut() {
+            startup.retis_version = format!("v{}.{}.0", current.major, current.minor + 1);
+            startup.section_versions = Some(BTreeMap::from([("skb".to_string(), 99)]));
+        }
+        let event = Event {
+            common: Some(CommonEvent {
+                smp_id: Some(3),
+                ..Default::default()
+            }),
+            skb: Some(Default::default()),
+            ..Default::default()
+        };
+
+        let mut data = PROTO_MAGIC.to_vec();
+        data.extend(proto::encode(&startup).unwrap());
+        data.extend(proto::encode(&event).unwrap());
+
+        // Incompatible sections are kept when merging files.
+        let fact = FileEventsFactory::from_stream(io::Cursor::new(data)).unwrap();
+        assert_eq!(fact.incompatible_sections, ["skb"]);
+        let mut fact = FileEventsFactory::merge(vec![(fact, 0)]).unwrap();
+        assert_eq!(fact.incompatible_sections, ["skb"]);
+
+        assert!(fact.next_event().unwrap().unwrap().startup.is_some());
+        let event = fact.next_event().unwrap().unwrap();
+        assert_eq!(event.common.unwrap().smp_id, Some(3));
+        assert!(event.skb.is_none());
+    }
+
+    #[test]
+    fn follow_file() {
+        let startup = startup_event("retis", TimeSpec::new(0, 0), None, 0, None).unwrap();
+        let event = Event {
+            common: Some(CommonEvent {
+                timestamp: 10,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        for format in [EventFormat::Json, EventFormat::Protobuf] {
+            let path = std::env::temp_dir().join(format!(
+                "retis-events-follow-{}-{format:?}",
+                std::process::id()
+            ));
+            let event = format.serialize(&event).unwrap();
+
+            // Start with a partial event.
+            let mut file = File::create(&path).unwrap();
+            file.write_all(format.header()).unwrap();
+            file.write_all(&format.serialize(&startup).unwrap())
+                .unwrap();
+            file.write_all(&event[..5]).unwrap();
+
+            let mut fact = FileEventsFactory::from_path(&path).unwrap();
+            fact.follow().unwrap();
+            assert!(fact.next_event().unwrap().unwrap().startup.is_some());
+            assert!(fact.next_event().unwrap().is_none());
+
+            file.write_all(&event[5..]).unwrap();
+            let next = fact.next_event().unwrap().unwrap();
+            assert_eq!(next.common.unwrap().timestamp, 10);
+            assert!(fact.next_event().unwrap().is_none());
+
+            // Complete events are read once written.
+            file.write_all(&event).unwrap();
+            assert!(fact.next_event().unwrap().is_some());
+            assert!(fact.next_event().unwrap().is_none());
+
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn read_from_stream() {
+        let (mut tx, rx) = std::os::unix::net::UnixStream::pair().unwrap();
+        std::thread::spawn(move || {
+            io::copy(
+                &mut File::open("test_data/test_events.json").unwrap(),
+                &mut tx,
+            )
+            .unwrap()
+        });
+
+        let mut fact = FileEventsFactory::from_stream(rx).unwrap();
+        assert!(fact.startup().is_some());
+        let mut events = 0;
+        while fact.next_event().unwrap().is_some() {
+            events += 1;
+        }
+        assert_eq!(events, 5);
+    }
+
+    #[test]
+    fn read_from_compressed_file() {
+        let path = std::env::temp_dir().join(format!("retis-events-{}.zst", std::process::id()));
+        zstd::stream::copy_encode(
+            File::open("test_data/test_events.json").unwrap(),
+            File::create(&path).unwrap(),
+            0,
+        )
+        .unwrap();
+
+        let mut fact = FileEventsFactory::from_path(&path).unwrap();
+        let mut events = 0;
+        while fact.next_event().unwrap().is_some() {
+            events += 1;
+        }
+        assert_eq!(events, 5);
+
+        assert!(is_compressed(&mut File::open(&path).unwrap()).unwrap());
+        assert!(!is_compressed(&mut File::open("test_data/test_events.json").unwrap()).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -0,0 +1,304 @@
+//! # Event file index
+//!
+//! Lightweight index of an event file, stored alongside it, allowing to only
+//! read the parts of the file containing events in a given time range or
+//! events from a given packet.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Number of events per index block.
+const INDEX_BLOCK_EVENTS: u64 = 1000;
+/// Current version of the index format.
+const INDEX_VERSION: u32 = 1;
+
+/// Block of consecutive events in an event file.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct IndexBlock {
+    /// Offset of the first event of the block in the file.
+    pub offset: u64,
+    /// Number of events in the block.
+    pub events: u64,
+    /// Lowest event timestamp in the block. Events are not strictly ordered
+    /// in a file.
+    pub min_ts: u64,
+    /// Highest event timestamp in the block.
+    pub max_ts: u64,
+}
+
+/// Part of an event file containing all events of a given packet.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct TrackingRange {
+    /// Offset of the first event of the packet.
+    pub start: u64,
+    /// Offset right after the last event of the packet.
+    pub end: u64,
+}
+
+/// Index of an event file.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct EventIndex {
+    /// Index format version.
+    version: u32,
+    /// Offset of the end of the indexed data, aka. the size of the file.
+    pub end: u64,
+    /// Blocks of events, in the file order.
+    pub blocks: Vec<IndexBlock>,
+    /// Part of the file containing events of a given packet, by tracking id
+    /// (in hex, as displayed).
+    pub tracking: BTreeMap<String, TrackingRange>,
+}
+
+impl EventIndex {
+    /// Path of the index of an event file.
+    pub fn path(file: &Path) -> PathBuf {
+        let mut path = file.to_path_buf().into_os_string();
+        path.push(".idx");
+        PathBuf::from(path)
+    }
+
+    /// Load the index of an event file, if any. The index is only returned if
+    /// it matches the file.
+    pub fn from_file(file: &Path) -> Result<Option<Self>> {
+        let path = Self::path(file);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let index: Self = serde_json::from_reader(io::BufReader::new(File::open(&path)?))
+            .map_err(|e| anyhow!("Could not parse {}: {e}", path.display()))?;
+        if index.version != INDEX_VERSION {
+            bail!(
+                "Unsupported index version ({}) in {}",
+                index.version,
+                path.display()
+            );
+        }
+        if fs::metadata(file)?.len() != index.end {
+            bail!("{} does not match {}", path.display(), file.display());
+        }
+
+        Ok(Some(index))
+    }
+
+    /// Write the index of an event file.
+    pub(crate) fn to_file(&self, file: &Path) -> io::Result<()> {
+        serde_json::to_writer(BufWriter::new(File::create(Self::path(file))?), self)
+            .map_err(io::Error::other)
+    }
+
+    /// Get the part of the file, as (start, end) offsets, containing all the
+    /// events with a timestamp in [from, to]. Returns None if no event can
+    /// match.
+    pub fn time_range(&self, from: u64, to: u64) -> Option<(u64, u64)> {
+        let first = self
+            .blocks
+            .iter()
+            .position(|b| b.max_ts >= from && b.min_ts <= to)?;
+        // Unwrap as we know at least one block matches.
+        let last = self
+            .blocks
+            .iter()
+            .rposition(|b| b.max_ts >= from && b.min_ts <= to)
+            .unwrap();
+
+        Some((
+            self.blocks[first].offset,
+            self.blocks
+                .get(last + 1)
+                .map(|b| b.offset)
+                .unwrap_or(self.end),
+        ))
+    }
+
+    /// Get the part of the file, as (start, end) offsets, containing all the
+    /// events of a given packet. Returns None if the packet was not seen.
+    pub fn tracking_range(&self, id: u128) -> Option<(u64, u64)> {
+        self.tracking
+            .get(&format!("{id:x}"))
+            .map(|range| (range.start, range.end))
+    }
+}
+
+/// Subset of the event fields used for indexing.
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct IndexedEvent {
+    common: Option<IndexedCommon>,
+    skb_tracking: Option<IndexedTracking>,
+}
+
+#[derive(Deserialize)]
+struct IndexedCommon {
+    timestamp: u64,
+}
+
+#[derive(Deserialize)]
+struct IndexedTracking {
+    orig_head: u64,
+    timestamp: u64,
+}
+
+/// Builds an `EventIndex` from serialized events, as they are written to a
+/// file.
+pub(crate) struct IndexBuilder {
+    index: EventIndex,
+    // Is the next write starting a new line?
+    line_start: bool,
+}
+
+impl IndexBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            index: EventIndex {
+                version: INDEX_VERSION,
+                ..Default::default()
+            },
+            line_start: true,
+        }
+    }
+
+    /// Index data written at a given offset of the file. Data not made of a
+    /// single full event (line) is not indexed, but is still part of the
+    /// current block.
+    pub(crate) fn add(&mut self, offset: u64, buf: &[u8]) {
+        let end = offset + buf.len() as u64;
+        let full = self.line_start
+            && buf.ends_with(b"\n")
+            && buf.iter().filter(|b| **b == b'\n').count() == 1;
+        self.line_start = buf.ends_with(b"\n");
+
+        if !full {
+            return;
+        }
+        let event = match serde_json::from_slice::<IndexedEvent>(buf) {
+            Ok(event) => event,
+            Err(_) => return,
+        };
+
+        if let Some(common) = event.common {
+            let ts = common.timestamp;
+            match self.index.blocks.last_mut() {
+                Some(block) if block.events < INDEX_BLOCK_EVENTS => {
+                    block.events += 1;
+                    block.min_ts = block.min_ts.min(ts);
+                    block.max_ts = block.max_ts.max(ts);
+                }
+                _ => self.index.blocks.push(IndexBlock {
+                    offset,
+                    events: 1,
+                    min_ts: ts,
+                    max_ts: ts,
+                }),
+            }
+        }
+
+        if let Some(tracking) = event.skb_tracking {
+            let id = ((tracking.timestamp as u128) << 64) | tracking.orig_head as u128;
+            self.index
+                .tracking
+                .entry(format!("{id:x}"))
+                .and_modify(|range| range.end = end)
+                .or_insert(TrackingRange { start: offset, end });
+        }
+    }
+
+    /// Get the index, given the final size of the file.
+    pub(crate) fn finish(mut self, end: u64) -> EventIndex {
+        self.index.end = end;
+        self.index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(ts: u64, tracking: Option<(u64, u64)>) -> Vec<u8> {
+        let mut event = match tracking {
+            Some((orig_head, timestamp)) => format!(
+                "{{\"common\":{{\"timestamp\":{ts}}},\"skb-tracking\":{{\"orig_head\":{orig_head},\"timestamp\":{timestamp},\"skb\":1}}}}"
+            ),
+            None => format!("{{\"common\":{{\"timestamp\":{ts}}}}}"),
+        };
+        event.push('\n');
+        event.into_bytes()
+    }
+
+    #[test]
+    fn index() {
+        let mut builder = IndexBuilder::new();
+        let mut offset = 100;
+        let mut offsets = Vec::new();
+
+        for i in 0..2500 {
+            let tracking = match i {
+                10 | 1500 => Some((0xff, 42)),
+                _ => None,
+            };
+            let event = event(1000 + i, tracking);
+            builder.add(offset, &event);
+            offsets.push(offset);
+            offset += event.len() as u64;
+        }
+        // Partial writes are not indexed.
+        builder.add(offset, b"{\"common\":");
+        builder.add(offset + 10, b"{\"timestamp\":0}}\n");
+        offset += 27;
+
+        let index = builder.finish(offset);
+        assert_eq!(index.blocks.len(), 3);
+        assert_eq!(
+            index.blocks[1],
+            IndexBlock {
+                offset: offsets[1000],
+                events: 1000,
+                min_ts: 2000,
+                max_ts: 2999,
+            }
+        );
+        assert_eq!(index.blocks[2].events, 500);
+
+        assert_eq!(
+            index.time_range(2500, 2600),
+            Some((offsets[1000], offsets[2000]))
+        );
+        assert_eq!(index.time_range(0, 1000), Some((offsets[0], offsets[1000])));
+        assert_eq!(index.time_range(3400, 4000), Some((offsets[2000], offset)));
+        assert_eq!(index.time_range(4000, 5000), None);
+
+        assert_eq!(
+            index.tracking_range((42 << 64) | 0xff),
+            Some((offsets[10], offsets[1501]))
+        );
+        assert_eq!(index.tracking_range(42), None);
+    }
+
+    #[test]
+    fn index_file() {
+        let path = std::env::temp_dir().join(format!("retis-index-{}", std::process::id()));
+        fs::write(&path, "0123456789").unwrap();
+
+        let mut builder = IndexBuilder::new();
+        builder.add(0, &event(1, Some((1, 1))));
+        let index = builder.finish(10);
+        index.to_file(&path).unwrap();
+
+        assert_eq!(EventIndex::from_file(&path).unwrap(), Some(index));
+
+        // The index does not match the file anymore.
+        fs::write(&path, "01234").unwrap();
+        assert!(EventIndex::from_file(&path).is_err());
+
+        fs::remove_file(EventIndex::path(&path)).unwrap();
+        assert!(EventIndex::from_file(&path).unwrap().is_none());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -7,4 +7,6 @@
 pub mod file;
 pub use file::*;
 
+pub mod index;
+pub mod proto;
 pub mod rotate;
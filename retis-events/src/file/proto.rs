@@ -0,0 +1,927 @@
+//! # Protobuf serialization
+//!
+//! Alternative serialization of events, so they can be parsed by non-Rust
+//! consumers using standard protobuf tooling. Event files start with
+//! `PROTO_MAGIC`, followed by length-delimited `Event` messages.
+//!
+//! Messages are typed per section and generated from the JSON Schema of events
+//! (`retis schema --proto` prints the matching `.proto` definition). Field
+//! numbers are derived from field names, so they are stable across versions as
+//! long as fields are not renamed. Parts of the schema without a protobuf
+//! equivalent (e.g. tuples or values of mixed types) are stored as strings
+//! holding their JSON representation.
+//!
+//! Decoded events go through the same compatibility layer as JSON ones.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Read},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use once_cell::sync::OnceCell;
+use prost::{
+    bytes::Buf,
+    encoding::{decode_key, decode_varint, encode_key, encode_varint, WireType, MAX_TAG},
+};
+use serde_json::{Map, Value};
+
+use super::file::guess_version;
+use crate::{compat::CompatVersion, Event};
+
+/// Magic starting protobuf event files.
+pub const PROTO_MAGIC: &[u8; 8] = b"RETISPB1";
+
+/// Maximum size of a single event. Lengths are read from the input and
+/// trusted to allocate buffers, they must be bounded.
+pub const MAX_EVENT_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Maximum nesting of messages when decoding.
+const MAX_DEPTH: usize = 64;
+
+/// Name of the root message.
+const ROOT: &str = "Event";
+
+/// Type of a protobuf field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Type {
+    Bool,
+    Int64,
+    Uint64,
+    Double,
+    String,
+    /// Value without protobuf equivalent, stored as a JSON string.
+    Json,
+    /// Nested message, by name.
+    Message(String),
+}
+
+impl Type {
+    fn wire_type(&self) -> WireType {
+        match self {
+            Type::Bool | Type::Int64 | Type::Uint64 => WireType::Varint,
+            Type::Double => WireType::SixtyFourBit,
+            Type::String | Type::Json | Type::Message(_) => WireType::LengthDelimited,
+        }
+    }
+
+    /// Value of scalar fields not present on the wire.
+    fn default_value(&self) -> Option<Value> {
+        match self {
+            Type::Bool => Some(Value::Bool(false)),
+            Type::Int64 | Type::Uint64 => Some(Value::from(0)),
+            Type::Double => Some(Value::from(0.0)),
+            Type::String => Some(Value::String(String::new())),
+            Type::Json | Type::Message(_) => None,
+        }
+    }
+
+    fn proto_name(&self) -> &str {
+        match self {
+            Type::Bool => "bool",
+            Type::Int64 => "int64",
+            Type::Uint64 => "uint64",
+            Type::Double => "double",
+            Type::String | Type::Json => "string",
+            Type::Message(name) => name,
+        }
+    }
+}
+
+/// Cardinality of a protobuf field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Label {
+    Single,
+    Repeated,
+    /// Map with string keys.
+    Map,
+}
+
+/// Protobuf field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// Name of the field, as found in the JSON representation.
+    pub name: String,
+    pub number: u32,
+    pub r#type: Type,
+    pub label: Label,
+    /// The field can be missing (or null in the JSON representation).
+    pub nullable: bool,
+}
+
+/// Protobuf message.
+#[derive(Clone, Debug, Default)]
+pub struct Message {
+    /// Fields, by number.
+    pub fields: BTreeMap<u32, Field>,
+    names: HashMap<String, u32>,
+}
+
+impl Message {
+    /// Get a field by name.
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.names.get(name).and_then(|n| self.fields.get(n))
+    }
+
+    fn insert(&mut self, field: Field) -> Result<()> {
+        if let Some(other) = self.fields.get(&field.number) {
+            if other.name != field.name {
+                bail!(
+                    "Fields {} and {} have the same number ({})",
+                    other.name,
+                    field.name,
+                    field.number
+                );
+            }
+        }
+
+        self.names.insert(field.name.clone(), field.number);
+        self.fields.insert(field.number, field);
+        Ok(())
+    }
+}
+
+/// Protobuf messages describing events.
+#[derive(Debug)]
+pub struct ProtoSchema {
+    /// Messages, by name. Events are `Event` messages.
+    pub messages: BTreeMap<String, Message>,
+}
+
+/// Get the protobuf messages describing events, generated from their JSON
+/// Schema.
+pub fn schema() -> Result<&'static ProtoSchema> {
+    static SCHEMA: OnceCell<ProtoSchema> = OnceCell::new();
+    SCHEMA
+        .get_or_try_init(|| ProtoSchema::from_json_schema(schemars::schema_for!(Event).as_value()))
+}
+
+impl ProtoSchema {
+    /// Generate protobuf messages from the JSON Schema of events.
+    pub fn from_json_schema(schema: &Value) -> Result<Self> {
+        let mut builder = Builder {
+            root: schema,
+            messages: BTreeMap::new(),
+            refs: HashMap::new(),
+        };
+
+        let kind = builder.kind(schema, ROOT)?;
+        if kind.r#type != Type::Message(ROOT.to_string()) || kind.label != Label::Single {
+            bail!("Events are not described as objects");
+        }
+
+        Ok(Self {
+            messages: builder.messages,
+        })
+    }
+
+    /// Generate the `.proto` definition of the messages.
+    pub fn to_proto(&self) -> String {
+        let mut proto = String::from("syntax = \"proto3\";\n\npackage retis;\n");
+
+        self.messages.iter().for_each(|(name, message)| {
+            proto.push_str(&format!("\nmessage {name} {{\n"));
+            message.fields.values().for_each(|field| {
+                let proto_type = field.r#type.proto_name();
+                let name = field.name.replace('-', "_");
+                let decl = match field.label {
+                    Label::Single if field.nullable => format!("optional {proto_type} {name}"),
+                    Label::Single => format!("{proto_type} {name}"),
+                    Label::Repeated => format!("repeated {proto_type} {name}"),
+                    Label::Map => format!("map<string, {proto_type}> {name}"),
+                };
+
+                if field.r#type == Type::Json {
+                    proto.push_str("    // JSON representation.\n");
+                }
+                proto.push_str(&format!("    {decl} = {};\n", field.number));
+            });
+            proto.push_str("}\n");
+        });
+
+        proto
+    }
+
+    fn message(&self, name: &str) -> Result<&Message> {
+        self.messages
+            .get(name)
+            .ok_or_else(|| anyhow!("Unknown message {name}"))
+    }
+
+    fn encode_message(
+        &self,
+        name: &str,
+        obj: &Map<String, Value>,
+        buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let message = self.message(name)?;
+
+        for (key, val) in obj.iter().filter(|(_, val)| !val.is_null()) {
+            let field = message
+                .field(key)
+                .ok_or_else(|| anyhow!("Unknown field {key} in message {name}"))?;
+
+            let res = match (field.label, val) {
+                (Label::Single, _) => self.encode_value(field.number, &field.r#type, val, buf),
+                (Label::Repeated, Value::Array(vals)) => vals
+                    .iter()
+                    .try_for_each(|val| self.encode_value(field.number, &field.r#type, val, buf)),
+                (Label::Map, Value::Object(map)) => map.iter().try_for_each(|(k, v)| {
+                    let mut entry = Vec::new();
+                    encode_bytes(1, k.as_bytes(), &mut entry);
+                    self.encode_value(2, &field.r#type, v, &mut entry)?;
+                    encode_bytes(field.number, &entry, buf);
+                    Ok(())
+                }),
+                _ => Err(anyhow!("Invalid value")),
+            };
+            res.with_context(|| format!("Cannot encode field {key} of message {name}"))?;
+        }
+
+        Ok(())
+    }
+
+    fn encode_value(
+        &self,
+        number: u32,
+        r#type: &Type,
+        val: &Value,
+        buf: &mut Vec<u8>,
+    ) -> Result<()> {
+        let invalid = || anyhow!("Invalid value ({val}), expected {:?}", r#type);
+
+        match r#type {
+            Type::Bool => {
+                encode_key(number, WireType::Varint, buf);
+                encode_varint(val.as_bool().ok_or_else(invalid)? as u64, buf);
+            }
+            Type::Int64 => {
+                encode_key(number, WireType::Varint, buf);
+                encode_varint(val.as_i64().ok_or_else(invalid)? as u64, buf);
+            }
+            Type::Uint64 => {
+                encode_key(number, WireType::Varint, buf);
+                encode_varint(val.as_u64().ok_or_else(invalid)?, buf);
+            }
+            Type::Double => {
+                encode_key(number, WireType::SixtyFourBit, buf);
+                buf.extend_from_slice(&val.as_f64().ok_or_else(invalid)?.to_le_bytes());
+            }
+            Type::String => encode_bytes(number, val.as_str().ok_or_else(invalid)?.as_bytes(), buf),
+            Type::Json => encode_bytes(number, serde_json::to_string(val)?.as_bytes(), buf),
+            Type::Message(name) => {
+                let Value::Object(obj) = val else {
+                    return Err(invalid());
+                };
+
+                let mut message = Vec::new();
+                self.encode_message(name, obj, &mut message)?;
+                encode_bytes(number, &message, buf);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_message(
+        &self,
+        name: &str,
+        mut data: &[u8],
+        depth: usize,
+    ) -> Result<Map<String, Value>> {
+        if depth > MAX_DEPTH {
+            bail!("Protobuf messages are nested too deeply");
+        }
+
+        let message = self.message(name)?;
+        let mut obj = Map::new();
+
+        while data.has_remaining() {
+            let (number, wire_type) = decode_key(&mut data)?;
+            let Some(field) = message.fields.get(&number) else {
+                skip_field(wire_type, &mut data)?;
+                continue;
+            };
+
+            match field.label {
+                Label::Single => {
+                    let val = self.decode_value(&field.r#type, wire_type, &mut data, depth)?;
+                    obj.insert(field.name.clone(), val);
+                }
+                Label::Repeated => {
+                    let Value::Array(vals) = obj
+                        .entry(field.name.clone())
+                        .or_insert_with(|| Value::Array(Vec::new()))
+                    else {
+                        bail!("Field {} of message {name} is not repeated", field.name);
+                    };
+
+                    // Repeated scalars can be packed.
+                    if wire_type == WireType::LengthDelimited
+                        && field.r#type.wire_type() != WireType::LengthDelimited
+                    {
+                        let mut packed = take(&mut data)?;
+                        while packed.has_remaining() {
+                            let wire_type = field.r#type.wire_type();
+                            vals.push(self.decode_value(
+                                &field.r#type,
+                                wire_type,
+                                &mut packed,
+                                depth,
+                            )?);
+                        }
+                    } else {
+                        vals.push(self.decode_value(&field.r#type, wire_type, &mut data, depth)?);
+                    }
+                }
+                Label::Map => {
+                    let mut entry = take(&mut data)?;
+                    let (mut key, mut val) = (String::new(), None);
+                    while entry.has_remaining() {
+                        match decode_key(&mut entry)? {
+                            (1, WireType::LengthDelimited) => {
+                                key = String::from_utf8(take(&mut entry)?.to_vec())?
+                            }
+                            (2, wire_type) => {
+                                val = Some(self.decode_value(
+                                    &field.r#type,
+                                    wire_type,
+                                    &mut entry,
+                                    depth,
+                                )?)
+                            }
+                            (_, wire_type) => skip_field(wire_type, &mut entry)?,
+                        }
+                    }
+
+                    let val = val
+                        .or_else(|| field.r#type.default_value())
+                        .ok_or_else(|| {
+                            anyhow!("Missing value in field {} of message {name}", field.name)
+                        })?;
+                    let Value::Object(map) = obj
+                        .entry(field.name.clone())
+                        .or_insert_with(|| Value::Object(Map::new()))
+                    else {
+                        bail!("Field {} of message {name} is not a map", field.name);
+                    };
+                    map.insert(key, val);
+                }
+            }
+        }
+
+        // Fields with default values can be omitted on the wire.
+        message
+            .fields
+            .values()
+            .filter(|field| !field.nullable && !obj.contains_key(&field.name))
+            .for_each(|field| {
+                let default = match field.label {
+                    Label::Single => field.r#type.default_value(),
+                    Label::Repeated => Some(Value::Array(Vec::new())),
+                    Label::Map => Some(Value::Object(Map::new())),
+                };
+                if let Some(default) = default {
+                    obj.insert(field.name.clone(), default);
+                }
+            });
+
+        Ok(obj)
+    }
+
+    fn decode_value(
+        &self,
+        r#type: &Type,
+        wire_type: WireType,
+        data: &mut &[u8],
+        depth: usize,
+    ) -> Result<Value> {
+        if wire_type != r#type.wire_type() {
+            bail!("Invalid wire type {wire_type:?}, expected {:?}", r#type);
+        }
+
+        Ok(match r#type {
+            Type::Bool => Value::Bool(decode_varint(data)? != 0),
+            Type::Int64 => Value::from(decode_varint(data)? as i64),
+            Type::Uint64 => Value::from(decode_varint(data)?),
+            Type::Double => {
+                if data.remaining() < 8 {
+                    bail!("Truncated protobuf message");
+                }
+                Value::from(data.get_f64_le())
+            }
+            Type::String => Value::String(String::from_utf8(take(data)?.to_vec())?),
+            Type::Json => serde_json::from_slice(take(data)?)?,
+            Type::Message(name) => {
+                Value::Object(self.decode_message(name, take(data)?, depth + 1)?)
+            }
+        })
+    }
+}
+
+/// Kind of values described by a JSON Schema.
+#[derive(Clone)]
+struct Kind {
+    r#type: Type,
+    label: Label,
+    nullable: bool,
+}
+
+impl Kind {
+    fn single(r#type: Type) -> Self {
+        Self {
+            r#type,
+            label: Label::Single,
+            nullable: false,
+        }
+    }
+}
+
+/// Generates protobuf messages from a JSON Schema.
+struct Builder<'a> {
+    root: &'a Value,
+    messages: BTreeMap<String, Message>,
+    /// Kinds of the already visited references.
+    refs: HashMap<String, Kind>,
+}
+
+impl<'a> Builder<'a> {
+    fn resolve(&self, reference: &str) -> Result<&'a Value> {
+        let root: &'a Value = self.root;
+        reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+            .ok_or_else(|| anyhow!("Invalid schema reference {reference}"))
+    }
+
+    /// Get the kind of values described by a schema. Inline objects are named
+    /// after `name`, referenced ones after their definition.
+    fn kind(&mut self, mut schema: &'a Value, name: &str) -> Result<Kind> {
+        let mut name = sanitize(name);
+        let mut references = Vec::new();
+        // Nullable where used, or in the referenced definitions.
+        let (mut outer, mut nullable) = (false, false);
+
+        loop {
+            // Option of referenced types.
+            if let Some([a, b]) = schema
+                .get("anyOf")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+            {
+                if is_null(a) || is_null(b) {
+                    schema = if is_null(a) { b } else { a };
+                    match references.is_empty() {
+                        true => outer = true,
+                        false => nullable = true,
+                    }
+                    continue;
+                }
+            }
+
+            if let Some(reference) = schema
+                .get("$ref")
+                .and_then(Value::as_str)
+                .filter(|_| schema.get("properties").is_none())
+            {
+                if let Some(kind) = self.refs.get(reference) {
+                    return Ok(Kind {
+                        nullable: outer || nullable || kind.nullable,
+                        ..kind.clone()
+                    });
+                }
+
+                // Placeholder for recursive definitions not described as
+                // messages.
+                self.refs
+                    .insert(reference.to_string(), Kind::single(Type::Json));
+                references.push(reference);
+
+                name = sanitize(reference.rsplit('/').next().unwrap_or(reference));
+                schema = self.resolve(reference)?;
+                continue;
+            }
+
+            break;
+        }
+
+        let mut types: Vec<&str> = match schema.get("type") {
+            Some(Value::String(r#type)) => vec![r#type.as_str()],
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if types.contains(&"null") {
+            nullable = true;
+            types.retain(|t| *t != "null");
+        }
+
+        let kind = match types.as_slice() {
+            ["boolean"] => Kind::single(Type::Bool),
+            ["integer"] if is_unsigned(schema) => Kind::single(Type::Uint64),
+            ["integer"] => Kind::single(Type::Int64),
+            ["number"] => Kind::single(Type::Double),
+            ["string"] => Kind::single(Type::String),
+            ["array"] => self.array(schema, &name)?,
+            [] if self.variants_are_strings(schema)? => Kind::single(Type::String),
+            ["object"] | [] => self.object(schema, &name, &references)?,
+            _ => Kind::single(Type::Json),
+        };
+        let kind = Kind {
+            nullable: nullable || kind.nullable,
+            ..kind
+        };
+
+        references.iter().for_each(|reference| {
+            self.refs.insert(reference.to_string(), kind.clone());
+        });
+
+        Ok(Kind {
+            nullable: outer || kind.nullable,
+            ..kind
+        })
+    }
+
+    fn array(&mut self, schema: &'a Value, name: &str) -> Result<Kind> {
+        let items = match schema.get("items") {
+            Some(items) if schema.get("prefixItems").is_none() => items,
+            _ => return Ok(Kind::single(Type::Json)),
+        };
+
+        let item = self.kind(items, &format!("{name}Item"))?;
+        Ok(match item.label {
+            Label::Single if !item.nullable || item.r#type == Type::Json => Kind {
+                r#type: item.r#type,
+                label: Label::Repeated,
+                nullable: false,
+            },
+            _ => Kind::single(Type::Json),
+        })
+    }
+
+    fn object(&mut self, schema: &'a Value, name: &str, references: &[&str]) -> Result<Kind> {
+        // Maps.
+        if let Some(values @ Value::Object(_)) = schema.get("additionalProperties") {
+            if schema.get("properties").is_none() {
+                let value = self.kind(values, &format!("{name}Value"))?;
+                return Ok(match value.label {
+                    Label::Single if !value.nullable || value.r#type == Type::Json => Kind {
+                        r#type: value.r#type,
+                        label: Label::Map,
+                        nullable: false,
+                    },
+                    _ => Kind::single(Type::Json),
+                });
+            }
+        }
+
+        let mut properties = Vec::new();
+        if !self.properties(schema, false, &mut properties)? || properties.is_empty() {
+            return Ok(Kind::single(Type::Json));
+        }
+
+        // Register the message before building its fields, for recursive
+        // definitions.
+        let name = self.unique(name);
+        let kind = Kind::single(Type::Message(name.clone()));
+        self.messages.insert(name.clone(), Message::default());
+        references.iter().for_each(|reference| {
+            self.refs.insert(reference.to_string(), kind.clone());
+        });
+
+        let mut message = Message::default();
+        for (property, schema, optional) in properties {
+            let prop = self.kind(schema, &format!("{name}{}", camel_case(property)))?;
+            let nullable = optional || prop.nullable;
+
+            let field = match message.field(property) {
+                // Fields found in multiple variants.
+                Some(field) if field.r#type == prop.r#type && field.label == prop.label => Field {
+                    nullable: nullable || field.nullable,
+                    ..field.clone()
+                },
+                Some(field) => Field {
+                    r#type: Type::Json,
+                    label: Label::Single,
+                    nullable: true,
+                    ..field.clone()
+                },
+                None => Field {
+                    name: property.to_string(),
+                    number: number(property),
+                    r#type: prop.r#type,
+                    label: prop.label,
+                    nullable,
+                },
+            };
+            message.insert(field)?;
+        }
+
+        self.messages.insert(name, message);
+        Ok(kind)
+    }
+
+    /// Collect the properties of an object, including the ones of all its
+    /// variants and referenced definitions, and whether they can be missing.
+    /// Returns false if the schema does not only describe objects.
+    fn properties(
+        &self,
+        schema: &'a Value,
+        optional: bool,
+        properties: &mut Vec<(&'a str, &'a Value, bool)>,
+    ) -> Result<bool> {
+        let Value::Object(obj) = schema else {
+            return Ok(false);
+        };
+        if obj.get("type").is_some_and(|t| *t != "object")
+            || obj
+                .get("additionalProperties")
+                .is_some_and(Value::is_object)
+        {
+            return Ok(false);
+        }
+
+        // References can come with extra properties, e.g. enum tags.
+        if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+            if !self.properties(self.resolve(reference)?, optional, properties)? {
+                return Ok(false);
+            }
+        }
+
+        let required: Vec<&str> = obj
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if let Some(Value::Object(props)) = obj.get("properties") {
+            props.iter().for_each(|(name, schema)| {
+                let optional = optional || !required.contains(&name.as_str());
+                properties.push((name.as_str(), schema, optional));
+            });
+        }
+
+        for (key, optional) in [("allOf", optional), ("oneOf", true), ("anyOf", true)] {
+            if let Some(Value::Array(variants)) = obj.get(key) {
+                for variant in variants {
+                    if !self.properties(variant, optional, properties)? {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Check if a schema describes strings only, e.g. enums without data.
+    fn variants_are_strings(&self, schema: &'a Value) -> Result<bool> {
+        if let Some(Value::Array(vals)) = schema.get("enum") {
+            return Ok(vals.iter().all(Value::is_string));
+        }
+
+        let Some(Value::Array(variants)) = schema.get("oneOf").or_else(|| schema.get("anyOf"))
+        else {
+            return Ok(false);
+        };
+
+        for mut variant in variants {
+            while let Some(reference) = variant.get("$ref").and_then(Value::as_str) {
+                variant = self.resolve(reference)?;
+            }
+
+            let string = match variant.get("type") {
+                Some(r#type) => *r#type == "string",
+                None => match variant.get("const").or_else(|| variant.get("enum")) {
+                    Some(Value::String(_)) => true,
+                    Some(Value::Array(vals)) => vals.iter().all(Value::is_string),
+                    _ => false,
+                },
+            };
+            if !string {
+                return Ok(false);
+            }
+        }
+
+        Ok(!variants.is_empty())
+    }
+
+    fn unique(&self, name: &str) -> String {
+        let mut unique = name.to_string();
+        let mut i = 1;
+        while self.messages.contains_key(&unique) {
+            i += 1;
+            unique = format!("{name}{i}");
+        }
+        unique
+    }
+}
+
+fn is_null(schema: &Value) -> bool {
+    schema.get("type").is_some_and(|t| *t == "null")
+}
+
+fn is_unsigned(schema: &Value) -> bool {
+    schema
+        .get("format")
+        .and_then(Value::as_str)
+        .is_some_and(|format| format.starts_with("uint"))
+        || schema
+            .get("minimum")
+            .and_then(Value::as_f64)
+            .is_some_and(|min| min >= 0.0)
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+fn camel_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Field number, derived from its name (FNV-1a) so it is stable across
+/// versions.
+fn number(name: &str) -> u32 {
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, b| {
+        (hash ^ b as u32).wrapping_mul(0x01000193)
+    });
+    let number = hash % MAX_TAG + 1;
+
+    // 19000 to 19999 are reserved by protobuf.
+    match (19000..20000).contains(&number) {
+        true => number + 1000,
+        false => number,
+    }
+}
+
+fn encode_bytes(number: u32, data: &[u8], buf: &mut Vec<u8>) {
+    encode_key(number, WireType::LengthDelimited, buf);
+    encode_varint(data.len() as u64, buf);
+    buf.extend_from_slice(data);
+}
+
+/// Take a length-delimited chunk out of the input.
+fn take<'a>(data: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let len = decode_varint(data)?;
+    if len > data.len() as u64 {
+        bail!("Truncated protobuf message");
+    }
+
+    let (chunk, rest) = data.split_at(len as usize);
+    *data = rest;
+    Ok(chunk)
+}
+
+fn skip_field(wire_type: WireType, data: &mut &[u8]) -> Result<()> {
+    let len = match wire_type {
+        WireType::Varint => {
+            decode_varint(data)?;
+            0
+        }
+        WireType::SixtyFourBit => 8,
+        WireType::ThirtyTwoBit => 4,
+        WireType::LengthDelimited => {
+            take(data)?;
+            0
+        }
+        WireType::StartGroup | WireType::EndGroup => bail!("Protobuf groups are not supported"),
+    };
+
+    if data.remaining() < len {
+        bail!("Truncated protobuf message");
+    }
+    data.advance(len);
+    Ok(())
+}
+
+/// Serialize an event as a length-delimited protobuf message.
+pub fn encode(event: &Event) -> Result<Vec<u8>> {
+    let Value::Object(event) = serde_json::to_value(event)? else {
+        bail!("Event is not a map");
+    };
+
+    let mut message = Vec::new();
+    schema()?.encode_message(ROOT, &event, &mut message)?;
+
+    let mut buf = Vec::with_capacity(message.len() + 10);
+    encode_varint(message.len() as u64, &mut buf);
+    buf.extend(message);
+    Ok(buf)
+}
+
+/// Read the next length-delimited protobuf event, in its JSON representation,
+/// or None at the end of the input. Events must then be converted using the
+/// compatibility layer, as JSON ones.
+pub fn decode_value<R: Read>(reader: &mut R) -> Result<Option<Value>> {
+    // Read the varint length prefix.
+    let mut len: u64 = 0;
+    for i in 0..10 {
+        let mut byte = [0];
+        if let Err(e) = reader.read_exact(&mut byte) {
+            if i == 0 && e.kind() == io::ErrorKind::UnexpectedEof {
+                return Ok(None);
+            }
+            return Err(e.into());
+        }
+
+        len |= ((byte[0] & 0x7f) as u64) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            if len > MAX_EVENT_SIZE {
+                bail!("Protobuf message too large ({len} bytes)");
+            }
+
+            let mut buf = vec![0; len as usize];
+            reader.read_exact(&mut buf)?;
+            return Ok(Some(Value::Object(
+                schema()?.decode_message(ROOT, &buf, 0)?,
+            )));
+        }
+    }
+
+    bail!("Invalid protobuf message length")
+}
+
+/// Read the first event of a protobuf file, which must be a startup event, and
+/// guess the compatibility version of the file from it.
+pub(crate) fn decode_first<R: Read>(reader: &mut R) -> Result<Option<(Value, CompatVersion)>> {
+    let Some(event) = decode_value(reader)? else {
+        return Ok(None);
+    };
+
+    let version = match &event {
+        Value::Object(obj) => guess_version(obj)?,
+        _ => bail!("File contains invalid protobuf data"),
+    };
+    Ok(Some((event, version)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::file::FileEventsFactory;
+
+    #[test]
+    fn proto_schema() {
+        let schema = schema().unwrap();
+
+        let common = schema.messages[ROOT].field("common").unwrap();
+        assert_eq!(common.r#type, Type::Message("CommonEvent".to_string()));
+        assert_eq!(common.label, Label::Single);
+        assert!(common.nullable);
+
+        let proto = schema.to_proto();
+        assert!(proto.contains("message Event {"));
+        assert!(proto.contains("message CommonEvent {"));
+        assert!(proto.contains(&format!(
+            "optional CommonEvent common = {};",
+            number("common")
+        )));
+    }
+
+    #[test]
+    fn proto_event() {
+        let mut fact = FileEventsFactory::from_path("test_data/test_events.json").unwrap();
+
+        let mut buf = PROTO_MAGIC.to_vec();
+        let mut events = Vec::new();
+        while let Some(mut event) = fact.next_event().unwrap() {
+            // Events are converted to the latest format while being read.
+            if let Some(startup) = event.startup.as_mut() {
+                startup.retis_version = format!("v{}", env!("CARGO_PKG_VERSION"));
+            }
+
+            buf.extend(encode(&event).unwrap());
+            events.push(serde_json::to_value(&event).unwrap());
+        }
+
+        let mut fact = FileEventsFactory::from_stream(Cursor::new(buf.clone())).unwrap();
+        let mut decoded = Vec::new();
+        while let Some(event) = fact.next_event().unwrap() {
+            decoded.push(serde_json::to_value(&event).unwrap());
+        }
+        assert_eq!(decoded, events);
+
+        // Truncated input.
+        let data = &buf[PROTO_MAGIC.len()..];
+        assert!(decode_value(&mut &data[..1]).is_err());
+
+        // Unbounded length.
+        let mut data = Vec::new();
+        encode_varint(MAX_EVENT_SIZE + 1, &mut data);
+        assert!(decode_value(&mut data.as_slice()).is_err());
+    }
+}
@@ -5,13 +5,25 @@ use std::{
     io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
     ops::Drop,
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
 use log::{error, info, warn};
 use nix::sys::utsname::uname;
 
-use crate::{compat::json, file::guess_version, helpers::time::*, *};
+use crate::{
+    compat::{json, section_versions},
+    file::{
+        guess_version,
+        index::{EventIndex, IndexBuilder},
+        is_compressed,
+        proto::{self, PROTO_MAGIC},
+        EventFormat,
+    },
+    helpers::time::*,
+    *,
+};
 
 /// Rotation policy
 ///
@@ -23,15 +35,64 @@ pub enum RotationPolicy {
     /// Split the output file based on its size.
     #[serde(rename = "size")]
     Size { limit: usize },
+    /// Split the output file based on the time spent writing to it, in
+    /// seconds.
+    #[serde(rename = "time")]
+    Time { interval: u64 },
+}
+
+/// Output file, optionally (zstd) compressed.
+enum OutFile {
+    Plain(BufWriter<File>),
+    Compressed(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl OutFile {
+    /// Flush and, if compressed, terminate the output.
+    fn finish(self) -> io::Result<()> {
+        match self {
+            OutFile::Plain(mut w) => w.flush(),
+            OutFile::Compressed(e) => e.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for OutFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            OutFile::Plain(w) => w.write(buf),
+            OutFile::Compressed(e) => e.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            OutFile::Plain(w) => w.flush(),
+            OutFile::Compressed(e) => e.flush(),
+        }
+    }
 }
 
 /// Writing to a file while following a rotation policy, which if conditions are
 /// met can generate multiple files spreading the overall input. A startup event
-/// is added at the top of all files generated by the writer.
+/// is added at the top of all files generated by the writer. Files can be
+/// compressed (zstd), in which case the rotation limit applies to the
+/// uncompressed data.
 pub struct RotateWriter {
-    inner: BufWriter<File>,
+    // Current output file, None once closed.
+    inner: Option<OutFile>,
     // Controls how rotation is done.
     policy: Option<RotationPolicy>,
+    // Compress the output files.
+    compress: bool,
+    // Serialization format of the events.
+    format: EventFormat,
+    // Number of rotated files to keep, older ones being removed.
+    keep: Option<u32>,
+    // Index of the current file, if enabled.
+    events_index: Option<IndexBuilder>,
+    // When the current file was created.
+    opened: Instant,
     // Target file name for the output. Will be suffixed following the rotation
     // policy rules.
     target: PathBuf,
@@ -43,72 +104,140 @@ pub struct RotateWriter {
     cmdline: String,
     // Monotonic clock offset, cached for using it in the initial events.
     monotonic_offset: TimeSpec,
+    // Collection information, cached for using it in the initial events.
+    collection: Option<CollectionInfo>,
 }
 
 impl RotateWriter {
-    /// Create a new `RotateWriter` given a file path and a rotation policy.
+    /// Create a new `RotateWriter` given a file path, a rotation policy,
+    /// whether the output should be compressed and the events format.
     pub fn new<P: AsRef<Path>>(
         file: P,
         policy: Option<RotationPolicy>,
+        compress: bool,
+        format: EventFormat,
         cmdline: &str,
         monotonic_offset: TimeSpec,
+        collection: Option<CollectionInfo>,
     ) -> Result<Self> {
         // Generate a startup event.
         let index = 0;
-        let startup =
-            serde_json::to_vec(&startup_event(cmdline, monotonic_offset, index, policy)?)?;
+        let startup = format.serialize(&startup_event(
+            cmdline,
+            monotonic_offset,
+            collection.as_ref(),
+            index,
+            policy,
+        )?)?;
 
-        let (inner, written) = Self::new_file(file.as_ref(), &startup)?;
+        let (inner, written) = Self::new_file(file.as_ref(), compress, format, &startup)?;
 
         Ok(Self {
-            inner,
+            inner: Some(inner),
             policy,
+            compress,
+            format,
+            keep: None,
+            events_index: None,
+            opened: Instant::now(),
             target: file.as_ref().to_path_buf(),
             index,
             written,
             cmdline: cmdline.to_string(),
             monotonic_offset,
+            collection,
         })
     }
 
-    fn new_file(target: &Path, head: &[u8]) -> io::Result<(BufWriter<File>, usize)> {
-        let mut w = BufWriter::new(
+    /// Only keep the `keep` last rotated files, older ones are removed.
+    pub fn keep(mut self, keep: Option<u32>) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Write an index alongside each (uncompressed, JSON) output file, see
+    /// `EventIndex`.
+    pub fn index(mut self, enabled: bool) -> Self {
+        self.events_index =
+            (enabled && !self.compress && self.format == EventFormat::Json).then(IndexBuilder::new);
+        self
+    }
+
+    fn new_file(
+        target: &Path,
+        compress: bool,
+        format: EventFormat,
+        startup: &[u8],
+    ) -> io::Result<(OutFile, usize)> {
+        let file = BufWriter::new(
             OpenOptions::new()
                 .create(true)
                 .write(true)
                 .truncate(true)
                 .open(target)?,
         );
+        let mut w = match compress {
+            true => OutFile::Compressed(zstd::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+            false => OutFile::Plain(file),
+        };
 
-        let mut written = w.write(head)?;
-        written += w.write(b"\n")?;
+        w.write_all(format.header())?;
+        w.write_all(startup)?;
+        let written = format.header().len() + startup.len();
 
         Ok((w, written))
     }
 
     fn close(&mut self) -> io::Result<()> {
-        // Flush the current buffer.
-        self.flush()?;
+        // Flush the current buffer and terminate the output.
+        match self.inner.take() {
+            Some(inner) => inner.finish()?,
+            None => return Ok(()),
+        }
 
         // Move the file, if needed.
-        if let Some(policy) = &self.policy {
-            match policy {
-                RotationPolicy::Size { .. } => {
-                    let mut target = self.target.clone().into_os_string();
-                    target.push(format!(".{}", self.index));
+        let path = match self.policy.is_some() {
+            // All policies suffix the output file with their index; we can
+            // reuse the internal index here.
+            true => {
+                let path = self.split_file(self.index);
+                fs::rename(&self.target, &path)?;
+                path
+            }
+            false => self.target.clone(),
+        };
 
-                    fs::rename(&self.target, &target)?;
+        // Write the file index, if any.
+        if let Some(builder) = self.events_index.take() {
+            builder.finish(self.written as u64).to_file(&path)?;
+            self.events_index = Some(IndexBuilder::new());
+        }
 
-                    // The `Size` policy suffix the output file with their index; we
-                    // can reuse the internal index here.
-                    self.index += 1;
+        if self.policy.is_some() {
+            // Remove the oldest file, if needed.
+            if let Some(old) = self.keep.and_then(|keep| self.index.checked_sub(keep)) {
+                let old = self.split_file(old);
+                for path in [EventIndex::path(&old), old] {
+                    match fs::remove_file(path) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                        _ => (),
+                    }
                 }
             }
+
+            self.index += 1;
         }
 
         Ok(())
     }
 
+    /// Path of the split file with a given index.
+    fn split_file(&self, index: u32) -> PathBuf {
+        let mut target = self.target.clone().into_os_string();
+        target.push(format!(".{index}"));
+        PathBuf::from(target)
+    }
+
     /// Rotate the file.
     fn rotate(&mut self) -> io::Result<()> {
         if self.policy.is_none() {
@@ -122,19 +251,25 @@ impl RotateWriter {
         self.close()?;
 
         // Generate a startup event.
-        let startup = serde_json::to_vec(
-            &startup_event(
-                &self.cmdline,
-                self.monotonic_offset,
-                self.index,
-                self.policy,
+        let startup = self
+            .format
+            .serialize(
+                &startup_event(
+                    &self.cmdline,
+                    self.monotonic_offset,
+                    self.collection.as_ref(),
+                    self.index,
+                    self.policy,
+                )
+                .map_err(io::Error::other)?,
             )
-            .map_err(io::Error::other)?,
-        )
-        .map_err(io::Error::other)?;
+            .map_err(io::Error::other)?;
 
         // Create the new file.
-        (self.inner, self.written) = Self::new_file(&self.target, &startup)?;
+        let (inner, written) = Self::new_file(&self.target, self.compress, self.format, &startup)?;
+        self.inner = Some(inner);
+        self.written = written;
+        self.opened = Instant::now();
 
         Ok(())
     }
@@ -143,23 +278,35 @@ impl RotateWriter {
 impl Write for RotateWriter {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         if let Some(policy) = &self.policy {
-            match policy {
-                RotationPolicy::Size { limit } => {
-                    if self.written + buf.len() > *limit {
-                        self.rotate()?;
-                    }
+            let rotate = match policy {
+                RotationPolicy::Size { limit } => self.written + buf.len() > *limit,
+                RotationPolicy::Time { interval } => {
+                    self.opened.elapsed() >= Duration::from_secs(*interval)
                 }
+            };
+            if rotate {
+                self.rotate()?;
             }
         }
 
-        let written = self.inner.write(buf)?;
+        let written = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| io::Error::other("Output file is closed"))?
+            .write(buf)?;
+        if let Some(builder) = self.events_index.as_mut() {
+            builder.add(self.written as u64, &buf[..written]);
+        }
         self.written += written;
 
         Ok(written)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        self.inner.flush()
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
     }
 }
 
@@ -176,9 +323,10 @@ impl Drop for RotateWriter {
 
 /// Generate an event with the startup section. This is used at
 /// post-processing time to have insights about the collection environment.
-pub(crate) fn startup_event(
+pub fn startup_event(
     cmdline: &str,
     clock_monotonic_offset: TimeSpec,
+    collection: Option<&CollectionInfo>,
     index: u32,
     policy: Option<RotationPolicy>,
 ) -> Result<Event> {
@@ -203,13 +351,45 @@ pub(crate) fn startup_event(
             kernel_release: release.to_string(),
             kernel_version: version.to_string(),
             hardware_name: machine.to_string(),
+            kernel_build_id: fs::read("/sys/kernel/notes")
+                .ok()
+                .and_then(|notes| build_id_from_notes(&notes)),
         },
+        collection: collection.cloned(),
         split_file: policy.map(|policy| SplitFile { id: index, policy }),
+        section_versions: Some(section_versions()),
     });
 
     Ok(event)
 }
 
+/// Retrieve the GNU build id from a list of ELF notes, e.g. from
+/// /sys/kernel/notes.
+fn build_id_from_notes(mut notes: &[u8]) -> Option<String> {
+    const NT_GNU_BUILD_ID: u32 = 3;
+
+    let read_u32 = |buf: &[u8], off: usize| -> Option<u32> {
+        Some(u32::from_ne_bytes(buf.get(off..off + 4)?.try_into().ok()?))
+    };
+    // Note names and descriptions are 4-byte aligned.
+    let align = |len: usize| (len + 3) & !3;
+
+    while notes.len() >= 12 {
+        let namesz = read_u32(notes, 0)? as usize;
+        let descsz = read_u32(notes, 4)? as usize;
+        let desc = 12 + align(namesz);
+
+        if read_u32(notes, 8)? == NT_GNU_BUILD_ID && notes.get(12..12 + namesz)? == b"GNU\0" {
+            let id = notes.get(desc..desc + descsz)?;
+            return Some(id.iter().map(|b| format!("{b:02x}")).collect());
+        }
+
+        notes = notes.get(desc + align(descsz)..)?;
+    }
+
+    None
+}
+
 /// Given a file name, reads it following a rotation policy. The rotation policy
 /// is detected automatically.
 pub struct RotateReader {
@@ -253,9 +433,10 @@ impl RotateReader {
         }
 
         // If allowed, fallback to trying using a known split file extension.
+        // The first files might have been removed (see `RotateWriter::keep`),
+        // use the first one available.
         if try_split {
-            let target = PathBuf::from(format!("{}.0", path.display()));
-            if target.is_file() {
+            if let Some(target) = Self::first_split_file(path) {
                 return Self::detect_policy_from_events(&target);
             }
         }
@@ -263,6 +444,33 @@ impl RotateReader {
         Err(anyhow!("Cannot open {}", path.display()))
     }
 
+    /// Find the split file with the lowest index for a given file name.
+    fn first_split_file(path: &Path) -> Option<PathBuf> {
+        let name = path.file_name()?.to_str()?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        let index = dir
+            .read_dir()
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix(name)?
+                    .strip_prefix('.')?
+                    .parse::<u32>()
+                    .ok()
+            })
+            .min()?;
+
+        let target = PathBuf::from(format!("{}.{index}", path.display()));
+        target.is_file().then_some(target)
+    }
+
     /// Given a file, detect the rotation policy reading the startup event, if
     /// any. It's important to check for the file existence before to throw real
     /// errors from this.
@@ -272,38 +480,47 @@ impl RotateReader {
     fn detect_policy_from_events(path: &PathBuf) -> Result<(PathBuf, u32, Option<RotationPolicy>)> {
         // Use a temporary BufReader to benefit from the `read_line`
         // implementation.
-        let mut reader = BufReader::new(File::open(path)?);
+        let mut file = File::open(path)?;
+        let mut reader: Box<dyn BufRead> = match is_compressed(&mut file)? {
+            true => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+            false => Box::new(BufReader::new(file)),
+        };
         let mut path = path.clone();
 
-        let mut line = String::new();
-        if reader.read_line(&mut line)? == 0 {
-            // File is empty, which is valid from our PoV here.
-            return Ok((path, 0, None));
-        }
+        let event = if reader.fill_buf()?.starts_with(PROTO_MAGIC) {
+            reader.consume(PROTO_MAGIC.len());
+            match proto::decode_first(&mut reader)? {
+                Some((event, version)) => json::from_value_with::<Event>(event, version, &[])?,
+                None => return Ok((path, 0, None)),
+            }
+        } else {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                // File is empty, which is valid from our PoV here.
+                return Ok((path, 0, None));
+            }
 
-        // Get the startup event in a 2-step way, to allow for backward
-        // compatibility logic to kick in.
-        let event: Event = match serde_json::from_str(line.as_str())? {
-            serde_json::Value::Object(ref obj) => {
-                json::from_str(line.as_str(), guess_version(obj)?)?
+            // Get the startup event in a 2-step way, to allow for backward
+            // compatibility logic to kick in.
+            match serde_json::from_str(line.as_str())? {
+                serde_json::Value::Object(ref obj) => {
+                    json::from_str::<Event>(line.as_str(), guess_version(obj)?)?
+                }
+                // Not supporting stored series here.
+                _ => return Ok((path, 0, None)),
             }
-            // Not supporting stored series here.
-            _ => return Ok((path, 0, None)),
         };
 
         if let Some(startup) = event.startup {
             if let Some(split) = startup.split_file {
-                match split.policy {
-                    RotationPolicy::Size { .. } => {
-                        if path.extension()
-                            == Some(<String as AsRef<OsStr>>::as_ref(&format!("{}", split.id)))
-                        {
-                            path.set_extension("");
-                            return Ok((path, split.id, Some(split.policy)));
-                        } else {
-                            warn!("File extension does not match the rotation policy");
-                        }
-                    }
+                // All policies suffix the files with their index.
+                if path.extension()
+                    == Some(<String as AsRef<OsStr>>::as_ref(&format!("{}", split.id)))
+                {
+                    path.set_extension("");
+                    return Ok((path, split.id, Some(split.policy)));
+                } else {
+                    warn!("File extension does not match the rotation policy");
                 }
             }
         }
@@ -317,9 +534,7 @@ impl RotateReader {
         policy: Option<RotationPolicy>,
     ) -> io::Result<Option<File>> {
         let target = match policy {
-            Some(RotationPolicy::Size { .. }) => {
-                PathBuf::from(format!("{}.{index}", target.display()))
-            }
+            Some(_) => PathBuf::from(format!("{}.{index}", target.display())),
             None => target.to_path_buf(),
         };
         if !target.is_file() {
@@ -359,3 +574,106 @@ impl Seek for RotateReader {
         self.inner.seek(pos)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::FileEventsFactory;
+
+    fn note(name: &[u8], r#type: u32, desc: &[u8]) -> Vec<u8> {
+        let mut note = Vec::new();
+        note.extend((name.len() as u32).to_ne_bytes());
+        note.extend((desc.len() as u32).to_ne_bytes());
+        note.extend(r#type.to_ne_bytes());
+        note.extend(name);
+        note.resize((note.len() + 3) & !3, 0);
+        note.extend(desc);
+        note.resize((note.len() + 3) & !3, 0);
+        note
+    }
+
+    #[test]
+    fn write_index() {
+        let path = std::env::temp_dir().join(format!("retis-rotate-{}", std::process::id()));
+
+        let mut writer = RotateWriter::new(
+            &path,
+            None,
+            false,
+            EventFormat::Json,
+            "retis",
+            TimeSpec::new(0, 0),
+            None,
+        )
+        .unwrap()
+        .index(true);
+        for ts in [10, 30, 20] {
+            writer
+                .write_all(format!("{{\"common\":{{\"timestamp\":{ts}}}}}\n").as_bytes())
+                .unwrap();
+        }
+        drop(writer);
+
+        let index = EventIndex::from_file(&path).unwrap().unwrap();
+        assert_eq!(index.blocks.len(), 1);
+        assert_eq!(index.blocks[0].events, 3);
+        assert_eq!((index.blocks[0].min_ts, index.blocks[0].max_ts), (10, 30));
+
+        fs::remove_file(EventIndex::path(&path)).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_protobuf() {
+        let path = std::env::temp_dir().join(format!("retis-rotate-pb-{}", std::process::id()));
+
+        // Rotate before each event.
+        let mut writer = RotateWriter::new(
+            &path,
+            Some(RotationPolicy::Size { limit: 1 }),
+            false,
+            EventFormat::Protobuf,
+            "retis",
+            TimeSpec::new(0, 0),
+            None,
+        )
+        .unwrap();
+        for timestamp in [10, 20, 30] {
+            let event = Event {
+                common: Some(CommonEvent {
+                    timestamp,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            writer
+                .write_all(&EventFormat::Protobuf.serialize(&event).unwrap())
+                .unwrap();
+        }
+        drop(writer);
+
+        let mut factory =
+            FileEventsFactory::new(Box::new(RotateReader::new(&path, true).unwrap())).unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = factory.next_event().unwrap() {
+            if event.startup.is_none() {
+                events.push(event.common.unwrap().timestamp);
+            }
+        }
+        assert_eq!(events, vec![10, 20, 30]);
+
+        (0..4).for_each(|i| fs::remove_file(format!("{}.{i}", path.display())).unwrap());
+    }
+
+    #[test]
+    fn build_id() {
+        let mut notes = note(b"Xen\0", 3, &[0; 6]);
+        notes.extend(note(b"GNU\0", 1, &[0; 16]));
+        notes.extend(note(b"GNU\0", 3, &[0xde, 0xad, 0xbe, 0xef, 0x01]));
+        assert_eq!(build_id_from_notes(&notes), Some("deadbeef01".to_string()));
+
+        assert_eq!(build_id_from_notes(&note(b"GNU\0", 1, &[0; 16])), None);
+        assert_eq!(build_id_from_notes(&[]), None);
+        assert_eq!(build_id_from_notes(&notes[..notes.len() - 4]), None);
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, Formatter};
+
+/// GRO/GSO section.
+#[event_section]
+#[derive(Default)]
+pub struct GroGsoEvent {
+    /// Operation: "gro-merge" (the packet was merged with another one),
+    /// "gro-complete" (a GRO packet is being passed up the stack) or
+    /// "gso-segment" (the packet was split into segments).
+    pub op: String,
+    /// Number of segments the packet was merged from or split into, if known.
+    pub segs: Option<u32>,
+    /// Address of the skb merged into the packet, for GRO merges. It can be
+    /// matched with the `skb` field of the skb-tracking section of other
+    /// events.
+    pub merged: Option<u64>,
+    /// Addresses of the first segments the packet was split into, for GSO
+    /// segmentations. They can be matched with the `skb` field of the
+    /// skb-tracking section of other events.
+    pub segments: Option<Vec<u64>>,
+    /// Error returned while segmenting the packet, if any.
+    pub err: Option<i32>,
+}
+
+impl EventFmt for GroGsoEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "{}", self.op)?;
+
+        if let Some(segs) = self.segs {
+            write!(f, " segs {segs}")?;
+        }
+
+        if let Some(merged) = self.merged {
+            write!(f, " merged skb {merged:x}")?;
+        }
+
+        if let Some(segments) = &self.segments {
+            write!(f, " skbs [")?;
+            let mut sep = DelimWriter::new(',');
+            for skb in segments.iter() {
+                sep.write(f)?;
+                write!(f, "{skb:x}")?;
+            }
+            write!(f, "]")?;
+        }
+
+        if let Some(err) = self.err {
+            write!(f, " err {err}")?;
+        }
+
+        Ok(())
+    }
+}
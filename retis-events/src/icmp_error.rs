@@ -0,0 +1,78 @@
+use std::{fmt, net::IpAddr};
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// ICMP error section.
+#[event_section]
+#[derive(Default)]
+pub struct IcmpErrorEvent {
+    /// Whether the error is an ICMPv6 one.
+    pub v6: bool,
+    /// ICMP (or ICMPv6) type.
+    pub r#type: u8,
+    /// ICMP (or ICMPv6) code.
+    pub code: u8,
+    /// Name of the error, e.g. "port-unreachable", "frag-needed" or
+    /// "ttl-exceeded".
+    pub error: String,
+    /// MTU reported by "frag-needed" and "packet-too-big" errors.
+    pub mtu: Option<u32>,
+    /// Header of the packet which triggered the error, as embedded in it.
+    pub orig: IcmpErrorOrig,
+}
+
+/// Header of the packet which triggered an ICMP error.
+#[event_type]
+#[derive(Default)]
+pub struct IcmpErrorOrig {
+    /// Source address.
+    pub src: String,
+    /// Destination address.
+    pub dst: String,
+    /// L4 protocol.
+    pub protocol: u8,
+    /// Source port, for TCP, UDP and SCTP.
+    pub sport: Option<u16>,
+    /// Destination port, for TCP, UDP and SCTP.
+    pub dport: Option<u16>,
+}
+
+impl IcmpErrorEvent {
+    /// Get the flow of the packet which triggered the error, which allows to
+    /// link the error to the events of this flow.
+    pub fn flow(&self) -> Option<PacketFlow> {
+        Some(PacketFlow {
+            src: self.orig.src.parse::<IpAddr>().ok()?,
+            dst: self.orig.dst.parse::<IpAddr>().ok()?,
+            protocol: self.orig.protocol,
+            ports: self.orig.sport.zip(self.orig.dport),
+        })
+    }
+}
+
+impl EventFmt for IcmpErrorEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({}/{})",
+            if self.v6 {
+                "icmpv6-error"
+            } else {
+                "icmp-error"
+            },
+            self.error,
+            self.r#type,
+            self.code
+        )?;
+
+        if let Some(mtu) = self.mtu {
+            write!(f, " mtu {mtu}")?;
+        }
+
+        match self.flow() {
+            Some(flow) => write!(f, " orig {flow}"),
+            None => write!(f, " orig {} > {}", self.orig.src, self.orig.dst),
+        }
+    }
+}
@@ -9,14 +9,20 @@ use crate::{event_section, event_type, Formatter};
 pub struct KernelEvent {
     /// Symbol name. I.e: which probe generated the event.
     pub symbol: String,
-    /// Probe type. One of "kprobe", "kretprobe" or "raw_tracepoint".
+    /// Probe type. One of "kprobe", "kretprobe", "raw_tracepoint" or "perf"
+    /// (CPU samples, in which case the symbol is the sampled instruction
+    /// pointer).
     pub probe_type: String,
+    /// Return value of the probed function. Only reported by kretprobes.
+    pub ret: Option<KernelRetval>,
+    /// Arguments of the probed function. Only reported when asked for.
+    pub args: Option<Vec<KernelArg>>,
     /// Stack trace.
     pub stack_trace: Option<StackTrace>,
 }
 
 impl EventFmt for KernelEvent {
-    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         write!(
             f,
             "[{}] {}",
@@ -24,15 +30,110 @@ impl EventFmt for KernelEvent {
                 "raw_tracepoint" => "tp",
                 "kprobe" => "k",
                 "kretprobe" => "kr",
+                "perf" => "perf",
                 _ => "invalid",
             },
-            self.symbol,
+            format.highlight(Highlight::Probe, &self.symbol),
         )?;
 
+        if let Some(ret) = &self.ret {
+            write!(f, " ")?;
+            ret.event_fmt(f, format)?;
+        }
+
+        if let Some(args) = &self.args {
+            write!(f, " args (")?;
+            args.iter().enumerate().try_for_each(|(i, arg)| {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                arg.event_fmt(f, format)
+            })?;
+            write!(f, ")")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Return value of a probed function, decoded using its type.
+#[event_type]
+#[derive(Default)]
+pub struct KernelRetval {
+    /// Kind of the returned value.
+    pub kind: KernelRetvalKind,
+    /// Returned value. Values of signed types are sign extended.
+    pub value: i64,
+    /// Symbolic name of the value, if any. This can be an errno name (for
+    /// error codes and error pointers), an enum variant name or NULL.
+    pub name: Option<String>,
+}
+
+impl EventFmt for KernelRetval {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        match self.kind {
+            KernelRetvalKind::Ptr => write!(f, "ret {:#x}", self.value as u64)?,
+            _ => write!(f, "ret {}", self.value)?,
+        }
+
+        if let Some(name) = &self.name {
+            write!(f, " ({name})")?;
+        }
+
         Ok(())
     }
 }
 
+/// Argument of a probed function, decoded using its type.
+#[event_type]
+#[derive(Default)]
+pub struct KernelArg {
+    /// Argument name.
+    pub name: String,
+    /// Raw value of the argument.
+    pub raw: u64,
+    /// Decoded value, if any. This can be an integer, an enum variant name or
+    /// a string.
+    pub value: Option<String>,
+    /// Decoded members of the struct the argument points to, if any.
+    pub members: Option<Vec<KernelArg>>,
+}
+
+impl EventFmt for KernelArg {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        write!(f, "{}=", self.name)?;
+
+        if let Some(value) = &self.value {
+            return write!(f, "{value}");
+        }
+
+        match &self.members {
+            Some(members) => {
+                write!(f, "{{")?;
+                members.iter().enumerate().try_for_each(|(i, member)| {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    member.event_fmt(f, format)
+                })?;
+                write!(f, "}}")
+            }
+            None => write!(f, "{:#x}", self.raw),
+        }
+    }
+}
+
+/// Kind of a returned value.
+#[event_type]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum KernelRetvalKind {
+    #[default]
+    Int,
+    Ptr,
+    Enum,
+}
+
 #[event_type]
 #[derive(Default)]
 pub struct StackTrace(pub Vec<String>);
@@ -0,0 +1,65 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// Kubernetes section. Set at post-processing time, when Kubernetes objects
+/// were retrieved during the collection, and reporting the pods, services or
+/// nodes the packet addresses and interface belong to.
+#[event_section]
+#[derive(Default)]
+pub struct KubeEvent {
+    /// Object the source address of the packet belongs to, if known.
+    pub src: Option<KubeEndpoint>,
+    /// Object the destination address of the packet belongs to, if known.
+    pub dst: Option<KubeEndpoint>,
+    /// Pod the interface of the event belongs to, if it is the host side of
+    /// a pod interface (e.g. a veth peer).
+    pub iface: Option<KubeEndpoint>,
+}
+
+impl EventFmt for KubeEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "kube")?;
+
+        if self.src.is_some() || self.dst.is_some() {
+            let endpoint = |e: &Option<KubeEndpoint>| match e {
+                Some(e) => e.to_string(),
+                None => "-".to_string(),
+            };
+            write!(f, " {} > {}", endpoint(&self.src), endpoint(&self.dst))?;
+        }
+
+        if let Some(iface) = &self.iface {
+            write!(f, " iface {iface}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Kubernetes object an address or interface belongs to.
+#[event_type]
+#[derive(Default)]
+pub struct KubeEndpoint {
+    /// Kind of object: "pod", "service" or "node".
+    pub kind: String,
+    /// Namespace of the object. Nodes are not namespaced.
+    pub namespace: Option<String>,
+    /// Name of the object.
+    pub name: String,
+}
+
+impl fmt::Display for KubeEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind.as_str() {
+            "service" => "svc",
+            kind => kind,
+        };
+
+        match &self.namespace {
+            Some(namespace) => write!(f, "{kind} {namespace}/{}", self.name),
+            None => write!(f, "{kind} {}", self.name),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, Formatter};
+
+/// Link aggregation (bonding and team) section.
+#[event_section]
+#[derive(Default)]
+pub struct LagEvent {
+    /// Operation: "xmit" (a packet is sent to a port of an aggregated device)
+    /// or "failover" (the active port of a bonding device changed).
+    pub op: String,
+    /// Kind of the aggregated device: "bond" or "team".
+    pub kind: String,
+    /// Name of the aggregated device.
+    pub master: String,
+    /// Bonding mode, e.g. "active-backup" or "802.3ad", if known.
+    pub mode: Option<String>,
+    /// Port the packet is sent to, or newly active port on failovers, if any.
+    pub port: Option<String>,
+    /// Previously active port on failovers, if any.
+    pub old_port: Option<String>,
+}
+
+impl EventFmt for LagEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.master)?;
+
+        if let Some(mode) = &self.mode {
+            write!(f, " ({mode})")?;
+        }
+
+        write!(f, " {}", self.op)?;
+
+        if self.op == "failover" {
+            write!(
+                f,
+                " {} > {}",
+                self.old_port.as_deref().unwrap_or("none"),
+                self.port.as_deref().unwrap_or("none"),
+            )?;
+        } else if let Some(port) = &self.port {
+            write!(f, " port {port}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -9,36 +9,79 @@ pub use events::*;
 pub mod display;
 pub use display::*;
 
+pub mod bridge;
+pub use bridge::*;
 pub mod common;
 pub use common::*;
 pub mod ct;
 pub use ct::*;
 pub mod dev;
 pub use dev::*;
+pub mod external;
+pub use external::*;
 pub mod kernel;
 pub use kernel::*;
+pub mod kube;
+pub use kube::*;
+pub mod gro_gso;
+pub use gro_gso::*;
+pub mod icmp_error;
+pub use icmp_error::*;
+pub mod lag;
+pub use lag::*;
+pub mod mem;
+pub use mem::*;
+pub mod mptcp;
+pub use mptcp::*;
+pub mod neigh;
+pub use neigh::*;
+pub mod nf;
+pub use nf::*;
 pub mod nft;
 pub use nft::*;
 pub mod ns;
 pub use ns::*;
+pub mod ns_lifecycle;
+pub use ns_lifecycle::*;
+pub mod offload;
+pub use offload::*;
 pub mod ovs;
 pub use ovs::*;
 pub mod packet;
 pub use packet::*;
+pub mod route;
+pub use route::*;
 pub mod skb;
 pub use skb::*;
 pub mod skb_drop;
 pub use skb_drop::*;
 pub mod skb_tracking;
 pub use skb_tracking::*;
+pub mod sock;
+pub use sock::*;
+pub mod tc;
+pub use tc::*;
+pub mod tcp;
+pub use tcp::*;
+pub mod tunnel;
+pub use tunnel::*;
 pub mod user;
 pub use user::*;
+pub mod wireguard;
+pub use wireguard::*;
+pub mod xdp;
+pub use xdp::*;
+pub mod xfrm;
+pub use xfrm::*;
+pub mod xsk;
+pub use xsk::*;
 
 pub(crate) mod compat;
 pub mod file;
 pub mod helpers;
 #[cfg(feature = "python")]
 pub mod python;
+pub mod sink;
 
 // Re-export derive macros.
 use retis_derive::*;
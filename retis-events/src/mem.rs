@@ -0,0 +1,126 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// Memory accounting section.
+#[event_section]
+#[derive(Default)]
+pub struct MemEvent {
+    /// Memory accounting of the packet and of its socket, if any.
+    pub skb: Option<MemSkb>,
+    /// State of a page pool, if any.
+    pub page_pool: Option<MemPagePool>,
+}
+
+/// Memory accounting of a packet.
+#[event_type]
+#[derive(Default)]
+pub struct MemSkb {
+    /// Memory used by the packet, including its metadata (`skb->truesize`).
+    pub truesize: u32,
+    /// Buffer occupancy of the socket the packet is attached to, if any.
+    pub sock: Option<MemSock>,
+}
+
+/// Socket buffers occupancy. Packets are dropped once the allocated memory
+/// exceeds the buffer size.
+#[event_type]
+#[derive(Default)]
+pub struct MemSock {
+    /// Memory allocated for received packets.
+    pub rmem_alloc: i32,
+    /// Receive buffer size.
+    pub rcvbuf: i32,
+    /// Memory allocated for packets being sent.
+    pub wmem_alloc: i32,
+    /// Send buffer size.
+    pub sndbuf: i32,
+    /// Number of packets dropped by the socket.
+    pub drops: u32,
+}
+
+/// State of a page pool, used by drivers to allocate and recycle the pages
+/// backing packets.
+#[event_type]
+#[derive(Default)]
+pub struct MemPagePool {
+    /// Address of the pool.
+    pub pool: u64,
+    /// Device using the pool. Only reported on newer kernels.
+    pub dev: Option<String>,
+    /// Pages handed out by the pool and not yet returned to it.
+    pub inflight: i32,
+    /// Pages in the lockless allocation cache.
+    pub cached: u32,
+    /// Pages in the recycling ring.
+    pub ring: u32,
+    /// Allocation statistics. Requires `CONFIG_PAGE_POOL_STATS`.
+    pub alloc: Option<MemPagePoolAllocStats>,
+}
+
+/// Page pool allocation statistics, since the pool creation.
+#[event_type]
+#[derive(Default)]
+pub struct MemPagePoolAllocStats {
+    /// Allocations served by the lockless cache.
+    pub fast: u64,
+    /// Allocations served by the page allocator.
+    pub slow: u64,
+    /// High order allocations served by the page allocator.
+    pub slow_high_order: u64,
+    /// Allocations which found both the cache and the ring empty.
+    pub empty: u64,
+    /// Allocations which refilled the cache from the ring.
+    pub refill: u64,
+    /// Pages from the ring not recycled because of a NUMA mismatch.
+    pub waive: u64,
+}
+
+impl EventFmt for MemEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        let mut space = DelimWriter::new(' ');
+
+        if let Some(skb) = &self.skb {
+            space.write(f)?;
+            write!(f, "mem truesize {}", skb.truesize)?;
+
+            if let Some(sock) = &skb.sock {
+                write!(
+                    f,
+                    " sk rmem {}/{} wmem {}/{} drops {}",
+                    sock.rmem_alloc, sock.rcvbuf, sock.wmem_alloc, sock.sndbuf, sock.drops
+                )?;
+            }
+        }
+
+        if let Some(pool) = &self.page_pool {
+            space.write(f)?;
+            write!(f, "page_pool {:#x}", pool.pool)?;
+
+            if let Some(dev) = &pool.dev {
+                write!(f, " dev {dev}")?;
+            }
+            write!(
+                f,
+                " inflight {} cached {} ring {}",
+                pool.inflight, pool.cached, pool.ring
+            )?;
+
+            if let Some(alloc) = &pool.alloc {
+                write!(
+                    f,
+                    " alloc fast {} slow {} slow-high-order {} empty {} refill {} waive {}",
+                    alloc.fast,
+                    alloc.slow,
+                    alloc.slow_high_order,
+                    alloc.empty,
+                    alloc.refill,
+                    alloc.waive
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// MPTCP section.
+#[event_section]
+#[derive(Default)]
+pub struct MptcpEvent {
+    /// Operation: "established" (connection fully established), "join"
+    /// (additional subflow joining the connection), "close" (subflow closed),
+    /// "reinject" (subflow selected to reinject data), "announce" (address
+    /// announced to the peer), "announce-received" (address announced by the
+    /// peer), "remove" (address removal sent to the peer) or "remove-received"
+    /// (address removal sent by the peer).
+    pub op: String,
+    /// MPTCP connection token, if known.
+    pub token: Option<u32>,
+    /// Subflow the operation applies to, if any.
+    pub subflow: Option<MptcpSubflow>,
+    /// Address announced, for announce operations.
+    pub addr: Option<MptcpAddr>,
+    /// Ids of the addresses removed, for remove operations.
+    pub rm_ids: Option<Vec<u8>>,
+    /// Whether the operation failed, for join operations.
+    pub failed: Option<bool>,
+}
+
+/// MPTCP subflow, identified by its TCP 5-tuple.
+#[event_type]
+#[derive(Default)]
+pub struct MptcpSubflow {
+    /// Local address of the subflow.
+    pub saddr: String,
+    /// Remote address of the subflow.
+    pub daddr: String,
+    /// Local port of the subflow.
+    pub sport: u16,
+    /// Remote port of the subflow.
+    pub dport: u16,
+    /// Local address id, if already set.
+    pub local_id: Option<u8>,
+    /// Remote address id.
+    pub remote_id: u8,
+}
+
+/// Address handled by an MPTCP path manager.
+#[event_type]
+#[derive(Default)]
+pub struct MptcpAddr {
+    /// Address id.
+    pub id: u8,
+    /// Address.
+    pub addr: String,
+    /// Port, if any.
+    pub port: Option<u16>,
+}
+
+impl EventFmt for MptcpEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "mptcp {}", self.op)?;
+
+        if let Some(token) = self.token {
+            write!(f, " token {token:#x}")?;
+        }
+
+        if let Some(subflow) = &self.subflow {
+            write!(
+                f,
+                " subflow {}.{} > {}.{} ids ",
+                subflow.saddr, subflow.sport, subflow.daddr, subflow.dport
+            )?;
+            match subflow.local_id {
+                Some(id) => write!(f, "{id}")?,
+                None => write!(f, "?")?,
+            }
+            write!(f, "/{}", subflow.remote_id)?;
+        }
+
+        if let Some(addr) = &self.addr {
+            write!(f, " addr {} {}", addr.id, addr.addr)?;
+            if let Some(port) = addr.port {
+                write!(f, ".{port}")?;
+            }
+        }
+
+        if let Some(ids) = &self.rm_ids {
+            write!(f, " ids {ids:?}")?;
+        }
+
+        if self.failed == Some(true) {
+            write!(f, " failed")?;
+        }
+
+        Ok(())
+    }
+}
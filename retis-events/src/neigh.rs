@@ -0,0 +1,57 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, Formatter};
+
+/// Neighbor section.
+#[event_section]
+#[derive(Default)]
+pub struct NeighEvent {
+    /// Operation: "update" (state transition), "timer" (state after a timer
+    /// run, e.g. when a resolution failed), "queue" (packet held back while
+    /// the neighbor is being resolved) or "failed" (packet dropped as the
+    /// resolution failed).
+    pub op: String,
+    /// Neighbor address.
+    pub addr: String,
+    /// Device the neighbor is reachable from, if known.
+    pub dev: Option<String>,
+    /// Neighbor state (e.g. "incomplete", "reachable" or "failed"). Old state
+    /// on updates.
+    pub state: String,
+    /// New neighbor state, on updates.
+    pub new_state: Option<String>,
+    /// Link-layer address, if known. New one on updates.
+    pub lladdr: Option<String>,
+    /// Number of solicitations sent.
+    pub probes: u32,
+    /// Number of packets queued waiting for the resolution.
+    pub queue_len: u32,
+}
+
+impl EventFmt for NeighEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "neigh {} {}", self.op, self.addr)?;
+
+        if let Some(dev) = &self.dev {
+            write!(f, " dev {dev}")?;
+        }
+
+        write!(f, " {}", self.state)?;
+        if let Some(new_state) = &self.new_state {
+            write!(f, " > {new_state}")?;
+        }
+
+        if let Some(lladdr) = &self.lladdr {
+            write!(f, " lladdr {lladdr}")?;
+        }
+        if self.probes > 0 {
+            write!(f, " probes {}", self.probes)?;
+        }
+        if self.queue_len > 0 {
+            write!(f, " queued {}", self.queue_len)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, Formatter};
+
+/// Netfilter hook section.
+#[event_section]
+#[derive(Default)]
+pub struct NfEvent {
+    /// Protocol family, e.g. "ipv4".
+    pub family: String,
+    /// Netfilter hook, e.g. "prerouting".
+    pub hook: String,
+    /// Verdict of the hook (accept, drop or stolen; the latter also covering
+    /// queued packets). Only reported when leaving the hook.
+    pub verdict: Option<String>,
+    /// Error reported for dropped packets.
+    pub err: Option<i32>,
+}
+
+impl EventFmt for NfEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "nf {} {}", self.family, self.hook)?;
+
+        if let Some(verdict) = &self.verdict {
+            write!(f, " {verdict}")?;
+        }
+
+        if let Some(err) = self.err {
+            write!(f, " (err {err})")?;
+        }
+
+        Ok(())
+    }
+}
@@ -12,6 +12,14 @@ pub struct NetnsEvent {
     pub cookie: Option<u64>,
     /// Netns inum. From the device or the associated socket (in that order).
     pub inum: u32,
+    /// Generation of the inum: number of times it was reused by a new netns
+    /// during the collection. Inums, unlike cookies, can be reused once a
+    /// netns is deleted. Set at post-processing time, using the ns-lifecycle
+    /// events, when the inum was reused.
+    pub generation: Option<u32>,
+    /// Container (or pod) the netns belongs to. Set at post-processing time,
+    /// when container information was retrieved during the collection.
+    pub container: Option<String>,
 }
 
 impl EventFmt for NetnsEvent {
@@ -22,6 +30,13 @@ impl EventFmt for NetnsEvent {
             write!(f, "ns {}", self.inum)?;
         }
 
+        if let Some(generation) = self.generation {
+            write!(f, " gen {generation}")?;
+        }
+        if let Some(container) = &self.container {
+            write!(f, " ({container})")?;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,70 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// Network namespace lifecycle section.
+#[event_section]
+#[derive(Default)]
+pub struct NsLifecycleEvent {
+    /// Operation: "create", "delete" or "dev-move".
+    pub op: String,
+    /// Netns being created or deleted, or the one a device is moved from.
+    pub netns: NsLifecycleNetns,
+    /// Netns a device is moved to.
+    pub target: Option<NsLifecycleNetns>,
+    /// Device being moved.
+    pub dev: Option<NsLifecycleDev>,
+}
+
+/// Identity of a network namespace.
+#[event_type]
+#[derive(Default)]
+pub struct NsLifecycleNetns {
+    /// Netns cookie, if available. See the netns section.
+    pub cookie: Option<u64>,
+    /// Netns inum.
+    pub inum: u32,
+    /// Generation of the inum, see the netns section.
+    pub generation: Option<u32>,
+}
+
+/// Device moved between network namespaces.
+#[event_type]
+#[derive(Default)]
+pub struct NsLifecycleDev {
+    /// Device name.
+    pub name: String,
+    /// Device ifindex, in its original netns.
+    pub ifindex: u32,
+}
+
+impl fmt::Display for NsLifecycleNetns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(cookie) = self.cookie {
+            write!(f, "{cookie:#x}/")?;
+        }
+        write!(f, "{}", self.inum)?;
+        if let Some(generation) = self.generation {
+            write!(f, " gen {generation}")?;
+        }
+        Ok(())
+    }
+}
+
+impl EventFmt for NsLifecycleEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "netns {}", self.op)?;
+
+        if let Some(dev) = &self.dev {
+            write!(f, " {} ({})", dev.name, dev.ifindex)?;
+        }
+
+        write!(f, " {}", self.netns)?;
+        if let Some(target) = &self.target {
+            write!(f, " > {target}")?;
+        }
+
+        Ok(())
+    }
+}
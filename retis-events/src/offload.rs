@@ -0,0 +1,105 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// Hardware offload section.
+#[event_section]
+#[derive(Default)]
+pub struct OffloadEvent {
+    /// Classifier filter offload request, if any.
+    pub filter: Option<OffloadFilter>,
+    /// Hardware offload marks of the packet, if any.
+    pub skb: Option<OffloadSkb>,
+}
+
+/// Classifier filter offload request, e.g. a tc flower filter being added. OvS
+/// offloads its flows using tc flower filters.
+#[event_type]
+#[derive(Default)]
+pub struct OffloadFilter {
+    /// Operation: "add", "replace" or "destroy".
+    pub op: String,
+    /// Type of the classifier, e.g. "clsflower".
+    pub setup: String,
+    /// Device the filter is attached to. Not reported for shared blocks.
+    pub dev: Option<String>,
+    /// Index of the filter block.
+    pub block: u32,
+    /// Filter chain.
+    pub chain: u32,
+    /// Filter priority.
+    pub prio: u32,
+    /// Filter protocol, e.g. 0x800 for IPv4.
+    pub protocol: u16,
+    /// Filter cookie, only reported for flower. OvS uses it to identify flows.
+    pub cookie: Option<u64>,
+    /// Number of drivers which offloaded the filter. 0 means the filter is not
+    /// in hardware.
+    pub in_hw: Option<u32>,
+    /// Error returned by the drivers, if the request failed.
+    pub error: Option<i32>,
+    /// Message reported by the drivers, usually why the filter could not be
+    /// offloaded.
+    pub msg: Option<String>,
+}
+
+/// Hardware offload marks of a packet, set when it was already handled in
+/// hardware (e.g. by a switchdev device).
+#[event_type]
+#[derive(Default)]
+pub struct OffloadSkb {
+    /// The packet was forwarded in hardware.
+    pub fwd_mark: bool,
+    /// The packet was routed in hardware.
+    pub l3_fwd_mark: bool,
+}
+
+impl EventFmt for OffloadEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        let mut space = DelimWriter::new(' ');
+
+        if let Some(filter) = &self.filter {
+            space.write(f)?;
+            write!(f, "offload {} {}", filter.op, filter.setup)?;
+
+            if let Some(dev) = &filter.dev {
+                write!(f, " dev {dev}")?;
+            }
+            write!(
+                f,
+                " block {} chain {} prio {} proto {:#06x}",
+                filter.block, filter.chain, filter.prio, filter.protocol
+            )?;
+            if let Some(cookie) = filter.cookie {
+                write!(f, " cookie {cookie:#x}")?;
+            }
+
+            if let Some(in_hw) = filter.in_hw {
+                match in_hw {
+                    0 => write!(f, " not in hw")?,
+                    x => write!(f, " in hw ({x})")?,
+                }
+            }
+            if let Some(error) = filter.error {
+                write!(f, " error {error}")?;
+            }
+            if let Some(msg) = &filter.msg {
+                write!(f, " ({msg})")?;
+            }
+        }
+
+        if let Some(skb) = &self.skb {
+            space.write(f)?;
+            write!(f, "offload marks")?;
+            if skb.fwd_mark {
+                write!(f, " fwd")?;
+            }
+            if skb.l3_fwd_mark {
+                write!(f, " l3-fwd")?;
+            }
+        }
+
+        Ok(())
+    }
+}
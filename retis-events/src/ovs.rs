@@ -62,6 +62,13 @@ pub enum OvsEvent {
         #[serde(flatten)]
         flow_lookup: LookupEvent,
     },
+
+    /// Flow update. It indicates a flow was installed in or removed from the datapath.
+    #[serde(rename = "flow_update")]
+    FlowUpdate {
+        #[serde(flatten)]
+        flow_update: FlowUpdateEvent,
+    },
 }
 
 impl EventFmt for OvsEvent {
@@ -75,6 +82,7 @@ impl EventFmt for OvsEvent {
             Operation { flow_operation } => flow_operation,
             Action { action_execute } => action_execute,
             DpLookup { flow_lookup } => flow_lookup,
+            FlowUpdate { flow_update } => flow_update,
         };
 
         disp.event_fmt(f, format)
@@ -133,6 +141,11 @@ pub struct LookupEvent {
     pub n_mask_hit: u32,
     /// Number of cache matches that occurred during the lookup.
     pub n_cache_hit: u32,
+    /// Number of masks in the flow table at lookup time. A high number of
+    /// masks tried on lookups is the sign of a megaflow explosion.
+    pub n_masks: Option<u32>,
+    /// Number of flows in the flow table at lookup time.
+    pub n_flows: Option<u32>,
     /// Datapath flow string.
     pub dpflow: String,
     /// Openflow flows.
@@ -144,17 +157,17 @@ impl EventFmt for LookupEvent {
         let sep = if d.multiline { "\n" } else { " " };
 
         if self.flow == 0 {
-            return write!(
-                f,
-                "flow miss mask {} cache {}",
-                self.n_mask_hit, self.n_cache_hit,
-            );
+            write!(f, "flow miss mask {}", self.n_mask_hit)?;
+            self.fmt_table(f)?;
+            return write!(f, " cache {}", self.n_cache_hit);
         }
 
+        write!(f, "flow hit ufid {} mask {}", self.ufid, self.n_mask_hit)?;
+        self.fmt_table(f)?;
         write!(
             f,
-            "flow hit ufid {} mask {} cache {} flow {:x} sf_acts {:x}",
-            self.ufid, self.n_mask_hit, self.n_cache_hit, self.flow, self.sf_acts,
+            " cache {} flow {:x} sf_acts {:x}",
+            self.n_cache_hit, self.flow, self.sf_acts,
         )?;
         if !self.dpflow.is_empty() {
             write!(f, "{sep}odpflow {}", self.dpflow)?;
@@ -174,6 +187,76 @@ impl EventFmt for LookupEvent {
 }
 
 impl LookupEvent {
+    /// Format the flow table statistics, if available.
+    fn fmt_table(&self, f: &mut Formatter) -> fmt::Result {
+        if let Some(n_masks) = self.n_masks {
+            write!(f, "/{n_masks}")?;
+        }
+        if let Some(n_flows) = self.n_flows {
+            write!(f, " flows {n_flows}")?;
+        }
+        Ok(())
+    }
+
+    /// Return a FlowId that represents a unique flow fingerprint.
+    pub fn flow_id(&self) -> FlowId {
+        FlowId {
+            ufid: self.ufid,
+            flow: self.flow,
+            sf_acts: self.sf_acts,
+        }
+    }
+}
+
+/// Datapath flow update.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct FlowUpdateEvent {
+    /// Update type: "install" or "remove".
+    pub r#type: String,
+    /// Flow pointer.
+    pub flow: u64,
+    /// Actions pointer.
+    pub sf_acts: u64,
+    /// Flow UFID.
+    pub ufid: Ufid,
+    /// Tracking ID of the upcall the flow was installed for. Only reported
+    /// for installs when upcall tracking is enabled.
+    pub queue_id: Option<u32>,
+    /// Datapath flow string, including its actions.
+    pub dpflow: String,
+    /// Openflow flows.
+    pub ofpflows: Vec<String>,
+}
+
+impl EventFmt for FlowUpdateEvent {
+    fn event_fmt(&self, f: &mut Formatter, d: &DisplayFormat) -> fmt::Result {
+        let sep = if d.multiline { "\n" } else { " " };
+
+        write!(f, "flow_{} ufid {}", self.r#type, self.ufid)?;
+        if let Some(queue_id) = self.queue_id {
+            write!(f, " q {queue_id}")?;
+        }
+        write!(f, " flow {:x} sf_acts {:x}", self.flow, self.sf_acts)?;
+
+        if !self.dpflow.is_empty() {
+            write!(f, "{sep}odpflow {}", self.dpflow)?;
+        }
+        if !self.ofpflows.is_empty() {
+            write!(f, "{sep}openflow{sep}")?;
+            if d.multiline {
+                f.conf.inc_level(4)
+            }
+            write!(f, "{}", self.ofpflows.join(sep))?;
+            if d.multiline {
+                f.conf.reset_level();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FlowUpdateEvent {
     /// Return a FlowId that represents a unique flow fingerprint.
     pub fn flow_id(&self) -> FlowId {
         FlowId {
@@ -249,6 +332,7 @@ pub struct OperationEvent {
         deserialize_with = "OperationEvent::deserialize_op",
         serialize_with = "OperationEvent::serialize_op"
     )]
+    #[schemars(with = "String")]
     pub op_type: u8,
     /// Tracking ID.
     pub queue_id: u32,
@@ -256,6 +340,10 @@ pub struct OperationEvent {
     pub batch_ts: u64,
     /// Batch index. Position within the upcall batch.
     pub batch_idx: u8,
+    /// Time between the upcall being enqueued by the kernel and this
+    /// operation, in nanoseconds. Only reported when upcall tracking is
+    /// enabled and the enqueue event was seen.
+    pub latency: Option<u64>,
 }
 
 impl OperationEvent {
@@ -298,7 +386,13 @@ impl EventFmt for OperationEvent {
             self.queue_id,
             self.batch_ts,
             self.batch_idx
-        )
+        )?;
+
+        if let Some(latency) = self.latency {
+            write!(f, " latency {latency}ns")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -357,8 +451,29 @@ impl EventFmt for ActionEvent {
         match &self.action {
             Some(OvsAction::Output { output }) => write!(f, " oport {}", output.port)?,
             Some(OvsAction::Userspace(_)) => write!(f, " userspace")?,
-            Some(OvsAction::Set(_)) => write!(f, " tunnel_set")?,
-            Some(OvsAction::PushVlan(_)) => write!(f, " push_vlan")?,
+            Some(OvsAction::Set { set }) => {
+                write!(f, " tunnel_set")?;
+                if let Some(tun) = &set.tunnel {
+                    let port =
+                        |port: Option<u16>| port.map(|p| format!(".{p}")).unwrap_or_default();
+                    write!(
+                        f,
+                        " id {} {}{} > {}{} tos {:#x} ttl {}",
+                        tun.id,
+                        tun.src,
+                        port(tun.sport),
+                        tun.dst,
+                        port(tun.dport),
+                        tun.tos,
+                        tun.ttl
+                    )?;
+                }
+            }
+            Some(OvsAction::PushVlan { push_vlan }) => write!(
+                f,
+                " push_vlan {:#06x} vlan {} p {}",
+                push_vlan.tpid, push_vlan.vid, push_vlan.pcp
+            )?,
             Some(OvsAction::PopVlan(_)) => write!(f, " pop_vlan")?,
             Some(OvsAction::Sample(_)) => write!(f, " sample")?,
             Some(OvsAction::Recirc { recirc }) => write!(f, " recirc {:#x}", recirc.id)?,
@@ -470,9 +585,15 @@ pub enum OvsAction {
     #[serde(rename = "userspace")]
     Userspace(OvsDummyAction),
     #[serde(rename = "set")]
-    Set(OvsDummyAction),
+    Set {
+        #[serde(flatten)]
+        set: OvsActionSet,
+    },
     #[serde(rename = "push_vlan")]
-    PushVlan(OvsDummyAction),
+    PushVlan {
+        #[serde(flatten)]
+        push_vlan: OvsActionPushVlan,
+    },
     #[serde(rename = "pop_vlan")]
     PopVlan(OvsDummyAction),
     #[serde(rename = "sample")]
@@ -529,6 +650,47 @@ pub struct OvsActionOutput {
     pub port: u32,
 }
 
+/// Set action. The datapath only uses it to set tunnel metadata, other
+/// fields are set using masked set actions.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionSet {
+    /// Tunnel metadata, if it could be retrieved.
+    pub tunnel: Option<OvsActionSetTunnel>,
+}
+
+/// Tunnel metadata set on a packet, used by tunnel ports for encapsulation.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct OvsActionSetTunnel {
+    /// Tunnel id: VNI for VXLAN and GENEVE, key for GRE.
+    pub id: u64,
+    /// Source address of the outer header.
+    pub src: String,
+    /// Destination address of the outer header.
+    pub dst: String,
+    /// Source port of the outer header, if any.
+    pub sport: Option<u16>,
+    /// Destination port of the outer header, if any.
+    pub dport: Option<u16>,
+    /// Type of service of the outer header.
+    pub tos: u8,
+    /// Time to live of the outer header.
+    pub ttl: u8,
+}
+
+/// Push VLAN action.
+#[event_type]
+#[derive(Copy, Default, PartialEq)]
+pub struct OvsActionPushVlan {
+    /// Tag protocol identifier, e.g. 0x8100.
+    pub tpid: u16,
+    /// VLAN id.
+    pub vid: u16,
+    /// Priority code point.
+    pub pcp: u8,
+}
+
 /// Recirc action.
 #[event_type]
 #[derive(Copy, Default, PartialEq)]
@@ -739,7 +901,7 @@ mod tests {
 
     #[test]
     fn test_event_to_from_json() -> Result<()> {
-        let events: [(&'static str, OvsEvent); 8] = [
+        let events: [(&'static str, OvsEvent); 11] = [
             // Upcall event
             (
                 r#"{"cmd":1,"cpu":0,"event_type":"upcall","port":4195744766}"#,
@@ -798,18 +960,86 @@ mod tests {
                         queue_id: 3316322986,
                         batch_ts: 61096237019698,
                         batch_idx: 0,
+                        latency: None,
                     },
                 },
             ),
             // Operation event put
             (
-                r#"{"batch_idx":0,"batch_ts":61096237019698,"event_type":"flow_operation","op_type":"put","queue_id":3316322986}"#,
+                r#"{"batch_idx":0,"batch_ts":61096237019698,"event_type":"flow_operation","latency":46037,"op_type":"put","queue_id":3316322986}"#,
                 OvsEvent::Operation {
                     flow_operation: OperationEvent {
                         op_type: 1,
                         queue_id: 3316322986,
                         batch_ts: 61096237019698,
                         batch_idx: 0,
+                        latency: Some(46037),
+                    },
+                },
+            ),
+            // Flow lookup miss event
+            (
+                r#"{"dpflow":"","event_type":"flow_lookup","flow":0,"n_cache_hit":0,"n_flows":1024,"n_mask_hit":12,"n_masks":12,"ofpflows":[],"sf_acts":0,"ufid":[0,0,0,0]}"#,
+                OvsEvent::DpLookup {
+                    flow_lookup: LookupEvent {
+                        n_mask_hit: 12,
+                        n_masks: Some(12),
+                        n_flows: Some(1024),
+                        ..Default::default()
+                    },
+                },
+            ),
+            // Flow update event
+            (
+                r#"{"dpflow":"","event_type":"flow_update","flow":18446620523563221504,"ofpflows":[],"queue_id":3316322986,"sf_acts":18446620523623419904,"type":"install","ufid":[1,2,3,4]}"#,
+                OvsEvent::FlowUpdate {
+                    flow_update: FlowUpdateEvent {
+                        r#type: String::from("install"),
+                        flow: 18446620523563221504,
+                        sf_acts: 18446620523623419904,
+                        ufid: Ufid(1, 2, 3, 4),
+                        queue_id: Some(3316322986),
+                        ..Default::default()
+                    },
+                },
+            ),
+            // Push VLAN action event
+            (
+                r#"{"action":"push_vlan","event_type":"action_execute","pcp":3,"recirc_id":0,"tpid":33024,"vid":100}"#,
+                OvsEvent::Action {
+                    action_execute: ActionEvent {
+                        action: Some(OvsAction::PushVlan {
+                            push_vlan: OvsActionPushVlan {
+                                tpid: 0x8100,
+                                vid: 100,
+                                pcp: 3,
+                            },
+                        }),
+                        recirc_id: 0,
+                        queue_id: None,
+                    },
+                },
+            ),
+            // Set tunnel action event
+            (
+                r#"{"action":"set","event_type":"action_execute","recirc_id":0,"tunnel":{"dport":4789,"dst":"172.16.0.2","id":42,"src":"172.16.0.1","tos":0,"ttl":64}}"#,
+                OvsEvent::Action {
+                    action_execute: ActionEvent {
+                        action: Some(OvsAction::Set {
+                            set: OvsActionSet {
+                                tunnel: Some(OvsActionSetTunnel {
+                                    id: 42,
+                                    src: String::from("172.16.0.1"),
+                                    dst: String::from("172.16.0.2"),
+                                    sport: None,
+                                    dport: Some(4789),
+                                    tos: 0,
+                                    ttl: 64,
+                                }),
+                            },
+                        }),
+                        recirc_id: 0,
+                        queue_id: None,
                     },
                 },
             ),
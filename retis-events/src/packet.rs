@@ -1,4 +1,4 @@
-use std::{fmt, str};
+use std::{fmt, net::IpAddr, str};
 
 use base64::{
     display::Base64Display, engine::general_purpose::STANDARD, prelude::BASE64_STANDARD, Engine,
@@ -25,6 +25,10 @@ pub struct PacketEvent {
     pub capture_len: u32,
     /// Raw packet data.
     pub data: RawPacket,
+    /// VLAN tag stored in the packet metadata instead of the raw data (VLAN
+    /// hardware acceleration). On ingress the tag is often already stripped
+    /// from the packet by the time it is seen.
+    pub vlan_accel: Option<SkbVlanAccelEvent>,
 }
 
 #[allow(dead_code)]
@@ -39,7 +43,7 @@ impl PacketEvent {
 
 impl EventFmt for PacketEvent {
     fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
-        self.data.event_fmt(f, format)
+        self.data.format(f, format, self.vlan_accel.as_ref())
     }
 }
 
@@ -48,7 +52,7 @@ impl EventFmt for PacketEvent {
 /// serde::Deserialize manually.
 #[derive(Clone, Debug, schemars::JsonSchema)]
 #[cfg_attr(feature = "python", pyclass)]
-pub struct RawPacket(pub Vec<u8>);
+pub struct RawPacket(#[schemars(with = "String")] pub Vec<u8>);
 
 impl serde::Serialize for RawPacket {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -130,10 +134,23 @@ type FmtResult<T> = std::result::Result<T, PacketFmtError>;
 
 impl EventFmt for RawPacket {
     fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        self.format(f, format, None)
+    }
+}
+
+impl RawPacket {
+    /// Format the packet, with an optional VLAN tag coming from the metadata
+    /// (see `PacketEvent::vlan_accel`).
+    fn format(
+        &self,
+        f: &mut Formatter,
+        format: &DisplayFormat,
+        vlan_accel: Option<&SkbVlanAccelEvent>,
+    ) -> fmt::Result {
         // Do not propagate errors on parsing: keep things best effort (except
         // for real formatting issues).
         use PacketFmtError::*;
-        match self.format_packet(f, format) {
+        match self.format_packet(f, format, vlan_accel) {
             Err(Truncated) => write!(f, "... (truncated or incomplete packet)"),
             Err(NotSupported(p)) => write!(f, "... ({p} not supported, use 'retis pcap')"),
             Err(Fmt(e)) => Err(e),
@@ -142,10 +159,308 @@ impl EventFmt for RawPacket {
     }
 }
 
+/// Flow (5-tuple) of a packet.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PacketFlow {
+    /// Source address.
+    pub src: IpAddr,
+    /// Destination address.
+    pub dst: IpAddr,
+    /// L4 protocol.
+    pub protocol: u8,
+    /// Source and destination ports, for TCP, UDP and SCTP.
+    pub ports: Option<(u16, u16)>,
+}
+
+impl PacketFlow {
+    /// Get the name of the L4 protocol, or its number if unknown.
+    pub fn protocol_name(&self) -> String {
+        match helpers::net::protocol_str(IpNextHeaderProtocol(self.protocol)) {
+            Some(proto) => proto.to_string(),
+            None => self.protocol.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for PacketFlow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ports {
+            Some((sport, dport)) => write!(f, "{}.{sport} > {}.{dport}", self.src, self.dst)?,
+            None => write!(f, "{} > {}", self.src, self.dst)?,
+        }
+
+        match helpers::net::protocol_str(IpNextHeaderProtocol(self.protocol)) {
+            Some(proto) => write!(f, " {proto}"),
+            None => write!(f, " proto {}", self.protocol),
+        }
+    }
+}
+
+/// TCP options of a packet, for the ones commonly used to troubleshoot
+/// connections.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TcpOptions {
+    /// Maximum segment size.
+    pub mss: Option<u16>,
+    /// Window scale (shift count).
+    pub wscale: Option<u8>,
+    /// SACK is permitted.
+    pub sack_permitted: bool,
+    /// SACK blocks, as (left edge, right edge).
+    pub sack: Vec<(u32, u32)>,
+    /// Timestamps, as (value, echo reply).
+    pub timestamps: Option<(u32, u32)>,
+}
+
+impl TcpOptions {
+    fn from_tcp(tcp: &TcpPacket) -> Self {
+        let mut opts = Self::default();
+        let be32 = |buf: &[u8]| u32::from_be_bytes(buf.try_into().unwrap());
+
+        for opt in tcp.get_options_iter() {
+            let payload = opt.payload();
+            match opt.get_number() {
+                TcpOptionNumbers::MSS => {
+                    opts.mss = payload
+                        .get(..2)
+                        .map(|mss| u16::from_be_bytes(mss.try_into().unwrap()));
+                }
+                TcpOptionNumbers::WSCALE => opts.wscale = payload.first().copied(),
+                TcpOptionNumbers::SACK_PERMITTED => opts.sack_permitted = true,
+                TcpOptionNumbers::SACK => {
+                    opts.sack = payload
+                        .chunks_exact(8)
+                        .map(|block| (be32(&block[..4]), be32(&block[4..])))
+                        .collect();
+                }
+                TcpOptionNumbers::TIMESTAMPS => {
+                    opts.timestamps = payload.get(..8).map(|ts| (be32(&ts[..4]), be32(&ts[4..])));
+                }
+                _ => (),
+            }
+        }
+
+        opts
+    }
+}
+
+/// Checksums validation of a packet. `None` means the checksum could not be
+/// validated (not part of the packet, not fully captured, etc).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PacketChecksums {
+    /// IPv4 header checksum is valid.
+    pub l3: Option<bool>,
+    /// L4 (TCP, UDP, ICMP or ICMPv6) checksum is valid.
+    pub l4: Option<bool>,
+}
+
+impl PacketChecksums {
+    /// Validate an L4 checksum. `pseudo` computes the checksum of the L4 data
+    /// including the IP pseudo-header, skipping a given 16-bit word.
+    fn l4_valid<F>(protocol: IpNextHeaderProtocol, data: &[u8], pseudo: F) -> Option<bool>
+    where
+        F: Fn(&[u8], usize, IpNextHeaderProtocol) -> u16,
+    {
+        let skipword = match protocol {
+            IpNextHeaderProtocols::Tcp => 8,
+            IpNextHeaderProtocols::Udp => 3,
+            IpNextHeaderProtocols::Icmp | IpNextHeaderProtocols::Icmpv6 => 1,
+            _ => return None,
+        };
+        let stored = u16::from_be_bytes(data.get(skipword * 2..skipword * 2 + 2)?.try_into().ok()?);
+
+        let csum = match protocol {
+            // The UDP checksum is optional over IPv4. Over IPv6 a zero
+            // checksum is invalid (but for some tunnels, which we can't know).
+            IpNextHeaderProtocols::Udp if stored == 0 => return None,
+            // A computed UDP checksum of 0 is transmitted as all ones.
+            IpNextHeaderProtocols::Udp => match pseudo(data, skipword, protocol) {
+                0 => 0xffff,
+                csum => csum,
+            },
+            // ICMP does not use a pseudo-header.
+            IpNextHeaderProtocols::Icmp => util::checksum(data, skipword),
+            _ => pseudo(data, skipword, protocol),
+        };
+
+        Some(csum == stored)
+    }
+}
+
 impl RawPacket {
-    fn format_packet(&self, f: &mut Formatter, format: &DisplayFormat) -> FmtResult<()> {
+    /// Get the ethertype and payload of the first non-VLAN header of an
+    /// (Ethernet, optionally VLAN tagged) packet.
+    fn l3(&self) -> Option<(EtherType, &[u8])> {
+        let eth = EthernetPacket::new(&self.0)?;
+        let mut etype = eth.get_ethertype();
+        let mut payload = eth.payload();
+
+        while matches!(
+            etype,
+            EtherTypes::Vlan | EtherTypes::PBridge | EtherTypes::QinQ
+        ) {
+            let vlan = VlanPacket::new(payload)?;
+            etype = vlan.get_ethertype();
+            payload = &payload[vlan.packet_size()..];
+        }
+
+        Some((etype, payload))
+    }
+
+    /// Get the addresses, L4 protocol and L4 header (if part of the packet) of
+    /// an (Ethernet, optionally VLAN tagged) IP packet. The outer headers are
+    /// used for encapsulated packets.
+    fn l4(&self) -> Option<(IpAddr, IpAddr, IpNextHeaderProtocol, Option<&[u8]>)> {
+        let (etype, payload) = self.l3()?;
+
+        match etype {
+            EtherTypes::Ipv4 => {
+                let ip = Ipv4Packet::new(payload)?;
+                // Only the first fragment holds the L4 header.
+                let header = match ip.get_fragment_offset() {
+                    0 => payload.get(ip.get_header_length() as usize * 4..),
+                    _ => None,
+                };
+                Some((
+                    IpAddr::V4(ip.get_source()),
+                    IpAddr::V4(ip.get_destination()),
+                    ip.get_next_level_protocol(),
+                    header,
+                ))
+            }
+            EtherTypes::Ipv6 => {
+                let ip = Ipv6Packet::new(payload)?;
+                let l4 = upper_layer(&ip);
+                let header = match l4.has_header() {
+                    true => payload.get(Ipv6Packet::minimum_packet_size() + l4.offset..),
+                    false => None,
+                };
+                Some((
+                    IpAddr::V6(ip.get_source()),
+                    IpAddr::V6(ip.get_destination()),
+                    l4.protocol,
+                    header,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the flow of an (Ethernet, optionally VLAN tagged) IP packet. The
+    /// outer headers are used for encapsulated packets.
+    pub fn flow(&self) -> Option<PacketFlow> {
+        let (src, dst, protocol, header) = self.l4()?;
+
+        // Get the ports from the L4 header, if supported.
+        let ports = header.and_then(|payload| match protocol {
+            IpNextHeaderProtocols::Tcp => {
+                TcpPacket::new(payload).map(|tcp| (tcp.get_source(), tcp.get_destination()))
+            }
+            IpNextHeaderProtocols::Udp => {
+                UdpPacket::new(payload).map(|udp| (udp.get_source(), udp.get_destination()))
+            }
+            IpNextHeaderProtocols::Sctp => {
+                SctpPacket::new(payload).map(|sctp| (sctp.get_source(), sctp.get_destination()))
+            }
+            _ => None,
+        });
+
+        Some(PacketFlow {
+            src,
+            dst,
+            protocol: protocol.0,
+            ports,
+        })
+    }
+
+    /// Validate the checksums of an (Ethernet, optionally VLAN tagged) IP
+    /// packet. The outer headers are used for encapsulated packets. The L4
+    /// checksum can only be validated for non-fragmented packets whose data is
+    /// fully part of the raw packet.
+    pub fn checksums(&self) -> PacketChecksums {
+        let mut csums = PacketChecksums::default();
+        let Some((etype, payload)) = self.l3() else {
+            return csums;
+        };
+
+        match etype {
+            EtherTypes::Ipv4 => {
+                let Some(ip) = Ipv4Packet::new(payload) else {
+                    return csums;
+                };
+
+                let hlen = ip.get_header_length() as usize * 4;
+                if hlen < Ipv4Packet::minimum_packet_size() {
+                    return csums;
+                }
+                if let Some(header) = payload.get(..hlen) {
+                    csums.l3 = Some(util::checksum(header, 5) == ip.get_checksum());
+                }
+
+                if ip.get_fragment_offset() != 0 || ip.get_flags() & Ipv4Flags::MoreFragments != 0 {
+                    return csums;
+                }
+
+                let (src, dst) = (ip.get_source(), ip.get_destination());
+                csums.l4 = (ip.get_total_length() as usize)
+                    .checked_sub(hlen)
+                    .and_then(|len| payload.get(hlen..(hlen + len)))
+                    .and_then(|data| {
+                        PacketChecksums::l4_valid(
+                            ip.get_next_level_protocol(),
+                            data,
+                            |data, skipword, proto| {
+                                util::ipv4_checksum(data, skipword, &[], &src, &dst, proto)
+                            },
+                        )
+                    });
+            }
+            EtherTypes::Ipv6 => {
+                let Some(ip) = Ipv6Packet::new(payload) else {
+                    return csums;
+                };
+
+                let l4 = upper_layer(&ip);
+                if l4.fragment.is_some() {
+                    return csums;
+                }
+
+                let (src, dst) = (ip.get_source(), ip.get_destination());
+                let start = Ipv6Packet::minimum_packet_size() + l4.offset;
+                let end = Ipv6Packet::minimum_packet_size() + ip.get_payload_length() as usize;
+                csums.l4 = payload.get(start..end).and_then(|data| {
+                    PacketChecksums::l4_valid(l4.protocol, data, |data, skipword, proto| {
+                        util::ipv6_checksum(data, skipword, &[], &src, &dst, proto)
+                    })
+                });
+            }
+            _ => (),
+        }
+
+        csums
+    }
+
+    /// Get the TCP options of an (Ethernet, optionally VLAN tagged) IP packet,
+    /// if it is a TCP one. The outer headers are used for encapsulated packets.
+    pub fn tcp_options(&self) -> Option<TcpOptions> {
+        match self.l4()? {
+            (_, _, IpNextHeaderProtocols::Tcp, Some(payload)) => {
+                TcpPacket::new(payload).map(|tcp| TcpOptions::from_tcp(&tcp))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl RawPacket {
+    fn format_packet(
+        &self,
+        f: &mut Formatter,
+        format: &DisplayFormat,
+        vlan_accel: Option<&SkbVlanAccelEvent>,
+    ) -> FmtResult<()> {
         match EthernetPacket::new(&self.0) {
-            Some(eth) => self.format_ethernet(f, format, &eth),
+            Some(eth) => self.format_ethernet(f, format, &eth, vlan_accel),
             None => Err(PacketFmtError::Truncated),
         }
     }
@@ -155,6 +470,7 @@ impl RawPacket {
         f: &mut Formatter,
         format: &DisplayFormat,
         eth: &EthernetPacket,
+        vlan_accel: Option<&SkbVlanAccelEvent>,
     ) -> FmtResult<()> {
         let etype = match helpers::net::etype_str(eth.get_ethertype()) {
             Some(etype) => etype,
@@ -177,6 +493,10 @@ impl RawPacket {
                 eth.get_destination(),
                 eth.get_ethertype().0
             )?;
+
+            if let Some(vlan) = vlan_accel {
+                self.format_vlan_accel(f, vlan)?;
+            }
         }
 
         self.traverse_vlan(f, format, eth.get_ethertype(), eth.payload())
@@ -232,6 +552,24 @@ impl RawPacket {
         Ok(())
     }
 
+    fn format_vlan_accel(&self, f: &mut Formatter, vlan: &SkbVlanAccelEvent) -> FmtResult<()> {
+        write!(
+            f,
+            " vlan_accel {} p {}{}",
+            vlan.vid,
+            vlan.pcp,
+            if vlan.dei { " DEI" } else { "" },
+        )?;
+
+        let tpid = EtherType::new(vlan.proto);
+        match helpers::net::etype_str(tpid) {
+            Some(etype) => write!(f, " tpid {etype} ({:#06x})", vlan.proto)?,
+            None => write!(f, " tpid ({:#06x})", vlan.proto)?,
+        }
+
+        Ok(())
+    }
+
     fn format_etype(
         &self,
         f: &mut Formatter,
@@ -325,12 +663,25 @@ impl RawPacket {
         if let Some((sport, dport)) = ports {
             write!(
                 f,
-                "{}.{sport} > {}.{dport}",
-                ip.get_source(),
-                ip.get_destination()
+                "{}",
+                format.highlight(
+                    Highlight::Address,
+                    format_args!(
+                        "{}.{sport} > {}.{dport}",
+                        ip.get_source(),
+                        ip.get_destination()
+                    )
+                )
             )?;
         } else {
-            write!(f, "{} > {}", ip.get_source(), ip.get_destination())?;
+            write!(
+                f,
+                "{}",
+                format.highlight(
+                    Highlight::Address,
+                    format_args!("{} > {}", ip.get_source(), ip.get_destination())
+                )
+            )?;
         }
 
         write!(
@@ -409,25 +760,44 @@ impl RawPacket {
         format: &DisplayFormat,
         ip: &Ipv6Packet,
     ) -> FmtResult<()> {
-        let ports =
-            match ip.get_next_header() {
-                IpNextHeaderProtocols::Tcp => TcpPacket::new(ip.payload())
-                    .map(|tcp| (tcp.get_source(), tcp.get_destination())),
-                IpNextHeaderProtocols::Udp => UdpPacket::new(ip.payload())
-                    .map(|udp| (udp.get_source(), udp.get_destination())),
-                IpNextHeaderProtocols::Sctp => SctpPacket::new(ip.payload())
-                    .map(|sctp| (sctp.get_source(), sctp.get_destination())),
-                _ => None,
-            };
+        // Walk the extension headers to find the upper-layer protocol.
+        let l4 = upper_layer(ip);
+
+        let ports = match l4.protocol {
+            _ if !l4.has_header() => None,
+            IpNextHeaderProtocols::Tcp => {
+                TcpPacket::new(l4.payload).map(|tcp| (tcp.get_source(), tcp.get_destination()))
+            }
+            IpNextHeaderProtocols::Udp => {
+                UdpPacket::new(l4.payload).map(|udp| (udp.get_source(), udp.get_destination()))
+            }
+            IpNextHeaderProtocols::Sctp => {
+                SctpPacket::new(l4.payload).map(|sctp| (sctp.get_source(), sctp.get_destination()))
+            }
+            _ => None,
+        };
         if let Some((sport, dport)) = ports {
             write!(
                 f,
-                "{}.{sport} > {}.{dport}",
-                ip.get_source(),
-                ip.get_destination()
+                "{}",
+                format.highlight(
+                    Highlight::Address,
+                    format_args!(
+                        "{}.{sport} > {}.{dport}",
+                        ip.get_source(),
+                        ip.get_destination()
+                    )
+                )
             )?;
         } else {
-            write!(f, "{} > {}", ip.get_source(), ip.get_destination())?;
+            write!(
+                f,
+                "{}",
+                format.highlight(
+                    Highlight::Address,
+                    format_args!("{} > {}", ip.get_source(), ip.get_destination())
+                )
+            )?;
         }
 
         write!(
@@ -450,39 +820,53 @@ impl RawPacket {
             write!(f, " len {len}")?;
         }
 
-        let mut protocol = ip.get_next_header();
-        let mut prev_protocol = protocol;
-        let mut len = ip.get_payload_length() as u32;
-        let mut payload = ip.payload();
-        let mut exts = Vec::new();
-
-        // Skip IPv6 extensions.
-        let extensions = ExtensionIterable::from(ip);
-        extensions.for_each(|ext| {
-            exts.push(format!("{protocol}"));
-            prev_protocol = protocol;
-            protocol = ext.get_next_header();
-            // Using `packet_size` works because the payload isn't part of the
-            // extension "packets".
-            len = len.saturating_sub(ext.packet_size() as u32);
-            payload = &payload[ext.packet_size()..];
-        });
+        if !l4.extensions.is_empty() {
+            write!(
+                f,
+                " exts [{}]",
+                l4.extensions
+                    .iter()
+                    .map(|ext| ext.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )?;
+        }
 
-        if !exts.is_empty() {
-            write!(f, " exts [{}]", exts.join(","),)?;
+        if let Some(frag) = &l4.fragment {
+            write!(
+                f,
+                " frag id {} off {}",
+                frag.get_id(),
+                frag.get_fragment_offset(),
+            )?;
+            if !frag.is_last_fragment() {
+                write!(f, " [+]")?;
+            }
         }
 
         // Payload, if any, is garbage.
-        if prev_protocol == IpNextHeaderProtocols::Ipv6NoNxt {
+        if l4.protocol == IpNextHeaderProtocols::Ipv6NoNxt {
             return Ok(());
         }
 
+        let protocol = l4.protocol;
         match helpers::net::protocol_str(protocol) {
             Some(proto) => write!(f, " proto {proto} ({})", protocol.0)?,
             None => write!(f, " proto ({})", protocol.0)?,
         }
 
-        self.format_protocol(f, format, protocol, payload, len)
+        // Only the first fragment holds the upper-layer header.
+        if !l4.has_header() {
+            return Ok(());
+        }
+
+        self.format_protocol(
+            f,
+            format,
+            protocol,
+            l4.payload,
+            (ip.get_payload_length() as u32).saturating_sub(l4.offset as u32),
+        )
     }
 
     fn format_macsec(
@@ -982,7 +1366,7 @@ impl RawPacket {
 
         write!(f, " ")?;
         match EthernetPacket::new(vxlan.payload()) {
-            Some(eth) => self.format_ethernet(f, format, &eth),
+            Some(eth) => self.format_ethernet(f, format, &eth, None),
             None => Err(PacketFmtError::Truncated),
         }
     }
@@ -1062,7 +1446,7 @@ impl RawPacket {
         write!(f, " ")?;
         match protocol {
             EtherTypes::Teb => match EthernetPacket::new(geneve.payload()) {
-                Some(eth) => self.format_ethernet(f, format, &eth),
+                Some(eth) => self.format_ethernet(f, format, &eth, None),
                 None => Err(PacketFmtError::Truncated),
             },
             _ => self.format_etype(f, format, protocol, geneve.payload()),
@@ -1090,6 +1474,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn packet_flow() {
+        let mut buf = Vec::new();
+        BASE64_STANDARD.decode_vec(
+            "rrBKar+vnh09MZ47ht1gBvSKACgGQBERAAAAAAAAAAAAAAAAAAEREQAAAAAAAAAAAAAAAAAC22QAULIRwcAAAAAAoAL9ICJTAAACBAWgBAIIClP9HoIAAAAAAQMDBw==",
+            &mut buf,
+        ).unwrap();
+        let flow = RawPacket(buf).flow().unwrap();
+        assert_eq!(flow.ports, Some((56164, 80)));
+        assert_eq!(flow.to_string(), "1111::1.56164 > 1111::2.80 TCP");
+        assert_eq!(flow.protocol_name(), "TCP");
+
+        assert!(RawPacket(vec![0; 8]).flow().is_none());
+    }
+
+    #[test]
+    fn packet_tcp_options() {
+        let mut buf = Vec::new();
+        BASE64_STANDARD.decode_vec(
+            "rrBKar+vnh09MZ47ht1gBvSKACgGQBERAAAAAAAAAAAAAAAAAAEREQAAAAAAAAAAAAAAAAAC22QAULIRwcAAAAAAoAL9ICJTAAACBAWgBAIIClP9HoIAAAAAAQMDBw==",
+            &mut buf,
+        ).unwrap();
+        assert_eq!(
+            RawPacket(buf).tcp_options().unwrap(),
+            TcpOptions {
+                mss: Some(1440),
+                wscale: Some(7),
+                sack_permitted: true,
+                sack: Vec::new(),
+                timestamps: Some((1409097346, 0)),
+            }
+        );
+
+        let mut buf = Vec::new();
+        BASE64_STANDARD.decode_vec(
+            "rrBKar+vnh09MZ47CABFAAA0AAFAAEAGAAAKAAABCgAAAh+QnEAAAAABAAAAAYAQAfYAAAAAAQEFCgAAA+gAAAfQ",
+            &mut buf,
+        ).unwrap();
+        let opts = RawPacket(buf).tcp_options().unwrap();
+        assert_eq!(opts.sack, vec![(1000, 2000)]);
+        assert!(opts.mss.is_none());
+
+        // Not a TCP packet.
+        let mut buf = Vec::new();
+        BASE64_STANDARD.decode_vec(
+            "rrBKar+vnh09MZ47ht1gAAAAACAAQBERAAAAAAAAAAAAAAAAAAEREQAAAAAAAAAAAAAAAAACLAABBAAAAAARAAABAAASNATSFi4AEAAAQUFBQUFBQUE=",
+            &mut buf,
+        ).unwrap();
+        assert!(RawPacket(buf).tcp_options().is_none());
+    }
+
     #[test]
     fn print_tcp6_in_vlan() {
         let mut buf = Vec::new();
@@ -1105,6 +1540,120 @@ mod tests {
         );
     }
 
+    #[test]
+    fn print_vlan_accel() {
+        let mut buf = Vec::new();
+        BASE64_STANDARD.decode_vec(
+            "rrBKar+vnh09MZ47ht1gBvSKACgGQBERAAAAAAAAAAAAAAAAAAEREQAAAAAAAAAAAAAAAAAC22QAULIRwcAAAAAAoAL9ICJTAAACBAWgBAIIClP9HoIAAAAAAQMDBw==",
+            &mut buf,
+        ).unwrap();
+        let packet = PacketEvent {
+            len: buf.len() as u32,
+            capture_len: buf.len() as u32,
+            data: RawPacket(buf),
+            vlan_accel: Some(SkbVlanAccelEvent {
+                proto: 0x8100,
+                pcp: 3,
+                dei: false,
+                vid: 100,
+            }),
+        };
+
+        assert_eq!(
+            &format!("{}", packet.display(&DisplayFormat::new().print_ll(true), &FormatterConf::new())),
+            "9e:1d:3d:31:9e:3b > ae:b0:4a:6a:bf:af ethertype IPv6 (0x86dd) vlan_accel 100 p 3 tpid 802.1Q (0x8100) 1111::1.56164 > 1111::2.80 ttl 64 label 0x6f48a len 40 proto TCP (6) flags [S] seq 2987508160 win 64800 [mss 1440,sackOK,TS val 1409097346 ecr 0,nop,wscale 7]"
+        );
+
+        // The tag is link-layer information.
+        assert_eq!(
+            &format!("{}", packet.display(&DisplayFormat::new(), &FormatterConf::new())),
+            "1111::1.56164 > 1111::2.80 ttl 64 label 0x6f48a len 40 proto TCP (6) flags [S] seq 2987508160 win 64800 [mss 1440,sackOK,TS val 1409097346 ecr 0,nop,wscale 7]"
+        );
+    }
+
+    #[test]
+    fn packet_checksums() {
+        let csums = |packet: &str| {
+            let mut buf = Vec::new();
+            BASE64_STANDARD.decode_vec(packet, &mut buf).unwrap();
+            RawPacket(buf).checksums()
+        };
+        let check = |l3, l4| PacketChecksums { l3, l4 };
+
+        // Valid IPv4 & UDP checksums.
+        assert_eq!(
+            csums("qrvM3e7/ESIzRFVmCABFAAAhEjRAAEARFJYKAAABCgAAAgTSFi4ADXcCcmV0aXM="),
+            check(Some(true), Some(true)),
+        );
+        // Same, with Ethernet padding.
+        assert_eq!(
+            csums(
+                "qrvM3e7/ESIzRFVmCABFAAAhEjRAAEARFJYKAAABCgAAAgTSFi4ADXcCcmV0aXMAAAAAAAAAAAAAAAAA"
+            ),
+            check(Some(true), Some(true)),
+        );
+        // Modified UDP payload.
+        assert_eq!(
+            csums("qrvM3e7/ESIzRFVmCABFAAAhEjRAAEARFJYKAAABCgAAAgTSFi4ADXcCcmV0aXI="),
+            check(Some(true), Some(false)),
+        );
+        // Modified TTL (the UDP checksum does not cover it).
+        assert_eq!(
+            csums("qrvM3e7/ESIzRFVmCABFAAAhEjRAAD8RFJYKAAABCgAAAgTSFi4ADXcCcmV0aXM="),
+            check(Some(false), Some(true)),
+        );
+        // Truncated UDP payload.
+        assert_eq!(
+            csums("qrvM3e7/ESIzRFVmCABFAAAhEjRAAEARFJYKAAABCgAAAgTSFi4ADXcCcmV0"),
+            check(Some(true), None),
+        );
+        // IPv6 TCP packet with its checksum not yet computed (offloaded).
+        assert_eq!(
+            csums("rrBKar+vnh09MZ47ht1gBvSKACgGQBERAAAAAAAAAAAAAAAAAAEREQAAAAAAAAAAAAAAAAAC22QAULIRwcAAAAAAoAL9ICJTAAACBAWgBAIIClP9HoIAAAAAAQMDBw=="),
+            check(None, Some(false)),
+        );
+        // IPv6 UDP first fragment.
+        assert_eq!(
+            csums("rrBKar+vnh09MZ47ht1gAAAAACAAQBERAAAAAAAAAAAAAAAAAAEREQAAAAAAAAAAAAAAAAACLAABBAAAAAARAAABAAASNATSFi4AEAAAQUFBQUFBQUE="),
+            check(None, None),
+        );
+    }
+
+    #[test]
+    fn print_udp6_fragments() {
+        let mut buf = Vec::new();
+        BASE64_STANDARD.decode_vec(
+            "rrBKar+vnh09MZ47ht1gAAAAACAAQBERAAAAAAAAAAAAAAAAAAEREQAAAAAAAAAAAAAAAAACLAABBAAAAAARAAABAAASNATSFi4AEAAAQUFBQUFBQUE=",
+            &mut buf,
+        ).unwrap();
+        let raw = RawPacket(buf);
+
+        // First fragment, the upper-layer header is found after the extensions.
+        assert_eq!(
+            &format!("{}", raw.display(&DisplayFormat::new(), &FormatterConf::new())),
+            "1111::1.1234 > 1111::2.5678 ttl 64 label 0x0 len 32 exts [Hopopt,Ipv6Frag] frag id 4660 off 0 [+] proto UDP (17) len 8"
+        );
+        let flow = raw.flow().unwrap();
+        assert_eq!(flow.ports, Some((1234, 5678)));
+        assert_eq!(flow.protocol_name(), "UDP");
+
+        let mut buf = Vec::new();
+        BASE64_STANDARD.decode_vec(
+            "rrBKar+vnh09MZ47ht1gAAAAABgAQBERAAAAAAAAAAAAAAAAAAEREQAAAAAAAAAAAAAAAAACLAABBAAAAAARAAWoAAASNEFBQUFBQUFB",
+            &mut buf,
+        ).unwrap();
+        let raw = RawPacket(buf);
+
+        // Last fragment, no upper-layer header.
+        assert_eq!(
+            &format!("{}", raw.display(&DisplayFormat::new(), &FormatterConf::new())),
+            "1111::1 > 1111::2 ttl 64 label 0x0 len 24 exts [Hopopt,Ipv6Frag] frag id 4660 off 1448 proto UDP (17)"
+        );
+        let flow = raw.flow().unwrap();
+        assert_eq!(flow.ports, None);
+        assert_eq!(flow.protocol_name(), "UDP");
+    }
+
     #[test]
     fn print_sctp_init() {
         let mut buf = Vec::new();
@@ -11,7 +11,10 @@ use pyo3::{
     types::{IntoPyDict, PyBool, PyList},
 };
 
-use crate::{file::*, *};
+use crate::{
+    file::{rotate::RotateReader, *},
+    *,
+};
 
 /// Python representation of an Event.
 ///
@@ -194,7 +197,14 @@ impl PyEventSeries {
 
 /// Python event reader
 ///
-/// Objects of this class can read events from unsorted event files.
+/// Objects of this class can read events from unsorted event files. When
+/// `split` is set, the following split files (see `retis collect --out-rotate`)
+/// are read at EOF, if any.
+///
+/// Events of multiple files (e.g. collected on different hosts) can be read
+/// as a single stream, ordered by timestamp, using `EventReader.merge`. An
+/// optional list of time deltas, in nanoseconds, can be given to compensate
+/// for clock differences between hosts (see `retis print --delta`).
 ///
 ///
 /// ## Example
@@ -202,7 +212,10 @@ impl PyEventSeries {
 /// ```python
 /// reader = EventReader("retis.data")
 ///
-/// for event in series:
+/// for event in reader:
+///     print(event)
+///
+/// for event in EventReader.merge(["host1.data", "host2.data"], [0, -1500]):
 ///     print(event)
 /// ```
 #[pyclass(name = "EventReader")]
@@ -210,19 +223,63 @@ pub(crate) struct PyEventReader {
     pub(crate) factory: FileEventsFactory,
 }
 
-#[pymethods]
 impl PyEventReader {
-    #[new]
-    pub(crate) fn new(path: PathBuf) -> PyResult<Self> {
-        let factory = FileEventsFactory::from_path(path)
-            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    fn factory(path: PathBuf, split: bool) -> PyResult<FileEventsFactory> {
+        let factory = match split {
+            true => RotateReader::new(path, true)
+                .and_then(|reader| FileEventsFactory::new(Box::new(reader))),
+            false => FileEventsFactory::from_path(path),
+        }
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
 
         if matches!(factory.file_type(), FileType::Series) {
             return Err(PyRuntimeError::new_err(
                 "Cannot create a EventReader from a sorted file. Use an SeriesReader instead",
             ));
         }
-        Ok(PyEventReader { factory })
+        Ok(factory)
+    }
+}
+
+#[pymethods]
+impl PyEventReader {
+    #[new]
+    #[pyo3(signature = (path, split=false))]
+    pub(crate) fn new(path: PathBuf, split: bool) -> PyResult<Self> {
+        Ok(PyEventReader {
+            factory: Self::factory(path, split)?,
+        })
+    }
+
+    /// Read the events of multiple files, ordered by timestamp.
+    #[staticmethod]
+    #[pyo3(signature = (paths, deltas=Vec::new(), split=false))]
+    pub(crate) fn merge(paths: Vec<PathBuf>, deltas: Vec<i64>, split: bool) -> PyResult<Self> {
+        if deltas.len() > paths.len() {
+            return Err(PyRuntimeError::new_err("More deltas than files were given"));
+        }
+
+        let inputs = paths
+            .into_iter()
+            .enumerate()
+            .map(|(i, path)| {
+                Ok((
+                    Self::factory(path, split)?,
+                    deltas.get(i).copied().unwrap_or_default(),
+                ))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        Ok(PyEventReader {
+            factory: FileEventsFactory::merge(inputs)
+                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?,
+        })
+    }
+
+    /// Returns the startup event of the file(s), describing the collection, if
+    /// any.
+    pub(crate) fn startup(&self, py: Python<'_>) -> PyResult<Option<PyEvent>> {
+        startup_event(py, &self.factory)
     }
 
     // Implementation of the iterator protocol.
@@ -251,16 +308,17 @@ impl PyEventReader {
 
 /// Python series reader
 ///
-/// Objects of this class can read events from unsorted event files.
+/// Objects of this class can read series from sorted event files.
 ///
 ///
 /// ## Example
 ///
 /// ```python
-/// reader = EventReader("retis.data")
+/// reader = SeriesReader("sorted.data")
 ///
-/// for event in series:
-///     print(event)
+/// for series in reader:
+///     for event in series:
+///         print(event)
 /// ```
 #[pyclass(name = "SeriesReader")]
 pub(crate) struct PySeriesReader {
@@ -353,12 +411,36 @@ impl PyEventFile {
     }
 
     pub(crate) fn events(&self) -> PyResult<PyEventReader> {
-        PyEventReader::new(self.path.clone())
+        PyEventReader::new(self.path.clone(), false)
     }
 
     pub(crate) fn series(&self) -> PyResult<PySeriesReader> {
         PySeriesReader::new(self.path.clone())
     }
+
+    /// Returns the startup event of the file, describing the collection, if
+    /// any.
+    pub(crate) fn startup(&self, py: Python<'_>) -> PyResult<Option<PyEvent>> {
+        let factory = FileEventsFactory::from_path(&self.path)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        startup_event(py, &factory)
+    }
+}
+
+/// Builds an event holding the startup section of a file, if any.
+fn startup_event(py: Python<'_>, factory: &FileEventsFactory) -> PyResult<Option<PyEvent>> {
+    factory
+        .startup()
+        .map(|startup| {
+            PyEvent::new(
+                py,
+                Event {
+                    startup: Some(startup.clone()),
+                    ..Default::default()
+                },
+            )
+        })
+        .transpose()
 }
 
 /// Converts a serde_json::Value to a PyObject.
@@ -0,0 +1,63 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, Formatter};
+
+/// Route section, reporting the result of a FIB lookup.
+#[event_section]
+#[derive(Default)]
+pub struct RouteEvent {
+    /// Routing table the lookup was done in.
+    pub table: u32,
+    /// Source address of the flow looked up.
+    pub saddr: String,
+    /// Destination address of the flow looked up.
+    pub daddr: String,
+    /// Prefix of the route matched, if any.
+    pub prefix: Option<String>,
+    /// Type of the route matched, if known (e.g. "unicast", "local" or
+    /// "unreachable").
+    pub r#type: Option<String>,
+    /// Gateway of the route matched, if any.
+    pub gw: Option<String>,
+    /// Output device, if any.
+    pub dev: Option<String>,
+    /// Output device index, if any.
+    pub ifindex: Option<u32>,
+    /// Error returned by the lookup, if known.
+    pub err: Option<i32>,
+}
+
+impl EventFmt for RouteEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "route table ")?;
+        match self.table {
+            253 => write!(f, "default")?,
+            254 => write!(f, "main")?,
+            255 => write!(f, "local")?,
+            x => write!(f, "{x}")?,
+        }
+        write!(f, " {} > {}", self.saddr, self.daddr)?;
+
+        if let Some(prefix) = &self.prefix {
+            write!(f, " prefix {prefix}")?;
+        }
+        if let Some(kind) = &self.r#type {
+            write!(f, " {kind}")?;
+        }
+        if let Some(gw) = &self.gw {
+            write!(f, " via {gw}")?;
+        }
+        if let Some(dev) = &self.dev {
+            write!(f, " dev {dev}")?;
+            if let Some(ifindex) = self.ifindex {
+                write!(f, " ({ifindex})")?;
+            }
+        }
+        if let Some(err) = self.err.filter(|e| *e != 0) {
+            write!(f, " err {err}")?;
+        }
+
+        Ok(())
+    }
+}
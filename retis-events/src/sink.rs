@@ -0,0 +1,198 @@
+//! # Event sinks
+//!
+//! Event sinks are the final consumers of events, e.g. writing them to a file,
+//! to stdout or to a remote endpoint. Custom sinks can be implemented using the
+//! `EventSink` trait.
+
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::Event;
+
+/// Number of events queued per sink before the producer is blocked.
+const SINK_QUEUE_LEN: usize = 1024;
+
+/// Consumer of events.
+pub trait EventSink: Send {
+    /// Process a single event.
+    fn process_one(&mut self, event: &Event) -> Result<()>;
+
+    /// Flush events buffered by the sink, if any.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+enum SinkMsg {
+    Event(Arc<Event>),
+    Flush,
+}
+
+struct SinkHandle {
+    name: String,
+    tx: Option<mpsc::SyncSender<SinkMsg>>,
+    thread: Option<thread::JoinHandle<Result<()>>>,
+}
+
+impl SinkHandle {
+    fn send(&mut self, msg: SinkMsg) -> Result<()> {
+        match self.tx.as_ref().map(|tx| tx.send(msg)) {
+            Some(Ok(())) => Ok(()),
+            // The sink thread stopped, report why.
+            _ => self.join(),
+        }
+    }
+
+    fn join(&mut self) -> Result<()> {
+        self.tx = None;
+        match self.thread.take() {
+            Some(thread) => thread
+                .join()
+                .map_err(|_| anyhow!("{}: sink thread panicked", self.name))?
+                .map_err(|e| anyhow!("{}: {e}", self.name)),
+            None => Err(anyhow!("{}: sink already stopped", self.name)),
+        }
+    }
+}
+
+/// Fans out events to multiple sinks. Each sink runs in its own thread, for
+/// sinks to process events concurrently and for a slow sink not to delay the
+/// others (until its queue is full).
+#[derive(Default)]
+pub struct MultiSink {
+    sinks: Vec<SinkHandle>,
+}
+
+impl MultiSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sink, its name being used to report errors.
+    pub fn add(&mut self, name: &str, mut sink: Box<dyn EventSink>) -> Result<()> {
+        let (tx, rx) = mpsc::sync_channel(SINK_QUEUE_LEN);
+
+        let thread = thread::Builder::new()
+            .name(format!("sink-{name}"))
+            .spawn(move || {
+                for msg in rx.iter() {
+                    match msg {
+                        SinkMsg::Event(event) => sink.process_one(&event)?,
+                        SinkMsg::Flush => sink.flush()?,
+                    }
+                }
+                sink.flush()
+            })?;
+
+        self.sinks.push(SinkHandle {
+            name: name.to_string(),
+            tx: Some(tx),
+            thread: Some(thread),
+        });
+        Ok(())
+    }
+
+    /// Send an event to all sinks.
+    pub fn process_one(&mut self, event: Event) -> Result<()> {
+        let event = Arc::new(event);
+        self.sinks
+            .iter_mut()
+            .try_for_each(|s| s.send(SinkMsg::Event(event.clone())))
+    }
+
+    /// Request all sinks to flush their events.
+    pub fn flush(&mut self) -> Result<()> {
+        self.sinks
+            .iter_mut()
+            .try_for_each(|s| s.send(SinkMsg::Flush))
+    }
+
+    /// Wait for all sinks to process their pending events, flush them and
+    /// stop their threads. The first error encountered, if any, is reported.
+    pub fn finish(mut self) -> Result<()> {
+        let mut res = Ok(());
+        self.sinks.iter_mut().for_each(|s| {
+            if let Err(e) = s.join() {
+                if res.is_ok() {
+                    res = Err(e);
+                }
+            }
+        });
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use anyhow::bail;
+
+    use super::*;
+    use crate::CommonEvent;
+
+    // Sink storing the timestamp of the events it sees.
+    struct Collector(Arc<Mutex<Vec<u64>>>);
+
+    impl EventSink for Collector {
+        fn process_one(&mut self, event: &Event) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(event.common.as_ref().unwrap().timestamp);
+            Ok(())
+        }
+    }
+
+    struct Failing;
+
+    impl EventSink for Failing {
+        fn process_one(&mut self, _: &Event) -> Result<()> {
+            bail!("failed")
+        }
+    }
+
+    fn event(timestamp: u64) -> Event {
+        Event {
+            common: Some(CommonEvent {
+                timestamp,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn multi_sink() {
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::new(Mutex::new(Vec::new()));
+
+        let mut sinks = MultiSink::new();
+        sinks.add("a", Box::new(Collector(a.clone()))).unwrap();
+        sinks.add("b", Box::new(Collector(b.clone()))).unwrap();
+
+        (0..10).for_each(|ts| sinks.process_one(event(ts)).unwrap());
+        sinks.finish().unwrap();
+
+        assert_eq!(*a.lock().unwrap(), (0..10).collect::<Vec<_>>());
+        assert_eq!(*b.lock().unwrap(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn multi_sink_error() {
+        let mut sinks = MultiSink::new();
+        sinks.add("failing", Box::new(Failing)).unwrap();
+
+        // The error is reported once the sink thread stopped.
+        let res = (0..10).try_for_each(|ts| sinks.process_one(event(ts)));
+        let err = match res {
+            Err(e) => e,
+            Ok(()) => sinks.finish().unwrap_err(),
+        };
+        assert_eq!(err.to_string(), "failing: failed");
+    }
+}
@@ -17,6 +17,23 @@ pub struct SkbEvent {
     pub data_ref: Option<SkbDataRefEvent>,
     /// GSO.
     pub gso: Option<SkbGsoEvent>,
+    /// Checksum status and validation.
+    pub csum: Option<SkbCsumEvent>,
+}
+
+/// Format the checksum status of an skb, see `skb->ip_summed`.
+fn fmt_csum_status(f: &mut Formatter, ip_summed: u8, csum: u32, level: u8) -> fmt::Result {
+    match ip_summed {
+        0 => write!(f, "none"),
+        1 => write!(f, "unnecessary (level {level})"),
+        2 => write!(f, "complete ({csum:#x})"),
+        3 => {
+            let start = csum & 0xffff;
+            let off = csum >> 16;
+            write!(f, "partial (start {start} off {off})")
+        }
+        x => write!(f, "unknown ({x})"),
+    }
 }
 
 impl EventFmt for SkbEvent {
@@ -49,17 +66,8 @@ impl EventFmt for SkbEvent {
 
             if let Some(meta) = &self.meta {
                 write!(f, "csum ")?;
-                match meta.ip_summed {
-                    0 => write!(f, "none ")?,
-                    1 => write!(f, "unnecessary (level {}) ", meta.csum_level)?,
-                    2 => write!(f, "complete ({:#x}) ", meta.csum)?,
-                    3 => {
-                        let start = meta.csum & 0xffff;
-                        let off = meta.csum >> 16;
-                        write!(f, "partial (start {start} off {off}) ")?;
-                    }
-                    x => write!(f, "unknown ({x}) ")?,
-                }
+                fmt_csum_status(f, meta.ip_summed, meta.csum, meta.csum_level)?;
+                write!(f, " ")?;
 
                 if meta.hash != 0 {
                     write!(f, "hash {:#x} ", meta.hash)?;
@@ -110,6 +118,26 @@ impl EventFmt for SkbEvent {
             write!(f, "size {}]", gso.size)?;
         }
 
+        if let Some(csum) = &self.csum {
+            space.write(f)?;
+            write!(f, "csum [")?;
+            fmt_csum_status(f, csum.ip_summed, csum.csum, csum.csum_level)?;
+
+            if csum.csum_valid {
+                write!(f, " valid")?;
+            }
+
+            let status = |valid: bool| if valid { "ok" } else { "bad" };
+            if let Some(valid) = csum.l3_valid {
+                write!(f, " l3 {}", status(valid))?;
+            }
+            if let Some(valid) = csum.l4_valid {
+                write!(f, " l4 {}", status(valid))?;
+            }
+
+            write!(f, "]")?;
+        }
+
         Ok(())
     }
 
@@ -118,6 +146,7 @@ impl EventFmt for SkbEvent {
             || self.meta.is_some()
             || self.data_ref.is_some()
             || self.gso.is_some()
+            || self.csum.is_some()
     }
 }
 
@@ -293,6 +322,26 @@ pub struct SkbMetaEvent {
     pub priority: u32,
 }
 
+/// Checksum status and validation.
+#[event_type]
+pub struct SkbCsumEvent {
+    /// Checksum status (`skb->ip_summed`).
+    pub ip_summed: u8,
+    /// Packet checksum (ip_summed == CHECKSUM_COMPLETE) or checksum
+    /// (start << 16)|offset (ip_summed == CHECKSUM_PARTIAL).
+    pub csum: u32,
+    /// Checksum level (ip_summed == CHECKSUM_UNNECESSARY).
+    pub csum_level: u8,
+    /// Checksum was already validated by the stack (`skb->csum_valid`).
+    pub csum_valid: bool,
+    /// IPv4 header checksum is valid, if it could be checked.
+    pub l3_valid: Option<bool>,
+    /// L4 (TCP, UDP, ICMP or ICMPv6) checksum is valid, if it could be
+    /// checked. This is only done for linear and fully captured packets whose
+    /// checksum is not offloaded (ip_summed != CHECKSUM_PARTIAL).
+    pub l4_valid: Option<bool>,
+}
+
 /// Skb data & refcnt.
 #[event_type]
 pub struct SkbDataRefEvent {
@@ -14,10 +14,21 @@ pub struct SkbDropEvent {
 }
 
 impl EventFmt for SkbDropEvent {
-    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
         match &self.subsys {
-            None => write!(f, "drop (reason {})", self.drop_reason),
-            Some(name) => write!(f, "drop (reason {name}/{})", self.drop_reason),
+            None => write!(
+                f,
+                "drop (reason {})",
+                format.highlight(Highlight::DropReason, &self.drop_reason)
+            ),
+            Some(name) => write!(
+                f,
+                "drop (reason {})",
+                format.highlight(
+                    Highlight::DropReason,
+                    format_args!("{name}/{}", self.drop_reason)
+                )
+            ),
         }
     }
 }
@@ -54,6 +54,27 @@ impl EventFmt for SkbTrackingEvent {
     }
 }
 
+/// Skb relationship section. Reported when a new skb is created out of an
+/// existing one, the latter being described by the skb tracking section.
+#[event_section]
+pub struct SkbRelationEvent {
+    /// Kind of relationship: "clone", "copy", "expand", "encap" or "decap".
+    /// Clones share their data, and thus their tracking id, with the original
+    /// skb; copies (and expanded copies) have their own. Segments of
+    /// encapsulated packets ("encap") and decapsulated packets ("decap") keep
+    /// the tracking id of the original skb.
+    pub kind: String,
+    /// Tracking information of the new skb.
+    pub child: SkbTrackingEvent,
+}
+
+impl EventFmt for SkbRelationEvent {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        write!(f, "{} to ", self.kind)?;
+        self.child.event_fmt(f, format)
+    }
+}
+
 /// Tracking event section. Generated at postprocessing with combined skb and ovs
 /// tracking information.
 #[event_section]
@@ -0,0 +1,66 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, Formatter};
+
+/// Socket section, reporting the socket a packet is attached to.
+#[event_section]
+#[derive(Default)]
+pub struct SockEvent {
+    /// Socket cookie, if one was already generated for the socket. See
+    /// `bpf_get_socket_cookie` in `man 7 bpf-helpers`, and `ss -e`.
+    pub cookie: Option<u64>,
+    /// Socket family, e.g. "inet", "inet6" or "unix".
+    pub family: String,
+    /// Socket type, e.g. "stream", "dgram" or "raw". Not reported for
+    /// request and time-wait sockets.
+    pub r#type: Option<String>,
+    /// Socket protocol number, e.g. 6 for TCP in inet sockets.
+    pub protocol: Option<u8>,
+    /// Owner user id of the socket.
+    pub uid: Option<u32>,
+    /// Id of the cgroup (v2) the socket is associated with. It might not be
+    /// available on older kernels.
+    pub cgroup_id: Option<u64>,
+    /// Container the socket cgroup belongs to. Set at post-processing time,
+    /// when container information was retrieved during the collection.
+    pub container: Option<String>,
+    /// Task which created (or accepted) the socket, if it was created during
+    /// the collection.
+    pub owner: Option<TaskEvent>,
+}
+
+impl EventFmt for SockEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "sock {}", self.family)?;
+
+        if let Some(r#type) = &self.r#type {
+            write!(f, " {}", r#type)?;
+        }
+        if let Some(protocol) = self.protocol {
+            write!(f, " proto {protocol}")?;
+        }
+        if let Some(cookie) = self.cookie {
+            write!(f, " cookie {cookie:#x}")?;
+        }
+        if let Some(uid) = self.uid {
+            write!(f, " uid {uid}")?;
+        }
+        if let Some(cgroup_id) = self.cgroup_id {
+            write!(f, " cgroup {cgroup_id}")?;
+        }
+        if let Some(container) = &self.container {
+            write!(f, " container {container}")?;
+        }
+
+        if let Some(owner) = &self.owner {
+            write!(f, " owner [{}] ", owner.comm)?;
+            if owner.tgid != owner.pid {
+                write!(f, "{}/", owner.pid)?;
+            }
+            write!(f, "{}", owner.tgid)?;
+        }
+
+        Ok(())
+    }
+}
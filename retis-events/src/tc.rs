@@ -0,0 +1,112 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// Tc section.
+#[event_section]
+#[derive(Default)]
+pub struct TcEvent {
+    /// Qdisc the packet is handled by, if any.
+    pub qdisc: Option<TcQdisc>,
+    /// Result of the tc classification, if the event was generated while
+    /// classifying the packet.
+    pub classify: Option<TcClassify>,
+}
+
+/// Qdisc information.
+#[event_type]
+#[derive(Default)]
+pub struct TcQdisc {
+    /// Qdisc kind, e.g. "fq_codel".
+    pub kind: String,
+    /// Qdisc handle.
+    pub handle: u32,
+    /// Handle of the parent qdisc or class.
+    pub parent: u32,
+    /// Number of packets in the queue.
+    pub qlen: u32,
+    /// Number of bytes in the queue.
+    pub backlog: u32,
+    /// Number of packets dequeued at once, for dequeue events.
+    pub packets: Option<u32>,
+}
+
+/// Tc classification result.
+#[event_type]
+#[derive(Default)]
+pub struct TcClassify {
+    /// Whether the packet was classified on ingress (or egress).
+    pub ingress: bool,
+    /// Classification verdict (unspec, ok, reclassify, shot, pipe, stolen,
+    /// queued, repeat, redirect or trap; unknown ones are reported using their
+    /// numerical value).
+    pub verdict: String,
+}
+
+/// Format a tc handle the way the tc tool does, e.g. "1:10".
+fn tc_handle(handle: u32) -> String {
+    match handle {
+        // TC_H_ROOT
+        0xffffffff => "root".to_string(),
+        // TC_H_UNSPEC
+        0 => "none".to_string(),
+        _ => match (handle >> 16, handle & 0xffff) {
+            (major, 0) => format!("{major:x}:"),
+            (major, minor) => format!("{major:x}:{minor:x}"),
+        },
+    }
+}
+
+impl EventFmt for TcEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        let mut space = DelimWriter::new(' ');
+
+        if let Some(classify) = &self.classify {
+            space.write(f)?;
+            write!(
+                f,
+                "tc {} {}",
+                if classify.ingress {
+                    "ingress"
+                } else {
+                    "egress"
+                },
+                classify.verdict
+            )?;
+        }
+
+        if let Some(qdisc) = &self.qdisc {
+            space.write(f)?;
+            write!(
+                f,
+                "qdisc {} {} parent {} qlen {} backlog {}b",
+                qdisc.kind,
+                tc_handle(qdisc.handle),
+                tc_handle(qdisc.parent),
+                qdisc.qlen,
+                qdisc.backlog,
+            )?;
+
+            if let Some(packets) = qdisc.packets {
+                write!(f, " dequeued {packets}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handle_format() {
+        assert_eq!(tc_handle(0xffffffff), "root");
+        assert_eq!(tc_handle(0), "none");
+        assert_eq!(tc_handle(0x10000), "1:");
+        assert_eq!(tc_handle(0x10010), "1:10");
+        assert_eq!(tc_handle(0xfffffff1), "ffff:fff1");
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// TCP socket section.
+#[event_section]
+#[derive(Default)]
+pub struct TcpEvent {
+    /// Operation: "state" (state transition), "retrans" (segment
+    /// retransmission), "rto" (retransmission timer expiry) or "drop" (segment
+    /// dropped by the TCP stack).
+    pub op: String,
+    /// Local address of the socket.
+    pub saddr: String,
+    /// Remote address of the socket.
+    pub daddr: String,
+    /// Local port of the socket.
+    pub sport: u16,
+    /// Remote port of the socket.
+    pub dport: u16,
+    /// State of the socket, e.g. "ESTABLISHED". For state transitions, this
+    /// is the state the socket is leaving.
+    pub state: String,
+    /// New state of the socket, for state transitions.
+    pub new_state: Option<String>,
+    /// Connection metrics, for other operations.
+    pub metrics: Option<TcpMetrics>,
+}
+
+/// TCP connection metrics.
+#[event_type]
+#[derive(Default)]
+pub struct TcpMetrics {
+    /// Retransmission timeout, in milliseconds. Not reported if the kernel
+    /// timer frequency is unknown.
+    pub rto_ms: Option<u32>,
+    /// Smoothed round trip time, in microseconds.
+    pub srtt_us: u32,
+    /// Number of unrecovered retransmission timeouts.
+    pub retransmits: u32,
+    /// Total number of retransmitted segments.
+    pub total_retrans: u32,
+}
+
+impl EventFmt for TcpEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(
+            f,
+            "tcp {} {}.{} > {}.{} {}",
+            self.op, self.saddr, self.sport, self.daddr, self.dport, self.state
+        )?;
+
+        if let Some(new_state) = &self.new_state {
+            write!(f, " > {new_state}")?;
+        }
+
+        if let Some(metrics) = &self.metrics {
+            if let Some(rto) = metrics.rto_ms {
+                write!(f, " rto {rto}ms")?;
+            }
+            write!(
+                f,
+                " srtt {}us retrans {}/{}",
+                metrics.srtt_us, metrics.retransmits, metrics.total_retrans
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// Tunnel metadata section.
+#[event_section]
+#[derive(Default)]
+pub struct TunnelEvent {
+    /// Whether the metadata is used for transmitting the packet
+    /// (encapsulation), or was set when receiving it (decapsulation).
+    pub tx: bool,
+    /// Tunnel id: VNI for VXLAN and GENEVE, key for GRE.
+    pub id: u64,
+    /// Source address of the outer header.
+    pub src: String,
+    /// Destination address of the outer header.
+    pub dst: String,
+    /// Source port of the outer header, if any.
+    pub sport: Option<u16>,
+    /// Destination port of the outer header, if any.
+    pub dport: Option<u16>,
+    /// Type of service of the outer header.
+    pub tos: u8,
+    /// Time to live of the outer header.
+    pub ttl: u8,
+    /// GENEVE options, if any.
+    pub geneve_opts: Option<Vec<TunnelGeneveOpt>>,
+    /// VXLAN group based policy id, if any.
+    pub vxlan_gbp: Option<u32>,
+}
+
+/// GENEVE option.
+#[event_type]
+#[derive(Default)]
+pub struct TunnelGeneveOpt {
+    /// Option class.
+    pub class: u16,
+    /// Option type.
+    pub r#type: u8,
+    /// Option data, in hexadecimal.
+    pub data: String,
+}
+
+impl EventFmt for TunnelEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        let port = |port: Option<u16>| port.map(|p| format!(".{p}")).unwrap_or_default();
+
+        write!(
+            f,
+            "tunnel {} id {} {}{} > {}{} tos {:#x} ttl {}",
+            if self.tx { "tx" } else { "rx" },
+            self.id,
+            self.src,
+            port(self.sport),
+            self.dst,
+            port(self.dport),
+            self.tos,
+            self.ttl,
+        )?;
+
+        if let Some(opts) = &self.geneve_opts {
+            write!(f, " geneve [")?;
+            let mut sep = DelimWriter::new(',');
+            for opt in opts.iter() {
+                sep.write(f)?;
+                write!(f, "{:#x}:{:#x}:{}", opt.class, opt.r#type, opt.data)?;
+            }
+            write!(f, "]")?;
+        }
+
+        if let Some(gbp) = self.vxlan_gbp {
+            write!(f, " gbp {gbp:#x}")?;
+        }
+
+        Ok(())
+    }
+}
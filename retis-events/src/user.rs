@@ -1,7 +1,7 @@
 use std::fmt;
 
 use super::*;
-use crate::{event_section, Formatter};
+use crate::{event_section, event_type, Formatter};
 
 /// Userspace section
 #[event_section]
@@ -18,14 +18,83 @@ pub struct UserEvent {
     pub pid: i32,
     /// Thread ID.
     pub tid: i32,
+    /// Probe arguments, if any. Values are sign or zero extended depending
+    /// on the argument type.
+    pub args: Option<Vec<i64>>,
 }
 
 impl EventFmt for UserEvent {
-    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
-        write!(f, "[u] {}", self.symbol)?;
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        write!(
+            f,
+            "[u] {}",
+            format.highlight(Highlight::Probe, &self.symbol)
+        )?;
         if let Some((_, bin)) = self.path.rsplit_once('/') {
             write!(f, " ({bin})")?;
         }
+        if let Some(args) = &self.args {
+            let args: Vec<_> = args.iter().map(|a| a.to_string()).collect();
+            write!(f, " args ({})", args.join(", "))?;
+        }
         Ok(())
     }
 }
+
+/// Userspace stack trace section
+#[event_section]
+pub struct UserStackEvent {
+    /// Process ID the stack trace belongs to.
+    pub pid: i32,
+    /// Stack frames, starting from the innermost one.
+    pub frames: Vec<UserStackFrame>,
+}
+
+impl EventFmt for UserStackEvent {
+    fn event_fmt(&self, f: &mut Formatter, format: &DisplayFormat) -> fmt::Result {
+        if !format.multiline {
+            write!(f, "[")?;
+        }
+        self.frames.iter().enumerate().try_for_each(|(i, frame)| {
+            if i > 0 {
+                match format.multiline {
+                    true => writeln!(f)?,
+                    false => write!(f, ", ")?,
+                }
+            }
+            frame.event_fmt(f, format)
+        })?;
+        if !format.multiline {
+            write!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+/// Userspace stack frame.
+#[event_type]
+pub struct UserStackFrame {
+    /// Instruction pointer.
+    pub ip: u64,
+    /// Path of the object file the instruction pointer belongs to, if known.
+    pub path: Option<String>,
+    /// Offset of the instruction pointer in the object file.
+    pub offset: Option<u64>,
+    /// Symbol and offset in the symbol. Resolved at post-processing time.
+    pub symbol: Option<String>,
+}
+
+impl EventFmt for UserStackFrame {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        let bin = self
+            .path
+            .as_ref()
+            .map(|path| path.rsplit_once('/').map(|(_, bin)| bin).unwrap_or(path));
+
+        match (&self.symbol, bin, self.offset) {
+            (Some(symbol), Some(bin), _) => write!(f, "{symbol} ({bin})"),
+            (None, Some(bin), Some(offset)) => write!(f, "{bin}+{offset:#x}"),
+            _ => write!(f, "{:#x}", self.ip),
+        }
+    }
+}
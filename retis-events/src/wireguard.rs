@@ -0,0 +1,59 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// WireGuard section.
+#[event_section]
+#[derive(Default)]
+pub struct WireguardEvent {
+    /// Operation: "xmit" (an inner packet is being sent through the WireGuard
+    /// device), "send" (an encrypted packet is sent to a peer), "recv" (an
+    /// outer packet was received) or "decrypt" (a packet was decrypted).
+    pub op: String,
+    /// Verdict: "queued" or "no-peer" (no peer has allowed IPs matching the
+    /// destination) when sending, "encrypted" when sending to a peer,
+    /// "decrypted" or "unallowed-src" (no peer has allowed IPs matching the
+    /// source) after decryption.
+    pub verdict: Option<String>,
+    /// Message type ("initiation", "response", "cookie" or "data"), on
+    /// receive.
+    pub msg: Option<String>,
+    /// Peer, if known.
+    pub peer: Option<WireguardPeer>,
+}
+
+/// WireGuard peer.
+#[event_type]
+#[derive(Default)]
+pub struct WireguardPeer {
+    /// Internal id of the peer, as reported by WireGuard debug messages.
+    pub id: u64,
+    /// Truncated public key of the peer, base64 encoded.
+    pub key: String,
+    /// Endpoint of the peer, if known.
+    pub endpoint: Option<String>,
+}
+
+impl EventFmt for WireguardEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "wg {}", self.op)?;
+
+        if let Some(msg) = &self.msg {
+            write!(f, " {msg}")?;
+        }
+
+        if let Some(verdict) = &self.verdict {
+            write!(f, " {verdict}")?;
+        }
+
+        if let Some(peer) = &self.peer {
+            write!(f, " peer {} ({}...)", peer.id, peer.key)?;
+            if let Some(endpoint) = &peer.endpoint {
+                write!(f, " endpoint {endpoint}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
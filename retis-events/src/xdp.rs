@@ -0,0 +1,40 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, Formatter};
+
+/// XDP section.
+#[event_section]
+#[derive(Default)]
+pub struct XdpEvent {
+    /// Id of the XDP program.
+    pub prog_id: u32,
+    /// Name of the XDP program, if any.
+    pub prog_name: Option<String>,
+    /// Action returned by the XDP program (ABORTED, DROP, PASS, TX or
+    /// REDIRECT; unknown actions are reported using their numerical value).
+    /// Not reported when the program is entered.
+    pub action: Option<String>,
+    /// Error reported when redirecting the packet failed.
+    pub err: Option<i32>,
+}
+
+impl EventFmt for XdpEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "xdp prog {}", self.prog_id)?;
+
+        if let Some(name) = &self.prog_name {
+            write!(f, " ({name})")?;
+        }
+
+        if let Some(action) = &self.action {
+            write!(f, " {action}")?;
+        }
+
+        if let Some(err) = self.err {
+            write!(f, " err {err}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// Xfrm (IPsec) section.
+#[event_section]
+#[derive(Default)]
+pub struct XfrmEvent {
+    /// Operation: "input" (packet received for decapsulation), "output"
+    /// (packet being encapsulated) or "policy" (packet blocked by a policy
+    /// check).
+    pub op: String,
+    /// Security parameter index, if known.
+    pub spi: Option<u32>,
+    /// IPsec protocol ("esp", "ah" or "comp"; unknown ones are reported using
+    /// their numerical value), if known.
+    pub proto: Option<String>,
+    /// Security association used, if known.
+    pub sa: Option<XfrmSa>,
+    /// Policy direction ("in", "out" or "fwd"), for policy operations.
+    pub dir: Option<String>,
+    /// Error returned while encapsulating the packet, if any.
+    pub err: Option<i32>,
+}
+
+/// Xfrm security association (state).
+#[event_type]
+#[derive(Default)]
+pub struct XfrmSa {
+    /// Mode, e.g. "tunnel" or "transport".
+    pub mode: String,
+    /// Request id, linking the security association to policies.
+    pub reqid: u32,
+    /// Source address.
+    pub src: String,
+    /// Destination address.
+    pub dst: String,
+}
+
+impl EventFmt for XfrmEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "xfrm {}", self.op)?;
+
+        if let Some(dir) = &self.dir {
+            write!(f, " {dir} block")?;
+        }
+
+        if let Some(spi) = self.spi {
+            write!(f, " spi {spi:#010x}")?;
+        }
+
+        if let Some(proto) = &self.proto {
+            write!(f, " {proto}")?;
+        }
+
+        if let Some(sa) = &self.sa {
+            write!(f, " {} reqid {} {} > {}", sa.mode, sa.reqid, sa.src, sa.dst)?;
+        }
+
+        if let Some(err) = self.err {
+            write!(f, " err {err}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+
+use super::*;
+use crate::{event_section, event_type, Formatter};
+
+/// AF_XDP section.
+#[event_section]
+#[derive(Default)]
+pub struct XskEvent {
+    /// Operation: "rx", "tx" or "tx-completion".
+    pub op: String,
+    /// Whether the operation was done in "copy" or "zero-copy" mode, if known.
+    pub mode: Option<String>,
+    /// Device the socket is bound to, if known.
+    pub dev: Option<String>,
+    /// Device queue the socket is bound to.
+    pub queue_id: u16,
+    /// Number of descriptors transmitted or completed, if relevant.
+    pub descs: Option<u32>,
+    /// Error returned by the operation, if it failed.
+    pub err: Option<i32>,
+    /// Socket ring: RX ring on receive, TX ring on transmit.
+    pub ring: Option<XskRing>,
+    /// Fill ring of the socket buffer pool, reported on receive.
+    pub fill: Option<XskRing>,
+    /// Completion ring of the socket buffer pool, reported on transmit.
+    pub completion: Option<XskRing>,
+}
+
+/// AF_XDP ring occupancy.
+#[event_type]
+#[derive(Default, PartialEq)]
+pub struct XskRing {
+    /// Number of entries in the ring.
+    pub used: u32,
+    /// Size of the ring.
+    pub size: u32,
+}
+
+impl EventFmt for XskEvent {
+    fn event_fmt(&self, f: &mut Formatter, _: &DisplayFormat) -> fmt::Result {
+        write!(f, "xsk {}", self.op)?;
+
+        if let Some(mode) = &self.mode {
+            write!(f, " {mode}")?;
+        }
+        if let Some(dev) = &self.dev {
+            write!(f, " dev {dev}")?;
+        }
+        write!(f, " queue {}", self.queue_id)?;
+
+        if let Some(descs) = self.descs {
+            write!(f, " descs {descs}")?;
+        }
+
+        [
+            ("ring", &self.ring),
+            ("fill", &self.fill),
+            ("completion", &self.completion),
+        ]
+        .iter()
+        .try_for_each(|(name, ring)| match ring {
+            Some(ring) => write!(f, " {name} {}/{}", ring.used, ring.size),
+            None => Ok(()),
+        })?;
+
+        if let Some(err) = self.err {
+            write!(f, " err {err}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ use std::cmp::min;
 pub struct ExtensionIterable<'a> {
     buf: &'a [u8],
     protocol: IpNextHeaderProtocol,
+    offset: usize,
 }
 
 impl<'a> ExtensionIterable<'a> {
@@ -15,24 +16,33 @@ impl<'a> ExtensionIterable<'a> {
         Self {
             buf: ipv6.payload(),
             protocol: ipv6.get_next_header(),
+            offset: 0,
         }
     }
 
+    /// Protocol of the header following the extensions walked so far.
+    pub fn protocol(&self) -> IpNextHeaderProtocol {
+        self.protocol
+    }
+
+    /// Offset of the header following the extensions walked so far, from the
+    /// start of the IPv6 payload.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Data following the extensions walked so far.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    // AH (different length encoding) and ESP (encrypted payload) are not
+    // walked and are handled as upper-layer protocols.
     fn is_extension(protocol: IpNextHeaderProtocol) -> bool {
         use IpNextHeaderProtocols::*;
         matches!(
             protocol,
-            Hopopt
-                | Ipv6Route
-                | Ipv6Frag
-                | Ah
-                | Esp
-                | Ipv6Opts
-                | MobilityHeader
-                | Hip
-                | Shim6
-                | Test1
-                | Test2
+            Hopopt | Ipv6Route | Ipv6Frag | Ipv6Opts | MobilityHeader | Hip | Shim6 | Test1 | Test2
         )
     }
 }
@@ -49,6 +59,7 @@ impl<'a> Iterator for ExtensionIterable<'a> {
             Some(ext) => {
                 let start = min(ext.packet_size(), self.buf.len());
                 self.buf = &self.buf[start..];
+                self.offset += start;
                 self.protocol = ext.get_next_header();
                 Some(ext)
             }
@@ -60,3 +71,58 @@ impl<'a> Iterator for ExtensionIterable<'a> {
         (0, None)
     }
 }
+
+/// Upper-layer (L4) information of an IPv6 packet, found by walking its
+/// extension headers.
+pub struct UpperLayer<'a> {
+    /// Upper-layer protocol.
+    pub protocol: IpNextHeaderProtocol,
+    /// Offset of the upper-layer header, from the start of the IPv6 payload.
+    pub offset: usize,
+    /// Upper-layer header and data.
+    pub payload: &'a [u8],
+    /// Extension headers, in order.
+    pub extensions: Vec<IpNextHeaderProtocol>,
+    /// Fragment header, if any.
+    pub fragment: Option<FragmentPacket<'a>>,
+}
+
+impl UpperLayer<'_> {
+    /// Returns true if the upper-layer header is part of the packet, i.e. the
+    /// packet is not a non-first fragment and has a next header.
+    pub fn has_header(&self) -> bool {
+        self.protocol != IpNextHeaderProtocols::Ipv6NoNxt
+            && self
+                .fragment
+                .as_ref()
+                .is_none_or(|frag| frag.get_fragment_offset() == 0)
+    }
+}
+
+/// Walk the extension headers of an IPv6 packet, up to its upper-layer one.
+pub fn upper_layer<'a>(ipv6: &'a Ipv6Packet) -> UpperLayer<'a> {
+    let mut iter = ExtensionIterable::from(ipv6);
+    let mut extensions = Vec::new();
+    let mut fragment = None;
+
+    loop {
+        let protocol = iter.protocol();
+        let buf = iter.remaining();
+        if iter.next().is_none() {
+            break;
+        }
+
+        extensions.push(protocol);
+        if protocol == IpNextHeaderProtocols::Ipv6Frag {
+            fragment = FragmentPacket::new(buf);
+        }
+    }
+
+    UpperLayer {
+        protocol: iter.protocol(),
+        offset: iter.offset(),
+        payload: iter.remaining(),
+        extensions,
+        fragment,
+    }
+}
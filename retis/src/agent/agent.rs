@@ -0,0 +1,622 @@
+use std::{
+    collections::HashSet,
+    env,
+    ffi::{CString, OsString},
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    iter,
+    net::{Shutdown, TcpListener, TcpStream},
+    os::unix::{
+        ffi::OsStringExt,
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::{parser::ValueSource, Parser};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{cli::*, collect::cli::Collect, helpers::signals::Running};
+
+/// Default port agents listen on, used when a remote host is given without
+/// one.
+pub(crate) const DEFAULT_AGENT_PORT: u16 = 9080;
+/// Time given to a collection to start (attaching probes can take a while),
+/// before giving up.
+const COLLECT_START_TIMEOUT: Duration = Duration::from_secs(120);
+/// Interval at which pending connections and collections are checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Arguments of `retis collect` which only make sense on the client side, and
+/// whether they take a value. Those are not forwarded to agents.
+const LOCAL_ARGS: &[(&str, ArgValue)] = &[
+    ("--remote", ArgValue::Required),
+    ("--remote-token-file", ArgValue::Required),
+    ("-o", ArgValue::Optional),
+    ("--out", ArgValue::Optional),
+    ("--out-rotate", ArgValue::Required),
+    ("--out-format", ArgValue::Required),
+    ("--out-index", ArgValue::Flag),
+    ("--compress", ArgValue::Flag),
+    ("--flight-recorder", ArgValue::Required),
+    ("--recorder-trigger", ArgValue::Required),
+    ("--print", ArgValue::Flag),
+    ("--ctl-socket", ArgValue::Optional),
+    ("--utc", ArgValue::Flag),
+    ("--format", ArgValue::Required),
+    ("-e", ArgValue::Flag),
+];
+
+/// Ids of the `retis collect` arguments which can be used in collections run
+/// on behalf of remote clients (agents and daemon): collectors, probes, filters
+/// and collection options. Others either only make sense on the client side or
+/// access files or run commands on the collecting host.
+const REMOTE_ARGS: &[&str] = &[
+    "collectors",
+    "exclude_collectors",
+    "probe",
+    "probe_max",
+    "yes",
+    "filter-packet",
+    "filter-meta",
+    "filter",
+    "filter_flow",
+    "filter_pid",
+    "filter_comm",
+    // Only allowed as an inode, see check_remote_args.
+    "netns",
+    "sample",
+    "rate_limit",
+    "events_buffer",
+    "lossless",
+    "throttle",
+    "stack",
+    "user_stack",
+    "cpu_sampling",
+    "probe_args",
+    "probe_stack",
+    "stop_after",
+    "until",
+    "allow_system_changes",
+    "container",
+    "enrich_containers",
+    "kube",
+    "skb_sections",
+    "skb_snaplen",
+    "ovs_track",
+    "ovs_enrich_flows",
+    "ovs_enrich_rate",
+    "nft_verdicts",
+    "skb_drop_reasons",
+    "ct_lifecycle",
+    "skb_tracking_relations",
+    "mem_page_pool_interval",
+];
+
+/// Value taken by a collect argument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ArgValue {
+    /// The argument does not take a value.
+    Flag,
+    /// The argument always takes a value.
+    Required,
+    /// The argument takes an optional value (e.g. `--out [OUT]`), only given
+    /// in the next argument if it does not start with '-'.
+    Optional,
+}
+
+/// Request sent by clients to start a collection, as a single JSON line. The
+/// agent first replies with its wall clock time ("clock <nanoseconds>"), for
+/// clients to estimate the clock difference between hosts. Once the collection
+/// started it replies with a single line, either "ok" or "error <reason>", then
+/// streams the events of the collection (JSON, one per line, starting with the
+/// startup event) until the client disconnects or the collection ends.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct AgentRequest {
+    /// Token shared by the agent and its clients, see `read_token`.
+    pub(crate) token: String,
+    /// Arguments of the collect command.
+    pub(crate) args: Vec<String>,
+}
+
+/// Find the client-side arguments (see `LOCAL_ARGS`) in a list of collect
+/// arguments. Returns the remaining arguments, and the client-side ones.
+pub(crate) fn split_local_args(args: &[String]) -> (Vec<String>, Vec<String>) {
+    split_args(args, LOCAL_ARGS)
+}
+
+/// Check a collection run on behalf of a remote client only uses the
+/// arguments in `REMOTE_ARGS`. Arguments are parsed the same way `retis
+/// collect` does, for combined short options (e.g. `-eo/tmp/foo`) not to be
+/// missed.
+pub(crate) fn check_remote_args(args: &[String]) -> Result<()> {
+    let mut command = Collect::new()?.command()?;
+    let matches = command
+        .try_get_matches_from_mut(iter::once("collect").chain(args.iter().map(String::as_str)))
+        .map_err(|e| {
+            // Errors are reported on a single line.
+            let e = e.to_string();
+            let e = e.lines().next().unwrap_or_default();
+            anyhow!(
+                "Invalid collect arguments: {}",
+                e.strip_prefix("error: ").unwrap_or(e)
+            )
+        })?;
+
+    let refused = command
+        .get_arguments()
+        .filter(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .filter(|arg| match arg.get_id().as_str() {
+            // Network namespaces can also be given as a path.
+            "netns" => matches
+                .get_one::<String>("netns")
+                .is_some_and(|netns| netns.parse::<u64>().is_err()),
+            id => !REMOTE_ARGS.contains(&id),
+        })
+        .map(|arg| match (arg.get_long(), arg.get_short()) {
+            (Some(long), _) => format!("--{long}"),
+            (None, Some(short)) => format!("-{short}"),
+            (None, None) => arg.get_id().to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    if !refused.is_empty() {
+        bail!("Arguments not supported remotely: {}", refused.join(" "));
+    }
+    Ok(())
+}
+
+/// Read the token shared by agents and their clients from a file.
+pub(crate) fn read_token(path: &Path) -> Result<String> {
+    let token = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Could not read token file {}: {e}", path.display()))?;
+    if fs::metadata(path)?.permissions().mode() & 0o077 != 0 {
+        warn!("Token file {} is accessible by other users", path.display());
+    }
+
+    let token = token.trim();
+    if token.is_empty() {
+        bail!("Empty token in {}", path.display());
+    }
+    Ok(token.to_string())
+}
+
+/// Compare tokens in constant time, not to leak how much of them matched.
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Find a set of arguments, given as (name, value taken), in a list of
+/// arguments. Returns the remaining arguments, and the matching ones (with
+/// their values).
+pub(crate) fn split_args(
+    args: &[String],
+    names: &[(&str, ArgValue)],
+) -> (Vec<String>, Vec<String>) {
+    let (mut remote, mut local) = (Vec::new(), Vec::new());
+
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        let known = names.iter().find_map(|(name, value)| {
+            if arg == name {
+                Some(*value)
+            } else if arg.starts_with(&format!("{name}="))
+                || (name.len() == 2 && arg.starts_with(name))
+            {
+                // Value given in the same argument, e.g. --out=file or
+                // -ofile.
+                Some(ArgValue::Flag)
+            } else {
+                None
+            }
+        });
+
+        match known {
+            Some(value) => {
+                local.push(arg.clone());
+                match value {
+                    ArgValue::Flag => (),
+                    ArgValue::Required => local.extend(args.next().cloned()),
+                    ArgValue::Optional => {
+                        local.extend(args.next_if(|arg| !arg.starts_with('-')).cloned())
+                    }
+                }
+            }
+            None => remote.push(arg.clone()),
+        }
+    }
+
+    (remote, local)
+}
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "agent",
+    about = "Run collections on behalf of remote clients.",
+    long_about = "Run collections on behalf of remote clients.
+
+Clients (`retis collect --remote`) connect to the agent, which starts a collection using the arguments they provide and streams its events back, until the client disconnects. Multiple clients can be served at the same time.
+
+Clients must provide the token read from --token-file (see `retis collect --remote-token-file`). Only collection arguments (collectors, probes, filters and their options) can be used; arguments accessing files or running commands on the host are refused. The token and events are not encrypted: only listen on trusted networks, or on a local address reached through a tunnel (e.g. ssh port forwarding)."
+)]
+pub(crate) struct Agent {
+    #[arg(
+        long,
+        value_name = "ADDR:PORT",
+        help = format!("Address and port to listen on, e.g. 127.0.0.1:{DEFAULT_AGENT_PORT} or [::]:{DEFAULT_AGENT_PORT}")
+    )]
+    listen: String,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "File holding the token clients must provide, e.g. generated using 'openssl rand -hex 32'. It should only be readable by its owner."
+    )]
+    token_file: PathBuf,
+}
+
+/// Pids of the running collections, stopped when the agent terminates.
+type Collections = Arc<Mutex<HashSet<u32>>>;
+
+impl SubCommandParserRunner for Agent {
+    fn run(&mut self, _: &MainConfig) -> Result<()> {
+        let token = Arc::new(read_token(&self.token_file)?);
+        let dir = private_dir("retis-agent")?;
+
+        let listener = TcpListener::bind(&self.listen)
+            .map_err(|e| anyhow!("Could not listen on {}: {e}", self.listen))?;
+        listener.set_nonblocking(true)?;
+        info!("Listening on {}", listener.local_addr()?);
+
+        let run = Running::new()?;
+        let collections: Collections = Arc::new(Mutex::new(HashSet::new()));
+        let mut id: u32 = 0;
+
+        while run.running() {
+            let (stream, peer) = match listener.accept() {
+                Ok(client) => client,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let (collections, token, dir) =
+                (Arc::clone(&collections), Arc::clone(&token), dir.clone());
+            id = id.wrapping_add(1);
+            thread::spawn(move || {
+                info!("{peer}: connected");
+                match handle_client(stream, id, &token, &dir, &collections) {
+                    Ok(()) => info!("{peer}: disconnected"),
+                    Err(e) => warn!("{peer}: {e}"),
+                }
+            });
+        }
+
+        // Stop the remaining collections.
+        collections
+            .lock()
+            .unwrap()
+            .iter()
+            .for_each(|pid| stop_collection(*pid));
+        let _ = fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
+
+/// Handle a single client: start a collection and stream its events back.
+/// Sockets used to communicate with the collection are created in `dir`.
+fn handle_client(
+    stream: TcpStream,
+    id: u32,
+    token: &str,
+    dir: &Path,
+    collections: &Collections,
+) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    stream.set_read_timeout(None)?;
+    writeln!(&stream, "clock {}", wall_clock()?)?;
+
+    let mut collection = match serde_json::from_str::<AgentRequest>(&line)
+        .map_err(|e| anyhow!("Invalid request: {e}"))
+        .and_then(|req| {
+            if !token_eq(&req.token, token) {
+                bail!("Authentication failed");
+            }
+            Collection::start(req, dir, id)
+        }) {
+        Ok(collection) => collection,
+        Err(e) => {
+            writeln!(&stream, "error {e}")?;
+            return Err(e);
+        }
+    };
+    collections.lock().unwrap().insert(collection.child.id());
+
+    let res = collection.stream(&stream);
+
+    collections.lock().unwrap().remove(&collection.child.id());
+    collection.stop();
+    res
+}
+
+/// Collection started on behalf of a client, running as a child process
+/// streaming its events to a unix socket.
+struct Collection {
+    child: Child,
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+impl Collection {
+    fn start(req: AgentRequest, dir: &Path, id: u32) -> Result<Self> {
+        check_remote_args(&req.args)?;
+
+        let path = dir.join(format!("collection-{id}.sock"));
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+
+        info!("Starting collection: {}", req.args.join(" "));
+        let child = Command::new(env::current_exe()?)
+            .arg("collect")
+            .args(&req.args)
+            .arg("--out")
+            .arg(format!("unix://{}", path.display()))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()?;
+
+        Ok(Self {
+            child,
+            path,
+            listener,
+        })
+    }
+
+    /// Wait for the collection to start, then stream its events to the
+    /// client until either side goes away.
+    fn stream(&mut self, mut client: &TcpStream) -> Result<()> {
+        let mut events = match self.wait_start() {
+            Ok(events) => events,
+            Err(e) => {
+                writeln!(client, "error {e}")?;
+                return Err(e);
+            }
+        };
+        writeln!(client, "ok")?;
+
+        let mut out = client.try_clone()?;
+        let copy = thread::spawn(move || {
+            let res = io::copy(&mut events, &mut out);
+            // Unblock the reading side below.
+            let _ = out.shutdown(Shutdown::Both);
+            res
+        });
+
+        // Clients do not send anything once the collection started; reading
+        // returns when they disconnect, or when the connection is shut down
+        // once the collection ended.
+        let _ = io::copy(&mut BufReader::new(client), &mut io::sink());
+        let _ = client.shutdown(Shutdown::Both);
+
+        self.stop();
+        match copy.join() {
+            Ok(Ok(_)) => Ok(()),
+            // The client went away while events were being sent.
+            Ok(Err(e)) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => bail!("Could not stream events"),
+        }
+    }
+
+    /// Wait for the collection to connect to its event socket.
+    fn wait_start(&mut self) -> Result<UnixStream> {
+        let start = Instant::now();
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    return Ok(stream);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => (),
+                Err(e) => return Err(e.into()),
+            }
+
+            if let Some(status) = self.child.try_wait()? {
+                bail!("Collection failed to start ({status})");
+            }
+            if start.elapsed() > COLLECT_START_TIMEOUT {
+                bail!("Collection did not start in time");
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Stop the collection, if still running, and wait for it to terminate.
+    fn stop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            stop_collection(self.child.id());
+        }
+        if let Err(e) = self.child.wait() {
+            warn!("Could not wait for the collection to terminate: {e}");
+        }
+    }
+}
+
+impl Drop for Collection {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Stop a collection, the same way it is stopped interactively.
+pub(crate) fn stop_collection(pid: u32) {
+    // Safety: sending a signal has no memory safety implication.
+    unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) };
+}
+
+/// Create a directory only accessible by the current user, with a unique name
+/// starting with `prefix`, in the temporary directory.
+pub(crate) fn private_dir(prefix: &str) -> Result<PathBuf> {
+    let template = env::temp_dir().join(format!("{prefix}-XXXXXX"));
+    let template = CString::new(template.into_os_string().into_vec())?;
+    let ptr = template.into_raw();
+
+    // Safety: the template is a valid, nul-terminated, string which mkdtemp
+    // modifies in place; it is given back to a CString below.
+    let res = unsafe { libc::mkdtemp(ptr) };
+    let path = unsafe { CString::from_raw(ptr) };
+    if res.is_null() {
+        bail!(
+            "Could not create a directory in {}: {}",
+            env::temp_dir().display(),
+            io::Error::last_os_error()
+        );
+    }
+
+    Ok(PathBuf::from(OsString::from_vec(path.into_bytes())))
+}
+
+/// Wall clock time, in nanoseconds since the epoch.
+pub(crate) fn wall_clock() -> Result<i64> {
+    Ok(i64::try_from(
+        SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos(),
+    )?)
+}
+
+/// Parse a remote agent address, given as HOST[:PORT]. IPv6 addresses must be
+/// enclosed in brackets when a port is given.
+pub(crate) fn agent_addr(host: &str) -> Result<String> {
+    if host.is_empty() {
+        bail!("Empty remote host");
+    }
+
+    let port = match host.rsplit_once(':') {
+        // IPv6 address without port.
+        Some((addr, _)) if addr.contains(':') && !addr.ends_with(']') => None,
+        Some((_, port)) => Some(
+            port.parse::<u16>()
+                .map_err(|_| anyhow!("Invalid port in remote host {host}"))?,
+        ),
+        None => None,
+    };
+
+    Ok(match port {
+        Some(_) => host.to_string(),
+        None if host.contains(':') && !host.starts_with('[') => {
+            format!("[{host}]:{DEFAULT_AGENT_PORT}")
+        }
+        None => format!("{host}:{DEFAULT_AGENT_PORT}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn local_args() {
+        let (remote, local) = split_local_args(&args(
+            "-c skb,ct --remote host1,host2 -o retis.data --print -f tcp --out-format=json -ofoo --stack",
+        ));
+        assert_eq!(remote, args("-c skb,ct -f tcp --stack"));
+        assert_eq!(
+            local,
+            args("--remote host1,host2 -o retis.data --print --out-format=json -ofoo")
+        );
+
+        // Optional values are not taken from the next argument if it is an
+        // option.
+        let (remote, local) =
+            split_local_args(&args("--remote n1 -o -c skb --out --stack --ctl-socket"));
+        assert_eq!(remote, args("-c skb --stack"));
+        assert_eq!(local, args("--remote n1 -o --out --ctl-socket"));
+
+        let (remote, local) = split_local_args(&args("--probe-stack --outx"));
+        assert_eq!(remote, args("--probe-stack --outx"));
+        assert!(local.is_empty());
+    }
+
+    #[test]
+    fn remote_args() {
+        assert!(check_remote_args(&args(
+            "-c skb,skb-drop -p tp:skb:kfree_skb -f tcp --stack --netns 4026531840 --skb-sections all"
+        ))
+        .is_ok());
+
+        let refused = |s: &str| check_remote_args(&args(s)).unwrap_err().to_string();
+        assert_eq!(
+            refused("-c skb --cmd id --external-collector /tmp/x --config c.yaml"),
+            "Arguments not supported remotely: --config --cmd --external-collector"
+        );
+        // Combined short options.
+        assert_eq!(
+            refused("-eo/etc/foo"),
+            "Arguments not supported remotely: -e --out"
+        );
+        assert_eq!(
+            refused("--netns /run/netns/blue --kconf /etc/shadow"),
+            "Arguments not supported remotely: --netns --kconf"
+        );
+        assert!(refused("--foo").starts_with("Invalid collect arguments: "));
+        assert!(!refused("--foo").contains('\n'));
+
+        // All allowed arguments exist.
+        let command = Collect::new().unwrap().command().unwrap();
+        REMOTE_ARGS.iter().for_each(|id| {
+            assert!(
+                command.get_arguments().any(|arg| arg.get_id() == id),
+                "Unknown argument {id}"
+            )
+        });
+    }
+
+    #[test]
+    fn tokens() {
+        assert!(token_eq("secret", "secret"));
+        assert!(!token_eq("secret", "secreT"));
+        assert!(!token_eq("secret", "secret2"));
+        assert!(!token_eq("secret", ""));
+    }
+
+    #[test]
+    fn agent_addrs() {
+        assert_eq!(agent_addr("host1").unwrap(), "host1:9080");
+        assert_eq!(agent_addr("host1:1234").unwrap(), "host1:1234");
+        assert_eq!(agent_addr("10.0.0.1").unwrap(), "10.0.0.1:9080");
+        assert_eq!(agent_addr("fd00::1").unwrap(), "[fd00::1]:9080");
+        assert_eq!(agent_addr("[fd00::1]:1234").unwrap(), "[fd00::1]:1234");
+        assert_eq!(agent_addr("[fd00::1]").unwrap(), "[fd00::1]:9080");
+        assert!(agent_addr("host1:foo").is_err());
+        assert!(agent_addr("").is_err());
+    }
+
+    #[test]
+    fn request() {
+        let req = AgentRequest {
+            token: "secret".to_string(),
+            args: args("-c skb -f tcp"),
+        };
+        let line = serde_json::to_string(&req).unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(serde_json::from_str::<AgentRequest>(&line).unwrap(), req);
+    }
+}
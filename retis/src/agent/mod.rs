@@ -0,0 +1,9 @@
+//! # Agent
+//!
+//! Runs collections on behalf of remote clients (`retis collect --remote`)
+//! and streams their events back.
+
+// Re-export agent.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod agent;
+pub(crate) use agent::*;
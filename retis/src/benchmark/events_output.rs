@@ -3,7 +3,7 @@ use std::{fs::OpenOptions, time::Instant};
 use anyhow::{bail, Result};
 
 use crate::{
-    events::{file::FileEventsFactory, *},
+    events::{file::FileEventsFactory, sink::EventSink, *},
     process::{display::*, series::EventSorter, tracking::AddTracking},
 };
 
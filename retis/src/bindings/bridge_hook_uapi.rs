@@ -0,0 +1,40 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub const IFNAMSIZ: enum_IFNAMSIZ = 16;
+pub type enum_IFNAMSIZ = ::std::os::raw::c_uint;
+#[doc = " Bridge hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct bridge_offsets {
+    pub port: s8,
+    pub bridge: s8,
+    pub pkt_type: s8,
+    pub state: s8,
+    pub addr: s8,
+    pub vid: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct bridge_config {
+    pub offsets: bridge_offsets,
+    pub type_: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct bridge_event {
+    pub type_: u8_,
+    pub has_port: u8_,
+    pub state: u8_,
+    pub pkt_type: u8_,
+    pub port_no: u16_,
+    pub vid: u16_,
+    pub addr: [u8_; 6usize],
+    pub port: [::std::os::raw::c_char; 16usize],
+    pub bridge: [::std::os::raw::c_char; 16usize],
+}
@@ -4,8 +4,12 @@ pub type __u64 = ::std::os::raw::c_ulonglong;
 pub type u64_ = __u64;
 pub type __u8 = ::std::os::raw::c_uchar;
 pub type __s8 = ::std::os::raw::c_schar;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type __u32 = ::std::os::raw::c_uint;
 pub type s8 = __s8;
 pub type u8_ = __u8;
+pub type u16_ = __u16;
+pub type u32_ = __u32;
 #[doc = " Per-probe parameter offsets. A value of -1 means the argument isn't\n available. Please try to reuse the targeted object names.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -16,17 +20,51 @@ pub struct retis_probe_offsets {
     pub net: s8,
     pub nft_pktinfo: s8,
     pub nft_traceinfo: s8,
+    pub sock: s8,
+    pub napi_struct: s8,
+    pub Qdisc: s8,
+    pub sk_buff_ref: s8,
 }
+pub const KERNEL_SECTION_CORE: enum_KERNEL_SECTION_CORE = 0;
+pub type enum_KERNEL_SECTION_CORE = ::std::os::raw::c_uint;
+pub const KERNEL_SECTION_ARGS: enum_KERNEL_SECTION_ARGS = 1;
+pub type enum_KERNEL_SECTION_ARGS = ::std::os::raw::c_uint;
+pub const KERNEL_SECTION_USER_STACK: enum_KERNEL_SECTION_USER_STACK = 2;
+pub type enum_KERNEL_SECTION_USER_STACK = ::std::os::raw::c_uint;
+pub const KERNEL_ARGS_MAX: enum_KERNEL_ARGS_MAX = 12;
+pub type enum_KERNEL_ARGS_MAX = ::std::os::raw::c_uint;
+pub const KERNEL_ARG_DATA_SIZE: enum_KERNEL_ARG_DATA_SIZE = 32;
+pub type enum_KERNEL_ARG_DATA_SIZE = ::std::os::raw::c_uint;
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
 pub struct kernel_event {
     pub symbol: u64_,
     pub stack_id: ::std::os::raw::c_long,
+    pub ret: u64_,
     pub type_: u8_,
 }
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
+pub struct kernel_args_event {
+    pub regs: [u64_; 12usize],
+    pub valid: u16_,
+    pub num: u8_,
+    pub data: [[u8_; 32usize]; 12usize],
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct kernel_user_stack_event {
+    pub stack_id: ::std::os::raw::c_long,
+    pub pid: u32_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
 pub struct retis_probe_config {
     pub offsets: retis_probe_offsets,
     pub stack_trace: u8_,
+    pub user_stack_trace: u8_,
+    pub args: u8_,
+    pub args_str: u16_,
+    pub args_deref: u16_,
+    pub sk_buff_ref_offset: u16_,
 }
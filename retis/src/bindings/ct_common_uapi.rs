@@ -11,6 +11,7 @@ pub type u8_ = __u8;
 pub const SECTION_META: ct_sections = 0;
 pub const SECTION_BASE_CONN: ct_sections = 1;
 pub const SECTION_PARENT_CONN: ct_sections = 2;
+pub const SECTION_LIFECYCLE: ct_sections = 3;
 pub type ct_sections = ::std::os::raw::c_uint;
 pub const RETIS_CT_DIR_ORIG: ct_flags = 1;
 pub const RETIS_CT_DIR_REPLY: ct_flags = 2;
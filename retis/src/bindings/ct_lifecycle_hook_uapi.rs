@@ -0,0 +1,19 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub const CT_LIFECYCLE_CREATE: ct_lifecycle_type = 0;
+pub const CT_LIFECYCLE_CONFIRM: ct_lifecycle_type = 1;
+pub const CT_LIFECYCLE_TIMEOUT: ct_lifecycle_type = 2;
+pub const CT_LIFECYCLE_DESTROY: ct_lifecycle_type = 3;
+pub type ct_lifecycle_type = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ct_lifecycle_config {
+    pub type_: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ct_lifecycle_event {
+    pub type_: u8_,
+}
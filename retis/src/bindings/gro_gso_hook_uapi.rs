@@ -0,0 +1,33 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
+#[doc = " Gro/gso hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct gro_gso_offsets {
+    pub merged: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct gro_gso_config {
+    pub offsets: gro_gso_offsets,
+    pub type_: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct gro_gso_event {
+    pub type_: u8_,
+    pub segs: u32_,
+    pub err: s32,
+    pub merged: u64_,
+    pub segments: [u64_; 8usize],
+}
@@ -0,0 +1,38 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+#[doc = " ICMP error hook configuration. Each probe has its own instance, as the data\n to retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct icmp_error_offsets {
+    pub type_: s8,
+    pub code: s8,
+    pub info: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct icmp_error_config {
+    pub offsets: icmp_error_offsets,
+    pub family: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct icmp_error_event {
+    pub info: u32_,
+    pub family: u16_,
+    pub sport: u16_,
+    pub dport: u16_,
+    pub src: [u8_; 16usize],
+    pub dst: [u8_; 16usize],
+    pub protocol: u8_,
+    pub has_ports: u8_,
+    pub type_: u8_,
+    pub code: u8_,
+}
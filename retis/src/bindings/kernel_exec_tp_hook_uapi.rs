@@ -1,6 +1,8 @@
 /* automatically generated by rust-bindgen 0.72.1 */
 
 pub type __u16 = ::std::os::raw::c_ushort;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
 pub type __u8 = ::std::os::raw::c_uchar;
 pub type u16_ = __u16;
 pub type __u32 = ::std::os::raw::c_uint;
@@ -33,6 +35,24 @@ pub struct exec_drop {
     pub reason: u32_,
 }
 #[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_push_vlan {
+    pub tpid: u16_,
+    pub tci: u16_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct exec_set_tunnel {
+    pub id: u64_,
+    pub src: [u8_; 16usize],
+    pub dst: [u8_; 16usize],
+    pub sport: u16_,
+    pub dport: u16_,
+    pub tos: u8_,
+    pub ttl: u8_,
+    pub mode: u8_,
+}
+#[repr(C)]
 #[derive(Copy, Clone)]
 pub union exec_ip {
     pub addr4: u32_,
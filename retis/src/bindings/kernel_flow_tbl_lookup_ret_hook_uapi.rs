@@ -10,6 +10,8 @@ pub struct flow_lookup_ret_event {
     pub ufid: [u32_; 4usize],
     pub n_mask_hit: u32_,
     pub n_cache_hit: u32_,
+    pub n_masks: u32_,
+    pub n_flows: u32_,
 }
 impl Default for flow_lookup_ret_event {
     fn default() -> Self {
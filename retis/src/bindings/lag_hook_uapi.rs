@@ -0,0 +1,35 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub const IFNAMSIZ: enum_IFNAMSIZ = 16;
+pub type enum_IFNAMSIZ = ::std::os::raw::c_uint;
+#[doc = " Lag hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct lag_offsets {
+    pub bond: s8,
+    pub slave: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct lag_config {
+    pub offsets: lag_offsets,
+    pub type_: u8_,
+    pub iff_bonding: u32_,
+    pub iff_team_port: u32_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct lag_event {
+    pub type_: u8_,
+    pub kind: u8_,
+    pub mode: s8,
+    pub master: [::std::os::raw::c_char; 16usize],
+    pub port: [::std::os::raw::c_char; 16usize],
+    pub old_port: [::std::os::raw::c_char; 16usize],
+}
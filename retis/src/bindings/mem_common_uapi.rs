@@ -0,0 +1,5 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub const MEM_SECTION_SKB: mem_sections = 1;
+pub const MEM_SECTION_PAGE_POOL: mem_sections = 2;
+pub type mem_sections = ::std::os::raw::c_uint;
@@ -0,0 +1,44 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub const IFNAMSIZ: enum_IFNAMSIZ = 16;
+pub type enum_IFNAMSIZ = ::std::os::raw::c_uint;
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
+#[doc = " Page pool hook configuration. Each probe has its own instance, as the data\n to retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct mem_page_pool_offsets {
+    pub page_pool: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct mem_page_pool_config {
+    pub offsets: mem_page_pool_offsets,
+    pub interval: u64_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct mem_page_pool_event {
+    pub pool: u64_,
+    pub alloc_fast: u64_,
+    pub alloc_slow: u64_,
+    pub alloc_slow_high_order: u64_,
+    pub alloc_empty: u64_,
+    pub alloc_refill: u64_,
+    pub alloc_waive: u64_,
+    pub inflight: s32,
+    pub cached: u32_,
+    pub ring_producer: s32,
+    pub ring_consumer: s32,
+    pub ring_size: s32,
+    pub has_stats: u8_,
+    pub dev: [::std::os::raw::c_char; 16usize],
+}
@@ -0,0 +1,19 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct mem_skb_event {
+    pub truesize: u32_,
+    pub rmem_alloc: s32,
+    pub rcvbuf: s32,
+    pub wmem_alloc: s32,
+    pub sndbuf: s32,
+    pub drops: u32_,
+    pub has_sock: u8_,
+}
@@ -21,17 +21,23 @@ impl Default for retis_probe_offsets {
             net: -1,
             nft_pktinfo: -1,
             nft_traceinfo: -1,
+            sock: -1,
+            napi_struct: -1,
+            Qdisc: -1,
+            sk_buff_ref: -1,
         }
     }
 }
 
 unsafe impl plain::Plain for retis_probe_config {}
 
-pub(crate) mod ct_hook_uapi;
-use ct_hook_uapi::ct_event;
+pub(crate) mod ct_common_uapi;
+use ct_common_uapi::ct_event;
 
 unsafe impl plain::Plain for ct_event {}
 
+pub(crate) mod ct_lifecycle_hook_uapi;
+
 pub(crate) mod nft_hook_uapi;
 use nft_hook_uapi::nft_offsets;
 
@@ -46,6 +52,15 @@ impl Default for nft_offsets {
     }
 }
 
+pub(crate) mod nf_hook_uapi;
+use nf_hook_uapi::nf_offsets;
+
+impl Default for nf_offsets {
+    fn default() -> Self {
+        Self { nf_hook_state: -1 }
+    }
+}
+
 pub(crate) mod skb_drop_hook_uapi;
 
 pub(crate) mod skb_tracking_uapi;
@@ -54,6 +69,7 @@ use skb_tracking_uapi::*;
 unsafe impl plain::Plain for tracking_config {}
 unsafe impl plain::Plain for tracking_info {}
 
+pub(crate) mod relation_hook_uapi;
 pub(crate) mod tracking_hook_uapi;
 
 pub(crate) mod if_vlan_uapi;
@@ -66,12 +82,281 @@ pub(crate) mod kernel_upcall_tp_hook_uapi;
 
 pub(crate) mod kernel_flow_tbl_lookup_ret_hook_uapi;
 pub(crate) mod ovs_common_uapi;
+pub(crate) mod ovs_flow_uapi;
 pub(crate) mod ovs_operation_uapi;
 pub(crate) mod user_recv_upcall_hook_uapi;
 
 pub(crate) mod dev_hook_uapi;
 pub(crate) mod netns_hook_uapi;
 
+pub(crate) mod tc_hook_uapi;
+use tc_hook_uapi::{tc_config, tc_offsets};
+
+unsafe impl plain::Plain for tc_config {}
+
+impl Default for tc_offsets {
+    fn default() -> Self {
+        Self { packets: -1 }
+    }
+}
+
+pub(crate) mod xfrm_hook_uapi;
+use xfrm_hook_uapi::{xfrm_config, xfrm_offsets};
+
+unsafe impl plain::Plain for xfrm_config {}
+
+impl Default for xfrm_offsets {
+    fn default() -> Self {
+        Self {
+            spi: -1,
+            int_arg: -1,
+        }
+    }
+}
+
+pub(crate) mod tunnel_hook_uapi;
+
+pub(crate) mod tcp_hook_uapi;
+use tcp_hook_uapi::{tcp_config, tcp_offsets};
+
+unsafe impl plain::Plain for tcp_config {}
+
+impl Default for tcp_offsets {
+    fn default() -> Self {
+        Self { state: -1 }
+    }
+}
+
+pub(crate) mod gro_gso_hook_uapi;
+use gro_gso_hook_uapi::{gro_gso_config, gro_gso_offsets};
+
+unsafe impl plain::Plain for gro_gso_config {}
+
+impl Default for gro_gso_offsets {
+    fn default() -> Self {
+        Self { merged: -1 }
+    }
+}
+
+pub(crate) mod bridge_hook_uapi;
+use bridge_hook_uapi::{bridge_config, bridge_offsets};
+
+unsafe impl plain::Plain for bridge_config {}
+
+impl Default for bridge_offsets {
+    fn default() -> Self {
+        Self {
+            port: -1,
+            bridge: -1,
+            pkt_type: -1,
+            state: -1,
+            addr: -1,
+            vid: -1,
+        }
+    }
+}
+
+pub(crate) mod lag_hook_uapi;
+use lag_hook_uapi::{lag_config, lag_offsets};
+
+unsafe impl plain::Plain for lag_config {}
+
+impl Default for lag_offsets {
+    fn default() -> Self {
+        Self {
+            bond: -1,
+            slave: -1,
+        }
+    }
+}
+
+pub(crate) mod wireguard_hook_uapi;
+use wireguard_hook_uapi::{wireguard_config, wireguard_offsets};
+
+unsafe impl plain::Plain for wireguard_config {}
+
+impl Default for wireguard_offsets {
+    fn default() -> Self {
+        Self { peer: -1 }
+    }
+}
+
+pub(crate) mod icmp_error_hook_uapi;
+use icmp_error_hook_uapi::{icmp_error_config, icmp_error_offsets};
+
+unsafe impl plain::Plain for icmp_error_config {}
+
+impl Default for icmp_error_offsets {
+    fn default() -> Self {
+        Self {
+            type_: -1,
+            code: -1,
+            info: -1,
+        }
+    }
+}
+
+pub(crate) mod ns_lifecycle_hook_uapi;
+use ns_lifecycle_hook_uapi::ns_lifecycle_config;
+
+unsafe impl plain::Plain for ns_lifecycle_config {}
+
+pub(crate) mod sock_common_uapi;
+pub(crate) mod sock_hook_uapi;
+pub(crate) mod sock_owner_hook_uapi;
+use sock_owner_hook_uapi::sock_owner_offsets;
+
+impl Default for sock_owner_offsets {
+    fn default() -> Self {
+        Self { socket: -1 }
+    }
+}
+
+pub(crate) mod mptcp_hook_uapi;
+use mptcp_hook_uapi::{mptcp_config, mptcp_offsets};
+
+unsafe impl plain::Plain for mptcp_config {}
+
+impl Default for mptcp_offsets {
+    fn default() -> Self {
+        Self {
+            ssk: -1,
+            msk: -1,
+            subflow: -1,
+            addr: -1,
+            rm_list: -1,
+        }
+    }
+}
+
+pub(crate) mod offload_common_uapi;
+pub(crate) mod offload_filter_hook_uapi;
+pub(crate) mod offload_skb_hook_uapi;
+use offload_filter_hook_uapi::{offload_filter_config, offload_filter_offsets};
+
+unsafe impl plain::Plain for offload_filter_config {}
+
+impl Default for offload_filter_offsets {
+    fn default() -> Self {
+        Self {
+            tcf_block: -1,
+            setup_type: -1,
+            type_data: -1,
+        }
+    }
+}
+
+impl Default for offload_filter_config {
+    fn default() -> Self {
+        Self {
+            offsets: offload_filter_offsets::default(),
+            // -1 means flower filters can't be identified.
+            clsflower: -1,
+            op: 0,
+        }
+    }
+}
+
+pub(crate) mod mem_common_uapi;
+pub(crate) mod mem_page_pool_hook_uapi;
+pub(crate) mod mem_skb_hook_uapi;
+use mem_page_pool_hook_uapi::{mem_page_pool_config, mem_page_pool_offsets};
+
+unsafe impl plain::Plain for mem_page_pool_config {}
+
+impl Default for mem_page_pool_offsets {
+    fn default() -> Self {
+        Self { page_pool: -1 }
+    }
+}
+
+pub(crate) mod xdp_hook_uapi;
+use xdp_hook_uapi::{xdp_config, xdp_offsets};
+
+unsafe impl plain::Plain for xdp_config {}
+
+impl Default for xdp_offsets {
+    fn default() -> Self {
+        Self {
+            bpf_prog: -1,
+            act: -1,
+            err: -1,
+        }
+    }
+}
+
+impl Default for xdp_config {
+    fn default() -> Self {
+        Self {
+            offsets: xdp_offsets::default(),
+            act_ret: 0,
+            // -1 means no fixed action is reported.
+            act: -1,
+        }
+    }
+}
+
+pub(crate) mod xsk_hook_uapi;
+use xsk_hook_uapi::{xsk_config, xsk_offsets};
+
+unsafe impl plain::Plain for xsk_config {}
+
+impl Default for xsk_offsets {
+    fn default() -> Self {
+        Self {
+            xdp_sock: -1,
+            sock: -1,
+            xdp_buff: -1,
+            xsk_buff_pool: -1,
+            descs: -1,
+        }
+    }
+}
+
+pub(crate) mod route_common_uapi;
+pub(crate) mod route_hook_uapi;
+pub(crate) mod route_stash_hook_uapi;
+use route_hook_uapi::{route_config, route_offsets};
+use route_stash_hook_uapi::{route_stash_config, route_stash_offsets};
+
+unsafe impl plain::Plain for route_config {}
+unsafe impl plain::Plain for route_stash_config {}
+
+impl Default for route_offsets {
+    fn default() -> Self {
+        Self {
+            tb_id: -1,
+            flowi4: -1,
+            nhc: -1,
+            err: -1,
+            fib6_result: -1,
+            fib6_table: -1,
+            flowi6: -1,
+        }
+    }
+}
+
+impl Default for route_stash_offsets {
+    fn default() -> Self {
+        Self { fib_result: -1 }
+    }
+}
+
+pub(crate) mod neigh_hook_uapi;
+use neigh_hook_uapi::{neigh_config, neigh_offsets};
+
+unsafe impl plain::Plain for neigh_config {}
+
+impl Default for neigh_offsets {
+    fn default() -> Self {
+        Self {
+            neigh: -1,
+            lladdr: -1,
+            new_state: -1,
+        }
+    }
+}
+
 pub(crate) mod events_uapi;
 use events_uapi::retis_log_event;
 
@@ -0,0 +1,54 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub const MPTCP_RM_IDS_MAX: enum_MPTCP_RM_IDS_MAX = 8;
+pub type enum_MPTCP_RM_IDS_MAX = ::std::os::raw::c_uint;
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __s16 = ::std::os::raw::c_short;
+pub type s16 = __s16;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+#[doc = " MPTCP hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct mptcp_offsets {
+    pub ssk: s8,
+    pub msk: s8,
+    pub subflow: s8,
+    pub addr: s8,
+    pub rm_list: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct mptcp_config {
+    pub offsets: mptcp_offsets,
+    pub op: u8_,
+    pub ssk_ret: u8_,
+    pub bool_ret: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct mptcp_event {
+    pub token: u32_,
+    pub saddr: [u8_; 16usize],
+    pub daddr: [u8_; 16usize],
+    pub sport: u16_,
+    pub dport: u16_,
+    pub family: u16_,
+    pub local_id: s16,
+    pub remote_id: u8_,
+    pub has_subflow: u8_,
+    pub addr: [u8_; 16usize],
+    pub addr_port: u16_,
+    pub addr_family: u16_,
+    pub addr_id: u8_,
+    pub has_addr: u8_,
+    pub rm_ids: [u8_; 8usize],
+    pub rm_nr: u8_,
+    pub op: u8_,
+    pub failed: u8_,
+}
@@ -0,0 +1,40 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub const IFNAMSIZ: enum_IFNAMSIZ = 16;
+pub type enum_IFNAMSIZ = ::std::os::raw::c_uint;
+pub const NEIGH_LLADDR_MAX: enum_NEIGH_LLADDR_MAX = 6;
+pub type enum_NEIGH_LLADDR_MAX = ::std::os::raw::c_uint;
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+#[doc = " Neighbor hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct neigh_offsets {
+    pub neigh: s8,
+    pub lladdr: s8,
+    pub new_state: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct neigh_config {
+    pub offsets: neigh_offsets,
+    pub op: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct neigh_event {
+    pub addr: [u8_; 16usize],
+    pub family: u8_,
+    pub op: u8_,
+    pub state: u8_,
+    pub new_state: u8_,
+    pub lladdr: [u8_; 6usize],
+    pub has_lladdr: u8_,
+    pub probes: u32_,
+    pub queue_len: u32_,
+    pub dev: [::std::os::raw::c_char; 16usize],
+}
@@ -0,0 +1,27 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+#[doc = " Netfilter hook configuration.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct nf_offsets {
+    pub nf_hook_state: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct nf_config {
+    pub offsets: nf_offsets,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct nf_event {
+    pub hook: u8_,
+    pub pf: u8_,
+    pub exit: u8_,
+    pub ret: s32,
+}
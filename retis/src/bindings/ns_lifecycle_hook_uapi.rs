@@ -0,0 +1,27 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
+pub const IFNAMSIZ: enum_IFNAMSIZ = 16;
+pub type enum_IFNAMSIZ = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ns_lifecycle_config {
+    pub type_: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ns_lifecycle_event {
+    pub cookie: u64_,
+    pub target_cookie: u64_,
+    pub inum: u32_,
+    pub target_inum: u32_,
+    pub ifindex: u32_,
+    pub type_: u8_,
+    pub has_cookie: u8_,
+    pub dev: [::std::os::raw::c_char; 16usize],
+}
@@ -0,0 +1,5 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub const OFFLOAD_SECTION_FILTER: offload_sections = 1;
+pub const OFFLOAD_SECTION_SKB: offload_sections = 2;
+pub type offload_sections = ::std::os::raw::c_uint;
@@ -0,0 +1,59 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub const IFNAMSIZ: enum_IFNAMSIZ = 16;
+pub type enum_IFNAMSIZ = ::std::os::raw::c_uint;
+pub const OFFLOAD_MSG_MAX: enum_OFFLOAD_MSG_MAX = 128;
+pub type enum_OFFLOAD_MSG_MAX = ::std::os::raw::c_uint;
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type __s64 = ::std::os::raw::c_longlong;
+pub type s64 = __s64;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
+#[doc = " Offload filter hook configuration. Each probe has its own instance, as the\n data to retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct offload_filter_offsets {
+    pub tcf_block: s8,
+    pub setup_type: s8,
+    pub type_data: s8,
+}
+#[doc = " Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct offload_filter_config {
+    pub offsets: offload_filter_offsets,
+    pub clsflower: s64,
+    pub op: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct offload_filter_event {
+    pub cookie: u64_,
+    pub ret: s32,
+    pub setup_type: u32_,
+    pub block: u32_,
+    pub chain: u32_,
+    pub prio: u32_,
+    pub protocol: u16_,
+    pub op: u8_,
+    pub dev: [::std::os::raw::c_char; 16usize],
+    pub msg: [::std::os::raw::c_char; 128usize],
+}
+impl Default for offload_filter_event {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
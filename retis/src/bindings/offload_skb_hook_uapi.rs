@@ -0,0 +1,10 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct offload_skb_event {
+    pub fwd_mark: u8_,
+    pub l3_fwd_mark: u8_,
+}
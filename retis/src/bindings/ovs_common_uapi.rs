@@ -15,6 +15,7 @@ pub struct upcall_context {
 #[derive(Debug, Copy, Clone)]
 pub struct execute_actions_ctx {
     pub skb: *mut ::std::os::raw::c_void,
+    pub tbl: *mut ::std::os::raw::c_void,
     pub n_mask_hit: *mut u32_,
     pub n_cache_hit: *mut u32_,
     pub queue_id: u32_,
@@ -29,3 +30,14 @@ impl Default for execute_actions_ctx {
         }
     }
 }
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct flow_put_key {
+    pub ufid: [u32_; 4usize],
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct flow_put_info {
+    pub ts: u64_,
+    pub queue_id: u32_,
+}
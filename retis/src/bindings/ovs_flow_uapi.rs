@@ -0,0 +1,29 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type bool_ = bool;
+pub type u8_ = __u8;
+pub const OVS_FLOW_INSTALL: ovs_flow_update_type = 0;
+pub const OVS_FLOW_REMOVE: ovs_flow_update_type = 1;
+pub type ovs_flow_update_type = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct flow_update_event {
+    pub flow: *mut ::std::os::raw::c_void,
+    pub sf_acts: *mut ::std::os::raw::c_void,
+    pub ufid: [u32_; 4usize],
+    pub queue_id: u32_,
+    pub has_queue_id: bool_,
+    pub type_: u8_,
+}
+impl Default for flow_update_event {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
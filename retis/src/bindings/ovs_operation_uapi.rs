@@ -11,6 +11,7 @@ pub type u8_ = __u8;
 #[derive(Debug, Default, Copy, Clone)]
 pub struct ovs_operation_event {
     pub batch_ts: u64_,
+    pub latency: u64_,
     pub queue_id: u32_,
     pub batch_idx: u8_,
     pub type_: u8_,
@@ -18,6 +19,7 @@ pub struct ovs_operation_event {
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
 pub struct user_upcall_info {
+    pub enqueue_ts: u64_,
     pub queue_id: u32_,
     pub skip_event: bool_,
     pub processed_ops: u8_,
@@ -40,3 +42,5 @@ impl Default for upcall_batch {
         }
     }
 }
+pub const UPCALL_LATENCY_BUCKETS: enum_UPCALL_LATENCY_BUCKETS = 65;
+pub type enum_UPCALL_LATENCY_BUCKETS = ::std::os::raw::c_uint;
@@ -0,0 +1,27 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u8_ = __u8;
+pub type u64_ = __u64;
+pub const SECTION_RELATION: enum_SECTION_RELATION = 2;
+pub type enum_SECTION_RELATION = ::std::os::raw::c_uint;
+pub const SKB_RELATION_CLONE: skb_relation_kind = 0;
+pub const SKB_RELATION_COPY: skb_relation_kind = 1;
+pub const SKB_RELATION_EXPAND: skb_relation_kind = 2;
+pub const SKB_RELATION_ENCAP: skb_relation_kind = 3;
+pub const SKB_RELATION_DECAP: skb_relation_kind = 4;
+pub type skb_relation_kind = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct skb_relation_config {
+    pub kind: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct skb_relation_event {
+    pub orig_head: u64_,
+    pub timestamp: u64_,
+    pub skb: u64_,
+    pub kind: u8_,
+}
@@ -0,0 +1,11 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
+#[doc = " Context kept while a route lookup is done on behalf of a packet, as the FIB\n lookup tracepoints do not have access to it.\n\n skb:        packet the route is looked up for.\n fib_result: IPv4 lookup result, set while in fib_table_lookup()."]
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct route_inflight {
+    pub skb: u64_,
+    pub fib_result: u64_,
+}
@@ -0,0 +1,48 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub const IFNAMSIZ: enum_IFNAMSIZ = 16;
+pub type enum_IFNAMSIZ = ::std::os::raw::c_uint;
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+#[doc = " Route hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct route_offsets {
+    pub tb_id: s8,
+    pub flowi4: s8,
+    pub nhc: s8,
+    pub err: s8,
+    pub fib6_result: s8,
+    pub fib6_table: s8,
+    pub flowi6: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct route_config {
+    pub offsets: route_offsets,
+    pub family: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct route_event {
+    pub table: u32_,
+    pub err: s32,
+    pub saddr: [u8_; 16usize],
+    pub daddr: [u8_; 16usize],
+    pub prefix: [u8_; 16usize],
+    pub gw: [u8_; 16usize],
+    pub ifindex: u32_,
+    pub dev: [::std::os::raw::c_char; 16usize],
+    pub family: u8_,
+    pub gw_family: u8_,
+    pub prefixlen: u8_,
+    pub has_prefix: u8_,
+    pub has_err: u8_,
+    pub type_: u8_,
+}
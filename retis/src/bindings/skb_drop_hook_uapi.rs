@@ -1,5 +1,12 @@
 /* automatically generated by rust-bindgen 0.72.1 */
 
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct skb_drop_config {
+    pub filter_reasons: u8_,
+}
 pub type __s32 = ::std::os::raw::c_int;
 pub type s32 = __s32;
 #[repr(C)]
@@ -11,11 +11,15 @@ pub const SECTION_VLAN: skb_sections = 2;
 pub const SECTION_META: skb_sections = 3;
 pub const SECTION_DATA_REF: skb_sections = 4;
 pub const SECTION_GSO: skb_sections = 5;
+pub const SECTION_CSUM: skb_sections = 6;
 pub type skb_sections = ::std::os::raw::c_uint;
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
 pub struct skb_config {
     pub sections: u64_,
+    pub snaplen: u32_,
+    pub page_offset_base: u64_,
+    pub vmemmap_base: u64_,
 }
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
@@ -47,11 +51,21 @@ pub struct skb_gso_event {
     pub gso_type: u32_,
 }
 #[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct skb_csum_event {
+    pub csum: u32_,
+    pub ip_summed: u8_,
+    pub csum_level: u8_,
+    pub csum_valid: u8_,
+}
+pub const PACKET_CAPTURE_DEFAULT: enum_PACKET_CAPTURE_DEFAULT = 255;
+pub type enum_PACKET_CAPTURE_DEFAULT = ::std::os::raw::c_uint;
+#[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct skb_packet_event {
     pub len: u32_,
     pub capture_len: u32_,
-    pub packet: [u8_; 255usize],
+    pub packet: [u8_; 1024usize],
 }
 impl Default for skb_packet_event {
     fn default() -> Self {
@@ -0,0 +1,11 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct sock_owner {
+    pub pid: u32_,
+    pub tgid: u32_,
+    pub comm: [::std::os::raw::c_char; 16usize],
+}
@@ -0,0 +1,30 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct sock_owner {
+    pub pid: u32_,
+    pub tgid: u32_,
+    pub comm: [::std::os::raw::c_char; 16usize],
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct sock_event {
+    pub cookie: u64_,
+    pub cgroup_id: u64_,
+    pub uid: u32_,
+    pub family: u16_,
+    pub type_: u16_,
+    pub protocol: u8_,
+    pub full: u8_,
+    pub has_owner: u8_,
+    pub owner: sock_owner,
+}
@@ -0,0 +1,18 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+#[doc = " Sock owner hook configuration. Each probe has its own instance, as the data\n to retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct sock_owner_offsets {
+    pub socket: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct sock_owner_config {
+    pub offsets: sock_owner_offsets,
+    pub type_: u8_,
+}
@@ -0,0 +1,35 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+#[doc = " Tc hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct tc_offsets {
+    pub packets: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct tc_config {
+    pub offsets: tc_offsets,
+    pub classify: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct tc_event {
+    pub kind: [::std::os::raw::c_char; 16usize],
+    pub handle: u32_,
+    pub parent: u32_,
+    pub qlen: u32_,
+    pub backlog: u32_,
+    pub packets: s32,
+    pub verdict: s32,
+    pub classify: u8_,
+    pub ingress: u8_,
+}
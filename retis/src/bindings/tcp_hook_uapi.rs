@@ -0,0 +1,39 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+#[doc = " Tcp hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct tcp_offsets {
+    pub state: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct tcp_config {
+    pub offsets: tcp_offsets,
+    pub type_: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct tcp_event {
+    pub type_: u8_,
+    pub state: u8_,
+    pub new_state: u8_,
+    pub has_metrics: u8_,
+    pub family: u16_,
+    pub sport: u16_,
+    pub dport: u16_,
+    pub saddr: [u8_; 16usize],
+    pub daddr: [u8_; 16usize],
+    pub rto: u32_,
+    pub srtt_us: u32_,
+    pub retransmits: u32_,
+    pub total_retrans: u32_,
+}
@@ -0,0 +1,32 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u8_ = __u8;
+pub type u16_ = __u16;
+pub type u64_ = __u64;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct tunnel_event {
+    pub id: u64_,
+    pub flags: u64_,
+    pub src: [u8_; 16usize],
+    pub dst: [u8_; 16usize],
+    pub sport: u16_,
+    pub dport: u16_,
+    pub tos: u8_,
+    pub ttl: u8_,
+    pub mode: u8_,
+    pub options_len: u8_,
+    pub options: [u8_; 64usize],
+}
+impl Default for tunnel_event {
+    fn default() -> Self {
+        let mut s = ::std::mem::MaybeUninit::<Self>::uninit();
+        unsafe {
+            ::std::ptr::write_bytes(s.as_mut_ptr(), 0, 1);
+            s.assume_init()
+        }
+    }
+}
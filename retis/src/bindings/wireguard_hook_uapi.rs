@@ -0,0 +1,34 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub type __u64 = ::std::os::raw::c_ulonglong;
+pub type u64_ = __u64;
+#[doc = " WireGuard hook configuration. Each probe has its own instance, as the data\n to retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct wireguard_offsets {
+    pub peer: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct wireguard_config {
+    pub offsets: wireguard_offsets,
+    pub type_: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct wireguard_event {
+    pub peer_id: u64_,
+    pub family: u16_,
+    pub port: u16_,
+    pub addr: [u8_; 16usize],
+    pub key: [u8_; 6usize],
+    pub type_: u8_,
+    pub msg_type: u8_,
+    pub has_peer: u8_,
+}
@@ -0,0 +1,36 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type __s64 = ::std::os::raw::c_longlong;
+pub type s64 = __s64;
+#[doc = " XDP hook configuration. Each probe has its own instance, as the action is\n retrieved differently depending on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct xdp_offsets {
+    pub bpf_prog: s8,
+    pub act: s8,
+    pub err: s8,
+}
+#[doc = " Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct xdp_config {
+    pub offsets: xdp_offsets,
+    pub act_ret: u8_,
+    pub act: s32,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct xdp_event {
+    pub prog_id: u32_,
+    pub prog_name: [::std::os::raw::c_char; 16usize],
+    pub action: s64,
+    pub err: s32,
+}
@@ -0,0 +1,40 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+#[doc = " Xfrm hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct xfrm_offsets {
+    pub spi: s8,
+    pub int_arg: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct xfrm_config {
+    pub offsets: xfrm_offsets,
+    pub type_: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct xfrm_event {
+    pub type_: u8_,
+    pub has_state: u8_,
+    pub proto: u8_,
+    pub mode: u8_,
+    pub family: u16_,
+    pub spi: u32_,
+    pub reqid: u32_,
+    pub saddr: [u8_; 16usize],
+    pub daddr: [u8_; 16usize],
+    pub int_arg: s32,
+    pub ret: s32,
+}
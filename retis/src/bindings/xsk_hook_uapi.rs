@@ -0,0 +1,52 @@
+/* automatically generated by rust-bindgen 0.72.1 */
+
+pub const IFNAMSIZ: enum_IFNAMSIZ = 16;
+pub type enum_IFNAMSIZ = ::std::os::raw::c_uint;
+pub type __s8 = ::std::os::raw::c_schar;
+pub type s8 = __s8;
+pub type __u8 = ::std::os::raw::c_uchar;
+pub type u8_ = __u8;
+pub type __u16 = ::std::os::raw::c_ushort;
+pub type u16_ = __u16;
+pub type __s32 = ::std::os::raw::c_int;
+pub type s32 = __s32;
+pub type __u32 = ::std::os::raw::c_uint;
+pub type u32_ = __u32;
+#[doc = " AF_XDP hook configuration. Each probe has its own instance, as the data to\n retrieve depends on the probe.\n\n Skip Default trait implementation:\n\n <div rustbindgen nodefault></div>"]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct xsk_offsets {
+    pub xdp_sock: s8,
+    pub sock: s8,
+    pub xdp_buff: s8,
+    pub xsk_buff_pool: s8,
+    pub descs: s8,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct xsk_config {
+    pub offsets: xsk_offsets,
+    pub op: u8_,
+    pub mode: u8_,
+    pub descs_ret: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct xsk_ring {
+    pub producer: u32_,
+    pub consumer: u32_,
+    pub size: u32_,
+    pub valid: u8_,
+}
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct xsk_event {
+    pub ring: xsk_ring,
+    pub pool_ring: xsk_ring,
+    pub err: s32,
+    pub descs: u32_,
+    pub queue_id: u16_,
+    pub op: u8_,
+    pub mode: u8_,
+    pub dev: [::std::os::raw::c_char; 16usize],
+}
@@ -3,7 +3,15 @@
 //! Cli module, providing tools for registering and accessing command line interface arguments
 //! as well as defining the subcommands that the tool supports.
 use std::{
-    any::Any, convert::From, env, ffi::OsString, fmt::Debug, fs, path::PathBuf, str::FromStr,
+    any::Any,
+    collections::HashSet,
+    convert::From,
+    env,
+    ffi::OsString,
+    fmt::Debug,
+    io::{stdout, IsTerminal},
+    path::PathBuf,
+    str::FromStr,
 };
 
 use anyhow::{anyhow, bail, Result};
@@ -11,6 +19,7 @@ use clap::{
     builder::PossibleValuesParser,
     error::Error as ClapError,
     error::ErrorKind,
+    parser::ValueSource,
     {ArgMatches, Args, Command, FromArgMatches, ValueEnum},
 };
 use log::{debug, LevelFilter};
@@ -18,7 +27,11 @@ use log::{debug, LevelFilter};
 #[cfg(feature = "benchmark")]
 use crate::benchmark::cli::Benchmark;
 use crate::{
-    collect::cli::Collect,
+    agent::Agent,
+    collect::{cli::Collect, config::CollectConfig},
+    ctl::Ctl,
+    daemon::Daemon,
+    events::ColorTheme,
     generate::Complete,
     helpers::{
         logger::{set_libbpf_rs_print_callback, Logger},
@@ -172,9 +185,27 @@ pub(crate) struct MainConfig {
         help = "Path to an additional directory with custom profiles. Takes precedence over built-in profiles directories."
     )]
     pub(crate) extra_profiles_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Do not use colors when printing events. Colors are otherwise used when the output is a terminal, unless the NO_COLOR environment variable is set"
+    )]
+    pub(crate) no_color: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CliColorTheme::Dark,
+        help = "Color theme used when printing events"
+    )]
+    pub(crate) color_theme: CliColorTheme,
+    /// Color theme to use when printing events, if colors are used.
+    #[arg(skip)]
+    pub(crate) colors: Option<ColorTheme>,
     /// Expanded command line that was used to invoke retis
     #[arg(skip)]
     pub(crate) cmdline: String,
+    /// Expanded command line arguments, including the program name.
+    #[arg(skip)]
+    pub(crate) args: Vec<String>,
 }
 
 const HELP_TEMPLATE: &str = "\
@@ -204,9 +235,19 @@ impl RetisCli {
         cli.add_subcommand(Box::new(Pcap::new()?))?;
         cli.add_subcommand(Box::new(Inspect::new()?))?;
         cli.add_subcommand(Box::new(ProfileCmd::new()?))?;
+        cli.add_subcommand(Box::new(Ctl::new()?))?;
+        cli.add_subcommand(Box::new(Agent::new()?))?;
+        cli.add_subcommand(Box::new(Daemon::new()?))?;
         cli.add_subcommand(Box::new(Complete::new()?))?;
         cli.add_subcommand(Box::new(PrintSchema::new()?))?;
         cli.add_subcommand(Box::new(Stats::new()?))?;
+        cli.add_subcommand(Box::new(Latency::new()?))?;
+        cli.add_subcommand(Box::new(Diff::new()?))?;
+        cli.add_subcommand(Box::new(ImportPcap::new()?))?;
+        cli.add_subcommand(Box::new(Grep::new()?))?;
+        cli.add_subcommand(Box::new(Watch::new()?))?;
+        cli.add_subcommand(Box::new(Scrub::new()?))?;
+        cli.add_subcommand(Box::new(Flows::new()?))?;
 
         #[cfg(feature = "benchmark")]
         cli.add_subcommand(Box::new(Benchmark::new()?))?;
@@ -241,27 +282,63 @@ impl RetisCli {
         }
 
         for name in main_config.profile.iter() {
-            // Profile could be a path to a file or a profile name.
-            let profile = match fs::read_to_string(name) {
-                Ok(s) => match Profile::from_str(&s) {
-                    Ok(profile) => profile,
-                    Err(e) => bail!("Could not import profile: {e}"),
-                },
-                _ => {
-                    let name = match name.to_str() {
-                        Some(name) => name,
-                        None => bail!("Invalid profile name ({})", name.display()),
-                    };
-                    Profile::find(name, main_config.extra_profiles_dir.as_ref())?
-                }
-            };
-
+            let profile = Profile::lookup(name, main_config.extra_profiles_dir.as_ref())?;
             let mut extra_args = profile.cli_args(subcommand)?;
             args.append(&mut extra_args);
         }
         Ok(())
     }
 
+    /// Expand the collect configuration file, if any. Arguments already set on
+    /// the command line (or by profiles) take precedence.
+    fn enhance_config(
+        command: &Command,
+        main_config: &MainConfig,
+        subcommand: &str,
+        args: &mut Vec<OsString>,
+    ) -> Result<()> {
+        if subcommand != "collect" {
+            return Ok(());
+        }
+
+        // Invalid arguments are reported by the final round of parsing.
+        let Ok(matches) = command.clone().try_get_matches_from(args.iter()) else {
+            return Ok(());
+        };
+        let Some(matches) = matches.subcommand_matches(subcommand) else {
+            return Ok(());
+        };
+        let Some(path) = matches.get_one::<PathBuf>("config") else {
+            return Ok(());
+        };
+
+        let set = matches
+            .ids()
+            .map(|id| id.as_str())
+            .filter(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+            .map(String::from)
+            .collect::<HashSet<_>>();
+        let collect = command
+            .find_subcommand(subcommand)
+            .ok_or_else(|| anyhow!("Subcommand {subcommand} not found"))?;
+        let mut extra_args = CollectConfig::from_file(path)?.cli_args(
+            collect,
+            set,
+            main_config.extra_profiles_dir.as_ref(),
+        )?;
+
+        // Replace the configuration by its content, so the resulting command
+        // line does not depend on the file.
+        match args.iter().position(|arg| arg.as_os_str() == "--config") {
+            Some(pos) => {
+                args.drain(pos..(pos + 2).min(args.len()));
+            }
+            None => args.retain(|arg| !arg.to_string_lossy().starts_with("--config=")),
+        }
+        args.append(&mut extra_args);
+        Ok(())
+    }
+
     fn get_version() -> String {
         let pkg_version = option_env!("RELEASE_VERSION").unwrap_or("unspec");
         let pkg_name = option_env!("RELEASE_NAME").unwrap_or("unreleased");
@@ -303,6 +380,12 @@ impl RetisCli {
         let mut main_config = MainConfig::default();
         main_config.update_from_arg_matches(&matches)?;
 
+        // Check if colors can be used before the pager is set up, as stdout
+        // is then redirected to it.
+        if !main_config.no_color && use_colors() {
+            main_config.colors = Some(main_config.color_theme.into());
+        }
+
         let log_level = main_config.log_level.as_str();
         let log_level = LevelFilter::from_str(log_level).map_err(|e| {
             command.error(
@@ -333,6 +416,11 @@ impl RetisCli {
             // Try setting up the pager for a selected subset of commands.
             // This needs to be done before the final round of cli parsing because logs can be emitted
             // and we need to redirect them to stdout if pager is active.
+            // Followed events never end, which does not play well with a pager.
+            "print"
+                if matches
+                    .subcommand_matches("print")
+                    .is_some_and(|m| m.get_flag("follow")) => {}
             "print" | "sort" | "stats" => {
                 try_enable_pager(&logger);
             }
@@ -343,6 +431,15 @@ impl RetisCli {
         RetisCli::enhance_profile(&main_config, subcommand.name().as_str(), &mut args)
             .map_err(|err| command.error(ErrorKind::InvalidValue, format!("{err}")))?;
 
+        // Expand the configuration file arguments.
+        RetisCli::enhance_config(
+            &command,
+            &main_config,
+            subcommand.name().as_str(),
+            &mut args,
+        )
+        .map_err(|err| command.error(ErrorKind::InvalidValue, format!("{err}")))?;
+
         let cmdline = args
             .iter()
             .map(|o| o.as_os_str().to_str().unwrap_or("<encoding error>"))
@@ -372,6 +469,10 @@ impl RetisCli {
 
         // Store the command line for use in initial event
         main_config.cmdline = cmdline;
+        main_config.args = args
+            .iter()
+            .map(|o| o.to_string_lossy().into_owned())
+            .collect();
 
         Ok(CliConfig {
             command,
@@ -400,3 +501,38 @@ pub(crate) enum CliDisplayFormat {
     #[default]
     MultiLine,
 }
+
+/// Type of the "color-theme" argument.
+// It is an enum that maps 1:1 with the color themes defined in events library.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, ValueEnum)]
+pub(crate) enum CliColorTheme {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl From<CliColorTheme> for ColorTheme {
+    fn from(theme: CliColorTheme) -> Self {
+        match theme {
+            CliColorTheme::Dark => ColorTheme::Dark,
+            CliColorTheme::Light => ColorTheme::Light,
+        }
+    }
+}
+
+/// Check if colors can be used when printing events to stdout.
+fn use_colors() -> bool {
+    if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    stdout().is_terminal() && matches!(env::var("TERM"), Ok(term) if term != "dumb")
+}
+
+/// Type of the "out-format" argument.
+// It is an enum that maps 1:1 with the file formats defined in events library.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, ValueEnum)]
+pub(crate) enum CliOutFormat {
+    #[default]
+    Json,
+    Protobuf,
+}
@@ -4,11 +4,21 @@
 
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use clap::{builder::PossibleValuesParser, Parser};
 
-use super::Collectors;
-use crate::{cli::*, collect::collector::*, core::inspect::init_inspector};
+use super::{remote::remote_collect, Collectors, COLLECTORS};
+use crate::{
+    cli::*,
+    collect::collector::*,
+    core::{inspect::init_inspector, user::container::Container},
+    ctl::DEFAULT_CTL_SOCKET,
+    helpers::{
+        file_rotate::parse_size,
+        flight_recorder::{DumpTrigger, RecorderLimit},
+        until::StopCondition,
+    },
+};
 
 #[derive(Parser, Debug, Default)]
 #[command(
@@ -19,12 +29,20 @@ use crate::{cli::*, collect::collector::*, core::inspect::init_inspector};
 The collect sub-command uses \"collectors\" to retrieve data and emit events. Collectors extract data from different places of the kernel or userspace daemons using eBPF. Some install probes automatically. Each collector is specialized in retrieving specific data. The list of enabled collectors can be configured using the --collectors argument."
 )]
 pub(crate) struct Collect {
+    // Expanded into the other arguments while parsing the command line, see
+    // RetisCli::enhance_config.
+    #[allow(dead_code)]
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Configuration file declaring the collection (collectors, probes, filters, outputs, limits, etc.), in YAML. Arguments given on the command line take precedence over the ones set in the file."
+    )]
+    pub(super) config: Option<PathBuf>,
     #[arg(
         short,
         long,
-        value_parser = PossibleValuesParser::new([
-            "auto", "skb-tracking", "skb", "skb-drop", "ovs", "nft", "ct", "dev", "ns",
-        ]),
+        visible_alias = "modules",
+        value_parser = PossibleValuesParser::new(["auto"].into_iter().chain(COLLECTORS.iter().copied())),
         value_delimiter = ',',
         default_value = "auto",
         help = "Comma-separated list of collectors to enable.
@@ -32,6 +50,14 @@ pub(crate) struct Collect {
 If 'auto' is in the list, all collectors not explicitly added are enabled if their prerequisites are met."
     )]
     pub(super) collectors: Vec<String>,
+    #[arg(
+        long,
+        value_name = "COLLECTORS",
+        value_parser = PossibleValuesParser::new(COLLECTORS.iter().copied()),
+        value_delimiter = ',',
+        help = "Comma-separated list of collectors not to enable, even if their prerequisites are met and 'auto' is used."
+    )]
+    pub(super) exclude_collectors: Vec<String>,
     // Use the plural in the struct but singular for the cli parameter as we're
     // dealing with a list here.
     #[arg(
@@ -44,22 +70,63 @@ When TYPE is not specified it is set to 'kprobe', except if a single ':' is foun
 
 Valid TYPEs:
 - kprobe | k: kernel probes.
-- kretprobe | kr: kernel return probes.
+- kretprobe | kr: kernel return probes, also reporting the function return value.
 - raw_tracepoint | tp: kernel tracepoints.
+- usdt: userspace statically defined tracepoints. TARGET follows the BINARY:PROVIDER::NAME pattern, where BINARY is either the path to an executable (probing all its existing and future processes), a pid, a command name (probing all processes running it, including ones started during the collection) or a shared library name starting with \"lib\", e.g. \"libssl\" (probing all processes using the matching libraries loaded at startup; the name is used as a command one if no such library is loaded).
 
-Wildcards (*) can be used, eg. \"kprobe:tcp_*\" or \"tp:skb:*\".
+Wildcards (*) can be used, eg. \"kprobe:tcp_*\" or \"tp:skb:*\". The number of probes a wildcard can expand to without confirmation is limited, see \"--probe-max\".
 
 OPTIONS can be used to configure probes on a per-probe basis. Options are a list of keywords separated by '/' (e.g. TARGET/opt1/opt2). Valid OPTIONS:
 - stack: enables stack traces retrieval (same as \"--stack\", on a per-probe basis).
+- ustack: enables user stack traces retrieval (same as \"--user-stack\", on a per-probe basis).
+- args: enables function arguments retrieval (same as \"--probe-args\", on a per-probe basis).
 
 If this is not set, no profile is used (\"--profile\") and no collector is explicitly enabled (\"--collector\"); \"net:netif_receive_skb\" and \"net:net_dev_start_xmit\" are automatically used. Also note the \"--probe-stack\" logic takes precedence over this.
 
 Examples:
 --probe tp:skb:kfree_skb --probe kprobe:consume_skb
 --probe skb:kfree_skb --probe consume_skb
--p skb:kfree_skb/stack -p consume_skb"
+-p skb:kfree_skb/stack -p consume_skb
+--probe usdt:ovs-vswitchd:dpif_recv::recv_upcall
+--probe usdt:libssl:openssl::handshake"
     )]
     pub(super) probes: Vec<String>,
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 64,
+        help = "Maximum number of probes a single --probe using wildcards can expand to without confirmation. When exceeded Retis asks for confirmation, or fails if not run interactively, unless --yes is used. 0 means no limit."
+    )]
+    pub(super) probe_max: usize,
+    #[arg(
+        long,
+        help = "Automatically confirm attaching all the probes matching wildcards, even when exceeding --probe-max."
+    )]
+    pub(super) yes: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_CTL_SOCKET,
+        help = "Listen for runtime control requests on a unix socket, allowing to add and remove probes while collecting (see `retis ctl`). If no path is given, /run/retis/ctl.sock is used."
+    )]
+    pub(super) ctl_socket: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "HOST[:PORT]",
+        value_delimiter = ',',
+        conflicts_with_all = ["ctl_socket", "flight_recorder"],
+        requires = "remote_token_file",
+        help = "Run the collection on remote hosts instead of the local one, using agents running there (see `retis agent`). Can be used multiple times or given as a comma separated list, e.g. '--remote node1,node2:9081' (the default port is 9080). Collection arguments (collectors, probes, filters and their options) are forwarded to the agents, while output arguments (--out, --print, --format, etc.) apply locally; arguments accessing files or running commands (e.g. --cmd, --config or --external-collector) can't be used. Events of all hosts are merged, ordered by timestamp and converted to the local clock; they are tagged with the host they were collected on."
+    )]
+    pub(super) remote: Vec<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "remote",
+        help = "File holding the token used to authenticate to the agents (see `retis agent --token-file`)."
+    )]
+    pub(super) remote_token_file: Option<PathBuf>,
     #[arg(
         id = "filter-packet",
         short,
@@ -84,6 +151,98 @@ Examples of meta filters:
 --filter-meta 'sk_buff.dev.nd_net.net.ns.inum == 4026531840'"#
     )]
     pub(super) meta_filter: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["filter-packet", "filter-meta"],
+        help = r#"Add a combined packet and meta filter to all targets. Each term is made of its kind (packet or meta) followed by its expression, using the same syntax as --filter-packet and --filter-meta respectively. At most one packet and one meta term can be used and they are combined with either "and" or "or":
+
+(packet: <expression>) [and|or] (meta: <expression>)
+
+Examples:
+--filter '(packet: tcp port 80) or (meta: sk_buff.mark == 5)'
+--filter '(packet: arp) and (meta: sk_buff.dev.name == "eth0")'"#
+    )]
+    pub(super) filter: Option<String>,
+    #[arg(
+        long,
+        value_name = "FLOW",
+        conflicts_with_all = ["filter-packet", "filter"],
+        help = r#"Add a packet filter matching a single flow, in both directions. If NAT is detected for the flow in the conntrack table at startup, the translated flow is matched as well. The syntax follows:
+
+<addr>[:<port>] <-> <addr>[:<port>][/<proto>]
+
+With proto ::= tcp | udp | sctp | icmp. IPv6 addresses must be enclosed in brackets when a port is given. Can be combined with --filter-meta.
+
+Examples:
+--filter-flow '10.0.0.1:1234 <-> 10.0.0.2:80/tcp'
+--filter-flow '[2001:db8::1]:1234 <-> [2001:db8::2]:53/udp'"#
+    )]
+    pub(super) filter_flow: Option<String>,
+    #[arg(
+        long,
+        value_name = "PID",
+        help = "Only generate events for tasks of a given process (its pid, aka. tgid). This is enforced in the probes and is not a packet filter: packets are still tracked, but events generated from other tasks context (including softirqs running on their behalf) are not reported."
+    )]
+    pub(super) filter_pid: Option<u32>,
+    #[arg(
+        long,
+        value_name = "COMM",
+        help = "Only generate events for tasks with a given name (comm, up to 15 characters). As for --filter-pid, this is enforced in the probes and packets are still tracked."
+    )]
+    pub(super) filter_comm: Option<String>,
+    #[arg(
+        long,
+        value_name = "INODE|PATH",
+        help = "Only generate events for a given network namespace, identified by its inode number or by a path to it (e.g. /run/netns/foo or /proc/<pid>/ns/net). Packets are matched using the namespace of their device (or socket), other events using the namespace of the current task. This is enforced in the probes and packets are still tracked.
+
+Examples:
+--netns 4026531840
+--netns /run/netns/blue"
+    )]
+    pub(super) netns: Option<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Only report 1 out of N new packets matching the filters. Sampled in packets are then tracked and reported along their whole path, while sampled out ones are ignored (but can be sampled in later on if they hit other probes before being tracked)."
+    )]
+    pub(super) sample: Option<u32>,
+    #[arg(
+        long,
+        value_name = "RATE",
+        value_parser = parse_rate_limit,
+        help = "Limit the number of events each probe can generate, in events per second (e.g. '1000/s' or '1000'). Each probe is limited independently so a single hot probe can't flood the events buffer and starve the others. Bursts of up to RATE events are allowed. Suppressed events are reported in the probe statistics."
+    )]
+    pub(super) rate_limit: Option<u32>,
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        help = "Size of the events buffer shared by all probes (e.g. 64M), rounded up to a power of two. Defaults to 16M. A bigger buffer helps absorbing bursts of events while the consumer is lagging behind, at the expense of locked memory."
+    )]
+    pub(super) events_buffer: Option<usize>,
+    #[arg(
+        long,
+        help = "Account for all the events lost because the events buffer was full: events lost since the previous report are recorded in the events stream, as \"lost\" events, so gaps in a collection can be identified."
+    )]
+    pub(super) lossless: bool,
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        requires = "lossless",
+        value_parser = clap::value_parser!(u32).range(1..100),
+        help = "Stop generating events while the events buffer is filled above PERCENT (1-99), letting the consumer catch up instead of overrunning the buffer. Events not generated are reported as lost. Only applies to kernel probes."
+    )]
+    pub(super) throttle: Option<u32>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Only generate events for tasks in a given cgroup (v2) or in one of its descendants, e.g. to only trace a container's traffic. The path is either absolute or relative to the cgroup v2 mount point (/sys/fs/cgroup). Packets are still tracked outside of the cgroup, but events generated from other tasks context (including softirqs running on their behalf) are not reported. This applies to all kernel probes.
+
+Example:
+--cgroup system.slice/docker-<id>.scope"
+    )]
+    pub(super) cgroup: Option<PathBuf>,
     #[arg(short = 'e', help = "Print link-layer information from the packet")]
     pub(crate) print_ll: bool,
     #[arg(
@@ -91,19 +250,74 @@ Examples of meta filters:
         long,
         num_args = 0..=1,
         default_missing_value = "retis.data",
-        help = "Write the events to a file rather than to sdout. If the flag is used without a file name, defaults to \"retis.data\"."
+        value_name = "OUT",
+        help = "Write the events to a file rather than to sdout. If the flag is used without a file name, defaults to \"retis.data\".
+
+Events can also be streamed live to a remote consumer, using \"tcp://HOST:PORT\" or \"unix:///PATH\". Each connection starts with a startup event, followed by the events in the same format as the file one. Events are buffered while the consumer is not reachable (or not keeping up) and connecting is retried; once the buffer is full, events are dropped.
+
+Can be used multiple times to write the events to a file and to streams, e.g. \"--out --out tcp://127.0.0.1:9000\"; only a single file can be used."
     )]
-    pub(super) out: Option<PathBuf>,
+    pub(super) out: Vec<String>,
     #[arg(
         long,
         requires = "out",
         value_name = "LIMIT",
-        help = "Rotate the output file (see `--out`) once a given limit is reached. The rotation limit can be:
-- A size: <LIMIT> is in bytes and must be suffixed with a size unit (MB, GB). The file names will be <OUT>.X, with X being a number starting at 0 and increasing over time. e.g. '--out-rotate 64MB'.
+        help = "Rotate the output file (see `--out`) once a given limit is reached. <LIMIT> is a comma separated list of parameters:
+- size=<SIZE>: rotate once the file reaches a given size. <SIZE> is in bytes and must be suffixed with a size unit (K, M or G, optionally followed by B). e.g. '--out-rotate size=512M'. For compatibility '--out-rotate 64MB' is also accepted.
+- time=<INTERVAL>: rotate once the file has been written to for a given time. <INTERVAL> must be suffixed with a time unit (s, m, h or d). e.g. '--out-rotate time=1h'. Files are only rotated when new events are written.
+- keep=<N>: only keep the N last files, older ones being removed. e.g. '--out-rotate size=512M,keep=8'.
+
+A single rotation limit (size or time) must be used. The file names will be <OUT>.X, with X being a number starting at 0 and increasing over time. The files can be read as a set by the post-processing commands.
 
 Events from the same series might end up on different files. If a previous collection with rotation enabled was not removed only the files required to store the new collection will be overridden. This includes the '--out' value."
     )]
     pub(super) out_rotate: Option<String>,
+    #[arg(
+        long,
+        requires = "out",
+        help = "Compress (zstd) the output file(s). Compressed files are detected automatically when reading them, e.g. by the print and sort commands. When used with --out-rotate, the rotation limit applies to the uncompressed data."
+    )]
+    pub(super) compress: bool,
+    #[arg(
+        long,
+        requires = "out",
+        conflicts_with = "compress",
+        help = "Write an index alongside the output file(s), as <OUT>.idx (or <OUT>.X.idx when rotating). The index allows post-processing commands to only read the events in a given time range or of a given packet, e.g. using 'retis print --since/--until/--tracking-id'."
+    )]
+    pub(super) out_index: bool,
+    #[arg(
+        long,
+        requires = "out",
+        conflicts_with = "out_index",
+        value_enum,
+        default_value_t = CliOutFormat::Json,
+        help = "Format of the events written to the output file (see `--out`): either one JSON event per line, or length-delimited protobuf messages (typed per section, see `retis schema --proto` for their definition), for non-Rust consumers to parse events using standard protobuf tooling. Both formats are detected automatically when reading files, e.g. by the print and sort commands. Streams always use JSON."
+    )]
+    pub(super) out_format: CliOutFormat,
+    #[arg(
+        long,
+        requires = "out",
+        value_name = "LIMIT",
+        help = "Flight recorder mode: only keep the last events in memory and write them to the output file (see `--out`) when a trigger fires (see `--recorder-trigger`), allowing to run a collection permanently while only storing events around incidents. <LIMIT> is either:
+- size=<SIZE>: keep up to SIZE of events. <SIZE> is in bytes and must be suffixed with a size unit (K, M or G, optionally followed by B). e.g. '--flight-recorder size=64M'.
+- time=<INTERVAL>: keep the events of the last INTERVAL. <INTERVAL> must be suffixed with a time unit (s, m, h or d). e.g. '--flight-recorder time=30s'.
+
+Events streamed to remote consumers and printed on stdout are not affected."
+    )]
+    pub(super) flight_recorder: Option<RecorderLimit>,
+    #[arg(
+        long = "recorder-trigger",
+        requires = "flight_recorder",
+        value_name = "TRIGGER",
+        value_delimiter = ',',
+        help = "Conditions triggering a dump of the events kept by the flight recorder (see `--flight-recorder`). Can be used multiple times or given as a comma separated list. Triggers are:
+- signal: SIGUSR2 is received, e.g. 'kill -USR2 $(pidof retis)'.
+- drop: a packet drop is reported (requires the skb-drop collector).
+- probe=<PROBE>: an event is reported by a given probe, e.g. 'probe=tcp_v4_rcv' or 'probe=kprobe:tcp_v4_rcv'.
+
+[default: signal]"
+    )]
+    pub(super) recorder_triggers: Vec<DumpTrigger>,
     #[arg(long, help = "Write the events to stdout even if --out is used.")]
     pub(super) print: bool,
     #[arg(
@@ -111,12 +325,29 @@ Events from the same series might end up on different files. If a previous colle
         help = "Include stack traces in the kernel events. The stack entries are limited and not released. If exhausted, no stack trace will be included."
     )]
     pub(super) stack: bool,
+    #[arg(
+        long,
+        help = "Include the user stack traces of the current task in the events, when available (kernel threads do not have one). Frames are resolved to their object file at collection time and symbolized at post-processing time (e.g. by the print and sort commands), using the object files found on the machine. This requires binaries to be built with frame pointers to get meaningful traces."
+    )]
+    pub(super) user_stack: bool,
+    #[arg(
+        long,
+        value_name = "HZ",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Sample what all CPUs are executing HZ times per second, using a cpu-clock perf event. Each sample is reported as a kernel event (of type \"perf\") holding the sampled instruction pointer and the current task, which allows correlating packet events with what the CPUs were doing. Stack traces are included when using --stack and/or --user-stack. Samples are not filtered."
+    )]
+    pub(super) cpu_sampling: Option<u64>,
+    #[arg(
+        long,
+        help = "Include the probed functions arguments in the kernel events. Arguments are decoded using their BTF type: integers, enums, strings and small structs can be reported. Other arguments are reported using their raw value."
+    )]
+    pub(super) probe_args: bool,
     #[arg(
         long,
         help = "When set, evaluates where Retis could add additional probes based on functions reported in the events stack traces (their display is still controlled by --stack). All matching functions are probed at runtime using kprobes.
 
 Notes:
-- Using a filter is required (--filter-packet and/or --filter-meta).
+- Using a filter is required (--filter-packet, --filter-flow, --filter-meta and/or --filter).
 - If no explicit probe is given, tp:skb:kfree_skb and tp:skb:consume_skb are used as a starting point.
 - Additional probes are added only after events including them in their stack trace are reported; this means the first packets hitting a probe won't be reported.
 - Packets will only be followed prior to the initial set of probes (as this mode uses stack traces). This also means the filter must match packets as they appear in the initial set of probes; packet transformation can't be automatically detected."
@@ -133,6 +364,19 @@ Notes:
         help = "Stop collection after receiving the specified number of events from probes."
     )]
     pub(super) stop_after: Option<u64>,
+    #[arg(
+        long,
+        value_name = "CONDITION",
+        help = "Stop the collection once a condition is met, evaluated on the live event stream. Can be used multiple times, the collection stopping once any of the conditions is met. Conditions are:
+- drops > N (or >= N): more than N packet drops were reported (requires the skb-drop collector).
+- events > N (or >= N): more than N events were reported by probes.
+- duration <INTERVAL>: the collection ran for a given time. <INTERVAL> must be suffixed with a time unit (s, m, h or d), e.g. 'duration 5m'.
+- event matches <FILTER>: an event whose packet matches a packet filter was reported. The filter uses the same syntax as --filter-packet (pcap-filter), e.g. \"event matches tcp dst port 443\". Events without a packet never match.
+- event matches-regex <REGEX>: an event matching a regular expression was reported. Events are matched using their single line text representation (as with 'retis grep'), which includes information not part of the packet, e.g. \"event matches-regex drop.*TCP_INVALID_SEQUENCE\".
+
+The event meeting a condition is the last one reported. In flight recorder mode (see `--flight-recorder`), the events kept in memory are written to the output file when stopping."
+    )]
+    pub(super) until: Vec<StopCondition>,
     #[arg(
         long,
         help = r#"Allow the tool to setup all the system changes needed to make the tracing fully operational:
@@ -154,6 +398,29 @@ Notes:
         help = "Path to kernel configuration (e.g. /boot/config-6.3.8-200.fc38.x86_64; default: auto-detect)"
     )]
     pub(crate) kconf: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "ID",
+        help = "Look up the processes to probe (e.g. USDT targets given by command name or pid, Open vSwitch daemons) in a given container, identified by its id or name. Pids are then the ones seen from inside the container."
+    )]
+    pub(crate) container: Option<String>,
+    #[arg(
+        long,
+        help = "Retrieve the containers running when the collection starts from the container runtimes (podman, docker and CRI runtimes through crictl) and store them along the events, so netns and cgroup ids can be mapped to containers and pods when post-processing events."
+    )]
+    pub(crate) enrich_containers: bool,
+    #[arg(
+        long,
+        help = "Kubernetes mode. Retrieve the pods, services and nodes from the API server (using kubectl) when the collection starts and store them along the events, so packet addresses and pod interfaces can be mapped to Kubernetes objects when post-processing events. Implies --enrich-containers."
+    )]
+    pub(crate) kube: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "kube",
+        help = "Path to the kubeconfig file used to reach the API server in Kubernetes mode (default: kubectl default)."
+    )]
+    pub(crate) kubeconfig: Option<PathBuf>,
     #[arg(long, help = "Print the time as UTC")]
     pub(super) utc: bool,
     #[arg(long, help = "Format used when printing an event.")]
@@ -175,10 +442,48 @@ pub(crate) struct CollectorsArgs {
 
     #[command(flatten, next_help_heading = "collector 'nft'")]
     pub(crate) nft: nft::NftCollectorArgs,
+
+    #[command(flatten, next_help_heading = "collector 'skb-drop'")]
+    pub(crate) skb_drop: skb_drop::SkbDropCollectorArgs,
+
+    #[command(flatten, next_help_heading = "collector 'ct'")]
+    pub(crate) ct: ct::CtCollectorArgs,
+
+    #[command(flatten, next_help_heading = "collector 'skb-tracking'")]
+    pub(crate) skb_tracking: skb_tracking::SkbTrackingCollectorArgs,
+
+    #[command(flatten, next_help_heading = "collector 'mem'")]
+    pub(crate) mem: mem::MemCollectorArgs,
+
+    #[command(flatten, next_help_heading = "collector 'external'")]
+    pub(crate) external: external::ExternalCollectorArgs,
+}
+
+/// Parse a rate limit, in events per second: "N/s" or "N".
+fn parse_rate_limit(rate: &str) -> Result<u32> {
+    let rate = rate.strip_suffix("/s").unwrap_or(rate).parse::<u32>()?;
+    if rate == 0 {
+        bail!("Rate limit must be greater than 0");
+    }
+    Ok(rate)
+}
+
+impl Collect {
+    /// Look up the container given using --container, if any.
+    pub(crate) fn container(&self) -> Result<Option<Container>> {
+        self.container
+            .as_deref()
+            .map(Container::from_id)
+            .transpose()
+    }
 }
 
 impl SubCommandParserRunner for Collect {
     fn run(&mut self, main_config: &MainConfig) -> Result<()> {
+        if !self.remote.is_empty() {
+            return remote_collect(self, main_config);
+        }
+
         if let Some(kconf) = &self.kconf {
             init_inspector(kconf)?;
         }
@@ -193,3 +498,17 @@ impl SubCommandParserRunner for Collect {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limit() {
+        assert_eq!(parse_rate_limit("1000/s").unwrap(), 1000);
+        assert_eq!(parse_rate_limit("42").unwrap(), 42);
+        assert!(parse_rate_limit("0/s").is_err());
+        assert!(parse_rate_limit("1000/m").is_err());
+        assert!(parse_rate_limit("-1").is_err());
+    }
+}
@@ -1,12 +1,13 @@
 #[cfg(not(test))]
 use std::os::fd::{AsFd, AsRawFd};
 use std::{
+    cell::Cell,
     collections::{HashMap, HashSet},
-    io,
-    path::Path,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
     process::{Command, Stdio},
-    sync::Arc,
-    time::Duration,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, bail, Context, Result};
@@ -16,39 +17,95 @@ use nix::{errno::Errno, mount::*, unistd::Uid};
 use super::{
     cli::Collect,
     collector::{
-        ct::CtCollector, dev::DevCollector, nft::NftCollector, ns::NsCollector, ovs::OvsCollector,
-        skb::SkbCollector, skb_drop::SkbDropCollector, skb_tracking::SkbTrackingCollector,
+        bridge::BridgeCollector, ct::CtCollector, dev::DevCollector, external::ExternalCollector,
+        gro_gso::GroGsoCollector, icmp_error::IcmpErrorCollector, lag::LagCollector,
+        mem::MemCollector, mptcp::MptcpCollector, neigh::NeighCollector, nf::NfCollector,
+        nft::NftCollector, ns::NsCollector, ns_lifecycle::NsLifecycleCollector,
+        offload::OffloadCollector, ovs::OvsCollector, route::RouteCollector, skb::SkbCollector,
+        skb_drop::SkbDropCollector, skb_tracking::SkbTrackingCollector, sock::SockCollector,
+        tc::TcCollector, tcp::TcpCollector, tunnel::TunnelCollector, wireguard::WireguardCollector,
+        xdp::XdpCollector, xfrm::XfrmCollector, xsk::XskCollector,
     },
 };
 use crate::{
     bindings::{meta_filter_uapi, packet_filter_uapi},
-    cli::{CliDisplayFormat, MainConfig},
+    cli::{CliDisplayFormat, CliOutFormat, MainConfig},
     collect::collector::section_factories,
     core::{
         events::*,
         filters::{
+            combined::{CombinedFilter, FiltersMode},
             filters::{BpfFilter, Filter},
             meta::filter::FilterMeta,
-            packets::filter::FilterPacket,
+            packets::{filter::FilterPacket, flow::FlowFilter},
         },
         inspect::check::collection_prerequisites,
         kernel::Symbol,
         probe::{
-            kernel::{probe_stack::ProbeStack, utils::probe_from_cli},
+            kernel::{probe_stack::ProbeStack, symbol_provides, utils::probe_from_cli},
+            user::{
+                utils::{CliUsdtProbe, CliUsdtTarget},
+                watch::UsdtWatcher,
+                UsdtProbe,
+            },
             *,
         },
         tracking::{
             gc::TrackingGC, skb_tracking::init_tracking, stack_tracking::init_stack_tracking,
         },
+        user::{
+            container::runtime_containers,
+            kube::kube_info,
+            proc::{libs_from_name, Process},
+        },
+    },
+    ctl::{CtlRequest, CtlServer},
+    events::{file::rotate::*, helpers::time::*, sink::*, *},
+    helpers::{
+        file_rotate::*,
+        flight_recorder::*,
+        signals::Running,
+        stream::*,
+        until::{StopCondition, StopConditions},
     },
-    events::{file::rotate::*, helpers::time::*, *},
-    helpers::{file_rotate::*, signals::Running},
     process::display::*,
 };
 
 #[cfg(not(test))]
 use crate::core::probe::kernel::{config::init_stack_map, kernel::KernelEventFactory};
 
+/// Collectors known to Retis, in the order they are initialized.
+pub(crate) const COLLECTORS: &[&str] = &[
+    "skb-tracking",
+    "skb",
+    "skb-drop",
+    "ovs",
+    "nft",
+    "nf",
+    "ct",
+    "dev",
+    "ns",
+    "tc",
+    "xdp",
+    "xfrm",
+    "tunnel",
+    "tcp",
+    "gro-gso",
+    "bridge",
+    "lag",
+    "wireguard",
+    "icmp-error",
+    "ns-lifecycle",
+    "sock",
+    "offload",
+    "mem",
+    "xsk",
+    "mptcp",
+    "route",
+    "neigh",
+    "external",
+];
+
 /// Generic trait representing a collector. All collectors are required to
 /// implement this, as they'll be manipulated through this trait.
 pub(crate) trait Collector {
@@ -99,6 +156,9 @@ pub(crate) trait Collector {
     }
 }
 
+/// Interval at which lost events are checked for, in lossless mode.
+const LOST_EVENTS_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Main collectors object and API.
 pub(crate) struct Collectors {
     collectors: HashMap<String, Box<dyn Collector>>,
@@ -114,6 +174,8 @@ pub(crate) struct Collectors {
     events_factory: Arc<RetisEventsFactory>,
     // Monotonic clock offset stored once and reused.
     monotonic_offset: TimeSpec,
+    // USDT probes to attach to processes started during the collection.
+    usdt_watcher: UsdtWatcher,
 }
 
 impl Collectors {
@@ -132,12 +194,42 @@ impl Collectors {
             stack_tracking_config_map: None,
             events_factory: Arc::new(RetisEventsFactory::default()),
             monotonic_offset: monotonic_clock_offset()?,
+            usdt_watcher: UsdtWatcher::default(),
         })
     }
 
+    /// Convert a flow specification into a packet filter expression, also
+    /// matching its NAT counterparts if any.
+    fn flow_to_packet_filter(flow: &str) -> Result<String> {
+        let flow = flow
+            .parse::<FlowFilter>()
+            .map_err(|e| anyhow!("flow filter: {e}"))?;
+
+        let nat = flow.nat_counterparts();
+        if !nat.is_empty() {
+            info!("NAT detected for the flow, also matching its translated counterpart");
+        }
+
+        let filter = flow.to_pcap_with(&nat);
+        debug!("Flow filter translated to '{filter}'");
+        Ok(filter)
+    }
+
     /// Setup user defined input filter.
     fn setup_filters(probes: &mut ProbeBuilderManager, collect: &Collect) -> Result<()> {
-        if let Some(f) = &collect.packet_filter {
+        let filter = match &collect.filter {
+            Some(f) => CombinedFilter::from_string(f).map_err(|e| anyhow!("filter: {e}"))?,
+            None => CombinedFilter {
+                packet: match &collect.filter_flow {
+                    Some(flow) => Some(Self::flow_to_packet_filter(flow)?),
+                    None => collect.packet_filter.clone(),
+                },
+                meta: collect.meta_filter.clone(),
+                mode: FiltersMode::And,
+            },
+        };
+
+        if let Some(f) = &filter.packet {
             // L2 filter MUST always succeed. Any failure means we need to bail.
             let fb = FilterPacket::from_string_opt(f.to_string(), packet_filter_uapi::L2)?;
 
@@ -172,7 +264,7 @@ impl Collectors {
             info!("{loaded_info} packet filter(s) loaded");
         }
 
-        if let Some(f) = &collect.meta_filter {
+        if let Some(f) = &filter.meta {
             let fb =
                 FilterMeta::from_string(f.to_string()).map_err(|e| anyhow!("meta filter: {e}"))?;
             probes.register_filter(Filter::Meta(
@@ -181,13 +273,98 @@ impl Collectors {
             ))?;
         }
 
+        if let Some(cgroup) = &collect.cgroup {
+            info!("Only reporting events from cgroup {}", cgroup.display());
+            probes.set_cgroup(cgroup)?;
+        }
+
+        if let Some(pid) = collect.filter_pid {
+            info!("Only reporting events from pid {pid}");
+            probes.set_filter_pid(pid)?;
+        }
+
+        if let Some(comm) = &collect.filter_comm {
+            info!("Only reporting events from tasks named {comm}");
+            probes.set_filter_comm(comm)?;
+        }
+
+        if let Some(netns) = &collect.netns {
+            info!("Only reporting events from netns {netns}");
+            probes.set_filter_netns(netns)?;
+        }
+
+        if let Some(rate) = collect.rate_limit {
+            info!("Limiting probes to {rate} event(s) per second");
+            probes.set_rate_limit(rate);
+        }
+
+        if let Some(percent) = collect.throttle {
+            info!("Throttling probes while the events buffer is more than {percent}% full");
+            probes.set_throttle(percent);
+        }
+
+        if let Some(rate) = collect.sample {
+            info!("Sampling 1 out of {rate} matching packets");
+            probes.set_sample_rate(rate);
+        }
+
+        // A single filter in OR mode would match all packets, as the missing
+        // one is a pass-through.
+        if filter.mode == FiltersMode::Or && filter.packet.is_some() && filter.meta.is_some() {
+            info!("Packet and meta filters combined with 'or'");
+            probes.set_filters_mode(FiltersMode::Or);
+        }
+
         Ok(())
     }
 
+    /// Describe the filters in effect, to be stored along the events.
+    fn filters_info(collect: &Collect) -> Vec<String> {
+        let mut filters = Vec::new();
+
+        if let Some(f) = &collect.filter {
+            filters.push(format!("filter: {f}"));
+        }
+        if let Some(f) = &collect.packet_filter {
+            filters.push(format!("packet: {f}"));
+        }
+        if let Some(f) = &collect.filter_flow {
+            filters.push(format!("flow: {f}"));
+        }
+        if let Some(f) = &collect.meta_filter {
+            filters.push(format!("meta: {f}"));
+        }
+        if let Some(cgroup) = &collect.cgroup {
+            filters.push(format!("cgroup: {}", cgroup.display()));
+        }
+        if let Some(pid) = collect.filter_pid {
+            filters.push(format!("pid: {pid}"));
+        }
+        if let Some(comm) = &collect.filter_comm {
+            filters.push(format!("comm: {comm}"));
+        }
+        if let Some(netns) = &collect.netns {
+            filters.push(format!("netns: {netns}"));
+        }
+        if let Some(rate) = collect.sample {
+            filters.push(format!("sample: 1/{rate}"));
+        }
+        if let Some(rate) = collect.rate_limit {
+            filters.push(format!("rate limit: {rate}/s"));
+        }
+
+        filters
+    }
+
     /// Check prerequisites and cli arguments to ensure we can run.
     pub(super) fn check(&mut self, collect: &Collect) -> Result<()> {
-        if collect.probe_stack && collect.packet_filter.is_none() && collect.meta_filter.is_none() {
-            bail!("Probe-stack mode requires filtering (--filter-packet and/or --filter-meta)");
+        if collect.probe_stack
+            && collect.packet_filter.is_none()
+            && collect.filter_flow.is_none()
+            && collect.meta_filter.is_none()
+            && collect.filter.is_none()
+        {
+            bail!("Probe-stack mode requires filtering (--filter-packet, --filter-flow, --filter-meta and/or --filter)");
         }
 
         // --allow-system-changes requires root.
@@ -263,40 +440,72 @@ impl Collectors {
                 .builder_mut()?
                 .set_probe_opt(probe::ProbeOption::ReportStack)?;
         }
+        if collect.user_stack {
+            self.probes
+                .builder_mut()?
+                .set_probe_opt(probe::ProbeOption::ReportUserStack)?;
+        }
+        if let Some(freq) = collect.cpu_sampling {
+            info!("Sampling CPUs at {freq}Hz");
+            self.probes.builder_mut()?.set_cpu_sampling(freq);
+        }
+        if collect.probe_args {
+            self.probes
+                .builder_mut()?
+                .set_probe_opt(probe::ProbeOption::ReportArgs)?;
+        }
         if collect.probe_stack {
             self.probes
                 .builder_mut()?
                 .set_probe_opt(probe::ProbeOption::ProbeStack)?;
         }
 
-        let collectors = &[
-            "skb-tracking",
-            "skb",
-            "skb-drop",
-            "ovs",
-            "nft",
-            "ct",
-            "dev",
-            "ns",
-        ];
         let auto = collect.collectors.iter().any(|c| c == "auto");
 
+        if let Some(name) = collect
+            .exclude_collectors
+            .iter()
+            .find(|x| collect.collectors.contains(x))
+        {
+            bail!("Collector {name} can't be both enabled and excluded");
+        }
+
         // Try initializing all collectors.
-        for name in collectors {
+        for name in COLLECTORS {
             let mut c: Box<dyn Collector> = match *name {
                 "skb-tracking" => Box::new(SkbTrackingCollector::new()?),
                 "skb" => Box::new(SkbCollector::new()?),
                 "skb-drop" => Box::new(SkbDropCollector::new()?),
                 "ovs" => Box::new(OvsCollector::new()?),
                 "nft" => Box::new(NftCollector::new()?),
+                "nf" => Box::new(NfCollector::new()?),
                 "ct" => Box::new(CtCollector::new()?),
                 "dev" => Box::new(DevCollector::new()?),
                 "ns" => Box::new(NsCollector::new()?),
+                "tc" => Box::new(TcCollector::new()?),
+                "xdp" => Box::new(XdpCollector::new()?),
+                "xfrm" => Box::new(XfrmCollector::new()?),
+                "tunnel" => Box::new(TunnelCollector::new()?),
+                "tcp" => Box::new(TcpCollector::new()?),
+                "gro-gso" => Box::new(GroGsoCollector::new()?),
+                "bridge" => Box::new(BridgeCollector::new()?),
+                "lag" => Box::new(LagCollector::new()?),
+                "wireguard" => Box::new(WireguardCollector::new()?),
+                "icmp-error" => Box::new(IcmpErrorCollector::new()?),
+                "ns-lifecycle" => Box::new(NsLifecycleCollector::new()?),
+                "sock" => Box::new(SockCollector::new()?),
+                "offload" => Box::new(OffloadCollector::new()?),
+                "mem" => Box::new(MemCollector::new()?),
+                "xsk" => Box::new(XskCollector::new()?),
+                "mptcp" => Box::new(MptcpCollector::new()?),
+                "route" => Box::new(RouteCollector::new()?),
+                "neigh" => Box::new(NeighCollector::new()?),
+                "external" => Box::new(ExternalCollector::new()?),
                 _ => bail!("Unknown collector {name}"),
             };
 
             let required = collect.collectors.iter().any(|c| c == *name);
-            if !auto && !required {
+            if (!auto && !required) || collect.exclude_collectors.iter().any(|c| c == *name) {
                 continue;
             }
 
@@ -398,27 +607,157 @@ impl Collectors {
         }
 
         // Setup user defined probes.
+        let container = match collect.probes.iter().any(|p| p.starts_with("usdt:")) {
+            true => collect.container()?,
+            false => None,
+        };
+        let skipped = Cell::new(0);
         let filter = |symbol: &Symbol| {
             // Skip probes not being compatible with the loaded collectors.
-            let ok = self.known_kernel_types.iter().any(|t| {
-                symbol
-                    .parameter_offset(t)
-                    .is_ok_and(|offset| offset.is_some())
-            });
+            let ok = self
+                .known_kernel_types
+                .iter()
+                .any(|t| symbol_provides(symbol, t));
             if !ok {
-                info!(
+                skipped.set(skipped.get() + 1);
+                debug!(
                     "No probe was attached to {symbol} as no collector could retrieve data from it"
                 );
             }
             ok
         };
         collect.probes.iter().try_for_each(|p| -> Result<()> {
-            probe_from_cli(p, filter)?
+            if let Some(usdt) = CliUsdtProbe::parse(p)? {
+                let proc = match usdt.target {
+                    CliUsdtTarget::Path(path) => Process::all(&path)?,
+                    CliUsdtTarget::Pid(pid) => Process::from_pid(match &container {
+                        Some(container) => container.host_pid(pid)?,
+                        None => pid,
+                    })?,
+                    // Probes are attached once the collection started, to
+                    // also cover processes started later on.
+                    CliUsdtTarget::Cmd(cmd) => {
+                        self.usdt_watcher
+                            .watch(&cmd, &usdt.probe, container.clone());
+                        return Ok(());
+                    }
+                    // Probes are attached to the libraries themselves, which
+                    // covers all processes using them.
+                    CliUsdtTarget::Lib(lib) => {
+                        let libs = libs_from_name(&lib, container.as_ref())?;
+                        if libs.is_empty() {
+                            debug!("No library named {lib} is loaded, using it as a command name");
+                            self.usdt_watcher
+                                .watch(&lib, &usdt.probe, container.clone());
+                            return Ok(());
+                        }
+
+                        let mut found = false;
+                        for path in libs.iter() {
+                            let proc = Process::all(
+                                path.to_str()
+                                    .ok_or_else(|| anyhow!("Invalid library path"))?,
+                            )?;
+                            if !proc.is_usdt(&usdt.probe)? {
+                                debug!("No USDT {} in {}", usdt.probe, path.display());
+                                continue;
+                            }
+
+                            self.probes.builder_mut()?.register_probe(Probe::usdt(
+                                UsdtProbe::new(&proc, &usdt.probe)?,
+                            )?)?;
+                            found = true;
+                        }
+
+                        if !found {
+                            bail!("Could not find USDT {} in {lib}", usdt.probe);
+                        }
+                        return Ok(());
+                    }
+                };
+
+                if !proc.is_usdt(&usdt.probe)? {
+                    bail!(
+                        "Could not find USDT {} in {}",
+                        usdt.probe,
+                        proc.path().display()
+                    );
+                }
+                return self
+                    .probes
+                    .builder_mut()?
+                    .register_probe(Probe::usdt(UsdtProbe::new(&proc, &usdt.probe)?)?);
+            }
+
+            skipped.set(0);
+            let mut probes = probe_from_cli(p, filter)?;
+
+            if skipped.get() > 0 {
+                info!(
+                    "{p}: {} target(s) skipped as no collector could retrieve data from them",
+                    skipped.get()
+                );
+            }
+            if p.contains('*') {
+                info!("{p}: {} probe(s) will be attached", probes.len());
+                confirm_probes(p, probes.len(), collect.probe_max, collect.yes)?;
+            }
+
+            probes
                 .drain(..)
                 .try_for_each(|p| self.probes.builder_mut()?.register_probe(p))
         })
     }
 
+    /// Handle a runtime control request, returning a message for the client.
+    fn handle_ctl_request(&mut self, req: CtlRequest, collect: &Collect) -> Result<String> {
+        let (probe, add) = match &req {
+            CtlRequest::ProbeAdd(probe) => (probe, true),
+            CtlRequest::ProbeDel(probe) => (probe, false),
+        };
+        if probe.starts_with("usdt:") {
+            bail!("USDT probes can't be managed at runtime");
+        }
+
+        let runtime = self.probes.runtime_mut()?;
+        let probes = if add {
+            let probes = probe_from_cli(probe, |symbol: &Symbol| {
+                self.known_kernel_types
+                    .iter()
+                    .any(|t| symbol_provides(symbol, t))
+            })?;
+            if collect.probe_max != 0 && probes.len() > collect.probe_max {
+                bail!(
+                    "{probe} matches {} targets, which is more than --probe-max ({})",
+                    probes.len(),
+                    collect.probe_max
+                );
+            }
+            probes
+        } else {
+            let attached = runtime.attached_probes();
+            probe_from_cli(probe, |_| true)?
+                .into_iter()
+                .filter(|p| attached.contains(&p.key()))
+                .collect()
+        };
+
+        if probes.is_empty() {
+            bail!("No probe matching {probe}");
+        }
+        let count = probes.len();
+
+        #[cfg(not(test))]
+        match add {
+            true => runtime.enable_probes(probes)?,
+            false => runtime.disable_probes(probes)?,
+        }
+
+        let msg = format!("{count} probe(s) {}", if add { "added" } else { "removed" });
+        info!("{probe}: {msg}");
+        Ok(msg)
+    }
+
     /// Start the event retrieval for all collectors by calling
     /// their `start()` function.
     #[cfg_attr(test, allow(unused_mut))]
@@ -452,6 +791,25 @@ impl Collectors {
         let probes = std::mem::take(&mut self.probes);
         let _ = std::mem::replace(&mut self.probes, probes.into_runtime()?);
 
+        // Allow querying the probe and filter statistics live, by sending
+        // SIGUSR1.
+        #[cfg(not(test))]
+        {
+            let report_probes = self.probes.runtime()?.probe_stats_reporter()?;
+            let report_filters = self.probes.runtime()?.filter_stats_reporter()?;
+            let mut sigs = signal_hook::iterator::Signals::new([signal_hook::consts::SIGUSR1])?;
+            std::thread::spawn(move || {
+                for _ in sigs.forever() {
+                    if let Err(e) = report_probes() {
+                        warn!("Could not report probe statistics: {e}");
+                    }
+                    if let Err(e) = report_filters() {
+                        warn!("Could not report filter statistics: {e}");
+                    }
+                }
+            });
+        }
+
         for (name, c) in &mut self.collectors {
             debug!("Starting collector {name}");
             if let Err(e) = c.start() {
@@ -478,6 +836,10 @@ impl Collectors {
             Default::default()
         };
         self.factory.config_logger(formatter, offset);
+        if let Some(size) = collect.events_buffer {
+            let size = self.factory.set_buffer_size(size)?;
+            info!("Using a {size} bytes events buffer");
+        }
         self.start_collectors(section_factories)?;
 
         Ok(())
@@ -489,6 +851,7 @@ impl Collectors {
     fn stop(&mut self) -> Result<()> {
         self.probes.runtime_mut()?.detach()?;
         self.probes.runtime_mut()?.report_counters()?;
+        self.probes.runtime_mut()?.report_filter_stats()?;
 
         for (name, c) in &mut self.collectors {
             debug!("Stopping collector {name}");
@@ -515,11 +878,12 @@ impl Collectors {
     /// (e.g. ctrl+c), then return after properly cleaning up. This is the main
     /// collector cmd loop.
     pub(super) fn process(&mut self, collect: &Collect, main_config: &MainConfig) -> Result<()> {
-        let mut printers = Vec::new();
+        // Events are fanned out to all sinks, processing them concurrently.
+        let mut sinks = MultiSink::new();
 
         // Write events to stdout if we don't write to a file (--out) or if
         // explicitly asked to (--print).
-        if collect.out.is_none() || collect.print {
+        if collect.out.is_empty() || collect.print {
             let format = DisplayFormat::new()
                 .multiline(collect.format == CliDisplayFormat::MultiLine)
                 .time_format(if collect.utc {
@@ -528,31 +892,138 @@ impl Collectors {
                     TimeFormat::MonotonicTimestamp
                 })
                 .monotonic_offset(self.monotonic_offset)
-                .print_ll(collect.print_ll);
+                .print_ll(collect.print_ll)
+                .colors(main_config.colors);
+
+            sinks.add(
+                "stdout",
+                Box::new(PrintEvent::new(
+                    Box::new(io::stdout()),
+                    PrintEventFormat::Text(format),
+                )),
+            )?;
+        }
+
+        // Collection information, stored along the events.
+        let mut probes = self.probes.runtime()?.attached_probes();
+        probes.sort();
+        let mut collection = CollectionInfo {
+            start_time: monotonic_timestamp()?,
+            probes,
+            filters: Self::filters_info(collect),
+            containers: None,
+            kube: None,
+        };
+        if collect.enrich_containers || collect.kube {
+            let containers = runtime_containers();
+            info!("{} container(s) found", containers.len());
+
+            if collect.kube {
+                let kube = kube_info(collect.kubeconfig.as_deref(), &containers)
+                    .context("Could not retrieve the Kubernetes objects")?;
+                info!(
+                    "{} pod(s), {} service(s) and {} node(s) found",
+                    kube.pods.len(),
+                    kube.services.len(),
+                    kube.nodes.len()
+                );
+                collection.kube = Some(kube);
+            }
+            collection.containers = Some(containers);
+        }
 
-            printers.push(PrintEvent::new(
-                Box::new(io::stdout()),
-                PrintEventFormat::Text(format),
-            ));
+        // Split the outputs between streams and files.
+        let mut out = None;
+        for target in collect.out.iter() {
+            match StreamTarget::from_url(target)? {
+                Some(stream) => sinks.add(
+                    target,
+                    Box::new(PrintEvent::new(
+                        Box::new(StreamWriter::new(
+                            stream,
+                            &main_config.cmdline,
+                            self.monotonic_offset,
+                            Some(collection.clone()),
+                        )?),
+                        PrintEventFormat::Json,
+                    )),
+                )?,
+                None if out.is_some() => bail!("Only a single output file can be used"),
+                None => out = Some(PathBuf::from(target)),
+            }
+        }
+        if out.is_none()
+            && (collect.out_rotate.is_some()
+                || collect.compress
+                || collect.out_index
+                || collect.flight_recorder.is_some())
+        {
+            bail!("--out-rotate, --compress, --out-index and --flight-recorder require an output file");
         }
 
+        // In flight recorder mode, events are only written to the output
+        // file when a trigger fires.
+        let mut dump = None;
+        let triggers = match collect.recorder_triggers.is_empty() {
+            true => vec![DumpTrigger::Signal],
+            false => collect.recorder_triggers.clone(),
+        };
+
         // Write the events to a file if asked to.
-        if let Some(out) = collect.out.as_ref() {
-            printers.push(PrintEvent::new(
-                Box::new(
-                    RotateWriter::new(
-                        out,
-                        match &collect.out_rotate {
-                            Some(s) => Some(rotation_policy_from_str(s)?),
-                            None => None,
-                        },
-                        &main_config.cmdline,
-                        self.monotonic_offset,
-                    )
-                    .or_else(|e| bail!("Could not create or open '{}': {e}", out.display()))?,
-                ),
-                PrintEventFormat::Json,
-            ));
+        if let Some(out) = out.as_ref() {
+            let (policy, keep) = match &collect.out_rotate {
+                Some(s) => {
+                    let (policy, keep) = rotation_from_str(s)?;
+                    (Some(policy), keep)
+                }
+                None => (None, None),
+            };
+            let format = match collect.out_format {
+                CliOutFormat::Json => file::EventFormat::Json,
+                CliOutFormat::Protobuf => file::EventFormat::Protobuf,
+            };
+            let writer: Box<dyn Write + Send> = Box::new(
+                RotateWriter::new(
+                    out,
+                    policy,
+                    collect.compress,
+                    format,
+                    &main_config.cmdline,
+                    self.monotonic_offset,
+                    Some(collection),
+                )
+                .or_else(|e| bail!("Could not create or open '{}': {e}", out.display()))?
+                .keep(keep)
+                .index(collect.out_index),
+            );
+
+            let sink: Box<dyn EventSink> = match collect.flight_recorder {
+                Some(limit) => {
+                    let recorder = RecorderWriter::new(writer, limit);
+                    let handle = recorder.dump_handle();
+                    if triggers.contains(&DumpTrigger::Signal) {
+                        signal_hook::flag::register(signal_hook::consts::SIGUSR2, handle.clone())?;
+                    }
+                    dump = Some(handle.clone());
+
+                    info!(
+                        "Flight recorder mode, events are written to {} on: {}",
+                        out.display(),
+                        triggers
+                            .iter()
+                            .map(|t| t.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    Box::new(TriggerSink::new(
+                        Box::new(PrintEvent::new(Box::new(recorder), format.into())),
+                        triggers,
+                        handle,
+                    ))
+                }
+                None => Box::new(PrintEvent::new(writer, format.into())),
+            };
+            sinks.add(&out.display().to_string(), sink)?;
         }
 
         if let Some(cmd) = collect.cmd.to_owned() {
@@ -578,15 +1049,63 @@ impl Collectors {
         let (mut iccount, mut eccount) = (0, 0);
         let mut probe_stack = ProbeStack::new(self.known_kernel_types.clone());
         let stop_count = collect.stop_after.unwrap_or_default();
+        let mut until = StopConditions::new(collect.until.clone())?;
+        let stop_on = {
+            let (run, dump) = (self.run.clone(), dump.clone());
+            move |cond: &StopCondition| {
+                // Write the events kept by the flight recorder, if any.
+                if let Some(dump) = dump.as_ref() {
+                    dump.store(true, Ordering::Relaxed);
+                }
+                run.terminate();
+                info!("Condition met ({cond}), terminating...");
+            }
+        };
+
+        let mut ctl = match &collect.ctl_socket {
+            Some(path) => {
+                info!("Listening for control requests on {}", path.display());
+                Some(CtlServer::bind(path)?)
+            }
+            None => None,
+        };
+
+        // Events lost so far, reported as internal events in lossless mode.
+        let (mut lost, mut lost_check) = (0, Instant::now());
 
         use EventResult::*;
         while self.run.running() {
+            // Attach USDT probes to newly started processes, if needed.
+            if let Err(e) = self.usdt_watcher.poll(self.probes.runtime_mut()?) {
+                warn!("Could not look for new processes to probe: {e}");
+            }
+
+            // Handle runtime control requests, if any.
+            if let Some(ctl) = ctl.as_mut() {
+                if let Err(e) = ctl.poll(|req| self.handle_ctl_request(req, collect)) {
+                    warn!("Could not handle control requests: {e}");
+                }
+            }
+
+            // Report events lost since the last check, if any.
+            if collect.lossless && lost_check.elapsed() >= LOST_EVENTS_INTERVAL {
+                lost_check = Instant::now();
+
+                let total = self.probes.runtime()?.lost_events()?;
+                if total > lost {
+                    let count = total - lost;
+                    self.events_factory.add_event(|e| {
+                        e.lost = Some(LostEvent { count });
+                        Ok(())
+                    })?;
+                    lost = total;
+                }
+            }
+
             // First always try to dequeue all Retis events. This is not a
             // blocking call.
             while let Some(event) = self.events_factory.next_event() {
-                printers
-                    .iter_mut()
-                    .try_for_each(|p| p.process_one(&event))?;
+                sinks.process_one(event)?;
                 iccount += 1;
             }
 
@@ -597,24 +1116,57 @@ impl Collectors {
                         probe_stack.process_event(self.probes.runtime_mut()?, &mut event)?;
                     }
 
-                    printers
-                        .iter_mut()
-                        .try_for_each(|p| p.process_one(&event))?;
+                    let met = until.process(&event).cloned();
+                    sinks.process_one(*event)?;
                     eccount += 1;
 
                     if stop_count > 0 && eccount >= stop_count {
                         self.run.terminate();
                         info!("Reached stop count ({stop_count}), terminating...");
                     }
+                    if let Some(cond) = met {
+                        stop_on(&cond);
+                    }
+                }
+                Timeout => {
+                    // Handle dumps requested by a signal while no event was
+                    // received.
+                    if dump.as_ref().is_some_and(|d| d.load(Ordering::Relaxed)) {
+                        sinks.flush()?;
+                    }
                 }
-                Timeout => continue,
+            }
+
+            if let Some(cond) = until.expired() {
+                stop_on(cond);
             }
         }
 
-        printers.iter_mut().try_for_each(|p| p.flush())?;
+        sinks.finish()?;
         info!("{eccount} event(s) processed");
         debug!("{iccount} internal event(s) processed");
 
         self.stop()
     }
 }
+
+/// Ask for confirmation before attaching a number of probes exceeding the
+/// configured maximum.
+fn confirm_probes(probe: &str, count: usize, max: usize, yes: bool) -> Result<()> {
+    if max == 0 || count <= max || yes {
+        return Ok(());
+    }
+
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        bail!("{probe} matches {count} targets, which is more than --probe-max ({max}). Use --yes to confirm or refine the probe.");
+    }
+
+    eprint!("{probe} matches {count} targets, attach them all? [y/N] ");
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => bail!("Aborted, {probe} was not confirmed"),
+    }
+}
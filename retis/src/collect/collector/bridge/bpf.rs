@@ -0,0 +1,106 @@
+use anyhow::Result;
+
+use crate::{
+    bindings::bridge_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string, raw_to_string_opt,
+};
+
+/// Bridge event types. Keep in sync with their BPF counterparts in
+/// bpf/bridge_hook.bpf.c
+pub(super) const BRIDGE_EV_FORWARD: u8 = 0;
+pub(super) const BRIDGE_EV_FLOOD: u8 = 1;
+pub(super) const BRIDGE_EV_LOCAL: u8 = 2;
+pub(super) const BRIDGE_EV_LEARN: u8 = 3;
+pub(super) const BRIDGE_EV_STP: u8 = 4;
+
+#[event_section_factory(FactoryId::Bridge)]
+#[derive(Default)]
+pub(crate) struct BridgeEventFactory {}
+
+impl RawEventSectionFactory for BridgeEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<bridge_event>(&raw_sections)?;
+        let mut bridge = BridgeEvent {
+            bridge: raw_to_string_opt!(&raw.bridge)?,
+            ..Default::default()
+        };
+
+        if raw.has_port == 1 {
+            bridge.port = Some(BridgePort {
+                name: raw_to_string!(&raw.port)?,
+                no: raw.port_no,
+                state: state_str(raw.state),
+            });
+        }
+
+        bridge.op = match raw.type_ {
+            BRIDGE_EV_FORWARD => "forward".to_string(),
+            BRIDGE_EV_FLOOD => {
+                bridge.pkt_type = Some(pkt_type_str(raw.pkt_type));
+                "flood".to_string()
+            }
+            BRIDGE_EV_LOCAL => "local".to_string(),
+            BRIDGE_EV_LEARN => {
+                bridge.mac = Some(mac_str(&raw.addr));
+                bridge.vid = Some(raw.vid).filter(|vid| *vid != 0);
+                "learn".to_string()
+            }
+            BRIDGE_EV_STP => "stp".to_string(),
+            x => x.to_string(),
+        };
+
+        event.bridge = Some(bridge);
+        Ok(())
+    }
+}
+
+/// Get the name of an STP port state, see BR_STATE_* in
+/// include/uapi/linux/if_bridge.h.
+fn state_str(state: u8) -> String {
+    match state {
+        0 => "disabled".to_string(),
+        1 => "listening".to_string(),
+        2 => "learning".to_string(),
+        3 => "forwarding".to_string(),
+        4 => "blocking".to_string(),
+        x => x.to_string(),
+    }
+}
+
+/// Get the name of a packet type, see `enum br_pkt_type` in
+/// net/bridge/br_private.h.
+fn pkt_type_str(pkt_type: u8) -> String {
+    match pkt_type {
+        0 => "unicast".to_string(),
+        1 => "multicast".to_string(),
+        2 => "broadcast".to_string(),
+        x => x.to_string(),
+    }
+}
+
+/// Format a MAC address.
+fn mac_str(addr: &[u8; 6]) -> String {
+    addr.iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bridge_mac() {
+        assert_eq!(
+            mac_str(&[0x0a, 0x58, 0x0a, 0xf4, 0x00, 0x01]),
+            "0a:58:0a:f4:00:01"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define BRIDGE_EV_FORWARD	0
+#define BRIDGE_EV_FLOOD		1
+#define BRIDGE_EV_LOCAL		2
+#define BRIDGE_EV_LEARN		3
+#define BRIDGE_EV_STP		4
+
+BINDING_DEF(IFNAMSIZ, 16)
+
+#define retis_get_bridge_port(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, port, struct net_bridge_port___bridge *)
+#define retis_get_bridge(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, bridge, struct net_bridge___bridge *)
+#define retis_get_bridge_pkt_type(ctx, cfg)	\
+	RETIS_HOOK_GET(ctx, cfg->offsets, pkt_type, int)
+#define retis_get_bridge_state(ctx, cfg)	\
+	RETIS_HOOK_GET(ctx, cfg->offsets, state, unsigned int)
+#define retis_get_bridge_addr(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, addr, const unsigned char *)
+#define retis_get_bridge_vid(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, vid, u16)
+
+/* Only the fields we report are needed. */
+struct net_bridge___bridge {
+	struct net_device *dev;
+} __attribute__((preserve_access_index));
+
+struct net_bridge_port___bridge {
+	struct net_bridge___bridge *br;
+	struct net_device *dev;
+	u8 state;
+	u16 port_no;
+} __attribute__((preserve_access_index));
+
+/**
+ * Bridge hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct bridge_offsets {
+	s8 port;
+	s8 bridge;
+	s8 pkt_type;
+	s8 state;
+	s8 addr;
+	s8 vid;
+};
+struct bridge_config {
+	struct bridge_offsets offsets;
+	u8 type;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct bridge_config);
+} bridge_config_map SEC(".maps");
+
+struct bridge_event {
+	u8 type;
+	/* Set if the port fields below are valid. */
+	u8 has_port;
+	/* STP state of the port, new one on STP events. */
+	u8 state;
+	/* Packet type (unicast, multicast or broadcast) on floods. */
+	u8 pkt_type;
+	u16 port_no;
+	u16 vid;
+	u8 addr[6];
+	char port[IFNAMSIZ];
+	char bridge[IFNAMSIZ];
+} __binding;
+
+static __always_inline void bridge_report_dev(char *name, struct net_device *dev)
+{
+	if (dev)
+		bpf_probe_read(name, IFNAMSIZ, dev->name);
+}
+
+DEFINE_HOOK_RAW(
+	struct net_bridge_port___bridge *p;
+	struct net_bridge___bridge *br;
+	struct bridge_config *cfg;
+	struct bridge_event *e;
+	const unsigned char *addr;
+	struct sk_buff *skb;
+	u32 key = 0;
+
+	/* Probes having access to an skb (forwarding decisions) are subject to
+	 * filtering, others (learning and STP) are not.
+	 */
+	skb = retis_get_sk_buff(ctx);
+	if (skb && !RETIS_TRACKABLE(ctx))
+		return 0;
+
+	cfg = bpf_map_lookup_elem(&bridge_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	p = retis_get_bridge_port(ctx, cfg);
+	br = retis_get_bridge(ctx, cfg);
+	if (!br && p)
+		br = BPF_CORE_READ(p, br);
+
+	/* Frames delivered locally: get the bridge from the ingress port, see
+	 * br_port_get_rcu().
+	 */
+	if (!br && cfg->type == BRIDGE_EV_LOCAL && skb) {
+		struct net_bridge_port___bridge *in;
+
+		in = BPF_CORE_READ(skb, dev, rx_handler_data);
+		if (in)
+			br = BPF_CORE_READ(in, br);
+	}
+
+	e = get_event_zsection(event, COLLECTOR_BRIDGE, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->type = cfg->type;
+
+	if (br)
+		bridge_report_dev(e->bridge, BPF_CORE_READ(br, dev));
+
+	if (p) {
+		e->has_port = 1;
+		e->state = BPF_CORE_READ(p, state);
+		e->port_no = BPF_CORE_READ(p, port_no);
+		bridge_report_dev(e->port, BPF_CORE_READ(p, dev));
+	}
+
+	switch (cfg->type) {
+	case BRIDGE_EV_FLOOD:
+		e->pkt_type = retis_get_bridge_pkt_type(ctx, cfg);
+		break;
+	case BRIDGE_EV_LEARN:
+		addr = retis_get_bridge_addr(ctx, cfg);
+		if (addr)
+			bpf_probe_read_kernel(e->addr, sizeof(e->addr), addr);
+		e->vid = retis_get_bridge_vid(ctx, cfg);
+		break;
+	case BRIDGE_EV_STP:
+		e->state = retis_get_bridge_state(ctx, cfg);
+		break;
+	}
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, bridge_hook};
+use crate::{
+    bindings::bridge_hook_uapi::bridge_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Frames forwarded to a known port.
+const BR_FORWARD: &str = "br_forward";
+/// Frames flooded to all ports.
+const BR_FLOOD: &str = "br_flood";
+/// Frames delivered to the bridge device itself. Might be inlined.
+const BR_LOCAL: &str = "br_pass_frame_up";
+/// Learned (new or moved) addresses.
+const BR_TP_LEARN: &str = "bridge:br_fdb_update";
+/// STP state changes.
+const BR_STP: &str = "br_set_state";
+
+#[derive(Default)]
+pub(crate) struct BridgeCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for BridgeCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name(BR_FORWARD) {
+            bail!("Could not resolve bridge kernel symbol (is the bridge module loaded?): {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let sym = Symbol::from_name(BR_FORWARD)?;
+        let cfg = Self::config(&sym, BRIDGE_EV_FORWARD)?;
+        self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+
+        if let Ok(sym) = Symbol::from_name(BR_FLOOD) {
+            let mut cfg = Self::config(&sym, BRIDGE_EV_FLOOD)?;
+            if let Some(offset) = sym.parameter_offset("enum br_pkt_type")? {
+                cfg.offsets.pkt_type = offset as i8;
+            }
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(BR_LOCAL) {
+            let cfg = Self::config(&sym, BRIDGE_EV_LOCAL)?;
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(BR_TP_LEARN) {
+            let mut cfg = Self::config(&sym, BRIDGE_EV_LEARN)?;
+            if let Some(offset) = sym.parameter_offset("unsigned char *")? {
+                cfg.offsets.addr = offset as i8;
+            }
+            if let Some(offset) = sym.parameter_offset("u16")? {
+                cfg.offsets.vid = offset as i8;
+            }
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(BR_STP) {
+            let mut cfg = Self::config(&sym, BRIDGE_EV_STP)?;
+            if let Some(offset) = sym.parameter_offset("unsigned int")? {
+                cfg.offsets.state = offset as i8;
+            }
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl BridgeCollector {
+    /// Build the hook configuration of a given probe.
+    fn config(sym: &Symbol, r#type: u8) -> Result<bridge_config> {
+        let mut cfg = bridge_config {
+            type_: r#type,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset("struct net_bridge_port *")? {
+            cfg.offsets.port = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct net_bridge *")? {
+            cfg.offsets.bridge = offset as i8;
+        }
+        Ok(cfg)
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: bridge_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/bridge_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(bridge_hook::DATA),
+            "bridge_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+//! # Bridge module
+//!
+//! Provides support for tracing the Linux bridge datapath: forwarding
+//! decisions, address learning and STP state changes.
+
+// Re-export bridge.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod bridge;
+pub(crate) use bridge::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::BridgeEventFactory;
+
+mod bridge_hook {
+    include!("bpf/.out/bridge_hook.rs");
+}
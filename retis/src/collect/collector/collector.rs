@@ -2,7 +2,12 @@ use anyhow::Result;
 
 use crate::{
     collect::{
-        collector::{ct::*, dev::*, nft::*, ns::*, ovs::*, skb::*, skb_drop::*, skb_tracking::*},
+        collector::{
+            bridge::*, ct::*, dev::*, external::*, gro_gso::*, icmp_error::*, lag::*, mem::*,
+            mptcp::*, neigh::*, nf::*, nft::*, ns::*, ns_lifecycle::*, offload::*, ovs::*,
+            route::*, skb::*, skb_drop::*, skb_tracking::*, sock::*, tc::*, tcp::*, tunnel::*,
+            wireguard::*, xdp::*, xfrm::*, xsk::*,
+        },
         Collector,
     },
     core::{
@@ -29,6 +34,35 @@ pub(crate) fn section_factories() -> Result<SectionFactories> {
     factories.insert(FactoryId::Ct, Box::new(CtEventFactory::new()?));
     factories.insert(FactoryId::Dev, Box::<DevEventFactory>::default());
     factories.insert(FactoryId::Ns, Box::new(NsEventFactory::new()?));
+    factories.insert(FactoryId::Nf, Box::<NfEventFactory>::default());
+    factories.insert(FactoryId::Tc, Box::<TcEventFactory>::default());
+    factories.insert(FactoryId::Xdp, Box::<XdpEventFactory>::default());
+    factories.insert(FactoryId::Xfrm, Box::<XfrmEventFactory>::default());
+    factories.insert(FactoryId::Tunnel, Box::<TunnelEventFactory>::default());
+    factories.insert(FactoryId::Tcp, Box::new(TcpEventFactory::new()?));
+    factories.insert(FactoryId::GroGso, Box::<GroGsoEventFactory>::default());
+    factories.insert(FactoryId::Bridge, Box::<BridgeEventFactory>::default());
+    factories.insert(FactoryId::Lag, Box::<LagEventFactory>::default());
+    factories.insert(
+        FactoryId::Wireguard,
+        Box::<WireguardEventFactory>::default(),
+    );
+    factories.insert(
+        FactoryId::IcmpError,
+        Box::<IcmpErrorEventFactory>::default(),
+    );
+    factories.insert(
+        FactoryId::NsLifecycle,
+        Box::<NsLifecycleEventFactory>::default(),
+    );
+    factories.insert(FactoryId::Sock, Box::<SockEventFactory>::default());
+    factories.insert(FactoryId::Offload, Box::new(OffloadEventFactory::new()?));
+    factories.insert(FactoryId::Mem, Box::<MemEventFactory>::default());
+    factories.insert(FactoryId::Xsk, Box::<XskEventFactory>::default());
+    factories.insert(FactoryId::Mptcp, Box::<MptcpEventFactory>::default());
+    factories.insert(FactoryId::Route, Box::<RouteEventFactory>::default());
+    factories.insert(FactoryId::Neigh, Box::<NeighEventFactory>::default());
+    factories.insert(FactoryId::External, Box::<ExternalEventFactory>::default());
 
     Ok(factories)
 }
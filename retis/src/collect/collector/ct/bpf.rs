@@ -6,7 +6,7 @@ use anyhow::{anyhow, bail, Result};
 use std::net::Ipv6Addr;
 
 use crate::{
-    bindings::ct_hook_uapi::*,
+    bindings::{ct_common_uapi::*, ct_lifecycle_hook_uapi::*},
     core::{
         events::{
             parse_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
@@ -19,6 +19,11 @@ use crate::{
     helpers,
 };
 
+/// Connection status bits, see enum ip_conntrack_status in
+/// include/uapi/linux/netfilter/nf_conntrack_common.h.
+const IPS_SRC_NAT: u64 = 1 << 4;
+const IPS_DST_NAT: u64 = 1 << 5;
+
 #[event_section_factory(FactoryId::Ct)]
 #[derive(Default)]
 pub(crate) struct CtEventFactory {
@@ -29,6 +34,15 @@ pub(crate) struct CtEventFactory {
 
 impl RawEventSectionFactory for CtEventFactory {
     fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        // Lifecycle events are not linked to a packet and have their own
+        // section.
+        if let Some(raw_section) = raw_sections
+            .iter()
+            .find(|s| s.header.data_type as u32 == SECTION_LIFECYCLE)
+        {
+            return self.create_lifecycle(raw_section, &raw_sections, event);
+        }
+
         let mut ct = CtEvent {
             state: {
                 let raw = parse_raw_section::<ct_meta_event>(
@@ -103,6 +117,44 @@ impl CtEventFactory {
         Ok(me)
     }
 
+    fn create_lifecycle(
+        &mut self,
+        raw_section: &BpfRawSection,
+        raw_sections: &[BpfRawSection],
+        event: &mut Event,
+    ) -> Result<()> {
+        let raw = parse_raw_section::<ct_lifecycle_event>(raw_section)?;
+
+        let op = match raw.type_ as ct_lifecycle_type {
+            CT_LIFECYCLE_CREATE => "create",
+            CT_LIFECYCLE_CONFIRM => "confirm",
+            CT_LIFECYCLE_TIMEOUT => "timeout",
+            CT_LIFECYCLE_DESTROY => "destroy",
+            x => bail!("ct: unsupported lifecycle event type {x}"),
+        };
+
+        let mut ct = CtLifecycleEvent {
+            op: op.to_string(),
+            conn: self.unmarshal_ct(
+                raw_sections
+                    .iter()
+                    .find(|s| s.header.data_type as u32 == SECTION_BASE_CONN)
+                    .ok_or_else(|| anyhow!("CT BPF event does not have a base section"))?,
+            )?,
+            parent: None,
+        };
+
+        if let Some(raw_section) = raw_sections
+            .iter()
+            .find(|s| s.header.data_type as u32 == SECTION_PARENT_CONN)
+        {
+            ct.parent = Some(self.unmarshal_ct(raw_section)?);
+        }
+
+        event.ct_lifecycle = Some(ct);
+        Ok(())
+    }
+
     pub(super) fn unmarshal_ct(&mut self, raw_section: &BpfRawSection) -> Result<CtConnEvent> {
         let raw = parse_raw_section::<ct_event>(raw_section)?;
         let flags = raw.flags;
@@ -237,18 +289,22 @@ impl CtEventFactory {
 
         let labels = U128::from_u128(u128::from_ne_bytes(raw.labels));
 
+        let orig = CtTuple {
+            ip: orig_ip,
+            proto: orig_proto,
+        };
+        let reply = CtTuple {
+            ip: reply_ip,
+            proto: reply_proto,
+        };
+
         Ok(CtConnEvent {
+            nat: ct_nat(raw.status, &orig, &reply),
             ct_status: raw.status,
             zone_id: raw.zone_id,
             zone_dir,
-            orig: CtTuple {
-                ip: orig_ip,
-                proto: orig_proto,
-            },
-            reply: CtTuple {
-                ip: reply_ip,
-                proto: reply_proto,
-            },
+            orig,
+            reply,
             proto_state,
             mark: if self.mark_available {
                 Some(raw.mark)
@@ -264,6 +320,89 @@ impl CtEventFactory {
     }
 }
 
+/// Source and destination ports of a tuple, for protocols using ports.
+fn tuple_ports(tuple: &CtTuple) -> (Option<u16>, Option<u16>) {
+    match &tuple.proto {
+        CtProto::Tcp { tcp } => (Some(tcp.sport), Some(tcp.dport)),
+        CtProto::Udp { udp } => (Some(udp.sport), Some(udp.dport)),
+        CtProto::Sctp { sctp } => (Some(sctp.sport), Some(sctp.dport)),
+        CtProto::Icmp { .. } => (None, None),
+    }
+}
+
+/// Compute the NAT translations of a connection. The reply tuple is the
+/// original one, inverted and with the NAT mangling applied: the source of the
+/// connection is translated to the reply destination, and its destination
+/// to the reply source.
+fn ct_nat(status: u64, orig: &CtTuple, reply: &CtTuple) -> Option<CtNat> {
+    if status & (IPS_SRC_NAT | IPS_DST_NAT) == 0 {
+        return None;
+    }
+
+    let (orig_sport, orig_dport) = tuple_ports(orig);
+    let (reply_sport, reply_dport) = tuple_ports(reply);
+
+    Some(CtNat {
+        src: (status & IPS_SRC_NAT != 0).then(|| CtNatMangle {
+            pre_addr: orig.ip.src.clone(),
+            post_addr: reply.ip.dst.clone(),
+            pre_port: orig_sport,
+            post_port: reply_dport,
+        }),
+        dst: (status & IPS_DST_NAT != 0).then(|| CtNatMangle {
+            pre_addr: orig.ip.dst.clone(),
+            post_addr: reply.ip.src.clone(),
+            pre_port: orig_dport,
+            post_port: reply_sport,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nat() {
+        let tuple = |src: &str, sport, dst: &str, dport| CtTuple {
+            ip: CtIp {
+                src: src.to_string(),
+                dst: dst.to_string(),
+                version: CtIpVersion::V4,
+            },
+            proto: CtProto::Tcp {
+                tcp: CtTcp { sport, dport },
+            },
+        };
+
+        // Masquerade of 10.0.0.1 to 192.168.1.1 for a connection to 1.1.1.1.
+        let orig = tuple("10.0.0.1", 40000, "1.1.1.1", 443);
+        let reply = tuple("1.1.1.1", 443, "192.168.1.1", 50000);
+
+        assert!(ct_nat(0, &orig, &reply).is_none());
+
+        let nat = ct_nat(IPS_SRC_NAT, &orig, &reply).unwrap();
+        assert!(nat.dst.is_none());
+        let src = nat.src.unwrap();
+        assert_eq!(src.pre_addr, "10.0.0.1");
+        assert_eq!(src.post_addr, "192.168.1.1");
+        assert_eq!(src.pre_port, Some(40000));
+        assert_eq!(src.post_port, Some(50000));
+
+        // Port forwarding of 1.1.1.1:8080 to 10.0.0.2:80.
+        let orig = tuple("2.2.2.2", 40000, "1.1.1.1", 8080);
+        let reply = tuple("10.0.0.2", 80, "2.2.2.2", 40000);
+
+        let nat = ct_nat(IPS_DST_NAT, &orig, &reply).unwrap();
+        assert!(nat.src.is_none());
+        let dst = nat.dst.unwrap();
+        assert_eq!(dst.pre_addr, "1.1.1.1");
+        assert_eq!(dst.post_addr, "10.0.0.2");
+        assert_eq!(dst.pre_port, Some(8080));
+        assert_eq!(dst.post_port, Some(80));
+    }
+}
+
 #[cfg(feature = "benchmark")]
 pub(crate) mod benchmark {
     use anyhow::Result;
@@ -0,0 +1,95 @@
+#include <ct_common.h>
+
+/* Keep in sync with include/uapi/linux/netfilter.h */
+#define NF_ACCEPT	1
+
+enum ct_lifecycle_type {
+	CT_LIFECYCLE_CREATE = 0,
+	CT_LIFECYCLE_CONFIRM,
+	CT_LIFECYCLE_TIMEOUT,
+	CT_LIFECYCLE_DESTROY,
+} __binding;
+
+struct ct_lifecycle_config {
+	u8 type;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct ct_lifecycle_config);
+} ct_lifecycle_config_map SEC(".maps");
+
+struct ct_lifecycle_event {
+	u8 type;
+} __binding;
+
+/* See nf_ct_is_expired(). */
+static __always_inline bool ct_is_expired(struct nf_conn *nf_conn)
+{
+	u32 timeout = BPF_CORE_READ(nf_conn, timeout);
+
+	return (s32)(timeout - (u32)bpf_jiffies64()) <= 0;
+}
+
+/* Hook for kretprobe:__nf_conntrack_alloc, kretprobe:__nf_conntrack_confirm,
+ * kprobe:nf_ct_delete and kprobe:nf_ct_destroy (or destroy_conntrack on older
+ * kernels).
+ */
+DEFINE_HOOK_RAW(
+	struct ct_lifecycle_config *cfg;
+	struct ct_lifecycle_event *e;
+	struct nf_conn *nf_conn;
+	struct sk_buff *skb;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&ct_lifecycle_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	switch (cfg->type) {
+	case CT_LIFECYCLE_CREATE:
+		/* See IS_ERR(). */
+		if (!ctx->regs.ret || ctx->regs.ret >= (u64)-4095)
+			return 0;
+		nf_conn = (struct nf_conn *)ctx->regs.ret;
+		break;
+	case CT_LIFECYCLE_CONFIRM:
+		if (ctx->regs.ret != NF_ACCEPT)
+			return 0;
+		skb = (struct sk_buff *)ctx->regs.reg[0];
+		nf_conn = (struct nf_conn *)
+			(BPF_CORE_READ(skb, _nfct) & NFCT_PTRMASK);
+		break;
+	case CT_LIFECYCLE_TIMEOUT:
+		nf_conn = (struct nf_conn *)ctx->regs.reg[0];
+		/* Entries can be deleted for other reasons (flush, TCP reset,
+		 * etc); only report the ones whose timeout expired.
+		 */
+		if (!nf_conn || !ct_is_expired(nf_conn))
+			return 0;
+		break;
+	case CT_LIFECYCLE_DESTROY:
+		/* struct nf_conntrack is the first member of struct nf_conn. */
+		nf_conn = (struct nf_conn *)ctx->regs.reg[0];
+		break;
+	default:
+		return 0;
+	}
+
+	if (!nf_conn)
+		return 0;
+
+	if (ct_fill_conn(event, nf_conn))
+		return 0;
+
+	e = get_event_section(event, COLLECTOR_CT, SECTION_LIFECYCLE,
+			      sizeof(*e));
+	if (!e)
+		return 0;
+	e->type = cfg->type;
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -1,19 +1,51 @@
-use std::sync::Arc;
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd},
+    sync::Arc,
+};
 
 use anyhow::{bail, Result};
+use clap::Parser;
+use libbpf_rs::MapCore;
 
-use super::ct_hook;
+use super::{ct_hook, ct_lifecycle_hook};
 use crate::{
+    bindings::ct_lifecycle_hook_uapi::*,
     collect::{cli::Collect, Collector},
     core::{
         events::*,
         inspect,
-        probe::{Hook, ProbeBuilderManager},
+        kernel::Symbol,
+        probe::{Hook, Probe, ProbeBuilderManager, ProbeOption},
     },
 };
 
+/// Conntrack entry allocation, when a connection is first seen.
+const CT_CREATE: &str = "__nf_conntrack_alloc";
+/// Conntrack entry insertion in the hash table.
+const CT_CONFIRM: &str = "__nf_conntrack_confirm";
+/// Conntrack entry removal from the hash table; only expired entries are
+/// reported.
+const CT_TIMEOUT: &str = "nf_ct_delete";
+/// Conntrack entry being freed. Only the first one found is used, the latter
+/// being used on older kernels.
+const CT_DESTROY_TARGETS: &[&str] = &["nf_ct_destroy", "destroy_conntrack"];
+
+#[derive(Parser, Debug, Default)]
+pub(crate) struct CtCollectorArgs {
+    #[arg(
+        long,
+        help = "Report conntrack entries lifecycle events: creation, confirmation, timeout expiry and destruction. Those events are not linked to packets and are not subject to filtering."
+    )]
+    pub(crate) ct_lifecycle: bool,
+}
+
 #[derive(Default)]
-pub(crate) struct CtCollector {}
+pub(crate) struct CtCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
 
 impl Collector for CtCollector {
     fn new() -> Result<Self> {
@@ -44,12 +76,91 @@ impl Collector for CtCollector {
 
     fn init(
         &mut self,
-        _: &Collect,
+        args: &Collect,
         probes: &mut ProbeBuilderManager,
         _: Arc<RetisEventsFactory>,
         _: &mut SectionFactories,
     ) -> Result<()> {
+        if args.collector_args.ct.ct_lifecycle {
+            self.add_lifecycle_probes(probes)?;
+        }
+
         // Register our generic conntrack hook.
         probes.register_kernel_hook(Hook::from(ct_hook::DATA))
     }
 }
+
+impl CtCollector {
+    fn add_lifecycle_probes(&mut self, probes: &mut ProbeBuilderManager) -> Result<()> {
+        self.add_lifecycle_probe(
+            probes,
+            Probe::kretprobe(Symbol::from_name(CT_CREATE)?)?,
+            CT_LIFECYCLE_CREATE,
+        )?;
+        self.add_lifecycle_probe(
+            probes,
+            Probe::kretprobe(Symbol::from_name(CT_CONFIRM)?)?,
+            CT_LIFECYCLE_CONFIRM,
+        )?;
+        self.add_lifecycle_probe(
+            probes,
+            Probe::kprobe(Symbol::from_name(CT_TIMEOUT)?)?,
+            CT_LIFECYCLE_TIMEOUT,
+        )?;
+
+        match CT_DESTROY_TARGETS
+            .iter()
+            .find_map(|target| Symbol::from_name(target).ok())
+        {
+            Some(sym) => {
+                self.add_lifecycle_probe(probes, Probe::kprobe(sym)?, CT_LIFECYCLE_DESTROY)?
+            }
+            None => bail!("Could not resolve the conntrack destruction function"),
+        }
+
+        Ok(())
+    }
+
+    /// Create the config map of a lifecycle probe, and register it with our
+    /// lifecycle hook.
+    fn add_lifecycle_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        mut probe: Probe,
+        r#type: ct_lifecycle_type,
+    ) -> Result<()> {
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its BPF counterpart in bpf/ct_lifecycle_hook.bpf.c
+        let config_map = libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Array,
+            Some("ct_lifecycle_config_map"),
+            mem::size_of::<u32>() as u32,
+            mem::size_of::<ct_lifecycle_config>() as u32,
+            1,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the ct lifecycle config map: {}", e))?;
+
+        let cfg = ct_lifecycle_config {
+            type_: r#type as u8,
+        };
+        let cfg = unsafe { plain::as_bytes(&cfg) };
+        config_map.update(&0_u32.to_ne_bytes(), cfg, libbpf_rs::MapFlags::empty())?;
+
+        // Lifecycle events are not linked to packets.
+        probe.set_option(ProbeOption::NoGenericHook)?;
+        probe.add_hook(
+            Hook::from(ct_lifecycle_hook::DATA)
+                .reuse_map("ct_lifecycle_config_map", config_map.as_fd().as_raw_fd())?
+                .to_owned(),
+        )?;
+        probes.register_probe(probe)?;
+
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
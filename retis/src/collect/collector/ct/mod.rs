@@ -9,3 +9,7 @@ pub(crate) use bpf::CtEventFactory;
 mod ct_hook {
     include!("bpf/.out/ct_hook.rs");
 }
+
+mod ct_lifecycle_hook {
+    include!("bpf/.out/ct_lifecycle_hook.rs");
+}
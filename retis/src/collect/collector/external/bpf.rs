@@ -0,0 +1,305 @@
+//! Rust<>BPF types definitions for the external module. Unlike the other
+//! collectors, the raw sections layouts are not known at build time but are
+//! described by the external collectors manifests.
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+
+use crate::{
+    core::events::{BpfRawSection, EventSectionFactory, FactoryId, RawEventSectionFactory},
+    event_section_factory,
+    events::*,
+};
+
+/// Type of a field in an external collector raw section.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExternalFieldType {
+    U8,
+    U16,
+    U32,
+    U64,
+    S8,
+    S16,
+    S32,
+    S64,
+    /// Big endian 16-bit integer (e.g. __be16).
+    Be16,
+    /// Big endian 32-bit integer (e.g. __be32).
+    Be32,
+    /// 8-bit boolean.
+    Bool,
+    /// IPv4 address, in network order (e.g. __be32).
+    Ipv4,
+    /// IPv6 address (e.g. u8[16]).
+    Ipv6,
+    /// MAC address (e.g. u8[6]).
+    Mac,
+    /// NUL-terminated string, in a fixed size array (e.g. char[IFNAMSIZ]).
+    Str,
+}
+
+impl ExternalFieldType {
+    /// Name of the type, as used in manifests.
+    fn name(&self) -> &'static str {
+        use ExternalFieldType::*;
+        match self {
+            U8 => "u8",
+            U16 => "u16",
+            U32 => "u32",
+            U64 => "u64",
+            S8 => "s8",
+            S16 => "s16",
+            S32 => "s32",
+            S64 => "s64",
+            Be16 => "be16",
+            Be32 => "be32",
+            Bool => "bool",
+            Ipv4 => "ipv4",
+            Ipv6 => "ipv6",
+            Mac => "mac",
+            Str => "str",
+        }
+    }
+
+    /// Size and alignment of the type, following the C rules for the type
+    /// the field is likely to be defined with in the BPF hook.
+    fn size_align(&self, size: Option<usize>) -> Result<(usize, usize)> {
+        use ExternalFieldType::*;
+        if size.is_some() && *self != Str {
+            bail!("Only str fields can have a size");
+        }
+
+        Ok(match self {
+            U8 | S8 | Bool => (1, 1),
+            U16 | S16 | Be16 => (2, 2),
+            U32 | S32 | Be32 | Ipv4 => (4, 4),
+            U64 | S64 => (8, 8),
+            Ipv6 => (16, 1),
+            Mac => (6, 1),
+            Str => match size {
+                Some(size) if size > 0 => (size, 1),
+                _ => bail!("str fields must have a non-zero size"),
+            },
+        })
+    }
+
+    /// Format the raw value of a field. The data is guaranteed to be of the
+    /// type size.
+    fn format(&self, data: &[u8]) -> Result<String> {
+        use ExternalFieldType::*;
+        Ok(match self {
+            U8 => data[0].to_string(),
+            U16 => u16::from_ne_bytes(data.try_into()?).to_string(),
+            U32 => u32::from_ne_bytes(data.try_into()?).to_string(),
+            U64 => u64::from_ne_bytes(data.try_into()?).to_string(),
+            S8 => (data[0] as i8).to_string(),
+            S16 => i16::from_ne_bytes(data.try_into()?).to_string(),
+            S32 => i32::from_ne_bytes(data.try_into()?).to_string(),
+            S64 => i64::from_ne_bytes(data.try_into()?).to_string(),
+            Be16 => u16::from_be_bytes(data.try_into()?).to_string(),
+            Be32 => u32::from_be_bytes(data.try_into()?).to_string(),
+            Bool => (data[0] != 0).to_string(),
+            Ipv4 => Ipv4Addr::new(data[0], data[1], data[2], data[3]).to_string(),
+            Ipv6 => Ipv6Addr::from(<[u8; 16]>::try_from(data)?).to_string(),
+            Mac => data
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(":"),
+            Str => {
+                let end = data.iter().position(|b| *b == 0).unwrap_or(data.len());
+                String::from_utf8_lossy(&data[..end]).to_string()
+            }
+        })
+    }
+}
+
+/// Definition of a field in an external collector raw section, as found in
+/// its manifest.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ExternalFieldDef {
+    /// Name of the field.
+    pub(crate) name: String,
+    /// Type of the field.
+    #[serde(rename = "type")]
+    pub(crate) kind: ExternalFieldType,
+    /// Size of the field, for types not having a fixed one (str).
+    pub(crate) size: Option<usize>,
+}
+
+/// Layout of an external collector raw section. Fields are laid out as the
+/// members of a C struct (not packed) would be.
+pub(crate) struct ExternalLayout {
+    /// Name of the external collector owning the section.
+    collector: String,
+    /// Fields definitions and their offset in the raw section.
+    fields: Vec<(ExternalFieldDef, usize)>,
+    /// Size of the raw section, including trailing padding.
+    size: usize,
+}
+
+impl ExternalLayout {
+    /// Compute the layout of a raw section given its fields definitions.
+    pub(crate) fn new(collector: &str, defs: &[ExternalFieldDef]) -> Result<Self> {
+        if defs.is_empty() {
+            bail!("No field defined");
+        }
+
+        let mut fields: Vec<(ExternalFieldDef, usize)> = Vec::new();
+        let (mut offset, mut max_align) = (0_usize, 1);
+        for def in defs.iter() {
+            if def.name.is_empty() {
+                bail!("Field names can't be empty");
+            }
+            if fields.iter().any(|(f, _)| f.name == def.name) {
+                bail!("Field {} is defined twice", def.name);
+            }
+
+            let (size, align) = def
+                .kind
+                .size_align(def.size)
+                .map_err(|e| anyhow!("Invalid field {}: {e}", def.name))?;
+            offset = offset.next_multiple_of(align);
+            max_align = max_align.max(align);
+
+            fields.push((def.clone(), offset));
+            offset += size;
+        }
+
+        Ok(Self {
+            collector: collector.to_string(),
+            fields,
+            size: offset.next_multiple_of(max_align),
+        })
+    }
+
+    /// Size of the raw section.
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Unmarshal a raw section following the layout.
+    fn unmarshal(&self, data: &[u8]) -> Result<ExternalSection> {
+        if data.len() < self.size {
+            bail!(
+                "Section of external collector {} is too small ({} < {})",
+                self.collector,
+                data.len(),
+                self.size
+            );
+        }
+
+        Ok(ExternalSection {
+            collector: self.collector.clone(),
+            fields: self
+                .fields
+                .iter()
+                .map(|(def, offset)| {
+                    let (size, _) = def.kind.size_align(def.size)?;
+                    Ok(ExternalField {
+                        name: def.name.clone(),
+                        kind: def.kind.name().to_string(),
+                        value: def.kind.format(&data[*offset..(offset + size)])?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+        })
+    }
+}
+
+#[event_section_factory(FactoryId::External)]
+#[derive(Default)]
+pub(crate) struct ExternalEventFactory {
+    /// Raw sections layouts, indexed by their data type.
+    layouts: HashMap<u8, ExternalLayout>,
+}
+
+impl ExternalEventFactory {
+    /// Register the layout of the raw sections of a given data type.
+    pub(crate) fn register(&mut self, data_type: u8, layout: ExternalLayout) -> Result<()> {
+        if let Some(other) = self.layouts.get(&data_type) {
+            bail!(
+                "Section id {data_type} is already used by external collector {}",
+                other.collector
+            );
+        }
+
+        self.layouts.insert(data_type, layout);
+        Ok(())
+    }
+}
+
+impl RawEventSectionFactory for ExternalEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let mut sections = Vec::new();
+
+        for section in raw_sections.iter() {
+            let data_type = section.header.data_type;
+            match self.layouts.get(&data_type) {
+                Some(layout) => sections.push(layout.unmarshal(section.data)?),
+                None => bail!("Unknown external section id ({data_type})"),
+            }
+        }
+
+        event.external = Some(ExternalEvent { sections });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(name: &str, kind: ExternalFieldType, size: Option<usize>) -> ExternalFieldDef {
+        ExternalFieldDef {
+            name: name.to_string(),
+            kind,
+            size,
+        }
+    }
+
+    #[test]
+    fn external_layout() -> Result<()> {
+        use ExternalFieldType::*;
+
+        // struct { u8 a; __be32 b; char c[3]; u64 d; u8 e[6]; }
+        let layout = ExternalLayout::new(
+            "test",
+            &[
+                def("a", Bool, None),
+                def("b", Ipv4, None),
+                def("c", Str, Some(3)),
+                def("d", S64, None),
+                def("e", Mac, None),
+            ],
+        )?;
+        assert_eq!(layout.size(), 32);
+
+        let mut data = vec![0; 32];
+        data[0] = 1;
+        data[4..8].copy_from_slice(&[10, 0, 0, 1]);
+        data[8..10].copy_from_slice(b"ab");
+        data[16..24].copy_from_slice(&(-42_i64).to_ne_bytes());
+        data[24..30].copy_from_slice(&[0, 1, 2, 0xa, 0xb, 0xc]);
+
+        let section = layout.unmarshal(&data)?;
+        let values: Vec<_> = section.fields.iter().map(|f| f.value.as_str()).collect();
+        assert_eq!(
+            values,
+            ["true", "10.0.0.1", "ab", "-42", "00:01:02:0a:0b:0c"]
+        );
+        assert!(layout.unmarshal(&data[..31]).is_err());
+
+        assert!(ExternalLayout::new("test", &[]).is_err());
+        assert!(ExternalLayout::new("test", &[def("a", Str, None)]).is_err());
+        assert!(ExternalLayout::new("test", &[def("a", U32, Some(4))]).is_err());
+        assert!(ExternalLayout::new("test", &[def("a", U8, None), def("a", U8, None)]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use log::{info, warn};
+use serde::Deserialize;
+
+use super::bpf::*;
+use crate::{
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        probe::{kernel::utils::probe_from_cli, Hook, ProbeBuilderManager},
+    },
+    profiles::version::{ApiVersion, ApiVersionSupport},
+};
+
+/// Version of the external collectors API, see the profiles versioning for
+/// the semantics.
+const EXTERNAL_API_VERSION_STR: &str = "1.0";
+
+#[derive(Parser, Debug, Default)]
+pub(crate) struct ExternalCollectorArgs {
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Load an external collector from its manifest. Can be used multiple times.
+
+External collectors are built out of the Retis tree. Their manifest describes the BPF hook to load, the probes to attach it to and the layout of the data it reports. See the documentation for the manifest format."
+    )]
+    pub(crate) external_collector: Vec<PathBuf>,
+}
+
+/// External collector manifest.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ExternalManifest {
+    /// Version of the external collectors API the collector was written for.
+    pub(crate) version: String,
+    /// Name of the collector, used when displaying its data.
+    pub(crate) name: String,
+    /// Data type of the raw sections reported by the BPF hook, under the
+    /// COLLECTOR_EXTERNAL owner. Must be unique across the external collectors
+    /// loaded at the same time.
+    pub(crate) id: u8,
+    /// Path to the BPF hook object. Relative paths are relative to the
+    /// manifest directory.
+    pub(crate) hook: PathBuf,
+    /// Probes the hook is attached to, using the --probe syntax.
+    pub(crate) probes: Vec<String>,
+    /// Layout of the raw section reported by the hook.
+    pub(crate) fields: Vec<ExternalFieldDef>,
+}
+
+impl ExternalManifest {
+    /// Load a manifest from a file.
+    pub(crate) fn from_file(path: &Path) -> Result<Self> {
+        let manifest: Self = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+
+        let supported = ApiVersion::parse(EXTERNAL_API_VERSION_STR)?;
+        match supported.supports(&ApiVersion::parse(&manifest.version)?)? {
+            ApiVersionSupport::Full => (),
+            ApiVersionSupport::Partial => warn!(
+                "{}: external collectors API ({supported}) only supports version {} partially",
+                manifest.name, manifest.version
+            ),
+            ApiVersionSupport::NotSupported => bail!(
+                "External collectors API ({supported}) does not support version {}",
+                manifest.version
+            ),
+        }
+
+        if manifest.name.is_empty() {
+            bail!("Name can't be empty");
+        }
+        if manifest.probes.is_empty() {
+            bail!("No probe defined");
+        }
+
+        Ok(manifest)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ExternalCollector {}
+
+impl Collector for ExternalCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, args: &Collect) -> Result<()> {
+        if args.collector_args.external.external_collector.is_empty() {
+            bail!("No external collector given (--external-collector)");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        args: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        section_factories: &mut SectionFactories,
+    ) -> Result<()> {
+        let factory = section_factories.get_mut::<ExternalEventFactory>(&FactoryId::External)?;
+        let mut names = HashSet::new();
+
+        for path in args.collector_args.external.external_collector.iter() {
+            let manifest = ExternalManifest::from_file(path)
+                .with_context(|| format!("Invalid external collector {}", path.display()))?;
+            if !names.insert(manifest.name.clone()) {
+                bail!("External collector {} is loaded twice", manifest.name);
+            }
+
+            Self::add_collector(probes, factory, path, &manifest)
+                .with_context(|| format!("Could not load external collector {}", manifest.name))?;
+            info!("External collector {} loaded", manifest.name);
+        }
+
+        Ok(())
+    }
+}
+
+impl ExternalCollector {
+    /// Register the layout of an external collector data and attach its hook
+    /// to its probes.
+    fn add_collector(
+        probes: &mut ProbeBuilderManager,
+        factory: &mut ExternalEventFactory,
+        path: &Path,
+        manifest: &ExternalManifest,
+    ) -> Result<()> {
+        factory.register(
+            manifest.id,
+            ExternalLayout::new(&manifest.name, &manifest.fields)?,
+        )?;
+
+        let hook = match path.parent() {
+            Some(dir) => dir.join(&manifest.hook),
+            None => manifest.hook.clone(),
+        };
+        let hook =
+            fs::read(&hook).map_err(|e| anyhow!("Could not read hook {}: {e}", hook.display()))?;
+        // Hooks are used for the whole lifetime of Retis, as the built-in
+        // ones.
+        let hook: &'static [u8] = Box::leak(hook.into_boxed_slice());
+
+        for target in manifest.probes.iter() {
+            if target.starts_with("usdt:") {
+                bail!("USDT probes are not supported by external collectors ({target})");
+            }
+
+            let matching = probe_from_cli(target, |_| true)?;
+            if matching.is_empty() {
+                bail!("No probe matching {target}");
+            }
+
+            for mut probe in matching.into_iter() {
+                probe.add_hook(Hook::from(hook))?;
+                probes.register_probe(probe)?;
+            }
+        }
+
+        Ok(())
+    }
+}
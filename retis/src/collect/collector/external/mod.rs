@@ -0,0 +1,13 @@
+//! # External module
+//!
+//! Loads collectors built out of the Retis tree. Their BPF hook, the probes it
+//! is attached to and the layout of the data it reports are described by a
+//! manifest given at runtime.
+
+// Re-export external.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod external;
+pub(crate) use external::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::ExternalEventFactory;
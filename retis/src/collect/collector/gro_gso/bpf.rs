@@ -0,0 +1,81 @@
+use anyhow::Result;
+
+use crate::{
+    bindings::gro_gso_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+};
+
+/// Gro/gso event types. Keep in sync with their BPF counterparts in
+/// bpf/gro_gso_hook.bpf.c
+pub(super) const GRO_EV_MERGE: u8 = 0;
+pub(super) const GRO_EV_COMPLETE: u8 = 1;
+pub(super) const GSO_EV_SEGMENT: u8 = 2;
+
+#[event_section_factory(FactoryId::GroGso)]
+#[derive(Default)]
+pub(crate) struct GroGsoEventFactory {}
+
+impl RawEventSectionFactory for GroGsoEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<gro_gso_event>(&raw_sections)?;
+        let mut gro_gso = GroGsoEvent {
+            // A null count means it couldn't be retrieved.
+            segs: Some(raw.segs).filter(|segs| *segs != 0),
+            ..Default::default()
+        };
+
+        match raw.type_ {
+            GRO_EV_MERGE => {
+                gro_gso.op = "gro-merge".to_string();
+                gro_gso.merged = Some(raw.merged).filter(|skb| *skb != 0);
+            }
+            GRO_EV_COMPLETE => gro_gso.op = "gro-complete".to_string(),
+            GSO_EV_SEGMENT => {
+                gro_gso.op = "gso-segment".to_string();
+                if raw.err != 0 {
+                    gro_gso.err = Some(raw.err);
+                } else {
+                    gro_gso.segments = Some(segments(raw));
+                }
+            }
+            x => gro_gso.op = x.to_string(),
+        }
+
+        event.gro_gso = Some(gro_gso);
+        Ok(())
+    }
+}
+
+/// Get the addresses of the reported segments.
+fn segments(raw: &gro_gso_event) -> Vec<u64> {
+    raw.segments
+        .iter()
+        .take(raw.segs as usize)
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gso_segments() {
+        let mut raw = gro_gso_event {
+            segs: 2,
+            ..Default::default()
+        };
+        raw.segments[..2].copy_from_slice(&[0xffff1, 0xffff2]);
+        assert_eq!(segments(&raw), vec![0xffff1, 0xffff2]);
+
+        // Only the first segments are reported.
+        raw.segs = 42;
+        raw.segments = [0xffff1; 8];
+        assert_eq!(segments(&raw).len(), 8);
+    }
+}
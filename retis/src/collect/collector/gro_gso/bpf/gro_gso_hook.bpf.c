@@ -0,0 +1,138 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define GRO_EV_MERGE		0
+#define GRO_EV_COMPLETE		1
+#define GSO_EV_SEGMENT		2
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define GSO_SEGS_REPORT_MAX	8
+/* Make the segments walk bounded. This is well above the number of segments
+ * a 64K packet is split into with usual MTUs.
+ */
+#define GSO_SEGS_MAX		256
+
+#define MAX_ERRNO		4095
+
+#define retis_get_gro_merged(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, merged, struct sk_buff *)
+
+/* See NAPI_GRO_CB(), only the fields we report are needed. */
+struct napi_gro_cb___gro {
+	u16 count;
+} __attribute__((preserve_access_index));
+
+/**
+ * Gro/gso hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct gro_gso_offsets {
+	/* Skb merged into the one being tracked, on GRO merges. */
+	s8 merged;
+};
+struct gro_gso_config {
+	struct gro_gso_offsets offsets;
+	u8 type;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct gro_gso_config);
+} gro_gso_config_map SEC(".maps");
+
+struct gro_gso_event {
+	u8 type;
+	/* Number of segments the skb was merged from or split into. */
+	u32 segs;
+	s32 err;
+	/* Skb merged into the current one, on GRO merges. */
+	u64 merged;
+	/* First segments the skb was split into, on GSO segmentation. */
+	u64 segments[GSO_SEGS_REPORT_MAX];
+} __binding;
+
+static __always_inline u32 gro_count(struct sk_buff *skb)
+{
+	struct napi_gro_cb___gro *cb = (void *)&skb->cb;
+
+	if (!bpf_core_field_exists(cb->count))
+		return 0;
+
+	return BPF_CORE_READ(cb, count);
+}
+
+static __always_inline void gso_report_segments(struct gro_gso_event *e,
+						struct sk_buff *segs)
+{
+	int i;
+
+	for (i = 0; i < GSO_SEGS_MAX; i++) {
+		if (!segs)
+			break;
+
+		if (i < GSO_SEGS_REPORT_MAX)
+			e->segments[i] = (u64)segs;
+
+		e->segs++;
+		segs = BPF_CORE_READ(segs, next);
+	}
+}
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct gro_gso_config *cfg;
+	struct gro_gso_event *e;
+	struct sk_buff *skb;
+	u32 key = 0;
+	u64 ret;
+
+	cfg = bpf_map_lookup_elem(&gro_gso_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	skb = retis_get_sk_buff(ctx);
+	if (!skb)
+		return 0;
+
+	ret = ctx->regs.ret;
+	/* Only report successful merges. */
+	if (cfg->type == GRO_EV_MERGE && ret)
+		return -ENOMSG;
+	/* No segmentation happened. */
+	if (cfg->type == GSO_EV_SEGMENT && !ret)
+		return -ENOMSG;
+
+	e = get_event_zsection(event, COLLECTOR_GRO_GSO, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->type = cfg->type;
+
+	switch (cfg->type) {
+	case GRO_EV_MERGE:
+		e->merged = (u64)retis_get_gro_merged(ctx, cfg);
+		e->segs = gro_count(skb);
+		break;
+	case GRO_EV_COMPLETE:
+		e->segs = gro_count(skb);
+		break;
+	case GSO_EV_SEGMENT:
+		/* See IS_ERR(). */
+		if (ret >= (u64)-MAX_ERRNO)
+			e->err = (s32)ret;
+		else
+			gso_report_segments(e, (struct sk_buff *)ret);
+		break;
+	}
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, gro_gso_hook};
+use crate::{
+    bindings::gro_gso_hook_uapi::gro_gso_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Merge of an skb into a GRO packet.
+const GRO_MERGE: &str = "skb_gro_receive";
+/// GRO packets being passed up the stack. Only the first one found is used, the
+/// latter being used on older kernels.
+const GRO_COMPLETE_TARGETS: &[&str] = &["gro_complete", "napi_gro_complete"];
+/// GSO segmentation.
+const GSO_SEGMENT: &str = "__skb_gso_segment";
+
+#[derive(Default)]
+pub(crate) struct GroGsoCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for GroGsoCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct sk_buff *"])
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if Symbol::from_name(GRO_MERGE).is_err() && Symbol::from_name(GSO_SEGMENT).is_err() {
+            bail!("Could not resolve GRO nor GSO kernel symbols");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        if let Ok(sym) = Symbol::from_name(GRO_MERGE) {
+            let mut cfg = Self::config(GRO_EV_MERGE);
+            // The merged skb directly follows the GRO packet.
+            if let Some(offset) = sym.parameter_offset("struct sk_buff *")? {
+                cfg.offsets.merged = offset as i8 + 1;
+            }
+            self.add_probe(probes, Probe::kretprobe(sym)?, cfg)?;
+        }
+
+        if let Some(sym) = GRO_COMPLETE_TARGETS
+            .iter()
+            .find_map(|target| Symbol::from_name(target).ok())
+        {
+            let cfg = Self::config(GRO_EV_COMPLETE);
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(GSO_SEGMENT) {
+            let cfg = Self::config(GSO_EV_SEGMENT);
+            self.add_probe(probes, Probe::kretprobe(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl GroGsoCollector {
+    fn config(r#type: u8) -> gro_gso_config {
+        gro_gso_config {
+            type_: r#type,
+            ..Default::default()
+        }
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: gro_gso_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/gro_gso_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(gro_gso_hook::DATA),
+            "gro_gso_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+//! # Gro/gso module
+//!
+//! Provides support for tracing GRO merges and GSO segmentations, to explain
+//! why packet counts differ across capture points.
+
+// Re-export gro_gso.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod gro_gso;
+pub(crate) use gro_gso::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::GroGsoEventFactory;
+
+mod gro_gso_hook {
+    include!("bpf/.out/gro_gso_hook.rs");
+}
@@ -0,0 +1,122 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use anyhow::Result;
+
+use crate::{
+    bindings::icmp_error_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+};
+
+pub(super) const AF_INET: u8 = 2;
+pub(super) const AF_INET6: u8 = 10;
+
+#[event_section_factory(FactoryId::IcmpError)]
+#[derive(Default)]
+pub(crate) struct IcmpErrorEventFactory {}
+
+impl RawEventSectionFactory for IcmpErrorEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<icmp_error_event>(&raw_sections)?;
+        let v6 = raw.family == AF_INET6 as u16;
+
+        let (src, dst) = if v6 {
+            (
+                Ipv6Addr::from(raw.src).to_string(),
+                Ipv6Addr::from(raw.dst).to_string(),
+            )
+        } else {
+            (
+                Ipv4Addr::new(raw.src[0], raw.src[1], raw.src[2], raw.src[3]).to_string(),
+                Ipv4Addr::new(raw.dst[0], raw.dst[1], raw.dst[2], raw.dst[3]).to_string(),
+            )
+        };
+
+        let (error, has_mtu) = match v6 {
+            false => icmp_error_str(raw.type_, raw.code),
+            true => icmpv6_error_str(raw.type_, raw.code),
+        };
+        let has_ports = raw.has_ports == 1;
+
+        event.icmp_error = Some(IcmpErrorEvent {
+            v6,
+            r#type: raw.type_,
+            code: raw.code,
+            error,
+            mtu: has_mtu.then_some(raw.info),
+            orig: IcmpErrorOrig {
+                src,
+                dst,
+                protocol: raw.protocol,
+                sport: has_ports.then_some(raw.sport),
+                dport: has_ports.then_some(raw.dport),
+            },
+        });
+
+        Ok(())
+    }
+}
+
+/// Get the name of an ICMP error and whether its info field is an MTU, see
+/// include/uapi/linux/icmp.h.
+fn icmp_error_str(r#type: u8, code: u8) -> (String, bool) {
+    let error = match (r#type, code) {
+        (3, 0) => "net-unreachable",
+        (3, 1) => "host-unreachable",
+        (3, 2) => "proto-unreachable",
+        (3, 3) => "port-unreachable",
+        (3, 4) => return ("frag-needed".to_string(), true),
+        (3, 13) => "admin-prohibited",
+        (3, _) => "dest-unreachable",
+        (5, _) => "redirect",
+        (11, 0) => "ttl-exceeded",
+        (11, 1) => "reassembly-timeout",
+        (11, _) => "time-exceeded",
+        (12, _) => "param-problem",
+        _ => "unknown",
+    };
+    (error.to_string(), false)
+}
+
+/// Get the name of an ICMPv6 error and whether its info field is an MTU, see
+/// include/uapi/linux/icmpv6.h.
+fn icmpv6_error_str(r#type: u8, code: u8) -> (String, bool) {
+    let error = match (r#type, code) {
+        (1, 0) => "no-route",
+        (1, 1) => "admin-prohibited",
+        (1, 3) => "addr-unreachable",
+        (1, 4) => "port-unreachable",
+        (1, _) => "dest-unreachable",
+        (2, _) => return ("packet-too-big".to_string(), true),
+        (3, 0) => "hop-limit-exceeded",
+        (3, 1) => "reassembly-timeout",
+        (3, _) => "time-exceeded",
+        (4, _) => "param-problem",
+        _ => "unknown",
+    };
+    (error.to_string(), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icmp_errors() {
+        assert_eq!(icmp_error_str(3, 4), ("frag-needed".to_string(), true));
+        assert_eq!(icmp_error_str(11, 0), ("ttl-exceeded".to_string(), false));
+        assert_eq!(
+            icmp_error_str(3, 9),
+            ("dest-unreachable".to_string(), false)
+        );
+        assert_eq!(icmpv6_error_str(2, 0), ("packet-too-big".to_string(), true));
+        assert_eq!(
+            icmpv6_error_str(3, 0),
+            ("hop-limit-exceeded".to_string(), false)
+        );
+    }
+}
@@ -0,0 +1,141 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_endian.h>
+
+#include <common.h>
+
+#define AF_INET			2
+#define AF_INET6		10
+
+#define IPPROTO_TCP		6
+#define IPPROTO_UDP		17
+#define IPPROTO_SCTP		132
+
+#define retis_get_icmp_type(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, type, int)
+#define retis_get_icmp_code(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, code, int)
+#define retis_get_icmp_info(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, info, u32)
+
+/**
+ * ICMP error hook configuration. Each probe has its own instance, as the data
+ * to retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct icmp_error_offsets {
+	s8 type;
+	s8 code;
+	s8 info;
+};
+struct icmp_error_config {
+	struct icmp_error_offsets offsets;
+	/* Family of the ICMP errors sent by the probed function. */
+	u8 family;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct icmp_error_config);
+} icmp_error_config_map SEC(".maps");
+
+struct icmp_error_event {
+	/* Host order info field, e.g. the MTU for fragmentation needed errors. */
+	u32 info;
+	u16 family;
+	/* Header of the original packet, embedded in the error. Host order
+	 * ports.
+	 */
+	u16 sport;
+	u16 dport;
+	u8 src[16];
+	u8 dst[16];
+	u8 protocol;
+	u8 has_ports;
+	u8 type;
+	u8 code;
+} __binding;
+
+/* Extract the original header from the packet triggering the error. */
+static __always_inline void icmp_error_orig(struct icmp_error_event *e,
+					    struct sk_buff *skb)
+{
+	void *head = BPF_CORE_READ(skb, head);
+	u16 network = BPF_CORE_READ(skb, network_header);
+	u16 ports[2];
+	void *l4;
+
+	if (e->family == AF_INET) {
+		struct iphdr ip;
+
+		if (bpf_probe_read_kernel(&ip, sizeof(ip), head + network))
+			return;
+
+		e->protocol = ip.protocol;
+		__builtin_memcpy(e->src, &ip.saddr, 4);
+		__builtin_memcpy(e->dst, &ip.daddr, 4);
+		l4 = head + network + ip.ihl * 4;
+	} else {
+		struct ipv6hdr ip6;
+
+		if (bpf_probe_read_kernel(&ip6, sizeof(ip6), head + network))
+			return;
+
+		/* Extension headers are not followed. */
+		e->protocol = ip6.nexthdr;
+		__builtin_memcpy(e->src, &ip6.saddr, 16);
+		__builtin_memcpy(e->dst, &ip6.daddr, 16);
+		l4 = head + network + sizeof(ip6);
+	}
+
+	switch (e->protocol) {
+	case IPPROTO_TCP:
+	case IPPROTO_UDP:
+	case IPPROTO_SCTP:
+		/* Ports are the first 4 bytes of those headers. */
+		if (bpf_probe_read_kernel(ports, sizeof(ports), l4))
+			break;
+		e->sport = bpf_ntohs(ports[0]);
+		e->dport = bpf_ntohs(ports[1]);
+		e->has_ports = 1;
+		break;
+	}
+}
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct icmp_error_config *cfg;
+	struct icmp_error_event *e;
+	struct sk_buff *skb;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&icmp_error_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	/* The packet triggering the error. */
+	skb = retis_get_sk_buff(ctx);
+	if (!skb || !is_network_data_valid(skb))
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_ICMP_ERROR, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->family = cfg->family;
+	e->type = retis_get_icmp_type(ctx, cfg);
+	e->code = retis_get_icmp_code(ctx, cfg);
+
+	/* The info field is given in network order for IPv4. */
+	e->info = retis_get_icmp_info(ctx, cfg);
+	if (cfg->family == AF_INET)
+		e->info = bpf_ntohl(e->info);
+
+	icmp_error_orig(e, skb);
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
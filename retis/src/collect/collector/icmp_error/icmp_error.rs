@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, icmp_error_hook};
+use crate::{
+    bindings::icmp_error_hook_uapi::icmp_error_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// ICMP errors being sent. Only the first one found is used, the latter being
+/// used on older kernels.
+const ICMP_TARGETS: &[&str] = &["__icmp_send", "icmp_send"];
+/// ICMPv6 errors being sent.
+const ICMPV6_TARGET: &str = "icmp6_send";
+
+#[derive(Default)]
+pub(crate) struct IcmpErrorCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for IcmpErrorCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct sk_buff *"])
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if !ICMP_TARGETS
+            .iter()
+            .any(|target| Symbol::from_name(target).is_ok())
+        {
+            bail!("Could not resolve the ICMP error sending function");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        if let Some(sym) = ICMP_TARGETS
+            .iter()
+            .find_map(|target| Symbol::from_name(target).ok())
+        {
+            let cfg = Self::config(&sym, AF_INET, "int", "__be32")?;
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        // IPv6 can be built as a module or disabled.
+        if let Ok(sym) = Symbol::from_name(ICMPV6_TARGET) {
+            let cfg = Self::config(&sym, AF_INET6, "u8", "__u32")?;
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl IcmpErrorCollector {
+    /// Build the hook configuration of a given probe. The ICMP type and code
+    /// are consecutive parameters, followed by the info field.
+    fn config(sym: &Symbol, family: u8, r#type: &str, info: &str) -> Result<icmp_error_config> {
+        let mut cfg = icmp_error_config {
+            family,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset(r#type)? {
+            cfg.offsets.type_ = offset as i8;
+            cfg.offsets.code = offset as i8 + 1;
+        }
+        if let Some(offset) = sym.parameter_offset(info)? {
+            cfg.offsets.info = offset as i8;
+        }
+        Ok(cfg)
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: icmp_error_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/icmp_error_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(icmp_error_hook::DATA),
+            "icmp_error_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
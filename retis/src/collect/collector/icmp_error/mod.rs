@@ -0,0 +1,16 @@
+//! # ICMP error module
+//!
+//! Provides support for reporting ICMP errors generated by the stack, along
+//! with the header of the packets which triggered them.
+
+// Re-export icmp_error.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod icmp_error;
+pub(crate) use icmp_error::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::IcmpErrorEventFactory;
+
+mod icmp_error_hook {
+    include!("bpf/.out/icmp_error_hook.rs");
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+
+use crate::{
+    bindings::lag_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string, raw_to_string_opt,
+};
+
+/// Lag event types. Keep in sync with their BPF counterparts in
+/// bpf/lag_hook.bpf.c
+pub(super) const LAG_EV_XMIT: u8 = 0;
+pub(super) const LAG_EV_FAILOVER: u8 = 1;
+
+/// Lag device kinds. Keep in sync with their BPF counterparts in
+/// bpf/lag_hook.bpf.c
+const LAG_KIND_BOND: u8 = 1;
+const LAG_KIND_TEAM: u8 = 2;
+
+#[event_section_factory(FactoryId::Lag)]
+#[derive(Default)]
+pub(crate) struct LagEventFactory {}
+
+impl RawEventSectionFactory for LagEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<lag_event>(&raw_sections)?;
+
+        event.lag = Some(LagEvent {
+            op: match raw.type_ {
+                LAG_EV_XMIT => "xmit".to_string(),
+                LAG_EV_FAILOVER => "failover".to_string(),
+                x => x.to_string(),
+            },
+            kind: match raw.kind {
+                LAG_KIND_BOND => "bond".to_string(),
+                LAG_KIND_TEAM => "team".to_string(),
+                x => x.to_string(),
+            },
+            master: raw_to_string!(&raw.master)?,
+            mode: (raw.mode >= 0).then(|| mode_str(raw.mode)),
+            port: raw_to_string_opt!(&raw.port)?,
+            old_port: raw_to_string_opt!(&raw.old_port)?,
+        });
+        Ok(())
+    }
+}
+
+/// Get the name of a bonding mode, see BOND_MODE_* in
+/// include/uapi/linux/if_bonding.h.
+fn mode_str(mode: i8) -> String {
+    match mode {
+        0 => "balance-rr".to_string(),
+        1 => "active-backup".to_string(),
+        2 => "balance-xor".to_string(),
+        3 => "broadcast".to_string(),
+        4 => "802.3ad".to_string(),
+        5 => "balance-tlb".to_string(),
+        6 => "balance-alb".to_string(),
+        x => x.to_string(),
+    }
+}
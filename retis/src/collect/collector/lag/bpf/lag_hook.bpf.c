@@ -0,0 +1,194 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define LAG_EV_XMIT		0
+#define LAG_EV_FAILOVER		1
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define LAG_KIND_BOND		1
+#define LAG_KIND_TEAM		2
+
+BINDING_DEF(IFNAMSIZ, 16)
+
+/* See include/uapi/linux/if.h */
+#define IFF_SLAVE		0x800
+
+#define retis_get_lag_bond(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, bond, struct bonding___lag *)
+#define retis_get_lag_slave(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, slave, struct slave___lag *)
+
+/* Only the fields we report are needed. */
+struct bonding___lag {
+	struct net_device *dev;
+	struct slave___lag *curr_active_slave;
+	struct {
+		int mode;
+	} params;
+} __attribute__((preserve_access_index));
+
+struct slave___lag {
+	struct net_device *dev;
+	struct bonding___lag *bond;
+} __attribute__((preserve_access_index));
+
+struct team___lag {
+	struct net_device *dev;
+} __attribute__((preserve_access_index));
+
+struct team_port___lag {
+	struct team___lag *team;
+} __attribute__((preserve_access_index));
+
+/**
+ * Lag hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct lag_offsets {
+	s8 bond;
+	s8 slave;
+};
+struct lag_config {
+	struct lag_offsets offsets;
+	u8 type;
+	/* IFF_BONDING and IFF_TEAM_PORT private flags values, as they vary
+	 * across kernel versions.
+	 */
+	u32 iff_bonding;
+	u32 iff_team_port;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct lag_config);
+} lag_config_map SEC(".maps");
+
+struct lag_event {
+	u8 type;
+	u8 kind;
+	/* Bonding mode, -1 if unknown. */
+	s8 mode;
+	char master[IFNAMSIZ];
+	/* Egress port, or newly active one on failovers. */
+	char port[IFNAMSIZ];
+	/* Previously active port on failovers. */
+	char old_port[IFNAMSIZ];
+} __binding;
+
+static __always_inline void lag_report_dev(char *name, struct net_device *dev)
+{
+	if (dev)
+		bpf_probe_read(name, IFNAMSIZ, dev->name);
+}
+
+static __always_inline void lag_report_bond(struct lag_event *e,
+					    struct bonding___lag *bond)
+{
+	e->kind = LAG_KIND_BOND;
+	e->mode = BPF_CORE_READ(bond, params.mode);
+	lag_report_dev(e->master, BPF_CORE_READ(bond, dev));
+}
+
+/* Report the port an skb is being sent to, if it belongs to a bonding or a
+ * team device. See netif_is_bond_slave() and netif_is_team_port().
+ */
+static __always_inline int lag_report_xmit(struct retis_raw_event *event,
+					   struct lag_config *cfg,
+					   struct sk_buff *skb)
+{
+	struct team_port___lag *port;
+	struct slave___lag *slave;
+	struct net_device *dev;
+	struct lag_event *e;
+	u32 priv_flags;
+
+	dev = BPF_CORE_READ(skb, dev);
+	if (!dev)
+		return 0;
+
+	priv_flags = BPF_CORE_READ_BITFIELD_PROBED(dev, priv_flags);
+
+	if (BPF_CORE_READ(dev, flags) & IFF_SLAVE &&
+	    priv_flags & cfg->iff_bonding) {
+		slave = BPF_CORE_READ(dev, rx_handler_data);
+		if (!slave)
+			return 0;
+
+		e = get_event_zsection(event, COLLECTOR_LAG, 1, sizeof(*e));
+		if (!e)
+			return 0;
+
+		lag_report_bond(e, BPF_CORE_READ(slave, bond));
+	} else if (priv_flags & cfg->iff_team_port) {
+		port = BPF_CORE_READ(dev, rx_handler_data);
+		if (!port)
+			return 0;
+
+		e = get_event_zsection(event, COLLECTOR_LAG, 1, sizeof(*e));
+		if (!e)
+			return 0;
+
+		e->kind = LAG_KIND_TEAM;
+		e->mode = -1;
+		lag_report_dev(e->master, BPF_CORE_READ(port, team, dev));
+	} else {
+		return 0;
+	}
+
+	e->type = LAG_EV_XMIT;
+	lag_report_dev(e->port, dev);
+	return 0;
+}
+
+DEFINE_HOOK_RAW(
+	struct bonding___lag *bond;
+	struct slave___lag *slave;
+	struct lag_config *cfg;
+	struct lag_event *e;
+	struct sk_buff *skb;
+	u32 key = 0;
+
+	/* Egress events are subject to filtering, failovers are not. */
+	skb = retis_get_sk_buff(ctx);
+	if (skb && !RETIS_TRACKABLE(ctx))
+		return 0;
+
+	cfg = bpf_map_lookup_elem(&lag_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	if (cfg->type == LAG_EV_XMIT)
+		return skb ? lag_report_xmit(event, cfg, skb) : 0;
+
+	bond = retis_get_lag_bond(ctx, cfg);
+	if (!bond)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_LAG, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->type = LAG_EV_FAILOVER;
+	lag_report_bond(e, bond);
+
+	/* The active port is not yet updated when entering the function. */
+	slave = BPF_CORE_READ(bond, curr_active_slave);
+	if (slave)
+		lag_report_dev(e->old_port, BPF_CORE_READ(slave, dev));
+
+	slave = retis_get_lag_slave(ctx, cfg);
+	if (slave)
+		lag_report_dev(e->port, BPF_CORE_READ(slave, dev));
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, lag_hook};
+use crate::{
+    bindings::lag_hook_uapi::lag_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        inspect::parse_enum,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Packets being queued to a device, after a port of an aggregated device was
+/// selected.
+const LAG_TP_XMIT: &str = "net:net_dev_queue";
+/// Bonding active port changes.
+const BOND_FAILOVER: &str = "bond_change_active_slave";
+
+#[derive(Default)]
+pub(crate) struct LagCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for LagCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct sk_buff *"])
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name(LAG_TP_XMIT) {
+            bail!("Could not resolve the device queueing tracepoint: {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        // Private flags values vary across kernel versions, retrieve them.
+        let priv_flags = parse_enum("netdev_priv_flags", &["IFF_"])?;
+        let flag = |name: &str| {
+            priv_flags
+                .iter()
+                .find_map(|(val, n)| (n == name).then_some(*val))
+                .unwrap_or(0)
+        };
+
+        let sym = Symbol::from_name(LAG_TP_XMIT)?;
+        let mut cfg = Self::config(&sym, LAG_EV_XMIT)?;
+        cfg.iff_bonding = flag("BONDING");
+        cfg.iff_team_port = flag("TEAM_PORT");
+        if cfg.iff_bonding == 0 && cfg.iff_team_port == 0 {
+            bail!("Could not retrieve the bonding and team private flags");
+        }
+        self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+
+        // The bonding module might not be loaded.
+        if let Ok(sym) = Symbol::from_name(BOND_FAILOVER) {
+            let cfg = Self::config(&sym, LAG_EV_FAILOVER)?;
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl LagCollector {
+    /// Build the hook configuration of a given probe.
+    fn config(sym: &Symbol, r#type: u8) -> Result<lag_config> {
+        let mut cfg = lag_config {
+            type_: r#type,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset("struct bonding *")? {
+            cfg.offsets.bond = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct slave *")? {
+            cfg.offsets.slave = offset as i8;
+        }
+        Ok(cfg)
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: lag_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/lag_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(lag_hook::DATA),
+            "lag_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+//! # Lag module
+//!
+//! Provides support for tracing link aggregation (bonding and team) devices:
+//! egress port selection and failovers.
+
+// Re-export lag.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod lag;
+pub(crate) use lag::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::LagEventFactory;
+
+mod lag_hook {
+    include!("bpf/.out/lag_hook.rs");
+}
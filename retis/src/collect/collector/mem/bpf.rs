@@ -0,0 +1,92 @@
+//! Rust<>BPF types definitions for the mem module.
+//! Please keep this file in sync with its BPF counterparts in bpf/.
+use anyhow::{bail, Result};
+
+use crate::{
+    bindings::{
+        mem_common_uapi::*, mem_page_pool_hook_uapi::mem_page_pool_event,
+        mem_skb_hook_uapi::mem_skb_event,
+    },
+    core::events::{
+        parse_raw_section, BpfRawSection, EventSectionFactory, FactoryId, RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string, raw_to_string_opt,
+};
+
+#[event_section_factory(FactoryId::Mem)]
+#[derive(Default)]
+pub(crate) struct MemEventFactory {}
+
+impl RawEventSectionFactory for MemEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let mut mem = MemEvent::default();
+
+        for section in raw_sections.iter() {
+            match section.header.data_type as u32 {
+                MEM_SECTION_SKB => {
+                    let raw = parse_raw_section::<mem_skb_event>(section)?;
+                    mem.skb = Some(MemSkb {
+                        truesize: raw.truesize,
+                        sock: (raw.has_sock == 1).then_some(MemSock {
+                            rmem_alloc: raw.rmem_alloc,
+                            rcvbuf: raw.rcvbuf,
+                            wmem_alloc: raw.wmem_alloc,
+                            sndbuf: raw.sndbuf,
+                            drops: raw.drops,
+                        }),
+                    });
+                }
+                MEM_SECTION_PAGE_POOL => {
+                    let raw = parse_raw_section::<mem_page_pool_event>(section)?;
+                    mem.page_pool = Some(MemPagePool {
+                        pool: raw.pool,
+                        dev: raw_to_string_opt!(&raw.dev)?,
+                        inflight: raw.inflight,
+                        cached: raw.cached,
+                        ring: ring_len(raw.ring_producer, raw.ring_consumer, raw.ring_size),
+                        alloc: (raw.has_stats == 1).then_some(MemPagePoolAllocStats {
+                            fast: raw.alloc_fast,
+                            slow: raw.alloc_slow,
+                            slow_high_order: raw.alloc_slow_high_order,
+                            empty: raw.alloc_empty,
+                            refill: raw.alloc_refill,
+                            waive: raw.alloc_waive,
+                        }),
+                    });
+                }
+                x => bail!("Unknown data type ({x})"),
+            }
+        }
+
+        event.mem = Some(mem);
+        Ok(())
+    }
+}
+
+/// Number of entries in a ptr_ring, from its producer and consumer indexes.
+/// As both are read without locking this is an approximation, and a full ring
+/// can't be told apart from an empty one.
+fn ring_len(producer: i32, consumer: i32, size: i32) -> u32 {
+    if size <= 0 || producer < 0 || consumer < 0 {
+        return 0;
+    }
+
+    ((producer - consumer).rem_euclid(size)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_pool_ring_len() {
+        assert_eq!(ring_len(0, 0, 1024), 0);
+        assert_eq!(ring_len(10, 4, 1024), 6);
+        // Producer wrapped around.
+        assert_eq!(ring_len(2, 1020, 1024), 6);
+        // Ring not allocated.
+        assert_eq!(ring_len(0, 0, 0), 0);
+    }
+}
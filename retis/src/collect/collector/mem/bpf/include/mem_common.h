@@ -0,0 +1,12 @@
+#ifndef __COLLECTOR_MEM_COMMON__
+#define __COLLECTOR_MEM_COMMON__
+
+#include <vmlinux.h>
+
+/* Section types, as the mem collector reports different kinds of data. */
+enum mem_sections {
+	MEM_SECTION_SKB = 1,
+	MEM_SECTION_PAGE_POOL,
+} __binding;
+
+#endif /* __COLLECTOR_MEM_COMMON__ */
@@ -0,0 +1,161 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include <mem_common.h>
+
+BINDING_DEF(IFNAMSIZ, 16)
+
+#define retis_get_page_pool(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, page_pool, struct page_pool___mem *)
+
+/* Only the fields we report are needed. */
+struct page_pool_params___mem {
+	struct net_device *netdev;
+} __attribute__((preserve_access_index));
+
+struct page_pool_alloc_stats___mem {
+	u64 fast;
+	u64 slow;
+	u64 slow_high_order;
+	u64 empty;
+	u64 refill;
+	u64 waive;
+} __attribute__((preserve_access_index));
+
+struct pp_alloc_cache___mem {
+	u32 count;
+} __attribute__((preserve_access_index));
+
+struct ptr_ring___mem {
+	int producer;
+	int consumer_head;
+	int size;
+} __attribute__((preserve_access_index));
+
+struct page_pool___mem {
+	struct page_pool_params___mem p;
+	struct page_pool_alloc_stats___mem alloc_stats;
+	struct pp_alloc_cache___mem alloc;
+	struct ptr_ring___mem ring;
+	u32 pages_state_hold_cnt;
+	atomic_t pages_state_release_cnt;
+} __attribute__((preserve_access_index));
+
+/**
+ * Page pool hook configuration. Each probe has its own instance, as the data
+ * to retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct mem_page_pool_offsets {
+	s8 page_pool;
+};
+struct mem_page_pool_config {
+	struct mem_page_pool_offsets offsets;
+	/* Minimum time between two reports of the same pool, in ns. */
+	u64 interval;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct mem_page_pool_config);
+} mem_page_pool_config_map SEC(".maps");
+
+/* Last time a pool was reported, indexed by the pool address. Shared by all
+ * page pool probes.
+ */
+struct {
+	__uint(type, BPF_MAP_TYPE_LRU_HASH);
+	__uint(max_entries, 1024);
+	__type(key, u64);
+	__type(value, u64);
+} mem_page_pool_last SEC(".maps");
+
+struct mem_page_pool_event {
+	u64 pool;
+	/* Allocation stats, only valid if has_stats is set. */
+	u64 alloc_fast;
+	u64 alloc_slow;
+	u64 alloc_slow_high_order;
+	u64 alloc_empty;
+	u64 alloc_refill;
+	u64 alloc_waive;
+	/* Pages handed out by the pool and not yet returned to it. */
+	s32 inflight;
+	/* Pages in the lockless allocation cache. */
+	u32 cached;
+	/* Recycling ring indexes, its occupancy is computed in userspace. */
+	s32 ring_producer;
+	s32 ring_consumer;
+	s32 ring_size;
+	u8 has_stats;
+	char dev[IFNAMSIZ];
+} __binding;
+
+/* Page pools are hit for every page allocated and recycled by drivers using
+ * them, report their state at most once per interval for a given pool. This
+ * hook is not tied to a packet and is not subject to filtering.
+ */
+DEFINE_HOOK_RAW(
+	struct mem_page_pool_config *cfg;
+	struct mem_page_pool_event *e;
+	struct page_pool___mem *pool;
+	struct net_device *dev;
+	u64 key, now, *last;
+	u32 idx = 0;
+
+	cfg = bpf_map_lookup_elem(&mem_page_pool_config_map, &idx);
+	if (!cfg)
+		return 0;
+
+	pool = retis_get_page_pool(ctx, cfg);
+	if (!pool)
+		return 0;
+
+	key = (u64)pool;
+	now = ctx->timestamp;
+	last = bpf_map_lookup_elem(&mem_page_pool_last, &key);
+	if (last && now - *last < cfg->interval)
+		return 0;
+	bpf_map_update_elem(&mem_page_pool_last, &key, &now, BPF_ANY);
+
+	e = get_event_zsection(event, COLLECTOR_MEM, MEM_SECTION_PAGE_POOL,
+			       sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->pool = key;
+	e->inflight = BPF_CORE_READ(pool, pages_state_hold_cnt) -
+		      BPF_CORE_READ(pool, pages_state_release_cnt.counter);
+	e->cached = BPF_CORE_READ(pool, alloc.count);
+	e->ring_producer = BPF_CORE_READ(pool, ring.producer);
+	e->ring_consumer = BPF_CORE_READ(pool, ring.consumer_head);
+	e->ring_size = BPF_CORE_READ(pool, ring.size);
+
+	/* Allocation stats depend on CONFIG_PAGE_POOL_STATS. */
+	if (bpf_core_field_exists(pool->alloc_stats)) {
+		e->has_stats = 1;
+		e->alloc_fast = BPF_CORE_READ(pool, alloc_stats.fast);
+		e->alloc_slow = BPF_CORE_READ(pool, alloc_stats.slow);
+		e->alloc_slow_high_order =
+			BPF_CORE_READ(pool, alloc_stats.slow_high_order);
+		e->alloc_empty = BPF_CORE_READ(pool, alloc_stats.empty);
+		e->alloc_refill = BPF_CORE_READ(pool, alloc_stats.refill);
+		e->alloc_waive = BPF_CORE_READ(pool, alloc_stats.waive);
+	}
+
+	/* Pools only know their device on newer kernels. */
+	if (bpf_core_field_exists(pool->p.netdev)) {
+		dev = BPF_CORE_READ(pool, p.netdev);
+		if (dev)
+			bpf_probe_read_kernel_str(e->dev, IFNAMSIZ, dev->name);
+	}
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
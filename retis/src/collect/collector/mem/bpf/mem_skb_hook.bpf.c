@@ -0,0 +1,70 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include <mem_common.h>
+
+#define TCP_TIME_WAIT		6
+#define TCP_NEW_SYN_RECV	12
+
+/* Only the fields we report are needed. */
+struct sock___mem {
+	atomic_t sk_drops;
+} __attribute__((preserve_access_index));
+
+struct mem_skb_event {
+	u32 truesize;
+	/* Socket fields below are only valid if has_sock is set. */
+	s32 rmem_alloc;
+	s32 rcvbuf;
+	s32 wmem_alloc;
+	s32 sndbuf;
+	u32 drops;
+	u8 has_sock;
+} __binding;
+
+/* See sk_fullsock() in the kernel. */
+static __always_inline bool sock_is_full(struct sock *sk)
+{
+	u8 state = BPF_CORE_READ(sk, __sk_common.skc_state);
+
+	return state != TCP_TIME_WAIT && state != TCP_NEW_SYN_RECV;
+}
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct sock___mem *s;
+	struct mem_skb_event *e;
+	struct sk_buff *skb;
+	struct sock *sk;
+
+	skb = retis_get_sk_buff(ctx);
+	if (!skb)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_MEM, MEM_SECTION_SKB,
+			       sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->truesize = BPF_CORE_READ(skb, truesize);
+
+	/* Buffer accounting is only done on full sockets. */
+	sk = BPF_CORE_READ(skb, sk);
+	if (!sk || !sock_is_full(sk))
+		return 0;
+
+	e->has_sock = 1;
+	e->rmem_alloc = BPF_CORE_READ(sk, sk_backlog.rmem_alloc.counter);
+	e->rcvbuf = BPF_CORE_READ(sk, sk_rcvbuf);
+	e->wmem_alloc = BPF_CORE_READ(sk, sk_wmem_alloc.refs.counter);
+	e->sndbuf = BPF_CORE_READ(sk, sk_sndbuf);
+
+	/* Newer kernels can spread drop counters across NUMA nodes. */
+	s = (void *)sk;
+	if (bpf_core_field_exists(s->sk_drops))
+		e->drops = BPF_CORE_READ(s, sk_drops.counter);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,143 @@
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd, RawFd},
+    sync::Arc,
+};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use log::warn;
+
+use super::hooks;
+use crate::{
+    bindings::mem_page_pool_hook_uapi::mem_page_pool_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Page pool functions allocating pages and recycling them into a pool. In
+/// each group only the first function found is used, the latter ones being
+/// used on older kernels.
+const PAGE_POOL_TARGETS: &[&[&str]] = &[
+    &["page_pool_alloc_netmems", "page_pool_alloc_pages"],
+    &[
+        "page_pool_put_unrefed_netmem",
+        "page_pool_put_unrefed_page",
+        "page_pool_put_defragged_page",
+        "page_pool_put_page",
+    ],
+];
+
+/// Maximum number of page pools rate-limited at the same time. Keep in sync
+/// with its BPF counterpart in bpf/mem_page_pool_hook.bpf.c
+const PAGE_POOLS_MAX: u32 = 1024;
+
+#[derive(Parser, Debug, Default)]
+pub(crate) struct MemCollectorArgs {
+    #[arg(
+        long,
+        default_value = "1000",
+        value_name = "MS",
+        help = "Minimum time between two reports of the state of a given page pool, in milliseconds. Page pools are used for every page allocated and recycled by the drivers using them, 0 reports their state each time."
+    )]
+    pub(crate) mem_page_pool_interval: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct MemCollector {
+    // Used to keep a reference to our internal maps.
+    #[allow(dead_code)]
+    maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for MemCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct sk_buff *"])
+    }
+
+    fn init(
+        &mut self,
+        args: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its BPF counterpart in bpf/mem_page_pool_hook.bpf.c
+        let last = libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::LruHash,
+            Some("mem_page_pool_last"),
+            mem::size_of::<u64>() as u32,
+            mem::size_of::<u64>() as u32,
+            PAGE_POOLS_MAX,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the page pool map: {}", e))?;
+        let last_fd = last.as_fd().as_raw_fd();
+        self.maps.push(last);
+
+        let interval = args
+            .collector_args
+            .mem
+            .mem_page_pool_interval
+            .saturating_mul(1_000_000);
+
+        // Page pools are not used by all drivers and might not be built in.
+        for targets in PAGE_POOL_TARGETS.iter() {
+            match targets
+                .iter()
+                .find_map(|target| Symbol::from_name(target).ok())
+            {
+                Some(sym) => self.add_page_pool_probe(probes, sym, interval, last_fd)?,
+                None => warn!("Page pool state won't be reported from {}", targets[0]),
+            }
+        }
+
+        probes.register_kernel_hook(Hook::from(hooks::skb::DATA))
+    }
+}
+
+impl MemCollector {
+    /// Add a probe reporting the state of the page pool it is given.
+    fn add_page_pool_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        sym: Symbol,
+        interval: u64,
+        last_fd: RawFd,
+    ) -> Result<()> {
+        let mut cfg = mem_page_pool_config {
+            interval,
+            ..Default::default()
+        };
+        match sym.parameter_offset("struct page_pool *")? {
+            Some(offset) => cfg.offsets.page_pool = offset as i8,
+            None => bail!("Could not find the page pool parameter of {}", sym.name()),
+        }
+
+        // Please keep in sync with its BPF counterpart in bpf/mem_page_pool_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            Probe::kprobe(sym)?,
+            Hook::from(hooks::page_pool::DATA)
+                .reuse_map("mem_page_pool_last", last_fd)?
+                .to_owned(),
+            "mem_page_pool_config_map",
+            &cfg,
+        )?;
+        self.maps.push(config_map);
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+//! # Mem module
+//!
+//! Reports memory accounting information of the datapath: the memory used by
+//! packets, the buffer occupancy of their sockets and the state of the page
+//! pools drivers allocate packets from.
+
+// Re-export mem.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod mem;
+pub(crate) use mem::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::MemEventFactory;
+
+mod hooks {
+    pub(super) mod skb {
+        include!("bpf/.out/mem_skb_hook.rs");
+    }
+    pub(super) mod page_pool {
+        include!("bpf/.out/mem_page_pool_hook.rs");
+    }
+}
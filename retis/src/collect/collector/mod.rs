@@ -8,11 +8,31 @@
 pub(crate) mod collector;
 pub(crate) use collector::*;
 
+pub(crate) mod bridge;
 pub(crate) mod ct;
 pub(crate) mod dev;
+pub(crate) mod external;
+pub(crate) mod gro_gso;
+pub(crate) mod icmp_error;
+pub(crate) mod lag;
+pub(crate) mod mem;
+pub(crate) mod mptcp;
+pub(crate) mod neigh;
+pub(crate) mod nf;
 pub(crate) mod nft;
 pub(crate) mod ns;
+pub(crate) mod ns_lifecycle;
+pub(crate) mod offload;
 pub(crate) mod ovs;
+pub(crate) mod route;
 pub(crate) mod skb;
 pub(crate) mod skb_drop;
 pub(crate) mod skb_tracking;
+pub(crate) mod sock;
+pub(crate) mod tc;
+pub(crate) mod tcp;
+pub(crate) mod tunnel;
+pub(crate) mod wireguard;
+pub(crate) mod xdp;
+pub(crate) mod xfrm;
+pub(crate) mod xsk;
@@ -0,0 +1,127 @@
+//! Rust<>BPF types definitions for the MPTCP module.
+//! Please keep this file in sync with its BPF counterparts in bpf/.
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    bindings::mptcp_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+};
+
+/// MPTCP operations. Keep in sync with their BPF counterparts in
+/// bpf/mptcp_hook.bpf.c
+pub(super) const MPTCP_OP_ESTABLISHED: u8 = 0;
+pub(super) const MPTCP_OP_JOIN: u8 = 1;
+pub(super) const MPTCP_OP_CLOSE: u8 = 2;
+pub(super) const MPTCP_OP_REINJECT: u8 = 3;
+pub(super) const MPTCP_OP_ANNOUNCE: u8 = 4;
+pub(super) const MPTCP_OP_ANNOUNCE_RECEIVED: u8 = 5;
+pub(super) const MPTCP_OP_REMOVE: u8 = 6;
+pub(super) const MPTCP_OP_REMOVE_RECEIVED: u8 = 7;
+
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+
+#[event_section_factory(FactoryId::Mptcp)]
+#[derive(Default)]
+pub(crate) struct MptcpEventFactory {}
+
+impl RawEventSectionFactory for MptcpEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<mptcp_event>(&raw_sections)?;
+
+        let mut mptcp = MptcpEvent {
+            op: op_str(raw.op)?,
+            token: Some(raw.token).filter(|t| *t != 0),
+            ..Default::default()
+        };
+
+        if raw.has_subflow == 1 {
+            mptcp.subflow = Some(MptcpSubflow {
+                saddr: addr_str(raw.family, &raw.saddr),
+                daddr: addr_str(raw.family, &raw.daddr),
+                sport: raw.sport,
+                dport: raw.dport,
+                // Negative ids are not initialized yet.
+                local_id: u8::try_from(raw.local_id).ok(),
+                remote_id: raw.remote_id,
+            });
+        }
+
+        if raw.has_addr == 1 {
+            mptcp.addr = Some(MptcpAddr {
+                id: raw.addr_id,
+                addr: addr_str(raw.addr_family, &raw.addr),
+                port: Some(raw.addr_port).filter(|p| *p != 0),
+            });
+        }
+
+        match raw.op {
+            MPTCP_OP_REMOVE | MPTCP_OP_REMOVE_RECEIVED => {
+                let nr = (raw.rm_nr as usize).min(raw.rm_ids.len());
+                mptcp.rm_ids = Some(raw.rm_ids[..nr].to_vec());
+            }
+            MPTCP_OP_JOIN => mptcp.failed = Some(raw.failed == 1),
+            _ => (),
+        }
+
+        event.mptcp = Some(mptcp);
+        Ok(())
+    }
+}
+
+/// Get the name of an MPTCP operation.
+fn op_str(op: u8) -> Result<String> {
+    Ok(match op {
+        MPTCP_OP_ESTABLISHED => "established",
+        MPTCP_OP_JOIN => "join",
+        MPTCP_OP_CLOSE => "close",
+        MPTCP_OP_REINJECT => "reinject",
+        MPTCP_OP_ANNOUNCE => "announce",
+        MPTCP_OP_ANNOUNCE_RECEIVED => "announce-received",
+        MPTCP_OP_REMOVE => "remove",
+        MPTCP_OP_REMOVE_RECEIVED => "remove-received",
+        x => bail!("Unknown MPTCP operation ({x})"),
+    }
+    .to_string())
+}
+
+/// Format a raw address given its family.
+fn addr_str(family: u16, addr: &[u8; 16]) -> String {
+    match family {
+        AF_INET => Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string(),
+        AF_INET6 => Ipv6Addr::from(*addr).to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mptcp_ops() {
+        assert_eq!(op_str(MPTCP_OP_JOIN).unwrap(), "join");
+        assert_eq!(
+            op_str(MPTCP_OP_ANNOUNCE_RECEIVED).unwrap(),
+            "announce-received"
+        );
+        assert!(op_str(8).is_err());
+    }
+
+    #[test]
+    fn mptcp_addr() {
+        let mut addr = [0; 16];
+        addr[..4].copy_from_slice(&[10, 0, 0, 1]);
+        assert_eq!(addr_str(AF_INET, &addr), "10.0.0.1");
+
+        let addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).octets();
+        assert_eq!(addr_str(AF_INET6, &addr), "2001:db8::1");
+    }
+}
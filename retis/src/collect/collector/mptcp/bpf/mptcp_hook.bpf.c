@@ -0,0 +1,241 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_endian.h>
+
+#include <common.h>
+
+/* Keep in sync with their Rust counterparts in bpf.rs */
+#define MPTCP_OP_ESTABLISHED		0
+#define MPTCP_OP_JOIN			1
+#define MPTCP_OP_CLOSE			2
+#define MPTCP_OP_REINJECT		3
+#define MPTCP_OP_ANNOUNCE		4
+#define MPTCP_OP_ANNOUNCE_RECEIVED	5
+#define MPTCP_OP_REMOVE			6
+#define MPTCP_OP_REMOVE_RECEIVED	7
+
+#define AF_INET			2
+#define AF_INET6		10
+
+BINDING_DEF(MPTCP_RM_IDS_MAX, 8)
+
+#define retis_get_ssk(ctx, cfg)			\
+	RETIS_HOOK_GET(ctx, cfg->offsets, ssk, struct sock *)
+#define retis_get_msk(ctx, cfg)			\
+	RETIS_HOOK_GET(ctx, cfg->offsets, msk, struct mptcp_sock___mptcp *)
+#define retis_get_subflow(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, subflow, struct mptcp_subflow_context___mptcp *)
+#define retis_get_addr(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, addr, struct mptcp_addr_info___mptcp *)
+#define retis_get_rm_list(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, rm_list, struct mptcp_rm_list___mptcp *)
+
+/* Only the fields we report are needed. */
+struct mptcp_sock___mptcp {
+	u32 token;
+} __attribute__((preserve_access_index));
+
+struct mptcp_subflow_context___mptcp {
+	struct sock *tcp_sock;
+	struct sock *conn;
+	s16 local_id;
+	u8 remote_id;
+} __attribute__((preserve_access_index));
+
+struct inet_connection_sock___mptcp {
+	void *icsk_ulp_data;
+} __attribute__((preserve_access_index));
+
+struct mptcp_addr_info___mptcp {
+	u8 id;
+	unsigned short family;
+	__be16 port;
+	union {
+		struct in_addr addr;
+		struct in6_addr addr6;
+	};
+} __attribute__((preserve_access_index));
+
+struct mptcp_rm_list___mptcp {
+	u8 ids[MPTCP_RM_IDS_MAX];
+	u8 nr;
+} __attribute__((preserve_access_index));
+
+/**
+ * MPTCP hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct mptcp_offsets {
+	s8 ssk;
+	s8 msk;
+	s8 subflow;
+	s8 addr;
+	s8 rm_list;
+};
+struct mptcp_config {
+	struct mptcp_offsets offsets;
+	u8 op;
+	/* The subflow socket is the probed function return value. */
+	u8 ssk_ret;
+	/* The probed function returns false on failure. */
+	u8 bool_ret;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct mptcp_config);
+} mptcp_config_map SEC(".maps");
+
+struct mptcp_event {
+	/* MPTCP connection token, 0 if unknown. */
+	u32 token;
+	/* Subflow, only valid if has_subflow is set. Host order ports. */
+	u8 saddr[16];
+	u8 daddr[16];
+	u16 sport;
+	u16 dport;
+	u16 family;
+	s16 local_id;
+	u8 remote_id;
+	u8 has_subflow;
+	/* Address announced or received, only valid if has_addr is set. */
+	u8 addr[16];
+	u16 addr_port;
+	u16 addr_family;
+	u8 addr_id;
+	u8 has_addr;
+	/* Address ids removed. */
+	u8 rm_ids[MPTCP_RM_IDS_MAX];
+	u8 rm_nr;
+	u8 op;
+	u8 failed;
+} __binding;
+
+static __always_inline u32 mptcp_token(struct sock *sk)
+{
+	return sk ? BPF_CORE_READ((struct mptcp_sock___mptcp *)sk, token) : 0;
+}
+
+static __always_inline void
+mptcp_fill_subflow(struct mptcp_event *e,
+		   struct mptcp_subflow_context___mptcp *subflow)
+{
+	struct sock *ssk = BPF_CORE_READ(subflow, tcp_sock);
+	u16 family;
+
+	if (!ssk)
+		return;
+
+	family = BPF_CORE_READ(ssk, __sk_common.skc_family);
+	if (family != AF_INET && family != AF_INET6)
+		return;
+
+	e->has_subflow = 1;
+	e->family = family;
+	e->sport = BPF_CORE_READ(ssk, __sk_common.skc_num);
+	e->dport = bpf_ntohs(BPF_CORE_READ(ssk, __sk_common.skc_dport));
+
+	if (family == AF_INET) {
+		BPF_CORE_READ_INTO(&e->saddr, ssk, __sk_common.skc_rcv_saddr);
+		BPF_CORE_READ_INTO(&e->daddr, ssk, __sk_common.skc_daddr);
+	} else {
+		BPF_CORE_READ_INTO(&e->saddr, ssk, __sk_common.skc_v6_rcv_saddr);
+		BPF_CORE_READ_INTO(&e->daddr, ssk, __sk_common.skc_v6_daddr);
+	}
+
+	/* Ids changed size (and used to be bitfields) across versions. */
+	e->local_id = BPF_CORE_READ_BITFIELD_PROBED(subflow, local_id);
+	e->remote_id = BPF_CORE_READ_BITFIELD_PROBED(subflow, remote_id);
+
+	if (!e->token)
+		e->token = mptcp_token(BPF_CORE_READ(subflow, conn));
+}
+
+static __always_inline void
+mptcp_fill_addr(struct mptcp_event *e, struct mptcp_addr_info___mptcp *addr)
+{
+	e->has_addr = 1;
+	e->addr_id = BPF_CORE_READ(addr, id);
+	e->addr_family = BPF_CORE_READ(addr, family);
+	e->addr_port = bpf_ntohs(BPF_CORE_READ(addr, port));
+
+	if (e->addr_family == AF_INET)
+		BPF_CORE_READ_INTO(&e->addr, addr, addr);
+	else
+		BPF_CORE_READ_INTO(&e->addr, addr, addr6);
+}
+
+static __always_inline void
+mptcp_fill_rm_list(struct mptcp_event *e, struct mptcp_rm_list___mptcp *list)
+{
+	e->rm_nr = BPF_CORE_READ(list, nr);
+	if (e->rm_nr > MPTCP_RM_IDS_MAX)
+		e->rm_nr = MPTCP_RM_IDS_MAX;
+
+	BPF_CORE_READ_INTO(&e->rm_ids, list, ids);
+}
+
+/* MPTCP events are about connections and their subflows, not packets, and
+ * are not subject to filtering.
+ */
+DEFINE_HOOK_RAW(
+	struct mptcp_subflow_context___mptcp *subflow;
+	struct mptcp_addr_info___mptcp *addr;
+	struct mptcp_rm_list___mptcp *list;
+	struct inet_connection_sock___mptcp *icsk;
+	struct mptcp_sock___mptcp *msk;
+	struct mptcp_config *cfg;
+	struct mptcp_event *e;
+	struct sock *ssk;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&mptcp_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	/* No subflow selected for reinjection. */
+	if (cfg->ssk_ret && !ctx->regs.ret)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_MPTCP, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->op = cfg->op;
+	if (cfg->bool_ret)
+		e->failed = !ctx->regs.ret;
+
+	msk = retis_get_msk(ctx, cfg);
+	if (msk)
+		e->token = BPF_CORE_READ(msk, token);
+
+	/* Subflows are retrieved from their context, or from their socket. */
+	subflow = retis_get_subflow(ctx, cfg);
+	if (!subflow) {
+		ssk = cfg->ssk_ret ? (struct sock *)ctx->regs.ret :
+				     retis_get_ssk(ctx, cfg);
+		if (ssk) {
+			icsk = (void *)ssk;
+			subflow = BPF_CORE_READ(icsk, icsk_ulp_data);
+		}
+	}
+	if (subflow)
+		mptcp_fill_subflow(e, subflow);
+
+	addr = retis_get_addr(ctx, cfg);
+	if (addr)
+		mptcp_fill_addr(e, addr);
+
+	list = retis_get_rm_list(ctx, cfg);
+	if (list)
+		mptcp_fill_rm_list(e, list);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
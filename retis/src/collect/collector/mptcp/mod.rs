@@ -0,0 +1,16 @@
+//! # MPTCP module
+//!
+//! Reports MPTCP subflows lifecycle, path manager decisions and reinjections.
+//! Subflows are identified by their TCP 5-tuple.
+
+// Re-export mptcp.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod mptcp;
+pub(crate) use mptcp::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::MptcpEventFactory;
+
+mod mptcp_hook {
+    include!("bpf/.out/mptcp_hook.rs");
+}
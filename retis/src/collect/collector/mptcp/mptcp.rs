@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, mptcp_hook};
+use crate::{
+    bindings::mptcp_hook_uapi::mptcp_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// MPTCP functions and the operation they report.
+const MPTCP_TARGETS: &[(&str, u8)] = &[
+    ("mptcp_pm_fully_established", MPTCP_OP_ESTABLISHED),
+    ("mptcp_finish_join", MPTCP_OP_JOIN),
+    ("mptcp_close_ssk", MPTCP_OP_CLOSE),
+    ("mptcp_subflow_get_retrans", MPTCP_OP_REINJECT),
+    ("mptcp_pm_announce_addr", MPTCP_OP_ANNOUNCE),
+    ("mptcp_pm_add_addr_received", MPTCP_OP_ANNOUNCE_RECEIVED),
+    ("mptcp_pm_remove_addr", MPTCP_OP_REMOVE),
+    ("mptcp_pm_rm_addr_received", MPTCP_OP_REMOVE_RECEIVED),
+];
+
+#[derive(Default)]
+pub(crate) struct MptcpCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for MptcpCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name("mptcp_finish_join") {
+            bail!("Could not resolve MPTCP kernel symbols (is CONFIG_MPTCP set?): {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        // Some of those functions can be inlined, only report what we can.
+        for (target, op) in MPTCP_TARGETS.iter() {
+            if let Ok(sym) = Symbol::from_name(target) {
+                self.add_probe(probes, sym, *op)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl MptcpCollector {
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(&mut self, probes: &mut ProbeBuilderManager, sym: Symbol, op: u8) -> Result<()> {
+        let mut cfg = mptcp_config {
+            op,
+            // Joins report whether they succeeded, reinjections the subflow
+            // selected.
+            bool_ret: (op == MPTCP_OP_JOIN) as u8,
+            ssk_ret: (op == MPTCP_OP_REINJECT) as u8,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset("struct sock *")? {
+            cfg.offsets.ssk = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct mptcp_sock *")? {
+            cfg.offsets.msk = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct mptcp_subflow_context *")? {
+            cfg.offsets.subflow = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct mptcp_addr_info *")? {
+            cfg.offsets.addr = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct mptcp_rm_list *")? {
+            cfg.offsets.rm_list = offset as i8;
+        }
+
+        let probe = match cfg.bool_ret == 1 || cfg.ssk_ret == 1 {
+            true => Probe::kretprobe(sym)?,
+            false => Probe::kprobe(sym)?,
+        };
+        // Please keep in sync with its BPF counterpart in bpf/mptcp_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(mptcp_hook::DATA),
+            "mptcp_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//! Rust<>BPF types definitions for the neighbor module.
+//! Please keep this file in sync with its BPF counterparts in bpf/.
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    bindings::neigh_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string_opt,
+};
+
+/// Neighbor operations. Keep in sync with their BPF counterparts in
+/// bpf/neigh_hook.bpf.c
+pub(super) const NEIGH_OP_UPDATE: u8 = 0;
+pub(super) const NEIGH_OP_TIMER: u8 = 1;
+pub(super) const NEIGH_OP_QUEUE: u8 = 2;
+pub(super) const NEIGH_OP_FAILED: u8 = 3;
+
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+
+#[event_section_factory(FactoryId::Neigh)]
+#[derive(Default)]
+pub(crate) struct NeighEventFactory {}
+
+impl RawEventSectionFactory for NeighEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<neigh_event>(&raw_sections)?;
+
+        event.neigh = Some(NeighEvent {
+            op: match raw.op {
+                NEIGH_OP_UPDATE => "update",
+                NEIGH_OP_TIMER => "timer",
+                NEIGH_OP_QUEUE => "queue",
+                NEIGH_OP_FAILED => "failed",
+                x => bail!("Unknown neighbor operation ({x})"),
+            }
+            .to_string(),
+            addr: match raw.family {
+                AF_INET => {
+                    Ipv4Addr::new(raw.addr[0], raw.addr[1], raw.addr[2], raw.addr[3]).to_string()
+                }
+                AF_INET6 => Ipv6Addr::from(raw.addr).to_string(),
+                x => bail!("Unknown neighbor address family ({x})"),
+            },
+            dev: raw_to_string_opt!(&raw.dev)?,
+            state: nud_state_str(raw.state),
+            new_state: (raw.op == NEIGH_OP_UPDATE).then(|| nud_state_str(raw.new_state)),
+            lladdr: (raw.has_lladdr == 1).then(|| {
+                raw.lladdr
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<Vec<_>>()
+                    .join(":")
+            }),
+            probes: raw.probes,
+            queue_len: raw.queue_len,
+        });
+
+        Ok(())
+    }
+}
+
+/// Get the name of a neighbor state (NUD_*).
+fn nud_state_str(state: u8) -> String {
+    match state {
+        0x00 => "none",
+        0x01 => "incomplete",
+        0x02 => "reachable",
+        0x04 => "stale",
+        0x08 => "delay",
+        0x10 => "probe",
+        0x20 => "failed",
+        0x40 => "noarp",
+        0x80 => "permanent",
+        x => return format!("{x:#04x}"),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neigh_states() {
+        assert_eq!(nud_state_str(0), "none");
+        assert_eq!(nud_state_str(0x01), "incomplete");
+        assert_eq!(nud_state_str(0x20), "failed");
+        assert_eq!(nud_state_str(0x03), "0x03");
+    }
+}
@@ -0,0 +1,162 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+/* Keep in sync with their Rust counterparts in bpf.rs */
+#define NEIGH_OP_UPDATE		0
+#define NEIGH_OP_TIMER		1
+#define NEIGH_OP_QUEUE		2
+#define NEIGH_OP_FAILED		3
+
+#define AF_INET			2
+#define AF_INET6		10
+
+#define NUD_INCOMPLETE		0x01
+#define NUD_REACHABLE		0x02
+#define NUD_STALE		0x04
+#define NUD_DELAY		0x08
+#define NUD_PROBE		0x10
+#define NUD_NOARP		0x40
+#define NUD_PERMANENT		0x80
+#define NUD_CONNECTED		(NUD_PERMANENT | NUD_NOARP | NUD_REACHABLE)
+#define NUD_VALID		(NUD_CONNECTED | NUD_PROBE | NUD_STALE | NUD_DELAY)
+
+BINDING_DEF(IFNAMSIZ, 16)
+BINDING_DEF(NEIGH_LLADDR_MAX, 6)
+
+#define retis_get_neigh(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, neigh, struct neighbour___neigh *)
+#define retis_get_lladdr(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, lladdr, const u8 *)
+#define retis_get_new_state(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, new_state, u8)
+
+/* Only the fields we report are needed. */
+struct neigh_table___neigh {
+	int family;
+} __attribute__((preserve_access_index));
+
+struct neighbour___neigh {
+	struct neigh_table___neigh *tbl;
+	atomic_t probes;
+	u8 nud_state;
+	struct sk_buff_head arp_queue;
+	unsigned char ha[32];
+	struct net_device *dev;
+	u8 primary_key[0];
+} __attribute__((preserve_access_index));
+
+/**
+ * Neighbor hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct neigh_offsets {
+	s8 neigh;
+	s8 lladdr;
+	s8 new_state;
+};
+struct neigh_config {
+	struct neigh_offsets offsets;
+	u8 op;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct neigh_config);
+} neigh_config_map SEC(".maps");
+
+struct neigh_event {
+	/* Neighbor address. */
+	u8 addr[16];
+	u8 family;
+	u8 op;
+	/* NUD_* state, old one on updates. */
+	u8 state;
+	/* New state, only valid on updates. */
+	u8 new_state;
+	/* Link-layer address, only valid if has_lladdr is set. New one on
+	 * updates.
+	 */
+	u8 lladdr[NEIGH_LLADDR_MAX];
+	u8 has_lladdr;
+	/* Number of solicitations sent. */
+	u32 probes;
+	/* Number of packets queued waiting for the resolution. */
+	u32 queue_len;
+	char dev[IFNAMSIZ];
+} __binding;
+
+DEFINE_HOOK_RAW(
+	struct neighbour___neigh *n;
+	struct neigh_config *cfg;
+	struct net_device *dev;
+	struct neigh_event *e;
+	struct sk_buff *skb;
+	const u8 *lladdr;
+	u32 key = 0;
+	u8 state;
+
+	/* Probes having access to an skb (packets waiting for a resolution)
+	 * are subject to filtering, others (state transitions) are not.
+	 */
+	skb = retis_get_sk_buff(ctx);
+	if (skb && !RETIS_TRACKABLE(ctx))
+		return 0;
+
+	cfg = bpf_map_lookup_elem(&neigh_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	n = retis_get_neigh(ctx, cfg);
+	if (!n)
+		return 0;
+
+	state = BPF_CORE_READ(n, nud_state);
+
+	/* Only report packets held back while the neighbor is being resolved,
+	 * others are sent right away, see __neigh_event_send().
+	 */
+	if (cfg->op == NEIGH_OP_QUEUE && state & NUD_VALID)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_NEIGH, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->op = cfg->op;
+	e->state = state;
+	e->probes = BPF_CORE_READ(n, probes.counter);
+	e->queue_len = BPF_CORE_READ(n, arp_queue.qlen);
+
+	e->family = BPF_CORE_READ(n, tbl, family);
+	if (e->family == AF_INET)
+		bpf_core_read(e->addr, 4, &n->primary_key);
+	else if (e->family == AF_INET6)
+		bpf_core_read(e->addr, 16, &n->primary_key);
+
+	dev = BPF_CORE_READ(n, dev);
+	if (dev)
+		bpf_probe_read(e->dev, IFNAMSIZ, dev->name);
+
+	if (cfg->op == NEIGH_OP_UPDATE) {
+		e->new_state = retis_get_new_state(ctx, cfg);
+		lladdr = retis_get_lladdr(ctx, cfg);
+	} else {
+		lladdr = state & NUD_VALID ? (const u8 *)&n->ha : NULL;
+	}
+
+	/* Only Ethernet-like link-layer addresses are reported. */
+	if (lladdr && dev && BPF_CORE_READ(dev, addr_len) == NEIGH_LLADDR_MAX &&
+	    !bpf_probe_read_kernel(e->lladdr, NEIGH_LLADDR_MAX, lladdr))
+		e->has_lladdr = 1;
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,16 @@
+//! # Neighbor module
+//!
+//! Reports neighbor (ARP and NDISC) state transitions and the packets waiting
+//! for, or dropped because of, a neighbor resolution.
+
+// Re-export neigh.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod neigh;
+pub(crate) use neigh::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::NeighEventFactory;
+
+mod neigh_hook {
+    include!("bpf/.out/neigh_hook.rs");
+}
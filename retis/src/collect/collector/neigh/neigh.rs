@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, neigh_hook};
+use crate::{
+    bindings::neigh_hook_uapi::neigh_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Neighbor state transitions.
+const NEIGH_TP_UPDATE: &str = "neigh:neigh_update";
+/// Neighbor timer runs, where resolutions are retried and fail.
+const NEIGH_TP_TIMER: &str = "neigh:neigh_timer_handler";
+/// Packets sent to a neighbor not yet resolved.
+const NEIGH_QUEUE: &str = "__neigh_event_send";
+/// Packets dropped as their neighbor resolution failed, for ARP and NDISC. The
+/// latter might not be available.
+const NEIGH_FAILED_TARGETS: &[&str] = &["arp_error_report", "ndisc_error_report"];
+
+#[derive(Default)]
+pub(crate) struct NeighCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for NeighCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name(NEIGH_QUEUE) {
+            bail!("Could not resolve the neighbor resolution function: {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let sym = Symbol::from_name(NEIGH_QUEUE)?;
+        let cfg = Self::config(&sym, NEIGH_OP_QUEUE)?;
+        self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+
+        for target in NEIGH_FAILED_TARGETS.iter() {
+            if let Ok(sym) = Symbol::from_name(target) {
+                let cfg = Self::config(&sym, NEIGH_OP_FAILED)?;
+                self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+            }
+        }
+
+        // Neighbor tracepoints are not available on older kernels.
+        if let Ok(sym) = Symbol::from_name(NEIGH_TP_UPDATE) {
+            let mut cfg = Self::config(&sym, NEIGH_OP_UPDATE)?;
+            if let Some(offset) = sym.parameter_offset("u8 *")? {
+                cfg.offsets.lladdr = offset as i8;
+            }
+            if let Some(offset) = sym.parameter_offset("u8")? {
+                cfg.offsets.new_state = offset as i8;
+            }
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(NEIGH_TP_TIMER) {
+            let cfg = Self::config(&sym, NEIGH_OP_TIMER)?;
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl NeighCollector {
+    fn config(sym: &Symbol, op: u8) -> Result<neigh_config> {
+        let mut cfg = neigh_config {
+            op,
+            ..Default::default()
+        };
+        match sym.parameter_offset("struct neighbour *")? {
+            Some(offset) => cfg.offsets.neigh = offset as i8,
+            None => bail!("Could not find the neighbor parameter of {}", sym.name()),
+        }
+        Ok(cfg)
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: neigh_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/neigh_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(neigh_hook::DATA),
+            "neigh_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
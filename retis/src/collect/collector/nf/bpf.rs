@@ -0,0 +1,102 @@
+use anyhow::Result;
+
+use crate::{
+    bindings::nf_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+};
+
+/// Protocol families, see NFPROTO_* in include/uapi/linux/netfilter.h.
+const NFPROTO_INET: u8 = 1;
+const NFPROTO_IPV4: u8 = 2;
+const NFPROTO_ARP: u8 = 3;
+const NFPROTO_NETDEV: u8 = 5;
+const NFPROTO_BRIDGE: u8 = 7;
+const NFPROTO_IPV6: u8 = 10;
+
+#[event_section_factory(FactoryId::Nf)]
+#[derive(Default)]
+pub(crate) struct NfEventFactory {}
+
+impl RawEventSectionFactory for NfEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<nf_event>(&raw_sections)?;
+
+        let mut nf = NfEvent {
+            family: family_str(raw.pf),
+            hook: hook_str(raw.pf, raw.hook),
+            ..Default::default()
+        };
+
+        // See nf_hook_slow() in net/netfilter/core.c.
+        if raw.exit == 1 {
+            nf.verdict = Some(
+                match raw.ret {
+                    1 => "accept",
+                    0 => "stolen",
+                    _ => "drop",
+                }
+                .to_string(),
+            );
+            nf.err = Some(raw.ret).filter(|ret| *ret < 0);
+        }
+
+        event.nf = Some(nf);
+        Ok(())
+    }
+}
+
+/// Get the name of a protocol family.
+fn family_str(pf: u8) -> String {
+    match pf {
+        NFPROTO_INET => "inet",
+        NFPROTO_IPV4 => "ipv4",
+        NFPROTO_ARP => "arp",
+        NFPROTO_NETDEV => "netdev",
+        NFPROTO_BRIDGE => "bridge",
+        NFPROTO_IPV6 => "ipv6",
+        x => return x.to_string(),
+    }
+    .to_string()
+}
+
+/// Get the name of a hook, which depends on the protocol family. See
+/// `enum nf_inet_hooks`, `enum nf_dev_hooks` and NF_ARP_* in the kernel.
+fn hook_str(pf: u8, hook: u8) -> String {
+    match (pf, hook) {
+        (NFPROTO_NETDEV, 0) => "ingress",
+        (NFPROTO_NETDEV, 1) => "egress",
+        (NFPROTO_ARP, 0) => "input",
+        (NFPROTO_ARP, 1) => "output",
+        (NFPROTO_ARP, 2) => "forward",
+        (NFPROTO_NETDEV | NFPROTO_ARP, x) => return x.to_string(),
+        (_, 0) => "prerouting",
+        (_, 1) => "input",
+        (_, 2) => "forward",
+        (_, 3) => "output",
+        (_, 4) => "postrouting",
+        (_, 5) => "ingress",
+        (_, x) => return x.to_string(),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hook_names() {
+        assert_eq!(hook_str(NFPROTO_IPV4, 0), "prerouting");
+        assert_eq!(hook_str(NFPROTO_IPV6, 4), "postrouting");
+        assert_eq!(hook_str(NFPROTO_BRIDGE, 2), "forward");
+        assert_eq!(hook_str(NFPROTO_NETDEV, 1), "egress");
+        assert_eq!(hook_str(NFPROTO_ARP, 1), "output");
+        assert_eq!(hook_str(NFPROTO_ARP, 3), "3");
+        assert_eq!(family_str(42), "42");
+    }
+}
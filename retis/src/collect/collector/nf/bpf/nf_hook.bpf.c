@@ -0,0 +1,75 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+#define retis_get_nf_hook_state(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, nf_hook_state, struct nf_hook_state___nf *)
+
+/* Only the hook and family are needed. */
+struct nf_hook_state___nf {
+	u8 hook;
+	u8 pf;
+} __attribute__((preserve_access_index));
+
+/**
+ * Netfilter hook configuration.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct nf_offsets {
+	s8 nf_hook_state;
+};
+struct nf_config {
+	struct nf_offsets offsets;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct nf_config);
+} nf_config_map SEC(".maps");
+
+struct nf_event {
+	u8 hook;
+	u8 pf;
+	/* Set when leaving the hook, ret then holding the return value of
+	 * nf_hook_slow: 1 if the packet was accepted, 0 if stolen or queued
+	 * and a negative errno if dropped.
+	 */
+	u8 exit;
+	s32 ret;
+} __binding;
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct nf_hook_state___nf *state;
+	struct nf_config *cfg;
+	struct nf_event *e;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&nf_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	state = retis_get_nf_hook_state(ctx, cfg);
+	if (!state)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_NF, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->hook = BPF_CORE_READ(state, hook);
+	e->pf = BPF_CORE_READ(state, pf);
+
+	if (ctx->probe_type == KERNEL_PROBE_KRETPROBE) {
+		e->exit = 1;
+		e->ret = (s32)ctx->regs.ret;
+	}
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,15 @@
+//! # Netfilter module
+//!
+//! Provides support for tracing Netfilter hooks and their verdicts.
+
+// Re-export nf.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod nf;
+pub(crate) use nf::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::NfEventFactory;
+
+mod nf_hook {
+    include!("bpf/.out/nf_hook.rs");
+}
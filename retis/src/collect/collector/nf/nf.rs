@@ -0,0 +1,95 @@
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd},
+    sync::Arc,
+};
+
+use anyhow::{bail, Result};
+use libbpf_rs::MapCore;
+
+use super::nf_hook;
+use crate::{
+    bindings::nf_hook_uapi::nf_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Function running the Netfilter hooks, when at least one is registered.
+const NF_HOOK_SLOW: &str = "nf_hook_slow";
+
+#[derive(Default)]
+pub(crate) struct NfCollector {
+    // Used to keep a reference to our internal config map.
+    #[allow(dead_code)]
+    config_map: Option<libbpf_rs::MapHandle>,
+}
+
+impl NfCollector {
+    fn config_map() -> Result<libbpf_rs::MapHandle> {
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its BPF counterpart in bpf/nf_hook.bpf.c
+        libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Array,
+            Some("nf_config_map"),
+            mem::size_of::<u32>() as u32,
+            mem::size_of::<nf_config>() as u32,
+            1,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the nf config map: {}", e))
+    }
+}
+
+impl Collector for NfCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name(NF_HOOK_SLOW) {
+            bail!("Could not resolve Netfilter kernel symbol: {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let config_map = Self::config_map()?;
+        let sym = Symbol::from_name(NF_HOOK_SLOW)?;
+
+        let mut cfg = nf_config::default();
+        if let Some(offset) = sym.parameter_offset("struct nf_hook_state *")? {
+            cfg.offsets.nf_hook_state = offset as i8;
+        }
+
+        let cfg = unsafe { plain::as_bytes(&cfg) };
+        config_map.update(&0_u32.to_ne_bytes(), cfg, libbpf_rs::MapFlags::empty())?;
+
+        // Report packets entering the hooks and the verdict when they leave
+        // them. Both probes share the same configuration.
+        for mut probe in [Probe::kprobe(sym.clone())?, Probe::kretprobe(sym)?] {
+            probe.add_hook(
+                Hook::from(nf_hook::DATA)
+                    .reuse_map("nf_config_map", config_map.as_fd().as_raw_fd())?
+                    .to_owned(),
+            )?;
+            probes.register_probe(probe)?;
+        }
+
+        self.config_map = Some(config_map);
+        Ok(())
+    }
+}
@@ -48,11 +48,15 @@ DEFINE_HOOK_RAW(
 	u64 cookie;
 	u32 inum;
 
+	/* Get the netns from the skb if possible, as in the end we care about
+	 * data linked to packets. Probes not having access to an skb can still
+	 * report it using their parameters.
+	 */
 	skb = retis_get_sk_buff(ctx);
-	if (!skb || !skb_is_tracked(skb))
+	if (skb && !skb_is_tracked(skb))
 		return 0;
 
-	net = get_net_from_skb(skb);
+	net = skb ? get_net_from_skb(skb) : NULL;
 	if (!net) {
 		/* Fallback to parameters. */
 		net = get_net_from_parms(ctx);
@@ -56,6 +56,8 @@ impl RawEventSectionFactory for NsEventFactory {
         event.netns = Some(NetnsEvent {
             cookie: Some(raw.cookie).filter(|_| self.net_cookie),
             inum: raw.inum,
+            generation: None,
+            container: None,
         });
 
         Ok(())
@@ -0,0 +1,55 @@
+use anyhow::Result;
+
+use crate::{
+    bindings::ns_lifecycle_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string,
+};
+
+/// Netns lifecycle event types. Keep in sync with their BPF counterparts in
+/// bpf/ns_lifecycle_hook.bpf.c
+pub(super) const NS_EV_CREATE: u8 = 0;
+pub(super) const NS_EV_DELETE: u8 = 1;
+pub(super) const NS_EV_DEV_MOVE: u8 = 2;
+
+#[event_section_factory(FactoryId::NsLifecycle)]
+#[derive(Default)]
+pub(crate) struct NsLifecycleEventFactory {}
+
+impl RawEventSectionFactory for NsLifecycleEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<ns_lifecycle_event>(&raw_sections)?;
+        let netns = |cookie, inum| NsLifecycleNetns {
+            cookie: Some(cookie).filter(|_| raw.has_cookie == 1),
+            inum,
+            generation: None,
+        };
+
+        let mut ns = NsLifecycleEvent {
+            netns: netns(raw.cookie, raw.inum),
+            ..Default::default()
+        };
+
+        match raw.type_ {
+            NS_EV_CREATE => ns.op = "create".to_string(),
+            NS_EV_DELETE => ns.op = "delete".to_string(),
+            NS_EV_DEV_MOVE => {
+                ns.op = "dev-move".to_string();
+                ns.target = Some(netns(raw.target_cookie, raw.target_inum));
+                ns.dev = Some(NsLifecycleDev {
+                    name: raw_to_string!(&raw.dev)?,
+                    ifindex: raw.ifindex,
+                });
+            }
+            x => ns.op = x.to_string(),
+        }
+
+        event.ns_lifecycle = Some(ns);
+        Ok(())
+    }
+}
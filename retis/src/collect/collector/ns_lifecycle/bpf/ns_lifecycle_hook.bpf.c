@@ -0,0 +1,89 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define NS_EV_CREATE		0
+#define NS_EV_DELETE		1
+#define NS_EV_DEV_MOVE		2
+
+BINDING_DEF(IFNAMSIZ, 16)
+
+struct ns_lifecycle_config {
+	u8 type;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct ns_lifecycle_config);
+} ns_lifecycle_config_map SEC(".maps");
+
+struct ns_lifecycle_event {
+	/* Netns being created or deleted, or the one a device is moved from. */
+	u64 cookie;
+	/* Netns a device is moved to. */
+	u64 target_cookie;
+	u32 inum;
+	u32 target_inum;
+	u32 ifindex;
+	u8 type;
+	/* Set if netns cookies are available. */
+	u8 has_cookie;
+	char dev[IFNAMSIZ];
+} __binding;
+
+DEFINE_HOOK_RAW(
+	/* Netns cookies are not available on older kernels. */
+	bool get_cookie = bpf_core_field_exists(struct net, net_cookie);
+	struct ns_lifecycle_config *cfg;
+	struct ns_lifecycle_event *e;
+	struct net_device *dev = NULL;
+	struct net *net;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&ns_lifecycle_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	net = retis_get_net(ctx);
+	if (!net)
+		return 0;
+
+	/* Only report successful initializations. */
+	if (cfg->type == NS_EV_CREATE && ctx->regs.ret)
+		return 0;
+
+	if (cfg->type == NS_EV_DEV_MOVE) {
+		dev = retis_get_net_device(ctx);
+		if (!dev)
+			return 0;
+	}
+
+	e = get_event_zsection(event, COLLECTOR_NS_LIFECYCLE, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->type = cfg->type;
+	e->has_cookie = get_cookie;
+
+	if (dev) {
+		bpf_probe_read_kernel_str(e->dev, IFNAMSIZ, dev->name);
+		e->ifindex = BPF_CORE_READ(dev, ifindex);
+
+		/* The device is still in its original netns at this point. */
+		if (get_cookie)
+			e->target_cookie = BPF_CORE_READ(net, net_cookie);
+		e->target_inum = BPF_CORE_READ(net, ns.inum);
+		net = BPF_CORE_READ(dev, nd_net.net);
+	}
+
+	if (get_cookie)
+		e->cookie = BPF_CORE_READ(net, net_cookie);
+	e->inum = BPF_CORE_READ(net, ns.inum);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,16 @@
+//! # Netns lifecycle module
+//!
+//! Reports network namespaces creation and deletion, as well as devices being
+//! moved between namespaces.
+
+// Re-export ns_lifecycle.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod ns_lifecycle;
+pub(crate) use ns_lifecycle::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::NsLifecycleEventFactory;
+
+mod ns_lifecycle_hook {
+    include!("bpf/.out/ns_lifecycle_hook.rs");
+}
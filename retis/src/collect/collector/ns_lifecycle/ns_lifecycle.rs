@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, ns_lifecycle_hook};
+use crate::{
+    bindings::ns_lifecycle_hook_uapi::ns_lifecycle_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Netns initialization, after its inum was allocated. This is a pernet
+/// operation, called indirectly, and can't be inlined.
+const NS_CREATE: &str = "net_ns_net_init";
+/// Netns cleanup, before its inum is freed.
+const NS_DELETE: &str = "net_ns_net_exit";
+/// Devices being moved to another netns. Only the first one found is used, the
+/// latter being used on older kernels.
+const NS_DEV_MOVE_TARGETS: &[&str] = &["__dev_change_net_namespace", "dev_change_net_namespace"];
+
+#[derive(Default)]
+pub(crate) struct NsLifecycleCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for NsLifecycleCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct net *", "struct net_device *"])
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name(NS_CREATE) {
+            bail!("Could not resolve the netns initialization function: {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let sym = Symbol::from_name(NS_CREATE)?;
+        self.add_probe(probes, Probe::kretprobe(sym)?, NS_EV_CREATE)?;
+
+        if let Ok(sym) = Symbol::from_name(NS_DELETE) {
+            self.add_probe(probes, Probe::kprobe(sym)?, NS_EV_DELETE)?;
+        }
+
+        if let Some(sym) = NS_DEV_MOVE_TARGETS
+            .iter()
+            .find_map(|target| Symbol::from_name(target).ok())
+        {
+            self.add_probe(probes, Probe::kprobe(sym)?, NS_EV_DEV_MOVE)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl NsLifecycleCollector {
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        r#type: u8,
+    ) -> Result<()> {
+        let cfg = ns_lifecycle_config { type_: r#type };
+        // Please keep in sync with its BPF counterpart in bpf/ns_lifecycle_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(ns_lifecycle_hook::DATA),
+            "ns_lifecycle_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
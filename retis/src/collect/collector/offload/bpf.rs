@@ -0,0 +1,117 @@
+//! Rust<>BPF types definitions for the offload module.
+//! Please keep this file in sync with its BPF counterparts in bpf/.
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::{
+    bindings::{
+        offload_common_uapi::*, offload_filter_hook_uapi::offload_filter_event,
+        offload_skb_hook_uapi::offload_skb_event,
+    },
+    core::{
+        events::{
+            parse_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+            RawEventSectionFactory,
+        },
+        inspect::parse_enum,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string, raw_to_string_opt,
+};
+
+/// Offload filter operations. Keep in sync with their BPF counterparts in
+/// bpf/offload_filter_hook.bpf.c
+pub(super) const OFFLOAD_OP_ADD: u8 = 0;
+pub(super) const OFFLOAD_OP_REPLACE: u8 = 1;
+pub(super) const OFFLOAD_OP_DESTROY: u8 = 2;
+
+#[event_section_factory(FactoryId::Offload)]
+pub(crate) struct OffloadEventFactory {
+    /// Classifier setup types (enum tc_setup_type), as they vary across
+    /// kernel versions.
+    setup_types: HashMap<u32, String>,
+}
+
+impl RawEventSectionFactory for OffloadEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let mut offload = OffloadEvent::default();
+
+        for section in raw_sections.iter() {
+            match section.header.data_type as u32 {
+                OFFLOAD_SECTION_FILTER => {
+                    offload.filter = Some(self.unmarshal_filter(section)?);
+                }
+                OFFLOAD_SECTION_SKB => {
+                    let raw = parse_raw_section::<offload_skb_event>(section)?;
+                    offload.skb = Some(OffloadSkb {
+                        fwd_mark: raw.fwd_mark == 1,
+                        l3_fwd_mark: raw.l3_fwd_mark == 1,
+                    });
+                }
+                x => bail!("Unknown data type ({x})"),
+            }
+        }
+
+        event.offload = Some(offload);
+        Ok(())
+    }
+}
+
+impl OffloadEventFactory {
+    pub(crate) fn new() -> Result<Self> {
+        Ok(Self {
+            setup_types: parse_enum("tc_setup_type", &["TC_SETUP_"])?
+                .into_iter()
+                .map(|(val, name)| (val, name.to_lowercase()))
+                .collect(),
+        })
+    }
+
+    fn unmarshal_filter(&self, section: &BpfRawSection) -> Result<OffloadFilter> {
+        let raw = parse_raw_section::<offload_filter_event>(section)?;
+
+        Ok(OffloadFilter {
+            op: op_str(raw.op)?,
+            setup: self
+                .setup_types
+                .get(&raw.setup_type)
+                .cloned()
+                .unwrap_or_else(|| raw.setup_type.to_string()),
+            dev: raw_to_string_opt!(&raw.dev)?,
+            block: raw.block,
+            chain: raw.chain,
+            prio: raw.prio,
+            protocol: raw.protocol,
+            cookie: Some(raw.cookie).filter(|c| *c != 0),
+            // Destroy requests do not fail, the count is not meaningful there.
+            in_hw: (raw.op != OFFLOAD_OP_DESTROY && raw.ret >= 0).then_some(raw.ret as u32),
+            error: (raw.ret < 0).then_some(raw.ret),
+            msg: raw_to_string_opt!(&raw.msg)?,
+        })
+    }
+}
+
+/// Get the name of an offload filter operation.
+fn op_str(op: u8) -> Result<String> {
+    Ok(match op {
+        OFFLOAD_OP_ADD => "add",
+        OFFLOAD_OP_REPLACE => "replace",
+        OFFLOAD_OP_DESTROY => "destroy",
+        x => bail!("Unknown offload operation ({x})"),
+    }
+    .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offload_ops() {
+        assert_eq!(op_str(OFFLOAD_OP_ADD).unwrap(), "add");
+        assert_eq!(op_str(OFFLOAD_OP_DESTROY).unwrap(), "destroy");
+        assert!(op_str(3).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+#ifndef __COLLECTOR_OFFLOAD_COMMON__
+#define __COLLECTOR_OFFLOAD_COMMON__
+
+#include <vmlinux.h>
+
+/* Section types, as the offload collector reports different kinds of data. */
+enum offload_sections {
+	OFFLOAD_SECTION_FILTER = 1,
+	OFFLOAD_SECTION_SKB,
+} __binding;
+
+#endif /* __COLLECTOR_OFFLOAD_COMMON__ */
@@ -0,0 +1,162 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_endian.h>
+
+#include <common.h>
+#include <offload_common.h>
+
+/* Keep in sync with its Rust counterpart in offload.rs */
+#define OFFLOAD_OP_ADD		0
+#define OFFLOAD_OP_REPLACE	1
+#define OFFLOAD_OP_DESTROY	2
+
+BINDING_DEF(IFNAMSIZ, 16)
+BINDING_DEF(OFFLOAD_MSG_MAX, 128)
+
+#define retis_get_tcf_block(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, tcf_block, struct tcf_block___offload *)
+#define retis_get_setup_type(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, setup_type, u32)
+#define retis_get_type_data(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, type_data, void *)
+
+/* Only the fields we report are needed. */
+struct netlink_ext_ack___offload {
+	const char *_msg;
+} __attribute__((preserve_access_index));
+
+/* Common part of all classifier offload requests. */
+struct flow_cls_common_offload___offload {
+	u32 chain_index;
+	__be16 protocol;
+	u32 prio;
+	struct netlink_ext_ack___offload *extack;
+} __attribute__((preserve_access_index));
+
+struct flow_cls_offload___offload {
+	struct flow_cls_common_offload___offload common;
+	unsigned long cookie;
+} __attribute__((preserve_access_index));
+
+struct netdev_queue___offload {
+	struct net_device *dev;
+} __attribute__((preserve_access_index));
+
+struct Qdisc___offload {
+	struct netdev_queue___offload *dev_queue;
+} __attribute__((preserve_access_index));
+
+struct tcf_block___offload {
+	struct Qdisc___offload *q;
+	u32 index;
+} __attribute__((preserve_access_index));
+
+/**
+ * Offload filter hook configuration. Each probe has its own instance, as the
+ * data to retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct offload_filter_offsets {
+	s8 tcf_block;
+	s8 setup_type;
+	s8 type_data;
+};
+/**
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct offload_filter_config {
+	struct offload_filter_offsets offsets;
+	/* Value of TC_SETUP_CLSFLOWER, -1 if unknown. */
+	s64 clsflower;
+	u8 op;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct offload_filter_config);
+} offload_filter_config_map SEC(".maps");
+
+struct offload_filter_event {
+	/* Filter cookie, only set for flower. */
+	u64 cookie;
+	/* Number of hardware callbacks which accepted the request, or error. */
+	s32 ret;
+	u32 setup_type;
+	u32 block;
+	u32 chain;
+	u32 prio;
+	u16 protocol;
+	u8 op;
+	char dev[IFNAMSIZ];
+	/* Message reported by the driver, if any. */
+	char msg[OFFLOAD_MSG_MAX];
+} __binding;
+
+/* Classifiers request their filters to be offloaded using the tc_setup_cb_*
+ * family of functions, which call the drivers bound to the block. This is
+ * also how OvS offloads its flows, through tc flower. This hook runs when those
+ * functions return, so the result of the request is known.
+ */
+DEFINE_HOOK_RAW(
+	struct flow_cls_common_offload___offload *common;
+	struct netlink_ext_ack___offload *extack;
+	struct offload_filter_config *cfg;
+	struct offload_filter_event *e;
+	struct tcf_block___offload *block;
+	struct net_device *dev;
+	const char *msg;
+	u32 setup_type;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&offload_filter_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	block = retis_get_tcf_block(ctx, cfg);
+	common = retis_get_type_data(ctx, cfg);
+	if (!block || !common)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_OFFLOAD, OFFLOAD_SECTION_FILTER,
+			       sizeof(*e));
+	if (!e)
+		return 0;
+
+	setup_type = retis_get_setup_type(ctx, cfg);
+
+	e->op = cfg->op;
+	e->ret = ctx->regs.ret;
+	e->setup_type = setup_type;
+	e->block = BPF_CORE_READ(block, index);
+
+	/* Shared blocks are not attached to a single device. */
+	dev = BPF_CORE_READ(block, q, dev_queue, dev);
+	if (dev)
+		bpf_probe_read_kernel_str(e->dev, IFNAMSIZ, dev->name);
+
+	/* All classifier offload requests start with the common part. */
+	e->chain = BPF_CORE_READ(common, chain_index);
+	e->protocol = bpf_ntohs(BPF_CORE_READ(common, protocol));
+	e->prio = BPF_CORE_READ(common, prio);
+
+	extack = BPF_CORE_READ(common, extack);
+	if (extack) {
+		msg = BPF_CORE_READ(extack, _msg);
+		if (msg)
+			bpf_probe_read_kernel_str(e->msg, OFFLOAD_MSG_MAX, msg);
+	}
+
+	if (cfg->clsflower >= 0 && setup_type == cfg->clsflower)
+		e->cookie = BPF_CORE_READ((struct flow_cls_offload___offload *)common,
+					  cookie);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
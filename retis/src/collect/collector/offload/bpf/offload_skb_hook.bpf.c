@@ -0,0 +1,46 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include <offload_common.h>
+
+struct offload_skb_event {
+	/* Packet was already forwarded in hardware (switchdev). */
+	u8 fwd_mark;
+	/* Packet was already routed in hardware (switchdev). */
+	u8 l3_fwd_mark;
+} __binding;
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct offload_skb_event *e;
+	u8 fwd_mark, l3_fwd_mark = 0;
+	struct sk_buff *skb;
+
+	skb = retis_get_sk_buff(ctx);
+	if (!skb)
+		return 0;
+
+	/* Both marks depend on CONFIG_NET_SWITCHDEV. */
+	if (!bpf_core_field_exists(skb->offload_fwd_mark))
+		return 0;
+
+	fwd_mark = BPF_CORE_READ_BITFIELD_PROBED(skb, offload_fwd_mark);
+	if (bpf_core_field_exists(skb->offload_l3_fwd_mark))
+		l3_fwd_mark = BPF_CORE_READ_BITFIELD_PROBED(skb, offload_l3_fwd_mark);
+
+	/* Only report packets which were handled in hardware. */
+	if (!fwd_mark && !l3_fwd_mark)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_OFFLOAD, OFFLOAD_SECTION_SKB,
+			       sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->fwd_mark = fwd_mark;
+	e->l3_fwd_mark = l3_fwd_mark;
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
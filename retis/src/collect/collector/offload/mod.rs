@@ -0,0 +1,22 @@
+//! # Offload module
+//!
+//! Reports hardware offload requests of classifier filters (e.g. tc flower,
+//! which OvS uses to offload its flows) and their result, as well as packets
+//! marked as already handled in hardware.
+
+// Re-export offload.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod offload;
+pub(crate) use offload::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::OffloadEventFactory;
+
+mod hooks {
+    pub(super) mod filter {
+        include!("bpf/.out/offload_filter_hook.rs");
+    }
+    pub(super) mod skb {
+        include!("bpf/.out/offload_skb_hook.rs");
+    }
+}
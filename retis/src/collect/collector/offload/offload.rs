@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, hooks};
+use crate::{
+    bindings::offload_filter_hook_uapi::offload_filter_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        inspect::parse_enum,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Classifier filters being offloaded, replaced or removed from the hardware.
+/// Those are used by all classifiers supporting offload (flower, u32, matchall,
+/// bpf).
+const OFFLOAD_FILTER_TARGETS: &[(&str, u8)] = &[
+    ("tc_setup_cb_add", OFFLOAD_OP_ADD),
+    ("tc_setup_cb_replace", OFFLOAD_OP_REPLACE),
+    ("tc_setup_cb_destroy", OFFLOAD_OP_DESTROY),
+];
+
+#[derive(Default)]
+pub(crate) struct OffloadCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for OffloadCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct sk_buff *"])
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        // Filter offload support is part of the core tc code, if not there
+        // offload is not supported at all.
+        if let Err(e) = Symbol::from_name(OFFLOAD_FILTER_TARGETS[0].0) {
+            bail!("Could not resolve the filter offload functions: {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        // Setup types values vary across kernel versions, retrieve the flower
+        // one as it has more data to report.
+        let clsflower = parse_enum("tc_setup_type", &["TC_SETUP_"])?
+            .into_iter()
+            .find_map(|(val, name)| (name == "CLSFLOWER").then_some(val as i64))
+            .unwrap_or(-1);
+
+        for (target, op) in OFFLOAD_FILTER_TARGETS.iter() {
+            if let Ok(sym) = Symbol::from_name(target) {
+                self.add_probe(probes, sym, *op, clsflower)?;
+            }
+        }
+
+        probes.register_kernel_hook(Hook::from(hooks::skb::DATA))
+    }
+}
+
+impl OffloadCollector {
+    /// Create the config map of a filter offload probe, and register it with
+    /// our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        sym: Symbol,
+        op: u8,
+        clsflower: i64,
+    ) -> Result<()> {
+        let mut cfg = offload_filter_config {
+            clsflower,
+            op,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset("struct tcf_block *")? {
+            cfg.offsets.tcf_block = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("enum tc_setup_type")? {
+            cfg.offsets.setup_type = offset as i8;
+            // The request data (void *) directly follows its type, and can't
+            // be looked up by type.
+            cfg.offsets.type_data = offset as i8 + 1;
+        }
+
+        let probe = Probe::kretprobe(sym)?;
+        // Please keep in sync with its BPF counterpart in bpf/offload_filter_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(hooks::filter::DATA),
+            "offload_filter_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@
 //! the bpf raw event. Please keep them in sync.
 
 use std::collections::HashMap;
-use std::net::Ipv6Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::mpsc;
 
 use anyhow::{anyhow, bail, Result};
@@ -13,11 +13,13 @@ use crate::{
     bindings::{
         kernel_enqueue_hook_uapi::upcall_enqueue_event,
         kernel_exec_tp_hook_uapi::{
-            exec_ct, exec_drop, exec_event, exec_output, exec_recirc, exec_track_event,
+            exec_ct, exec_drop, exec_event, exec_output, exec_push_vlan, exec_recirc,
+            exec_set_tunnel, exec_track_event,
         },
         kernel_flow_tbl_lookup_ret_hook_uapi::flow_lookup_ret_event,
         kernel_upcall_ret_hook_uapi::upcall_ret_event,
         kernel_upcall_tp_hook_uapi::upcall_event,
+        ovs_flow_uapi::{flow_update_event, OVS_FLOW_INSTALL, OVS_FLOW_REMOVE},
         ovs_operation_uapi::ovs_operation_event,
         user_recv_upcall_hook_uapi::recv_upcall_event,
     },
@@ -35,6 +37,13 @@ use crate::{
 
 use super::flow_info;
 
+/// Tunnel info mode flag, see IP_TUNNEL_INFO_IPV6 in include/net/ip_tunnels.h.
+const IP_TUNNEL_INFO_IPV6: u8 = 0x2;
+
+/// VLAN TCI fields, see include/linux/if_vlan.h.
+const VLAN_VID_MASK: u16 = 0x0fff;
+const VLAN_PRIO_SHIFT: u16 = 13;
+
 /// Event data types supported by the ovs module.
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub(crate) enum OvsDataType {
@@ -62,6 +71,12 @@ pub(crate) enum OvsDataType {
     DropAction = 10,
     /// Flow lookup
     FlowLookup = 11,
+    /// Flow installed in or removed from the datapath.
+    FlowUpdate = 12,
+    /// Push VLAN action.
+    PushVlanAction = 13,
+    /// Set (tunnel) action.
+    SetTunnelAction = 14,
 }
 
 impl OvsDataType {
@@ -80,6 +95,9 @@ impl OvsDataType {
             9 => ConntrackAction,
             10 => DropAction,
             11 => FlowLookup,
+            12 => FlowUpdate,
+            13 => PushVlanAction,
+            14 => SetTunnelAction,
             x => bail!("Can't construct a OvsDataType from {}", x),
         })
     }
@@ -154,6 +172,65 @@ pub(super) fn unmarshall_recirc(raw_section: &BpfRawSection, event: &mut OvsEven
     )
 }
 
+pub(super) fn unmarshall_push_vlan(
+    raw_section: &BpfRawSection,
+    event: &mut OvsEvent,
+) -> Result<()> {
+    let raw = parse_raw_section::<exec_push_vlan>(raw_section)?;
+    let tci = u16::from_be(raw.tci);
+
+    update_action_event(
+        event,
+        OvsAction::PushVlan {
+            push_vlan: OvsActionPushVlan {
+                tpid: u16::from_be(raw.tpid),
+                vid: tci & VLAN_VID_MASK,
+                pcp: (tci >> VLAN_PRIO_SHIFT) as u8,
+            },
+        },
+    )
+}
+
+pub(super) fn unmarshall_set_tunnel(
+    raw_section: &BpfRawSection,
+    event: &mut OvsEvent,
+) -> Result<()> {
+    let raw = parse_raw_section::<exec_set_tunnel>(raw_section)?;
+
+    let (src, dst) = if raw.mode & IP_TUNNEL_INFO_IPV6 != 0 {
+        (
+            Ipv6Addr::from(raw.src).to_string(),
+            Ipv6Addr::from(raw.dst).to_string(),
+        )
+    } else {
+        (
+            Ipv4Addr::new(raw.src[0], raw.src[1], raw.src[2], raw.src[3]).to_string(),
+            Ipv4Addr::new(raw.dst[0], raw.dst[1], raw.dst[2], raw.dst[3]).to_string(),
+        )
+    };
+    let port = |port: u16| match u16::from_be(port) {
+        0 => None,
+        x => Some(x),
+    };
+
+    update_action_event(
+        event,
+        OvsAction::Set {
+            set: OvsActionSet {
+                tunnel: Some(OvsActionSetTunnel {
+                    id: u64::from_be(raw.id),
+                    src,
+                    dst,
+                    sport: port(raw.sport),
+                    dport: port(raw.dport),
+                    tos: raw.tos,
+                    ttl: raw.ttl,
+                }),
+            },
+        },
+    )
+}
+
 pub(super) fn unmarshall_drop(raw_section: &BpfRawSection, event: &mut OvsEvent) -> Result<()> {
     let raw = parse_raw_section::<exec_drop>(raw_section)?;
 
@@ -243,6 +320,7 @@ pub(super) fn unmarshall_operation(raw_section: &BpfRawSection) -> Result<OvsEve
             queue_id: raw.queue_id,
             batch_idx: raw.batch_idx,
             op_type: raw.type_,
+            latency: (raw.latency != 0).then_some(raw.latency),
         },
     })
 }
@@ -299,6 +377,51 @@ impl OvsEventFactory {
         self.ufid_sender = Some(ufid_sender)
     }
 
+    /// Request the datapath flow and its OpenFlow rules to be retrieved from
+    /// ovs-vswitchd, if flow enrichment is enabled.
+    fn enrich(&self, ufid: Ufid, flow: u64, sf_acts: u64) {
+        if let Some(sender) = &self.ufid_sender {
+            match sender.try_send(flow_info::EnrichRequest::new(ufid, flow, sf_acts)) {
+                Err(mpsc::TrySendError::Full(_)) => {
+                    warn!("Flow enrichment channel full, dropping enrichment request");
+                }
+                Err(mpsc::TrySendError::Disconnected(_)) => {
+                    warn!("Flow enrichment channel disconnected, dropping enrichment request");
+                }
+                Ok(_) => (),
+            }
+        }
+    }
+
+    fn unmarshall_flow_update(&mut self, raw_section: &BpfRawSection) -> Result<OvsEvent> {
+        let raw = parse_raw_section::<flow_update_event>(raw_section)?;
+        let ufid = Ufid::from(raw.ufid);
+        let flow = raw.flow as usize as u64;
+        let sf_acts = raw.sf_acts as usize as u64;
+
+        let update_type = match raw.type_ as u32 {
+            OVS_FLOW_INSTALL => {
+                // Removed flows can't be retrieved from ovs-vswitchd anymore.
+                self.enrich(ufid, flow, sf_acts);
+                "install"
+            }
+            OVS_FLOW_REMOVE => "remove",
+            x => bail!("Unknown flow update type {x}"),
+        };
+
+        Ok(OvsEvent::FlowUpdate {
+            flow_update: FlowUpdateEvent {
+                r#type: update_type.to_string(),
+                flow,
+                sf_acts,
+                ufid,
+                queue_id: raw.has_queue_id.then_some(raw.queue_id),
+                dpflow: String::default(),
+                ofpflows: Vec::default(),
+            },
+        })
+    }
+
     fn unmarshall_flow_lookup(&mut self, raw_section: &BpfRawSection) -> Result<OvsEvent> {
         let raw = parse_raw_section::<flow_lookup_ret_event>(raw_section)?;
         let ufid = Ufid::from(raw.ufid);
@@ -306,17 +429,7 @@ impl OvsEventFactory {
         let sf_acts = raw.sf_acts as usize as u64;
 
         if flow != 0 {
-            if let Some(sender) = &self.ufid_sender {
-                match sender.try_send(flow_info::EnrichRequest::new(ufid, flow, sf_acts)) {
-                    Err(mpsc::TrySendError::Full(_)) => {
-                        warn!("Flow enrichment channel full, dropping enrichment request");
-                    }
-                    Err(mpsc::TrySendError::Disconnected(_)) => {
-                        warn!("Flow enrichment channel disconnected, dropping enrichment request");
-                    }
-                    Ok(_) => (),
-                }
-            }
+            self.enrich(ufid, flow, sf_acts);
         }
 
         Ok(OvsEvent::DpLookup {
@@ -326,6 +439,8 @@ impl OvsEventFactory {
                 ufid,
                 n_mask_hit: raw.n_mask_hit,
                 n_cache_hit: raw.n_cache_hit,
+                n_masks: Some(raw.n_masks),
+                n_flows: Some(raw.n_flows),
                 dpflow: String::default(),
                 ofpflows: Vec::default(),
             },
@@ -351,8 +466,12 @@ impl OvsEventFactory {
                         output: OvsActionOutput::default(),
                     }),
                     Some("USERSPACE") => Some(OvsAction::Userspace(OvsDummyAction)),
-                    Some("SET") => Some(OvsAction::Set(OvsDummyAction)),
-                    Some("PUSH_VLAN") => Some(OvsAction::PushVlan(OvsDummyAction)),
+                    Some("SET") => Some(OvsAction::Set {
+                        set: OvsActionSet::default(),
+                    }),
+                    Some("PUSH_VLAN") => Some(OvsAction::PushVlan {
+                        push_vlan: OvsActionPushVlan::default(),
+                    }),
                     Some("POP_VLAN") => Some(OvsAction::PopVlan(OvsDummyAction)),
                     Some("SAMPLE") => Some(OvsAction::Sample(OvsDummyAction)),
                     Some("RECIRC") => Some(OvsAction::Recirc {
@@ -417,6 +536,9 @@ impl RawEventSectionFactory for OvsEventFactory {
                 OvsDataType::FlowLookup => {
                     ovs = Some(self.unmarshall_flow_lookup(section)?);
                 }
+                OvsDataType::FlowUpdate => {
+                    ovs = Some(self.unmarshall_flow_update(section)?);
+                }
                 OvsDataType::ActionExecTrack => unmarshall_exec_track(
                     section,
                     ovs.as_mut()
@@ -437,6 +559,16 @@ impl RawEventSectionFactory for OvsEventFactory {
                     ovs.as_mut()
                         .ok_or_else(|| anyhow!("received action data without action"))?,
                 )?,
+                OvsDataType::PushVlanAction => unmarshall_push_vlan(
+                    section,
+                    ovs.as_mut()
+                        .ok_or_else(|| anyhow!("received action data without action"))?,
+                )?,
+                OvsDataType::SetTunnelAction => unmarshall_set_tunnel(
+                    section,
+                    ovs.as_mut()
+                        .ok_or_else(|| anyhow!("received action data without action"))?,
+                )?,
                 OvsDataType::DropAction => unmarshall_drop(
                     section,
                     ovs.as_mut()
@@ -21,6 +21,9 @@ enum trace_ovs_data_type {
 	OVS_DP_ACTION_CONNTRACK = 9,
 	OVS_DP_ACTION_DROP = 10,
 	OVS_FLOW_TBL_LOOKUP_RETURN = 11,
+	OVS_FLOW_UPDATE = 12,
+	OVS_DP_ACTION_PUSH_VLAN = 13,
+	OVS_DP_ACTION_SET_TUNNEL = 14,
 };
 
 /* Used to keep the context of an upcall operation for its upcall enqueue
@@ -53,6 +56,7 @@ struct {
 /* Context saved between the begining and end of ovs_execute_actions calls. */
 struct execute_actions_ctx {
 	BINDING_PTR(struct sk_buff *, skb);
+	BINDING_PTR(struct flow_table *, tbl);
 	u32 *n_mask_hit;
 	u32 *n_cache_hit;
 	u32 queue_id;
@@ -82,6 +86,27 @@ struct {
 	__type(value, 64);
 } flow_exec_tracking SEC(".maps");
 
+/* Used to link datapath flows to the upcall they were installed for.
+ * When userspace puts a flow on behalf of an upcall, it saves the upcall queue
+ * id indexed by the flow ufid. When the flow is inserted in the datapath, the
+ * ufid is looked up.
+ * Please keep in sync with its Rust counterpart in crate::module::ovs::ovs.rs. */
+struct flow_put_key {
+	u32 ufid[4];
+} __binding;
+
+struct flow_put_info {
+	u64 ts;
+	u32 queue_id;
+} __binding;
+
+struct {
+	__uint(type, BPF_MAP_TYPE_HASH);
+	__uint(max_entries, 8192);
+	__type(key, struct flow_put_key);
+	__type(value, struct flow_put_info);
+} flow_put_tracking SEC(".maps");
+
 #define PACKET_HASH_SIZE 64
 /* Packet data to be used to for hashing.
  * Stack size is limited in ebpf programs, so we use a per-cpu array to store
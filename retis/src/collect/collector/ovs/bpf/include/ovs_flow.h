@@ -0,0 +1,70 @@
+#ifndef __MODULE_OVS_FLOW__
+#define __MODULE_OVS_FLOW__
+
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include "ovs_common.h"
+
+/* Please keep in sync with its Rust counterpart in retis-events::ovs. */
+enum ovs_flow_update_type {
+	OVS_FLOW_INSTALL = 0,
+	OVS_FLOW_REMOVE = 1,
+} __binding;
+
+struct flow_update_event {
+	BINDING_PTR(struct sw_flow *, flow);
+	BINDING_PTR(struct sw_flow_actions *, sf_acts);
+	u32 ufid[4];
+	/* Queue id of the upcall the flow was installed for, if known. */
+	u32 queue_id;
+	bool has_queue_id;
+	/* enum ovs_flow_update_type */
+	u8 type;
+} __binding;
+
+/* Report a datapath flow being installed or removed. */
+static __always_inline int flow_update_process(struct retis_raw_event *event,
+					       struct sw_flow *flow,
+					       enum ovs_flow_update_type type)
+{
+	struct flow_update_event *e;
+	struct flow_put_info *info;
+	struct flow_put_key key;
+
+	/* Flows without an ufid can't be linked to anything else. OvS uses
+	 * ufids since 2.4 if supported by the datapath. */
+	if (!flow || !BPF_CORE_READ(flow, id.ufid_len))
+		return 0;
+
+	if (BPF_CORE_READ_INTO(&key.ufid, flow, id.ufid))
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_OVS, OVS_FLOW_UPDATE,
+			       sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->type = type;
+	e->flow = flow;
+	__builtin_memcpy(e->ufid, key.ufid, sizeof(e->ufid));
+
+	if (bpf_core_read(&e->sf_acts, sizeof(e->sf_acts), &flow->sf_acts))
+		log_error("Failed to read sf_acts");
+
+	if (type != OVS_FLOW_INSTALL)
+		return 0;
+
+	/* Link the flow to the upcall it was put for, if any. */
+	info = bpf_map_lookup_elem(&flow_put_tracking, &key);
+	if (info) {
+		e->queue_id = info->queue_id;
+		e->has_queue_id = true;
+		bpf_map_delete_elem(&flow_put_tracking, &key);
+	}
+
+	return 0;
+}
+
+#endif /* __MODULE_OVS_FLOW__ */
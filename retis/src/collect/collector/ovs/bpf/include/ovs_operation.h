@@ -13,6 +13,8 @@ enum ovs_operation_type {
 
 struct ovs_operation_event {
 	u64 batch_ts;
+	/* Time since the upcall was enqueued by the kernel, 0 if unknown. */
+	u64 latency;
 	u32 queue_id;
 	u8 batch_idx;
 	/* enum ovs_operation_type */
@@ -38,6 +40,8 @@ struct ovs_operation_event {
 
 /* Upcall information that is carried through userspace events. */
 struct user_upcall_info {
+	/* Timestamp of the upcall being enqueued by the kernel, 0 if unknown. */
+	u64 enqueue_ts;
 	u32 queue_id;
 
 	/* It indicates that the upcall event was filtered out so no events
@@ -81,6 +85,65 @@ struct {
 	__type(value, u32);
 } pid_to_batch SEC(".maps");
 
+/* Histogram of the upcall latencies, from the upcall being enqueued by the
+ * kernel to its first operation being processed by ovs-vswitchd. Latencies
+ * are accounted in power of 2 buckets, in nanoseconds: bucket 0 holds 0 and
+ * bucket i holds [2^(i-1), 2^i). Please keep in sync with its Rust
+ * counterpart in crate::module::ovs::ovs.rs.
+ */
+BINDING_DEF(UPCALL_LATENCY_BUCKETS, 65)
+struct {
+	__uint(type, BPF_MAP_TYPE_PERCPU_ARRAY);
+	__uint(max_entries, UPCALL_LATENCY_BUCKETS);
+	__type(key, u32);
+	__type(value, u64);
+} upcall_latency SEC(".maps");
+
+/* Get the power of 2 bucket of a latency. */
+static __always_inline u32 latency_bucket(u64 val)
+{
+	u32 bucket = 0;
+
+	if (!val)
+		return 0;
+
+	if (val >> 32) {
+		val >>= 32;
+		bucket += 32;
+	}
+	if (val >> 16) {
+		val >>= 16;
+		bucket += 16;
+	}
+	if (val >> 8) {
+		val >>= 8;
+		bucket += 8;
+	}
+	if (val >> 4) {
+		val >>= 4;
+		bucket += 4;
+	}
+	if (val >> 2) {
+		val >>= 2;
+		bucket += 2;
+	}
+	if (val >> 1)
+		bucket += 1;
+
+	return bucket + 1;
+}
+
+/* Account an upcall latency in the histogram. */
+static __always_inline void latency_account(u64 latency)
+{
+	u32 bucket = latency_bucket(latency);
+	u64 *count;
+
+	count = bpf_map_lookup_elem(&upcall_latency, &bucket);
+	if (count)
+		*count += 1;
+}
+
 /* Get the batch for the current handler thread. */
 static __always_inline struct upcall_batch *batch_get() {
 	u32 *idx;
@@ -160,6 +223,7 @@ static __always_inline struct user_upcall_info *batch_put(struct upcall_batch *b
 /* Process an upcall receive event. */
 static __always_inline struct upcall_batch *batch_process_recv(u64 timestamp,
 							       u32 queue_id,
+							       u64 enqueue_ts,
 							       bool skip)
 {
 	struct upcall_batch *batch = batch_get();
@@ -176,6 +240,7 @@ static __always_inline struct upcall_batch *batch_process_recv(u64 timestamp,
 	if (!info)
 		return NULL;
 
+	info->enqueue_ts = enqueue_ts;
 	info->queue_id = queue_id;
 	info->skip_event = skip;
 
@@ -191,12 +256,14 @@ static __always_inline struct upcall_batch *batch_process_recv(u64 timestamp,
  * information.
  * If an event is generated, it's returned in *op. */
 static __always_inline int batch_process_op(enum ovs_operation_type type,
+					    u64 timestamp,
 					    struct retis_raw_event *event,
 					    struct ovs_operation_event **op)
 {
 	struct upcall_batch *batch;
 	struct user_upcall_info *info;
 	u8 op_flag = 0x1 << type;
+	u64 latency = 0;
 
 	if (op)
 		*op = NULL;
@@ -226,6 +293,15 @@ static __always_inline int batch_process_op(enum ovs_operation_type type,
 		if (!info)
 			return -1;
 	}
+
+	if (info->enqueue_ts && timestamp > info->enqueue_ts) {
+		latency = timestamp - info->enqueue_ts;
+
+		/* Only the first operation on an upcall is accounted, as it
+		 * is when ovs-vswitchd started processing it. */
+		if (!info->processed_ops)
+			latency_account(latency);
+	}
 	info->processed_ops |= op_flag;
 
 	if (info->skip_event)
@@ -240,6 +316,7 @@ static __always_inline int batch_process_op(enum ovs_operation_type type,
 	op_event->type = type;
 	op_event->queue_id = info->queue_id;
 	op_event->batch_ts = batch->leader_ts;
+	op_event->latency = latency;
 	op_event->batch_idx = batch->current_upcall;
 
 	if (op)
@@ -27,6 +27,56 @@ struct exec_drop {
 	u32 reason;
 } __binding;
 
+struct exec_push_vlan {
+	/* Raw (network order) values. */
+	u16 tpid;
+	u16 tci;
+} __binding;
+
+struct exec_set_tunnel {
+	/* Raw (network order) tunnel id. */
+	u64 id;
+	u8 src[16];
+	u8 dst[16];
+	/* Raw (network order) ports. */
+	u16 sport;
+	u16 dport;
+	u8 tos;
+	u8 ttl;
+	u8 mode;
+} __binding;
+
+/* Only defined in the kernel part of the uAPI, see OVS_KEY_ATTR_TUNNEL_INFO. */
+enum ovs_key_attr___ovs {
+	OVS_KEY_ATTR_TUNNEL_INFO___ovs,
+};
+
+/* Only the fields we report are needed. */
+struct ip_tunnel_key___ovs {
+	__be64 tun_id;
+	union {
+		struct {
+			struct in6_addr src;
+			struct in6_addr dst;
+		} ipv6;
+	} u;
+	u8 tos;
+	u8 ttl;
+	__be16 tp_src;
+	__be16 tp_dst;
+} __attribute__((preserve_access_index));
+
+struct ip_tunnel_info___ovs {
+	struct ip_tunnel_key___ovs key;
+	u8 mode;
+} __attribute__((preserve_access_index));
+
+struct metadata_dst___ovs {
+	union {
+		struct ip_tunnel_info___ovs tun_info;
+	} u;
+} __attribute__((preserve_access_index));
+
 /* Please keep in sync with its Rust counterpart in retis-events::ovs. */
 #define R_OVS_CT_COMMIT				(1 << 0)
 #define R_OVS_CT_FORCE				(1 << 1)
@@ -101,6 +151,45 @@ static __always_inline void fill_nat(struct ovs_conntrack_info *info,
 	}
 }
 
+/* Set actions only apply to tunnel metadata, others are converted to masked
+ * sets. The key attribute then holds a struct ovs_tunnel_info, which only
+ * contains a pointer to the tunnel metadata dst.
+ */
+static __always_inline void fill_set_tunnel(struct retis_raw_event *event,
+					    struct nlattr *attr)
+{
+	struct ip_tunnel_info___ovs *info;
+	struct metadata_dst___ovs *md;
+	struct exec_set_tunnel *tun;
+	struct nlattr *key = nla_data(attr);
+
+	if (!bpf_core_enum_value_exists(enum ovs_key_attr___ovs,
+					OVS_KEY_ATTR_TUNNEL_INFO___ovs) ||
+	    nla_type(key) != bpf_core_enum_value(enum ovs_key_attr___ovs,
+						 OVS_KEY_ATTR_TUNNEL_INFO___ovs))
+		return;
+
+	if (bpf_probe_read_kernel(&md, sizeof(md), nla_data(key)) || !md)
+		return;
+
+	tun = get_event_zsection(event, COLLECTOR_OVS, OVS_DP_ACTION_SET_TUNNEL,
+				 sizeof(*tun));
+	if (!tun)
+		return;
+
+	info = &md->u.tun_info;
+	tun->id = BPF_CORE_READ(info, key.tun_id);
+	tun->sport = BPF_CORE_READ(info, key.tp_src);
+	tun->dport = BPF_CORE_READ(info, key.tp_dst);
+	tun->tos = BPF_CORE_READ(info, key.tos);
+	tun->ttl = BPF_CORE_READ(info, key.ttl);
+	tun->mode = BPF_CORE_READ(info, mode);
+
+	/* IPv6 addresses are reported as-is, IPv4 ones use the first 4 bytes. */
+	BPF_CORE_READ_INTO(&tun->src, info, key.u.ipv6.src);
+	BPF_CORE_READ_INTO(&tun->dst, info, key.u.ipv6.dst);
+}
+
 /* Hook for ovs_do_execute_action tracepoint. */
 DEFINE_HOOK_RAW(
 	struct nlattr *attr;
@@ -161,6 +250,18 @@ DEFINE_HOOK_RAW(
 
 		bpf_probe_read_kernel(&recirc->id, sizeof(recirc->id),
 				      nla_data(attr));
+	} else if (exec->action == OVS_ACTION_ATTR_PUSH_VLAN) {
+		struct exec_push_vlan *vlan =
+			get_event_section(event, COLLECTOR_OVS,
+					  OVS_DP_ACTION_PUSH_VLAN,
+					  sizeof(*vlan));
+		if (!vlan)
+			return 0;
+
+		/* struct ovs_action_push_vlan */
+		bpf_probe_read_kernel(vlan, sizeof(*vlan), nla_data(attr));
+	} else if (exec->action == OVS_ACTION_ATTR_SET) {
+		fill_set_tunnel(event, attr);
 	} else if (exec->action == OVS_ACTION_ATTR_CT) {
 		struct ovs_conntrack_info info;
 		bpf_probe_read_kernel(&info, sizeof(info), nla_data(attr));
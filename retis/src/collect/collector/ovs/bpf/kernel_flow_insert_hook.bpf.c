@@ -0,0 +1,14 @@
+#include <common.h>
+#include <ovs_flow.h>
+
+/* Hook for kretprobe:ovs_flow_tbl_insert */
+DEFINE_HOOK_RAW(
+	/* The flow was not installed. */
+	if ((int)ctx->regs.ret)
+		return 0;
+
+	return flow_update_process(event, (struct sw_flow *)ctx->regs.reg[1],
+				   OVS_FLOW_INSTALL);
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,10 @@
+#include <common.h>
+#include <ovs_flow.h>
+
+/* Hook for kprobe:ovs_flow_tbl_remove */
+DEFINE_HOOK_RAW(
+	return flow_update_process(event, (struct sw_flow *)ctx->regs.reg[1],
+				   OVS_FLOW_REMOVE);
+)
+
+char __license[] SEC("license") = "GPL";
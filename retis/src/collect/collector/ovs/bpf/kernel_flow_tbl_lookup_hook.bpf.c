@@ -11,6 +11,7 @@ DEFINE_HOOK_RAW(
 		return 0;
 	}
 
+	ectx->tbl = (struct flow_table *)ctx->regs.reg[0];
 	ectx->n_mask_hit = (u32 *)ctx->regs.reg[3];
 	ectx->n_cache_hit = (u32 *)ctx->regs.reg[4];
 
@@ -3,18 +3,33 @@
 
 #define MAX_UFID_LENGTH 16
 
+/* Only the fields we report are needed. */
+struct mask_array___ovs {
+	int count;
+} __attribute__((preserve_access_index));
+
+struct flow_table___ovs {
+	struct mask_array___ovs *mask_array;
+	unsigned int count;
+} __attribute__((preserve_access_index));
+
 struct flow_lookup_ret_event {
 	BINDING_PTR(struct sw_flow *, flow);
 	BINDING_PTR(struct sw_flow_actions *, sf_acts);
 	u32 ufid[MAX_UFID_LENGTH / 4];
 	u32 n_mask_hit;
 	u32 n_cache_hit;
+	/* Number of masks and flows in the table at lookup time, helping to
+	 * put n_mask_hit in perspective. */
+	u32 n_masks;
+	u32 n_flows;
 } __binding;
 
 /* Hook for kretprobe:ovs_flow_tbl_lookup_stats */
 DEFINE_HOOK_RAW(
 	struct flow_lookup_ret_event *ret;
 	struct execute_actions_ctx *ectx;
+	struct flow_table___ovs *tbl;
 	u64 sb = ctx->stack_base;
 	struct sw_flow *flow;
 	u32 ufid_len = 0;
@@ -24,9 +39,10 @@ DEFINE_HOOK_RAW(
 		return 0;
 	}
 
-	ret = get_event_section(event, COLLECTOR_OVS,
-				OVS_FLOW_TBL_LOOKUP_RETURN,
-				sizeof(*ret));
+	/* Zeroed as misses only report the lookup statistics. */
+	ret = get_event_zsection(event, COLLECTOR_OVS,
+				 OVS_FLOW_TBL_LOOKUP_RETURN,
+				 sizeof(*ret));
 	if (!ret)
 		return 0;
 
@@ -38,6 +54,12 @@ DEFINE_HOOK_RAW(
 				  ectx->n_cache_hit))
 		log_error("Failed to retrieve n_cache_hit");
 
+	tbl = (struct flow_table___ovs *)ectx->tbl;
+	if (tbl) {
+		ret->n_masks = BPF_CORE_READ(tbl, mask_array, count);
+		ret->n_flows = BPF_CORE_READ(tbl, count);
+	}
+
 	flow = (struct sw_flow *)ctx->regs.ret;
 	if (!flow) {
 		/* No flows. This is most likely an upcall.
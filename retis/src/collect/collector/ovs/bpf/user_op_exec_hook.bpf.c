@@ -6,7 +6,7 @@
 /* Hook for usdt:dpif_netlink_operate__::op_flow_execute. */
 DEFINE_USDT_HOOK (
 	struct ovs_operation_event *op;
-	batch_process_op(OVS_OP_EXEC, event, &op);
+	batch_process_op(OVS_OP_EXEC, ctx->timestamp, event, &op);
 
 	if (op) {
 		u32 queue_id = op->queue_id;
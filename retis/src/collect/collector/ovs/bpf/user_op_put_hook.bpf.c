@@ -3,9 +3,42 @@
 #include <user_common.h>
 #include <ovs_operation.h>
 
+/* Beginning of ovs-vswitchd's struct dpif_flow_put (lib/dpif.h). Userspace
+ * types are not described by BTF, only the fields up to the ufid are needed.
+ */
+struct user_dpif_flow_put {
+	u32 flags;
+	const void *key;
+	u64 key_len;
+	const void *mask;
+	u64 mask_len;
+	const void *ufid;
+};
+
 /* Hook for usdt:dpif_netlink_operate__::op_flow_put. */
 DEFINE_USDT_HOOK (
-	return batch_process_op(OVS_OP_PUT, event, NULL);
+	struct user_dpif_flow_put *put = (void *) ctx->args[1];
+	struct ovs_operation_event *op;
+	struct flow_put_info info = {};
+	struct flow_put_key key;
+	const void *ufid;
+
+	batch_process_op(OVS_OP_PUT, ctx->timestamp, event, &op);
+	if (!op || !put)
+		return 0;
+
+	if (bpf_probe_read_user(&ufid, sizeof(ufid), &put->ufid) || !ufid ||
+	    bpf_probe_read_user(key.ufid, sizeof(key.ufid), ufid))
+		return 0;
+
+	/* Let the datapath side link the flow to this upcall. Existing entries
+	 * are overwritten, as the same flow can be put again (e.g. if the first
+	 * attempt failed). */
+	info.ts = ctx->timestamp;
+	info.queue_id = op->queue_id;
+	bpf_map_update_elem(&flow_put_tracking, &key, &info, BPF_ANY);
+
+	return 0;
 )
 
 char __license[] SEC("license") = "GPL";
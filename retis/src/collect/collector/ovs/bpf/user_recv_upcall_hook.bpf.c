@@ -34,15 +34,21 @@ DEFINE_USDT_HOOK (
 	u32 size = (u32) ctx->args[3];
 	u32 queue_id = queue_id_gen_data((void *) ctx->args[2], size);
 	bool skip_event = false;
+	u64 enqueue_ts = 0;
+	u64 *ts;
 
-	if (!bpf_map_lookup_elem(&upcall_tracking, &queue_id)) {
+	ts = bpf_map_lookup_elem(&upcall_tracking, &queue_id);
+	if (!ts) {
 	    /* The upcall enqueue event was missed or filtered. */
 	    skip_event = true;
+	} else {
+	    enqueue_ts = *ts;
 	}
 	bpf_map_delete_elem(&upcall_tracking, &queue_id);
 
 
-	batch = batch_process_recv(ctx->timestamp, queue_id, skip_event);
+	batch = batch_process_recv(ctx->timestamp, queue_id, enqueue_ts,
+				   skip_event);
 	if (!batch)
 		return 0;
 
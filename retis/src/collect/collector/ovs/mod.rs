@@ -25,6 +25,12 @@ mod hooks {
     pub(super) mod kernel_exec_tp {
         include!("bpf/.out/kernel_exec_tp_hook.rs");
     }
+    pub(super) mod kernel_flow_insert {
+        include!("bpf/.out/kernel_flow_insert_hook.rs");
+    }
+    pub(super) mod kernel_flow_remove {
+        include!("bpf/.out/kernel_flow_remove_hook.rs");
+    }
     pub(super) mod kernel_process_packet {
         include!("bpf/.out/kernel_process_packet_hook.rs");
     }
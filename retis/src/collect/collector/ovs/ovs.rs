@@ -9,14 +9,14 @@ use std::{
 use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
 use libbpf_rs::MapCore;
-use log::warn;
+use log::{info, warn};
 
 use super::{bpf::OvsEventFactory, flow_info::FlowEnricher, hooks};
 
 use crate::{
     bindings::{
-        ovs_common_uapi::{execute_actions_ctx, upcall_context},
-        ovs_operation_uapi::upcall_batch,
+        ovs_common_uapi::{execute_actions_ctx, flow_put_info, flow_put_key, upcall_context},
+        ovs_operation_uapi::{upcall_batch, UPCALL_LATENCY_BUCKETS},
     },
     collect::{cli::Collect, Collector},
     core::{
@@ -25,9 +25,12 @@ use crate::{
         kernel::Symbol,
         probe::{user::UsdtProbe, Hook, Probe, ProbeBuilderManager, ProbeOption},
         tracking::gc::TrackingGC,
-        user::proc::{Process, ThreadInfo},
+        user::{
+            container::Container,
+            proc::{Process, ThreadInfo},
+        },
     },
-    helpers::signals::Running,
+    helpers::{histogram::*, signals::Running},
 };
 
 // GC runs in a thread every OVS_TRACKING_GC_INTERVAL seconds to collect and
@@ -68,12 +71,15 @@ pub(crate) struct OvsCollector {
 
     /* Tracking file descriptors (the maps are owned by the GC) */
     flow_exec_tracking_fd: i32,
+    flow_put_tracking_fd: i32,
     upcall_tracking_fd: i32,
     gc: Option<TrackingGC>,
     running: Running,
     /* Batch tracking maps. */
     upcall_batches: Option<libbpf_rs::MapHandle>,
     pid_to_batch: Option<libbpf_rs::MapHandle>,
+    /* Upcall latency histogram. */
+    upcall_latency: Option<libbpf_rs::MapHandle>,
 
     flow_enricher: Option<FlowEnricher>,
 }
@@ -85,11 +91,13 @@ impl Collector for OvsCollector {
             inflight_upcalls_map: None,
             inflight_exec_map: None,
             flow_exec_tracking_fd: 0,
+            flow_put_tracking_fd: 0,
             upcall_tracking_fd: 0,
             gc: None,
             running: Running::ignore_signals(),
             upcall_batches: None,
             pid_to_batch: None,
+            upcall_latency: None,
             flow_enricher: None,
         })
     }
@@ -136,13 +144,15 @@ impl Collector for OvsCollector {
         // Create tracking maps and add USDT hooks.
         self.init_tracking_maps()?;
         if self.track {
-            self.add_usdt_hooks(probes)?;
+            self.add_usdt_hooks(probes, cli.container()?.as_ref())?;
         }
         // Add targetted hooks.
         // Upcall related hooks:
         self.add_upcall_hooks(probes)?;
         // Exec related hooks
         self.add_processing_hooks(probes)?;
+        // Flow install and removal hooks.
+        self.add_flow_hooks(probes)?;
 
         Ok(())
     }
@@ -167,6 +177,9 @@ impl Collector for OvsCollector {
         if let Some(enricher) = &mut self.flow_enricher {
             enricher.join()?;
         }
+        if let Err(e) = self.report_upcall_latency() {
+            warn!("Could not report the upcall latency: {e}");
+        }
         Ok(())
     }
 }
@@ -190,6 +203,24 @@ impl OvsCollector {
         .or_else(|e| bail!("Could not create the flow_exec_tracking map: {}", e))
     }
 
+    fn create_flow_put_tracking_map() -> Result<libbpf_rs::MapHandle> {
+        // Please keep in sync with its C counterpart in bpf/ovs_common.h
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Hash,
+            Some("flow_put_tracking"),
+            mem::size_of::<flow_put_key>() as u32,
+            mem::size_of::<flow_put_info>() as u32,
+            8192,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the flow_put_tracking map: {}", e))
+    }
+
     fn create_upcall_tracking_map() -> Result<libbpf_rs::MapHandle> {
         // Please keep in sync with its C counterpart in bpf/ovs_common.h
         let opts = libbpf_sys::bpf_map_create_opts {
@@ -285,6 +316,24 @@ impl OvsCollector {
             .or_else(|e| bail!("Could not create the pid_to_batch map: {}", e))?,
         );
 
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its C counterpart in bpf/ovs_operation.h
+        self.upcall_latency = Some(
+            libbpf_rs::MapHandle::create(
+                libbpf_rs::MapType::PercpuArray,
+                Some("upcall_latency"),
+                mem::size_of::<u32>() as u32,
+                mem::size_of::<u64>() as u32,
+                UPCALL_LATENCY_BUCKETS,
+                &opts,
+            )
+            .or_else(|e| bail!("Could not create the upcall_latency map: {}", e))?,
+        );
+
         /* Populate pid_to_batch map. */
         for (batch_idx, handler) in (0_u32..).zip(handlers.iter().as_ref().iter()) {
             self.pid_to_batch.as_mut().unwrap().update(
@@ -398,9 +447,34 @@ impl OvsCollector {
         Ok(())
     }
 
+    /// Add datapath flow install and removal hooks.
+    fn add_flow_hooks(&self, probes: &mut ProbeBuilderManager) -> Result<()> {
+        // ovs_flow_tbl_insert kretprobe
+        let mut flow_insert_hook = Hook::from(hooks::kernel_flow_insert::DATA);
+        flow_insert_hook.reuse_map("flow_put_tracking", self.flow_put_tracking_fd)?;
+        let mut probe = Probe::kretprobe(Symbol::from_name("ovs_flow_tbl_insert")?)?;
+        probe.set_option(ProbeOption::NoGenericHook)?;
+        probe.add_hook(flow_insert_hook)?;
+        probes.register_probe(probe)?;
+
+        // ovs_flow_tbl_remove kprobe
+        let mut flow_remove_hook = Hook::from(hooks::kernel_flow_remove::DATA);
+        flow_remove_hook.reuse_map("flow_put_tracking", self.flow_put_tracking_fd)?;
+        let mut probe = Probe::kprobe(Symbol::from_name("ovs_flow_tbl_remove")?)?;
+        probe.set_option(ProbeOption::NoGenericHook)?;
+        probe.add_hook(flow_remove_hook)?;
+        probes.register_probe(probe)?;
+
+        Ok(())
+    }
+
     /// Add USDT hooks.
-    fn add_usdt_hooks(&mut self, probes: &mut ProbeBuilderManager) -> Result<()> {
-        let ovs = Process::from_cmd("ovs-vswitchd")?;
+    fn add_usdt_hooks(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        container: Option<&Container>,
+    ) -> Result<()> {
+        let ovs = Process::from_cmd("ovs-vswitchd", container)?;
         if !ovs.is_usdt("main::run_start")? {
             bail!(
                 "Cannot find USDT probes in ovs-vswitchd. Was it built with --enable-usdt-probes?"
@@ -419,12 +493,20 @@ impl OvsCollector {
             .ok_or_else(|| anyhow!("pid_to_batch map not created"))?
             .as_fd()
             .as_raw_fd();
+        let upcall_latency_fd = self
+            .upcall_latency
+            .as_ref()
+            .ok_or_else(|| anyhow!("upcall_latency map not created"))?
+            .as_fd()
+            .as_raw_fd();
 
         let mut user_recv_hook = Hook::from(hooks::user_recv_upcall::DATA);
         user_recv_hook.reuse_map("upcall_tracking", self.upcall_tracking_fd)?;
 
         let mut user_exec_hook = Hook::from(hooks::user_op_exec::DATA);
         user_exec_hook.reuse_map("flow_exec_tracking", self.flow_exec_tracking_fd)?;
+        let mut user_put_hook = Hook::from(hooks::user_op_put::DATA);
+        user_put_hook.reuse_map("flow_put_tracking", self.flow_put_tracking_fd)?;
         let mut batch_probes = vec![
             (
                 Probe::usdt(UsdtProbe::new(&ovs, "dpif_recv::recv_upcall")?)?,
@@ -439,28 +521,68 @@ impl OvsCollector {
             ),
             (
                 Probe::usdt(UsdtProbe::new(&ovs, "dpif_netlink_operate__::op_flow_put")?)?,
-                Hook::from(hooks::user_op_put::DATA),
+                user_put_hook,
             ),
         ];
 
         while let Some((mut probe, mut hook)) = batch_probes.pop() {
             hook.reuse_map("upcall_batches", upcall_batches_fd)?
-                .reuse_map("pid_to_batch", pid_to_batch_fd)?;
+                .reuse_map("pid_to_batch", pid_to_batch_fd)?
+                .reuse_map("upcall_latency", upcall_latency_fd)?;
             probe.add_hook(hook)?;
             probes.register_probe(probe)?;
         }
         Ok(())
     }
 
+    /// Report the upcall latency histogram, from the upcalls being enqueued by
+    /// the kernel to ovs-vswitchd starting to process them. Only available
+    /// when upcall tracking is enabled.
+    fn report_upcall_latency(&self) -> Result<()> {
+        let Some(map) = &self.upcall_latency else {
+            return Ok(());
+        };
+
+        let mut buckets = vec![0; UPCALL_LATENCY_BUCKETS as usize];
+        for (i, bucket) in (0_u32..).zip(buckets.iter_mut()) {
+            let Some(values) = map.lookup_percpu(&i.to_ne_bytes(), libbpf_rs::MapFlags::ANY)?
+            else {
+                continue;
+            };
+
+            for val in values.iter() {
+                *bucket += u64::from_ne_bytes(val[0..8].try_into()?);
+            }
+        }
+
+        let hist = log2_histogram(&buckets);
+        if hist.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "OpenvSwitch upcall latency ({} upcalls):",
+            buckets.iter().sum::<u64>()
+        );
+        histogram_lines(&hist)
+            .iter()
+            .for_each(|line| info!("{line}"));
+        Ok(())
+    }
+
     fn init_tracking_maps(&mut self) -> Result<()> {
         let upcall_tracking = Self::create_upcall_tracking_map()?;
         let flow_exec_tracking = Self::create_flow_exec_tracking_map()?;
+        let flow_put_tracking = Self::create_flow_put_tracking_map()?;
         self.upcall_tracking_fd = upcall_tracking.as_fd().as_raw_fd();
         self.flow_exec_tracking_fd = flow_exec_tracking.as_fd().as_raw_fd();
+        self.flow_put_tracking_fd = flow_put_tracking.as_fd().as_raw_fd();
 
+        // All tracking map values start with their insertion time.
         let tracking_maps = HashMap::from([
             ("enqueue_tracking", upcall_tracking),
             ("flow_exec_tracking", flow_exec_tracking),
+            ("flow_put_tracking", flow_put_tracking),
         ]);
 
         self.gc = Some(
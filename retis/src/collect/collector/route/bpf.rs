@@ -0,0 +1,105 @@
+//! Rust<>BPF types definitions for the route module.
+//! Please keep this file in sync with its BPF counterparts in bpf/.
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    bindings::route_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string_opt,
+};
+
+pub(super) const AF_INET: u8 = 2;
+pub(super) const AF_INET6: u8 = 10;
+
+#[event_section_factory(FactoryId::Route)]
+#[derive(Default)]
+pub(crate) struct RouteEventFactory {}
+
+impl RawEventSectionFactory for RouteEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<route_event>(&raw_sections)?;
+
+        event.route = Some(RouteEvent {
+            table: raw.table,
+            saddr: addr_str(raw.family, &raw.saddr)?,
+            daddr: addr_str(raw.family, &raw.daddr)?,
+            prefix: match raw.has_prefix {
+                1 => Some(format!(
+                    "{}/{}",
+                    addr_str(raw.family, &raw.prefix)?,
+                    raw.prefixlen
+                )),
+                _ => None,
+            },
+            r#type: route_type_str(raw.type_).map(String::from),
+            gw: match raw.gw_family {
+                0 => None,
+                family => Some(addr_str(family, &raw.gw)?),
+            },
+            dev: raw_to_string_opt!(&raw.dev)?,
+            ifindex: Some(raw.ifindex).filter(|i| *i != 0),
+            err: (raw.has_err == 1).then_some(raw.err),
+        });
+
+        Ok(())
+    }
+}
+
+/// Format a raw address given its family.
+fn addr_str(family: u8, addr: &[u8; 16]) -> Result<String> {
+    Ok(match family {
+        AF_INET => Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string(),
+        AF_INET6 => Ipv6Addr::from(*addr).to_string(),
+        x => bail!("Unknown address family ({x})"),
+    })
+}
+
+/// Get the name of a route type (RTN_*), if known.
+fn route_type_str(r#type: u8) -> Option<&'static str> {
+    Some(match r#type {
+        1 => "unicast",
+        2 => "local",
+        3 => "broadcast",
+        4 => "anycast",
+        5 => "multicast",
+        6 => "blackhole",
+        7 => "unreachable",
+        8 => "prohibit",
+        9 => "throw",
+        10 => "nat",
+        11 => "xresolve",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_addr() {
+        let mut addr = [0; 16];
+        addr[..4].copy_from_slice(&[192, 168, 0, 0]);
+        assert_eq!(addr_str(AF_INET, &addr).unwrap(), "192.168.0.0");
+
+        let addr = Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1).octets();
+        assert_eq!(addr_str(AF_INET6, &addr).unwrap(), "fd00::1");
+
+        assert!(addr_str(0, &addr).is_err());
+    }
+
+    #[test]
+    fn route_types() {
+        assert_eq!(route_type_str(0), None);
+        assert_eq!(route_type_str(1), Some("unicast"));
+        assert_eq!(route_type_str(7), Some("unreachable"));
+        assert_eq!(route_type_str(12), None);
+    }
+}
@@ -0,0 +1,30 @@
+#ifndef __COLLECTOR_ROUTE_COMMON__
+#define __COLLECTOR_ROUTE_COMMON__
+
+#include <vmlinux.h>
+
+/* Keep in sync with its Rust counterpart in route.rs */
+#define ROUTE_INFLIGHT_MAX	1024
+
+/* Context kept while a route lookup is done on behalf of a packet, as the FIB
+ * lookup tracepoints do not have access to it.
+ *
+ * skb:        packet the route is looked up for.
+ * fib_result: IPv4 lookup result, set while in fib_table_lookup().
+ */
+struct route_inflight {
+	u64 skb;
+	u64 fib_result;
+} __binding;
+
+/* Indexed by stack base. Please keep in sync with its Rust counterpart in
+ * route.rs.
+ */
+struct {
+	__uint(type, BPF_MAP_TYPE_HASH);
+	__uint(max_entries, ROUTE_INFLIGHT_MAX);
+	__type(key, u64);
+	__type(value, struct route_inflight);
+} route_inflight SEC(".maps");
+
+#endif /* __COLLECTOR_ROUTE_COMMON__ */
@@ -0,0 +1,21 @@
+#include <common.h>
+#include <route_common.h>
+
+/* Hook for extending the skb context in the FIB lookup tracepoints. */
+DEFINE_CTX_HOOK(
+	struct route_inflight *inflight;
+	u64 sb = ctx->stack_base;
+
+	if (retis_arg_valid(ctx, sk_buff))
+		return 0;
+
+	inflight = bpf_map_lookup_elem(&route_inflight, &sb);
+	if (!inflight || !inflight->skb)
+		return 0;
+
+	retis_set_ext_sk_buff(ctx, (struct sk_buff *)inflight->skb);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,265 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include <route_common.h>
+
+#define AF_INET			2
+#define AF_INET6		10
+
+BINDING_DEF(IFNAMSIZ, 16)
+
+#define retis_get_tb_id(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, tb_id, u32)
+#define retis_get_flowi4(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, flowi4, struct flowi4___route *)
+#define retis_get_nhc(ctx, cfg)			\
+	RETIS_HOOK_GET(ctx, cfg->offsets, nhc, struct fib_nh_common___route *)
+#define retis_get_err(ctx, cfg)			\
+	RETIS_HOOK_GET(ctx, cfg->offsets, err, int)
+#define retis_get_fib6_result(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, fib6_result, struct fib6_result___route *)
+#define retis_get_fib6_table(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, fib6_table, struct fib6_table___route *)
+#define retis_get_flowi6(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, flowi6, struct flowi6___route *)
+
+/* Only the fields we report are needed. */
+struct flowi4___route {
+	__be32 saddr;
+	__be32 daddr;
+} __attribute__((preserve_access_index));
+
+struct flowi6___route {
+	struct in6_addr daddr;
+	struct in6_addr saddr;
+} __attribute__((preserve_access_index));
+
+struct fib_nh_common___route {
+	struct net_device *nhc_dev;
+	u8 nhc_gw_family;
+	union {
+		__be32 ipv4;
+		struct in6_addr ipv6;
+	} nhc_gw;
+} __attribute__((preserve_access_index));
+
+struct fib_result___route {
+	__be32 prefix;
+	unsigned char prefixlen;
+	unsigned char type;
+} __attribute__((preserve_access_index));
+
+struct fib6_nh___route {
+	struct fib_nh_common___route nh_common;
+} __attribute__((preserve_access_index));
+
+struct rt6key___route {
+	struct in6_addr addr;
+	int plen;
+} __attribute__((preserve_access_index));
+
+struct fib6_info___route {
+	struct rt6key___route fib6_dst;
+} __attribute__((preserve_access_index));
+
+struct fib6_result___route {
+	struct fib6_nh___route *nh;
+	struct fib6_info___route *f6i;
+	u8 fib6_type;
+} __attribute__((preserve_access_index));
+
+struct fib6_table___route {
+	u32 tb6_id;
+} __attribute__((preserve_access_index));
+
+struct netns_ipv6___route {
+	struct fib6_info___route *fib6_null_entry;
+} __attribute__((preserve_access_index));
+
+struct net___route {
+	struct netns_ipv6___route ipv6;
+} __attribute__((preserve_access_index));
+
+/**
+ * Route hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct route_offsets {
+	/* fib:fib_table_lookup */
+	s8 tb_id;
+	s8 flowi4;
+	s8 nhc;
+	s8 err;
+	/* fib6:fib6_table_lookup */
+	s8 fib6_result;
+	s8 fib6_table;
+	s8 flowi6;
+};
+struct route_config {
+	struct route_offsets offsets;
+	u8 family;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct route_config);
+} route_config_map SEC(".maps");
+
+struct route_event {
+	u32 table;
+	/* Lookup error, only valid if has_err is set. */
+	s32 err;
+	/* Flow being looked up. */
+	u8 saddr[16];
+	u8 daddr[16];
+	/* Prefix of the matched route, only valid if has_prefix is set. */
+	u8 prefix[16];
+	/* Gateway, only valid if gw_family is set. */
+	u8 gw[16];
+	/* Output device, only valid if ifindex is set. */
+	u32 ifindex;
+	char dev[IFNAMSIZ];
+	u8 family;
+	u8 gw_family;
+	u8 prefixlen;
+	u8 has_prefix;
+	u8 has_err;
+	/* Route type (RTN_*), 0 if unknown. */
+	u8 type;
+} __binding;
+
+static __always_inline void route_fill_nhc(struct route_event *e,
+					   struct fib_nh_common___route *nhc)
+{
+	struct net_device *dev;
+	u8 gw_family;
+
+	if (!nhc)
+		return;
+
+	gw_family = BPF_CORE_READ(nhc, nhc_gw_family);
+	if (gw_family == AF_INET) {
+		bpf_core_read(e->gw, 4, &nhc->nhc_gw.ipv4);
+		e->gw_family = gw_family;
+	} else if (gw_family == AF_INET6) {
+		bpf_core_read(e->gw, 16, &nhc->nhc_gw.ipv6);
+		e->gw_family = gw_family;
+	}
+
+	dev = BPF_CORE_READ(nhc, nhc_dev);
+	if (dev) {
+		e->ifindex = BPF_CORE_READ(dev, ifindex);
+		bpf_probe_read(e->dev, IFNAMSIZ, dev->name);
+	}
+}
+
+static __always_inline void route_fib4(struct retis_context *ctx,
+				       struct route_config *cfg,
+				       struct route_event *e)
+{
+	struct fib_result___route *res = NULL;
+	struct route_inflight *inflight;
+	struct flowi4___route *flp;
+	u64 sb = ctx->stack_base;
+
+	e->table = retis_get_tb_id(ctx, cfg);
+
+	flp = retis_get_flowi4(ctx, cfg);
+	if (flp) {
+		bpf_core_read(e->saddr, 4, &flp->saddr);
+		bpf_core_read(e->daddr, 4, &flp->daddr);
+	}
+
+	if (retis_offset_valid(cfg->offsets.err)) {
+		e->err = retis_get_err(ctx, cfg);
+		e->has_err = 1;
+	}
+
+	/* The result is only filled on successful lookups. It is consumed
+	 * here, to not be reused by a later lookup.
+	 */
+	inflight = bpf_map_lookup_elem(&route_inflight, &sb);
+	if (inflight) {
+		res = (void *)inflight->fib_result;
+		inflight->fib_result = 0;
+	}
+	if (res && !e->err) {
+		bpf_core_read(e->prefix, 4, &res->prefix);
+		e->prefixlen = BPF_CORE_READ(res, prefixlen);
+		e->type = BPF_CORE_READ(res, type);
+		e->has_prefix = 1;
+	}
+
+	route_fill_nhc(e, retis_get_nhc(ctx, cfg));
+}
+
+static __always_inline void route_fib6(struct retis_context *ctx,
+				       struct route_config *cfg,
+				       struct route_event *e)
+{
+	struct fib6_result___route *res;
+	struct fib6_table___route *table;
+	struct fib6_info___route *f6i;
+	struct fib6_nh___route *nh;
+	struct flowi6___route *flp;
+	struct net___route *net;
+
+	table = retis_get_fib6_table(ctx, cfg);
+	if (table)
+		e->table = BPF_CORE_READ(table, tb6_id);
+
+	flp = retis_get_flowi6(ctx, cfg);
+	if (flp) {
+		bpf_core_read(e->saddr, 16, &flp->saddr);
+		bpf_core_read(e->daddr, 16, &flp->daddr);
+	}
+
+	res = retis_get_fib6_result(ctx, cfg);
+	if (!res)
+		return;
+
+	e->type = BPF_CORE_READ(res, fib6_type);
+
+	/* No route was found if the null entry was returned. */
+	f6i = BPF_CORE_READ(res, f6i);
+	net = (void *)retis_get_net(ctx);
+	if (f6i && (!net || f6i != BPF_CORE_READ(net, ipv6.fib6_null_entry))) {
+		bpf_core_read(e->prefix, 16, &f6i->fib6_dst.addr);
+		e->prefixlen = BPF_CORE_READ(f6i, fib6_dst.plen);
+		e->has_prefix = 1;
+	}
+
+	nh = BPF_CORE_READ(res, nh);
+	if (nh)
+		route_fill_nhc(e, &nh->nh_common);
+}
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct route_config *cfg;
+	struct route_event *e;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&route_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_ROUTE, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->family = cfg->family;
+	if (cfg->family == AF_INET)
+		route_fib4(ctx, cfg, e);
+	else
+		route_fib6(ctx, cfg, e);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,74 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include <route_common.h>
+
+/* Keep in sync with their Rust counterparts in route.rs */
+#define ROUTE_STASH_SKB		0
+#define ROUTE_STASH_CLEAR	1
+#define ROUTE_STASH_RESULT	2
+
+#define retis_get_fib_result(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, fib_result, u64)
+
+/**
+ * Route stash hook configuration. Each probe has its own instance, as the
+ * operation depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct route_stash_offsets {
+	s8 fib_result;
+};
+struct route_stash_config {
+	struct route_stash_offsets offsets;
+	u8 op;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct route_stash_config);
+} route_stash_config_map SEC(".maps");
+
+/* Hook for the functions looking up routes on behalf of a packet, and for
+ * fib_table_lookup(). Does not report anything.
+ */
+DEFINE_HOOK_RAW(
+	struct route_inflight new = {}, *inflight;
+	struct route_stash_config *cfg;
+	u64 sb = ctx->stack_base;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&route_stash_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	switch (cfg->op) {
+	case ROUTE_STASH_SKB:
+		/* Only lookups done for packets matching the filters are
+		 * reported.
+		 */
+		if (!RETIS_TRACKABLE(ctx))
+			return 0;
+
+		new.skb = (u64)retis_get_sk_buff(ctx);
+		bpf_map_update_elem(&route_inflight, &sb, &new, BPF_ANY);
+		break;
+	case ROUTE_STASH_CLEAR:
+		bpf_map_delete_elem(&route_inflight, &sb);
+		break;
+	case ROUTE_STASH_RESULT:
+		inflight = bpf_map_lookup_elem(&route_inflight, &sb);
+		if (inflight)
+			inflight->fib_result = retis_get_fib_result(ctx, cfg);
+		break;
+	}
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,23 @@
+//! # Route module
+//!
+//! Reports the result of FIB lookups (routing decisions) done for packets.
+
+// Re-export route.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod route;
+pub(crate) use route::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::RouteEventFactory;
+
+mod hooks {
+    pub(super) mod route {
+        include!("bpf/.out/route_hook.rs");
+    }
+    pub(super) mod route_ctx {
+        include!("bpf/.out/route_ctx_hook.rs");
+    }
+    pub(super) mod route_stash {
+        include!("bpf/.out/route_stash_hook.rs");
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd, RawFd},
+    sync::Arc,
+};
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, hooks};
+use crate::{
+    bindings::{
+        route_common_uapi::route_inflight, route_hook_uapi::route_config,
+        route_stash_hook_uapi::route_stash_config,
+    },
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager, ProbeOption},
+    },
+};
+
+/// IPv4 FIB lookups.
+const ROUTE_TP_FIB4: &str = "fib:fib_table_lookup";
+/// IPv6 FIB lookups.
+const ROUTE_TP_FIB6: &str = "fib6:fib6_table_lookup";
+/// IPv4 FIB lookup function, the lookup result is retrieved from.
+const ROUTE_FIB4_LOOKUP: &str = "fib_table_lookup";
+/// Functions looking up routes on behalf of a packet. The packet is made
+/// available to the FIB lookup tracepoints, for their events to be part of its
+/// series. Some of those might not be available.
+const ROUTE_SKB_TARGETS: &[&str] = &["ip_route_input_noref", "ip6_route_input", "__ip_queue_xmit"];
+
+/// Stash hook operations. Keep in sync with their BPF counterparts in
+/// bpf/route_stash_hook.bpf.c
+const ROUTE_STASH_SKB: u8 = 0;
+const ROUTE_STASH_CLEAR: u8 = 1;
+const ROUTE_STASH_RESULT: u8 = 2;
+
+/// Maximum number of route lookups in flight. Keep in sync with its BPF
+/// counterpart in bpf/include/route_common.h
+const ROUTE_INFLIGHT_MAX: u32 = 1024;
+
+#[derive(Default)]
+pub(crate) struct RouteCollector {
+    // Used to keep a reference to our internal maps.
+    #[allow(dead_code)]
+    maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for RouteCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if Symbol::from_name(ROUTE_TP_FIB4).is_err() && Symbol::from_name(ROUTE_TP_FIB6).is_err() {
+            bail!("Could not resolve the FIB lookup tracepoints");
+        }
+        // Lookups are only reported for packets.
+        if !ROUTE_SKB_TARGETS
+            .iter()
+            .any(|target| Symbol::from_name(target).is_ok())
+        {
+            bail!("Could not resolve any route lookup function");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its BPF counterpart in
+        // bpf/include/route_common.h
+        let inflight = libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Hash,
+            Some("route_inflight"),
+            mem::size_of::<u64>() as u32,
+            mem::size_of::<route_inflight>() as u32,
+            ROUTE_INFLIGHT_MAX,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the route inflight map: {}", e))?;
+        let inflight_fd = inflight.as_fd().as_raw_fd();
+        self.maps.push(inflight);
+
+        for target in ROUTE_SKB_TARGETS.iter() {
+            if let Ok(sym) = Symbol::from_name(target) {
+                let cfg = Self::stash_config(ROUTE_STASH_SKB);
+                self.add_stash_probe(probes, Probe::kprobe(sym.clone())?, cfg, inflight_fd)?;
+
+                let cfg = Self::stash_config(ROUTE_STASH_CLEAR);
+                self.add_stash_probe(probes, Probe::kretprobe(sym)?, cfg, inflight_fd)?;
+            }
+        }
+
+        if let Ok(sym) = Symbol::from_name(ROUTE_TP_FIB4) {
+            let mut cfg = route_config {
+                family: AF_INET,
+                ..Default::default()
+            };
+            if let Some(offset) = sym.parameter_offset("u32")? {
+                cfg.offsets.tb_id = offset as i8;
+            }
+            if let Some(offset) = sym.parameter_offset("struct flowi4 *")? {
+                cfg.offsets.flowi4 = offset as i8;
+            }
+            if let Some(offset) = sym.parameter_offset("struct fib_nh_common *")? {
+                cfg.offsets.nhc = offset as i8;
+            }
+            if let Some(offset) = sym.parameter_offset("int")? {
+                cfg.offsets.err = offset as i8;
+            }
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg, inflight_fd)?;
+
+            // The matched prefix is not part of the tracepoint.
+            if let Ok(sym) = Symbol::from_name(ROUTE_FIB4_LOOKUP) {
+                let mut cfg = Self::stash_config(ROUTE_STASH_RESULT);
+                match sym.parameter_offset("struct fib_result *")? {
+                    Some(offset) => cfg.offsets.fib_result = offset as i8,
+                    None => bail!("Could not find the FIB result parameter of {}", sym.name()),
+                }
+                self.add_stash_probe(probes, Probe::kprobe(sym)?, cfg, inflight_fd)?;
+            }
+        }
+
+        if let Ok(sym) = Symbol::from_name(ROUTE_TP_FIB6) {
+            let mut cfg = route_config {
+                family: AF_INET6,
+                ..Default::default()
+            };
+            if let Some(offset) = sym.parameter_offset("struct fib6_result *")? {
+                cfg.offsets.fib6_result = offset as i8;
+            }
+            if let Some(offset) = sym.parameter_offset("struct fib6_table *")? {
+                cfg.offsets.fib6_table = offset as i8;
+            }
+            if let Some(offset) = sym.parameter_offset("struct flowi6 *")? {
+                cfg.offsets.flowi6 = offset as i8;
+            }
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg, inflight_fd)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl RouteCollector {
+    /// Create the config map of a FIB lookup tracepoint, and register it with
+    /// our hooks.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        mut probe: Probe,
+        cfg: route_config,
+        inflight_fd: RawFd,
+    ) -> Result<()> {
+        probe.set_ctx_hook(
+            Hook::from(hooks::route_ctx::DATA)
+                .reuse_map("route_inflight", inflight_fd)?
+                .to_owned(),
+        )?;
+        // Please keep in sync with its BPF counterpart in bpf/route_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(hooks::route::DATA)
+                .reuse_map("route_inflight", inflight_fd)?
+                .to_owned(),
+            "route_config_map",
+            &cfg,
+        )?;
+        self.maps.push(config_map);
+        Ok(())
+    }
+
+    fn stash_config(op: u8) -> route_stash_config {
+        route_stash_config {
+            op,
+            ..Default::default()
+        }
+    }
+
+    /// Create the config map of a probe keeping track of a route lookup
+    /// context, and register it with the stash hook. Those probes do not
+    /// report events on their own.
+    fn add_stash_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        mut probe: Probe,
+        cfg: route_stash_config,
+        inflight_fd: RawFd,
+    ) -> Result<()> {
+        probe.set_option(ProbeOption::NoGenericHook)?;
+        // Please keep in sync with its BPF counterpart in bpf/route_stash_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(hooks::route_stash::DATA)
+                .reuse_map("route_inflight", inflight_fd)?
+                .to_owned(),
+            "route_stash_config_map",
+            &cfg,
+        )?;
+        self.maps.push(config_map);
+        Ok(())
+    }
+}
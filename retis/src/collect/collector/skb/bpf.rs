@@ -64,13 +64,46 @@ pub(super) fn unmarshal_gso(raw_section: &BpfRawSection) -> Result<SkbGsoEvent>
     })
 }
 
+pub(super) fn unmarshal_csum(raw_section: &BpfRawSection) -> Result<SkbCsumEvent> {
+    let raw = parse_raw_section::<skb_csum_event>(raw_section)?;
+
+    Ok(SkbCsumEvent {
+        ip_summed: raw.ip_summed,
+        csum: raw.csum,
+        csum_level: raw.csum_level,
+        csum_valid: raw.csum_valid == 1,
+        // Validation is done once the packet is known, see below.
+        l3_valid: None,
+        l4_valid: None,
+    })
+}
+
+/// The packet section is variable-size: only the captured bytes are sent by the
+/// BPF part, so we can't use parse_raw_section here.
 pub(super) fn unmarshal_packet(raw_section: &BpfRawSection) -> Result<PacketEvent> {
-    let raw = parse_raw_section::<skb_packet_event>(raw_section)?;
+    let data = &raw_section.data;
+    let offset = memoffset::offset_of!(skb_packet_event, packet);
+
+    if data.len() < offset {
+        bail!("Packet section is too small ({} < {offset})", data.len());
+    }
+
+    let len = u32::from_ne_bytes(data[0..4].try_into()?);
+    let capture_len = u32::from_ne_bytes(data[4..8].try_into()?);
+
+    let packet = &data[offset..];
+    if capture_len as usize > packet.len() {
+        bail!(
+            "Packet capture length is past the section end ({capture_len} > {})",
+            packet.len()
+        );
+    }
 
     Ok(PacketEvent {
-        len: raw.len,
-        capture_len: raw.capture_len,
-        data: RawPacket(raw.packet[..(raw.capture_len as usize)].to_vec()),
+        len,
+        capture_len,
+        data: RawPacket(packet[..(capture_len as usize)].to_vec()),
+        vlan_accel: None,
     })
 }
 
@@ -81,22 +114,44 @@ pub(crate) struct SkbEventFactory {}
 impl RawEventSectionFactory for SkbEventFactory {
     fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
         let mut skb: Option<SkbEvent> = None;
+        let mut vlan_accel = None;
 
         for section in raw_sections.iter() {
             match section.header.data_type as u32 {
-                SECTION_VLAN => {
-                    skb.get_or_insert_default().vlan_accel = Some(unmarshal_vlan(section)?)
-                }
+                SECTION_VLAN => vlan_accel = Some(unmarshal_vlan(section)?),
                 SECTION_META => skb.get_or_insert_default().meta = Some(unmarshal_meta(section)?),
                 SECTION_DATA_REF => {
                     skb.get_or_insert_default().data_ref = Some(unmarshal_data_ref(section)?)
                 }
                 SECTION_GSO => skb.get_or_insert_default().gso = Some(unmarshal_gso(section)?),
                 SECTION_PACKET => event.packet = Some(unmarshal_packet(section)?),
+                SECTION_CSUM => skb.get_or_insert_default().csum = Some(unmarshal_csum(section)?),
                 x => bail!("Unknown data type ({x})"),
             }
         }
 
+        // The accelerated VLAN tag is reported along the packet it belongs
+        // to. Keep it in the skb section if the packet couldn't be retrieved.
+        if let Some(vlan) = vlan_accel {
+            match event.packet.as_mut() {
+                Some(packet) => packet.vlan_accel = Some(vlan),
+                None => skb.get_or_insert_default().vlan_accel = Some(vlan),
+            }
+        }
+
+        // Validate the checksums on the captured packet data. The L4 checksum
+        // isn't computed yet when offloaded (CHECKSUM_PARTIAL).
+        if let (Some(csum), Some(packet)) = (
+            skb.as_mut().and_then(|skb| skb.csum.as_mut()),
+            event.packet.as_ref(),
+        ) {
+            let checksums = packet.data.checksums();
+            csum.l3_valid = checksums.l3;
+            if csum.ip_summed != 3 {
+                csum.l4_valid = checksums.l4;
+            }
+        }
+
         event.skb = skb;
         Ok(())
     }
@@ -111,29 +166,25 @@ pub(crate) mod benchmark {
 
     impl RawSectionBuilder for skb_packet_event {
         fn build_raw(out: &mut Vec<u8>) -> Result<()> {
-            let data = Self {
-                len: 66,
-                capture_len: 66,
-                packet: [
-                    46, 137, 59, 254, 34, 122, 42, 186, 90, 193, 129, 79, 8, 0, 69, 0, 0, 52, 32,
-                    32, 64, 0, 55, 6, 237, 160, 1, 1, 1, 1, 10, 0, 42, 2, 1, 187, 157, 12, 31, 149,
-                    22, 86, 145, 251, 180, 241, 128, 17, 0, 8, 17, 72, 0, 0, 1, 1, 8, 10, 28, 109,
-                    231, 120, 127, 134, 144, 92, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                ],
+            const PACKET: [u8; 66] = [
+                46, 137, 59, 254, 34, 122, 42, 186, 90, 193, 129, 79, 8, 0, 69, 0, 0, 52, 32, 32,
+                64, 0, 55, 6, 237, 160, 1, 1, 1, 1, 10, 0, 42, 2, 1, 187, 157, 12, 31, 149, 22, 86,
+                145, 251, 180, 241, 128, 17, 0, 8, 17, 72, 0, 0, 1, 1, 8, 10, 28, 109, 231, 120,
+                127, 134, 144, 92,
+            ];
+
+            let mut data = Self {
+                len: PACKET.len() as u32,
+                capture_len: PACKET.len() as u32,
+                packet: [0; 1024],
             };
-            build_raw_section(
-                out,
-                FactoryId::Skb as u8,
-                SECTION_PACKET as u8,
-                &mut as_u8_vec(&data),
-            );
+            data.packet[..PACKET.len()].copy_from_slice(&PACKET);
+
+            // Only the captured bytes are part of the section.
+            let mut data = as_u8_vec(&data);
+            data.truncate(memoffset::offset_of!(Self, packet) + PACKET.len());
+
+            build_raw_section(out, FactoryId::Skb as u8, SECTION_PACKET as u8, &mut data);
             Ok(())
         }
     }
@@ -11,6 +11,9 @@
 #define ETH_P_ARP	0x0806
 #define ETH_P_IPV6	0x86dd
 
+#define IPPROTO_TCP	6
+#define IPPROTO_UDP	17
+
 /* Skb raw event sections. */
 enum skb_sections {
 	SECTION_PACKET = 1,
@@ -18,6 +21,7 @@ enum skb_sections {
 	SECTION_META,
 	SECTION_DATA_REF,
 	SECTION_GSO,
+	SECTION_CSUM,
 } __binding;
 
 /* Skb hook configuration. A map is used to set the config from
@@ -25,6 +29,16 @@ enum skb_sections {
  */
 struct skb_config {
 	u64 sections;
+	/* Number of bytes captured after the L3 and L4 headers. If 0, the
+	 * first PACKET_CAPTURE_DEFAULT bytes of the packet are captured.
+	 */
+	u32 snaplen;
+	/* Addresses of the page_offset_base and vmemmap_base kernel variables,
+	 * used to access the data of non-linear skbs (x86_64 only). If not
+	 * set, only the linear part of skbs is captured.
+	 */
+	u64 page_offset_base;
+	u64 vmemmap_base;
 } __binding;
 struct {
 	__uint(type, BPF_MAP_TYPE_ARRAY);
@@ -56,12 +70,39 @@ struct skb_gso_event {
 	u32 gso_segs;
 	u32 gso_type;
 } __binding;
+struct skb_csum_event {
+	u32 csum;
+	u8 ip_summed;
+	u8 csum_level;
+	u8 csum_valid;
+} __binding;
+BINDING_DEF(PACKET_CAPTURE_DEFAULT, 255)
+
+/* The packet section has a variable size: only the first capture_len bytes of
+ * the packet buffer are part of it.
+ */
 struct skb_packet_event {
 	u32 len;
 	u32 capture_len;
-#define PACKET_CAPTURE_SIZE	255
+#define PACKET_CAPTURE_SIZE	1024
 	u8 packet[PACKET_CAPTURE_SIZE];
 } __binding;
+#define PACKET_CAPTURE_MASK	(PACKET_CAPTURE_SIZE - 1)
+
+/* Upper bounds for walking the non-linear data of skbs. */
+#define MAX_SKB_FRAGS		17
+#define MAX_FRAG_LIST		8
+
+#define PAGE_SHIFT		12
+
+/* Layout of skb_frag_t since v5.4 (struct bio_vec, then struct skb_frag); the
+ * first member being a struct page pointer or a netmem_ref.
+ */
+struct retis_skb_frag {
+	u64 netmem;
+	u32 len;
+	u32 offset;
+};
 
 /* Retrieve an skb linear len */
 static __always_inline int skb_linear_len(struct sk_buff *skb)
@@ -111,7 +152,200 @@ static __always_inline u16 skb_protocol(struct sk_buff *skb)
 	return 0;
 }
 
+/* Retrieve the length of the L3 and L4 headers of a packet, starting at its
+ * network header. L4 headers are only accounted for TCP and UDP. Returns 0 if
+ * unknown.
+ */
+static __always_inline int skb_l3_l4_hlen(struct sk_buff *skb,
+					  unsigned char *head, int network)
+{
+	int l3hlen, l4hlen = 0;
+	u8 byte, protocol;
+
+	bpf_probe_read_kernel(&byte, sizeof(byte), head + network);
+	switch (byte >> 4) {
+	case 4:
+		l3hlen = (byte & 0xf) * 4;
+		bpf_probe_read_kernel(&protocol, sizeof(protocol),
+				      head + network +
+				      offsetof(struct iphdr, protocol));
+		break;
+	case 6:
+		l3hlen = sizeof(struct ipv6hdr);
+		bpf_probe_read_kernel(&protocol, sizeof(protocol),
+				      head + network +
+				      offsetof(struct ipv6hdr, nexthdr));
+		break;
+	default:
+		return 0;
+	}
+
+	/* Prefer the transport offset when set, as it accounts for IP options
+	 * and IPv6 extension headers.
+	 */
+	if (is_transport_data_valid(skb))
+		l3hlen = BPF_CORE_READ(skb, transport_header) - network;
+
+	switch (protocol) {
+	case IPPROTO_TCP:
+		/* See struct tcphdr, doff is the upper 4 bits of byte 12. */
+		bpf_probe_read_kernel(&byte, sizeof(byte),
+				      head + network + l3hlen + 12);
+		l4hlen = (byte >> 4) * 4;
+		break;
+	case IPPROTO_UDP:
+		l4hlen = sizeof(struct udphdr);
+		break;
+	}
+
+	return l3hlen + l4hlen;
+}
+
+/* Compute the maximum capture size of a packet, given the length of the headers
+ * preceding its network one (which are always captured). The packet section
+ * must also fit in what is left of the raw event, including its header.
+ */
+static __always_inline long packet_capture_size(struct retis_raw_event *event,
+						struct skb_config *cfg,
+						struct sk_buff *skb,
+						unsigned char *head,
+						int network, int l2hlen)
+{
+	long size, left;
+
+	left = (long)sizeof(event->data) - get_event_size(event) -
+	       (long)sizeof(struct retis_raw_event_section_header) -
+	       (long)offsetof(struct skb_packet_event, packet);
+	if (left <= 0)
+		return 0;
+
+	if (!cfg->snaplen) {
+		size = PACKET_CAPTURE_DEFAULT;
+	} else {
+		size = l2hlen + cfg->snaplen;
+		if (is_network_data_valid(skb))
+			size += skb_l3_l4_hlen(skb, head, network);
+	}
+
+	return MIN(MIN(size, left), PACKET_CAPTURE_SIZE);
+}
+
+/* Copy up to len bytes of src in the packet buffer, at *pos and without going
+ * past size. On success *pos is advanced; returns non-zero if no more data
+ * should be copied.
+ */
+static __always_inline int packet_copy(u8 *buf, long *pos, long size,
+				       const void *src, long len)
+{
+	long off = *pos;
+
+	len = MIN(len, size - off);
+	if (len <= 0)
+		return size <= off;
+
+	/* Help the verifier. */
+	if (off >= PACKET_CAPTURE_SIZE || len > PACKET_CAPTURE_SIZE - off)
+		return -1;
+
+	if (bpf_probe_read_kernel(buf + (off & PACKET_CAPTURE_MASK),
+				  len & PACKET_CAPTURE_MASK, src))
+		return -1;
+
+	*pos = off + len;
+	return 0;
+}
+
+/* See page_address(), using the kernel direct mapping of pages (x86_64).
+ * Returns NULL if the page data can't be accessed.
+ */
+static __always_inline void *skb_frag_address(struct skb_config *cfg,
+					      struct retis_skb_frag *frag)
+{
+	u64 page_offset_base, vmemmap_base, pfn;
+
+	if (!cfg->page_offset_base || !cfg->vmemmap_base)
+		return NULL;
+
+	/* Device memory (net_iov) can't be accessed, see
+	 * netmem_is_net_iov().
+	 */
+	if (frag->netmem & 1)
+		return NULL;
+
+	if (bpf_probe_read_kernel(&page_offset_base, sizeof(page_offset_base),
+				  (void *)cfg->page_offset_base) ||
+	    bpf_probe_read_kernel(&vmemmap_base, sizeof(vmemmap_base),
+				  (void *)cfg->vmemmap_base))
+		return NULL;
+
+	if (frag->netmem < vmemmap_base)
+		return NULL;
+
+	pfn = (frag->netmem - vmemmap_base) / bpf_core_type_size(struct page);
+	return (void *)(page_offset_base + (pfn << PAGE_SHIFT) + frag->offset);
+}
+
+/* Copy the page fragments of an skb in the packet buffer, see packet_copy(). */
+static __always_inline int skb_copy_frags(struct skb_config *cfg,
+					  struct sk_buff *skb, u8 *buf,
+					  long *pos, long size)
+{
+	struct skb_shared_info *shinfo;
+	struct retis_skb_frag frag;
+	void *addr;
+	u8 nr_frags;
+	int i;
+
+	/* See skb_shinfo */
+	shinfo = (void *)(BPF_CORE_READ(skb, head) + BPF_CORE_READ(skb, end));
+	nr_frags = BPF_CORE_READ(shinfo, nr_frags);
+
+	for (i = 0; i < MAX_SKB_FRAGS && i < nr_frags; i++) {
+		if (bpf_probe_read_kernel(&frag, sizeof(frag),
+					  &shinfo->frags[i]))
+			return -1;
+
+		addr = skb_frag_address(cfg, &frag);
+		if (!addr)
+			return -1;
+
+		if (packet_copy(buf, pos, size, addr, frag.len))
+			return -1;
+	}
+
+	return 0;
+}
+
+/* Copy the non-linear data of an skb (its page fragments, then the skbs in its
+ * frag_list) in the packet buffer, see packet_copy().
+ */
+static __always_inline void skb_copy_nonlinear(struct skb_config *cfg,
+					       struct sk_buff *skb, u8 *buf,
+					       long *pos, long size)
+{
+	struct skb_shared_info *shinfo;
+	struct sk_buff *iter;
+	int i;
+
+	if (skb_copy_frags(cfg, skb, buf, pos, size))
+		return;
+
+	shinfo = (void *)(BPF_CORE_READ(skb, head) + BPF_CORE_READ(skb, end));
+	iter = BPF_CORE_READ(shinfo, frag_list);
+
+	for (i = 0; i < MAX_FRAG_LIST && iter; i++) {
+		if (packet_copy(buf, pos, size, BPF_CORE_READ(iter, data),
+				skb_linear_len(iter)))
+			return;
+		if (skb_copy_frags(cfg, iter, buf, pos, size))
+			return;
+
+		iter = BPF_CORE_READ(iter, next);
+	}
+}
+
 static __always_inline int process_packet(struct retis_raw_event *event,
+					  struct skb_config *cfg,
 					  struct sk_buff *skb)
 {
 	/* Use int instead of the underlying (smaller) unsigned type to allow
@@ -137,27 +371,38 @@ static __always_inline int process_packet(struct retis_raw_event *event,
 
 	/* Best case: mac offset is set and valid */
 	if (is_mac_data_valid(skb)) {
-		long mac_offset, size;
+		long mac_offset, size, linear, pos = 0;
 
 		mac_offset = mac - headroom;
-		size = MIN(linear_len - mac_offset, PACKET_CAPTURE_SIZE);
-		if (size <= 0)
+		size = MIN(len - mac_offset,
+			   packet_capture_size(event, cfg, skb, head, network,
+					       is_network_data_valid(skb) ?
+					       network - mac : 0));
+		linear = linear_len - mac_offset;
+		if (linear <= 0 || size <= 0 || size > PACKET_CAPTURE_SIZE)
 			return 0;
 
+		/* Non-linear data can only be retrieved if supported. */
+		if (!cfg->page_offset_base)
+			size = MIN(size, linear);
+
 		e = get_event_section(event, COLLECTOR_SKB, SECTION_PACKET,
-				      sizeof(*e));
+				      offsetof(struct skb_packet_event, packet) +
+				      size);
 		if (!e)
 			return 0;
 
+		if (!packet_copy(e->packet, &pos, size, head + mac, linear))
+			skb_copy_nonlinear(cfg, skb, e->packet, &pos, size);
+
 		e->len = len - mac_offset;
-		e->capture_len = size;
-		bpf_probe_read_kernel(e->packet, size, head + mac);
+		e->capture_len = pos;
 	/* Valid network offset with an unset or invalid mac offset: we can fake
 	 * the eth header.
 	 */
 	} else if (is_network_data_valid(skb)) {
+		long network_offset, size, linear, pos = sizeof(struct ethhdr);
 		u16 etype = skb_protocol(skb);
-		long network_offset, size;
 		struct ethhdr *eth;
 
 		/* We do need the ethertype to be set at the skb level here,
@@ -166,14 +411,23 @@ static __always_inline int process_packet(struct retis_raw_event *event,
 		if (!etype)
 			return 0;
 
+		/* Sizes below include the fake Ethernet header. */
 		network_offset = network - headroom;
-		size = MIN(linear_len - network_offset,
-			   PACKET_CAPTURE_SIZE - sizeof(struct ethhdr));
-		if (size <= 0)
+		size = MIN(len - network_offset + sizeof(*eth),
+			   packet_capture_size(event, cfg, skb, head, network,
+					       sizeof(*eth)));
+		linear = linear_len - network_offset;
+		if (linear <= 0 || size <= sizeof(*eth) ||
+		    size > PACKET_CAPTURE_SIZE)
 			return 0;
 
+		/* Non-linear data can only be retrieved if supported. */
+		if (!cfg->page_offset_base)
+			size = MIN(size, linear + sizeof(*eth));
+
 		e = get_event_section(event, COLLECTOR_SKB, SECTION_PACKET,
-				      sizeof(*e));
+				      offsetof(struct skb_packet_event, packet) +
+				      size);
 		if (!e)
 			return 0;
 
@@ -182,10 +436,11 @@ static __always_inline int process_packet(struct retis_raw_event *event,
 		__builtin_memset(eth, 0, sizeof(*eth));
 		eth->h_proto = etype;
 
+		if (!packet_copy(e->packet, &pos, size, head + network, linear))
+			skb_copy_nonlinear(cfg, skb, e->packet, &pos, size);
+
 		e->len = len - network_offset + sizeof(*eth);
-		e->capture_len = size + sizeof(struct ethhdr);
-		bpf_probe_read_kernel(e->packet + sizeof(*eth), size,
-				      head + network);
+		e->capture_len = pos;
 	/* Can't guess any useful packet offset */
 	} else {
 		return 0;
@@ -208,7 +463,7 @@ static __always_inline int process_skb(struct retis_context *ctx,
 		return 0;
 
 	/* Always retrieve the raw packet */
-	process_packet(event, skb);
+	process_packet(event, cfg, skb);
 
 	if (cfg->sections & BIT(SECTION_META)) {
 		struct skb_meta_event *e =
@@ -226,6 +481,19 @@ static __always_inline int process_skb(struct retis_context *ctx,
 		e->priority = BPF_CORE_READ(skb, priority);
 	}
 
+	if (cfg->sections & BIT(SECTION_CSUM)) {
+		struct skb_csum_event *e =
+			get_event_section(event, COLLECTOR_SKB,
+					  SECTION_CSUM, sizeof(*e));
+		if (!e)
+			return 0;
+
+		e->csum = BPF_CORE_READ(skb, csum);
+		e->ip_summed = (u8)BPF_CORE_READ_BITFIELD_PROBED(skb, ip_summed);
+		e->csum_level = (u8)BPF_CORE_READ_BITFIELD_PROBED(skb, csum_level);
+		e->csum_valid = (u8)BPF_CORE_READ_BITFIELD_PROBED(skb, csum_valid);
+	}
+
 	if (cfg->sections & BIT(SECTION_DATA_REF)) {
 		unsigned char *head = BPF_CORE_READ(skb, head);
 		struct skb_data_ref_event *e =
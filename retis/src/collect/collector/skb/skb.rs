@@ -7,7 +7,7 @@ use std::{
 use anyhow::{bail, Result};
 use clap::{builder::PossibleValuesParser, Parser};
 use libbpf_rs::MapCore;
-use log::warn;
+use log::{debug, warn};
 
 use super::skb_hook;
 use crate::{
@@ -15,6 +15,7 @@ use crate::{
     collect::{cli::Collect, Collector},
     core::{
         events::*,
+        inspect::inspector,
         probe::{Hook, ProbeBuilderManager},
     },
 };
@@ -24,7 +25,7 @@ pub(crate) struct SkbCollectorArgs {
     #[arg(
         long,
         value_parser=PossibleValuesParser::new([
-            "all", "eth", "meta", "dataref", "gso",
+            "all", "eth", "meta", "dataref", "gso", "csum",
             // Below values are deprecated.
             "arp", "ip", "tcp", "udp", "icmp", "packet", "vlan", "dev", "ns",
         ]),
@@ -35,6 +36,7 @@ Supported values:
 - meta: include skb metadata information (len, data_len, hash, etc).
 - dataref: include data & refcnt information (cloned, users, data refs, etc).
 - gso: include generic segmentation offload (GSO) information.
+- csum: include the checksum status and validate the IPv4 header and L4 (TCP, UDP, ICMP, ICMPv6) checksums of captured packets.
 - all: all of the above.
 
 The packet section as well as the VLAN offloading metadata are always retrieved.
@@ -42,6 +44,16 @@ The packet section as well as the VLAN offloading metadata are always retrieved.
 The following values are ignored and no event section will be generated as the corresponding data is part of the raw packet: eth, arp, ip, tcp, udp, icmp."
     )]
     pub(crate) skb_sections: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = clap::value_parser!(u32).range(1..=1024),
+        help = "Number of payload bytes to capture after the L3 and L4 (TCP, UDP) headers of packets. Link-layer and L3/L4 headers are always captured. The whole capture is limited to 1024 bytes.
+
+If not set, the first 255 bytes of packets are captured."
+    )]
+    pub(crate) skb_snaplen: Option<u32>,
 }
 
 #[derive(Default)]
@@ -79,6 +91,7 @@ impl Collector for SkbCollector {
                 "meta" => sections |= 1 << SECTION_META,
                 "dataref" => sections |= 1 << SECTION_DATA_REF,
                 "gso" => sections |= 1 << SECTION_GSO,
+                "csum" => sections |= 1 << SECTION_CSUM,
                 "eth" => (),
                 "packet" | "arp" | "ip" | "tcp" | "udp" | "icmp" => {
                     warn!(
@@ -100,7 +113,13 @@ impl Collector for SkbCollector {
         let config_map = Self::config_map()?;
 
         // Set the config.
-        let cfg = skb_config { sections };
+        let (page_offset_base, vmemmap_base) = Self::page_mapping()?;
+        let cfg = skb_config {
+            sections,
+            snaplen: args.collector_args.skb.skb_snaplen.unwrap_or(0),
+            page_offset_base,
+            vmemmap_base,
+        };
         let cfg = unsafe { plain::as_bytes(&cfg) };
 
         let key = 0_u32.to_ne_bytes();
@@ -119,6 +138,29 @@ impl Collector for SkbCollector {
 }
 
 impl SkbCollector {
+    /// Get the addresses of the kernel variables describing the direct mapping
+    /// of pages, used to capture the non-linear data of skbs (page fragments).
+    /// Only supported on x86_64; (0, 0) is returned otherwise.
+    fn page_mapping() -> Result<(u64, u64)> {
+        if !cfg!(target_arch = "x86_64") {
+            return Ok((0, 0));
+        }
+
+        let kernel = &inspector()?.kernel;
+        Ok(
+            match (
+                kernel.get_symbol_addr("page_offset_base"),
+                kernel.get_symbol_addr("vmemmap_base"),
+            ) {
+                (Ok(page_offset_base), Ok(vmemmap_base)) => (page_offset_base, vmemmap_base),
+                _ => {
+                    debug!("Pages mapping is unknown, only the linear part of packets will be captured");
+                    (0, 0)
+                }
+            },
+        )
+    }
+
     fn config_map() -> Result<libbpf_rs::MapHandle> {
         let opts = libbpf_sys::bpf_map_create_opts {
             sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
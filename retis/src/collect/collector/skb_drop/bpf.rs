@@ -105,6 +105,26 @@ impl SkbDropEventFactory {
         Ok(Self { reasons })
     }
 
+    /// Get the raw value of a drop reason given its name, as displayed in
+    /// events: "REASON" for core reasons and "subsys/REASON" otherwise.
+    /// Names are case insensitive.
+    pub(crate) fn reason_value(&self, name: &str) -> Option<u32> {
+        let (subsys, reason) = match name.split_once('/') {
+            Some((subsys, reason)) => (Some(subsys.to_lowercase()), reason),
+            None => (None, name),
+        };
+
+        self.reasons
+            .values()
+            .filter(|r| r.subsys_name == subsys)
+            .find_map(|r| {
+                r.reasons
+                    .iter()
+                    .find(|(_, n)| n.eq_ignore_ascii_case(reason))
+                    .map(|(val, _)| *val)
+            })
+    }
+
     /// Converts a raw drop reason value to a tuple of an optional sub-system
     /// name and a string representation of the drop reason.
     fn get_reason(&self, raw_val: i32) -> (Option<String>, String) {
@@ -138,3 +158,23 @@ impl SkbDropEventFactory {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reason_value() {
+        let factory = SkbDropEventFactory::new().unwrap();
+
+        let val = factory.reason_value("NO_SOCKET").unwrap();
+        assert_eq!(
+            factory.get_reason(val as i32),
+            (None, "NO_SOCKET".to_string())
+        );
+        assert_eq!(factory.reason_value("no_socket"), Some(val));
+
+        assert!(factory.reason_value("core/NO_SOCKET").is_none());
+        assert!(factory.reason_value("UNKNOWN_REASON").is_none());
+    }
+}
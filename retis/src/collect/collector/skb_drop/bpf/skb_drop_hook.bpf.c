@@ -4,12 +4,38 @@
 
 #include <common.h>
 
+/* Keep in sync with its Rust counterpart in skb_drop.rs */
+#define SKB_DROP_REASONS_MAX	64
+
+/* Hook configuration. */
+struct skb_drop_config {
+	/* Only report drops whose reason is in skb_drop_reasons_map. */
+	u8 filter_reasons;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct skb_drop_config);
+} skb_drop_config_map SEC(".maps");
+
+/* Drop reasons to report, when filtering them. */
+struct {
+	__uint(type, BPF_MAP_TYPE_HASH);
+	__uint(max_entries, SKB_DROP_REASONS_MAX);
+	__type(key, u32);
+	__type(value, u8);
+} skb_drop_reasons_map SEC(".maps");
+
 struct skb_drop_event {
 	s32 drop_reason;
 } __binding;
 
 DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct skb_drop_config *cfg;
 	struct skb_drop_event *e;
+	u32 key = 0;
+	s32 reason;
 
 	/* Check if the kernel knows about skb drop reasons, and if so check we
 	 * can retrieve it. This should be the common case. In case the kernel
@@ -20,12 +46,22 @@ DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
 	    !retis_arg_valid(ctx, skb_drop_reason))
 		return 0;
 
+	reason = bpf_core_type_exists(enum skb_drop_reason) ?
+		retis_get_skb_drop_reason(ctx) : -1;
+
+	cfg = bpf_map_lookup_elem(&skb_drop_config_map, &key);
+	if (cfg && cfg->filter_reasons) {
+		u32 r = reason;
+
+		if (!bpf_map_lookup_elem(&skb_drop_reasons_map, &r))
+			return -ENOMSG;
+	}
+
 	e = get_event_section(event, COLLECTOR_SKB_DROP, 1, sizeof(*e));
 	if (!e)
 		return 0;
 
-	e->drop_reason = bpf_core_type_exists(enum skb_drop_reason) ?
-		retis_get_skb_drop_reason(ctx) : -1;
+	e->drop_reason = reason;
 
 	return 0;
 )
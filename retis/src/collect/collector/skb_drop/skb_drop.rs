@@ -1,10 +1,17 @@
-use std::sync::Arc;
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd},
+    sync::Arc,
+};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use libbpf_rs::MapCore;
 use log::warn;
 
-use super::skb_drop_hook;
+use super::{bpf::SkbDropEventFactory, skb_drop_hook};
 use crate::{
+    bindings::skb_drop_hook_uapi::skb_drop_config,
     collect::{cli::Collect, Collector},
     core::{
         events::*,
@@ -14,14 +21,32 @@ use crate::{
     },
 };
 
+// Please keep in sync with its BPF counterpart in bpf/skb_drop_hook.bpf.c
+const SKB_DROP_REASONS_MAX: u32 = 64;
+
+#[derive(Parser, Debug, Default)]
+pub(crate) struct SkbDropCollectorArgs {
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "REASONS",
+        help = "Comma separated list of drop reasons whose events will be collected, e.g. 'NO_SOCKET,NETFILTER_DROP'. Reasons are given as displayed in events, non-core ones being prefixed by their sub-system (e.g. 'openvswitch/OVS_DROP_EXPLICIT'). By default events are collected whatever their drop reason. Note that this also applies to events of other probes having a drop reason parameter."
+    )]
+    pub(crate) skb_drop_reasons: Vec<String>,
+}
+
 pub(crate) struct SkbDropCollector {
     reasons_available: bool,
+    // Used to keep a reference to our internal maps.
+    #[allow(dead_code)]
+    maps: Vec<libbpf_rs::MapHandle>,
 }
 
 impl Collector for SkbDropCollector {
     fn new() -> Result<Self> {
         Ok(Self {
             reasons_available: true,
+            maps: Vec::new(),
         })
     }
 
@@ -70,13 +95,25 @@ impl Collector for SkbDropCollector {
 
     fn init(
         &mut self,
-        _: &Collect,
+        args: &Collect,
         probes: &mut ProbeBuilderManager,
         _: Arc<RetisEventsFactory>,
         _: &mut SectionFactories,
     ) -> Result<()> {
         let mut probe = Probe::raw_tracepoint(Symbol::from_name("skb:kfree_skb")?)?;
-        let hook = Hook::from(skb_drop_hook::DATA);
+        let mut hook = Hook::from(skb_drop_hook::DATA);
+
+        let reasons = &args.collector_args.skb_drop.skb_drop_reasons;
+        if !reasons.is_empty() {
+            if !self.reasons_available {
+                bail!("Drop reasons can't be filtered: this kernel doesn't provide them");
+            }
+
+            let (config_map, reasons_map) = Self::filter_maps(reasons)?;
+            hook.reuse_map("skb_drop_config_map", config_map.as_fd().as_raw_fd())?
+                .reuse_map("skb_drop_reasons_map", reasons_map.as_fd().as_raw_fd())?;
+            self.maps = vec![config_map, reasons_map];
+        }
 
         if self.reasons_available {
             probes.register_kernel_hook(hook)?;
@@ -94,3 +131,52 @@ impl Collector for SkbDropCollector {
         Ok(())
     }
 }
+
+impl SkbDropCollector {
+    /// Create and fill the maps used to filter events by drop reason.
+    fn filter_maps(reasons: &[String]) -> Result<(libbpf_rs::MapHandle, libbpf_rs::MapHandle)> {
+        if reasons.len() > SKB_DROP_REASONS_MAX as usize {
+            bail!("Too many drop reasons to filter (max {SKB_DROP_REASONS_MAX})");
+        }
+
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with their BPF counterparts in
+        // bpf/skb_drop_hook.bpf.c
+        let config_map = libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Array,
+            Some("skb_drop_config_map"),
+            mem::size_of::<u32>() as u32,
+            mem::size_of::<skb_drop_config>() as u32,
+            1,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the skb drop config map: {}", e))?;
+        let reasons_map = libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Hash,
+            Some("skb_drop_reasons_map"),
+            mem::size_of::<u32>() as u32,
+            mem::size_of::<u8>() as u32,
+            SKB_DROP_REASONS_MAX,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the skb drop reasons map: {}", e))?;
+
+        let factory = SkbDropEventFactory::new()?;
+        for name in reasons.iter() {
+            let val = factory
+                .reason_value(name)
+                .ok_or_else(|| anyhow!("Unknown drop reason ({name})"))?;
+            reasons_map.update(&val.to_ne_bytes(), &[1], libbpf_rs::MapFlags::ANY)?;
+        }
+
+        let cfg = skb_drop_config { filter_reasons: 1 };
+        let cfg = unsafe { plain::as_bytes(&cfg) };
+        config_map.update(&0_u32.to_ne_bytes(), cfg, libbpf_rs::MapFlags::empty())?;
+
+        Ok((config_map, reasons_map))
+    }
+}
@@ -0,0 +1,157 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include <skb_tracking.h>
+
+/* Section 1 is the tracking one, see tracking_hook.bpf.c */
+BINDING_DEF(SECTION_RELATION, 2)
+
+/* Maximum number of segments of a tunnel packet inheriting its tracking info. */
+#define ENCAP_SEGS_MAX		256
+#define MAX_ERRNO		4095
+
+enum skb_relation_kind {
+	SKB_RELATION_CLONE = 0,
+	SKB_RELATION_COPY,
+	SKB_RELATION_EXPAND,
+	SKB_RELATION_ENCAP,
+	SKB_RELATION_DECAP,
+} __binding;
+
+struct skb_relation_config {
+	u8 kind;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct skb_relation_config);
+} skb_relation_config_map SEC(".maps");
+
+/* Tracking information of the new skb, the original one being reported in the
+ * tracking section.
+ */
+struct skb_relation_event {
+	u64 orig_head;
+	u64 timestamp;
+	u64 skb;
+	u8 kind;
+} __binding;
+
+/* Segments of an encapsulated GSO packet are allocated by the segmentation
+ * logic and would otherwise start their own journey. Make them share the
+ * tracking info of the packet they were built from, as clones do. Returns the
+ * first segment.
+ */
+static __always_inline struct sk_buff *
+encap_inherit_tracking(struct tracking_info *ti, struct sk_buff *segs)
+{
+	struct sk_buff *first = segs;
+	u64 head;
+	int i;
+
+	for (i = 0; i < ENCAP_SEGS_MAX; i++) {
+		if (!segs)
+			break;
+
+		head = (u64)BPF_CORE_READ(segs, head);
+		if (head)
+			bpf_map_update_elem(&tracking_map, &head, ti,
+					    BPF_NOEXIST);
+
+		segs = BPF_CORE_READ(segs, next);
+	}
+
+	return first;
+}
+
+/* Hook for kretprobe:skb_clone, kretprobe:skb_copy,
+ * kretprobe:__pskb_copy_fclone, kretprobe:skb_copy_expand,
+ * kretprobe:skb_udp_tunnel_segment, kretprobe:gre_gso_segment and
+ * kretprobe:__iptunnel_pull_header.
+ */
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct tracking_info *ti, new;
+	struct skb_relation_config *cfg;
+	struct skb_relation_event *e;
+	struct sk_buff *skb, *nskb;
+	u32 key = 0;
+	u64 head, ret;
+
+	cfg = bpf_map_lookup_elem(&skb_relation_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	skb = (struct sk_buff *)ctx->regs.reg[0];
+	if (!skb)
+		return 0;
+
+	/* Only report relations of skbs we know about. */
+	ti = skb_tracking_info_by_skb(skb);
+	if (!ti)
+		return 0;
+
+	ret = ctx->regs.ret;
+
+	switch (cfg->kind) {
+	case SKB_RELATION_ENCAP:
+		/* No segmentation happened, or it failed (see IS_ERR()). */
+		if (!ret || ret >= (u64)-MAX_ERRNO)
+			return 0;
+
+		nskb = encap_inherit_tracking(ti, (struct sk_buff *)ret);
+		break;
+	case SKB_RELATION_DECAP:
+		/* The inner packet is the same skb, with its outer headers
+		 * pulled. Nothing to report if this failed.
+		 */
+		if (ret)
+			return 0;
+
+		nskb = skb;
+		break;
+	default:
+		nskb = (struct sk_buff *)ret;
+		if (!nskb)
+			return 0;
+
+		head = (u64)BPF_CORE_READ(nskb, head);
+		if (!head)
+			return 0;
+
+		/* Clones share their data (and tracking info) with the
+		 * original skb, while copies have their own. In the latter
+		 * case start tracking the new skb now, so the relationship
+		 * between the two ids is known.
+		 */
+		if (cfg->kind != SKB_RELATION_CLONE) {
+			ti = skb_tracking_info(&head);
+			if (!ti) {
+				new.timestamp = ctx->timestamp;
+				new.last_seen = ctx->timestamp;
+				new.orig_head = head;
+				new.stack_ref = 0;
+
+				bpf_map_update_elem(&tracking_map, &head, &new,
+						    BPF_NOEXIST);
+				ti = &new;
+			}
+		}
+		break;
+	}
+
+	e = get_event_section(event, COLLECTOR_SKB_TRACKING, SECTION_RELATION,
+			      sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->orig_head = ti->orig_head;
+	e->timestamp = ti->timestamp;
+	e->skb = (u64)nskb;
+	e->kind = cfg->kind;
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
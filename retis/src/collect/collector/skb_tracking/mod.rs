@@ -7,6 +7,9 @@
 pub(crate) mod skb_tracking;
 pub(crate) use skb_tracking::*;
 
+mod relation_hook {
+    include!("bpf/.out/relation_hook.rs");
+}
 mod tracking_hook {
     include!("bpf/.out/tracking_hook.rs");
 }
@@ -1,21 +1,56 @@
-use std::sync::Arc;
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd},
+    sync::Arc,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use clap::Parser;
+use libbpf_rs::MapCore;
+use log::warn;
 
-use super::tracking_hook;
+use super::{relation_hook, tracking_hook};
 use crate::{
-    bindings::tracking_hook_uapi::skb_tracking_event,
+    bindings::{relation_hook_uapi::*, tracking_hook_uapi::skb_tracking_event},
     collect::{cli::Collect, Collector},
     core::{
         events::*,
-        probe::{manager::ProbeBuilderManager, Hook},
+        kernel::Symbol,
+        probe::{manager::ProbeBuilderManager, Hook, Probe},
     },
     event_section_factory,
     events::*,
 };
 
+/// Functions creating a new skb out of an existing one, or crossing an
+/// encapsulation boundary, and the kind of relationship between the two.
+const RELATION_TARGETS: &[(&str, skb_relation_kind)] = &[
+    ("skb_clone", SKB_RELATION_CLONE),
+    ("skb_copy", SKB_RELATION_COPY),
+    ("__pskb_copy_fclone", SKB_RELATION_COPY),
+    ("skb_copy_expand", SKB_RELATION_EXPAND),
+    // Segmentation of tunnel packets, e.g. VXLAN, GENEVE and GRE.
+    ("skb_udp_tunnel_segment", SKB_RELATION_ENCAP),
+    ("gre_gso_segment", SKB_RELATION_ENCAP),
+    // Removal of the outer headers on tunnel receive.
+    ("__iptunnel_pull_header", SKB_RELATION_DECAP),
+];
+
+#[derive(Parser, Debug, Default)]
+pub(crate) struct SkbTrackingCollectorArgs {
+    #[arg(
+        long,
+        help = "Report relationship events when a tracked skb is cloned, copied or expanded into a new one, linking the tracking id of the new skb to the original one, and when it crosses a tunnel boundary. Segments of encapsulated packets keep the tracking id of the packet they were built from. The post-processing commands use those to group a packet, its copies and its encapsulated form into a single series."
+    )]
+    pub(crate) skb_tracking_relations: bool,
+}
+
 #[derive(Default)]
-pub(crate) struct SkbTrackingCollector {}
+pub(crate) struct SkbTrackingCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
 
 impl Collector for SkbTrackingCollector {
     fn new() -> Result<Self> {
@@ -28,32 +63,120 @@ impl Collector for SkbTrackingCollector {
 
     fn init(
         &mut self,
-        _: &Collect,
+        args: &Collect,
         probes: &mut ProbeBuilderManager,
         _: Arc<RetisEventsFactory>,
         _: &mut SectionFactories,
     ) -> Result<()> {
+        if args.collector_args.skb_tracking.skb_tracking_relations {
+            self.add_relation_probes(probes)?;
+        }
+
         probes.register_kernel_hook(Hook::from(tracking_hook::DATA))
     }
 }
 
+impl SkbTrackingCollector {
+    fn add_relation_probes(&mut self, probes: &mut ProbeBuilderManager) -> Result<()> {
+        for (target, kind) in RELATION_TARGETS.iter() {
+            // Some of those functions can be inlined, do not fail.
+            let symbol = match Symbol::from_name(target) {
+                Ok(symbol) => symbol,
+                Err(e) => {
+                    warn!("Can't report skb relationships from {target}: {e}");
+                    continue;
+                }
+            };
+
+            self.add_relation_probe(probes, Probe::kretprobe(symbol)?, *kind)?;
+        }
+
+        Ok(())
+    }
+
+    /// Create the config map of a relation probe, and register it with our
+    /// relation hook.
+    fn add_relation_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        mut probe: Probe,
+        kind: skb_relation_kind,
+    ) -> Result<()> {
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its BPF counterpart in bpf/relation_hook.bpf.c
+        let config_map = libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Array,
+            Some("skb_relation_config_map"),
+            mem::size_of::<u32>() as u32,
+            mem::size_of::<skb_relation_config>() as u32,
+            1,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the skb relation config map: {}", e))?;
+
+        let cfg = skb_relation_config { kind: kind as u8 };
+        let cfg = unsafe { plain::as_bytes(&cfg) };
+        config_map.update(&0_u32.to_ne_bytes(), cfg, libbpf_rs::MapFlags::empty())?;
+
+        probe.add_hook(
+            Hook::from(relation_hook::DATA)
+                .reuse_map("skb_relation_config_map", config_map.as_fd().as_raw_fd())?
+                .to_owned(),
+        )?;
+        probes.register_probe(probe)?;
+
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
+
 #[event_section_factory(FactoryId::SkbTracking)]
 #[derive(Default)]
 pub(crate) struct SkbTrackingEventFactory {}
 
 impl RawEventSectionFactory for SkbTrackingEventFactory {
     fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
-        // Both raw event and actual event map 1:1 but we still want
-        // to keep the bindings for consistency
-        let raw = parse_single_raw_section::<skb_tracking_event>(&raw_sections)?;
-
-        let track = SkbTrackingEvent {
-            orig_head: raw.orig_head,
-            timestamp: raw.timestamp,
-            skb: raw.skb,
-        };
+        for section in raw_sections.iter() {
+            match section.header.data_type as u32 {
+                // Both raw event and actual event map 1:1 but we still want
+                // to keep the bindings for consistency
+                1 => {
+                    let raw = parse_raw_section::<skb_tracking_event>(section)?;
+
+                    event.skb_tracking = Some(SkbTrackingEvent {
+                        orig_head: raw.orig_head,
+                        timestamp: raw.timestamp,
+                        skb: raw.skb,
+                    });
+                }
+                SECTION_RELATION => {
+                    let raw = parse_raw_section::<skb_relation_event>(section)?;
+
+                    event.skb_relation = Some(SkbRelationEvent {
+                        kind: match raw.kind as u32 {
+                            SKB_RELATION_CLONE => "clone",
+                            SKB_RELATION_COPY => "copy",
+                            SKB_RELATION_EXPAND => "expand",
+                            SKB_RELATION_ENCAP => "encap",
+                            SKB_RELATION_DECAP => "decap",
+                            x => bail!("Unknown skb relation kind ({x})"),
+                        }
+                        .to_string(),
+                        child: SkbTrackingEvent {
+                            orig_head: raw.orig_head,
+                            timestamp: raw.timestamp,
+                            skb: raw.skb,
+                        },
+                    });
+                }
+                x => bail!("Unknown data type ({x})"),
+            }
+        }
 
-        event.skb_tracking = Some(track);
         Ok(())
     }
 }
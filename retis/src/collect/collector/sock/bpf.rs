@@ -0,0 +1,84 @@
+use anyhow::Result;
+
+use crate::{
+    bindings::sock_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string,
+};
+
+#[event_section_factory(FactoryId::Sock)]
+#[derive(Default)]
+pub(crate) struct SockEventFactory {}
+
+impl RawEventSectionFactory for SockEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<sock_event>(&raw_sections)?;
+        let full = raw.full == 1;
+
+        event.sock = Some(SockEvent {
+            cookie: Some(raw.cookie).filter(|c| *c != 0),
+            family: family_str(raw.family),
+            r#type: full.then(|| type_str(raw.type_)),
+            protocol: full.then_some(raw.protocol),
+            uid: full.then_some(raw.uid),
+            cgroup_id: Some(raw.cgroup_id).filter(|id| full && *id != 0),
+            owner: match raw.has_owner == 1 {
+                true => Some(TaskEvent {
+                    pid: raw.owner.pid as i32,
+                    tgid: raw.owner.tgid as i32,
+                    comm: raw_to_string!(&raw.owner.comm)?,
+                }),
+                false => None,
+            },
+            container: None,
+        });
+
+        Ok(())
+    }
+}
+
+/// Get the name of a socket family, see include/linux/socket.h.
+fn family_str(family: u16) -> String {
+    match family {
+        1 => "unix".to_string(),
+        2 => "inet".to_string(),
+        10 => "inet6".to_string(),
+        16 => "netlink".to_string(),
+        17 => "packet".to_string(),
+        40 => "vsock".to_string(),
+        44 => "xdp".to_string(),
+        x => x.to_string(),
+    }
+}
+
+/// Get the name of a socket type, see include/linux/net.h.
+fn type_str(r#type: u16) -> String {
+    match r#type {
+        1 => "stream".to_string(),
+        2 => "dgram".to_string(),
+        3 => "raw".to_string(),
+        4 => "rdm".to_string(),
+        5 => "seqpacket".to_string(),
+        6 => "dccp".to_string(),
+        10 => "packet".to_string(),
+        x => x.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sock_names() {
+        assert_eq!(family_str(10), "inet6");
+        assert_eq!(family_str(99), "99");
+        assert_eq!(type_str(1), "stream");
+        assert_eq!(type_str(2), "dgram");
+    }
+}
@@ -0,0 +1,26 @@
+#ifndef __COLLECTOR_SOCK_COMMON__
+#define __COLLECTOR_SOCK_COMMON__
+
+#include <vmlinux.h>
+
+/* Keep in sync with its Rust counterpart in sock.rs */
+#define SOCK_OWNERS_MAX		16384
+
+/* Task which created a socket. */
+struct sock_owner {
+	u32 pid;
+	u32 tgid;
+	char comm[16];
+} __binding;
+
+/* Owners of sockets, indexed by their struct socket address. Please keep in
+ * sync with its Rust counterpart in sock.rs.
+ */
+struct {
+	__uint(type, BPF_MAP_TYPE_LRU_HASH);
+	__uint(max_entries, SOCK_OWNERS_MAX);
+	__type(key, u64);
+	__type(value, struct sock_owner);
+} sock_owners SEC(".maps");
+
+#endif /* __COLLECTOR_SOCK_COMMON__ */
@@ -0,0 +1,115 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include <sock_common.h>
+
+#define TCP_TIME_WAIT		6
+#define TCP_NEW_SYN_RECV	12
+
+/* Only the fields we report are needed. */
+struct kernfs_node___sock {
+	u64 id;
+} __attribute__((preserve_access_index));
+
+struct cgroup___sock {
+	struct kernfs_node___sock *kn;
+} __attribute__((preserve_access_index));
+
+struct sock_cgroup_data___sock {
+	struct cgroup___sock *cgroup;
+} __attribute__((preserve_access_index));
+
+struct sock___sock {
+	struct sock_cgroup_data___sock sk_cgrp_data;
+} __attribute__((preserve_access_index));
+
+struct sock_event {
+	/* Socket cookie, 0 if none was generated yet. */
+	u64 cookie;
+	/* Cgroup id, 0 if unknown. */
+	u64 cgroup_id;
+	u32 uid;
+	u16 family;
+	u16 type;
+	u8 protocol;
+	/* Set if the socket is a full one, fields below the family are only
+	 * valid for those.
+	 */
+	u8 full;
+	u8 has_owner;
+	struct sock_owner owner;
+} __binding;
+
+/* See sk_fullsock() in the kernel. */
+static __always_inline bool sock_is_full(struct sock *sk)
+{
+	u8 state = BPF_CORE_READ(sk, __sk_common.skc_state);
+
+	return state != TCP_TIME_WAIT && state != TCP_NEW_SYN_RECV;
+}
+
+static __always_inline u64 sock_cgroup_id(struct sock *sk)
+{
+	struct sock___sock *s = (void *)sk;
+	struct cgroup___sock *cgrp;
+
+	/* Older kernels encode the cgroup in a value we do not decode. */
+	if (!bpf_core_field_exists(s->sk_cgrp_data.cgroup))
+		return 0;
+
+	cgrp = BPF_CORE_READ(s, sk_cgrp_data.cgroup);
+	if (!cgrp)
+		return 0;
+
+	return BPF_CORE_READ(cgrp, kn, id);
+}
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct sock_owner *owner;
+	struct sock_event *e;
+	struct socket *socket;
+	struct sk_buff *skb;
+	struct sock *sk;
+	u64 key;
+
+	skb = retis_get_sk_buff(ctx);
+	if (!skb)
+		return 0;
+
+	sk = BPF_CORE_READ(skb, sk);
+	if (!sk)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_SOCK, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->cookie = BPF_CORE_READ(sk, __sk_common.skc_cookie.counter);
+	e->family = BPF_CORE_READ(sk, __sk_common.skc_family);
+
+	if (!sock_is_full(sk))
+		return 0;
+
+	e->full = 1;
+	/* sk_type and sk_protocol used to be bitfields. */
+	e->type = BPF_CORE_READ_BITFIELD_PROBED(sk, sk_type);
+	e->protocol = BPF_CORE_READ_BITFIELD_PROBED(sk, sk_protocol);
+	e->uid = BPF_CORE_READ(sk, sk_uid.val);
+	e->cgroup_id = sock_cgroup_id(sk);
+
+	socket = BPF_CORE_READ(sk, sk_socket);
+	if (!socket)
+		return 0;
+
+	key = (u64)socket;
+	owner = bpf_map_lookup_elem(&sock_owners, &key);
+	if (owner) {
+		e->has_owner = 1;
+		e->owner = *owner;
+	}
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
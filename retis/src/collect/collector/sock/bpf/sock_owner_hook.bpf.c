@@ -0,0 +1,70 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+#include <sock_common.h>
+
+/* Keep in sync with its Rust counterpart in sock.rs */
+#define SOCK_OWNER_SET		0
+#define SOCK_OWNER_DEL		1
+
+#define retis_get_socket(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, socket, struct socket *)
+
+/**
+ * Sock owner hook configuration. Each probe has its own instance, as the data
+ * to retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct sock_owner_offsets {
+	s8 socket;
+};
+struct sock_owner_config {
+	struct sock_owner_offsets offsets;
+	u8 type;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct sock_owner_config);
+} sock_owner_config_map SEC(".maps");
+
+/* Files are allocated for sockets in the context of the task creating or
+ * accepting them, owners are recorded then and forgotten when sockets are
+ * released. This hook does not report anything by itself.
+ */
+DEFINE_HOOK_RAW(
+	struct sock_owner_config *cfg;
+	struct sock_owner owner = {};
+	struct socket *socket;
+	u64 pid_tgid, key;
+	u32 idx = 0;
+
+	cfg = bpf_map_lookup_elem(&sock_owner_config_map, &idx);
+	if (!cfg)
+		return 0;
+
+	socket = retis_get_socket(ctx, cfg);
+	if (!socket)
+		return 0;
+
+	key = (u64)socket;
+	if (cfg->type == SOCK_OWNER_DEL) {
+		bpf_map_delete_elem(&sock_owners, &key);
+		return 0;
+	}
+
+	pid_tgid = bpf_get_current_pid_tgid();
+	owner.pid = pid_tgid;
+	owner.tgid = pid_tgid >> 32;
+	bpf_get_current_comm(owner.comm, sizeof(owner.comm));
+	bpf_map_update_elem(&sock_owners, &key, &owner, BPF_ANY);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
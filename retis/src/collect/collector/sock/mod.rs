@@ -0,0 +1,21 @@
+//! # Sock module
+//!
+//! Reports information about the socket packets are attached to, including the
+//! task which created it.
+
+// Re-export sock.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod sock;
+pub(crate) use sock::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::SockEventFactory;
+
+mod hooks {
+    pub(super) mod sock {
+        include!("bpf/.out/sock_hook.rs");
+    }
+    pub(super) mod sock_owner {
+        include!("bpf/.out/sock_owner_hook.rs");
+    }
+}
@@ -0,0 +1,150 @@
+use std::{
+    mem,
+    os::fd::{AsFd, AsRawFd, RawFd},
+    sync::Arc,
+};
+
+use anyhow::{bail, Result};
+use libbpf_rs::MapCore;
+use log::warn;
+
+use super::hooks;
+use crate::{
+    bindings::{sock_common_uapi::sock_owner, sock_owner_hook_uapi::sock_owner_config},
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Sock owner hook types. Keep in sync with their BPF counterparts in
+/// bpf/sock_owner_hook.bpf.c
+const SOCK_OWNER_SET: u8 = 0;
+const SOCK_OWNER_DEL: u8 = 1;
+
+/// Maximum number of socket owners tracked. Keep in sync with its BPF
+/// counterpart in bpf/include/sock_common.h
+const SOCK_OWNERS_MAX: u32 = 16384;
+
+/// Files being allocated for sockets, when created or accepted by a task.
+const SOCK_ALLOC_FILE: &str = "sock_alloc_file";
+/// Sockets being released. Only the first one found is used, the latter being
+/// used on older kernels.
+const SOCK_RELEASE_TARGETS: &[&str] = &["__sock_release", "sock_release"];
+
+#[derive(Default)]
+pub(crate) struct SockCollector {
+    // Used to keep a reference to our internal maps.
+    #[allow(dead_code)]
+    maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for SockCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct sk_buff *"])
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its BPF counterpart in bpf/include/sock_common.h
+        let owners = libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::LruHash,
+            Some("sock_owners"),
+            mem::size_of::<u64>() as u32,
+            mem::size_of::<sock_owner>() as u32,
+            SOCK_OWNERS_MAX,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the sock owners map: {}", e))?;
+        let owners_fd = owners.as_fd().as_raw_fd();
+        self.maps.push(owners);
+
+        // Socket owners can only be retrieved for sockets created during the
+        // collection.
+        match Symbol::from_name(SOCK_ALLOC_FILE) {
+            Ok(sym) => {
+                self.add_owner_probe(probes, sym, SOCK_OWNER_SET, owners_fd)?;
+
+                if let Some(sym) = SOCK_RELEASE_TARGETS
+                    .iter()
+                    .find_map(|target| Symbol::from_name(target).ok())
+                {
+                    self.add_owner_probe(probes, sym, SOCK_OWNER_DEL, owners_fd)?;
+                }
+            }
+            Err(e) => warn!("Socket owners won't be reported: {e}"),
+        }
+
+        probes.register_kernel_hook(
+            Hook::from(hooks::sock::DATA)
+                .reuse_map("sock_owners", owners_fd)?
+                .to_owned(),
+        )
+    }
+}
+
+impl SockCollector {
+    /// Add a probe recording or forgetting socket owners.
+    fn add_owner_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        sym: Symbol,
+        r#type: u8,
+        owners_fd: RawFd,
+    ) -> Result<()> {
+        let mut cfg = sock_owner_config {
+            type_: r#type,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset("struct socket *")? {
+            cfg.offsets.socket = offset as i8;
+        }
+
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+
+        // Please keep in sync with its BPF counterpart in bpf/sock_owner_hook.bpf.c
+        let config_map = libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::Array,
+            Some("sock_owner_config_map"),
+            mem::size_of::<u32>() as u32,
+            mem::size_of::<sock_owner_config>() as u32,
+            1,
+            &opts,
+        )
+        .or_else(|e| bail!("Could not create the sock owner config map: {}", e))?;
+
+        let cfg = unsafe { plain::as_bytes(&cfg) };
+        config_map.update(&0_u32.to_ne_bytes(), cfg, libbpf_rs::MapFlags::empty())?;
+
+        let mut probe = Probe::kprobe(sym)?;
+        probe.add_hook(
+            Hook::from(hooks::sock_owner::DATA)
+                .reuse_map("sock_owner_config_map", config_map.as_fd().as_raw_fd())?
+                .reuse_map("sock_owners", owners_fd)?
+                .to_owned(),
+        )?;
+        probes.register_probe(probe)?;
+
+        self.maps.push(config_map);
+        Ok(())
+    }
+}
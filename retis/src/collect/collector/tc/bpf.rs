@@ -0,0 +1,64 @@
+use anyhow::Result;
+
+use crate::{
+    bindings::tc_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string,
+};
+
+#[event_section_factory(FactoryId::Tc)]
+#[derive(Default)]
+pub(crate) struct TcEventFactory {}
+
+impl RawEventSectionFactory for TcEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<tc_event>(&raw_sections)?;
+        let mut tc = TcEvent::default();
+
+        let kind = raw_to_string!(&raw.kind)?;
+        if !kind.is_empty() {
+            tc.qdisc = Some(TcQdisc {
+                kind,
+                handle: raw.handle,
+                parent: raw.parent,
+                qlen: raw.qlen,
+                backlog: raw.backlog,
+                packets: u32::try_from(raw.packets).ok(),
+            });
+        }
+
+        if raw.classify == 1 {
+            tc.classify = Some(TcClassify {
+                ingress: raw.ingress == 1,
+                verdict: verdict_str(raw.verdict),
+            });
+        }
+
+        event.tc = Some(tc);
+        Ok(())
+    }
+}
+
+/// Get the name of a tc verdict, see TC_ACT_* in
+/// include/uapi/linux/pkt_cls.h.
+fn verdict_str(verdict: i32) -> String {
+    match verdict {
+        -1 => "unspec",
+        0 => "ok",
+        1 => "reclassify",
+        2 => "shot",
+        3 => "pipe",
+        4 => "stolen",
+        5 => "queued",
+        6 => "repeat",
+        7 => "redirect",
+        8 => "trap",
+        x => return x.to_string(),
+    }
+    .to_string()
+}
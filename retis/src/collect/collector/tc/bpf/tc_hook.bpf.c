@@ -0,0 +1,91 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+#define retis_get_qdisc_packets(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, packets, int)
+
+/**
+ * Tc hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct tc_offsets {
+	s8 packets;
+};
+struct tc_config {
+	struct tc_offsets offsets;
+	/* The probe is on the tc classification function return; its
+	 * verdict is the return value.
+	 */
+	u8 classify;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct tc_config);
+} tc_config_map SEC(".maps");
+
+struct tc_event {
+	/* Qdisc information, if available. */
+	char kind[16];
+	u32 handle;
+	u32 parent;
+	u32 qlen;
+	u32 backlog;
+	/* Number of dequeued packets, < 0 if not available. */
+	s32 packets;
+	/* Classification result, if classify is set. */
+	s32 verdict;
+	u8 classify;
+	u8 ingress;
+} __binding;
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	const struct Qdisc_ops___retis *ops;
+	struct Qdisc___retis *q;
+	struct tc_config *cfg;
+	struct sk_buff *skb;
+	struct tc_event *e;
+	u32 key = 0;
+
+	cfg = bpf_map_lookup_elem(&tc_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_TC, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	if (cfg->classify) {
+		skb = retis_get_sk_buff(ctx);
+
+		e->classify = 1;
+		e->verdict = (s32)ctx->regs.ret;
+		e->ingress = BPF_CORE_READ_BITFIELD_PROBED(skb, tc_at_ingress);
+	}
+
+	e->packets = retis_offset_valid(cfg->offsets.packets) ?
+		retis_get_qdisc_packets(ctx, cfg) : -1;
+
+	q = (void *)retis_get_Qdisc(ctx);
+	if (!q)
+		return 0;
+
+	ops = BPF_CORE_READ(q, ops);
+	if (ops)
+		bpf_core_read_str(e->kind, sizeof(e->kind), &ops->id);
+	e->handle = BPF_CORE_READ(q, handle);
+	e->parent = BPF_CORE_READ(q, parent);
+	e->qlen = BPF_CORE_READ(q, q.qlen);
+	e->backlog = BPF_CORE_READ(q, qstats.backlog);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,15 @@
+//! # Tc module
+//!
+//! Provides support for tracing tc classification and qdiscs.
+
+// Re-export tc.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod tc;
+pub(crate) use tc::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::TcEventFactory;
+
+mod tc_hook {
+    include!("bpf/.out/tc_hook.rs");
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::tc_hook;
+use crate::{
+    bindings::tc_hook_uapi::tc_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Qdisc tracepoints.
+const QDISC_TP_ENQUEUE: &str = "qdisc:qdisc_enqueue";
+const QDISC_TP_DEQUEUE: &str = "qdisc:qdisc_dequeue";
+/// Function running the tc filters, on both ingress and egress.
+const TC_CLASSIFY: &str = "tcf_classify";
+
+#[derive(Default)]
+pub(crate) struct TcCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for TcCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if [QDISC_TP_ENQUEUE, QDISC_TP_DEQUEUE, TC_CLASSIFY]
+            .iter()
+            .all(|target| Symbol::from_name(target).is_err())
+        {
+            bail!("Could not find tc kernel functions nor qdisc tracepoints");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        if let Ok(sym) = Symbol::from_name(QDISC_TP_ENQUEUE) {
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, tc_config::default())?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(QDISC_TP_DEQUEUE) {
+            let mut cfg = tc_config::default();
+            if let Some(offset) = sym.parameter_offset("int")? {
+                cfg.offsets.packets = offset as i8;
+            }
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+        }
+
+        // The classification verdict is the function return value.
+        if let Ok(sym) = Symbol::from_name(TC_CLASSIFY) {
+            let cfg = tc_config {
+                classify: 1,
+                ..Default::default()
+            };
+            self.add_probe(probes, Probe::kretprobe(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TcCollector {
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: tc_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/tc_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(tc_hook::DATA),
+            "tc_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use anyhow::Result;
+
+use crate::{
+    bindings::tcp_hook_uapi::*,
+    core::{
+        events::{
+            parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+            RawEventSectionFactory,
+        },
+        inspect::inspector,
+    },
+    event_section_factory,
+    events::*,
+};
+
+/// Tcp event types. Keep in sync with their BPF counterparts in
+/// bpf/tcp_hook.bpf.c
+pub(super) const TCP_EV_STATE: u8 = 0;
+pub(super) const TCP_EV_RETRANS: u8 = 1;
+pub(super) const TCP_EV_RTO: u8 = 2;
+pub(super) const TCP_EV_DROP: u8 = 3;
+
+const AF_INET: u16 = 2;
+
+#[event_section_factory(FactoryId::Tcp)]
+#[derive(Default)]
+pub(crate) struct TcpEventFactory {
+    /// Kernel timer frequency, used to convert jiffies.
+    hz: Option<u32>,
+}
+
+impl RawEventSectionFactory for TcpEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<tcp_event>(&raw_sections)?;
+
+        let (saddr, daddr) = if raw.family == AF_INET {
+            (
+                Ipv4Addr::new(raw.saddr[0], raw.saddr[1], raw.saddr[2], raw.saddr[3]).to_string(),
+                Ipv4Addr::new(raw.daddr[0], raw.daddr[1], raw.daddr[2], raw.daddr[3]).to_string(),
+            )
+        } else {
+            (
+                Ipv6Addr::from(raw.saddr).to_string(),
+                Ipv6Addr::from(raw.daddr).to_string(),
+            )
+        };
+
+        let mut tcp = TcpEvent {
+            op: match raw.type_ {
+                TCP_EV_STATE => "state".to_string(),
+                TCP_EV_RETRANS => "retrans".to_string(),
+                TCP_EV_RTO => "rto".to_string(),
+                TCP_EV_DROP => "drop".to_string(),
+                x => x.to_string(),
+            },
+            saddr,
+            daddr,
+            sport: raw.sport,
+            dport: raw.dport,
+            state: state_str(raw.state),
+            ..Default::default()
+        };
+
+        if raw.type_ == TCP_EV_STATE {
+            tcp.new_state = Some(state_str(raw.new_state));
+        }
+
+        if raw.has_metrics == 1 {
+            tcp.metrics = Some(TcpMetrics {
+                rto_ms: self.hz.map(|hz| (raw.rto as u64 * 1000 / hz as u64) as u32),
+                srtt_us: raw.srtt_us,
+                retransmits: raw.retransmits,
+                total_retrans: raw.total_retrans,
+            });
+        }
+
+        event.tcp = Some(tcp);
+        Ok(())
+    }
+}
+
+impl TcpEventFactory {
+    pub(crate) fn new() -> Result<Self> {
+        let hz = inspector()?
+            .kernel
+            .get_config_option("CONFIG_HZ")
+            .ok()
+            .flatten()
+            .and_then(|hz| hz.parse::<u32>().ok())
+            .filter(|hz| *hz != 0);
+
+        Ok(Self { hz })
+    }
+}
+
+/// Get the name of a TCP state, see include/net/tcp_states.h.
+fn state_str(state: u8) -> String {
+    match state {
+        1 => "ESTABLISHED".to_string(),
+        2 => "SYN_SENT".to_string(),
+        3 => "SYN_RECV".to_string(),
+        4 => "FIN_WAIT1".to_string(),
+        5 => "FIN_WAIT2".to_string(),
+        6 => "TIME_WAIT".to_string(),
+        7 => "CLOSE".to_string(),
+        8 => "CLOSE_WAIT".to_string(),
+        9 => "LAST_ACK".to_string(),
+        10 => "LISTEN".to_string(),
+        11 => "CLOSING".to_string(),
+        12 => "NEW_SYN_RECV".to_string(),
+        13 => "BOUND_INACTIVE".to_string(),
+        x => x.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tcp_state() {
+        assert_eq!(state_str(1), "ESTABLISHED");
+        assert_eq!(state_str(10), "LISTEN");
+        assert_eq!(state_str(42), "42");
+    }
+}
@@ -0,0 +1,140 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_endian.h>
+
+#include <common.h>
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define TCP_EV_STATE		0
+#define TCP_EV_RETRANS		1
+#define TCP_EV_RTO		2
+#define TCP_EV_DROP		3
+
+#define AF_INET			2
+#define AF_INET6		10
+#define IPPROTO_TCP		6
+
+#define retis_get_tcp_state(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, state, int)
+
+/* Only the fields we report are needed. */
+struct inet_connection_sock___tcp {
+	__u32 icsk_rto;
+	__u8 icsk_retransmits;
+} __attribute__((preserve_access_index));
+
+struct tcp_sock___tcp {
+	u32 srtt_us;
+	u32 total_retrans;
+} __attribute__((preserve_access_index));
+
+/**
+ * Tcp hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct tcp_offsets {
+	/* New state of the socket on state transitions. */
+	s8 state;
+};
+struct tcp_config {
+	struct tcp_offsets offsets;
+	u8 type;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct tcp_config);
+} tcp_config_map SEC(".maps");
+
+struct tcp_event {
+	u8 type;
+	/* Current state of the socket, old one on state transitions. */
+	u8 state;
+	u8 new_state;
+	/* Set if the connection metrics below are valid. */
+	u8 has_metrics;
+	u16 family;
+	/* Host order ports. */
+	u16 sport;
+	u16 dport;
+	u8 saddr[16];
+	u8 daddr[16];
+	/* Retransmission timeout, in jiffies. */
+	u32 rto;
+	u32 srtt_us;
+	u32 retransmits;
+	u32 total_retrans;
+} __binding;
+
+DEFINE_HOOK_RAW(
+	struct tcp_config *cfg;
+	struct tcp_event *e;
+	struct sock *sk;
+	u32 key = 0;
+	u16 family;
+
+	/* Probes having access to an skb (e.g. retransmits) are subject to
+	 * filtering, others (e.g. state transitions) are not.
+	 */
+	if (retis_get_sk_buff(ctx) && !RETIS_TRACKABLE(ctx))
+		return 0;
+
+	cfg = bpf_map_lookup_elem(&tcp_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	sk = retis_get_sock(ctx);
+	if (!sk)
+		return 0;
+
+	/* The state transition tracepoint is shared with other protocols. Note
+	 * that sk_protocol used to be a bitfield.
+	 */
+	if (BPF_CORE_READ_BITFIELD_PROBED(sk, sk_protocol) != IPPROTO_TCP)
+		return 0;
+
+	family = BPF_CORE_READ(sk, __sk_common.skc_family);
+	if (family != AF_INET && family != AF_INET6)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_TCP, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->type = cfg->type;
+	e->family = family;
+	e->state = BPF_CORE_READ(sk, __sk_common.skc_state);
+	e->sport = BPF_CORE_READ(sk, __sk_common.skc_num);
+	e->dport = bpf_ntohs(BPF_CORE_READ(sk, __sk_common.skc_dport));
+
+	if (family == AF_INET) {
+		BPF_CORE_READ_INTO(&e->saddr, sk, __sk_common.skc_rcv_saddr);
+		BPF_CORE_READ_INTO(&e->daddr, sk, __sk_common.skc_daddr);
+	} else {
+		BPF_CORE_READ_INTO(&e->saddr, sk, __sk_common.skc_v6_rcv_saddr);
+		BPF_CORE_READ_INTO(&e->daddr, sk, __sk_common.skc_v6_daddr);
+	}
+
+	if (cfg->type == TCP_EV_STATE) {
+		e->new_state = retis_get_tcp_state(ctx, cfg);
+	} else {
+		struct inet_connection_sock___tcp *icsk = (void *)sk;
+		struct tcp_sock___tcp *tp = (void *)sk;
+
+		e->has_metrics = 1;
+		e->rto = BPF_CORE_READ(icsk, icsk_rto);
+		e->retransmits = BPF_CORE_READ(icsk, icsk_retransmits);
+		/* srtt_us is stored left-shifted by 3. */
+		e->srtt_us = BPF_CORE_READ(tp, srtt_us) >> 3;
+		e->total_retrans = BPF_CORE_READ(tp, total_retrans);
+	}
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
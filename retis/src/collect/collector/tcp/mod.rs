@@ -0,0 +1,16 @@
+//! # Tcp module
+//!
+//! Provides support for reporting TCP socket level events: state transitions,
+//! retransmissions and drops.
+
+// Re-export tcp.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod tcp;
+pub(crate) use tcp::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::TcpEventFactory;
+
+mod tcp_hook {
+    include!("bpf/.out/tcp_hook.rs");
+}
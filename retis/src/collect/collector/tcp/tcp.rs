@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, tcp_hook};
+use crate::{
+    bindings::tcp_hook_uapi::tcp_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Socket state transitions.
+const TCP_TP_STATE: &str = "sock:inet_sock_set_state";
+/// Segment retransmissions.
+const TCP_TP_RETRANS: &str = "tcp:tcp_retransmit_skb";
+/// Retransmission timer expiry.
+const TCP_RTO: &str = "tcp_retransmit_timer";
+/// Segments dropped by the TCP stack. Only the first one found is used, the
+/// latter being used on older kernels. Those might be inlined.
+const TCP_DROP_TARGETS: &[&str] = &["tcp_drop_reason", "tcp_drop"];
+
+#[derive(Default)]
+pub(crate) struct TcpCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for TcpCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name(TCP_TP_STATE) {
+            bail!("Could not resolve the TCP state tracepoint: {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let sym = Symbol::from_name(TCP_TP_STATE)?;
+        let mut cfg = Self::config(TCP_EV_STATE);
+        // The new state directly follows the old one.
+        if let Some(offset) = sym.parameter_offset("int")? {
+            cfg.offsets.state = offset as i8 + 1;
+        }
+        self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+
+        if let Ok(sym) = Symbol::from_name(TCP_TP_RETRANS) {
+            let cfg = Self::config(TCP_EV_RETRANS);
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(TCP_RTO) {
+            let cfg = Self::config(TCP_EV_RTO);
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        if let Some(sym) = TCP_DROP_TARGETS
+            .iter()
+            .find_map(|target| Symbol::from_name(target).ok())
+        {
+            let cfg = Self::config(TCP_EV_DROP);
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TcpCollector {
+    fn config(r#type: u8) -> tcp_config {
+        tcp_config {
+            type_: r#type,
+            ..Default::default()
+        }
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: tcp_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/tcp_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(tcp_hook::DATA),
+            "tcp_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
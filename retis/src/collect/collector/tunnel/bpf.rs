@@ -0,0 +1,121 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use anyhow::Result;
+
+use crate::{
+    bindings::tunnel_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+};
+
+/// Tunnel info modes, see IP_TUNNEL_INFO_* in include/net/ip_tunnels.h.
+const IP_TUNNEL_INFO_TX: u8 = 0x1;
+const IP_TUNNEL_INFO_IPV6: u8 = 0x2;
+
+/// Tunnel flags bits, see IP_TUNNEL_*_OPT_BIT in include/net/ip_tunnels.h.
+const IP_TUNNEL_GENEVE_OPT_BIT: u64 = 11;
+const IP_TUNNEL_VXLAN_OPT_BIT: u64 = 12;
+
+/// Size of the options retrieved by the BPF hook. Keep in sync with its BPF
+/// counterpart in bpf/tunnel_hook.bpf.c
+const TUNNEL_OPTS_MAX: usize = 64;
+
+#[event_section_factory(FactoryId::Tunnel)]
+#[derive(Default)]
+pub(crate) struct TunnelEventFactory {}
+
+impl RawEventSectionFactory for TunnelEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<tunnel_event>(&raw_sections)?;
+
+        let (src, dst) = if raw.mode & IP_TUNNEL_INFO_IPV6 != 0 {
+            (
+                Ipv6Addr::from(raw.src).to_string(),
+                Ipv6Addr::from(raw.dst).to_string(),
+            )
+        } else {
+            (
+                Ipv4Addr::new(raw.src[0], raw.src[1], raw.src[2], raw.src[3]).to_string(),
+                Ipv4Addr::new(raw.dst[0], raw.dst[1], raw.dst[2], raw.dst[3]).to_string(),
+            )
+        };
+        let port = |port: u16| match u16::from_be(port) {
+            0 => None,
+            x => Some(x),
+        };
+
+        let mut tunnel = TunnelEvent {
+            tx: raw.mode & IP_TUNNEL_INFO_TX != 0,
+            id: u64::from_be(raw.id),
+            src,
+            dst,
+            sport: port(raw.sport),
+            dport: port(raw.dport),
+            tos: raw.tos,
+            ttl: raw.ttl,
+            ..Default::default()
+        };
+
+        let opts = &raw.options[..(raw.options_len as usize).min(TUNNEL_OPTS_MAX)];
+        if raw.flags & (1 << IP_TUNNEL_GENEVE_OPT_BIT) != 0 {
+            tunnel.geneve_opts = Some(parse_geneve_opts(opts));
+        } else if raw.flags & (1 << IP_TUNNEL_VXLAN_OPT_BIT) != 0 && opts.len() >= 4 {
+            // struct vxlan_metadata, stored in host order.
+            tunnel.vxlan_gbp = Some(u32::from_ne_bytes(opts[..4].try_into()?));
+        }
+
+        event.tunnel = Some(tunnel);
+        Ok(())
+    }
+}
+
+/// Parse GENEVE options (struct geneve_opt). Options which were truncated are
+/// not reported.
+fn parse_geneve_opts(mut opts: &[u8]) -> Vec<TunnelGeneveOpt> {
+    let mut res = Vec::new();
+
+    while opts.len() >= 4 {
+        // Option length is expressed in 4 bytes multiples, excluding the
+        // option header.
+        let len = 4 + (opts[3] & 0x1f) as usize * 4;
+        if opts.len() < len {
+            break;
+        }
+
+        res.push(TunnelGeneveOpt {
+            class: u16::from_be_bytes([opts[0], opts[1]]),
+            r#type: opts[2],
+            data: opts[4..len].iter().map(|b| format!("{b:02x}")).collect(),
+        });
+        opts = &opts[len..];
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geneve_opts() {
+        let opts = [
+            0x01, 0x02, 0x80, 0x01, 0xde, 0xad, 0xbe, 0xef, // 4 bytes of data
+            0xff, 0xff, 0x01, 0x00, // No data
+            0x00, 0x01, 0x02, 0x02, 0x00, 0x00, // Truncated
+        ];
+        let res = parse_geneve_opts(&opts);
+
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].class, 0x0102);
+        assert_eq!(res[0].r#type, 0x80);
+        assert_eq!(res[0].data, "deadbeef");
+        assert_eq!(res[1].class, 0xffff);
+        assert_eq!(res[1].r#type, 0x01);
+        assert!(res[1].data.is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_endian.h>
+
+#include <common.h>
+#include <compat.h>
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define TUNNEL_OPTS_MAX		64
+
+#define SKB_DST_PTRMASK		~(1UL)
+#define DST_METADATA		0x0080
+
+/* Only the fields we report are needed. */
+struct dst_entry___tunnel {
+	unsigned short flags;
+} __attribute__((preserve_access_index));
+
+struct ip_tunnel_key___tunnel {
+	__be64 tun_id;
+	union {
+		struct {
+			__be32 src;
+			__be32 dst;
+		} ipv4;
+		struct {
+			struct in6_addr src;
+			struct in6_addr dst;
+		} ipv6;
+	} u;
+	u8 tos;
+	u8 ttl;
+	__be16 tp_src;
+	__be16 tp_dst;
+} __attribute__((preserve_access_index));
+
+struct ip_tunnel_info___tunnel {
+	struct ip_tunnel_key___tunnel key;
+	u8 options_len;
+	u8 mode;
+} __attribute__((preserve_access_index));
+
+enum metadata_type___tunnel {
+	METADATA_IP_TUNNEL___tunnel,
+};
+
+struct metadata_dst___tunnel {
+	struct dst_entry___tunnel dst;
+	enum metadata_type___tunnel type;
+	union {
+		struct ip_tunnel_info___tunnel tun_info;
+	} u;
+} __attribute__((preserve_access_index));
+
+struct tunnel_event {
+	/* Raw (network order) tunnel id. */
+	u64 id;
+	u64 flags;
+	u8 src[16];
+	u8 dst[16];
+	/* Raw (network order) ports. */
+	u16 sport;
+	u16 dport;
+	u8 tos;
+	u8 ttl;
+	u8 mode;
+	/* Length of the options, they're truncated to TUNNEL_OPTS_MAX. */
+	u8 options_len;
+	u8 options[TUNNEL_OPTS_MAX];
+} __binding;
+
+/* See skb_tunnel_info() in the kernel. */
+static __always_inline struct ip_tunnel_info___tunnel *
+tunnel_info(struct sk_buff *skb)
+{
+	struct metadata_dst___tunnel *md_dst;
+
+	md_dst = (void *)(BPF_CORE_READ(skb, _skb_refdst) & SKB_DST_PTRMASK);
+	if (!md_dst)
+		return NULL;
+
+	if (!(BPF_CORE_READ(md_dst, dst.flags) & DST_METADATA))
+		return NULL;
+
+	if (BPF_CORE_READ(md_dst, type) != METADATA_IP_TUNNEL___tunnel)
+		return NULL;
+
+	return &md_dst->u.tun_info;
+}
+
+static __always_inline u64 tunnel_flags(struct ip_tunnel_info___tunnel *info)
+{
+	struct ip_tunnel_key___6_10_0 *key_6_10 = (void *)&info->key;
+	struct ip_tunnel_key___3_12_0 *key = (void *)&info->key;
+	unsigned long flags = 0;
+	__be16 flags_be = 0;
+
+	if (bpf_core_field_exists(key_6_10->tun_flags)) {
+		bpf_core_read(&flags, sizeof(flags), &key_6_10->tun_flags);
+		return flags;
+	}
+
+	bpf_core_read(&flags_be, sizeof(flags_be), &key->tun_flags);
+	return bpf_ntohs(flags_be);
+}
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct ip_tunnel_info___tunnel *info;
+	struct tunnel_event *e;
+	struct sk_buff *skb;
+	u8 len;
+
+	skb = retis_get_sk_buff(ctx);
+	if (!skb)
+		return 0;
+
+	info = tunnel_info(skb);
+	if (!info)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_TUNNEL, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->id = BPF_CORE_READ(info, key.tun_id);
+	e->flags = tunnel_flags(info);
+	e->sport = BPF_CORE_READ(info, key.tp_src);
+	e->dport = BPF_CORE_READ(info, key.tp_dst);
+	e->tos = BPF_CORE_READ(info, key.tos);
+	e->ttl = BPF_CORE_READ(info, key.ttl);
+	e->mode = BPF_CORE_READ(info, mode);
+
+	/* IPv6 addresses are reported as-is, IPv4 ones use the first 4 bytes. */
+	BPF_CORE_READ_INTO(&e->src, info, key.u.ipv6.src);
+	BPF_CORE_READ_INTO(&e->dst, info, key.u.ipv6.dst);
+
+	/* Options are stored right after the tunnel info, see
+	 * ip_tunnel_info_opts().
+	 */
+	len = BPF_CORE_READ(info, options_len);
+	e->options_len = len;
+	if (len > TUNNEL_OPTS_MAX)
+		len = TUNNEL_OPTS_MAX;
+	if (len)
+		bpf_probe_read_kernel(e->options, len,
+				      (void *)info + bpf_core_type_size(struct ip_tunnel_info___tunnel));
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,15 @@
+//! # Tunnel module
+//!
+//! Provides support for retrieving the tunnel metadata attached to skbs.
+
+// Re-export tunnel.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod tunnel;
+pub(crate) use tunnel::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::TunnelEventFactory;
+
+mod tunnel_hook {
+    include!("bpf/.out/tunnel_hook.rs");
+}
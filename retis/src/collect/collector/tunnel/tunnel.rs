@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use super::tunnel_hook;
+use crate::{
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        probe::{Hook, ProbeBuilderManager},
+    },
+};
+
+#[derive(Default)]
+pub(crate) struct TunnelCollector {}
+
+impl Collector for TunnelCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct sk_buff *"])
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        probes.register_kernel_hook(Hook::from(tunnel_hook::DATA))
+    }
+}
@@ -0,0 +1,123 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{
+    bindings::wireguard_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+};
+
+/// WireGuard event types. Keep in sync with their BPF counterparts in
+/// bpf/wireguard_hook.bpf.c
+pub(super) const WG_EV_XMIT: u8 = 0;
+pub(super) const WG_EV_SEND: u8 = 1;
+pub(super) const WG_EV_RECV: u8 = 2;
+pub(super) const WG_EV_DECRYPT: u8 = 3;
+
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+
+#[event_section_factory(FactoryId::Wireguard)]
+#[derive(Default)]
+pub(crate) struct WireguardEventFactory {}
+
+impl RawEventSectionFactory for WireguardEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<wireguard_event>(&raw_sections)?;
+        let has_peer = raw.has_peer == 1;
+        let mut wg = WireguardEvent::default();
+
+        match raw.type_ {
+            WG_EV_XMIT => {
+                wg.op = "xmit".to_string();
+                wg.verdict = Some(if has_peer { "queued" } else { "no-peer" }.to_string());
+            }
+            WG_EV_SEND => {
+                wg.op = "send".to_string();
+                wg.verdict = Some("encrypted".to_string());
+            }
+            WG_EV_RECV => {
+                wg.op = "recv".to_string();
+                wg.msg = Some(msg_str(raw.msg_type));
+            }
+            WG_EV_DECRYPT => {
+                wg.op = "decrypt".to_string();
+                wg.verdict = Some(
+                    if has_peer {
+                        "decrypted"
+                    } else {
+                        "unallowed-src"
+                    }
+                    .to_string(),
+                );
+            }
+            x => wg.op = x.to_string(),
+        }
+
+        if has_peer {
+            wg.peer = Some(WireguardPeer {
+                id: raw.peer_id,
+                key: STANDARD.encode(raw.key),
+                endpoint: endpoint(raw),
+            });
+        }
+
+        event.wireguard = Some(wg);
+        Ok(())
+    }
+}
+
+/// Get the name of a WireGuard message type, see MESSAGE_* in
+/// drivers/net/wireguard/messages.h.
+fn msg_str(msg_type: u8) -> String {
+    match msg_type {
+        1 => "initiation".to_string(),
+        2 => "response".to_string(),
+        3 => "cookie".to_string(),
+        4 => "data".to_string(),
+        x => x.to_string(),
+    }
+}
+
+/// Format the endpoint of a peer, if known.
+fn endpoint(raw: &wireguard_event) -> Option<String> {
+    let addr = raw.addr;
+    match raw.family {
+        AF_INET => Some(SocketAddr::from((
+            Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]),
+            raw.port,
+        ))),
+        AF_INET6 => Some(SocketAddr::from((Ipv6Addr::from(addr), raw.port))),
+        _ => None,
+    }
+    .map(|addr| addr.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wg_endpoint() {
+        let mut raw = wireguard_event {
+            family: AF_INET,
+            port: 51820,
+            ..Default::default()
+        };
+        raw.addr[..4].copy_from_slice(&[192, 0, 2, 1]);
+        assert_eq!(endpoint(&raw).unwrap(), "192.0.2.1:51820");
+
+        raw.family = AF_INET6;
+        raw.addr = [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        assert_eq!(endpoint(&raw).unwrap(), "[2001:db8::1]:51820");
+
+        raw.family = 0;
+        assert!(endpoint(&raw).is_none());
+    }
+}
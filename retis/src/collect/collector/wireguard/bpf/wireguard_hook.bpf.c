@@ -0,0 +1,145 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_endian.h>
+
+#include <common.h>
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define WG_EV_XMIT		0
+#define WG_EV_SEND		1
+#define WG_EV_RECV		2
+#define WG_EV_DECRYPT		3
+
+/* Only the first bytes of the public keys are reported. */
+#define WG_KEY_LEN		6
+
+#define AF_INET			2
+#define AF_INET6		10
+#define UDP_HLEN		8
+
+#define retis_get_wg_peer(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, peer, struct wg_peer___wg *)
+
+/* Only the fields we report are needed. */
+struct wg_peer___wg {
+	struct {
+		union {
+			struct {
+				u16 sin_family;
+				__be16 sin_port;
+				struct in_addr sin_addr;
+			} addr4;
+			struct {
+				u16 sin6_family;
+				__be16 sin6_port;
+				struct in6_addr sin6_addr;
+			} addr6;
+		};
+	} endpoint;
+	struct {
+		u8 remote_static[32];
+	} handshake;
+	u64 internal_id;
+} __attribute__((preserve_access_index));
+
+/**
+ * WireGuard hook configuration. Each probe has its own instance, as the data
+ * to retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct wireguard_offsets {
+	s8 peer;
+};
+struct wireguard_config {
+	struct wireguard_offsets offsets;
+	u8 type;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct wireguard_config);
+} wireguard_config_map SEC(".maps");
+
+struct wireguard_event {
+	u64 peer_id;
+	/* Peer endpoint. Host order port. */
+	u16 family;
+	u16 port;
+	u8 addr[16];
+	u8 key[WG_KEY_LEN];
+	u8 type;
+	/* Message type, on receive. */
+	u8 msg_type;
+	/* Set if the peer fields above are valid. */
+	u8 has_peer;
+} __binding;
+
+static __always_inline void wg_report_peer(struct wireguard_event *e,
+					   struct wg_peer___wg *peer)
+{
+	e->has_peer = 1;
+	e->peer_id = BPF_CORE_READ(peer, internal_id);
+	bpf_core_read(e->key, WG_KEY_LEN, &peer->handshake.remote_static);
+
+	e->family = BPF_CORE_READ(peer, endpoint.addr4.sin_family);
+	if (e->family == AF_INET) {
+		e->port = bpf_ntohs(BPF_CORE_READ(peer, endpoint.addr4.sin_port));
+		BPF_CORE_READ_INTO(&e->addr, peer, endpoint.addr4.sin_addr);
+	} else if (e->family == AF_INET6) {
+		e->port = bpf_ntohs(BPF_CORE_READ(peer, endpoint.addr6.sin6_port));
+		BPF_CORE_READ_INTO(&e->addr, peer, endpoint.addr6.sin6_addr);
+	}
+}
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct wireguard_config *cfg;
+	struct wireguard_event *e;
+	struct wg_peer___wg *peer;
+	struct sk_buff *skb;
+	u32 key = 0;
+	void *head;
+
+	cfg = bpf_map_lookup_elem(&wireguard_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	skb = retis_get_sk_buff(ctx);
+	if (!skb)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_WIREGUARD, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->type = cfg->type;
+
+	switch (cfg->type) {
+	case WG_EV_XMIT:
+	case WG_EV_DECRYPT:
+		/* Allowed IPs lookups return the matching peer, if any. */
+		peer = (void *)ctx->regs.ret;
+		break;
+	case WG_EV_RECV:
+		/* Messages start right after the UDP header. */
+		head = BPF_CORE_READ(skb, head);
+		bpf_probe_read_kernel(&e->msg_type, sizeof(e->msg_type),
+				      head + BPF_CORE_READ(skb, transport_header) +
+				      UDP_HLEN);
+		peer = NULL;
+		break;
+	default:
+		peer = retis_get_wg_peer(ctx, cfg);
+		break;
+	}
+
+	if (peer)
+		wg_report_peer(e, peer);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,16 @@
+//! # WireGuard module
+//!
+//! Provides support for tracing the WireGuard datapath: peer selection,
+//! encryption and decryption.
+
+// Re-export wireguard.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod wireguard;
+pub(crate) use wireguard::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::WireguardEventFactory;
+
+mod wireguard_hook {
+    include!("bpf/.out/wireguard_hook.rs");
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, wireguard_hook};
+use crate::{
+    bindings::wireguard_hook_uapi::wireguard_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Peer lookup of packets sent through a WireGuard device.
+const WG_XMIT: &str = "wg_allowedips_lookup_dst";
+/// Encrypted packets sent to a peer.
+const WG_SEND: &str = "wg_socket_send_skb_to_peer";
+/// Packets received from the network.
+const WG_RECV: &str = "wg_packet_receive";
+/// Source check of decrypted packets.
+const WG_DECRYPT: &str = "wg_allowedips_lookup_src";
+
+#[derive(Default)]
+pub(crate) struct WireguardCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for WireguardCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn known_kernel_types(&self) -> Option<Vec<&'static str>> {
+        Some(vec!["struct sk_buff *"])
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name(WG_RECV) {
+            bail!(
+                "Could not resolve WireGuard kernel symbol (is the wireguard module loaded?): {e}"
+            );
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let sym = Symbol::from_name(WG_RECV)?;
+        let cfg = Self::config(&sym, WG_EV_RECV)?;
+        self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+
+        if let Ok(sym) = Symbol::from_name(WG_XMIT) {
+            let cfg = Self::config(&sym, WG_EV_XMIT)?;
+            self.add_probe(probes, Probe::kretprobe(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(WG_SEND) {
+            let cfg = Self::config(&sym, WG_EV_SEND)?;
+            self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(WG_DECRYPT) {
+            let cfg = Self::config(&sym, WG_EV_DECRYPT)?;
+            self.add_probe(probes, Probe::kretprobe(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl WireguardCollector {
+    /// Build the hook configuration of a given probe.
+    fn config(sym: &Symbol, r#type: u8) -> Result<wireguard_config> {
+        let mut cfg = wireguard_config {
+            type_: r#type,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset("struct wg_peer *")? {
+            cfg.offsets.peer = offset as i8;
+        }
+        Ok(cfg)
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: wireguard_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/wireguard_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(wireguard_hook::DATA),
+            "wireguard_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use anyhow::Result;
+
+use crate::{
+    bindings::xdp_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string, raw_to_string_opt,
+};
+
+/// XDP actions, see `enum xdp_action` in include/uapi/linux/bpf.h.
+pub(super) const XDP_REDIRECT: i32 = 4;
+
+#[event_section_factory(FactoryId::Xdp)]
+#[derive(Default)]
+pub(crate) struct XdpEventFactory {}
+
+impl RawEventSectionFactory for XdpEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<xdp_event>(&raw_sections)?;
+
+        event.xdp = Some(XdpEvent {
+            prog_id: raw.prog_id,
+            prog_name: raw_to_string_opt!(&raw.prog_name)?,
+            action: (raw.action >= 0).then(|| action_str(raw.action)),
+            err: Some(raw.err).filter(|err| *err != 0),
+        });
+        Ok(())
+    }
+}
+
+/// Get the name of an XDP action.
+fn action_str(action: i64) -> String {
+    match action {
+        0 => "ABORTED".to_string(),
+        1 => "DROP".to_string(),
+        2 => "PASS".to_string(),
+        3 => "TX".to_string(),
+        4 => "REDIRECT".to_string(),
+        x => x.to_string(),
+    }
+}
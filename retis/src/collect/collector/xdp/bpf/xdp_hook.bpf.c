@@ -0,0 +1,110 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+#define BPF_OBJ_NAME_LEN	16
+
+#define retis_get_bpf_prog(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, bpf_prog, struct bpf_prog___xdp *)
+#define retis_get_xdp_act(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, act, u32)
+#define retis_get_xdp_err(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, err, int)
+
+/* Only the program identifiers are needed. */
+struct bpf_prog_aux___xdp {
+	u32 id;
+	char name[BPF_OBJ_NAME_LEN];
+} __attribute__((preserve_access_index));
+
+struct bpf_prog___xdp {
+	struct bpf_prog_aux___xdp *aux;
+} __attribute__((preserve_access_index));
+
+/**
+ * XDP hook configuration. Each probe has its own instance, as the action is
+ * retrieved differently depending on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct xdp_offsets {
+	s8 bpf_prog;
+	s8 act;
+	s8 err;
+};
+/**
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct xdp_config {
+	struct xdp_offsets offsets;
+	/* The action is the probed function return value. */
+	u8 act_ret;
+	/* Fixed action reported by the probe, if >= 0. */
+	s32 act;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct xdp_config);
+} xdp_config_map SEC(".maps");
+
+struct xdp_event {
+	u32 prog_id;
+	char prog_name[BPF_OBJ_NAME_LEN];
+	/* XDP action, < 0 if not available. */
+	s64 action;
+	s32 err;
+} __binding;
+
+DEFINE_HOOK_RAW(
+	struct bpf_prog_aux___xdp *aux;
+	struct bpf_prog___xdp *prog;
+	struct xdp_config *cfg;
+	struct xdp_event *e;
+	u32 key = 0;
+
+	/* Packets not matching the filters are not reported. Probes not having
+	 * access to an skb (e.g. the xdp tracepoints) are not subject to
+	 * filtering.
+	 */
+	if (retis_get_sk_buff(ctx) && !RETIS_TRACKABLE(ctx))
+		return 0;
+
+	cfg = bpf_map_lookup_elem(&xdp_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	prog = retis_get_bpf_prog(ctx, cfg);
+	if (!prog)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_XDP, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	aux = BPF_CORE_READ(prog, aux);
+	if (aux) {
+		e->prog_id = BPF_CORE_READ(aux, id);
+		bpf_core_read_str(e->prog_name, sizeof(e->prog_name),
+				  &aux->name);
+	}
+
+	if (cfg->act_ret)
+		e->action = (u32)ctx->regs.ret;
+	else if (retis_offset_valid(cfg->offsets.act))
+		e->action = retis_get_xdp_act(ctx, cfg);
+	else
+		e->action = cfg->act;
+
+	e->err = retis_get_xdp_err(ctx, cfg);
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,15 @@
+//! # XDP module
+//!
+//! Provides support for tracing XDP programs and their actions.
+
+// Re-export xdp.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod xdp;
+pub(crate) use xdp::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::XdpEventFactory;
+
+mod xdp_hook {
+    include!("bpf/.out/xdp_hook.rs");
+}
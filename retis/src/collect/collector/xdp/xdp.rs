@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::XDP_REDIRECT, xdp_hook};
+use crate::{
+    bindings::xdp_hook_uapi::xdp_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Functions running generic (skb) XDP programs. Only the first one found is
+/// used, the latter being used on older kernels.
+const XDP_GENERIC_TARGETS: &[&str] = &["bpf_prog_run_generic_xdp", "netif_receive_generic_xdp"];
+/// XDP tracepoints, reporting exceptions and redirections.
+const XDP_TP_EXCEPTION: &str = "xdp:xdp_exception";
+const XDP_TP_REDIRECT: &[&str] = &["xdp:xdp_redirect", "xdp:xdp_redirect_err"];
+
+#[derive(Default)]
+pub(crate) struct XdpCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for XdpCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if Self::generic_target().is_none() && Symbol::from_name(XDP_TP_EXCEPTION).is_err() {
+            bail!("Could not find XDP kernel functions nor tracepoints");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        // Generic XDP: report the program when it is entered and the action it
+        // returned when it exits.
+        if let Some(sym) = Self::generic_target() {
+            let cfg = Self::config(&sym, false)?;
+            self.add_probe(probes, Probe::kprobe(sym.clone())?, cfg)?;
+
+            let cfg = Self::config(&sym, true)?;
+            self.add_probe(probes, Probe::kretprobe(sym)?, cfg)?;
+        }
+
+        // Exceptions, reported for both generic and native XDP programs.
+        if let Ok(sym) = Symbol::from_name(XDP_TP_EXCEPTION) {
+            let mut cfg = Self::config(&sym, false)?;
+            if let Some(offset) = sym.parameter_offset("u32")? {
+                cfg.offsets.act = offset as i8;
+            }
+            self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+        }
+
+        // Redirections, reported for both generic and native XDP programs.
+        for target in XDP_TP_REDIRECT.iter() {
+            if let Ok(sym) = Symbol::from_name(target) {
+                let mut cfg = Self::config(&sym, false)?;
+                cfg.act = XDP_REDIRECT;
+                if let Some(offset) = sym.parameter_offset("int")? {
+                    cfg.offsets.err = offset as i8;
+                }
+                self.add_probe(probes, Probe::raw_tracepoint(sym)?, cfg)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl XdpCollector {
+    /// Get the function running generic XDP programs, if any.
+    fn generic_target() -> Option<Symbol> {
+        XDP_GENERIC_TARGETS
+            .iter()
+            .find_map(|target| Symbol::from_name(target).ok())
+    }
+
+    /// Build the hook configuration of a given probe.
+    fn config(sym: &Symbol, act_ret: bool) -> Result<xdp_config> {
+        let mut cfg = xdp_config {
+            act_ret: act_ret as u8,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset("struct bpf_prog *")? {
+            cfg.offsets.bpf_prog = offset as i8;
+        }
+        Ok(cfg)
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: xdp_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/xdp_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(xdp_hook::DATA),
+            "xdp_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
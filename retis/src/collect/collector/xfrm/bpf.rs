@@ -0,0 +1,123 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use anyhow::Result;
+
+use crate::{
+    bindings::xfrm_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+};
+
+/// Xfrm event types. Keep in sync with their BPF counterparts in
+/// bpf/xfrm_hook.bpf.c
+pub(super) const XFRM_EV_INPUT: u8 = 0;
+pub(super) const XFRM_EV_OUTPUT: u8 = 1;
+pub(super) const XFRM_EV_OUTPUT_RET: u8 = 2;
+pub(super) const XFRM_EV_POLICY_RET: u8 = 3;
+
+const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
+
+#[event_section_factory(FactoryId::Xfrm)]
+#[derive(Default)]
+pub(crate) struct XfrmEventFactory {}
+
+impl RawEventSectionFactory for XfrmEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<xfrm_event>(&raw_sections)?;
+        let mut xfrm = XfrmEvent::default();
+
+        match raw.type_ {
+            XFRM_EV_INPUT => {
+                xfrm.op = "input".to_string();
+                // A null SPI means xfrm_input is resuming processing of an
+                // already looked up packet.
+                if raw.spi != 0 {
+                    xfrm.spi = Some(raw.spi);
+                    xfrm.proto = Some(proto_str(raw.int_arg as u8));
+                }
+            }
+            XFRM_EV_OUTPUT | XFRM_EV_OUTPUT_RET => {
+                xfrm.op = "output".to_string();
+                if raw.type_ == XFRM_EV_OUTPUT_RET {
+                    xfrm.err = Some(raw.ret);
+                }
+            }
+            XFRM_EV_POLICY_RET => {
+                xfrm.op = "policy".to_string();
+                xfrm.dir = Some(match raw.int_arg {
+                    0 => "in".to_string(),
+                    1 => "out".to_string(),
+                    2 => "fwd".to_string(),
+                    x => x.to_string(),
+                });
+            }
+            x => xfrm.op = x.to_string(),
+        }
+
+        if raw.has_state == 1 {
+            xfrm.spi = Some(raw.spi);
+            xfrm.proto = Some(proto_str(raw.proto));
+            xfrm.sa = Some(XfrmSa {
+                mode: mode_str(raw.mode),
+                reqid: raw.reqid,
+                src: addr_str(raw.family, &raw.saddr),
+                dst: addr_str(raw.family, &raw.daddr),
+            });
+        }
+
+        event.xfrm = Some(xfrm);
+        Ok(())
+    }
+}
+
+/// Get the name of an IPsec protocol.
+fn proto_str(proto: u8) -> String {
+    match proto {
+        50 => "esp".to_string(),
+        51 => "ah".to_string(),
+        108 => "comp".to_string(),
+        x => x.to_string(),
+    }
+}
+
+/// Get the name of an xfrm mode, see XFRM_MODE_* in include/uapi/linux/xfrm.h.
+fn mode_str(mode: u8) -> String {
+    match mode {
+        0 => "transport".to_string(),
+        1 => "tunnel".to_string(),
+        2 => "ro".to_string(),
+        3 => "in_trigger".to_string(),
+        4 => "beet".to_string(),
+        5 => "iptfs".to_string(),
+        x => x.to_string(),
+    }
+}
+
+/// Format an xfrm address (xfrm_address_t) of a given family.
+fn addr_str(family: u16, addr: &[u8; 16]) -> String {
+    match family {
+        AF_INET => Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string(),
+        AF_INET6 => Ipv6Addr::from(*addr).to_string(),
+        x => format!("family {x}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xfrm_addr() {
+        let mut addr = [0; 16];
+        addr[..4].copy_from_slice(&[10, 0, 0, 1]);
+        assert_eq!(addr_str(AF_INET, &addr), "10.0.0.1");
+
+        let addr = [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        assert_eq!(addr_str(AF_INET6, &addr), "2001:db8::1");
+    }
+}
@@ -0,0 +1,151 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_endian.h>
+
+#include <common.h>
+
+/* Keep in sync with its Rust counterpart in bpf.rs */
+#define XFRM_EV_INPUT		0
+#define XFRM_EV_OUTPUT		1
+#define XFRM_EV_OUTPUT_RET	2
+#define XFRM_EV_POLICY_RET	3
+
+#define SKB_DST_PTRMASK		~(1UL)
+
+#define retis_get_xfrm_spi(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, spi, __be32)
+#define retis_get_xfrm_int(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, int_arg, int)
+
+/* Only the state fields we report are needed. */
+union xfrm_address___xfrm {
+	u32 a4;
+	u32 a6[4];
+} __attribute__((preserve_access_index));
+
+struct xfrm_state___xfrm {
+	struct {
+		union xfrm_address___xfrm daddr;
+		__be32 spi;
+		u8 proto;
+	} id;
+	struct {
+		u32 reqid;
+		u8 mode;
+		u16 family;
+		union xfrm_address___xfrm saddr;
+	} props;
+} __attribute__((preserve_access_index));
+
+struct dst_entry___xfrm {
+	struct xfrm_state___xfrm *xfrm;
+} __attribute__((preserve_access_index));
+
+/**
+ * Xfrm hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct xfrm_offsets {
+	s8 spi;
+	/* Next header on input, direction on policy checks. */
+	s8 int_arg;
+};
+struct xfrm_config {
+	struct xfrm_offsets offsets;
+	u8 type;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct xfrm_config);
+} xfrm_config_map SEC(".maps");
+
+struct xfrm_event {
+	u8 type;
+	/* Set if the state fields below are valid. */
+	u8 has_state;
+	u8 proto;
+	u8 mode;
+	u16 family;
+	u32 spi;
+	u32 reqid;
+	u8 saddr[16];
+	u8 daddr[16];
+	/* Next header on input, direction on policy checks. */
+	s32 int_arg;
+	s32 ret;
+} __binding;
+
+static __always_inline void xfrm_report_state(struct xfrm_event *e,
+					      struct sk_buff *skb)
+{
+	struct xfrm_state___xfrm *x;
+	struct dst_entry___xfrm *dst;
+
+	dst = (void *)(BPF_CORE_READ(skb, _skb_refdst) & SKB_DST_PTRMASK);
+	if (!dst)
+		return;
+
+	x = BPF_CORE_READ(dst, xfrm);
+	if (!x)
+		return;
+
+	e->has_state = 1;
+	e->spi = bpf_ntohl(BPF_CORE_READ(x, id.spi));
+	e->proto = BPF_CORE_READ(x, id.proto);
+	e->mode = BPF_CORE_READ(x, props.mode);
+	e->family = BPF_CORE_READ(x, props.family);
+	e->reqid = BPF_CORE_READ(x, props.reqid);
+	BPF_CORE_READ_INTO(&e->saddr, x, props.saddr);
+	BPF_CORE_READ_INTO(&e->daddr, x, id.daddr);
+}
+
+DEFINE_HOOK(F_AND, RETIS_ALL_FILTERS,
+	struct xfrm_config *cfg;
+	struct sk_buff *skb;
+	struct xfrm_event *e;
+	u32 key = 0;
+	s32 ret = 0;
+
+	cfg = bpf_map_lookup_elem(&xfrm_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	/* Only report failures when returning from the probed functions:
+	 * errors on output and packets blocked by policy checks.
+	 */
+	if (cfg->type == XFRM_EV_OUTPUT_RET || cfg->type == XFRM_EV_POLICY_RET) {
+		ret = (s32)ctx->regs.ret;
+		if ((cfg->type == XFRM_EV_OUTPUT_RET && ret >= 0) ||
+		    (cfg->type == XFRM_EV_POLICY_RET && ret))
+			return -ENOMSG;
+	}
+
+	e = get_event_zsection(event, COLLECTOR_XFRM, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->type = cfg->type;
+	e->ret = ret;
+	e->int_arg = retis_get_xfrm_int(ctx, cfg);
+
+	switch (cfg->type) {
+	case XFRM_EV_INPUT:
+		e->spi = bpf_ntohl(retis_get_xfrm_spi(ctx, cfg));
+		break;
+	case XFRM_EV_OUTPUT:
+		skb = retis_get_sk_buff(ctx);
+		if (skb)
+			xfrm_report_state(e, skb);
+		break;
+	}
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
@@ -0,0 +1,15 @@
+//! # Xfrm module
+//!
+//! Provides support for tracing the xfrm (IPsec) layer.
+
+// Re-export xfrm.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod xfrm;
+pub(crate) use xfrm::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::XfrmEventFactory;
+
+mod xfrm_hook {
+    include!("bpf/.out/xfrm_hook.rs");
+}
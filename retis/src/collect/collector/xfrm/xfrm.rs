@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, xfrm_hook};
+use crate::{
+    bindings::xfrm_hook_uapi::xfrm_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// Entry point of received IPsec packets.
+const XFRM_INPUT: &str = "xfrm_input";
+/// Encapsulation of packets.
+const XFRM_OUTPUT: &str = "xfrm_output";
+/// Policy checks of received and forwarded packets.
+const XFRM_POLICY_CHECK: &str = "__xfrm_policy_check";
+
+#[derive(Default)]
+pub(crate) struct XfrmCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for XfrmCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, _: &Collect) -> Result<()> {
+        if let Err(e) = Symbol::from_name(XFRM_INPUT) {
+            bail!("Could not resolve xfrm kernel symbol: {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        let sym = Symbol::from_name(XFRM_INPUT)?;
+        let mut cfg = Self::config(XFRM_EV_INPUT);
+        if let Some(offset) = sym.parameter_offset("__be32")? {
+            cfg.offsets.spi = offset as i8;
+        }
+        // Next header.
+        if let Some(offset) = sym.parameter_offset("int")? {
+            cfg.offsets.int_arg = offset as i8;
+        }
+        self.add_probe(probes, Probe::kprobe(sym)?, cfg)?;
+
+        if let Ok(sym) = Symbol::from_name(XFRM_OUTPUT) {
+            let cfg = Self::config(XFRM_EV_OUTPUT);
+            self.add_probe(probes, Probe::kprobe(sym.clone())?, cfg)?;
+
+            let cfg = Self::config(XFRM_EV_OUTPUT_RET);
+            self.add_probe(probes, Probe::kretprobe(sym)?, cfg)?;
+        }
+
+        if let Ok(sym) = Symbol::from_name(XFRM_POLICY_CHECK) {
+            let mut cfg = Self::config(XFRM_EV_POLICY_RET);
+            // Direction.
+            if let Some(offset) = sym.parameter_offset("int")? {
+                cfg.offsets.int_arg = offset as i8;
+            }
+            self.add_probe(probes, Probe::kretprobe(sym)?, cfg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl XfrmCollector {
+    fn config(r#type: u8) -> xfrm_config {
+        xfrm_config {
+            type_: r#type,
+            ..Default::default()
+        }
+    }
+
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        probe: Probe,
+        cfg: xfrm_config,
+    ) -> Result<()> {
+        // Please keep in sync with its BPF counterpart in bpf/xfrm_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(xfrm_hook::DATA),
+            "xfrm_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
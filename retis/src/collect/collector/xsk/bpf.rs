@@ -0,0 +1,102 @@
+//! Rust<>BPF types definitions for the xsk module.
+//! Please keep this file in sync with its BPF counterparts in bpf/.
+use anyhow::{bail, Result};
+
+use crate::{
+    bindings::xsk_hook_uapi::*,
+    core::events::{
+        parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+        RawEventSectionFactory,
+    },
+    event_section_factory,
+    events::*,
+    raw_to_string, raw_to_string_opt,
+};
+
+/// AF_XDP operations. Keep in sync with their BPF counterparts in
+/// bpf/xsk_hook.bpf.c
+pub(super) const XSK_OP_RX: u8 = 0;
+pub(super) const XSK_OP_TX: u8 = 1;
+pub(super) const XSK_OP_TX_COMPLETION: u8 = 2;
+
+/// AF_XDP modes. Keep in sync with their BPF counterparts in
+/// bpf/xsk_hook.bpf.c
+pub(super) const XSK_MODE_DETECT: u8 = 0;
+pub(super) const XSK_MODE_COPY: u8 = 1;
+pub(super) const XSK_MODE_ZC: u8 = 2;
+
+#[event_section_factory(FactoryId::Xsk)]
+#[derive(Default)]
+pub(crate) struct XskEventFactory {}
+
+impl RawEventSectionFactory for XskEventFactory {
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let raw = parse_single_raw_section::<xsk_event>(&raw_sections)?;
+        let rx = raw.op == XSK_OP_RX;
+        let pool_ring = unmarshal_ring(&raw.pool_ring);
+
+        event.xsk = Some(XskEvent {
+            op: match raw.op {
+                XSK_OP_RX => "rx",
+                XSK_OP_TX => "tx",
+                XSK_OP_TX_COMPLETION => "tx-completion",
+                x => bail!("Unknown AF_XDP operation ({x})"),
+            }
+            .to_string(),
+            mode: match raw.mode {
+                XSK_MODE_COPY => Some("copy".to_string()),
+                XSK_MODE_ZC => Some("zero-copy".to_string()),
+                _ => None,
+            },
+            dev: raw_to_string_opt!(&raw.dev)?,
+            queue_id: raw.queue_id,
+            descs: (raw.op != XSK_OP_RX)
+                .then_some(raw.descs)
+                .filter(|d| *d != 0),
+            err: Some(raw.err).filter(|e| *e < 0),
+            ring: unmarshal_ring(&raw.ring),
+            fill: pool_ring.clone().filter(|_| rx),
+            completion: pool_ring.filter(|_| !rx),
+        });
+
+        Ok(())
+    }
+}
+
+/// Convert a raw AF_XDP ring. Its indexes are free running and wrap around.
+fn unmarshal_ring(raw: &xsk_ring) -> Option<XskRing> {
+    (raw.valid == 1).then(|| XskRing {
+        used: raw.producer.wrapping_sub(raw.consumer),
+        size: raw.size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xsk_ring_used() {
+        let mut raw = xsk_ring {
+            producer: 10,
+            consumer: 4,
+            size: 2048,
+            valid: 1,
+        };
+        assert_eq!(
+            unmarshal_ring(&raw),
+            Some(XskRing {
+                used: 6,
+                size: 2048
+            })
+        );
+
+        // Producer wrapped around.
+        raw.producer = 2;
+        raw.consumer = u32::MAX - 1;
+        assert_eq!(unmarshal_ring(&raw).unwrap().used, 4);
+
+        raw.valid = 0;
+        assert_eq!(unmarshal_ring(&raw), None);
+    }
+}
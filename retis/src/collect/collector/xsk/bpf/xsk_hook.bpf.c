@@ -0,0 +1,228 @@
+#include <vmlinux.h>
+#include <bpf/bpf_core_read.h>
+
+#include <common.h>
+
+BINDING_DEF(IFNAMSIZ, 16)
+
+/* Keep in sync with their Rust counterparts in bpf.rs */
+#define XSK_OP_RX		0
+#define XSK_OP_TX		1
+#define XSK_OP_TX_COMPLETION	2
+
+#define XSK_MODE_DETECT		0
+#define XSK_MODE_COPY		1
+#define XSK_MODE_ZC		2
+
+#define retis_get_xdp_sock(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, xdp_sock, struct xdp_sock___xsk *)
+#define retis_get_sock(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, sock, struct xdp_sock___xsk *)
+#define retis_get_xdp_buff(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, xdp_buff, struct xdp_buff___xsk *)
+#define retis_get_xsk_buff_pool(ctx, cfg)	\
+	RETIS_HOOK_GET(ctx, cfg->offsets, xsk_buff_pool, struct xsk_buff_pool___xsk *)
+#define retis_get_descs(ctx, cfg)		\
+	RETIS_HOOK_GET(ctx, cfg->offsets, descs, u32)
+
+enum xdp_mem_type___xsk {
+	MEM_TYPE_XSK_BUFF_POOL___xsk,
+};
+
+/* Only the fields we report are needed. */
+struct xdp_ring___xsk {
+	u32 producer;
+	u32 consumer;
+} __attribute__((preserve_access_index));
+
+struct xsk_queue___xsk {
+	u32 nentries;
+	struct xdp_ring___xsk *ring;
+} __attribute__((preserve_access_index));
+
+struct xsk_buff_pool___xsk {
+	struct net_device *netdev;
+	struct xsk_queue___xsk *fq;
+	struct xsk_queue___xsk *cq;
+	u16 queue_id;
+} __attribute__((preserve_access_index));
+
+/* Sockets embed their struct sock first, so a struct sock * can be used as
+ * a struct xdp_sock * for AF_XDP sockets.
+ */
+struct xdp_sock___xsk {
+	struct xsk_queue___xsk *rx;
+	struct net_device *dev;
+	struct xsk_buff_pool___xsk *pool;
+	u16 queue_id;
+	struct xsk_queue___xsk *tx;
+} __attribute__((preserve_access_index));
+
+struct xdp_mem_info___xsk {
+	u32 type;
+} __attribute__((preserve_access_index));
+
+struct xdp_rxq_info___xsk {
+	struct xdp_mem_info___xsk mem;
+} __attribute__((preserve_access_index));
+
+struct xdp_buff___xsk {
+	struct xdp_rxq_info___xsk *rxq;
+} __attribute__((preserve_access_index));
+
+/**
+ * AF_XDP hook configuration. Each probe has its own instance, as the data to
+ * retrieve depends on the probe.
+ *
+ * Skip Default trait implementation:
+ *
+ * <div rustbindgen nodefault></div>
+ */
+struct xsk_offsets {
+	s8 xdp_sock;
+	s8 sock;
+	s8 xdp_buff;
+	s8 xsk_buff_pool;
+	s8 descs;
+};
+struct xsk_config {
+	struct xsk_offsets offsets;
+	u8 op;
+	u8 mode;
+	/* The probed function returns the number of descriptors. */
+	u8 descs_ret;
+} __binding;
+struct {
+	__uint(type, BPF_MAP_TYPE_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct xsk_config);
+} xsk_config_map SEC(".maps");
+
+/* Ring indexes are free running, occupancy is computed in userspace. */
+struct xsk_ring {
+	u32 producer;
+	u32 consumer;
+	u32 size;
+	u8 valid;
+};
+
+struct xsk_event {
+	/* Socket ring: RX ring on receive, TX ring on transmit. */
+	struct xsk_ring ring;
+	/* Pool ring: fill ring on receive, completion ring on transmit. */
+	struct xsk_ring pool_ring;
+	s32 err;
+	u32 descs;
+	u16 queue_id;
+	u8 op;
+	u8 mode;
+	char dev[IFNAMSIZ];
+} __binding;
+
+static __always_inline void xsk_read_ring(struct xsk_ring *r,
+					  struct xsk_queue___xsk *q)
+{
+	struct xdp_ring___xsk *ring;
+
+	if (!q)
+		return;
+
+	ring = BPF_CORE_READ(q, ring);
+	if (!ring)
+		return;
+
+	r->producer = BPF_CORE_READ(ring, producer);
+	r->consumer = BPF_CORE_READ(ring, consumer);
+	r->size = BPF_CORE_READ(q, nentries);
+	r->valid = 1;
+}
+
+/* Copy mode is used unless the buffer comes from the socket pool itself,
+ * see xsk_rcv().
+ */
+static __always_inline u8 xsk_rx_mode(struct xdp_buff___xsk *xdp)
+{
+	u32 type;
+
+	if (!xdp ||
+	    !bpf_core_enum_value_exists(enum xdp_mem_type___xsk,
+					MEM_TYPE_XSK_BUFF_POOL___xsk))
+		return XSK_MODE_DETECT;
+
+	type = BPF_CORE_READ(xdp, rxq, mem.type);
+	return type == bpf_core_enum_value(enum xdp_mem_type___xsk,
+					   MEM_TYPE_XSK_BUFF_POOL___xsk) ?
+	       XSK_MODE_ZC : XSK_MODE_COPY;
+}
+
+/* AF_XDP sockets get packets directly from XDP buffers, those are not skbs
+ * and this hook is not subject to filtering.
+ */
+DEFINE_HOOK_RAW(
+	struct xsk_buff_pool___xsk *pool;
+	struct xdp_sock___xsk *xs;
+	struct xsk_config *cfg;
+	struct net_device *dev;
+	struct xsk_event *e;
+	u32 key = 0;
+	s64 ret;
+
+	cfg = bpf_map_lookup_elem(&xsk_config_map, &key);
+	if (!cfg)
+		return 0;
+
+	xs = retis_get_xdp_sock(ctx, cfg);
+	if (!xs)
+		xs = retis_get_sock(ctx, cfg);
+	pool = retis_get_xsk_buff_pool(ctx, cfg);
+	if (!xs && !pool)
+		return 0;
+
+	ret = ctx->regs.ret;
+	/* Nothing was sent. */
+	if (cfg->descs_ret && !ret)
+		return 0;
+
+	e = get_event_zsection(event, COLLECTOR_XSK, 1, sizeof(*e));
+	if (!e)
+		return 0;
+
+	e->op = cfg->op;
+	e->mode = cfg->mode;
+
+	if (cfg->descs_ret)
+		e->descs = ret;
+	else if (cfg->op == XSK_OP_TX_COMPLETION)
+		e->descs = retis_get_descs(ctx, cfg);
+	else if (ctx->probe_type == KERNEL_PROBE_KRETPROBE)
+		e->err = ret;
+
+	if (cfg->mode == XSK_MODE_DETECT)
+		e->mode = xsk_rx_mode(retis_get_xdp_buff(ctx, cfg));
+
+	if (xs) {
+		dev = BPF_CORE_READ(xs, dev);
+		e->queue_id = BPF_CORE_READ(xs, queue_id);
+		pool = BPF_CORE_READ(xs, pool);
+
+		xsk_read_ring(&e->ring, cfg->op == XSK_OP_RX ?
+				       BPF_CORE_READ(xs, rx) :
+				       BPF_CORE_READ(xs, tx));
+	} else {
+		dev = BPF_CORE_READ(pool, netdev);
+		e->queue_id = BPF_CORE_READ(pool, queue_id);
+	}
+
+	if (dev)
+		bpf_probe_read_kernel_str(e->dev, IFNAMSIZ, dev->name);
+
+	if (pool)
+		xsk_read_ring(&e->pool_ring, cfg->op == XSK_OP_RX ?
+					    BPF_CORE_READ(pool, fq) :
+					    BPF_CORE_READ(pool, cq));
+
+	return 0;
+)
+
+char __license[] SEC("license") = "GPL";
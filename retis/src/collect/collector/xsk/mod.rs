@@ -0,0 +1,16 @@
+//! # Xsk module
+//!
+//! Provides support for tracing AF_XDP sockets: packets received and sent
+//! through their rings, and whether those were copied or not.
+
+// Re-export xsk.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod xsk;
+pub(crate) use xsk::*;
+
+pub(crate) mod bpf;
+pub(crate) use bpf::XskEventFactory;
+
+mod xsk_hook {
+    include!("bpf/.out/xsk_hook.rs");
+}
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use super::{bpf::*, xsk_hook};
+use crate::{
+    bindings::xsk_hook_uapi::xsk_config,
+    collect::{cli::Collect, Collector},
+    core::{
+        events::*,
+        kernel::Symbol,
+        probe::{common::register_config_probe, Hook, Probe, ProbeBuilderManager},
+    },
+};
+
+/// AF_XDP probe description.
+struct XskTarget {
+    /// Functions to probe. Only the first one found is used, the latter ones
+    /// being used on older kernels.
+    targets: &'static [&'static str],
+    op: u8,
+    mode: u8,
+    /// Use a kretprobe, to report the result of the operation.
+    kretprobe: bool,
+    /// The function returns the number of descriptors it handled.
+    descs_ret: bool,
+}
+
+/// Packets redirected to an AF_XDP socket by a native XDP program; and by a
+/// generic one, in which case they're always copied.
+const XSK_RX_TARGET: XskTarget = XskTarget {
+    targets: &["__xsk_map_redirect"],
+    op: XSK_OP_RX,
+    mode: XSK_MODE_DETECT,
+    kretprobe: true,
+    descs_ret: false,
+};
+const XSK_TARGETS: &[XskTarget] = &[
+    XSK_RX_TARGET,
+    XskTarget {
+        targets: &["xsk_generic_rcv"],
+        op: XSK_OP_RX,
+        mode: XSK_MODE_COPY,
+        kretprobe: true,
+        descs_ret: false,
+    },
+    // Packets sent in copy mode, when the socket is not bound in zero-copy
+    // mode or the driver does not support it.
+    XskTarget {
+        targets: &["__xsk_generic_xmit", "xsk_generic_xmit"],
+        op: XSK_OP_TX,
+        mode: XSK_MODE_COPY,
+        kretprobe: true,
+        descs_ret: false,
+    },
+    // Descriptors consumed from the TX ring by zero-copy drivers.
+    XskTarget {
+        targets: &["xsk_tx_peek_release_desc_batch"],
+        op: XSK_OP_TX,
+        mode: XSK_MODE_ZC,
+        kretprobe: true,
+        descs_ret: true,
+    },
+    XskTarget {
+        targets: &["xsk_tx_peek_desc"],
+        op: XSK_OP_TX,
+        mode: XSK_MODE_ZC,
+        kretprobe: true,
+        descs_ret: true,
+    },
+    // Descriptors sent by zero-copy drivers, given back to the application.
+    XskTarget {
+        targets: &["xsk_tx_completed"],
+        op: XSK_OP_TX_COMPLETION,
+        mode: XSK_MODE_ZC,
+        kretprobe: false,
+        descs_ret: false,
+    },
+];
+
+#[derive(Default)]
+pub(crate) struct XskCollector {
+    // Used to keep a reference to our internal config maps.
+    #[allow(dead_code)]
+    config_maps: Vec<libbpf_rs::MapHandle>,
+}
+
+impl Collector for XskCollector {
+    fn new() -> Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn can_run(&mut self, args: &Collect) -> Result<()> {
+        // AF_XDP packets are not skbs, their events can't be filtered and
+        // would be reported for every packet of every AF_XDP socket.
+        if !args.collectors.iter().any(|c| c == "xsk") {
+            bail!("The xsk collector must be explicitly enabled");
+        }
+
+        if let Err(e) = Symbol::from_name(XSK_RX_TARGET.targets[0]) {
+            bail!("Could not resolve AF_XDP kernel symbols (is CONFIG_XDP_SOCKETS set?): {e}");
+        }
+        Ok(())
+    }
+
+    fn init(
+        &mut self,
+        _: &Collect,
+        probes: &mut ProbeBuilderManager,
+        _: Arc<RetisEventsFactory>,
+        _: &mut SectionFactories,
+    ) -> Result<()> {
+        for target in XSK_TARGETS.iter() {
+            if let Some(sym) = target
+                .targets
+                .iter()
+                .find_map(|t| Symbol::from_name(t).ok())
+            {
+                self.add_probe(probes, sym, target)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl XskCollector {
+    /// Create the config map of a probe, and register it with our hook.
+    fn add_probe(
+        &mut self,
+        probes: &mut ProbeBuilderManager,
+        sym: Symbol,
+        target: &XskTarget,
+    ) -> Result<()> {
+        let mut cfg = xsk_config {
+            op: target.op,
+            mode: target.mode,
+            descs_ret: target.descs_ret as u8,
+            ..Default::default()
+        };
+        if let Some(offset) = sym.parameter_offset("struct xdp_sock *")? {
+            cfg.offsets.xdp_sock = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct sock *")? {
+            cfg.offsets.sock = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct xdp_buff *")? {
+            cfg.offsets.xdp_buff = offset as i8;
+        }
+        if let Some(offset) = sym.parameter_offset("struct xsk_buff_pool *")? {
+            cfg.offsets.xsk_buff_pool = offset as i8;
+        }
+        if target.op == XSK_OP_TX_COMPLETION {
+            if let Some(offset) = sym.parameter_offset("u32")? {
+                cfg.offsets.descs = offset as i8;
+            }
+        }
+
+        let probe = match target.kretprobe {
+            true => Probe::kretprobe(sym)?,
+            false => Probe::kprobe(sym)?,
+        };
+        // Please keep in sync with its BPF counterpart in bpf/xsk_hook.bpf.c
+        let config_map = register_config_probe(
+            probes,
+            probe,
+            Hook::from(xsk_hook::DATA),
+            "xsk_config_map",
+            &cfg,
+        )?;
+        self.config_maps.push(config_map);
+        Ok(())
+    }
+}
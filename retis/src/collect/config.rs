@@ -0,0 +1,203 @@
+//! # Collect configuration
+//!
+//! Configuration files declaring a collection (collectors, probes, filters,
+//! outputs, limits, etc.) so it can be reproduced. Their content is expanded
+//! into `retis collect` arguments, the ones set on the command line taking
+//! precedence.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::Command;
+use log::{debug, info, warn};
+use serde::Deserialize;
+
+use crate::profiles::{
+    args_to_cli,
+    version::{ApiVersion, ApiVersionSupport},
+    ArgValue, Profile,
+};
+
+/// Collect configuration file.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub(crate) struct CollectConfig {
+    /// Version of the Profile API that is being used.
+    pub(crate) version: ApiVersion,
+    /// Information about the configuration in human readable format.
+    pub(crate) about: Option<String>,
+    /// Profiles to apply, by name or path.
+    #[serde(default = "Vec::new")]
+    pub(crate) profiles: Vec<PathBuf>,
+    /// Collect arguments, using the same format as profiles.
+    #[serde(default = "BTreeMap::new")]
+    pub(crate) args: BTreeMap<String, ArgValue>,
+}
+
+impl CollectConfig {
+    /// Load a configuration from a file.
+    pub(crate) fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Could not read configuration {}: {e}", path.display()))?;
+        let config = Self::from_str(&contents)
+            .map_err(|e| anyhow!("Invalid configuration {}: {e}", path.display()))?;
+
+        match &config.about {
+            Some(about) => info!("Using configuration {}: {about}", path.display()),
+            None => info!("Using configuration {}", path.display()),
+        }
+        Ok(config)
+    }
+
+    /// Load a configuration from a string.
+    pub(crate) fn from_str(contents: &str) -> Result<Self> {
+        let config: Self = serde_yaml::from_str(contents)?;
+
+        let retis_version = Profile::api_version()?;
+        match retis_version.supports(&config.version)? {
+            ApiVersionSupport::Full => (),
+            ApiVersionSupport::Partial => warn!(
+                "Retis' profile engine ({retis_version}) only supports configuration version ({}) \
+                partially. Some bits of configuration might not be applied.",
+                config.version
+            ),
+            ApiVersionSupport::NotSupported => bail!(
+                "Retis' profile engine ({retis_version}) does not support configuration version ({})",
+                config.version
+            ),
+        }
+        Ok(config)
+    }
+
+    /// Generate the collect cli arguments from the configuration. Arguments
+    /// whose id is in `set` are left out, as well as arguments of the profiles
+    /// also set by the configuration itself.
+    pub(crate) fn cli_args(
+        &self,
+        command: &Command,
+        mut set: HashSet<String>,
+        custom_dir: Option<&PathBuf>,
+    ) -> Result<Vec<OsString>> {
+        let profiles = self
+            .profiles
+            .iter()
+            .map(|name| Profile::lookup(name, custom_dir))
+            .collect::<Result<Vec<_>>>()?;
+        let mut profiles_args = Vec::new();
+        for profile in profiles.iter() {
+            if let Some(args) = profile.subcommand_args("collect")? {
+                profiles_args.push(args);
+            }
+        }
+
+        // Sources are ordered by (descending) priority. Arguments from
+        // multiple profiles are combined, as when using `retis -p`.
+        let mut result = Vec::new();
+        for source in [vec![&self.args], profiles_args] {
+            let mut ids = HashSet::new();
+            let mut args = Vec::new();
+
+            for (key, value) in source.iter().flat_map(|args| args.iter()) {
+                let id = arg_id(command, key)?;
+                if set.contains(&id) {
+                    debug!("Configuration argument {key} overridden");
+                    continue;
+                }
+
+                ids.insert(id);
+                args.push((key, value));
+            }
+
+            result.append(&mut args_to_cli(args.into_iter()));
+            set.extend(ids);
+        }
+
+        Ok(result)
+    }
+}
+
+/// Find the id of a collect argument given its long name or alias; '_' and '-'
+/// can be used interchangeably.
+fn arg_id(command: &Command, key: &str) -> Result<String> {
+    let long = key.replace('_', "-");
+    command
+        .get_arguments()
+        .find(|arg| {
+            arg.get_long() == Some(long.as_str())
+                || arg
+                    .get_all_aliases()
+                    .is_some_and(|aliases| aliases.contains(&long.as_str()))
+        })
+        .map(|arg| arg.get_id().to_string())
+        .ok_or_else(|| anyhow!("Unknown collect argument '{key}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cli::SubCommand, collect::cli::Collect};
+
+    fn args(config: &str, set: &[&str]) -> Result<Vec<String>> {
+        let command = Collect::new()?.command()?;
+        let set = set.iter().map(|id| id.to_string()).collect();
+
+        Ok(CollectConfig::from_str(config)?
+            .cli_args(&command, set, None)?
+            .iter()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    #[test]
+    fn config_args() {
+        let config = r#"
+version: 1.0
+about: Test configuration
+args:
+  modules: skb,skb-drop
+  probe:
+    - tp:net:netif_receive_skb
+    - kprobe:ip_rcv
+  filter-packet: tcp port 443
+  out: retis.data
+  until: duration 5m
+  allow_system_changes: ~
+"#;
+
+        assert_eq!(
+            args(config, &[]).unwrap(),
+            vec![
+                "--allow-system-changes",
+                "--filter-packet",
+                "tcp port 443",
+                "--modules",
+                "skb,skb-drop",
+                "--out",
+                "retis.data",
+                "--probe",
+                "tp:net:netif_receive_skb",
+                "--probe",
+                "kprobe:ip_rcv",
+                "--until",
+                "duration 5m",
+            ]
+        );
+
+        // Arguments set on the command line take precedence.
+        assert_eq!(
+            args(config, &["collectors", "probe", "filter-packet", "out"]).unwrap(),
+            vec!["--allow-system-changes", "--until", "duration 5m"]
+        );
+
+        assert!(args("version: 1.0\nargs:\n  foo: bar\n", &[]).is_err());
+        assert!(args("version: 1.0\narg:\n  out: retis.data\n", &[]).is_err());
+        assert!(args("version: 9.0\n", &[]).is_err());
+        assert!(args("args:\n  out: retis.data\n", &[]).is_err());
+        assert!(args("version: 1.0\n", &[]).unwrap().is_empty());
+    }
+}
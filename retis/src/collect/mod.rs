@@ -22,3 +22,5 @@ pub(crate) use collect::*;
 
 pub(crate) mod cli;
 pub(crate) mod collector;
+pub(crate) mod config;
+pub(crate) mod remote;
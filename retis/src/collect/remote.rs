@@ -0,0 +1,496 @@
+//! # Remote collection
+//!
+//! Runs a collection on remote hosts through the agents running there (see
+//! `retis agent`) and merges the events they stream back, converting their
+//! timestamps to the local clock. This allows following packets across
+//! multiple nodes.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpStream},
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use log::{info, warn};
+
+use super::cli::Collect;
+use crate::{
+    agent::{
+        agent_addr, check_remote_args, read_token, split_local_args, wall_clock, AgentRequest,
+    },
+    cli::{CliDisplayFormat, CliOutFormat, MainConfig},
+    events::{
+        file::{rotate::*, FileEventsFactory},
+        helpers::time::*,
+        sink::*,
+        *,
+    },
+    helpers::{file_rotate::*, signals::Running, stream::*},
+    process::display::*,
+};
+
+/// Time events are kept before being written, waiting for older events of
+/// other hosts to be received.
+const REORDER_WINDOW: Duration = Duration::from_secs(1);
+/// Interval at which events ready to be written are checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Run a collection on remote hosts and merge their events.
+pub(crate) fn remote_collect(collect: &Collect, main_config: &MainConfig) -> Result<()> {
+    // Forward the collect arguments, without the client-side ones.
+    let args = match main_config.args.iter().position(|arg| arg == "collect") {
+        Some(pos) => split_local_args(&main_config.args[pos + 1..]).0,
+        None => bail!("Could not find the collect arguments"),
+    };
+    check_remote_args(&args)?;
+    let token = match &collect.remote_token_file {
+        Some(path) => read_token(path)?,
+        None => bail!("A token file is required to connect to agents"),
+    };
+    let monotonic_offset = monotonic_clock_offset()?;
+
+    // Send the requests first, for the collections to start in parallel.
+    let mut hosts = collect
+        .remote
+        .iter()
+        .map(|host| RemoteHost::connect(host, &token, &args))
+        .collect::<Result<Vec<_>>>()?;
+    let mut factories = Vec::new();
+    for host in hosts.iter_mut() {
+        factories.push(host.wait_start(monotonic_offset)?);
+        info!("{}: collection started", host.name);
+    }
+
+    let collection = factories
+        .first()
+        .and_then(|f| f.startup())
+        .and_then(|s| s.collection.clone());
+    let mut sinks = sinks(collect, main_config, monotonic_offset, collection)?;
+
+    let (tx, rx) = mpsc::channel();
+    for (i, mut factory) in factories.into_iter().enumerate() {
+        let (name, adjust, tx) = (hosts[i].name.clone(), hosts[i].adjust, tx.clone());
+        thread::spawn(move || {
+            let res = forward(&mut factory, i, &name, adjust, &tx);
+            let _ = tx.send(HostMsg::End(i, res));
+        });
+    }
+    drop(tx);
+
+    let run = Running::new()?;
+    let mut merger = Merger::new(hosts.len());
+    let mut count = 0;
+    while run.running() && !merger.done() {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(HostMsg::Event(i, event)) => merger.push(i, event, Instant::now()),
+            Ok(HostMsg::End(i, res)) => {
+                match res {
+                    Ok(()) => info!("{}: collection ended", hosts[i].name),
+                    Err(e) => warn!("{}: {e}", hosts[i].name),
+                }
+                merger.end(i);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => (),
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        for event in merger.pop_ready(Instant::now()) {
+            sinks.process_one(event)?;
+            count += 1;
+        }
+    }
+
+    // Disconnecting stops the remote collections.
+    hosts.iter().for_each(|host| host.stop());
+    for event in merger.drain() {
+        sinks.process_one(event)?;
+        count += 1;
+    }
+
+    sinks.finish()?;
+    info!("{count} event(s) processed");
+    Ok(())
+}
+
+/// Forward the events of a host to the merging loop, converting their
+/// timestamps to the local clock and tagging them with the host name.
+fn forward(
+    factory: &mut FileEventsFactory,
+    host: usize,
+    name: &str,
+    adjust: i64,
+    tx: &mpsc::Sender<HostMsg>,
+) -> Result<()> {
+    while let Some(mut event) = factory.next_event()? {
+        // Only the local startup event is kept.
+        if event.startup.is_some() {
+            continue;
+        }
+        if let Some(common) = event.common.as_mut() {
+            common.timestamp = common.timestamp.saturating_add_signed(adjust);
+            common.host = Some(name.to_string());
+        }
+        if tx.send(HostMsg::Event(host, event)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Build the local sinks, following the output arguments of the collect
+/// command.
+fn sinks(
+    collect: &Collect,
+    main_config: &MainConfig,
+    monotonic_offset: TimeSpec,
+    collection: Option<CollectionInfo>,
+) -> Result<MultiSink> {
+    let mut sinks = MultiSink::new();
+
+    if collect.out.is_empty() || collect.print {
+        let format = DisplayFormat::new()
+            .multiline(collect.format == CliDisplayFormat::MultiLine)
+            .time_format(if collect.utc {
+                TimeFormat::UtcDate
+            } else {
+                TimeFormat::MonotonicTimestamp
+            })
+            .monotonic_offset(monotonic_offset)
+            .print_ll(collect.print_ll)
+            .colors(main_config.colors);
+
+        sinks.add(
+            "stdout",
+            Box::new(PrintEvent::new(
+                Box::new(io::stdout()),
+                PrintEventFormat::Text(format),
+            )),
+        )?;
+    }
+
+    let mut out = None;
+    for target in collect.out.iter() {
+        match StreamTarget::from_url(target)? {
+            Some(stream) => sinks.add(
+                target,
+                Box::new(PrintEvent::new(
+                    Box::new(StreamWriter::new(
+                        stream,
+                        &main_config.cmdline,
+                        monotonic_offset,
+                        collection.clone(),
+                    )?),
+                    PrintEventFormat::Json,
+                )),
+            )?,
+            None if out.is_some() => bail!("Only a single output file can be used"),
+            None => out = Some(PathBuf::from(target)),
+        }
+    }
+
+    if let Some(out) = out.as_ref() {
+        let (policy, keep) = match &collect.out_rotate {
+            Some(s) => {
+                let (policy, keep) = rotation_from_str(s)?;
+                (Some(policy), keep)
+            }
+            None => (None, None),
+        };
+        let format = match collect.out_format {
+            CliOutFormat::Json => file::EventFormat::Json,
+            CliOutFormat::Protobuf => file::EventFormat::Protobuf,
+        };
+        let writer = RotateWriter::new(
+            out,
+            policy,
+            collect.compress,
+            format,
+            &main_config.cmdline,
+            monotonic_offset,
+            collection,
+        )
+        .or_else(|e| bail!("Could not create or open '{}': {e}", out.display()))?
+        .keep(keep)
+        .index(collect.out_index);
+
+        sinks.add(
+            &out.display().to_string(),
+            Box::new(PrintEvent::new(Box::new(writer), format.into())),
+        )?;
+    }
+
+    Ok(sinks)
+}
+
+enum HostMsg {
+    /// Event received from a host, its timestamp already converted.
+    Event(usize, Event),
+    /// The events of a host ended, e.g. its collection stopped.
+    End(usize, Result<()>),
+}
+
+/// Connection to an agent running a collection on our behalf.
+struct RemoteHost {
+    name: String,
+    stream: TcpStream,
+    /// Replies of the agent, until the collection started.
+    reader: Option<BufReader<TcpStream>>,
+    /// Local wall clock time when the request was sent, in nanoseconds.
+    sent: i64,
+    /// Value added to the host timestamps to convert them to the local
+    /// monotonic clock, in nanoseconds.
+    adjust: i64,
+}
+
+impl RemoteHost {
+    /// Connect to an agent and request a collection to be started.
+    fn connect(host: &str, token: &str, args: &[String]) -> Result<Self> {
+        let addr = agent_addr(host)?;
+        let mut stream =
+            TcpStream::connect(&addr).map_err(|e| anyhow!("Could not connect to {addr}: {e}"))?;
+
+        let sent = wall_clock()?;
+        writeln!(
+            stream,
+            "{}",
+            serde_json::to_string(&AgentRequest {
+                token: token.to_string(),
+                args: args.to_vec(),
+            })?
+        )?;
+
+        Ok(Self {
+            name: host.to_string(),
+            reader: Some(BufReader::new(stream.try_clone()?)),
+            stream,
+            sent,
+            adjust: 0,
+        })
+    }
+
+    /// Wait for the collection to start and return a factory reading its
+    /// events.
+    fn wait_start(&mut self, monotonic_offset: TimeSpec) -> Result<FileEventsFactory> {
+        // The agent first reports its wall clock, estimate the difference
+        // with ours assuming symmetric network delays.
+        let remote = self
+            .read_line()?
+            .strip_prefix("clock ")
+            .and_then(|clock| clock.parse::<i64>().ok())
+            .ok_or_else(|| anyhow!("{}: unexpected reply from agent", self.name))?;
+        let skew = remote - (self.sent + wall_clock()?) / 2;
+
+        match self.read_line()?.as_str() {
+            "ok" => (),
+            reply => match reply.strip_prefix("error ") {
+                Some(e) => bail!("{}: {e}", self.name),
+                None => bail!("{}: unexpected reply from agent", self.name),
+            },
+        }
+
+        // Events might already be buffered, keep reading from the same
+        // reader.
+        let reader = self
+            .reader
+            .take()
+            .ok_or_else(|| anyhow!("{}: collection already started", self.name))?;
+        let factory = FileEventsFactory::from_stream(reader)?;
+        let offset = factory
+            .startup()
+            .map(|s| i64::from(s.clock_monotonic_offset))
+            .ok_or_else(|| anyhow!("{}: no startup event received", self.name))?;
+        self.adjust = offset - i64::from(monotonic_offset) - skew;
+
+        Ok(factory)
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let reader = self
+            .reader
+            .as_mut()
+            .ok_or_else(|| anyhow!("{}: collection already started", self.name))?;
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("{}: connection closed by agent", self.name);
+        }
+        Ok(line.trim_end().to_string())
+    }
+
+    /// Disconnect from the agent, stopping the collection.
+    fn stop(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Event waiting to be written.
+struct Pending {
+    timestamp: u64,
+    /// Reception order, to keep events with the same timestamp ordered.
+    seq: u64,
+    received: Instant,
+    event: Event,
+}
+
+impl Pending {
+    fn key(&self) -> (u64, u64) {
+        (self.timestamp, self.seq)
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Merges the events of multiple hosts, each one being ordered, into a single
+/// ordered stream. Events are released once all the hosts still running sent
+/// a newer one, or after `REORDER_WINDOW` so a quiet host does not hold the
+/// others back.
+struct Merger {
+    pending: BinaryHeap<Reverse<Pending>>,
+    /// Timestamp of the last event of each host, None once the host ended.
+    last: Vec<Option<u64>>,
+    seq: u64,
+}
+
+impl Merger {
+    fn new(hosts: usize) -> Self {
+        Self {
+            pending: BinaryHeap::new(),
+            last: vec![Some(0); hosts],
+            seq: 0,
+        }
+    }
+
+    fn push(&mut self, host: usize, event: Event, now: Instant) {
+        let timestamp = event
+            .common
+            .as_ref()
+            .map(|c| c.timestamp)
+            .unwrap_or_default();
+        if let Some(last) = self.last[host].as_mut() {
+            *last = (*last).max(timestamp);
+        }
+
+        self.seq += 1;
+        self.pending.push(Reverse(Pending {
+            timestamp,
+            seq: self.seq,
+            received: now,
+            event,
+        }));
+    }
+
+    fn end(&mut self, host: usize) {
+        self.last[host] = None;
+    }
+
+    /// Are all the hosts done and all their events released?
+    fn done(&self) -> bool {
+        self.pending.is_empty() && self.last.iter().all(Option::is_none)
+    }
+
+    /// Release the events which can't be preceded by events still to come.
+    fn pop_ready(&mut self, now: Instant) -> Vec<Event> {
+        let watermark = self.last.iter().flatten().min().copied();
+
+        let mut events = Vec::new();
+        while let Some(Reverse(next)) = self.pending.peek() {
+            let ready = watermark.is_none_or(|w| next.timestamp <= w)
+                || now.duration_since(next.received) >= REORDER_WINDOW;
+            if !ready {
+                break;
+            }
+            if let Some(Reverse(next)) = self.pending.pop() {
+                events.push(next.event);
+            }
+        }
+        events
+    }
+
+    /// Release all the remaining events.
+    fn drain(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        while let Some(Reverse(next)) = self.pending.pop() {
+            events.push(next.event);
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp: u64) -> Event {
+        Event {
+            common: Some(CommonEvent {
+                timestamp,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn timestamps(events: Vec<Event>) -> Vec<u64> {
+        events
+            .iter()
+            .map(|e| e.common.as_ref().unwrap().timestamp)
+            .collect()
+    }
+
+    #[test]
+    fn merge_hosts() {
+        let now = Instant::now();
+        let mut merger = Merger::new(2);
+
+        merger.push(0, event(10), now);
+        merger.push(0, event(20), now);
+        // Nothing received from the second host yet.
+        assert!(merger.pop_ready(now).is_empty());
+
+        merger.push(1, event(5), now);
+        merger.push(1, event(15), now);
+        assert_eq!(timestamps(merger.pop_ready(now)), vec![5, 10, 15]);
+
+        // Host 1 is quiet, events are released after the reorder window.
+        merger.push(0, event(30), now);
+        assert!(merger.pop_ready(now).is_empty());
+        assert_eq!(
+            timestamps(merger.pop_ready(now + REORDER_WINDOW)),
+            vec![20, 30]
+        );
+
+        merger.push(0, event(40), now);
+        merger.push(1, event(35), now);
+        merger.end(1);
+        assert_eq!(timestamps(merger.pop_ready(now)), vec![35, 40]);
+        assert!(!merger.done());
+
+        merger.push(0, event(50), now);
+        merger.end(0);
+        assert_eq!(timestamps(merger.drain()), vec![50]);
+        assert!(merger.done());
+    }
+}
@@ -7,4 +7,7 @@
 /* bpf specific definitions (BPF_A, BPF_{TAX,TXA}). */
 #include "linux/filter.h"
 
+/* perf_event_open(2) definitions, for sampling probes. */
+#include "linux/perf_event.h"
+
 #endif
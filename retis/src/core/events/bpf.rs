@@ -109,19 +109,7 @@ pub(crate) struct BpfEventsFactory {
 #[cfg(not(test))]
 impl BpfEventsFactory {
     pub(crate) fn new() -> Result<BpfEventsFactory> {
-        let opts = libbpf_sys::bpf_map_create_opts {
-            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
-            ..Default::default()
-        };
-        let map = libbpf_rs::MapHandle::create(
-            libbpf_rs::MapType::RingBuf,
-            Some("events_map"),
-            0,
-            0,
-            mem::size_of::<RawEvent>() as u32 * EVENTS_MAX,
-            &opts,
-        )
-        .or_else(|e| bail!("Failed to create events map: {}", e))?;
+        let map = Self::events_map(mem::size_of::<RawEvent>() as u32 * EVENTS_MAX)?;
 
         let opts = libbpf_sys::bpf_map_create_opts {
             sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
@@ -149,6 +137,36 @@ impl BpfEventsFactory {
         })
     }
 
+    fn events_map(size: u32) -> Result<libbpf_rs::MapHandle> {
+        let opts = libbpf_sys::bpf_map_create_opts {
+            sz: mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+            ..Default::default()
+        };
+        libbpf_rs::MapHandle::create(
+            libbpf_rs::MapType::RingBuf,
+            Some("events_map"),
+            0,
+            0,
+            size,
+            &opts,
+        )
+        .or_else(|e| bail!("Failed to create events map: {}", e))
+    }
+
+    /// Resize the events buffer, shared by all probes. The size is rounded up
+    /// to a power of two (and to at least a page), as required by BPF ring
+    /// buffers. Must be called before the events map is reused by probes.
+    pub(crate) fn set_buffer_size(&mut self, size: usize) -> Result<usize> {
+        let page_size = nix::unistd::sysconf(nix::unistd::SysconfVar::PAGE_SIZE)?
+            .ok_or_else(|| anyhow!("Could not get the page size"))?;
+        let size = size.max(page_size as usize).next_power_of_two();
+
+        self.map = Self::events_map(
+            u32::try_from(size).map_err(|_| anyhow!("Events buffer too large ({size})"))?,
+        )?;
+        Ok(size)
+    }
+
     /// Configure logger timestamp formatting
     pub(crate) fn config_logger(&mut self, format: TimeFormat, monotonic_offset: Option<TimeSpec>) {
         self.time_format = format;
@@ -495,6 +513,9 @@ impl BpfEventsFactory {
         _monotonic_offset: Option<TimeSpec>,
     ) {
     }
+    pub(crate) fn set_buffer_size(&mut self, size: usize) -> Result<usize> {
+        Ok(size)
+    }
     pub(crate) fn map_fd(&self) -> i32 {
         0
     }
@@ -575,8 +596,28 @@ pub(crate) enum FactoryId {
     Ct = 9,
     Dev = 10,
     Ns = 11,
+    Xdp = 12,
+    Tc = 13,
+    Nf = 14,
+    Xfrm = 15,
+    Tunnel = 16,
+    Tcp = 17,
+    GroGso = 18,
+    Bridge = 19,
+    Lag = 20,
+    Wireguard = 21,
+    IcmpError = 22,
+    NsLifecycle = 23,
+    Sock = 24,
+    Offload = 25,
+    Mem = 26,
+    Xsk = 27,
+    Mptcp = 28,
+    Route = 29,
+    Neigh = 30,
+    External = 31,
     // TODO: use std::mem::variant_count once in stable.
-    _MAX = 12,
+    _MAX = 32,
 }
 
 impl FactoryId {
@@ -595,6 +636,26 @@ impl FactoryId {
             9 => Ct,
             10 => Dev,
             11 => Ns,
+            12 => Xdp,
+            13 => Tc,
+            14 => Nf,
+            15 => Xfrm,
+            16 => Tunnel,
+            17 => Tcp,
+            18 => GroGso,
+            19 => Bridge,
+            20 => Lag,
+            21 => Wireguard,
+            22 => IcmpError,
+            23 => NsLifecycle,
+            24 => Sock,
+            25 => Offload,
+            26 => Mem,
+            27 => Xsk,
+            28 => Mptcp,
+            29 => Route,
+            30 => Neigh,
+            31 => External,
             x => bail!("Can't construct a FactoryId from {}", x),
         })
     }
@@ -29,6 +29,26 @@ enum retis_event_owners {
 	COLLECTOR_CT = 9,
 	COLLECTOR_DEV = 10,
 	COLLECTOR_NS = 11,
+	COLLECTOR_XDP = 12,
+	COLLECTOR_TC = 13,
+	COLLECTOR_NF = 14,
+	COLLECTOR_XFRM = 15,
+	COLLECTOR_TUNNEL = 16,
+	COLLECTOR_TCP = 17,
+	COLLECTOR_GRO_GSO = 18,
+	COLLECTOR_BRIDGE = 19,
+	COLLECTOR_LAG = 20,
+	COLLECTOR_WIREGUARD = 21,
+	COLLECTOR_ICMP_ERROR = 22,
+	COLLECTOR_NS_LIFECYCLE = 23,
+	COLLECTOR_SOCK = 24,
+	COLLECTOR_OFFLOAD = 25,
+	COLLECTOR_MEM = 26,
+	COLLECTOR_XSK = 27,
+	COLLECTOR_MPTCP = 28,
+	COLLECTOR_ROUTE = 29,
+	COLLECTOR_NEIGH = 30,
+	COLLECTOR_EXTERNAL = 31,
 };
 
 struct retis_raw_event {
@@ -67,6 +87,23 @@ static __always_inline struct retis_raw_event *get_event()
 	return event;
 }
 
+/* Check the events buffer is not filled above a given threshold, in percent,
+ * to stop generating events while the consumer is lagging behind. A threshold
+ * of 0 disables throttling.
+ */
+static __always_inline bool events_throttle_pass(u32 threshold)
+{
+	u64 avail, size;
+
+	if (!threshold)
+		return true;
+
+	avail = bpf_ringbuf_query(&events_map, BPF_RB_AVAIL_DATA);
+	size = bpf_ringbuf_query(&events_map, BPF_RB_RING_SIZE);
+
+	return avail * 100 < size * threshold;
+}
+
 static __always_inline void discard_event(struct retis_raw_event *event)
 {
 	bpf_ringbuf_discard(event, 0);
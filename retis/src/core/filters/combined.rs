@@ -0,0 +1,210 @@
+//! # Combined filters
+//!
+//! Front-end allowing to express a single filter made of a packet and a meta
+//! filter, combined with a boolean operator. The expression follows:
+//!
+//! ```text
+//! (packet: <pcap-filter>) [and|or] (meta: <meta filter>)
+//! ```
+//!
+//! Each term is optional but at most one packet and one meta term can be
+//! given. When a single term is used, it behaves as if the corresponding
+//! standalone filter was used.
+
+use anyhow::{bail, Result};
+
+/// How the packet and meta filters results are combined. Please keep in sync
+/// with its BPF counterpart in core/probe/bpf/include/common_defs.h
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum FiltersMode {
+    /// Both the packet and meta filters must match.
+    #[default]
+    And = 0,
+    /// Either the packet or meta filter must match.
+    Or = 1,
+}
+
+/// Packet and meta filter expressions, along with the way they are combined.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CombinedFilter {
+    /// Packet filter expression, in the pcap-filter(7) syntax.
+    pub(crate) packet: Option<String>,
+    /// Meta filter expression.
+    pub(crate) meta: Option<String>,
+    /// How the above filters results are combined.
+    pub(crate) mode: FiltersMode,
+}
+
+impl CombinedFilter {
+    /// Parse a combined filter expression.
+    pub(crate) fn from_string(expr: &str) -> Result<Self> {
+        let mut filter = CombinedFilter::default();
+
+        let (rest, first) = Self::parse_term(expr)?;
+        filter.set_term(first)?;
+
+        let rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(filter);
+        }
+
+        let (mode, rest) = if let Some(rest) = Self::strip_op(rest, &["and", "&&"]) {
+            (FiltersMode::And, rest)
+        } else if let Some(rest) = Self::strip_op(rest, &["or", "||"]) {
+            (FiltersMode::Or, rest)
+        } else {
+            bail!("Expected 'and' or 'or' operator, found '{rest}'");
+        };
+        filter.mode = mode;
+
+        let (rest, second) = Self::parse_term(rest)?;
+        filter.set_term(second)?;
+
+        if !rest.trim().is_empty() {
+            bail!("Unexpected trailing data '{}'", rest.trim());
+        }
+
+        Ok(filter)
+    }
+
+    fn set_term(&mut self, term: Term) -> Result<()> {
+        let (slot, name) = match term.kind {
+            TermKind::Packet => (&mut self.packet, "packet"),
+            TermKind::Meta => (&mut self.meta, "meta"),
+        };
+
+        if slot.is_some() {
+            bail!("Only one {name} term can be used");
+        }
+        *slot = Some(term.expr);
+        Ok(())
+    }
+
+    fn strip_op<'a>(input: &'a str, ops: &[&str]) -> Option<&'a str> {
+        ops.iter().find_map(|op| {
+            let rest = input.strip_prefix(op)?;
+            // Keyword operators must be followed by a separator.
+            match rest.chars().next() {
+                Some(c) if c.is_whitespace() || c == '(' => Some(rest),
+                Some(_) if op.starts_with(|c: char| !c.is_alphabetic()) => Some(rest),
+                _ => None,
+            }
+        })
+    }
+
+    /// Parse a single "(kind: expr)" term, returning the remaining input.
+    fn parse_term(input: &str) -> Result<(&str, Term)> {
+        let input = input.trim_start();
+        let Some(inner) = input.strip_prefix('(') else {
+            bail!("Expected '(' at the start of a filter term, found '{input}'");
+        };
+
+        let Some((kind, _)) = inner.split_once(':') else {
+            bail!("Filter term is missing its kind (packet: or meta:)");
+        };
+        let kind = match kind.trim() {
+            "packet" => TermKind::Packet,
+            "meta" => TermKind::Meta,
+            x => bail!("Unknown filter term kind '{x}' (expected packet or meta)"),
+        };
+
+        // Find the closing parenthesis, taking into account nested ones
+        // and quoted strings (used in meta filters).
+        let mut depth = 1;
+        let mut quoted = false;
+        let mut end = None;
+        for (i, c) in inner.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                '(' if !quoted => depth += 1,
+                ')' if !quoted => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = Some(i);
+                        break;
+                    }
+                }
+                _ => (),
+            }
+        }
+        let Some(end) = end else {
+            bail!("Unbalanced parentheses in filter term");
+        };
+
+        // Safe as we know the ':' is part of the term.
+        let (_, expr) = inner[..end].split_once(':').unwrap();
+        let expr = expr.trim();
+        if expr.is_empty() {
+            bail!("Empty filter expression in term");
+        }
+
+        Ok((
+            &inner[end + 1..],
+            Term {
+                kind,
+                expr: expr.to_string(),
+            },
+        ))
+    }
+}
+
+enum TermKind {
+    Packet,
+    Meta,
+}
+
+struct Term {
+    kind: TermKind,
+    expr: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_term() {
+        let f = CombinedFilter::from_string("(packet: tcp port 80)").unwrap();
+        assert_eq!(f.packet.as_deref(), Some("tcp port 80"));
+        assert!(f.meta.is_none());
+        assert_eq!(f.mode, FiltersMode::And);
+
+        let f = CombinedFilter::from_string("(meta: sk_buff.mark == 5)").unwrap();
+        assert!(f.packet.is_none());
+        assert_eq!(f.meta.as_deref(), Some("sk_buff.mark == 5"));
+    }
+
+    #[test]
+    fn combined_terms() {
+        let f = CombinedFilter::from_string("(packet: tcp port 80) or (meta: sk_buff.mark == 5)")
+            .unwrap();
+        assert_eq!(f.packet.as_deref(), Some("tcp port 80"));
+        assert_eq!(f.meta.as_deref(), Some("sk_buff.mark == 5"));
+        assert_eq!(f.mode, FiltersMode::Or);
+
+        let f = CombinedFilter::from_string(
+            "(meta: (sk_buff.mark == 5 || sk_buff.dev.name == \"eth(0)\"))&&(packet: (arp or ip))",
+        )
+        .unwrap();
+        assert_eq!(f.packet.as_deref(), Some("(arp or ip)"));
+        assert_eq!(
+            f.meta.as_deref(),
+            Some("(sk_buff.mark == 5 || sk_buff.dev.name == \"eth(0)\")")
+        );
+        assert_eq!(f.mode, FiltersMode::And);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(CombinedFilter::from_string("").is_err());
+        assert!(CombinedFilter::from_string("tcp port 80").is_err());
+        assert!(CombinedFilter::from_string("(packet: tcp port 80").is_err());
+        assert!(CombinedFilter::from_string("(packet:)").is_err());
+        assert!(CombinedFilter::from_string("(foo: bar)").is_err());
+        assert!(CombinedFilter::from_string("(packet: tcp) or (packet: udp)").is_err());
+        assert!(CombinedFilter::from_string("(packet: tcp) xor (meta: sk_buff.mark)").is_err());
+        assert!(CombinedFilter::from_string("(packet: tcp) orr (meta: sk_buff.mark)").is_err());
+        assert!(CombinedFilter::from_string("(packet: tcp) or (meta: sk_buff.mark) x").is_err());
+    }
+}
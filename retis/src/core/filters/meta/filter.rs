@@ -22,6 +22,13 @@ use crate::core::{
     inspect::{inspector, BtfInfo},
 };
 
+/// Aliases to commonly used expressions, along with their expansion.
+const META_ALIASES: &[(&str, &str)] = &[
+    ("ct.mark", "sk_buff._nfct:~0x7:nf_conn.mark"),
+    ("ct.status", "sk_buff._nfct:~0x7:nf_conn.status"),
+    ("ct.zone", "sk_buff._nfct:~0x7:nf_conn.zone.id"),
+];
+
 const PTR_BIT: u8 = 1 << 6;
 const SIGN_BIT: u8 = 1 << 7;
 
@@ -292,13 +299,37 @@ impl ParserMeta {
     fn parse_lhs(pair: pest::iterators::Pair<Rule>) -> Result<Lhs> {
         let mut lhs = Vec::new();
         for inner in pair.into_inner() {
-            if inner.as_rule() == Rule::ident {
-                lhs.push(Self::parse_ident(inner.clone())?);
+            match inner.as_rule() {
+                Rule::ident => lhs.push(Self::parse_ident(inner.clone())?),
+                Rule::alias => return Self::parse_alias(inner.as_str()),
+                _ => (),
             }
         }
         Ok(lhs)
     }
 
+    fn parse_alias(alias: &str) -> Result<Lhs> {
+        let (_, expansion) = META_ALIASES
+            .iter()
+            .find(|(name, _)| *name == alias)
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown alias '{alias}' (supported: {})",
+                    META_ALIASES
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+
+        Self::parse_lhs(
+            ParserMeta::parse(Rule::lhs, expansion)?
+                .next()
+                .ok_or_else(|| anyhow!("failed to parse alias '{alias}'"))?,
+        )
+    }
+
     fn parse_rhs(pair: pest::iterators::Pair<Rule>) -> Result<Rhs> {
         let pair = pair
             .into_inner()
@@ -1134,7 +1165,17 @@ impl<'a> MetaExpr<'a> {
                     let mut types = self
                         .btf_info
                         .resolve_types_by_name(tgt)
-                        .map_err(|e| anyhow!("unable to resolve data type: {e}"))?;
+                        .map_err(|e| {
+                            // The type might be defined in a module lacking
+                            // BTF information.
+                            let missing = inspector()
+                                .map(|i| i.kernel.modules_without_btf().join(", "))
+                                .unwrap_or_default();
+                            match missing.is_empty() {
+                                true => anyhow!("unable to resolve data type: {e}"),
+                                false => anyhow!("unable to resolve data type: {e} (no BTF information is available for modules: {missing})"),
+                            }
+                        })?;
 
                     (self.btf, self.btf_type) = match types.iter_mut().find(|(_, t)| {
                         matches!(t, Type::Union(_))
@@ -1615,6 +1656,15 @@ mod tests {
         assert!(FilterMeta::from_string("sk_buff._nfct:~0x0:nf_conn.mark".to_string()).is_ok())
     }
 
+    #[test]
+    fn meta_filter_alias() {
+        assert!(FilterMeta::from_string("ct.mark == 1".to_string()).is_ok());
+        assert!(FilterMeta::from_string("ct.status:0x8".to_string()).is_ok());
+        // Unknown aliases.
+        assert!(FilterMeta::from_string("ct.foo == 1".to_string()).is_err());
+        assert!(FilterMeta::from_string("ct == 1".to_string()).is_err());
+    }
+
     // Only validates for what type of targets lhs-only expressions
     // are allowed. The offset extraction is not required as it is
     // already performed by previous tests.
@@ -1713,6 +1763,8 @@ mod tests {
     #[test_case("sk_buff.vlan_tci == 1 or sk_buff.dev.name == 'verylongtruncatedname'" => false; "negative two fields false or false")]
     #[test_case("sk_buff._nfct:0x7 == 0x2 and sk_buff._nfct:~0x7:nf_conn.mark > 2" => true; "two fields with cast and mask+cast (true and true)")]
     #[test_case("sk_buff._nfct:0x7 == 0x2 and sk_buff._nfct:~0x7:nf_conn.mark != 3" => false; "negative two fields with cast and mask+cast (true and true)")]
+    #[test_case("sk_buff._nfct:0x7 == 0x2 and ct.mark == 3" => true; "field and alias (true and true)")]
+    #[test_case("ct.mark != 3" => false; "negative alias")]
     #[test_case("sk_buff.vlan_tci == 1 and sk_buff.dev.name == 'foo' or sk_buff.dev.name == 'verylongtruncat'" => true; "three field default precedence (false and false) or true")]
     #[test_case("sk_buff.vlan_tci == 1 and (sk_buff.dev.name == 'foo' or sk_buff.dev.name == 'verylongtruncat')" => false; "negative three field false and (false or true)")]
     fn meta_filter_runtime(expr: &'static str) -> bool {
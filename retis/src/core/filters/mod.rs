@@ -2,8 +2,10 @@
 pub(crate) mod filters;
 pub(crate) use filters::*;
 
+pub(crate) mod combined;
 pub(crate) mod meta;
 pub(crate) mod packets;
+pub(crate) mod stats;
 
 #[cfg(test)]
 pub(in crate::core::filters) use filters::test_helpers::*;
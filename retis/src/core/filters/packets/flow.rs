@@ -0,0 +1,338 @@
+//! # Flow filter
+//!
+//! Front-end turning a single flow specification into a packet filter
+//! matching both of its directions. The specification follows:
+//!
+//! ```text
+//! <addr>[:<port>] <-> <addr>[:<port>][/<proto>]
+//! ```
+//!
+//! IPv6 addresses must be enclosed in brackets when a port is given, e.g.
+//! `[2001:db8::1]:1234`.
+
+use std::{fs, net::IpAddr, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+
+/// Flow protocols supported by the flow filter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FlowProto {
+    Tcp,
+    Udp,
+    Sctp,
+    Icmp,
+}
+
+impl FlowProto {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FlowProto::Tcp => "tcp",
+            FlowProto::Udp => "udp",
+            FlowProto::Sctp => "sctp",
+            FlowProto::Icmp => "icmp",
+        }
+    }
+
+    fn has_ports(&self) -> bool {
+        !matches!(self, FlowProto::Icmp)
+    }
+}
+
+impl FromStr for FlowProto {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "tcp" => FlowProto::Tcp,
+            "udp" => FlowProto::Udp,
+            "sctp" => FlowProto::Sctp,
+            "icmp" => FlowProto::Icmp,
+            x => bail!("Unsupported flow protocol '{x}'"),
+        })
+    }
+}
+
+/// A flow endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct FlowEndpoint {
+    pub(crate) addr: IpAddr,
+    pub(crate) port: Option<u16>,
+}
+
+impl FromStr for FlowEndpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        // [addr]:port or [addr]
+        if let Some(s) = s.strip_prefix('[') {
+            let (addr, rest) = s
+                .split_once(']')
+                .ok_or_else(|| anyhow!("Missing ']' in flow endpoint"))?;
+            let port = match rest {
+                "" => None,
+                x => Some(
+                    x.strip_prefix(':')
+                        .ok_or_else(|| anyhow!("Invalid flow endpoint '{s}'"))?
+                        .parse()?,
+                ),
+            };
+            return Ok(FlowEndpoint {
+                addr: addr.parse()?,
+                port,
+            });
+        }
+
+        // Plain IPv6 address, without a port.
+        if let Ok(addr) = s.parse::<IpAddr>() {
+            return Ok(FlowEndpoint { addr, port: None });
+        }
+
+        // addr:port
+        let (addr, port) = s
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("Invalid flow endpoint '{s}'"))?;
+        Ok(FlowEndpoint {
+            addr: addr
+                .parse()
+                .map_err(|e| anyhow!("Invalid address '{addr}': {e}"))?,
+            port: Some(
+                port.parse()
+                    .map_err(|e| anyhow!("Invalid port '{port}': {e}"))?,
+            ),
+        })
+    }
+}
+
+/// A flow, as described by its two endpoints and an optional protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct FlowFilter {
+    pub(crate) a: FlowEndpoint,
+    pub(crate) b: FlowEndpoint,
+    pub(crate) proto: Option<FlowProto>,
+}
+
+impl FromStr for FlowFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (a, rest) = s.split_once("<->").ok_or_else(|| {
+            anyhow!("Flow must be of the form '<addr>[:<port>] <-> <addr>[:<port>][/<proto>]'")
+        })?;
+
+        let (b, proto) = match rest.rsplit_once('/') {
+            Some((b, proto)) => (b, Some(proto.trim().parse::<FlowProto>()?)),
+            None => (rest, None),
+        };
+
+        let flow = FlowFilter {
+            a: a.parse()?,
+            b: b.parse()?,
+            proto,
+        };
+
+        if flow.a.addr.is_ipv4() != flow.b.addr.is_ipv4() {
+            bail!("Flow endpoints must use the same address family");
+        }
+        if let Some(proto) = flow.proto {
+            if !proto.has_ports() && (flow.a.port.is_some() || flow.b.port.is_some()) {
+                bail!("Ports can't be used with {}", proto.as_str());
+            }
+        }
+
+        Ok(flow)
+    }
+}
+
+impl FlowFilter {
+    /// Generate a pcap-filter(7) expression matching the flow in both
+    /// directions.
+    pub(crate) fn to_pcap(&self) -> String {
+        self.to_pcap_with(&[])
+    }
+
+    /// Same as `to_pcap` but also matching the given (translated) flows.
+    pub(crate) fn to_pcap_with(&self, others: &[FlowFilter]) -> String {
+        let mut dirs = Vec::new();
+        std::iter::once(self).chain(others).for_each(|f| {
+            dirs.push(Self::direction(&f.a, &f.b));
+            dirs.push(Self::direction(&f.b, &f.a));
+        });
+        dirs.dedup();
+
+        let dirs = dirs.join(" or ");
+        match self.proto {
+            Some(proto) => format!("{} and ({dirs})", Self::proto_expr(proto, &self.a.addr)),
+            None => format!("({dirs})"),
+        }
+    }
+
+    fn proto_expr(proto: FlowProto, addr: &IpAddr) -> &'static str {
+        match (proto, addr) {
+            (FlowProto::Icmp, IpAddr::V6(_)) => "icmp6",
+            (proto, _) => proto.as_str(),
+        }
+    }
+
+    fn direction(src: &FlowEndpoint, dst: &FlowEndpoint) -> String {
+        let mut expr = format!("src host {} and dst host {}", src.addr, dst.addr);
+        if let Some(port) = src.port {
+            expr.push_str(&format!(" and src port {port}"));
+        }
+        if let Some(port) = dst.port {
+            expr.push_str(&format!(" and dst port {port}"));
+        }
+        format!("({expr})")
+    }
+
+    /// Look for NAT translations of this flow in the conntrack table, if
+    /// available. The returned flows are the reply direction tuples of the
+    /// translated entries.
+    pub(crate) fn nat_counterparts(&self) -> Vec<FlowFilter> {
+        match fs::read_to_string("/proc/net/nf_conntrack") {
+            Ok(table) => self.nat_counterparts_from(&table),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn nat_counterparts_from(&self, table: &str) -> Vec<FlowFilter> {
+        table
+            .lines()
+            .filter_map(Self::parse_conntrack_entry)
+            .filter(|(orig, _)| self.matches(orig))
+            .filter_map(|(orig, reply)| {
+                // No translation if the reply tuple is the reverse of the
+                // original one.
+                let reverse = FlowFilter {
+                    a: orig.b,
+                    b: orig.a,
+                    proto: orig.proto,
+                };
+                (reply != reverse).then_some(FlowFilter {
+                    proto: self.proto,
+                    ..reply
+                })
+            })
+            .collect()
+    }
+
+    /// Check if a fully defined flow matches this one, in any direction.
+    fn matches(&self, other: &FlowFilter) -> bool {
+        let ep = |x: &FlowEndpoint, y: &FlowEndpoint| {
+            x.addr == y.addr && x.port.map(|p| Some(p) == y.port).unwrap_or(true)
+        };
+
+        (self.proto.is_none() || self.proto == other.proto)
+            && ((ep(&self.a, &other.a) && ep(&self.b, &other.b))
+                || (ep(&self.a, &other.b) && ep(&self.b, &other.a)))
+    }
+
+    /// Parse a /proc/net/nf_conntrack entry into its original and reply
+    /// tuples.
+    fn parse_conntrack_entry(line: &str) -> Option<(FlowFilter, FlowFilter)> {
+        let mut fields = line.split_whitespace();
+        let proto = fields.nth(2)?.parse::<FlowProto>().ok();
+
+        let mut addrs = Vec::new();
+        let mut ports = Vec::new();
+        for field in fields {
+            match field.split_once('=') {
+                Some(("src" | "dst", addr)) => addrs.push(addr.parse::<IpAddr>().ok()?),
+                Some(("sport" | "dport", port)) => ports.push(port.parse::<u16>().ok()?),
+                _ => (),
+            }
+        }
+
+        if addrs.len() != 4 {
+            return None;
+        }
+        let port = |i: usize| ports.get(i).copied();
+        let flow = |i: usize| FlowFilter {
+            a: FlowEndpoint {
+                addr: addrs[i],
+                port: port(i),
+            },
+            b: FlowEndpoint {
+                addr: addrs[i + 1],
+                port: port(i + 1),
+            },
+            proto,
+        };
+
+        Some((flow(0), flow(2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_flow() {
+        let f = "10.0.0.1:1234 <-> 10.0.0.2:80/tcp"
+            .parse::<FlowFilter>()
+            .unwrap();
+        assert_eq!(f.a.addr, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(f.a.port, Some(1234));
+        assert_eq!(f.b.port, Some(80));
+        assert_eq!(f.proto, Some(FlowProto::Tcp));
+
+        let f = "[2001:db8::1]:1234<->2001:db8::2"
+            .parse::<FlowFilter>()
+            .unwrap();
+        assert_eq!(f.a.port, Some(1234));
+        assert_eq!(f.b.addr, "2001:db8::2".parse::<IpAddr>().unwrap());
+        assert!(f.b.port.is_none());
+        assert!(f.proto.is_none());
+
+        assert!("10.0.0.1 <-> 10.0.0.2/icmp".parse::<FlowFilter>().is_ok());
+
+        assert!("10.0.0.1:1234".parse::<FlowFilter>().is_err());
+        assert!("10.0.0.1 <-> 2001:db8::2".parse::<FlowFilter>().is_err());
+        assert!("10.0.0.1:1 <-> 10.0.0.2/icmp"
+            .parse::<FlowFilter>()
+            .is_err());
+        assert!("10.0.0.1 <-> 10.0.0.2/foo".parse::<FlowFilter>().is_err());
+        assert!("10.0.0.1:99999 <-> 10.0.0.2".parse::<FlowFilter>().is_err());
+    }
+
+    #[test]
+    fn to_pcap() {
+        let f = "10.0.0.1:1234 <-> 10.0.0.2:80/tcp"
+            .parse::<FlowFilter>()
+            .unwrap();
+        assert_eq!(
+            f.to_pcap(),
+            "tcp and ((src host 10.0.0.1 and dst host 10.0.0.2 and src port 1234 and dst port 80) or (src host 10.0.0.2 and dst host 10.0.0.1 and src port 80 and dst port 1234))"
+        );
+
+        let f = "10.0.0.1 <-> 10.0.0.2".parse::<FlowFilter>().unwrap();
+        assert_eq!(
+            f.to_pcap(),
+            "((src host 10.0.0.1 and dst host 10.0.0.2) or (src host 10.0.0.2 and dst host 10.0.0.1))"
+        );
+    }
+
+    #[test]
+    fn nat() {
+        let table = "\
+ipv4     2 tcp      6 431999 ESTABLISHED src=10.0.0.1 dst=10.0.0.2 sport=1234 dport=80 src=192.168.0.2 dst=10.0.0.1 sport=8080 dport=1234 [ASSURED] mark=0 zone=0 use=2
+ipv4     2 tcp      6 431999 ESTABLISHED src=10.0.0.3 dst=10.0.0.2 sport=1234 dport=80 src=192.168.0.2 dst=10.0.0.3 sport=8080 dport=1234 [ASSURED] mark=0 zone=0 use=2
+ipv4     2 udp      17 29 src=10.0.0.1 dst=10.0.0.2 sport=1234 dport=80 src=10.0.0.2 dst=10.0.0.1 sport=80 dport=1234 mark=0 zone=0 use=2
+";
+        let f = "10.0.0.1:1234 <-> 10.0.0.2:80"
+            .parse::<FlowFilter>()
+            .unwrap();
+        let nat = f.nat_counterparts_from(table);
+        assert_eq!(nat.len(), 1);
+        assert_eq!(nat[0].a.addr, "192.168.0.2".parse::<IpAddr>().unwrap());
+        assert_eq!(nat[0].a.port, Some(8080));
+        assert_eq!(nat[0].b.port, Some(1234));
+
+        let f = "10.0.0.1:1234 <-> 10.0.0.2:80/udp"
+            .parse::<FlowFilter>()
+            .unwrap();
+        assert!(f.nat_counterparts_from(table).is_empty());
+    }
+}
@@ -3,3 +3,4 @@ pub(crate) mod cbpf;
 pub(crate) mod ebpf;
 pub(crate) mod ebpfinsn;
 pub(crate) mod filter;
+pub(crate) mod flow;
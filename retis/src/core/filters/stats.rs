@@ -0,0 +1,126 @@
+//! # Filter statistics
+//!
+//! Per filter stage counters of evaluated and matched packets, maintained by
+//! the eBPF filtering logic. Those help figuring out if a filter is wrong or
+//! if matching traffic is simply absent.
+
+use std::ops::AddAssign;
+
+use anyhow::{bail, Result};
+use libbpf_rs::MapCore;
+use log::info;
+use plain::Plain;
+
+// Please keep in sync with its BPF counterpart in
+// core/probe/kernel/bpf/include/common.h
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub(crate) struct FilterStats {
+    pub(crate) packet_evaluated: u64,
+    pub(crate) packet_matched: u64,
+    pub(crate) meta_evaluated: u64,
+    pub(crate) meta_matched: u64,
+}
+unsafe impl Plain for FilterStats {}
+
+impl AddAssign for FilterStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.packet_evaluated += rhs.packet_evaluated;
+        self.packet_matched += rhs.packet_matched;
+        self.meta_evaluated += rhs.meta_evaluated;
+        self.meta_matched += rhs.meta_matched;
+    }
+}
+
+impl FilterStats {
+    /// Retrieve the statistics from the (per-CPU) map, summing the values of
+    /// all CPUs.
+    pub(crate) fn from_map(map: &libbpf_rs::MapHandle) -> Result<Self> {
+        let mut stats = FilterStats::default();
+        let Some(values) = map.lookup_percpu(&0u32.to_ne_bytes(), libbpf_rs::MapFlags::ANY)? else {
+            return Ok(stats);
+        };
+
+        for val in values.iter() {
+            let mut cpu = FilterStats::default();
+            cpu.copy_from_bytes(val)
+                .or_else(|_| bail!("Cannot retrieve the filter statistics"))?;
+            stats += cpu;
+        }
+
+        Ok(stats)
+    }
+
+    /// Get a human readable summary of the statistics for the given filter
+    /// stages.
+    pub(crate) fn summary(&self, packet: bool, meta: bool) -> Vec<String> {
+        let line = |name: &str, evaluated: u64, matched: u64| {
+            format!(
+                "{name} filter: {evaluated} evaluated, {matched} matched, {} rejected",
+                evaluated.saturating_sub(matched)
+            )
+        };
+
+        let mut summary = Vec::new();
+        if packet {
+            summary.push(line("packet", self.packet_evaluated, self.packet_matched));
+        }
+        if meta {
+            summary.push(line("meta", self.meta_evaluated, self.meta_matched));
+        }
+        summary
+    }
+
+    /// Log the statistics for the given filter stages.
+    pub(crate) fn report(&self, packet: bool, meta: bool) {
+        self.summary(packet, meta)
+            .iter()
+            .for_each(|line| info!("{line}"));
+    }
+}
+
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) fn init_filter_stats_map() -> Result<libbpf_rs::MapHandle> {
+    let opts = libbpf_sys::bpf_map_create_opts {
+        sz: std::mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+        ..Default::default()
+    };
+
+    Ok(libbpf_rs::MapHandle::create(
+        libbpf_rs::MapType::PercpuArray,
+        Some("filter_stats_map"),
+        std::mem::size_of::<u32>() as u32,
+        std::mem::size_of::<FilterStats>() as u32,
+        1,
+        &opts,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let mut stats = FilterStats {
+            packet_evaluated: 10,
+            packet_matched: 4,
+            meta_evaluated: 4,
+            meta_matched: 1,
+        };
+        stats += FilterStats {
+            packet_evaluated: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            stats.summary(true, true),
+            vec![
+                "packet filter: 11 evaluated, 4 matched, 7 rejected",
+                "meta filter: 4 evaluated, 1 matched, 3 rejected",
+            ]
+        );
+        assert_eq!(stats.summary(false, true).len(), 1);
+        assert!(stats.summary(false, false).is_empty());
+    }
+}
@@ -1,17 +1,44 @@
-use std::fs;
+use std::{collections::HashMap, fs};
 
 use anyhow::{anyhow, bail, Result};
 use btf_rs::{Btf, Type};
+use log::warn;
 
 use super::BASE_TEST_DIR;
 use crate::core::kernel::Symbol;
 
+/// Type of a value (function parameter or return value), as needed to decode
+/// it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ValueType {
+    Void,
+    Int {
+        size: usize,
+        signed: bool,
+    },
+    Ptr,
+    Enum {
+        size: usize,
+        signed: bool,
+        variants: HashMap<i64, String>,
+    },
+    /// Pointer to a string.
+    Str,
+    /// Pointer to a (small) struct, with its members name, offset and type.
+    Struct {
+        name: String,
+        members: Vec<(String, usize, ValueType)>,
+    },
+    /// Types we do not know how to decode.
+    Other,
+}
+
 /// Btf provides multi-module Btf lookups.
 pub(crate) struct BtfInfo {
     /// Main Btf object (vmlinux).
     vmlinux: Btf,
-    /// Extra Btf objects (modules).
-    modules: Vec<Btf>,
+    /// Extra Btf objects (modules), indexed by module name.
+    modules: HashMap<String, Btf>,
 }
 
 impl BtfInfo {
@@ -25,21 +52,45 @@ impl BtfInfo {
         let vmlinux = Btf::from_file(vmlinux.clone())
             .map_err(|e| anyhow!("Could not open {vmlinux}: {e}"))?;
 
-        // Load module btf files if possible.
-        let modules = match cfg!(test) || cfg!(feature = "benchmark") {
-            false => fs::read_dir("/sys/kernel/btf")?
-                .filter(|f| f.is_ok() && f.as_ref().unwrap().file_name().ne("vmlinux"))
-                .map(|f| Btf::from_split_file(f.as_ref().unwrap().path(), &vmlinux))
-                .collect::<Result<Vec<Btf>>>()?,
-            true => vec![Btf::from_split_file(
-                BASE_TEST_DIR.to_owned() + "/test_data/openvswitch",
-                &vmlinux,
-            )?],
-        };
+        // Load module split btf files if possible. A module with invalid BTF
+        // should not prevent from using the others.
+        let mut modules = HashMap::new();
+        match cfg!(test) || cfg!(feature = "benchmark") {
+            false => {
+                for entry in fs::read_dir("/sys/kernel/btf")? {
+                    let entry = entry?;
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if name == "vmlinux" {
+                        continue;
+                    }
+
+                    match Btf::from_split_file(entry.path(), &vmlinux) {
+                        Ok(btf) => {
+                            modules.insert(name, btf);
+                        }
+                        Err(e) => warn!("Could not parse BTF of module {name}: {e}"),
+                    }
+                }
+            }
+            true => {
+                modules.insert(
+                    "openvswitch".to_string(),
+                    Btf::from_split_file(
+                        BASE_TEST_DIR.to_owned() + "/test_data/openvswitch",
+                        &vmlinux,
+                    )?,
+                );
+            }
+        }
 
         Ok(BtfInfo { vmlinux, modules })
     }
 
+    /// Check if BTF information is available for a given module.
+    pub(crate) fn has_module(&self, module: &str) -> bool {
+        self.modules.contains_key(module)
+    }
+
     /// Get a function's number of arguments.
     pub(super) fn function_nargs(&self, symbol: &Symbol) -> Result<u32> {
         // Events have a void* pointing to the data as their first argument, which
@@ -83,6 +134,206 @@ impl BtfInfo {
         Ok(None)
     }
 
+    /// Find an sk_buff reachable one level deep from a function parameters,
+    /// either through a `struct sk_buff **` parameter or through a pointer to
+    /// a struct having a `struct sk_buff *skb` member. Returns the parameter
+    /// offset and the offset (in bytes) of the sk_buff pointer in the data the
+    /// parameter points to.
+    pub(crate) fn nested_skb_offset(&self, symbol: &Symbol) -> Result<Option<(u32, u32)>> {
+        // Events have a void* pointing to the data as their first argument, which
+        // does not end up in their context. We have to skip it. See
+        // include/trace/bpf_probe.h in the __DEFINE_EVENT definition.
+        let fix = match symbol {
+            Symbol::Event(_) => 1,
+            _ => 0,
+        };
+
+        let (btf, proto) = self.find_prototype_btf(symbol)?;
+        for (offset, param) in proto.parameters.iter().enumerate().skip(fix) {
+            let pointee = match Self::strip_qualifiers(btf, btf.resolve_chained_type(param)?)? {
+                Type::Ptr(t) => Self::strip_qualifiers(btf, btf.resolve_chained_type(&t)?)?,
+                _ => continue,
+            };
+
+            match pointee {
+                // struct sk_buff **
+                Type::Ptr(t) => {
+                    if Self::is_skb(btf, btf.resolve_chained_type(&t)?)? {
+                        return Ok(Some(((offset - fix) as u32, 0)));
+                    }
+                }
+                Type::Struct(t) if btf.resolve_name(&t)? != "sk_buff" => {
+                    for member in t.members.iter() {
+                        if member.bitfield_size().is_some_and(|sz| sz > 0)
+                            || btf.resolve_name(member)? != "skb"
+                        {
+                            continue;
+                        }
+
+                        if let Type::Ptr(m) =
+                            Self::strip_qualifiers(btf, btf.resolve_chained_type(member)?)?
+                        {
+                            if Self::is_skb(btf, btf.resolve_chained_type(&m)?)? {
+                                return Ok(Some(((offset - fix) as u32, member.bit_offset() / 8)));
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Skip the const and volatile qualifiers of a type.
+    fn strip_qualifiers(btf: &Btf, mut r#type: Type) -> Result<Type> {
+        loop {
+            r#type = match r#type {
+                Type::Volatile(t) => btf.resolve_chained_type(&t)?,
+                Type::Const(t) => btf.resolve_chained_type(&t)?,
+                _ => return Ok(r#type),
+            }
+        }
+    }
+
+    /// Is a type a `struct sk_buff`?
+    fn is_skb(btf: &Btf, r#type: Type) -> Result<bool> {
+        Ok(match Self::strip_qualifiers(btf, r#type)? {
+            Type::Struct(t) => btf.resolve_name(&t)? == "sk_buff",
+            _ => false,
+        })
+    }
+
+    /// Get a function's return type.
+    pub(crate) fn return_type(&self, symbol: &Symbol) -> Result<ValueType> {
+        let (btf, proto) = self.find_prototype_btf(symbol)?;
+        Self::value_type(btf, btf.resolve_chained_type(&proto)?, 0)
+    }
+
+    /// Get a function's parameters names and types. Pointers to strings and
+    /// to structs fitting in `deref_max` bytes are reported as such, so the
+    /// data they point to can be retrieved and decoded.
+    pub(crate) fn parameter_types(
+        &self,
+        symbol: &Symbol,
+        deref_max: usize,
+    ) -> Result<Vec<(String, ValueType)>> {
+        // Events have a void* pointing to the data as their first argument, which
+        // does not end up in their context. We have to skip it. See
+        // include/trace/bpf_probe.h in the __DEFINE_EVENT definition.
+        let fix = match symbol {
+            Symbol::Event(_) => 1,
+            _ => 0,
+        };
+
+        let (btf, proto) = self.find_prototype_btf(symbol)?;
+        proto
+            .parameters
+            .iter()
+            .skip(fix)
+            .map(|param| {
+                Ok((
+                    btf.resolve_name(param)?,
+                    Self::value_type(btf, btf.resolve_chained_type(param)?, deref_max)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn value_type(btf: &Btf, mut r#type: Type, deref_max: usize) -> Result<ValueType> {
+        loop {
+            r#type = match r#type {
+                Type::Void => return Ok(ValueType::Void),
+                Type::Int(t) => {
+                    return Ok(ValueType::Int {
+                        size: t.size(),
+                        signed: t.is_signed(),
+                    })
+                }
+                Type::Enum(t) => {
+                    let mut variants = HashMap::new();
+                    for member in t.members.iter() {
+                        let val = match t.is_signed() {
+                            true => member.val() as i32 as i64,
+                            false => member.val() as i64,
+                        };
+                        variants.insert(val, btf.resolve_name(member)?);
+                    }
+                    return Ok(ValueType::Enum {
+                        size: 4,
+                        signed: t.is_signed(),
+                        variants,
+                    });
+                }
+                Type::Enum64(t) => {
+                    let mut variants = HashMap::new();
+                    for member in t.members.iter() {
+                        variants.insert(member.val() as i64, btf.resolve_name(member)?);
+                    }
+                    return Ok(ValueType::Enum {
+                        size: 8,
+                        signed: t.is_signed(),
+                        variants,
+                    });
+                }
+                Type::Ptr(t) => {
+                    return match deref_max {
+                        0 => Ok(ValueType::Ptr),
+                        _ => Self::pointee_type(btf, btf.resolve_chained_type(&t)?, deref_max),
+                    }
+                }
+                Type::Typedef(t) => btf.resolve_chained_type(&t)?,
+                Type::Volatile(t) => btf.resolve_chained_type(&t)?,
+                Type::Const(t) => btf.resolve_chained_type(&t)?,
+                Type::Restrict(t) => btf.resolve_chained_type(&t)?,
+                Type::TypeTag(t) => btf.resolve_chained_type(&t)?,
+                _ => return Ok(ValueType::Other),
+            }
+        }
+    }
+
+    /// Get the type of a pointer given the type it points to.
+    fn pointee_type(btf: &Btf, mut r#type: Type, deref_max: usize) -> Result<ValueType> {
+        loop {
+            r#type = match r#type {
+                Type::Int(t) if t.size() == 1 => return Ok(ValueType::Str),
+                Type::Struct(t) => {
+                    let mut members = Vec::new();
+                    for member in t.members.iter() {
+                        // Bitfields are not supported.
+                        if member.bitfield_size().is_some_and(|sz| sz > 0) {
+                            return Ok(ValueType::Ptr);
+                        }
+
+                        let offset = (member.bit_offset() / 8) as usize;
+                        let r#type = Self::value_type(btf, btf.resolve_chained_type(member)?, 0)?;
+                        let size = match r#type {
+                            ValueType::Int { size, .. } | ValueType::Enum { size, .. } => size,
+                            ValueType::Ptr => 8,
+                            _ => return Ok(ValueType::Ptr),
+                        };
+                        if offset + size > deref_max {
+                            return Ok(ValueType::Ptr);
+                        }
+
+                        members.push((btf.resolve_name(member)?, offset, r#type));
+                    }
+
+                    return Ok(ValueType::Struct {
+                        name: btf.resolve_name(&t)?,
+                        members,
+                    });
+                }
+                Type::Typedef(t) => btf.resolve_chained_type(&t)?,
+                Type::Volatile(t) => btf.resolve_chained_type(&t)?,
+                Type::Const(t) => btf.resolve_chained_type(&t)?,
+                Type::Restrict(t) => btf.resolve_chained_type(&t)?,
+                Type::TypeTag(t) => btf.resolve_chained_type(&t)?,
+                _ => return Ok(ValueType::Ptr),
+            }
+        }
+    }
+
     /// Look for a type based on its name and return both a Vec of Type objects as well as
     /// the Btf object where it was found.
     /// Subsequent lookups based on this type (such as nested types by id) must be done on
@@ -94,7 +345,7 @@ impl BtfInfo {
 
         let mut base_types = self.vmlinux.resolve_types_by_name(name).unwrap_or_default();
 
-        for module in self.modules.iter() {
+        for module in self.modules.values() {
             if let Ok(mut res) = module.resolve_types_by_name(name) {
                 // FIXME: We can't filter base types so they'll be reported more
                 // than once (we need some changes in btf-rs that are not
@@ -150,11 +401,11 @@ impl BtfInfo {
         // Only support valid resolve_chained_type calls and exclude function
         // pointers, static/global variables and especially typedef as we don't
         // want to traverse its full definition!
-        let mut is_pointer = false;
+        let mut pointers = 0;
         loop {
             resolved = match resolved {
                 Type::Ptr(t) => {
-                    is_pointer = true;
+                    pointers += 1;
                     btf.resolve_chained_type(&t)?
                 }
                 Type::Volatile(t) => btf.resolve_chained_type(&t)?,
@@ -179,8 +430,9 @@ impl BtfInfo {
         full_name.push_str(type_name.as_str());
 
         // Set the pointer information C style.
-        if is_pointer {
-            full_name.push_str(" *");
+        if pointers > 0 {
+            full_name.push(' ');
+            full_name.push_str(&"*".repeat(pointers));
         }
 
         // We do not get the symbol name; useless and not always there (e.g.
@@ -250,6 +502,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn return_type() {
+        let btf = BtfInfo::new().unwrap();
+        assert_eq!(
+            btf.return_type(&Symbol::Func("kfree_skb_reason".to_string()))
+                .unwrap(),
+            ValueType::Void
+        );
+        assert_eq!(
+            btf.return_type(&Symbol::Func("ovs_dp_upcall".to_string()))
+                .unwrap(),
+            ValueType::Int {
+                size: 4,
+                signed: true
+            }
+        );
+    }
+
+    #[test]
+    fn parameter_types() {
+        let btf = BtfInfo::new().unwrap();
+        let params = btf
+            .parameter_types(&Symbol::Func("kfree_skb_reason".to_string()), 32)
+            .unwrap();
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].0, "skb");
+        assert_eq!(params[0].1, ValueType::Ptr);
+        assert_eq!(params[1].0, "reason");
+        assert!(matches!(params[1].1, ValueType::Enum { size: 4, .. }));
+
+        let params = btf
+            .parameter_types(&Symbol::Event("skb:kfree_skb".to_string()), 32)
+            .unwrap();
+        assert!(params.len() >= 3);
+        assert_eq!(params[0].1, ValueType::Ptr);
+    }
+
+    #[test]
+    fn nested_skb_offset() {
+        let btf = BtfInfo::new().unwrap();
+
+        // Direct sk_buff parameters are not nested ones.
+        assert!(btf
+            .nested_skb_offset(&Symbol::Func("kfree_skb_reason".to_string()))
+            .unwrap()
+            .is_none());
+
+        // struct sk_buff **pskb
+        let symbol = Symbol::Func("netdev_frame_hook".to_string());
+        assert_eq!(btf.nested_skb_offset(&symbol).unwrap(), Some((0, 0)));
+        assert!(btf
+            .parameter_offset(&symbol, "struct sk_buff *")
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            btf.parameter_offset(&symbol, "struct sk_buff **").unwrap(),
+            Some(0)
+        );
+
+        // struct nf_queue_entry *entry, with entry->skb.
+        let (arg, _) = btf
+            .nested_skb_offset(&Symbol::Func("nf_reinject".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(arg, 0);
+    }
+
     #[test]
     fn parameter_offset() {
         let btf = BtfInfo::new().unwrap();
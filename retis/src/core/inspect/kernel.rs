@@ -25,6 +25,8 @@ pub(crate) struct KernelInspector {
     pub(crate) btf: BtfInfo,
     /// Symbols bi-directional map (addr<>name).
     symbols: BiBTreeMap<u64, String>,
+    /// Map of module symbols to the module they belong to.
+    symbol_modules: HashMap<String, String>,
     /// Set of traceable events (e.g. tracepoints).
     traceable_events: Option<HashSet<String>>,
     /// Set of traceable functions (e.g. kprobes).
@@ -69,6 +71,7 @@ impl KernelInspector {
 
         // First parse the symbol file.
         let mut symbols = BiBTreeMap::new();
+        let mut symbol_modules = HashMap::new();
         // Lines have to be processed backward in order to overwrite
         // duplicate addresses and keep the first (which is the last
         // inserted in the common case involving module init
@@ -79,8 +82,9 @@ impl KernelInspector {
                 bail!("Invalid kallsyms line: {}", line);
             }
 
-            let symbol: &str = data[2]
-                .split('\t')
+            // Module symbols are formatted as "symbol\t[module]".
+            let mut fields = data[2].split('\t');
+            let symbol: &str = fields
                 .next()
                 .ok_or_else(|| anyhow!("Couldn't get symbol name for {}", data[0]))?;
 
@@ -93,6 +97,14 @@ impl KernelInspector {
                 continue;
             }
 
+            if let Some(module) = fields
+                .next()
+                .and_then(|m| m.strip_prefix('['))
+                .and_then(|m| m.strip_suffix(']'))
+            {
+                symbol_modules.insert(symbol.to_string(), module.to_string());
+            }
+
             symbols.insert(u64::from_str_radix(data[0], 16)?, String::from(symbol));
         }
 
@@ -108,6 +120,7 @@ impl KernelInspector {
         let inspector = KernelInspector {
             btf,
             symbols,
+            symbol_modules,
             // Not all events we'll get from BTF/kallsyms are traceable. Use the
             // following, when available, to narrow down our checks.
             traceable_events: Self::file_to_hashset(events_file),
@@ -259,6 +272,24 @@ impl KernelInspector {
             .map(|modules| modules.contains(&module.to_string()))
     }
 
+    /// Return the module a symbol belongs to, if any.
+    pub(crate) fn get_symbol_module(&self, name: &str) -> Option<&str> {
+        self.symbol_modules.get(name).map(|m| m.as_str())
+    }
+
+    /// Return the loaded modules for which no BTF information is available.
+    pub(crate) fn modules_without_btf(&self) -> Vec<&str> {
+        let mut modules = self
+            .modules
+            .iter()
+            .flatten()
+            .filter(|m| !self.btf.has_module(m))
+            .map(|m| m.as_str())
+            .collect::<Vec<_>>();
+        modules.sort();
+        modules
+    }
+
     /// Return a symbol name given its address, if a relationship is found.
     pub(crate) fn get_symbol_name(&self, addr: u64) -> Result<String> {
         Ok(self
@@ -495,5 +526,16 @@ mod tests {
     fn kernel_modules() {
         assert_eq!(inspector().is_module_loaded("zram"), Some(true));
         assert_eq!(inspector().is_module_loaded("openvswitch"), Some(false));
+
+        assert_eq!(
+            inspector().get_symbol_module("ovs_dst_get_mtu"),
+            Some("openvswitch")
+        );
+        assert_eq!(
+            inspector().get_symbol_module("fw_cfg_showrev"),
+            Some("qemu_fw_cfg")
+        );
+        assert!(inspector().get_symbol_module("kfree_skb_reason").is_none());
+        assert!(inspector().modules_without_btf().contains(&"zram"));
     }
 }
@@ -23,6 +23,17 @@ impl Symbol {
         }
 
         if inspector()?.kernel.btf.find_prototype_btf(&self).is_err() {
+            // Give a clear hint when the symbol is part of a module lacking
+            // BTF information.
+            if let Some(module) = inspector()?.kernel.get_symbol_module(&self.addr_name()) {
+                if !inspector()?.kernel.btf.has_module(module) {
+                    bail!(
+                        "Symbol {} is not supported: module {module} has no BTF information (/sys/kernel/btf/{module} is missing, the module must be built with CONFIG_DEBUG_INFO_BTF_MODULES)",
+                        self
+                    );
+                }
+            }
+
             bail!(
                 "Symbol {} is not supported (no corresponding BTF definition)",
                 self
@@ -273,6 +284,15 @@ mod tests {
             .is_none());
     }
 
+    #[test]
+    fn module_without_btf() {
+        // Module symbols can be used when the module BTF is available.
+        assert!(Symbol::from_name("ovs_dp_upcall").is_ok());
+
+        let err = Symbol::from_name("fw_cfg_showrev").err().unwrap();
+        assert!(err.to_string().contains("module qemu_fw_cfg has no BTF"));
+    }
+
     #[test]
     fn from_addr() {
         // From an address (is an event).
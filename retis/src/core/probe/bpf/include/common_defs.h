@@ -29,6 +29,32 @@
  */
 struct retis_global_config {
 	u8 enabled;
+	/* How packet and meta filters are combined, see enum
+	 * retis_filters_mode.
+	 */
+	u8 filters_mode;
+	/* Only generate events for tasks in the cgroup stored in cgroup_map. */
+	u8 cgroup_filter;
+	/* Only report 1 out of sample_rate new matching packets. 0 or 1
+	 * disable sampling.
+	 */
+	u32 sample_rate;
+	/* Only generate events for tasks of a given process, if not 0. */
+	u32 pid;
+	/* Only generate events for tasks with a given name, if not empty. */
+	char comm[16];
+	/* Only generate events for a given network namespace (its inode
+	 * number), if not 0.
+	 */
+	u32 netns;
+	/* Maximum number of events per second each probe can generate, 0
+	 * disables rate limiting.
+	 */
+	u32 rate_limit;
+	/* Events buffer usage, in percent, above which no event is generated.
+	 * 0 disables throttling.
+	 */
+	u32 throttle;
 };
 struct {
 	__uint(type, BPF_MAP_TYPE_HASH);
@@ -37,14 +63,63 @@ struct {
 	__type(value, struct retis_global_config);
 } global_config_map SEC(".maps");
 
-static __always_inline bool collection_enabled() {
-	struct retis_global_config *cfg;
+/* Cgroup events generation is restricted to, if any. */
+struct {
+	__uint(type, BPF_MAP_TYPE_CGROUP_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, u32);
+} cgroup_map SEC(".maps");
+
+/* Keep in sync with its Rust counterpart in crate::core::filters::combined */
+enum retis_filters_mode {
+	RETIS_FILTERS_AND = 0,
+	RETIS_FILTERS_OR,
+};
+
+static __always_inline struct retis_global_config *global_config() {
 	u8 key = 0;
 
-	cfg = bpf_map_lookup_elem(&global_config_map, &key);
+	return bpf_map_lookup_elem(&global_config_map, &key);
+}
+
+static __always_inline bool collection_enabled() {
+	struct retis_global_config *cfg = global_config();
+
 	return cfg && !!cfg->enabled;
 }
 
+/* Returns true if the current task matches the task filters (cgroup, pid
+ * and comm), or if no such filter is in place.
+ */
+static __always_inline bool task_match(const struct retis_global_config *cfg) {
+	char comm[sizeof(cfg->comm)];
+	int i;
+
+	/* The task must be in the cgroup, or in one of its descendants. */
+	if (cfg->cgroup_filter &&
+	    bpf_current_task_under_cgroup(&cgroup_map, 0) != 1)
+		return false;
+
+	if (cfg->pid && (bpf_get_current_pid_tgid() >> 32) != cfg->pid)
+		return false;
+
+	if (!cfg->comm[0])
+		return true;
+
+	if (bpf_get_current_comm(comm, sizeof(comm)))
+		return false;
+
+	for (i = 0; i < sizeof(comm); i++) {
+		if (comm[i] != cfg->comm[i])
+			return false;
+		if (!comm[i])
+			break;
+	}
+
+	return true;
+}
+
 #define COMMON_SECTION_CORE	0
 #define COMMON_SECTION_TASK	1
 
@@ -94,10 +169,17 @@ struct retis_counters_key {
 	u64 pid;
 };
 
-/* Contains the counters of the error path.  This is then processed
- * and reported from user-space. */
+/* Contains the per-probe counters. This is then processed and reported from
+ * user-space. */
 struct retis_counters {
+	/* Events which could not be emitted. */
 	u64 dropped_events;
+	/* Number of times the probe was hit. */
+	u64 hits;
+	/* Number of hits which passed (or were not subject to) filtering. */
+	u64 filter_pass;
+	/* Events suppressed by rate limiting. */
+	u64 rate_limited;
 };
 
 /* Probe configuration; the key is the target symbol address */
@@ -108,18 +190,33 @@ struct {
 	__type(value, struct retis_counters);
 } counters_map SEC(".maps");
 
-static __always_inline void err_report(u64 sym_addr, u32 pid)
+static __always_inline struct retis_counters *get_counters(u64 sym_addr,
+							   u32 pid)
 {
-	struct retis_counters *err_counters;
 	struct retis_counters_key key;
 
 	key.pid = pid;
 	key.sym_addr = sym_addr;
-	err_counters = bpf_map_lookup_elem(&counters_map, &key);
-	/* Update only if exists. Any error here should be
-	 * reported in a dedicated trace pipe. */
-	if (err_counters)
-		__sync_fetch_and_add(&err_counters->dropped_events, 1);
+	/* Update only if exists. Any error here should be reported in a
+	 * dedicated trace pipe. */
+	return bpf_map_lookup_elem(&counters_map, &key);
+}
+
+static __always_inline void counters_inc(struct retis_counters *counters,
+					 bool filter_pass)
+{
+	if (!counters)
+		return;
+
+	__sync_fetch_and_add(&counters->hits, 1);
+	if (filter_pass)
+		__sync_fetch_and_add(&counters->filter_pass, 1);
+}
+
+static __always_inline void err_report(struct retis_counters *counters)
+{
+	if (counters)
+		__sync_fetch_and_add(&counters->dropped_events, 1);
 }
 
 #ifndef likely
@@ -1,14 +1,39 @@
 //! # Common
 //!
 //! Module providing infrastructure shared by all probes
+use std::os::fd::{AsFd, AsRawFd};
+
 use anyhow::{bail, Result};
+use libbpf_rs::MapCore;
 
-use crate::core::{inspect, probe::PROBE_MAX};
+use crate::core::{
+    inspect,
+    probe::{Hook, Probe, ProbeBuilderManager, PROBE_MAX},
+};
 
 // Please keep in sync with its BPF counterpart in bpf/include/common_defs.h
 #[repr(C)]
 pub(crate) struct GlobalConfig {
     pub(crate) enabled: u8,
+    /// See crate::core::filters::combined::FiltersMode.
+    pub(crate) filters_mode: u8,
+    /// Only generate events for tasks in the cgroup set in the cgroup map.
+    pub(crate) cgroup_filter: u8,
+    /// Only report 1 out of sample_rate new matching packets.
+    pub(crate) sample_rate: u32,
+    /// Only generate events for tasks of a given process, if not 0.
+    pub(crate) pid: u32,
+    /// Only generate events for tasks with a given name, if not empty.
+    pub(crate) comm: [u8; 16],
+    /// Only generate events for a given network namespace (its inode
+    /// number), if not 0.
+    pub(crate) netns: u32,
+    /// Maximum number of events per second each probe can generate, 0
+    /// disables rate limiting.
+    pub(crate) rate_limit: u32,
+    /// Events buffer usage, in percent, above which no event is generated.
+    /// 0 disables throttling.
+    pub(crate) throttle: u32,
 }
 unsafe impl plain::Plain for GlobalConfig {}
 
@@ -29,6 +54,57 @@ pub(crate) fn init_global_config_map() -> Result<libbpf_rs::MapHandle> {
     )?)
 }
 
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) fn init_sampling_map() -> Result<libbpf_rs::MapHandle> {
+    let opts = libbpf_sys::bpf_map_create_opts {
+        sz: std::mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+        ..Default::default()
+    };
+
+    Ok(libbpf_rs::MapHandle::create(
+        libbpf_rs::MapType::PercpuArray,
+        Some("sampling_map"),
+        std::mem::size_of::<u32>() as u32,
+        std::mem::size_of::<u64>() as u32,
+        1,
+        &opts,
+    )?)
+}
+
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) fn init_cgroup_map() -> Result<libbpf_rs::MapHandle> {
+    let opts = libbpf_sys::bpf_map_create_opts {
+        sz: std::mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+        ..Default::default()
+    };
+
+    Ok(libbpf_rs::MapHandle::create(
+        libbpf_rs::MapType::CgroupArray,
+        Some("cgroup_map"),
+        std::mem::size_of::<u32>() as u32,
+        std::mem::size_of::<u32>() as u32,
+        1,
+        &opts,
+    )?)
+}
+
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) fn init_rate_limit_map() -> Result<libbpf_rs::MapHandle> {
+    let opts = libbpf_sys::bpf_map_create_opts {
+        sz: std::mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+        ..Default::default()
+    };
+
+    Ok(libbpf_rs::MapHandle::create(
+        libbpf_rs::MapType::Hash,
+        Some("rate_limit_map"),
+        std::mem::size_of::<u64>() as u32,
+        std::mem::size_of::<u64>() as u32,
+        PROBE_MAX as u32,
+        &opts,
+    )?)
+}
+
 // Please keep in sync with its BPF counterpart in bpf/include/common_defs.h
 #[derive(Default)]
 #[repr(C)]
@@ -42,12 +118,19 @@ pub(crate) struct CountersKey {
 unsafe impl plain::Plain for CountersKey {}
 
 // Please keep in sync with its BPF counterpart in bpf/include/common_defs.h
-/// Contains the counters of the error path.  This is then processed
-/// and reported from user-space. */
-#[derive(Default)]
+/// Contains the per-probe counters. This is then processed and reported from
+/// user-space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[repr(C)]
 pub(crate) struct Counters {
+    /// Events which could not be emitted.
     pub(crate) dropped_events: u64,
+    /// Number of times the probe was hit.
+    pub(crate) hits: u64,
+    /// Number of hits which passed (or were not subject to) filtering.
+    pub(crate) filter_pass: u64,
+    /// Events suppressed by rate limiting.
+    pub(crate) rate_limited: u64,
 }
 unsafe impl plain::Plain for Counters {}
 
@@ -93,3 +176,39 @@ pub(crate) fn get_thread_size() -> Result<u32> {
 
     Ok((end_addr - start_addr) as u32)
 }
+
+/// Create the config map of a hook, holding a single configuration, and
+/// register a probe running the hook with it. The map name must match the one
+/// of its BPF counterpart. The returned map must be kept alive for as long as
+/// the probe is used.
+pub(crate) fn register_config_probe<T: plain::Plain>(
+    probes: &mut ProbeBuilderManager,
+    mut probe: Probe,
+    mut hook: Hook,
+    map_name: &str,
+    cfg: &T,
+) -> Result<libbpf_rs::MapHandle> {
+    let opts = libbpf_sys::bpf_map_create_opts {
+        sz: std::mem::size_of::<libbpf_sys::bpf_map_create_opts>() as libbpf_sys::size_t,
+        ..Default::default()
+    };
+
+    let config_map = libbpf_rs::MapHandle::create(
+        libbpf_rs::MapType::Array,
+        Some(map_name),
+        std::mem::size_of::<u32>() as u32,
+        std::mem::size_of::<T>() as u32,
+        1,
+        &opts,
+    )
+    .or_else(|e| bail!("Could not create the {map_name} map: {e}"))?;
+
+    let cfg = unsafe { plain::as_bytes(cfg) };
+    config_map.update(&0_u32.to_ne_bytes(), cfg, libbpf_rs::MapFlags::empty())?;
+
+    hook.reuse_map(map_name, config_map.as_fd().as_raw_fd())?;
+    probe.add_hook(hook)?;
+    probes.register_probe(probe)?;
+
+    Ok(config_map)
+}
@@ -0,0 +1,62 @@
+#include <vmlinux.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+
+#include <common.h>
+
+/* Trampoline programs are attached to a single function, the loaded object
+ * can't be shared between probes and the symbol address is set per-object.
+ */
+const volatile u64 ksym = 0;
+
+static __always_inline void fentry_get_regs(struct retis_regs *regs, void *ctx)
+{
+	u64 nargs = bpf_get_func_arg_cnt(ctx);
+	int i;
+
+#pragma unroll
+	for (i = 0; i <= REG_MAX; i++) {
+		if (i >= nargs)
+			break;
+
+		bpf_get_func_arg(ctx, i, &regs->reg[i]);
+	}
+
+	regs->num = i;
+}
+
+SEC("fentry/probe")
+int probe_fentry(void *ctx)
+{
+	struct retis_context context = {};
+
+	context.timestamp = bpf_ktime_get_ns();
+	context.ksym = ksym;
+	context.probe_type = KERNEL_PROBE_FENTRY;
+	context.orig_ctx = ctx;
+	context.stack_base = get_stack_base(ctx, context.probe_type);
+	fentry_get_regs(&context.regs, ctx);
+
+	return chain(&context);
+}
+
+/* Unlike kretprobes, fexit programs have access to both the arguments and the
+ * returned value, no need to pair them with an fentry one.
+ */
+SEC("fexit/probe")
+int probe_fexit(void *ctx)
+{
+	struct retis_context context = {};
+
+	context.timestamp = bpf_ktime_get_ns();
+	context.ksym = ksym;
+	context.probe_type = KERNEL_PROBE_FEXIT;
+	context.orig_ctx = ctx;
+	context.stack_base = get_stack_base(ctx, context.probe_type);
+	fentry_get_regs(&context.regs, ctx);
+	bpf_get_func_ret(ctx, &context.regs.ret);
+
+	return chain(&context);
+}
+
+char __license[] SEC("license") = "GPL";
@@ -14,18 +14,66 @@
 #include <skb_tracking.h>
 #include <stack_tracking.h>
 
+BINDING_DEF(KERNEL_SECTION_CORE, 0)
+BINDING_DEF(KERNEL_SECTION_ARGS, 1)
+BINDING_DEF(KERNEL_SECTION_USER_STACK, 2)
+
+/* Maximum number of reported function arguments. */
+BINDING_DEF(KERNEL_ARGS_MAX, REG_MAX + 1)
+/* Size of the data retrieved behind pointer arguments. */
+BINDING_DEF(KERNEL_ARG_DATA_SIZE, 32)
+
 /* Kernel section of the event data. */
 struct kernel_event {
 	u64 symbol;
 	long stack_id;
+	/* Return value of the probed function, only set for kretprobes. */
+	u64 ret;
 	/* values from enum kernel_probe_type */
 	u8 type;
 } __binding;
 
-/* Per-probe configuration. */
+/* Function arguments section of the event data.
+ *
+ * regs:  arguments raw values.
+ * valid: mask of arguments for which data was retrieved.
+ * num:   number of reported arguments.
+ * data:  data pointed to by arguments, see retis_probe_config.
+ */
+struct kernel_args_event {
+	u64 regs[KERNEL_ARGS_MAX];
+	u16 valid;
+	u8 num;
+	u8 data[KERNEL_ARGS_MAX][KERNEL_ARG_DATA_SIZE];
+} __binding;
+
+/* User stack trace section of the event data.
+ *
+ * stack_id: id of the user stack trace in the stack map.
+ * pid:      process (tgid) the stack trace belongs to.
+ */
+struct kernel_user_stack_event {
+	long stack_id;
+	u32 pid;
+} __binding;
+
+/* Per-probe configuration.
+ *
+ * user_stack_trace: report the user stack trace of the current task.
+ * args:       report the function arguments.
+ * args_str:   mask of arguments pointing to a string.
+ * args_deref: mask of arguments pointing to data to retrieve.
+ * sk_buff_ref_offset: offset of the sk_buff pointer in the data pointed to by
+ *             the offsets.sk_buff_ref parameter.
+ */
 struct retis_probe_config {
 	struct retis_probe_offsets offsets;
 	u8 stack_trace;
+	u8 user_stack_trace;
+	u8 args;
+	u16 args_str;
+	u16 args_deref;
+	u16 sk_buff_ref_offset;
 } __binding;
 
 /* Probe configuration; the key is the target symbol address */
@@ -36,6 +84,50 @@ struct {
 	__type(value, struct retis_probe_config);
 } config_map SEC(".maps");
 
+/* Per-CPU count of new packets matching the filters, used for sampling. */
+struct {
+	__uint(type, BPF_MAP_TYPE_PERCPU_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, u64);
+} sampling_map SEC(".maps");
+
+/* Per-probe rate limiting state, the key is the target symbol address. The
+ * value is the theoretical arrival time (in ns) of the next event, see
+ * rate_limit_pass().
+ */
+struct {
+	__uint(type, BPF_MAP_TYPE_HASH);
+	__uint(max_entries, PROBE_MAX);
+	__type(key, u64);
+	__type(value, u64);
+} rate_limit_map SEC(".maps");
+
+/* Per filter stage statistics. Please keep in sync with its Rust counterpart
+ * in crate::core::filters::stats.
+ */
+struct retis_filter_stats {
+	u64 packet_evaluated;
+	u64 packet_matched;
+	u64 meta_evaluated;
+	u64 meta_matched;
+};
+struct {
+	__uint(type, BPF_MAP_TYPE_PERCPU_ARRAY);
+	__uint(max_entries, 1);
+	__type(key, u32);
+	__type(value, struct retis_filter_stats);
+} filter_stats_map SEC(".maps");
+
+/* Per-CPU map, no need for atomic operations. */
+#define filter_stats_inc(stats, stage, match)		\
+	do {						\
+		if (stats) {				\
+			stats->stage##_evaluated++;	\
+			stats->stage##_matched += !!(match); \
+		}					\
+	} while (0)
+
 /* Probe stack trace map. */
 struct {
 	__uint(type, BPF_MAP_TYPE_STACK_TRACE);
@@ -197,13 +289,112 @@ static __always_inline int extend_ctx_nft(struct retis_context *ctx)
 	return 0;
 }
 
-static __always_inline int extend_ctx(struct retis_context *ctx)
+/* Retrieve an skb reachable one level deep from a parameter, e.g. a
+ * struct sk_buff ** parameter or a struct having an skb member.
+ */
+static __always_inline int extend_ctx_sk_buff_ref(struct retis_context *ctx,
+						  struct retis_probe_config *cfg)
+{
+	struct sk_buff *skb = NULL;
+	void *ref;
+
+	if (retis_arg_valid(ctx, sk_buff) || !retis_arg_valid(ctx, sk_buff_ref))
+		return 0;
+
+	ref = retis_get_sk_buff_ref(ctx);
+	if (!ref)
+		return 0;
+
+	if (bpf_probe_read_kernel(&skb, sizeof(skb),
+				  ref + cfg->sk_buff_ref_offset))
+		return 0;
+
+	/* Do not mark the skb as valid if it's not, so other context
+	 * fetchers can still be used.
+	 */
+	if (skb)
+		retis_set_ext_sk_buff(ctx, skb);
+	return 0;
+}
+
+/* Builtin context fetchers for probes not having access to an skb: the net
+ * device and the netns are derived from other parameters when possible, so
+ * hooks can still report them.
+ */
+static __always_inline int extend_ctx_net_device(struct retis_context *ctx)
+{
+	struct napi_struct___retis *napi;
+	struct net_device *dev;
+	struct Qdisc___retis *q;
+
+	if (retis_arg_valid(ctx, sk_buff) || retis_arg_valid(ctx, net_device))
+		return 0;
+
+	if (retis_arg_valid(ctx, napi_struct)) {
+		napi = (void *)retis_get_napi_struct(ctx);
+		if (!napi)
+			return 0;
+
+		dev = BPF_CORE_READ(napi, dev);
+	} else if (retis_arg_valid(ctx, Qdisc)) {
+		q = (void *)retis_get_Qdisc(ctx);
+		if (!q)
+			return 0;
+
+		dev = BPF_CORE_READ(q, dev_queue, dev);
+	} else {
+		return 0;
+	}
+
+	if (dev)
+		retis_set_ext_net_device(ctx, dev);
+	return 0;
+}
+
+static __always_inline int extend_ctx_net(struct retis_context *ctx)
+{
+	struct net_device *dev;
+	struct net *net = NULL;
+	struct sock *sk;
+
+	if (retis_arg_valid(ctx, sk_buff) || retis_arg_valid(ctx, net))
+		return 0;
+
+	sk = retis_get_sock(ctx);
+	if (sk)
+		net = BPF_CORE_READ(sk, __sk_common.skc_net.net);
+
+	if (!net) {
+		dev = retis_get_net_device(ctx);
+		if (dev)
+			net = BPF_CORE_READ(dev, nd_net.net);
+	}
+
+	if (net)
+		retis_set_ext_net(ctx, net);
+	return 0;
+}
+
+static __always_inline int extend_ctx(struct retis_context *ctx,
+				      struct retis_probe_config *cfg)
 {
 	void *orig_ctx;
 	int ret;
 
 	/* Builtin context extensions. */
 	ret = extend_ctx_nft(ctx);
+	if (ret)
+		return ret;
+	ret = extend_ctx_sk_buff_ref(ctx, cfg);
+	if (ret)
+		return ret;
+	/* The net device must be retrieved first, as it can be used to
+	 * retrieve the netns.
+	 */
+	ret = extend_ctx_net_device(ctx);
+	if (ret)
+		return ret;
+	ret = extend_ctx_net(ctx);
 	if (ret)
 		return ret;
 
@@ -248,10 +439,105 @@ FILTER(l2)
 FILTER(l3)
 FILTER(meta)
 
-static __always_inline u32 filter(struct sk_buff *skb)
+/* Returns true if a new matching packet should be reported, given the
+ * configured sampling rate.
+ */
+static __always_inline bool sample(u32 rate)
+{
+	u32 key = 0;
+	u64 *count;
+
+	if (likely(rate <= 1))
+		return true;
+
+	count = bpf_map_lookup_elem(&sampling_map, &key);
+	if (!count)
+		return true;
+
+	/* Per-CPU map, no need for atomic operations. */
+	return !((*count)++ % rate);
+}
+
+#define NSEC_PER_SEC	1000000000ULL
+
+/* Rate limits events on a per-probe basis, using the generic cell rate
+ * algorithm (GCRA, a token bucket variant): each event costs 1/rate second
+ * and bursts of up to one second worth of events are allowed. Updates are not
+ * atomic across CPUs, which makes the limit approximate.
+ */
+static __always_inline bool rate_limit_pass(u64 ksym, u32 rate)
+{
+	u64 now, cost, tat, init = 0, *next;
+
+	if (!rate)
+		return true;
+
+	next = bpf_map_lookup_elem(&rate_limit_map, &ksym);
+	if (!next) {
+		bpf_map_update_elem(&rate_limit_map, &ksym, &init, BPF_NOEXIST);
+		next = bpf_map_lookup_elem(&rate_limit_map, &ksym);
+		if (!next)
+			return true;
+	}
+
+	now = bpf_ktime_get_ns();
+	cost = NSEC_PER_SEC / rate;
+
+	tat = *next;
+	if (tat < now)
+		tat = now;
+	if (tat + cost > now + NSEC_PER_SEC)
+		return false;
+
+	*next = tat + cost;
+	return true;
+}
+
+/* Returns the inode number of the network namespace an skb belongs to. If no
+ * skb is given or if its namespace can't be found, the namespace of the
+ * current task is used.
+ */
+static __always_inline u32 netns_inum(struct sk_buff *skb)
+{
+	struct task_struct *task;
+	struct net_device *dev;
+	struct sock *sk;
+
+	if (skb) {
+		dev = BPF_CORE_READ(skb, dev);
+		if (dev)
+			return BPF_CORE_READ(dev, nd_net.net, ns.inum);
+
+		sk = BPF_CORE_READ(skb, sk);
+		if (sk)
+			return BPF_CORE_READ(sk, __sk_common.skc_net.net, ns.inum);
+	}
+
+	task = (struct task_struct *)bpf_get_current_task();
+	return BPF_CORE_READ(task, nsproxy, net_ns, ns.inum);
+}
+
+/* Returns true if an skb (or the current task, see netns_inum) belongs to the
+ * network namespace events generation is restricted to, if any.
+ */
+static __always_inline bool netns_match(const struct retis_global_config *cfg,
+					struct sk_buff *skb)
+{
+	return !cfg->netns || netns_inum(skb) == cfg->netns;
+}
+
+/* Runs the packet and meta filters on a given skb and returns the resulting
+ * flags. In RETIS_FILTERS_OR mode, a single matching filter is enough for the
+ * skb to be considered as matching all of them. New matching packets are then
+ * subject to sampling.
+ */
+static __always_inline u32 filter(struct sk_buff *skb,
+				  const struct retis_global_config *cfg)
 {
 	struct retis_packet_filter_ctx fctx = {};
-	u32 flags = 0;
+	struct retis_filter_stats *stats;
+	u32 flags = 0, key = 0;
+	bool meta;
 	char *head;
 
 	if (!skb)
@@ -265,6 +551,8 @@ static __always_inline u32 filter(struct sk_buff *skb)
 	if (skb_is_tracked(skb))
 		return RETIS_ALL_FILTERS;
 
+	stats = bpf_map_lookup_elem(&filter_stats_map, &key);
+
 	head = (char *)BPF_CORE_READ(skb, head);
 	fctx.len = BPF_CORE_READ(skb, len);
 
@@ -277,11 +565,12 @@ static __always_inline u32 filter(struct sk_buff *skb)
 	if (is_mac_data_valid(skb)) {
 		fctx.data = head + BPF_CORE_READ(skb, mac_header);
 		flags |= !!filter_l2(&fctx) << RETIS_F_PACKET_PASS_SH;
-		goto next_filter;
+		goto packet_done;
 	}
 
+	/* The meta filter can still match in OR mode. */
 	if (!is_network_data_valid(skb))
-		goto ret;
+		goto no_packet;
 
 	fctx.data = head + BPF_CORE_READ(skb, network_header);
 	/* L3 filter can be a nop, meaning the criteria are not enough to
@@ -289,25 +578,111 @@ static __always_inline u32 filter(struct sk_buff *skb)
 	 */
 	flags |= !!filter_l3(&fctx) << RETIS_F_PACKET_PASS_SH;
 
-next_filter:
-	flags |= !!filter_meta(skb) << RETIS_F_META_PASS_SH;
-ret:
+packet_done:
+	filter_stats_inc(stats, packet, flags & RETIS_F_PACKET_PASS);
+
+	meta = !!filter_meta(skb);
+	filter_stats_inc(stats, meta, meta);
+	flags |= meta << RETIS_F_META_PASS_SH;
+	if (cfg->filters_mode == RETIS_FILTERS_OR && flags)
+		flags = RETIS_ALL_FILTERS;
+	goto sampling;
+
+no_packet:
+	if (cfg->filters_mode != RETIS_FILTERS_OR)
+		return 0;
+
+	meta = !!filter_meta(skb);
+	filter_stats_inc(stats, meta, meta);
+	if (!meta)
+		return 0;
+	flags = RETIS_ALL_FILTERS;
+
+sampling:
+	/* Sampled out packets are not tracked and won't generate events. */
+	if (flags == RETIS_ALL_FILTERS && !sample(cfg->sample_rate))
+		return 0;
 	return flags;
 }
 
+/* Report the probed function arguments, along with the data they point to
+ * if requested.
+ */
+static __always_inline void report_args(struct retis_raw_event *event,
+					struct retis_context *ctx,
+					struct retis_probe_config *cfg)
+{
+	struct kernel_args_event *a;
+	int i;
+
+	a = get_event_section(event, KERNEL, KERNEL_SECTION_ARGS, sizeof(*a));
+	if (!a)
+		return;
+
+	a->num = ctx->regs.num;
+	a->valid = 0;
+
+	for (i = 0; i < KERNEL_ARGS_MAX; i++) {
+		void *ptr;
+		long err;
+
+		if (i >= ctx->regs.num)
+			break;
+
+		a->regs[i] = ctx->regs.reg[i];
+		ptr = (void *)ctx->regs.reg[i];
+
+		if (cfg->args_str & (1 << i))
+			err = bpf_probe_read_kernel_str(a->data[i],
+							KERNEL_ARG_DATA_SIZE, ptr);
+		else if (cfg->args_deref & (1 << i))
+			err = bpf_probe_read_kernel(a->data[i],
+						    KERNEL_ARG_DATA_SIZE, ptr);
+		else
+			continue;
+
+		if (err >= 0)
+			a->valid |= 1 << i;
+	}
+}
+
+/* Report the user stack trace of the current task, if any (kernel threads
+ * do not have one).
+ */
+static __always_inline void report_user_stack(struct retis_raw_event *event,
+					      struct retis_context *ctx)
+{
+	struct kernel_user_stack_event *u;
+	long stack_id;
+
+	stack_id = bpf_get_stackid(ctx->orig_ctx, &stack_map,
+				   BPF_F_USER_STACK | BPF_F_FAST_STACK_CMP);
+	if (stack_id < 0)
+		return;
+
+	u = get_event_section(event, KERNEL, KERNEL_SECTION_USER_STACK,
+			      sizeof(*u));
+	if (!u)
+		return;
+
+	u->stack_id = stack_id;
+	u->pid = bpf_get_current_pid_tgid() >> 32;
+}
+
 /* The chaining function, which contains all our core probe logic. This is
  * called from each probe specific part after filling the common context and
  * just before returning.
  */
 static __always_inline int chain(struct retis_context *ctx)
 {
+	struct retis_counters *counters;
 	struct retis_probe_config *cfg;
 	struct retis_raw_event *event;
 	/* volatile needed here to prevent from optimizing the
 	 * event usage length read before and after the hook chain.
 	 */
 	struct common_task_event *ti;
-	static bool enabled = false;
+	static struct retis_global_config config = {};
 	volatile u16 pass_threshold;
 	struct common_event *e;
 	struct kernel_event *k;
@@ -315,30 +690,38 @@ static __always_inline int chain(struct retis_context *ctx)
 	int ret;
 
 	/* Check if the collection is enabled, otherwise bail out. Once we have
-	 * a positive result, cache it.
+	 * a positive result, cache it along with the rest of the global
+	 * configuration (which is set once and for all).
 	 */
-	if (unlikely(!enabled)) {
-		enabled = collection_enabled();
-		if (!enabled)
+	if (unlikely(!config.enabled)) {
+		struct retis_global_config *gcfg = global_config();
+
+		if (!gcfg || !gcfg->enabled)
 			return 0;
+
+		config = *gcfg;
 	}
 
 	cfg = bpf_map_lookup_elem(&config_map, &ctx->ksym);
 	if (!cfg)
 		return 0;
 
+	counters = get_counters(ctx->ksym, 0);
 	ctx->offsets = cfg->offsets;
 
-	ret = extend_ctx(ctx);
+	ret = extend_ctx(ctx, cfg);
 	if (ret)
 		log_warning("ctx extension failed: %d", ret);
 
 	skb = retis_get_sk_buff(ctx);
 	if (skb)
-		ctx->flags = filter(skb);
+		ctx->flags = filter(skb, &config);
 	else if (stack_is_tracked(ctx->stack_base))
 		ctx->flags = RETIS_F_STACK_PASS;
 
+	/* Probes not having access to an skb are not subject to filtering. */
+	counters_inc(counters, !skb || ctx->flags);
+
 	/* Track the skb. Note that this is done *after* filtering! If no skb is
 	 * available this is a no-op.
 	 *
@@ -358,15 +741,40 @@ static __always_inline int chain(struct retis_context *ctx)
 		 */
 		track_stack_end(ctx->stack_base);
 
+	/* Tasks not matching the task filters (cgroup, pid, comm) do not
+	 * generate events, but tracking still has to run so packets are
+	 * followed consistently.
+	 */
+	if (!task_match(&config))
+		goto exit;
+
+	/* Same for events from other network namespaces. */
+	if (!netns_match(&config, skb))
+		goto exit;
+
 	/* Shortcut when there are no hooks (e.g. tracking-only probe); no need
 	 * to allocate and fill an event to drop it later on.
 	 */
 	if (nhooks == 0)
 		goto exit;
 
+	if (!rate_limit_pass(ctx->ksym, config.rate_limit)) {
+		if (counters)
+			__sync_fetch_and_add(&counters->rate_limited, 1);
+		goto exit;
+	}
+
+	/* Events not generated because of throttling are accounted as lost,
+	 * for them to be reported.
+	 */
+	if (!events_throttle_pass(config.throttle)) {
+		err_report(counters);
+		goto exit;
+	}
+
 	event = get_event();
 	if (!event) {
-		err_report(ctx->ksym, 0);
+		err_report(counters);
 		goto exit;
 	}
 
@@ -384,17 +792,25 @@ static __always_inline int chain(struct retis_context *ctx)
 	ti->pid = bpf_get_current_pid_tgid();
 	bpf_get_current_comm(ti->comm, sizeof(ti->comm));
 
-	k = get_event_section(event, KERNEL, 0, sizeof(*k));
+	k = get_event_section(event, KERNEL, KERNEL_SECTION_CORE, sizeof(*k));
 	if (!k)
 		goto discard_event;
 
 	k->symbol = ctx->ksym;
 	k->type = ctx->probe_type;
+	k->ret = ctx->probe_type == KERNEL_PROBE_KRETPROBE ||
+		 ctx->probe_type == KERNEL_PROBE_FEXIT ? ctx->regs.ret : 0;
 	if (cfg->stack_trace)
 		k->stack_id = bpf_get_stackid(ctx->orig_ctx, &stack_map, BPF_F_FAST_STACK_CMP);
 	else
 		k->stack_id = -1;
 
+	if (cfg->user_stack_trace)
+		report_user_stack(event, ctx);
+
+	if (cfg->args)
+		report_args(event, ctx, cfg);
+
 	pass_threshold = get_event_size(event);
 	barrier_var(pass_threshold);
 
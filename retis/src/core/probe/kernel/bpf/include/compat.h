@@ -24,4 +24,47 @@ struct sk_buff___6_1_0 {
        u8 vlan_present:1;
 } __attribute__((preserve_access_index));
 
+/* Tunnel flags were converted from a __be16 to a bitmap in v6.10; the bit
+ * positions did not change.
+ */
+struct ip_tunnel_key___6_10_0 {
+	unsigned long tun_flags[1];
+} __attribute__((preserve_access_index));
+
+struct ip_tunnel_key___3_12_0 {
+	__be16 tun_flags;
+} __attribute__((preserve_access_index));
+
+/* Minimal definitions of types not part of our vmlinux.h, only exposing the
+ * fields we use.
+ */
+struct netdev_queue___retis {
+	struct net_device *dev;
+} __attribute__((preserve_access_index));
+
+struct napi_struct___retis {
+	struct net_device *dev;
+} __attribute__((preserve_access_index));
+
+struct Qdisc_ops___retis {
+	char id[16];
+} __attribute__((preserve_access_index));
+
+struct qdisc_skb_head___retis {
+	u32 qlen;
+} __attribute__((preserve_access_index));
+
+struct gnet_stats_queue___retis {
+	u32 backlog;
+} __attribute__((preserve_access_index));
+
+struct Qdisc___retis {
+	const struct Qdisc_ops___retis *ops;
+	u32 handle;
+	u32 parent;
+	struct netdev_queue___retis *dev_queue;
+	struct qdisc_skb_head___retis q;
+	struct gnet_stats_queue___retis qstats;
+} __attribute__((preserve_access_index));
+
 #endif /* __CORE_PROBE_KERNEL_BPF_COMPAT__ */
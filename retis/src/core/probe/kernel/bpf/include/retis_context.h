@@ -10,6 +10,9 @@ enum kernel_probe_type {
 	KERNEL_PROBE_KPROBE = 0,
 	KERNEL_PROBE_KRETPROBE = 1,
 	KERNEL_PROBE_TRACEPOINT = 2,
+	KERNEL_PROBE_PERF_EVENT = 3,
+	KERNEL_PROBE_FENTRY = 4,
+	KERNEL_PROBE_FEXIT = 5,
 };
 
 /**
@@ -27,6 +30,13 @@ struct retis_probe_offsets {
 	s8 net;	 /* netns */
 	s8 nft_pktinfo;
 	s8 nft_traceinfo;
+	s8 sock;
+	s8 napi_struct;
+	s8 Qdisc;
+	/* Parameter an sk_buff can be retrieved from, one level deep (see
+	 * retis_probe_config.sk_buff_ref_offset).
+	 */
+	s8 sk_buff_ref;
 };
 
 enum {
@@ -36,6 +46,8 @@ enum {
 	 * arguments.
 	 */
 	EXT_REG_SKB,	/* Extended register for struct sk_buff.*/
+	EXT_REG_NET_DEVICE,	/* Extended register for struct net_device. */
+	EXT_REG_NET,	/* Extended register for struct net. */
 
 	__EXT_REG_END,
 	EXT_REG_MAX = __EXT_REG_END - 1,
@@ -120,6 +132,14 @@ struct retis_context {
 	RETIS_GET(ctx, nft_pktinfo, struct nft_pktinfo *)
 #define retis_get_nft_traceinfo(ctx)	\
 	RETIS_GET(ctx, nft_traceinfo, struct nft_traceinfo *)
+#define retis_get_sock(ctx)		\
+	RETIS_GET(ctx, sock, struct sock *)
+#define retis_get_napi_struct(ctx)	\
+	RETIS_GET(ctx, napi_struct, struct napi_struct *)
+#define retis_get_Qdisc(ctx)		\
+	RETIS_GET(ctx, Qdisc, struct Qdisc *)
+#define retis_get_sk_buff_ref(ctx)	\
+	RETIS_GET(ctx, sk_buff_ref, void *)
 
 /* Extended register helpers */
 static __always_inline void retis_set_ext_sk_buff(struct retis_context *ctx,
@@ -129,4 +149,18 @@ static __always_inline void retis_set_ext_sk_buff(struct retis_context *ctx,
 	ctx->offsets.sk_buff = EXT_REG_SKB;
 }
 
+static __always_inline void retis_set_ext_net_device(struct retis_context *ctx,
+						     struct net_device *dev)
+{
+	ctx->regs.reg[EXT_REG_NET_DEVICE] = (u64)(dev);
+	ctx->offsets.net_device = EXT_REG_NET_DEVICE;
+}
+
+static __always_inline void retis_set_ext_net(struct retis_context *ctx,
+					      struct net *net)
+{
+	ctx->regs.reg[EXT_REG_NET] = (u64)(net);
+	ctx->offsets.net = EXT_REG_NET;
+}
+
 #endif /* __CORE_PROBE_KERNEL_BPF_RETIS_CONTEXT__ */
@@ -0,0 +1,87 @@
+#include <vmlinux.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+
+#include <common.h>
+
+/* Report the kernel and user stack traces along with the samples. */
+const volatile bool kernel_stack = false;
+const volatile bool user_stack = false;
+
+/* Sampling probe, running on a perf event (e.g. cpu-clock) firing on all
+ * CPUs. This does not run the hooks nor the filters as the samples are not
+ * related to a packet, but only reports what the CPU is currently executing.
+ */
+SEC("perf_event")
+int probe_perf_event(struct bpf_perf_event_data *ctx)
+{
+	static struct retis_global_config config = {};
+	struct retis_context context = {};
+	struct common_task_event *ti;
+	struct retis_raw_event *event;
+	struct common_event *e;
+	struct kernel_event *k;
+
+	/* Check if the collection is enabled, otherwise bail out. Once we have
+	 * a positive result, cache it along with the rest of the global
+	 * configuration.
+	 */
+	if (unlikely(!config.enabled)) {
+		struct retis_global_config *gcfg = global_config();
+
+		if (!gcfg || !gcfg->enabled)
+			return 0;
+
+		config = *gcfg;
+	}
+
+	if (!task_match(&config) || !netns_match(&config, NULL))
+		return 0;
+
+	event = get_event();
+	if (!event)
+		return 0;
+
+	e = get_event_section(event, COMMON, COMMON_SECTION_CORE, sizeof(*e));
+	if (!e)
+		goto discard_event;
+
+	e->timestamp = bpf_ktime_get_ns();
+	e->smp_id = bpf_get_smp_processor_id();
+
+	ti = get_event_zsection(event, COMMON, COMMON_SECTION_TASK, sizeof(*ti));
+	if (!ti)
+		goto discard_event;
+
+	ti->pid = bpf_get_current_pid_tgid();
+	bpf_get_current_comm(ti->comm, sizeof(ti->comm));
+
+	k = get_event_section(event, KERNEL, KERNEL_SECTION_CORE, sizeof(*k));
+	if (!k)
+		goto discard_event;
+
+	/* The symbol is the sampled instruction pointer, which can be in the
+	 * kernel or in user space.
+	 */
+	k->symbol = PT_REGS_IP(&ctx->regs);
+	k->type = KERNEL_PROBE_PERF_EVENT;
+	k->ret = 0;
+	if (kernel_stack)
+		k->stack_id = bpf_get_stackid(ctx, &stack_map, BPF_F_FAST_STACK_CMP);
+	else
+		k->stack_id = -1;
+
+	if (user_stack) {
+		context.orig_ctx = ctx;
+		report_user_stack(event, &context);
+	}
+
+	send_event(event);
+	return 0;
+
+discard_event:
+	discard_event(event);
+	return 0;
+}
+
+char __license[] SEC("license") = "GPL";
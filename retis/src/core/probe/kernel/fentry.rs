@@ -0,0 +1,152 @@
+//! # Fentry
+//!
+//! Module to handle attaching programs to kernel functions using BPF
+//! trampolines (fentry/fexit). Those have a lower overhead than kprobes and
+//! give access to all the function arguments, but they can't be extended: they
+//! are only used for probes without hooks. The module is split in two parts,
+//! the Rust code (here) and the eBPF one (bpf/fentry.bpf.c and its
+//! auto-generated part in bpf/.out/).
+
+use std::os::fd::RawFd;
+
+use anyhow::{anyhow, bail, Result};
+use libbpf_rs::skel::{OpenSkel, Skel};
+
+use crate::core::{inspect, probe::builder::*, probe::*, workaround::*};
+
+mod fentry_bpf {
+    include!("bpf/.out/fentry.skel.rs");
+}
+use fentry_bpf::*;
+
+#[derive(Default)]
+pub(crate) struct FentryBuilder<'a> {
+    // Trampoline programs are bound to their target at load time, keep one
+    // object per probe.
+    skels: Vec<SkelStorage<FentrySkel<'a>>>,
+    fexit: bool,
+    map_fds: Vec<(String, RawFd)>,
+    stack_sz: u32,
+    probes: Vec<Probe>,
+    links: Vec<libbpf_rs::Link>,
+}
+
+impl<'a> ProbeBuilder for FentryBuilder<'a> {
+    fn new() -> Result<FentryBuilder<'a>> {
+        Ok(FentryBuilder::default())
+    }
+
+    fn init(
+        &mut self,
+        map_fds: Vec<(String, RawFd)>,
+        hooks: Vec<Hook>,
+        ctx_hook: Option<Hook>,
+        stack_sz: u32,
+    ) -> Result<()> {
+        if !hooks.is_empty() || ctx_hook.is_some() {
+            bail!("Fentry builder does not support hooks");
+        }
+
+        self.map_fds = map_fds;
+        self.stack_sz = stack_sz;
+        Ok(())
+    }
+
+    fn add_probe(&mut self, probe: Probe) -> Result<()> {
+        self.probes.push(probe);
+        Ok(())
+    }
+
+    fn attach(&mut self) -> Result<()> {
+        let name = self.prog_name();
+
+        for probe in std::mem::take(&mut self.probes) {
+            let symbol = match probe.r#type() {
+                ProbeType::Kprobe(probe) if !self.fexit => &probe.symbol,
+                ProbeType::Kretprobe(probe) if self.fexit => &probe.symbol,
+                _ => bail!("Wrong probe type {}", probe),
+            };
+
+            let skel = self.init_skel(symbol.attach_name(), symbol.addr()?)?;
+            let prog = skel
+                .object()
+                .progs_mut()
+                .find(|p| p.name() == name)
+                .ok_or_else(|| anyhow!("Couldn't get program"))?;
+
+            self.links.push(prog.attach_trace()?);
+            self.skels.push(skel);
+        }
+
+        Ok(())
+    }
+
+    fn detach(&mut self) -> Result<()> {
+        self.links.drain(..);
+        Ok(())
+    }
+}
+
+impl<'a> FentryBuilder<'a> {
+    pub(crate) fn fexit(mut self) -> Self {
+        self.fexit = true;
+        self
+    }
+
+    /// Inspect the running kernel, returning true if trampolines can be used
+    /// in place of kprobes. Retrieving the arguments and return value of
+    /// functions requires helpers added in Linux v5.17.
+    pub(crate) fn supported() -> Result<bool> {
+        let helpers = inspect::parse_enum("bpf_func_id", &[])?;
+        let has_helper = |name: &str| helpers.values().any(|variant| variant == name);
+
+        Ok(has_helper("BPF_FUNC_get_func_arg")
+            && has_helper("BPF_FUNC_get_func_ret")
+            && has_helper("BPF_FUNC_get_func_arg_cnt")
+            && matches!(
+                inspect::inspector()?
+                    .kernel
+                    .get_config_option("CONFIG_DYNAMIC_FTRACE_WITH_DIRECT_CALLS"),
+                Ok(Some("y"))
+            ))
+    }
+
+    fn prog_name(&self) -> &'static str {
+        match self.fexit {
+            false => "probe_fentry",
+            true => "probe_fexit",
+        }
+    }
+
+    fn init_skel(&self, target: String, ksym: u64) -> Result<SkelStorage<FentrySkel<'a>>> {
+        let mut skel = OpenSkelStorage::new::<FentrySkelBuilder>()?;
+
+        let rodata = skel
+            .maps
+            .rodata_data
+            .as_deref_mut()
+            .ok_or_else(|| anyhow!("Can't access eBPF rodata: not memory mapped"))?;
+        rodata.ksym = ksym;
+        rodata.nhooks = 0;
+        rodata.log_level = log::max_level() as u8;
+        rodata.THREAD_SIZE = self.stack_sz;
+
+        reuse_map_fds(skel.open_object_mut(), &self.map_fds)?;
+
+        for mut prog in skel.open_object_mut().progs_mut() {
+            if prog.name() != self.prog_name() {
+                prog.set_autoload(false);
+                continue;
+            }
+
+            prog.set_prog_type(libbpf_rs::ProgramType::Tracing);
+            prog.set_attach_type(match self.fexit {
+                false => libbpf_rs::ProgramAttachType::TraceFentry,
+                true => libbpf_rs::ProgramAttachType::TraceFexit,
+            });
+            prog.set_attach_target(0, Some(target.clone()))?;
+        }
+
+        SkelStorage::load(skel)
+    }
+}
@@ -1,6 +1,54 @@
 use anyhow::Result;
 
-use crate::{bindings::common_uapi::retis_probe_config, core::kernel::Symbol};
+use crate::{
+    bindings::common_uapi::{retis_probe_config, KERNEL_ARGS_MAX, KERNEL_ARG_DATA_SIZE},
+    core::{
+        inspect::{inspector, ValueType},
+        kernel::Symbol,
+    },
+};
+
+/// Context fetchers: kernel types probes can retrieve even if they are not
+/// part of their parameters, along with the parameter types they can be
+/// derived from. See the extend_ctx*() helpers in bpf/include/common.h.
+const CTX_FETCHERS: &[(&str, &[&str])] = &[
+    (
+        "struct sk_buff *",
+        &["struct nft_pktinfo *", "struct nft_traceinfo *"],
+    ),
+    (
+        "struct net_device *",
+        &["struct napi_struct *", "struct Qdisc *"],
+    ),
+    (
+        "struct net *",
+        &[
+            "struct sock *",
+            "struct net_device *",
+            "struct napi_struct *",
+            "struct Qdisc *",
+        ],
+    ),
+];
+
+/// Returns true if a kernel type can be retrieved by a probe on the given
+/// symbol, either as one of its parameters or using a context fetcher.
+pub(crate) fn symbol_provides(symbol: &Symbol, r#type: &str) -> bool {
+    let has = |t: &str| symbol.parameter_offset(t).is_ok_and(|o| o.is_some());
+
+    has(r#type)
+        || (r#type == "struct sk_buff *"
+            && inspector().is_ok_and(|i| {
+                i.kernel
+                    .btf
+                    .nested_skb_offset(symbol)
+                    .is_ok_and(|o| o.is_some())
+            }))
+        || CTX_FETCHERS
+            .iter()
+            .filter(|(target, _)| *target == r#type)
+            .any(|(_, from)| from.iter().any(|t| has(t)))
+}
 
 /// Inspect a target using BTF and fill its description.
 pub(super) fn inspect_symbol(symbol: &Symbol) -> Result<retis_probe_config> {
@@ -9,6 +57,9 @@ pub(super) fn inspect_symbol(symbol: &Symbol) -> Result<retis_probe_config> {
     // Look for known parameter types.
     if let Some(offset) = symbol.parameter_offset("struct sk_buff *")? {
         cfg.offsets.sk_buff = offset as i8;
+    } else if let Some((offset, ref_offset)) = inspector()?.kernel.btf.nested_skb_offset(symbol)? {
+        cfg.offsets.sk_buff_ref = offset as i8;
+        cfg.sk_buff_ref_offset = ref_offset as u16;
     }
     if let Some(offset) = drop_reason_offset(symbol)? {
         cfg.offsets.skb_drop_reason = offset;
@@ -25,10 +76,41 @@ pub(super) fn inspect_symbol(symbol: &Symbol) -> Result<retis_probe_config> {
     if let Some(offset) = symbol.parameter_offset("struct nft_traceinfo *")? {
         cfg.offsets.nft_traceinfo = offset as i8;
     }
+    // Used by the context fetchers, for probes not having an skb.
+    if let Some(offset) = symbol.parameter_offset("struct sock *")? {
+        cfg.offsets.sock = offset as i8;
+    }
+    if let Some(offset) = symbol.parameter_offset("struct napi_struct *")? {
+        cfg.offsets.napi_struct = offset as i8;
+    }
+    if let Some(offset) = symbol.parameter_offset("struct Qdisc *")? {
+        cfg.offsets.Qdisc = offset as i8;
+    }
 
     Ok(cfg)
 }
 
+/// Inspect a target using BTF and fill its arguments reporting configuration.
+pub(super) fn inspect_args(symbol: &Symbol, cfg: &mut retis_probe_config) -> Result<()> {
+    let params = inspector()?
+        .kernel
+        .btf
+        .parameter_types(symbol, KERNEL_ARG_DATA_SIZE as usize)?;
+
+    cfg.args = 1;
+    params
+        .iter()
+        .take(KERNEL_ARGS_MAX as usize)
+        .enumerate()
+        .for_each(|(i, (_, r#type))| match r#type {
+            ValueType::Str => cfg.args_str |= 1 << i,
+            ValueType::Struct { .. } => cfg.args_deref |= 1 << i,
+            _ => (),
+        });
+
+    Ok(())
+}
+
 /// Find the offset of drop reason enums. We do not distinguish them as they're
 /// part of the same (virtual) enum.
 fn drop_reason_offset(symbol: &Symbol) -> Result<Option<i8>> {
@@ -83,5 +165,56 @@ mod tests {
         assert!(config.offsets.skb_drop_reason == -1);
         assert!(config.offsets.net_device == 1);
         assert!(config.offsets.net == 0);
+
+        // Inspect functions with parameters used by context fetchers.
+        let config = super::inspect_symbol(&Symbol::from_name("tcp_v4_connect").unwrap()).unwrap();
+        assert!(config.offsets.sk_buff == -1);
+        assert!(config.offsets.sock == 0);
+
+        let config =
+            super::inspect_symbol(&Symbol::from_name("napi_complete_done").unwrap()).unwrap();
+        assert!(config.offsets.napi_struct == 0);
+
+        let config = super::inspect_symbol(&Symbol::from_name("qdisc_reset").unwrap()).unwrap();
+        assert!(config.offsets.Qdisc == 0);
+
+        // Inspect a function with an sk_buff reachable one level deep.
+        let config =
+            super::inspect_symbol(&Symbol::from_name("netdev_frame_hook").unwrap()).unwrap();
+        assert!(config.offsets.sk_buff == -1);
+        assert!(config.offsets.sk_buff_ref == 0);
+        assert!(config.sk_buff_ref_offset == 0);
+    }
+
+    #[test]
+    fn symbol_provides() {
+        let symbol = Symbol::from_name("tcp_v4_connect").unwrap();
+        assert!(super::symbol_provides(&symbol, "struct sock *"));
+        assert!(super::symbol_provides(&symbol, "struct net *"));
+        assert!(!super::symbol_provides(&symbol, "struct sk_buff *"));
+        assert!(!super::symbol_provides(&symbol, "struct net_device *"));
+
+        let symbol = Symbol::from_name("napi_complete_done").unwrap();
+        assert!(super::symbol_provides(&symbol, "struct net_device *"));
+        assert!(super::symbol_provides(&symbol, "struct net *"));
+
+        let symbol = Symbol::from_name("netdev_frame_hook").unwrap();
+        assert!(super::symbol_provides(&symbol, "struct sk_buff *"));
+
+        let symbol = Symbol::from_name("kfree_skb_reason").unwrap();
+        assert!(super::symbol_provides(&symbol, "struct sk_buff *"));
+        assert!(!super::symbol_provides(&symbol, "struct net *"));
+    }
+
+    #[test]
+    fn inspect_args() {
+        let mut config =
+            super::inspect_symbol(&Symbol::from_name("kfree_skb_reason").unwrap()).unwrap();
+        assert!(config.args == 0);
+
+        super::inspect_args(&Symbol::from_name("kfree_skb_reason").unwrap(), &mut config).unwrap();
+        assert!(config.args == 1);
+        assert!(config.args_str == 0);
+        assert!(config.args_deref == 0);
     }
 }
@@ -1,19 +1,25 @@
 #![allow(dead_code)] // FIXME
 
-use std::{collections::HashMap, fmt};
+use std::{cmp, collections::HashMap, fmt};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 #[cfg(not(test))]
 use libbpf_rs::MapCore;
+use nix::errno::Errno;
 
-use super::inspect::inspect_symbol;
+use super::inspect::{inspect_args, inspect_symbol};
 use crate::{
-    bindings::common_uapi::{kernel_event, retis_probe_config},
+    bindings::common_uapi::{
+        kernel_args_event, kernel_event, kernel_user_stack_event, retis_probe_config,
+        KERNEL_ARGS_MAX, KERNEL_ARG_DATA_SIZE, KERNEL_SECTION_ARGS, KERNEL_SECTION_CORE,
+        KERNEL_SECTION_USER_STACK,
+    },
     core::{
         events::{
-            parse_single_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
+            parse_raw_section, BpfRawSection, EventSectionFactory, FactoryId,
             RawEventSectionFactory,
         },
+        inspect::{inspector, ValueType},
         kernel::Symbol,
         probe::{
             common::{Counters, CountersKey},
@@ -26,7 +32,11 @@ use crate::{
 
 // Split to exclude from tests.
 #[cfg(not(test))]
-use crate::{core::inspect::inspector, events::kernel::StackTrace};
+use crate::{core::user::proc::ProcMaps, events::kernel::StackTrace};
+
+/// Maximum number of processes for which memory mappings are cached.
+#[cfg(not(test))]
+const PROC_MAPS_CACHE_MAX: usize = 256;
 
 /// Kernel encapsulates all the information about a kernel probe (kprobe or tracepoint) needed to attach to it.
 #[derive(Clone)]
@@ -43,13 +53,17 @@ impl KernelProbe {
     pub(crate) fn gen_config(&self, options: &[ProbeOption]) -> Result<retis_probe_config> {
         let mut config = inspect_symbol(&self.symbol)?;
 
-        #[allow(clippy::single_match)]
-        options.iter().for_each(|o| match o {
-            ProbeOption::ProbeStack | ProbeOption::ReportStack => {
-                config.stack_trace = 1;
+        options.iter().try_for_each(|o| -> Result<()> {
+            match o {
+                ProbeOption::ProbeStack | ProbeOption::ReportStack => {
+                    config.stack_trace = 1;
+                }
+                ProbeOption::ReportUserStack => config.user_stack_trace = 1,
+                ProbeOption::ReportArgs => inspect_args(&self.symbol, &mut config)?,
+                _ => (),
             }
-            _ => (),
-        });
+            Ok(())
+        })?;
 
         Ok(config)
     }
@@ -79,34 +93,42 @@ pub(crate) struct KernelEventFactory {
     pub(crate) stack_map: Option<libbpf_rs::MapHandle>,
     // Cache of symbol addr -> name
     symbols_cache: HashMap<u64, String>,
+    // Cache of symbol addr -> return type, if it can be decoded.
+    ret_types_cache: HashMap<u64, Option<ValueType>>,
+    // Cache of symbol addr -> parameters names and types, if available.
+    params_cache: HashMap<u64, Option<Vec<(String, ValueType)>>>,
+    // Cache of pid -> memory mappings, to resolve user stack frames.
+    #[cfg(not(test))]
+    proc_maps_cache: HashMap<i32, ProcMaps>,
 }
 
 impl KernelEventFactory {
+    /// Retrieve the addresses of a stack trace from the stack map.
+    #[cfg(not(test))]
+    fn stack_lookup(&self, stackid: i32) -> Result<Option<Vec<u64>>> {
+        // Only stack_map.lookup() gets intentionally performed. This means that at some point
+        // it's possible that stack_map's entries could be exhausted.
+        Ok(self
+            .stack_map
+            .as_ref()
+            .expect("Stack map is None")
+            .lookup(&stackid.to_ne_bytes(), libbpf_rs::MapFlags::ANY)?
+            .map(|stack_bytes| {
+                stack_bytes
+                    .chunks_exact(std::mem::size_of::<u64>())
+                    .map(|addr| u64::from_ne_bytes(addr.try_into().unwrap()))
+                    .take_while(|addr| *addr != 0)
+                    .collect()
+            }))
+    }
+
     #[cfg(not(test))]
     fn unmarshal_stackid(&self, event: &mut KernelEvent, stackid: i32) -> Result<()> {
         if stackid >= 0 {
             let mut stack_trace: Vec<String> = Vec::new();
-            // Only stack_map.lookup() gets intentionally performed. This means that at some point
-            // it's possible that stack_map's entries could be exhausted.
-            if let Some(stack_bytes) = self
-                .stack_map
-                .as_ref()
-                .expect("Stack map is None")
-                .lookup(&stackid.to_ne_bytes(), libbpf_rs::MapFlags::ANY)?
-            {
-                let sstack: &[u64] = unsafe {
-                    std::slice::from_raw_parts(
-                        stack_bytes.as_ptr() as *const u64,
-                        stack_bytes.len() / std::mem::size_of::<u64>(),
-                    )
-                };
-
-                for sym in sstack {
-                    if *sym == 0x00_u64 {
-                        break;
-                    }
-
-                    match inspector()?.kernel.get_name_offt_from_addr_near(*sym) {
+            if let Some(stack) = self.stack_lookup(stackid)? {
+                for sym in stack {
+                    match inspector()?.kernel.get_name_offt_from_addr_near(sym) {
                         Ok((symbol, offset)) => stack_trace.push(format!("{symbol}+{offset:#x}")),
                         Err(_) => stack_trace.push(format!("{sym:#x}")),
                     }
@@ -117,16 +139,280 @@ impl KernelEventFactory {
         }
         Ok(())
     }
+
+    /// Retrieve a user stack trace and resolve its frames to their object
+    /// file, while the process is still around. Symbols are resolved at
+    /// post-processing time.
+    #[cfg(not(test))]
+    fn unmarshal_user_stack(&mut self, raw: &kernel_user_stack_event) -> Result<UserStackEvent> {
+        let pid = raw.pid as i32;
+        let stack = self.stack_lookup(raw.stack_id as i32)?.unwrap_or_default();
+
+        // Mappings can change over time, refresh them if an address is not
+        // found in the cached ones.
+        let cached = self
+            .proc_maps_cache
+            .get(&pid)
+            .is_some_and(|maps| stack.iter().all(|ip| maps.resolve(*ip).is_some()));
+        if !cached {
+            if self.proc_maps_cache.len() >= PROC_MAPS_CACHE_MAX {
+                self.proc_maps_cache.clear();
+            }
+            // The process might be gone already, in which case frames can't
+            // be resolved.
+            self.proc_maps_cache
+                .insert(pid, ProcMaps::from_pid(pid).unwrap_or_default());
+        }
+        let maps = &self.proc_maps_cache[&pid];
+
+        Ok(UserStackEvent {
+            pid,
+            frames: stack
+                .iter()
+                .map(|ip| {
+                    let resolved = maps.resolve(*ip);
+                    UserStackFrame {
+                        ip: *ip,
+                        path: resolved.map(|(path, _)| path.to_string()),
+                        offset: resolved.map(|(_, offset)| offset),
+                        symbol: None,
+                    }
+                })
+                .collect(),
+        })
+    }
+}
+
+impl KernelEventFactory {
+    /// Decode a function return value using its BTF return type.
+    fn unmarshal_ret(&mut self, symbol_addr: u64, raw: u64) -> Result<Option<KernelRetval>> {
+        let ret_type = match self.ret_types_cache.get(&symbol_addr) {
+            Some(ret_type) => ret_type,
+            None => {
+                let symbol = Symbol::from_addr(symbol_addr)?;
+                // Not being able to decode the return value is not fatal, the
+                // event can still be reported.
+                let ret_type = inspector()?.kernel.btf.return_type(&symbol).ok();
+                self.ret_types_cache.entry(symbol_addr).or_insert(ret_type)
+            }
+        };
+
+        Ok(ret_type.as_ref().and_then(|t| decode_ret(t, raw)))
+    }
+
+    /// Decode the function arguments using their BTF types.
+    fn unmarshal_args(
+        &mut self,
+        symbol_addr: u64,
+        raw: &kernel_args_event,
+    ) -> Result<Option<Vec<KernelArg>>> {
+        let params = match self.params_cache.get(&symbol_addr) {
+            Some(params) => params,
+            None => {
+                let symbol = Symbol::from_addr(symbol_addr)?;
+                let params = inspector()?
+                    .kernel
+                    .btf
+                    .parameter_types(&symbol, KERNEL_ARG_DATA_SIZE as usize)
+                    .ok();
+                self.params_cache.entry(symbol_addr).or_insert(params)
+            }
+        };
+        let Some(params) = params else {
+            return Ok(None);
+        };
+
+        let num = cmp::min(raw.num as usize, KERNEL_ARGS_MAX as usize);
+        Ok(Some(
+            params
+                .iter()
+                .take(num)
+                .enumerate()
+                .map(|(i, (name, r#type))| {
+                    let name = match name.is_empty() {
+                        true => format!("arg{i}"),
+                        false => name.clone(),
+                    };
+                    let data = (raw.valid & (1 << i) != 0).then_some(&raw.data[i][..]);
+                    decode_arg(name, r#type, raw.regs[i], data)
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Maximum errno value, see include/linux/err.h in the kernel.
+const MAX_ERRNO: i64 = 4095;
+
+/// Get the name of an error code, if the value is one.
+fn errno_name(val: i64) -> Option<String> {
+    if !(-MAX_ERRNO..0).contains(&val) {
+        return None;
+    }
+
+    match Errno::from_raw(-val as i32) {
+        Errno::UnknownErrno => None,
+        errno => Some(format!("{errno:?}")),
+    }
+}
+
+/// Sign or zero extend a raw value given its size.
+fn extend(raw: u64, size: usize, signed: bool) -> i64 {
+    match (size, signed) {
+        (1, true) => raw as i8 as i64,
+        (2, true) => raw as i16 as i64,
+        (4, true) => raw as i32 as i64,
+        (1, false) => raw as u8 as i64,
+        (2, false) => raw as u16 as i64,
+        (4, false) => raw as u32 as i64,
+        _ => raw as i64,
+    }
+}
+
+/// Decode a raw return value given its type.
+fn decode_ret(ret_type: &ValueType, raw: u64) -> Option<KernelRetval> {
+    Some(match ret_type {
+        ValueType::Void | ValueType::Other => return None,
+        ValueType::Int { size, signed } => {
+            let value = extend(raw, *size, *signed);
+            KernelRetval {
+                kind: KernelRetvalKind::Int,
+                value,
+                name: signed.then(|| errno_name(value)).flatten(),
+            }
+        }
+        ValueType::Ptr | ValueType::Str | ValueType::Struct { .. } => KernelRetval {
+            kind: KernelRetvalKind::Ptr,
+            value: raw as i64,
+            name: match raw {
+                0 => Some("NULL".to_string()),
+                _ => errno_name(raw as i64),
+            },
+        },
+        ValueType::Enum {
+            size,
+            signed,
+            variants,
+        } => {
+            let value = extend(raw, *size, *signed);
+            KernelRetval {
+                kind: KernelRetvalKind::Enum,
+                value,
+                name: variants.get(&value).cloned(),
+            }
+        }
+    })
+}
+
+/// Get a name for an instruction pointer reported by a sampling probe. Kernel
+/// addresses are resolved to their nearest symbol while user space ones are
+/// not resolved (the user stack trace can be used for this).
+fn sampled_symbol(addr: u64) -> String {
+    // Kernel addresses live in the upper half of the address space.
+    if (addr as i64) >= 0 {
+        return "[user]".to_string();
+    }
+
+    match inspector().and_then(|i| i.kernel.get_name_offt_from_addr_near(addr)) {
+        Ok((symbol, offset)) => format!("{symbol}+{offset:#x}"),
+        Err(_) => format!("{addr:#x}"),
+    }
+}
+
+/// Decode a raw argument given its type and, if any, the data it points to.
+fn decode_arg(name: String, r#type: &ValueType, raw: u64, data: Option<&[u8]>) -> KernelArg {
+    let mut arg = KernelArg {
+        name,
+        raw,
+        ..Default::default()
+    };
+
+    match r#type {
+        ValueType::Int { size, signed } => {
+            arg.value = Some(extend(raw, *size, *signed).to_string());
+        }
+        ValueType::Enum {
+            size,
+            signed,
+            variants,
+        } => {
+            let value = extend(raw, *size, *signed);
+            arg.value = Some(variants.get(&value).cloned().unwrap_or(value.to_string()));
+        }
+        ValueType::Str => {
+            if let Some(data) = data {
+                let len = data.iter().position(|c| *c == 0).unwrap_or(data.len());
+                arg.value = Some(format!("{:?}", String::from_utf8_lossy(&data[..len])));
+            }
+        }
+        ValueType::Struct { members, .. } => {
+            if let Some(data) = data {
+                arg.members = Some(
+                    members
+                        .iter()
+                        .filter_map(|(name, offset, r#type)| {
+                            let size = match r#type {
+                                ValueType::Int { size, .. } | ValueType::Enum { size, .. } => *size,
+                                _ => 8,
+                            };
+                            let mut bytes = [0u8; 8];
+                            bytes[..size].copy_from_slice(data.get(*offset..*offset + size)?);
+                            Some(decode_arg(
+                                name.clone(),
+                                r#type,
+                                u64::from_ne_bytes(bytes),
+                                None,
+                            ))
+                        })
+                        .collect(),
+                );
+            }
+        }
+        ValueType::Ptr | ValueType::Void | ValueType::Other => (),
+    }
+
+    arg
 }
 
 impl RawEventSectionFactory for KernelEventFactory {
     fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
-        let raw = parse_single_raw_section::<kernel_event>(&raw_sections)?;
+        let mut raw = None;
+        let mut raw_args = None;
+        #[cfg_attr(test, allow(unused_variables))]
+        let mut raw_user_stack = None;
+
+        for section in raw_sections.iter() {
+            match section.header.data_type as u32 {
+                KERNEL_SECTION_CORE => raw = Some(parse_raw_section::<kernel_event>(section)?),
+                KERNEL_SECTION_ARGS => {
+                    raw_args = Some(parse_raw_section::<kernel_args_event>(section)?)
+                }
+                KERNEL_SECTION_USER_STACK => {
+                    raw_user_stack = Some(parse_raw_section::<kernel_user_stack_event>(section)?)
+                }
+                x => bail!("Unknown kernel section data type ({x})"),
+            }
+        }
+        let raw =
+            raw.ok_or_else(|| anyhow!("Kernel event from BPF is missing its core section"))?;
         let mut kernel = KernelEvent::default();
 
+        kernel.probe_type = match raw.type_ {
+            // Trampolines are attached in place of kprobes, report them as
+            // such.
+            0 | 4 => "kprobe",
+            1 | 5 => "kretprobe",
+            2 => "raw_tracepoint",
+            3 => "perf",
+            x => bail!("Unknown probe type {x}"),
+        }
+        .to_string();
+
         let symbol_addr = raw.symbol;
         kernel.symbol = match self.symbols_cache.get(&symbol_addr) {
             Some(name) => name.clone(),
+            // Samples can point anywhere, do not cache those.
+            None if raw.type_ == 3 => sampled_symbol(symbol_addr),
             None => {
                 let name = Symbol::from_addr(symbol_addr)?.name();
                 self.symbols_cache.insert(symbol_addr, name.clone());
@@ -134,22 +420,113 @@ impl RawEventSectionFactory for KernelEventFactory {
             }
         };
 
-        kernel.probe_type = match raw.type_ {
-            0 => "kprobe",
-            1 => "kretprobe",
-            2 => "raw_tracepoint",
-            x => bail!("Unknown probe type {x}"),
+        if raw.type_ == 1 {
+            kernel.ret = self.unmarshal_ret(symbol_addr, raw.ret)?;
+        }
+
+        if let Some(raw_args) = raw_args {
+            kernel.args = self.unmarshal_args(symbol_addr, raw_args)?;
         }
-        .to_string();
 
         #[cfg(not(test))]
         self.unmarshal_stackid(&mut kernel, raw.stack_id as i32)?;
+        #[cfg(not(test))]
+        if let Some(raw_user_stack) = raw_user_stack {
+            event.user_stack = Some(self.unmarshal_user_stack(&raw_user_stack)?);
+        }
 
         event.kernel = Some(kernel);
         Ok(())
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_ret() {
+        let int = ValueType::Int {
+            size: 4,
+            signed: true,
+        };
+        let ret = super::decode_ret(&int, -22i64 as u64).unwrap();
+        assert_eq!(ret.value, -22);
+        assert_eq!(ret.name.as_deref(), Some("EINVAL"));
+        let ret = super::decode_ret(&int, 0xffffffff).unwrap();
+        assert_eq!(ret.value, -1);
+        assert_eq!(ret.name.as_deref(), Some("EPERM"));
+        assert!(super::decode_ret(&int, 42).unwrap().name.is_none());
+
+        let uint = ValueType::Int {
+            size: 4,
+            signed: false,
+        };
+        let ret = super::decode_ret(&uint, 0xffffffff).unwrap();
+        assert_eq!(ret.value, 0xffffffff);
+        assert!(ret.name.is_none());
+
+        let ret = super::decode_ret(&ValueType::Ptr, 0).unwrap();
+        assert_eq!(ret.name.as_deref(), Some("NULL"));
+        let ret = super::decode_ret(&ValueType::Ptr, -12i64 as u64).unwrap();
+        assert_eq!(ret.name.as_deref(), Some("ENOMEM"));
+        assert!(super::decode_ret(&ValueType::Ptr, 0xffff888000000000)
+            .unwrap()
+            .name
+            .is_none());
+
+        let r#enum = ValueType::Enum {
+            size: 4,
+            signed: false,
+            variants: HashMap::from([(1, "FOO".to_string())]),
+        };
+        let ret = super::decode_ret(&r#enum, 1).unwrap();
+        assert_eq!(ret.name.as_deref(), Some("FOO"));
+        assert!(super::decode_ret(&r#enum, 2).unwrap().name.is_none());
+
+        assert!(super::decode_ret(&ValueType::Void, 0).is_none());
+    }
+
+    #[test]
+    fn decode_arg() {
+        let int = ValueType::Int {
+            size: 4,
+            signed: true,
+        };
+        let arg = super::decode_arg("len".to_string(), &int, 0xffffffff, None);
+        assert_eq!(arg.value.as_deref(), Some("-1"));
+
+        let mut data = [0u8; 32];
+        data[..4].copy_from_slice(b"eth0");
+        let arg = super::decode_arg("name".to_string(), &ValueType::Str, 0x1000, Some(&data));
+        assert_eq!(arg.value.as_deref(), Some("\"eth0\""));
+        let arg = super::decode_arg("name".to_string(), &ValueType::Str, 0x1000, None);
+        assert!(arg.value.is_none());
+
+        let r#struct = ValueType::Struct {
+            name: "foo".to_string(),
+            members: vec![
+                ("a".to_string(), 0, int.clone()),
+                ("b".to_string(), 8, ValueType::Ptr),
+            ],
+        };
+        let mut data = [0u8; 32];
+        data[..4].copy_from_slice(&42i32.to_ne_bytes());
+        data[8..16].copy_from_slice(&0xdeadu64.to_ne_bytes());
+        let arg = super::decode_arg("foo".to_string(), &r#struct, 0x1000, Some(&data));
+        let members = arg.members.unwrap();
+        assert_eq!(members[0].value.as_deref(), Some("42"));
+        assert_eq!(members[1].raw, 0xdead);
+        assert!(members[1].value.is_none());
+    }
+
+    #[test]
+    fn sampled_symbol() {
+        assert_eq!(super::sampled_symbol(0x401000), "[user]");
+        assert_eq!(super::sampled_symbol(0x7fffffffe000), "[user]");
+    }
+}
+
 #[cfg(feature = "benchmark")]
 pub(crate) mod benchmark {
     use anyhow::Result;
@@ -166,6 +543,7 @@ pub(crate) mod benchmark {
                 symbol: Symbol::from_name("openvswitch:ovs_do_execute_action")?.addr()?,
                 type_: 2, // Raw tracepoint.
                 stack_id: -1,
+                ret: 0,
             };
             build_raw_section(out, FactoryId::Kernel as u8, 0, &mut as_u8_vec(&data));
             Ok(())
@@ -0,0 +1,155 @@
+//! # PerfEvent
+//!
+//! Module to handle sampling what CPUs are executing, by attaching a program
+//! to a cpu-clock perf event on all online CPUs. The module is split in two
+//! parts, the Rust code (here) and the eBPF one (bpf/perf_event.bpf.c and its
+//! auto-generated part in bpf/.out/).
+
+use std::{fs, io, mem, os::fd::RawFd};
+
+use anyhow::{anyhow, bail, Result};
+use libbpf_rs::skel::{OpenSkel, Skel};
+use log::debug;
+
+use crate::core::{
+    bpf_sys::{perf_event_attr, perf_sw_ids, perf_type_id},
+    workaround::*,
+};
+
+mod perf_event_bpf {
+    include!("bpf/.out/perf_event.skel.rs");
+}
+use perf_event_bpf::*;
+
+/// See include/uapi/linux/perf_event.h in the kernel.
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+/// Samples what all CPUs are executing at a given frequency.
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) struct CpuSampler<'a> {
+    skel: SkelStorage<PerfEventSkel<'a>>,
+    links: Vec<libbpf_rs::Link>,
+}
+
+#[cfg_attr(test, allow(dead_code))]
+impl<'a> CpuSampler<'a> {
+    /// Load the sampling program and attach it to a cpu-clock perf event on
+    /// all online CPUs, firing `freq` times per second.
+    pub(crate) fn new(
+        freq: u64,
+        map_fds: &[(String, RawFd)],
+        kernel_stack: bool,
+        user_stack: bool,
+        stack_sz: u32,
+    ) -> Result<Self> {
+        if freq == 0 {
+            bail!("Sampling frequency must be greater than 0");
+        }
+
+        let mut skel = OpenSkelStorage::new::<PerfEventSkelBuilder>()?;
+
+        let rodata = skel
+            .maps
+            .rodata_data
+            .as_deref_mut()
+            .ok_or_else(|| anyhow!("Can't access eBPF rodata: not memory mapped"))?;
+        rodata.kernel_stack = kernel_stack;
+        rodata.user_stack = user_stack;
+        rodata.log_level = log::max_level() as u8;
+        rodata.THREAD_SIZE = stack_sz;
+
+        reuse_map_fds(skel.open_object_mut(), map_fds)?;
+
+        let skel = SkelStorage::load(skel)?;
+        let mut sampler = Self {
+            skel,
+            links: Vec::new(),
+        };
+
+        let cpus = online_cpus()?;
+        for cpu in cpus.iter() {
+            sampler.attach(*cpu, freq)?;
+        }
+        debug!("Sampling {} CPU(s) at {freq}Hz", cpus.len());
+
+        Ok(sampler)
+    }
+
+    fn attach(&mut self, cpu: i32, freq: u64) -> Result<()> {
+        // Unions and bitfields do not implement Default, the structure is
+        // valid when zeroed.
+        let mut attr: perf_event_attr = unsafe { mem::zeroed() };
+        attr.type_ = perf_type_id::PERF_TYPE_SOFTWARE as u32;
+        attr.size = mem::size_of::<perf_event_attr>() as u32;
+        attr.config = perf_sw_ids::PERF_COUNT_SW_CPU_CLOCK as u64;
+        attr.__bindgen_anon_1.sample_freq = freq;
+        attr.set_freq(1);
+
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const perf_event_attr,
+                -1,
+                cpu,
+                -1,
+                PERF_FLAG_FD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            bail!(
+                "Could not open perf event on CPU {cpu}: {}",
+                io::Error::last_os_error()
+            );
+        }
+
+        let prog = self
+            .skel
+            .object()
+            .progs_mut()
+            .find(|p| p.name() == "probe_perf_event")
+            .ok_or_else(|| anyhow!("Couldn't get program"))?;
+
+        // The link takes ownership of the perf event fd, which is closed on
+        // detach.
+        self.links.push(prog.attach_perf_event(fd as i32)?);
+        Ok(())
+    }
+
+    /// Stop sampling.
+    pub(crate) fn detach(&mut self) {
+        self.links.clear();
+    }
+}
+
+/// Get the list of online CPUs.
+#[cfg_attr(test, allow(dead_code))]
+fn online_cpus() -> Result<Vec<i32>> {
+    parse_cpu_list(&fs::read_to_string("/sys/devices/system/cpu/online")?)
+}
+
+/// Parse a CPU list, e.g. "0-3,5,7-8".
+fn parse_cpu_list(list: &str) -> Result<Vec<i32>> {
+    let mut cpus = Vec::new();
+
+    for range in list.trim().split(',').filter(|r| !r.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => cpus.extend(start.parse::<i32>()?..=end.parse::<i32>()?),
+            None => cpus.push(range.parse()?),
+        }
+    }
+
+    Ok(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_list() {
+        assert_eq!(parse_cpu_list("0\n").unwrap(), vec![0]);
+        assert_eq!(parse_cpu_list("0-3").unwrap(), vec![0, 1, 2, 3]);
+        assert_eq!(parse_cpu_list("0-1,4,6-7").unwrap(), vec![0, 1, 4, 6, 7]);
+        assert!(parse_cpu_list("0-a").is_err());
+    }
+}
@@ -3,34 +3,43 @@
 use std::{
     cmp,
     collections::HashMap,
-    os::fd::{AsFd, AsRawFd, RawFd},
+    fs,
+    os::{
+        fd::{AsFd, AsRawFd, RawFd},
+        unix::fs::MetadataExt,
+    },
+    path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, bail, Result};
 use libbpf_rs::MapCore;
 use log::{debug, info, warn};
-use plain::Plain;
 
-use super::common::{Counters, CountersKey};
+use super::stats::ProbeStats;
 use super::*;
 use super::{
     builder::ProbeBuilder,
-    kernel::{kprobe, raw_tracepoint},
+    kernel::{fentry, kprobe, perf_event::CpuSampler, raw_tracepoint},
     user::usdt,
 };
 
 use super::{common::*, kernel::config::init_config_map};
 use crate::core::{
-    filters::{self, fixup_filter_load_fn, register_filter_handler, Filter},
+    filters::{
+        self, combined::FiltersMode, fixup_filter_load_fn, register_filter_handler, stats::*,
+        Filter,
+    },
     kernel::Symbol,
     probe::user::UsdtProbe,
-    user::proc::Process,
 };
 
 // Keep in sync with their BPF counterparts in bpf/include/common.h
 pub(crate) const PROBE_MAX: usize = 1024;
 pub(super) const HOOK_MAX: usize = 10;
 
+/// Mount point of the cgroup v2 hierarchy.
+const CGROUP2_MOUNT: &str = "/sys/fs/cgroup";
+
 /// ProbeManager is the main object providing an API for consumers to register
 /// probes, hooks, maps, etc. It has two main states: builder and runtime.
 ///
@@ -126,6 +135,16 @@ impl ProbeManager {
             libbpf_rs::ProgramType::Kprobe,
             Some(fixup_filter_load_fn),
         )?;
+        register_filter_handler(
+            "fentry/probe",
+            libbpf_rs::ProgramType::Tracing,
+            Some(fixup_filter_load_fn),
+        )?;
+        register_filter_handler(
+            "fexit/probe",
+            libbpf_rs::ProgramType::Tracing,
+            Some(fixup_filter_load_fn),
+        )?;
         register_filter_handler(
             "raw_tracepoint/probe",
             libbpf_rs::ProgramType::RawTracepoint,
@@ -139,6 +158,8 @@ impl ProbeManager {
             config_map: builder.config_map,
             #[cfg(not(test))]
             counters_map: builder.counters_map,
+            #[cfg(not(test))]
+            filter_stats_map: builder.filter_stats_map,
             map_fds: builder.maps.into_iter().collect(),
             hooks: builder.generic_hooks.into_iter().collect(),
             generic_builders: HashMap::new(),
@@ -146,9 +167,13 @@ impl ProbeManager {
             targeted_builders: Vec::new(),
             links: Vec::new(),
             probes: HashMap::new(),
+            disabled_probes: HashMap::new(),
+            disabled_configs: HashMap::new(),
             global_probes_options: builder.global_probes_options.into_iter().collect(),
             filters: builder.filters,
             stack_sz: get_thread_size()?,
+            #[cfg(not(test))]
+            cpu_sampler: None,
         };
 
         // Install probes.
@@ -167,11 +192,36 @@ impl ProbeManager {
         // All probes loaded, issue an info log.
         info!("{probes_count} probe(s) loaded");
 
+        #[cfg(not(test))]
+        if builder.cpu_sampling > 0 {
+            runtime.cpu_sampler = Some(CpuSampler::new(
+                builder.cpu_sampling,
+                &runtime.map_fds,
+                runtime
+                    .global_probes_options
+                    .contains(&ProbeOption::ReportStack),
+                runtime
+                    .global_probes_options
+                    .contains(&ProbeOption::ReportUserStack),
+                runtime.stack_sz,
+            )?);
+        }
+
         #[cfg(not(test))]
         {
             // Set the global config once all probes are installed, to avoid
             // inconsistencies.
-            let config = GlobalConfig { enabled: 1 };
+            let config = GlobalConfig {
+                enabled: 1,
+                filters_mode: builder.filters_mode as u8,
+                cgroup_filter: builder.cgroup_filter as u8,
+                pid: builder.filter_pid,
+                comm: builder.filter_comm,
+                netns: builder.filter_netns,
+                rate_limit: builder.rate_limit,
+                throttle: builder.throttle,
+                sample_rate: builder.sample_rate,
+            };
             let config = unsafe { plain::as_bytes(&config) };
             builder
                 .global_config_map
@@ -191,6 +241,25 @@ pub(crate) struct ProbeBuilderManager {
     generic_hooks: Vec<Hook>,
     /// Filters, meant to be attached to all probes.
     filters: Vec<Filter>,
+    /// How the above filters results are combined.
+    filters_mode: FiltersMode,
+    /// Only report 1 out of sample_rate new matching packets.
+    sample_rate: u32,
+    /// Frequency (in Hz) at which CPUs are sampled, 0 to disable.
+    cpu_sampling: u64,
+    /// Only generate events for tasks in the cgroup set in cgroup_map.
+    cgroup_filter: bool,
+    /// Only generate events for tasks of a given process, if not 0.
+    filter_pid: u32,
+    /// Only generate events for tasks with a given name, if not empty.
+    filter_comm: [u8; 16],
+    /// Only generate events for a given network namespace inode, if not 0.
+    filter_netns: u32,
+    /// Maximum number of events per second each probe can generate, 0 to
+    /// disable rate limiting.
+    rate_limit: u32,
+    /// Events buffer usage, in percent, above which no event is generated.
+    throttle: u32,
     /// List of global probe options to enable/disable additional probes behavior at a high level.
     global_probes_options: Vec<ProbeOption>,
     /// HashMap of map names and file descriptors, to be reused in all hooks.
@@ -198,12 +267,24 @@ pub(crate) struct ProbeBuilderManager {
     /// Common configuration for all probes.
     #[cfg(not(test))]
     global_config_map: libbpf_rs::MapHandle,
+    /// Per-CPU counter of new matching packets, used for sampling.
+    #[cfg(not(test))]
+    sampling_map: libbpf_rs::MapHandle,
+    /// Cgroup events generation is restricted to, if any.
+    #[cfg(not(test))]
+    cgroup_map: libbpf_rs::MapHandle,
+    /// Per-probe rate limiting state.
+    #[cfg(not(test))]
+    rate_limit_map: libbpf_rs::MapHandle,
     /// Dynamic probes requires a map that provides extra information at runtime. This is that map.
     #[cfg(not(test))]
     config_map: libbpf_rs::MapHandle,
     /// Global per-probe map used to report counters.
     #[cfg(not(test))]
     counters_map: libbpf_rs::MapHandle,
+    /// Per filter stage statistics.
+    #[cfg(not(test))]
+    filter_stats_map: libbpf_rs::MapHandle,
 }
 
 impl ProbeBuilderManager {
@@ -215,14 +296,31 @@ impl ProbeBuilderManager {
             probes: HashMap::new(),
             generic_hooks: Vec::new(),
             filters: Vec::new(),
+            filters_mode: FiltersMode::default(),
+            sample_rate: 0,
+            cpu_sampling: 0,
+            cgroup_filter: false,
+            filter_pid: 0,
+            filter_comm: [0; 16],
+            filter_netns: 0,
+            rate_limit: 0,
+            throttle: 0,
             global_probes_options: Vec::new(),
             maps: HashMap::new(),
             #[cfg(not(test))]
             global_config_map: init_global_config_map()?,
             #[cfg(not(test))]
+            sampling_map: init_sampling_map()?,
+            #[cfg(not(test))]
+            cgroup_map: init_cgroup_map()?,
+            #[cfg(not(test))]
+            rate_limit_map: init_rate_limit_map()?,
+            #[cfg(not(test))]
             config_map: init_config_map()?,
             #[cfg(not(test))]
             counters_map: init_counters_map()?,
+            #[cfg(not(test))]
+            filter_stats_map: init_filter_stats_map()?,
         };
 
         #[cfg(not(test))]
@@ -231,6 +329,22 @@ impl ProbeBuilderManager {
             mgr.global_config_map.as_fd().as_raw_fd(),
         );
 
+        #[cfg(not(test))]
+        mgr.maps.insert(
+            "sampling_map".to_string(),
+            mgr.sampling_map.as_fd().as_raw_fd(),
+        );
+
+        #[cfg(not(test))]
+        mgr.maps
+            .insert("cgroup_map".to_string(), mgr.cgroup_map.as_fd().as_raw_fd());
+
+        #[cfg(not(test))]
+        mgr.maps.insert(
+            "rate_limit_map".to_string(),
+            mgr.rate_limit_map.as_fd().as_raw_fd(),
+        );
+
         #[cfg(not(test))]
         mgr.maps
             .insert("config_map".to_string(), mgr.config_map.as_fd().as_raw_fd());
@@ -241,6 +355,12 @@ impl ProbeBuilderManager {
             mgr.counters_map.as_fd().as_raw_fd(),
         );
 
+        #[cfg(not(test))]
+        mgr.maps.insert(
+            "filter_stats_map".to_string(),
+            mgr.filter_stats_map.as_fd().as_raw_fd(),
+        );
+
         Ok(mgr)
     }
 
@@ -334,6 +454,91 @@ impl ProbeBuilderManager {
         Ok(())
     }
 
+    /// Set how the packet and meta filters results are combined. Defaults to
+    /// FiltersMode::And.
+    pub(crate) fn set_filters_mode(&mut self, mode: FiltersMode) {
+        self.filters_mode = mode;
+    }
+
+    /// Only report 1 out of `rate` new packets matching the filters. A rate of
+    /// 0 or 1 disables sampling.
+    pub(crate) fn set_sample_rate(&mut self, rate: u32) {
+        self.sample_rate = rate;
+    }
+
+    /// Only generate events for tasks in a given cgroup (v2), or in one of
+    /// its descendants. The path is either absolute or relative to the cgroup
+    /// v2 mount point.
+    pub(crate) fn set_cgroup(&mut self, path: &Path) -> Result<()> {
+        let path = cgroup_path(path)?;
+        #[cfg_attr(test, allow(unused_variables))]
+        let cgroup = fs::File::open(&path)
+            .map_err(|e| anyhow!("Could not open cgroup {}: {e}", path.display()))?;
+
+        #[cfg(not(test))]
+        self.cgroup_map.update(
+            &0u32.to_ne_bytes(),
+            &(cgroup.as_raw_fd() as u32).to_ne_bytes(),
+            libbpf_rs::MapFlags::ANY,
+        )?;
+
+        self.cgroup_filter = true;
+        Ok(())
+    }
+
+    /// Only generate events for tasks of a given process (tgid).
+    pub(crate) fn set_filter_pid(&mut self, pid: u32) -> Result<()> {
+        if pid == 0 {
+            bail!("Invalid pid 0");
+        }
+
+        self.filter_pid = pid;
+        Ok(())
+    }
+
+    /// Only generate events for tasks with a given name (comm). Task names
+    /// are truncated by the kernel, longer names can't match.
+    pub(crate) fn set_filter_comm(&mut self, comm: &str) -> Result<()> {
+        // Keep room for the trailing NUL byte.
+        let max = self.filter_comm.len() - 1;
+        if comm.is_empty() || comm.len() > max {
+            bail!("Invalid task name '{comm}': must be 1 to {max} bytes long");
+        }
+
+        self.filter_comm = [0; 16];
+        self.filter_comm[..comm.len()].copy_from_slice(comm.as_bytes());
+        Ok(())
+    }
+
+    /// Only generate events for a given network namespace, identified by its
+    /// inode number or by a path to it (e.g. /run/netns/foo or
+    /// /proc/<pid>/ns/net). Packets are matched using their own namespace,
+    /// when available, or the one of the current task.
+    pub(crate) fn set_filter_netns(&mut self, netns: &str) -> Result<()> {
+        self.filter_netns = netns_inum(netns)?;
+        Ok(())
+    }
+
+    /// Limit the number of events each probe can generate to `rate` per
+    /// second, with bursts of up to `rate` events. Suppressed events are
+    /// reported in the probe statistics. A rate of 0 disables rate limiting.
+    pub(crate) fn set_rate_limit(&mut self, rate: u32) {
+        self.rate_limit = rate;
+    }
+
+    /// Stop generating events while the events buffer is filled above
+    /// `percent`, letting the consumer catch up. Events not generated are
+    /// accounted as lost. A value of 0 disables throttling.
+    pub(crate) fn set_throttle(&mut self, percent: u32) {
+        self.throttle = percent;
+    }
+
+    /// Sample what all CPUs are executing `freq` times per second, reporting
+    /// an event for each sample. A frequency of 0 disables CPU sampling.
+    pub(crate) fn set_cpu_sampling(&mut self, freq: u64) {
+        self.cpu_sampling = freq;
+    }
+
     /// Request a hook to be attached to all kernel probes.
     ///
     /// ```
@@ -378,6 +583,9 @@ pub(crate) struct ProbeRuntimeManager {
     /// Global per-probe map used to report counters.
     #[cfg(not(test))]
     counters_map: libbpf_rs::MapHandle,
+    /// Per filter stage statistics.
+    #[cfg(not(test))]
+    filter_stats_map: libbpf_rs::MapHandle,
     generic_builders: HashMap<ProbeTypeKey, Box<dyn ProbeBuilder>>,
     targeted_nohook_builders: HashMap<ProbeTypeKey, Box<dyn ProbeBuilder>>,
     targeted_builders: Vec<Box<dyn ProbeBuilder>>,
@@ -385,9 +593,16 @@ pub(crate) struct ProbeRuntimeManager {
     map_fds: Vec<(String, RawFd)>,
     hooks: Vec<Hook>,
     probes: HashMap<String, Vec<ProbeOption>>,
+    /// Probes disabled at runtime, along with their symbol address.
+    disabled_probes: HashMap<String, u64>,
+    /// Configuration of disabled probes, indexed by their symbol address.
+    disabled_configs: HashMap<u64, Vec<u8>>,
     global_probes_options: Vec<ProbeOption>,
     filters: Vec<Filter>,
     stack_sz: u32,
+    /// CPU sampler, when enabled.
+    #[cfg(not(test))]
+    cpu_sampler: Option<CpuSampler<'static>>,
 }
 
 impl ProbeRuntimeManager {
@@ -439,6 +654,21 @@ impl ProbeRuntimeManager {
         })
     }
 
+    /// Generate a new builder for the given probe, knowing it won't have any
+    /// hook. Trampolines are used for kernel functions when supported, as they
+    /// can't be extended; kprobes are used otherwise.
+    fn gen_nohook_builder(probe: &Probe) -> Result<Box<dyn ProbeBuilder>> {
+        Ok(match probe.r#type() {
+            ProbeType::Kprobe(_) if fentry::FentryBuilder::supported()? => {
+                Box::new(fentry::FentryBuilder::new()?)
+            }
+            ProbeType::Kretprobe(_) if fentry::FentryBuilder::supported()? => {
+                Box::new(fentry::FentryBuilder::new()?.fexit())
+            }
+            _ => Self::gen_builder(probe)?,
+        })
+    }
+
     /// Initialize a map of reusable probe builders (e.g. for generic hooks).
     fn gen_builders(
         &mut self,
@@ -453,18 +683,18 @@ impl ProbeRuntimeManager {
 
         let mut builders = HashMap::new();
         fake_probes.iter().try_for_each(|p| -> Result<()> {
-            let mut builder = ProbeRuntimeManager::gen_builder(p)?;
-
-            builder.init(
-                self.map_fds.clone(),
-                if generic && p.supports_generic_hooks() {
-                    self.hooks.clone()
-                } else {
-                    Vec::new()
-                },
-                None,
-                self.stack_sz,
-            )?;
+            let hooks = if generic && p.supports_generic_hooks() {
+                self.hooks.clone()
+            } else {
+                Vec::new()
+            };
+
+            let mut builder = match hooks.is_empty() {
+                true => ProbeRuntimeManager::gen_nohook_builder(p)?,
+                false => ProbeRuntimeManager::gen_builder(p)?,
+            };
+
+            builder.init(self.map_fds.clone(), hooks, None, self.stack_sz)?;
 
             builders.insert(p.type_key(), builder);
             Ok(())
@@ -505,7 +735,7 @@ impl ProbeRuntimeManager {
 
     /// Add a new targeted probe.
     #[cfg(not(test))]
-    fn add_targeted_probe(&mut self, mut probe: Probe) -> Result<()> {
+    pub(crate) fn add_targeted_probe(&mut self, mut probe: Probe) -> Result<()> {
         self.prepare_probe(&mut probe)?;
         if self
             .probes
@@ -587,6 +817,80 @@ impl ProbeRuntimeManager {
         Ok(())
     }
 
+    /// Enable probes at runtime. Probes previously disabled are re-enabled,
+    /// others are added as generic probes and attached.
+    #[cfg(not(test))]
+    pub(crate) fn enable_probes(&mut self, probes: Vec<Probe>) -> Result<()> {
+        for probe in probes {
+            let key = probe.key();
+            let addr = match self.disabled_probes.remove(&key) {
+                Some(addr) => addr,
+                None => {
+                    self.add_generic_probe(probe)?;
+                    continue;
+                }
+            };
+
+            if let Some(config) = self.disabled_configs.remove(&addr) {
+                self.config_map
+                    .update(&addr.to_ne_bytes(), &config, libbpf_rs::MapFlags::ANY)?;
+            }
+            // Probes sharing the same configuration are enabled as well.
+            self.disabled_probes.retain(|other, other_addr| {
+                if *other_addr == addr {
+                    warn!("{other} is enabled as well, as it shares its configuration with {key}");
+                    return false;
+                }
+                true
+            });
+        }
+
+        self.attach_probes()
+    }
+
+    /// Disable kernel probes at runtime. Their programs stay attached but no
+    /// event is generated as their configuration is removed.
+    #[cfg(not(test))]
+    pub(crate) fn disable_probes(&mut self, probes: Vec<Probe>) -> Result<()> {
+        for probe in probes {
+            let key = probe.key();
+            if !self.probes.contains_key(&key) || self.disabled_probes.contains_key(&key) {
+                bail!("No probe on {probe} is enabled");
+            }
+
+            let symbol = match probe.r#type() {
+                ProbeType::Kprobe(kp) | ProbeType::Kretprobe(kp) | ProbeType::RawTracepoint(kp) => {
+                    &kp.symbol
+                }
+                ProbeType::Usdt(_) => bail!("USDT probes can't be disabled"),
+            };
+            let addr = symbol.addr()?;
+
+            if let Some(config) = self
+                .config_map
+                .lookup(&addr.to_ne_bytes(), libbpf_rs::MapFlags::ANY)?
+            {
+                self.config_map.delete(&addr.to_ne_bytes())?;
+                self.disabled_configs.insert(addr, config);
+            }
+            self.disabled_probes.insert(key.clone(), addr);
+
+            // Kprobes and kretprobes on the same symbol share their
+            // configuration, disabling one disables the other.
+            let other = match probe.r#type() {
+                ProbeType::Kprobe(_) => format!("kretprobe:{symbol}"),
+                ProbeType::Kretprobe(_) => format!("kprobe:{symbol}"),
+                _ => continue,
+            };
+            if self.probes.contains_key(&other) && !self.disabled_probes.contains_key(&other) {
+                warn!("{other} is disabled as well, as it shares its configuration with {key}");
+                self.disabled_probes.insert(other, addr);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get the list of all currently attached probes.
     pub(crate) fn attached_probes(&self) -> Vec<String> {
         self.probes.keys().cloned().collect()
@@ -599,6 +903,11 @@ impl ProbeRuntimeManager {
 
     /// Detach all probes.
     pub(crate) fn detach(&mut self) -> Result<()> {
+        #[cfg(not(test))]
+        if let Some(sampler) = self.cpu_sampler.as_mut() {
+            sampler.detach();
+        }
+
         self.generic_builders
             .values_mut()
             .try_for_each(|builder| builder.detach())?;
@@ -607,61 +916,110 @@ impl ProbeRuntimeManager {
             .try_for_each(|builder| builder.detach())
     }
 
+    /// Returns which filter stages (packet, meta) are in use.
+    fn filter_stages(&self) -> (bool, bool) {
+        (
+            self.filters.iter().any(|f| matches!(f, Filter::Packet(..))),
+            self.filters.iter().any(|f| matches!(f, Filter::Meta(..))),
+        )
+    }
+
+    /// Get a function retrieving and reporting the filter statistics, which
+    /// can be used from another thread while the collection is running.
+    #[cfg(not(test))]
+    pub(crate) fn filter_stats_reporter(&self) -> Result<impl Fn() -> Result<()> + Send> {
+        let (packet, meta) = self.filter_stages();
+        let map = self.filter_stats_map.try_clone()?;
+
+        Ok(move || {
+            FilterStats::from_map(&map)?.report(packet, meta);
+            Ok(())
+        })
+    }
+
+    /// Get a function retrieving and reporting the probes statistics, which
+    /// can be used from another thread while the collection is running.
+    #[cfg(not(test))]
+    pub(crate) fn probe_stats_reporter(&self) -> Result<impl Fn() -> Result<()> + Send> {
+        let map = self.counters_map.try_clone()?;
+
+        Ok(move || {
+            ProbeStats::from_map(&map)?.report();
+            Ok(())
+        })
+    }
+
+    /// Report the filter statistics, if filters are in use.
+    #[cfg(not(test))]
+    pub(crate) fn report_filter_stats(&self) -> Result<()> {
+        let (packet, meta) = self.filter_stages();
+        if packet || meta {
+            FilterStats::from_map(&self.filter_stats_map)?.report(packet, meta);
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    pub(crate) fn report_filter_stats(&self) -> Result<()> {
+        Ok(())
+    }
+
     #[cfg(test)]
     pub(crate) fn report_counters(&self) -> Result<()> {
         Ok(())
     }
 
+    /// Get the total number of events lost so far, from all probes.
     #[cfg(not(test))]
-    pub(crate) fn report_counters(&self) -> Result<()> {
-        let mut counters_key = CountersKey::default();
-        let mut counters = Counters::default();
-        let mut total_lost: u64 = 0;
-        let mut proc_cache: HashMap<u64, String> = HashMap::new();
-
-        for k in self.counters_map.keys() {
-            counters_key
-                .copy_from_bytes(&k)
-                .or_else(|_| bail!("Cannot retrieve the counters map key"))?;
-            if let Some(counters_val) = self.counters_map.lookup(&k, libbpf_rs::MapFlags::ANY)? {
-                counters
-                    .copy_from_bytes(&counters_val)
-                    .or_else(|_| bail!("Cannot retrieve the counters map value"))?;
-                if counters.dropped_events == 0 {
-                    continue;
-                }
+    pub(crate) fn lost_events(&self) -> Result<u64> {
+        ProbeStats::lost_from_map(&self.counters_map)
+    }
 
-                /* kernel symbols */
-                if counters_key.pid == 0 {
-                    let ksym = Symbol::from_addr(counters_key.sym_addr)?;
-                    warn!("lost {} event(s) from {ksym}", counters.dropped_events);
-                } else {
-                    let usdt_info;
-
-                    if let Some(path) = proc_cache.get(&counters_key.pid) {
-                        usdt_info = path.to_string();
-                    } else {
-                        let proc = Process::from_pid(counters_key.pid as i32)?;
-                        let note = proc
-                            .get_note_from_symbol(counters_key.sym_addr)?
-                            .ok_or_else(|| anyhow!("Failed to get symbol information"))?;
-                        usdt_info = format!("{}:{note}", proc.path().display());
-                        proc_cache.insert(counters_key.pid, usdt_info.to_string());
-                    }
-
-                    warn!("lost {} event(s) from {usdt_info}", counters.dropped_events);
-                }
+    #[cfg(test)]
+    pub(crate) fn lost_events(&self) -> Result<u64> {
+        Ok(0)
+    }
 
-                total_lost = total_lost.saturating_add(counters.dropped_events);
-            }
-        }
+    /// Report the probes statistics and lost events.
+    #[cfg(not(test))]
+    pub(crate) fn report_counters(&self) -> Result<()> {
+        let stats = ProbeStats::from_map(&self.counters_map)?;
+        stats.report();
+        stats.report_lost();
+        Ok(())
+    }
+}
 
-        if total_lost > 0 {
-            warn!("total events lost: {total_lost}");
-        }
+/// Get the full path of a cgroup (v2) given its absolute path or its path
+/// relative to the cgroup v2 mount point.
+fn cgroup_path(path: &Path) -> Result<PathBuf> {
+    let path = match path.is_absolute() {
+        true => path.to_path_buf(),
+        false => Path::new(CGROUP2_MOUNT).join(path),
+    };
+
+    // Only exists in cgroup v2 directories.
+    if !path.join("cgroup.controllers").exists() {
+        bail!("{} is not a cgroup v2 directory", path.display());
+    }
 
-        Ok(())
+    Ok(path)
+}
+
+/// Get a network namespace inode number given either the inode number itself
+/// or a path to the namespace.
+fn netns_inum(netns: &str) -> Result<u32> {
+    let inum = match netns.parse::<u64>() {
+        Ok(inum) => inum,
+        Err(_) => fs::metadata(netns)
+            .map_err(|e| anyhow!("Could not find network namespace {netns}: {e}"))?
+            .ino(),
+    };
+
+    if inum == 0 || inum > u32::MAX as u64 {
+        bail!("Invalid network namespace inode number ({inum})");
     }
+    Ok(inum as u32)
 }
 
 #[cfg(test)]
@@ -685,6 +1043,47 @@ mod tests {
         };
     }
 
+    #[test]
+    fn cgroup() {
+        assert!(cgroup_path(Path::new("/tmp")).is_err());
+        assert!(cgroup_path(Path::new("retis/does/not/exist")).is_err());
+
+        let root = Path::new(CGROUP2_MOUNT);
+        if root.join("cgroup.controllers").exists() {
+            assert_eq!(cgroup_path(root).unwrap(), root);
+
+            let mut mgr = ProbeBuilderManager::new().unwrap();
+            assert!(mgr.set_cgroup(Path::new("")).is_ok());
+            assert!(mgr.cgroup_filter);
+        }
+    }
+
+    #[test]
+    fn task_filters() {
+        let mut mgr = ProbeBuilderManager::new().unwrap();
+
+        assert!(mgr.set_filter_pid(0).is_err());
+        assert!(mgr.set_filter_pid(42).is_ok());
+        assert_eq!(mgr.filter_pid, 42);
+
+        assert!(mgr.set_filter_comm("").is_err());
+        assert!(mgr.set_filter_comm("a-very-long-name").is_err());
+        assert!(mgr.set_filter_comm("ovs-vswitchd").is_ok());
+        assert_eq!(&mgr.filter_comm[..13], b"ovs-vswitchd\0");
+        assert!(mgr.set_filter_comm("iperf3").is_ok());
+        assert_eq!(&mgr.filter_comm[..7], b"iperf3\0");
+    }
+
+    #[test]
+    fn netns() {
+        assert_eq!(netns_inum("4026531840").unwrap(), 4026531840);
+        assert!(netns_inum("0").is_err());
+        assert!(netns_inum("/retis/does/not/exist").is_err());
+
+        let own = fs::metadata("/proc/self/ns/net").unwrap().ino();
+        assert_eq!(netns_inum("/proc/self/ns/net").unwrap() as u64, own);
+    }
+
     #[test]
     fn register_probe() {
         let mut mgr = ProbeBuilderManager::new().unwrap();
@@ -17,4 +17,6 @@ pub(crate) mod probe;
 // There is an external module called "probe" so use self::probe to disambiguate.
 pub(crate) use self::probe::*;
 
+pub(crate) mod stats;
+
 pub(crate) mod user;
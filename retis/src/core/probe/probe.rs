@@ -28,6 +28,8 @@ pub(crate) enum ProbeType {
 pub(crate) enum ProbeOption {
     ProbeStack,
     ReportStack,
+    ReportUserStack,
+    ReportArgs,
     NoGenericHook,
 }
 
@@ -37,6 +39,8 @@ impl TryFrom<&str> for ProbeOption {
     fn try_from(option: &str) -> Result<Self> {
         Ok(match option {
             "stack" => Self::ReportStack,
+            "ustack" => Self::ReportUserStack,
+            "args" => Self::ReportArgs,
             _ => bail!("'{option}' is an invalid probe option."),
         })
     }
@@ -195,8 +199,8 @@ impl Probe {
         }
 
         // Merge options.
-        // - ProbeOption::{ProbeStack,ReportStack}: if any of the probes has it, it should be
-        //   set in the resulting probe.
+        // - ProbeOption::{ProbeStack,ReportStack,ReportUserStack,ReportArgs}: if any of the
+        //   probes has it, it should be set in the resulting probe.
         // - ProbeOption::NoGenericHook: has to be set in both probes to be set in the
         //   resulting probe.
         if let Some(opt) = other.options.take(&ProbeOption::ProbeStack) {
@@ -205,6 +209,12 @@ impl Probe {
         if let Some(opt) = other.options.take(&ProbeOption::ReportStack) {
             self.options.insert(opt);
         }
+        if let Some(opt) = other.options.take(&ProbeOption::ReportUserStack) {
+            self.options.insert(opt);
+        }
+        if let Some(opt) = other.options.take(&ProbeOption::ReportArgs) {
+            self.options.insert(opt);
+        }
         if !other.options.contains(&ProbeOption::NoGenericHook) {
             self.options.remove(&ProbeOption::NoGenericHook);
         }
@@ -0,0 +1,190 @@
+//! # Probe statistics
+//!
+//! Per probe hit, filter pass, rate limiting and event emission failure
+//! counters, maintained
+//! by the eBPF probes. Those help figuring out which probes never fired, or
+//! never saw matching traffic.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+use libbpf_rs::MapCore;
+use log::{info, warn};
+use plain::Plain;
+
+use super::common::{Counters, CountersKey};
+use crate::core::{kernel::Symbol, user::proc::Process};
+
+/// Statistics of all the probes, sorted by probe name.
+#[derive(Debug, Default)]
+pub(crate) struct ProbeStats(Vec<(String, Counters)>);
+
+#[cfg_attr(test, allow(dead_code))]
+impl ProbeStats {
+    /// Retrieve the statistics from the counters map.
+    pub(crate) fn from_map(map: &libbpf_rs::MapHandle) -> Result<Self> {
+        let mut key = CountersKey::default();
+        let mut proc_cache: HashMap<u64, Process> = HashMap::new();
+        let mut stats = Vec::new();
+
+        for k in map.keys() {
+            let Some(val) = map.lookup(&k, libbpf_rs::MapFlags::ANY)? else {
+                continue;
+            };
+
+            let mut counters = Counters::default();
+            key.copy_from_bytes(&k)
+                .or_else(|_| bail!("Cannot retrieve the counters map key"))?;
+            counters
+                .copy_from_bytes(&val)
+                .or_else(|_| bail!("Cannot retrieve the counters map value"))?;
+
+            // Processes could have gone away, do not fail in that case.
+            let name = Self::probe_name(&key, &mut proc_cache)
+                .unwrap_or_else(|_| format!("{:#x}[{}]", key.sym_addr, key.pid));
+            stats.push((name, counters));
+        }
+
+        Ok(Self::new(stats))
+    }
+
+    /// Retrieve the total number of lost events, from all probes. Cheaper
+    /// than `from_map` as probe names are not resolved.
+    pub(crate) fn lost_from_map(map: &libbpf_rs::MapHandle) -> Result<u64> {
+        let mut counters = Counters::default();
+        let mut total: u64 = 0;
+
+        for k in map.keys() {
+            let Some(val) = map.lookup(&k, libbpf_rs::MapFlags::ANY)? else {
+                continue;
+            };
+
+            counters
+                .copy_from_bytes(&val)
+                .or_else(|_| bail!("Cannot retrieve the counters map value"))?;
+            total = total.saturating_add(counters.dropped_events);
+        }
+
+        Ok(total)
+    }
+
+    fn new(mut stats: Vec<(String, Counters)>) -> Self {
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        Self(stats)
+    }
+
+    /// Get a probe name from its counters key. A pid of 0 means the probe is
+    /// a kernel one, otherwise it is an USDT.
+    fn probe_name(key: &CountersKey, proc_cache: &mut HashMap<u64, Process>) -> Result<String> {
+        if key.pid == 0 {
+            return Ok(Symbol::from_addr(key.sym_addr)?.to_string());
+        }
+
+        if !proc_cache.contains_key(&key.pid) {
+            proc_cache.insert(key.pid, Process::from_pid(key.pid as i32)?);
+        }
+        // Unwrap as we just made sure the process is in the cache.
+        let proc = proc_cache.get(&key.pid).unwrap();
+        let note = proc
+            .get_note_from_symbol(key.sym_addr)?
+            .ok_or_else(|| anyhow!("Failed to get symbol information"))?;
+        Ok(format!("{}:{note}", proc.path().display()))
+    }
+
+    /// Get a human readable table of the statistics, one line per probe.
+    /// Probes which were never hit are flagged.
+    pub(crate) fn table(&self) -> Vec<String> {
+        let width = self
+            .0
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or_default()
+            .max("probe".len());
+
+        let mut table = vec![format!(
+            "{:<width$} {:>12} {:>12} {:>12} {:>12}",
+            "probe", "hits", "filter pass", "rate limited", "lost"
+        )];
+        self.0.iter().for_each(|(name, c)| {
+            table.push(format!(
+                "{name:<width$} {:>12} {:>12} {:>12} {:>12}{}",
+                c.hits,
+                c.filter_pass,
+                c.rate_limited,
+                c.dropped_events,
+                if c.hits == 0 { "  (never hit)" } else { "" },
+            ))
+        });
+        table
+    }
+
+    /// Log the statistics table.
+    pub(crate) fn report(&self) {
+        if self.0.is_empty() {
+            return;
+        }
+
+        info!("Probe statistics:");
+        self.table().iter().for_each(|line| info!("{line}"));
+    }
+
+    /// Report lost events, per probe and in total, and events suppressed by
+    /// rate limiting.
+    pub(crate) fn report_lost(&self) {
+        let mut total_lost: u64 = 0;
+
+        self.0
+            .iter()
+            .filter(|(_, c)| c.dropped_events > 0)
+            .for_each(|(name, c)| {
+                warn!("lost {} event(s) from {name}", c.dropped_events);
+                total_lost = total_lost.saturating_add(c.dropped_events);
+            });
+
+        if total_lost > 0 {
+            warn!("total events lost: {total_lost}");
+        }
+
+        self.0
+            .iter()
+            .filter(|(_, c)| c.rate_limited > 0)
+            .for_each(|(name, c)| {
+                info!(
+                    "{} event(s) from {name} suppressed by rate limiting",
+                    c.rate_limited
+                )
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table() {
+        let stats = ProbeStats::new(vec![
+            (
+                "tp:skb:kfree_skb".to_string(),
+                Counters {
+                    hits: 10,
+                    filter_pass: 4,
+                    rate_limited: 2,
+                    dropped_events: 1,
+                },
+            ),
+            ("ip_rcv".to_string(), Counters::default()),
+        ]);
+
+        assert_eq!(
+            stats.table(),
+            vec![
+                "probe                    hits  filter pass rate limited         lost",
+                "ip_rcv                      0            0            0            0  (never hit)",
+                "tp:skb:kfree_skb           10            4            2            1",
+            ]
+        );
+        assert_eq!(ProbeStats::default().table().len(), 1);
+    }
+}
@@ -12,6 +12,9 @@ enum userspace_event_type {
 	USDT = 1,
 };
 
+#define USER_SECTION_CORE	1
+#define USER_SECTION_ARGS	2
+
 /* Userspace section of the event data. */
 struct user_event {
 	u64 symbol;
@@ -19,6 +22,12 @@ struct user_event {
 	u8  event_type;
 } __packed;
 
+/* Userspace probe arguments section of the event data. */
+struct user_args_event {
+	u32 num;
+	long args[BPF_USDT_MAX_ARG_CNT];
+} __packed;
+
 /* Userspace context */
 struct user_ctx {
 	long args[BPF_USDT_MAX_ARG_CNT];
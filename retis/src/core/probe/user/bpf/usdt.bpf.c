@@ -4,6 +4,11 @@
 
 #include <user_common.h>
 
+/* Report probe hits even if no hook added data to the event. Set by
+ * user-space for probes not having any hook.
+ */
+const volatile u8 report_hits = 0;
+
 /* Hook placeholder */
 __noinline
 int hook0(struct user_ctx *ctx, struct retis_raw_event *event)
@@ -53,10 +58,12 @@ int probe_usdt(struct pt_regs *ctx)
 {
 	u64 pid = bpf_get_current_pid_tgid();
 	u64 sym_addr = PT_REGS_IP(ctx);
+	struct retis_counters *counters;
 	struct retis_raw_event *event;
 	struct common_task_event *ti;
 	static bool enabled = false;
 	volatile u16 pass_threshold;
+	struct user_args_event *args;
 	struct user_ctx uctx = {};
 	struct common_event *e;
 	struct user_event *u;
@@ -70,12 +77,16 @@ int probe_usdt(struct pt_regs *ctx)
 			return 0;
 	}
 
+	/* USDT probes are not subject to filtering. */
+	counters = get_counters(sym_addr, pid >> 32);
+	counters_inc(counters, true);
+
 	if (get_args(&uctx, ctx) != 0)
 		return -1;
 
 	event = get_event();
 	if (!event) {
-		err_report(sym_addr, pid >> 32);
+		err_report(counters);
 		return 0;
 	}
 
@@ -94,7 +105,7 @@ int probe_usdt(struct pt_regs *ctx)
 	ti->pid = pid;
 	bpf_get_current_comm(ti->comm, sizeof(ti->comm));
 
-	u = get_event_section(event, USERSPACE, 1, sizeof(*u));
+	u = get_event_section(event, USERSPACE, USER_SECTION_CORE, sizeof(*u));
 	if (!u)
 		goto discard_event;
 
@@ -102,13 +113,23 @@ int probe_usdt(struct pt_regs *ctx)
 	u->pid = pid;
 	u->event_type = USDT;
 
+	if (uctx.num) {
+		args = get_event_section(event, USERSPACE, USER_SECTION_ARGS,
+					 sizeof(*args));
+		if (!args)
+			goto discard_event;
+
+		args->num = uctx.num;
+		__builtin_memcpy(args->args, uctx.args, sizeof(args->args));
+	}
+
 	pass_threshold = get_event_size(event);
 	barrier_var(pass_threshold);
 
 	/* UST only supports a single hook. */
 	hook0(&uctx, event);
 
-	if (get_event_size(event) > pass_threshold) {
+	if (report_hits || get_event_size(event) > pass_threshold) {
 		send_event(event);
 		return 0;
 	}
@@ -10,3 +10,5 @@ pub(crate) mod user;
 pub(crate) use user::*;
 
 pub(crate) mod usdt;
+pub(crate) mod utils;
+pub(crate) mod watch;
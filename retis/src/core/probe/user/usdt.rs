@@ -1,10 +1,15 @@
-use std::os::fd::{AsFd, AsRawFd, RawFd};
+use std::{
+    os::fd::{AsFd, AsRawFd, RawFd},
+    path::Path,
+};
 
 use anyhow::{anyhow, bail, Result};
 use libbpf_rs::skel::{OpenSkel, Skel};
+use log::{debug, warn};
 
 use crate::core::{
     probe::{builder::*, Hook, Probe, ProbeType},
+    user::proc::read_semaphore,
     workaround::*,
 };
 
@@ -13,6 +18,10 @@ mod usdt_bpf {
 }
 use usdt_bpf::*;
 
+/// Present when the kernel supports reference counters (used for USDT
+/// semaphores) in uprobes.
+const UPROBE_REF_CTR: &str = "/sys/bus/event_source/devices/uprobe/format/ref_ctr_offset";
+
 #[derive(Default)]
 pub(crate) struct UsdtBuilder<'a> {
     skel: Option<SkelStorage<UsdtSkel<'a>>>,
@@ -65,6 +74,15 @@ impl UsdtBuilder<'_> {
             _ => bail!("Wrong probe type"),
         };
 
+        // Probes gated by a semaphore only fire when it is non-zero. Semaphores
+        // are incremented by the kernel when the uprobe is registered (and
+        // decremented when it is removed), in all processes mapping the
+        // object, including future ones; libbpf sets the reference counter
+        // offset from the USDT note.
+        if probe.sema_addr != 0 && !Path::new(UPROBE_REF_CTR).exists() {
+            bail!("{probe}: USDT semaphores are not supported by the running kernel (uprobe reference counters are required)");
+        }
+
         let mut skel = OpenSkelStorage::new::<UsdtSkelBuilder>()?;
         let rodata = skel
             .maps
@@ -72,6 +90,8 @@ impl UsdtBuilder<'_> {
             .as_deref_mut()
             .ok_or_else(|| anyhow!("Can't access eBPF rodata: not memory mapped"))?;
         rodata.log_level = log::max_level() as u8;
+        // Probes without hook would otherwise never generate an event.
+        rodata.report_hits = self.hooks.is_empty() as u8;
 
         reuse_map_fds(skel.open_object_mut(), &self.map_fds)?;
 
@@ -88,6 +108,14 @@ impl UsdtBuilder<'_> {
             .push(prog.attach_usdt(probe.pid, &probe.path, &probe.provider, &probe.name)?);
         self.skel = Some(skel);
 
+        if probe.sema_addr != 0 && probe.pid >= 0 {
+            match read_semaphore(probe.pid, &probe.path, probe.sema_addr) {
+                Ok(0) => warn!("{probe}: USDT semaphore is not enabled, the probe might not fire"),
+                Ok(val) => debug!("{probe}: USDT semaphore enabled ({val})"),
+                Err(e) => warn!("{probe}: could not check the USDT semaphore: {e}"),
+            }
+        }
+
         Ok(())
     }
 }
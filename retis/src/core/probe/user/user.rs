@@ -1,6 +1,11 @@
 #![allow(dead_code)] // FIXME
 
-use std::{any::Any, collections::HashMap, fmt, path::PathBuf};
+use std::{
+    any::Any,
+    collections::{hash_map::Entry, HashMap},
+    fmt,
+    path::PathBuf,
+};
 
 use anyhow::{anyhow, bail, Result};
 
@@ -8,12 +13,19 @@ use crate::{
     core::{
         events::{BpfRawSection, EventSectionFactory, FactoryId, RawEventSectionFactory},
         probe::common::{Counters, CountersKey},
-        user::proc::Process,
+        user::proc::{Process, UsdtArg},
     },
     event_section_factory,
     events::*,
 };
 
+// Please keep in sync with their BPF counterparts in
+// bpf/include/user_common.h
+const USER_SECTION_CORE: u8 = 1;
+const USER_SECTION_ARGS: u8 = 2;
+/// Maximum number of USDT arguments retrieved, as defined by libbpf.
+const USDT_MAX_ARG_CNT: usize = 12;
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct UsdtProbe {
     /// The provider name.
@@ -22,6 +34,8 @@ pub(crate) struct UsdtProbe {
     pub name: String,
     /// The probe's symbol.
     pub ksym: u64,
+    /// Link-time address of the probe semaphore, 0 if the probe has none.
+    pub sema_addr: u64,
 
     /// The target's path
     pub path: PathBuf,
@@ -36,10 +50,20 @@ impl UsdtProbe {
             .get_note(target)?
             .ok_or_else(|| anyhow!("Target not found"))?;
 
+        // Make sure the arguments can be reported.
+        let args = note.parse_args()?;
+        if args.len() > USDT_MAX_ARG_CNT {
+            bail!(
+                "{note}: too many arguments ({} > {USDT_MAX_ARG_CNT})",
+                args.len()
+            );
+        }
+
         Ok(UsdtProbe {
             provider: note.provider.to_owned(),
             name: note.name.to_owned(),
             ksym: note.addr,
+            sema_addr: note.sema_addr as u64,
             path: path.to_owned(),
             pid: proc.pid(),
         })
@@ -51,6 +75,7 @@ impl UsdtProbe {
             provider: "".to_string(),
             name: "".to_string(),
             ksym: 0,
+            sema_addr: 0,
             path: PathBuf::new(),
             pid: -1,
         }
@@ -75,7 +100,12 @@ impl UsdtProbe {
 
 impl fmt::Display for UsdtProbe {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}:{}:{}", self.path.display(), self.provider, self.name)
+        write!(f, "{}", self.path.display())?;
+        // Probes attached to a specific process.
+        if self.pid >= 0 {
+            write!(f, "[{}]", self.pid)?;
+        }
+        write!(f, ":{}:{}", self.provider, self.name)
     }
 }
 
@@ -86,13 +116,18 @@ pub(crate) struct UserEventFactory {
 }
 
 impl RawEventSectionFactory for UserEventFactory {
-    fn create(&mut self, mut raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
-        if raw_sections.len() != 1 {
-            bail!("User event from BPF must be a single section")
+    fn create(&mut self, raw_sections: Vec<BpfRawSection>, event: &mut Event) -> Result<()> {
+        let mut raw = None;
+        let mut raw_args = None;
+
+        for section in raw_sections.into_iter() {
+            match section.header.data_type {
+                USER_SECTION_CORE => raw = Some(section),
+                USER_SECTION_ARGS => raw_args = Some(section),
+                x => bail!("Unknown user section data type ({x})"),
+            }
         }
-
-        // Unwrap as we just checked the vector contains 1 element.
-        let raw = raw_sections.pop().unwrap();
+        let raw = raw.ok_or_else(|| anyhow!("User event from BPF is missing its core section"))?;
 
         if raw.data.len() != 17 {
             bail!(
@@ -111,24 +146,28 @@ impl RawEventSectionFactory for UserEventFactory {
 
         let pid_key = format!("user_proc_{pid}");
         // Try to obtain the Process object from the Context.
-        let proc = match self.cache.get(&pid_key) {
-            Some(val) => val.downcast_ref::<Process>(),
-            None => {
-                // Not found, create it, insert it and retrieve it.
-                let proc = Box::new(Process::from_pid(pid)?);
-                self.cache.insert(pid_key.clone(), proc);
-                self.cache
-                    .get(&pid_key)
-                    .ok_or_else(|| anyhow!("Failed to insert process"))?
-                    .downcast_ref::<Process>()
-            }
+        let proc = match self.cache.entry(pid_key) {
+            Entry::Occupied(val) => val.into_mut(),
+            // Not found, create it and insert it.
+            Entry::Vacant(val) => val.insert(Box::new(Process::from_pid(pid)?)),
         }
+        .downcast_mut::<Process>()
         .ok_or_else(|| anyhow!("Failed to retrieve process information"))?;
 
+        // The probe might be in a library loaded after the Process object was
+        // created.
+        if proc.get_note_from_symbol(symbol)?.is_none() {
+            proc.refresh()?;
+        }
         let note = proc
             .get_note_from_symbol(symbol)?
             .ok_or_else(|| anyhow!("Failed to get symbol information"))?;
 
+        let args = match raw_args {
+            Some(raw_args) => Some(Self::unmarshal_args(&raw_args, &note.parse_args()?)?),
+            None => None,
+        };
+
         let user = UserEvent {
             pid,
             tid,
@@ -144,9 +183,35 @@ impl RawEventSectionFactory for UserEventFactory {
                 _ => "unknown",
             }
             .to_string(),
+            args,
         };
 
         event.userspace = Some(user);
         Ok(())
     }
 }
+
+impl UserEventFactory {
+    /// Unmarshal the raw arguments, casting them according to their
+    /// description in the USDT note.
+    fn unmarshal_args(raw: &BpfRawSection, desc: &[UsdtArg]) -> Result<Vec<i64>> {
+        let size = std::mem::size_of::<u32>() + USDT_MAX_ARG_CNT * std::mem::size_of::<i64>();
+        if raw.data.len() != size {
+            bail!(
+                "Args section data is not the expected size {} != {size}",
+                raw.data.len()
+            );
+        }
+
+        let num = u32::from_ne_bytes(raw.data[0..4].try_into()?) as usize;
+        if num > USDT_MAX_ARG_CNT || num != desc.len() {
+            bail!("Unexpected number of USDT arguments ({num})");
+        }
+
+        raw.data[4..]
+            .chunks_exact(8)
+            .zip(desc)
+            .map(|(val, arg)| Ok(arg.cast(i64::from_ne_bytes(val.try_into()?))))
+            .collect()
+    }
+}
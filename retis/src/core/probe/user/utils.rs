@@ -0,0 +1,108 @@
+use anyhow::{anyhow, bail, Result};
+
+/// Target of a USDT probe given through cli arguments.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum CliUsdtTarget {
+    /// Path to a binary. All existing and future processes running it are
+    /// probed.
+    Path(String),
+    /// Pid of a running process.
+    Pid(i32),
+    /// Command name. All existing and future processes running it are probed.
+    Cmd(String),
+    /// Shared library name, e.g. "libssl". All existing and future processes
+    /// using the libraries loaded under this name are probed. If no such
+    /// library is loaded the target is handled as a command name.
+    Lib(String),
+}
+
+/// USDT probe given through cli arguments, following the
+/// usdt:TARGET:PROVIDER::NAME pattern.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct CliUsdtProbe {
+    pub(crate) target: CliUsdtTarget,
+    /// Probe, as "provider::name".
+    pub(crate) probe: String,
+}
+
+impl CliUsdtProbe {
+    /// Parses a probe given as a cli argument. Returns None if the probe is
+    /// not a USDT one.
+    pub(crate) fn parse(input: &str) -> Result<Option<Self>> {
+        let Some(input) = input.strip_prefix("usdt:") else {
+            return Ok(None);
+        };
+
+        let (rest, name) = input
+            .rsplit_once("::")
+            .ok_or_else(|| anyhow!("Invalid USDT probe {input}: expected TARGET:PROVIDER::NAME"))?;
+        let (target, provider) = rest
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow!("Invalid USDT probe {input}: expected TARGET:PROVIDER::NAME"))?;
+
+        if target.is_empty() || provider.is_empty() || name.is_empty() {
+            bail!("Invalid USDT probe {input}: empty parts are not allowed");
+        }
+
+        let target = if target.starts_with('/') {
+            CliUsdtTarget::Path(target.to_string())
+        } else if let Ok(pid) = target.parse::<i32>() {
+            CliUsdtTarget::Pid(pid)
+        } else if target.starts_with("lib") {
+            CliUsdtTarget::Lib(target.to_string())
+        } else {
+            CliUsdtTarget::Cmd(target.to_string())
+        };
+
+        Ok(Some(CliUsdtProbe {
+            target,
+            probe: format!("{provider}::{name}"),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cli_usdt() {
+        assert!(CliUsdtProbe::parse("kprobe:consume_skb").unwrap().is_none());
+        assert!(CliUsdtProbe::parse("skb:kfree_skb").unwrap().is_none());
+
+        assert_eq!(
+            CliUsdtProbe::parse("usdt:/usr/sbin/ovs-vswitchd:main::run_start").unwrap(),
+            Some(CliUsdtProbe {
+                target: CliUsdtTarget::Path("/usr/sbin/ovs-vswitchd".to_string()),
+                probe: "main::run_start".to_string(),
+            })
+        );
+        assert_eq!(
+            CliUsdtProbe::parse("usdt:42:main::run_start")
+                .unwrap()
+                .unwrap()
+                .target,
+            CliUsdtTarget::Pid(42)
+        );
+        assert_eq!(
+            CliUsdtProbe::parse("usdt:ovs-vswitchd:dpif_recv::recv_upcall")
+                .unwrap()
+                .unwrap()
+                .target,
+            CliUsdtTarget::Cmd("ovs-vswitchd".to_string())
+        );
+        assert_eq!(
+            CliUsdtProbe::parse("usdt:libssl:openssl::handshake")
+                .unwrap()
+                .unwrap()
+                .target,
+            CliUsdtTarget::Lib("libssl".to_string())
+        );
+
+        assert!(CliUsdtProbe::parse("usdt:").is_err());
+        assert!(CliUsdtProbe::parse("usdt:ovs-vswitchd").is_err());
+        assert!(CliUsdtProbe::parse("usdt:main::run_start").is_err());
+        assert!(CliUsdtProbe::parse("usdt::main::run_start").is_err());
+        assert!(CliUsdtProbe::parse("usdt:ovs-vswitchd:main::").is_err());
+    }
+}
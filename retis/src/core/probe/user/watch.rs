@@ -0,0 +1,142 @@
+//! # USDT watcher
+//!
+//! Attach USDT probes to processes started during the collection, based on
+//! their command name. Processes not having the probe are rescanned, as it
+//! can be part of a library loaded later on (dlopen).
+
+#![cfg_attr(test, allow(unused_variables))]
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use log::{debug, info, warn};
+
+use crate::core::{
+    probe::{user::UsdtProbe, Probe, ProbeRuntimeManager},
+    user::{
+        container::Container,
+        proc::{pids_from_cmd, Process},
+    },
+};
+
+/// Minimum interval between two scans of the running processes.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A USDT probe to attach to all processes running a given command.
+struct UsdtWatch {
+    /// Command name.
+    cmd: String,
+    /// Probe, as "provider::name".
+    probe: String,
+    /// Container the processes must run in, if any.
+    container: Option<Container>,
+    /// Processes the probe was already attached to (or can't be).
+    seen: HashSet<i32>,
+    /// Processes not having the probe (yet), kept to detect newly loaded
+    /// libraries.
+    pending: HashMap<i32, Process>,
+}
+
+/// Keep track of USDT probes to attach to processes running a given command,
+/// including processes started after the collection began.
+#[derive(Default)]
+pub(crate) struct UsdtWatcher {
+    watches: Vec<UsdtWatch>,
+    last_scan: Option<Instant>,
+}
+
+impl UsdtWatcher {
+    /// Attach `probe` (as "provider::name") to all existing and future
+    /// processes running `cmd`, optionally restricted to a given container.
+    pub(crate) fn watch(&mut self, cmd: &str, probe: &str, container: Option<Container>) {
+        self.watches.push(UsdtWatch {
+            cmd: cmd.to_string(),
+            probe: probe.to_string(),
+            container,
+            seen: HashSet::new(),
+            pending: HashMap::new(),
+        });
+    }
+
+    /// Scan the running processes (at most once per SCAN_INTERVAL) and
+    /// attach the watched probes to new matching ones. Errors are not fatal
+    /// as processes can come and go at any time.
+    pub(crate) fn poll(&mut self, mgr: &mut ProbeRuntimeManager) -> Result<()> {
+        if self.watches.is_empty()
+            || self
+                .last_scan
+                .is_some_and(|last| last.elapsed() < SCAN_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.last_scan = Some(Instant::now());
+
+        let mut added = false;
+        for watch in self.watches.iter_mut() {
+            let pids = pids_from_cmd(&watch.cmd, watch.container.as_ref())?;
+            // Forget about processes which are gone, pids can be reused.
+            watch.seen.retain(|pid| pids.contains(pid));
+            watch.pending.retain(|pid, _| pids.contains(pid));
+
+            for pid in pids {
+                if watch.seen.contains(&pid) {
+                    continue;
+                }
+
+                let res = match watch.pending.get_mut(&pid) {
+                    // Only look for the probe again if new libraries were
+                    // loaded since the last scan.
+                    Some(proc) => match proc.refresh() {
+                        Ok(true) => Self::add_probe(mgr, proc, &watch.probe),
+                        Ok(false) => continue,
+                        Err(e) => Err(e),
+                    },
+                    None => Process::from_pid(pid).and_then(|proc| {
+                        let res = Self::add_probe(mgr, &proc, &watch.probe);
+                        if let Ok(false) = res {
+                            debug!("No usdt {} in {} ({pid})", watch.probe, watch.cmd);
+                            watch.pending.insert(pid, proc);
+                        }
+                        res
+                    }),
+                };
+
+                match res {
+                    Ok(true) => {
+                        info!("Attaching usdt {} to {} ({pid})", watch.probe, watch.cmd);
+                        added = true;
+                    }
+                    Ok(false) => continue,
+                    Err(e) => warn!(
+                        "Could not add usdt {} to {} ({pid}): {e}",
+                        watch.probe, watch.cmd
+                    ),
+                }
+                watch.seen.insert(pid);
+                watch.pending.remove(&pid);
+            }
+        }
+
+        if added {
+            #[cfg(not(test))]
+            mgr.attach_probes()?;
+        }
+
+        Ok(())
+    }
+
+    fn add_probe(mgr: &mut ProbeRuntimeManager, proc: &Process, probe: &str) -> Result<bool> {
+        if !proc.is_usdt(probe)? {
+            return Ok(false);
+        }
+
+        let probe = Probe::usdt(UsdtProbe::new(proc, probe)?)?;
+        #[cfg(not(test))]
+        mgr.add_targeted_probe(probe)?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,347 @@
+//! Container
+//!
+//! Module providing container lookup capabilities, so processes running in
+//! other PID namespaces can be found and probed, and listing the containers
+//! known to the container runtimes so events can be mapped to containers.
+
+use std::{fs, os::unix::fs::MetadataExt, path::Path, process::Command};
+
+use anyhow::{anyhow, bail, Result};
+use log::{debug, warn};
+
+use crate::events::ContainerInfo;
+
+/// A container, as identified by its PID namespace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Container {
+    /// Container id, as given by the user.
+    pub(crate) id: String,
+    /// Inode of the container PID namespace.
+    pidns: u64,
+}
+
+impl Container {
+    /// Look up a container given its id (or name, when the container runtime
+    /// is used). The container runtimes are queried first; if none knows
+    /// about the container, processes are looked up by their cgroup (which
+    /// contains the full container id with most runtimes).
+    pub(crate) fn from_id(id: &str) -> Result<Self> {
+        if id.is_empty() {
+            bail!("Empty container id");
+        }
+
+        let pid = match Self::runtime_lookup(id) {
+            Some(pid) => pid,
+            None => {
+                Self::cgroup_lookup(id)?.ok_or_else(|| anyhow!("Could not find container {id}"))?
+            }
+        };
+
+        Ok(Container {
+            id: id.to_string(),
+            pidns: pidns(pid)?,
+        })
+    }
+
+    /// Ask the known container runtimes for the pid of the container init
+    /// process.
+    fn runtime_lookup(id: &str) -> Option<i32> {
+        let runtimes: [(&str, &[&str]); 3] = [
+            ("podman", &["inspect", "--format", "{{.State.Pid}}"]),
+            ("docker", &["inspect", "--format", "{{.State.Pid}}"]),
+            (
+                "crictl",
+                &[
+                    "inspect",
+                    "--output",
+                    "go-template",
+                    "--template",
+                    "{{.info.pid}}",
+                ],
+            ),
+        ];
+
+        runtimes.iter().find_map(|(runtime, args)| {
+            let output = Command::new(runtime).args(*args).arg(id).output().ok()?;
+            if !output.status.success() {
+                return None;
+            }
+
+            let pid = String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse::<i32>()
+                .ok()
+                .filter(|pid| *pid > 0)?;
+            debug!("Container {id} found using {runtime} (pid {pid})");
+            Some(pid)
+        })
+    }
+
+    /// Find a process belonging to the container by looking at the processes
+    /// cgroup.
+    fn cgroup_lookup(id: &str) -> Result<Option<i32>> {
+        for entry in Path::new("/proc/").read_dir()? {
+            let entry = entry?;
+            let pid = match entry.file_name().to_str().map(|s| s.parse::<i32>()) {
+                Some(Ok(pid)) => pid,
+                _ => continue,
+            };
+
+            // Processes can go away at any time, ignore errors.
+            if let Ok(cgroup) = fs::read_to_string(entry.path().join("cgroup")) {
+                if cgroup.lines().any(|l| l.contains(id)) {
+                    debug!("Container {id} found using cgroups (pid {pid})");
+                    return Ok(Some(pid));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Does the given (host) pid belong to the container?
+    pub(crate) fn contains(&self, pid: i32) -> bool {
+        pidns(pid).is_ok_and(|ns| ns == self.pidns)
+    }
+
+    /// Translate a pid as seen from inside the container into its host pid.
+    pub(crate) fn host_pid(&self, pid: i32) -> Result<i32> {
+        for entry in Path::new("/proc/").read_dir()? {
+            let entry = entry?;
+            let host_pid = match entry.file_name().to_str().map(|s| s.parse::<i32>()) {
+                Some(Ok(pid)) => pid,
+                _ => continue,
+            };
+
+            if !self.contains(host_pid) {
+                continue;
+            }
+
+            // The last NSpid entry is the pid in the innermost namespace.
+            if let Ok(status) = fs::read_to_string(entry.path().join("status")) {
+                if nspid(&status) == Some(pid) {
+                    return Ok(host_pid);
+                }
+            }
+        }
+
+        bail!("Could not find pid {pid} in container {}", self.id)
+    }
+}
+
+/// List the containers known to the container runtimes (podman, docker and
+/// CRI runtimes through crictl), along with their netns inum and cgroup id.
+/// Runtimes not available on the system are silently skipped.
+pub(crate) fn runtime_containers() -> Vec<ContainerInfo> {
+    // Fields are separated by tabs, as names can't contain any.
+    let runtimes: [(&str, &str); 3] = [
+        (
+            "podman",
+            "{{.Id}}\t{{.Name}}\t{{.State.Pid}}\t{{.PodName}}\t",
+        ),
+        ("docker", "{{.Id}}\t{{.Name}}\t{{.State.Pid}}\t\t"),
+        (
+            "crictl",
+            "{{.status.id}}\t{{.status.metadata.name}}\t{{.info.pid}}\t\
+             {{index .status.labels \"io.kubernetes.pod.name\"}}\t\
+             {{index .status.labels \"io.kubernetes.pod.namespace\"}}",
+        ),
+    ];
+
+    let mut containers = Vec::new();
+    for (runtime, template) in runtimes.iter() {
+        let ids = match runtime_output(runtime, &["ps", "-q"]) {
+            Some(ids) => ids,
+            None => continue,
+        };
+
+        for id in ids.lines().map(str::trim).filter(|id| !id.is_empty()) {
+            // crictl does not support formatting multiple containers at once,
+            // inspect them one by one for all runtimes.
+            let info = match *runtime {
+                "crictl" => runtime_output(
+                    runtime,
+                    &[
+                        "inspect",
+                        "--output",
+                        "go-template",
+                        "--template",
+                        template,
+                        id,
+                    ],
+                ),
+                _ => runtime_output(runtime, &["inspect", "--format", template, id]),
+            };
+
+            match info
+                .as_deref()
+                .and_then(|info| parse_container_info(runtime, info))
+            {
+                Some(container) => containers.push(container),
+                None => warn!("Could not retrieve information about {runtime} container {id}"),
+            }
+        }
+    }
+
+    containers
+}
+
+/// Run a container runtime command and return its output, if successful.
+fn runtime_output(runtime: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(runtime).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse the container information reported by a runtime, as formatted by
+/// `runtime_containers`: "id\tname\tpid\tpod\tpod namespace". The netns and
+/// cgroup of the container are then retrieved using its pid.
+fn parse_container_info(runtime: &str, info: &str) -> Option<ContainerInfo> {
+    let mut fields = info.trim_end_matches('\n').split('\t');
+    let mut next = || {
+        fields
+            .next()
+            .map(str::trim)
+            .filter(|f| !f.is_empty() && *f != "<no value>")
+            .map(String::from)
+    };
+
+    let id = next()?;
+    // Docker reports names with a leading '/'.
+    let name = next()?.trim_start_matches('/').to_string();
+    let pid = next()?.parse::<i32>().ok().filter(|pid| *pid > 0);
+    let pod = next();
+    let pod_namespace = next();
+
+    Some(ContainerInfo {
+        id,
+        name,
+        runtime: runtime.to_string(),
+        pod,
+        pod_namespace,
+        pid,
+        netns: pid.and_then(|pid| netns(pid).ok()),
+        cgroup_id: pid.and_then(|pid| cgroup_id(pid).ok()),
+    })
+}
+
+/// Get the network namespace inum of a process.
+fn netns(pid: i32) -> Result<u32> {
+    let link = fs::read_link(format!("/proc/{pid}/ns/net"))?;
+    let link = link
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid network namespace link"))?;
+
+    Ok(u32::try_from(parse_ns_link(link)?)?)
+}
+
+/// Get the id of the cgroup (v2) of a process, which is the inode of its
+/// cgroup directory.
+fn cgroup_id(pid: i32) -> Result<u64> {
+    let cgroup = fs::read_to_string(format!("/proc/{pid}/cgroup"))?;
+    let path = cgroup_v2_path(&cgroup).ok_or_else(|| anyhow!("No cgroup v2 for pid {pid}"))?;
+
+    Ok(fs::metadata(format!("/sys/fs/cgroup{path}"))?.ino())
+}
+
+/// Get the cgroup v2 path of a process, from the content of its
+/// /proc/<pid>/cgroup file.
+fn cgroup_v2_path(cgroup: &str) -> Option<&str> {
+    cgroup.lines().find_map(|l| l.strip_prefix("0::"))
+}
+
+/// Get the PID namespace inode of a process.
+fn pidns(pid: i32) -> Result<u64> {
+    let link = fs::read_link(format!("/proc/{pid}/ns/pid"))?;
+    let link = link
+        .to_str()
+        .ok_or_else(|| anyhow!("Invalid PID namespace link"))?;
+
+    parse_ns_link(link)
+}
+
+/// Parse a namespace link, e.g. "pid:[4026531836]", and return its inode.
+pub(super) fn parse_ns_link(link: &str) -> Result<u64> {
+    Ok(link
+        .split_once(":[")
+        .and_then(|(_, ino)| ino.strip_suffix(']'))
+        .ok_or_else(|| anyhow!("Invalid namespace link ({link})"))?
+        .parse()?)
+}
+
+/// Get the pid of a process in its innermost PID namespace, from the content
+/// of its /proc/<pid>/status file.
+fn nspid(status: &str) -> Option<i32> {
+    status
+        .lines()
+        .find_map(|l| l.strip_prefix("NSpid:"))?
+        .split_whitespace()
+        .last()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ns_helpers() {
+        assert_eq!(parse_ns_link("pid:[4026531836]").unwrap(), 4026531836);
+        assert!(parse_ns_link("pid:4026531836").is_err());
+        assert!(parse_ns_link("pid:[foo]").is_err());
+
+        assert_eq!(
+            nspid("Name:\tbash\nNSpid:\t1234\t42\nNSpgid:\t1\n"),
+            Some(42)
+        );
+        assert_eq!(nspid("NSpid:\t1234\n"), Some(1234));
+        assert_eq!(nspid("Name:\tbash\n"), None);
+
+        assert_eq!(
+            cgroup_v2_path("1:name=systemd:/foo\n0::/system.slice/foo.scope\n"),
+            Some("/system.slice/foo.scope")
+        );
+        assert_eq!(cgroup_v2_path("1:name=systemd:/foo\n"), None);
+    }
+
+    #[test]
+    fn container_info() {
+        let info = parse_container_info("docker", "abcd\t/web\t0\t\t\n").unwrap();
+        assert_eq!(info.id, "abcd");
+        assert_eq!(info.name, "web");
+        assert_eq!(info.runtime, "docker");
+        assert!(info.pod.is_none() && info.pod_namespace.is_none());
+        assert!(info.netns.is_none() && info.cgroup_id.is_none());
+        assert_eq!(info.full_name(), "web");
+
+        let info = parse_container_info("crictl", "abcd\tnginx\t-1\tfrontend\tdefault\n").unwrap();
+        assert_eq!(info.pod.as_deref(), Some("frontend"));
+        assert_eq!(info.pod_namespace.as_deref(), Some("default"));
+        assert_eq!(info.full_name(), "default/frontend/nginx");
+        assert_eq!(info.netns_name(), "default/frontend");
+
+        let info = parse_container_info(
+            "podman",
+            &format!("abcd\tdb\t{}\t<no value>\t", std::process::id()),
+        )
+        .unwrap();
+        assert!(info.pod.is_none());
+        assert!(info.netns.is_some());
+
+        assert!(parse_container_info("podman", "abcd").is_none());
+    }
+
+    #[test]
+    fn own_pidns() {
+        let pid = std::process::id() as i32;
+        let container = Container {
+            id: "self".to_string(),
+            pidns: pidns(pid).unwrap(),
+        };
+        assert!(container.contains(pid));
+        assert!(!container.contains(-1));
+    }
+}
@@ -0,0 +1,352 @@
+//! Kubernetes
+//!
+//! Module retrieving the Kubernetes objects (pods, services and nodes) from the
+//! API server, so the addresses and interfaces found in events can be mapped
+//! to them. The API server is queried using kubectl, which handles the
+//! authentication and cluster configuration.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result};
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use super::container::parse_ns_link;
+use crate::events::{ContainerInfo, KubeInfo, KubeNode, KubePod, KubeService};
+
+/// Retrieve the Kubernetes objects using kubectl. The host side of the pod
+/// interfaces is looked up using the containers running on the local node.
+pub(crate) fn kube_info(
+    kubeconfig: Option<&Path>,
+    containers: &[ContainerInfo],
+) -> Result<KubeInfo> {
+    let kubectl = Kubectl {
+        kubeconfig: kubeconfig.map(Path::to_path_buf),
+    };
+
+    let mut pods: Vec<KubePod> = kubectl
+        .list::<Pod>("pods")?
+        .into_iter()
+        .map(KubePod::from)
+        .collect();
+    let services = kubectl
+        .list::<Service>("services")?
+        .into_iter()
+        .map(KubeService::from)
+        .collect();
+    let nodes = kubectl
+        .list::<Node>("nodes")?
+        .into_iter()
+        .map(KubeNode::from)
+        .collect();
+
+    // Pods share a netns between their containers, only look it up once.
+    let mut ifindexes: HashMap<(&str, &str), Vec<u32>> = HashMap::new();
+    for container in containers.iter() {
+        let (Some(namespace), Some(pod), Some(pid)) =
+            (&container.pod_namespace, &container.pod, container.pid)
+        else {
+            continue;
+        };
+        if ifindexes.contains_key(&(namespace.as_str(), pod.as_str())) {
+            continue;
+        }
+
+        match host_ifindexes(pid) {
+            Ok(indexes) => {
+                ifindexes.insert((namespace.as_str(), pod.as_str()), indexes);
+            }
+            Err(e) => debug!("Could not get the interfaces of pod {namespace}/{pod}: {e}"),
+        }
+    }
+    pods.iter_mut().for_each(|pod| {
+        if let Some(indexes) = ifindexes.get(&(pod.namespace.as_str(), pod.name.as_str())) {
+            pod.host_ifindexes = indexes.clone();
+        }
+    });
+
+    Ok(KubeInfo {
+        host_netns: fs::read_link("/proc/self/ns/net")
+            .ok()
+            .and_then(|link| parse_ns_link(link.to_str()?).ok())
+            .and_then(|inum| u32::try_from(inum).ok()),
+        pods,
+        services,
+        nodes,
+    })
+}
+
+/// Get the ifindexes of the peers of the interfaces of a process netns (e.g.
+/// veth peers), in their own netns. Host pod interfaces are expected to be
+/// in the host netns.
+fn host_ifindexes(pid: i32) -> Result<Vec<u32>> {
+    let output = Command::new("nsenter")
+        .args(["-t", &pid.to_string(), "-n", "ip", "-j", "link", "show"])
+        .output()?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    parse_link_peers(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the output of `ip -j link show` and return the peer ifindexes of the
+/// interfaces in another netns.
+fn parse_link_peers(links: &str) -> Result<Vec<u32>> {
+    #[derive(Deserialize)]
+    struct Link {
+        link_index: Option<u32>,
+        link_netnsid: Option<i32>,
+    }
+
+    Ok(serde_json::from_str::<Vec<Link>>(links)?
+        .into_iter()
+        .filter(|link| link.link_netnsid.is_some())
+        .filter_map(|link| link.link_index)
+        .collect())
+}
+
+/// kubectl wrapper.
+struct Kubectl {
+    kubeconfig: Option<PathBuf>,
+}
+
+impl Kubectl {
+    /// List all the objects of a given kind, in all namespaces.
+    fn list<T: DeserializeOwned>(&self, kind: &str) -> Result<Vec<T>> {
+        let mut cmd = Command::new("kubectl");
+        if let Some(kubeconfig) = &self.kubeconfig {
+            cmd.arg("--kubeconfig").arg(kubeconfig);
+        }
+        let output = cmd
+            .args(["get", kind, "--all-namespaces", "--output", "json"])
+            .output()
+            .context("Could not run kubectl")?;
+        if !output.status.success() {
+            bail!(
+                "Could not list Kubernetes {kind}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let list: List<T> = serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("Could not parse the Kubernetes {kind}"))?;
+        if list.items.is_empty() {
+            warn!("No Kubernetes {kind} found");
+        }
+        Ok(list.items)
+    }
+}
+
+// Subset of the Kubernetes API objects used to build the mapping.
+
+#[derive(Deserialize)]
+struct List<T> {
+    items: Vec<T>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Metadata {
+    name: String,
+    namespace: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Address {
+    ip: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Pod {
+    metadata: Metadata,
+    spec: PodSpec,
+    status: PodStatus,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct PodSpec {
+    node_name: Option<String>,
+    host_network: bool,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct PodStatus {
+    #[serde(rename = "podIPs")]
+    pod_ips: Vec<Address>,
+}
+
+impl From<Pod> for KubePod {
+    fn from(pod: Pod) -> Self {
+        KubePod {
+            name: pod.metadata.name,
+            namespace: pod.metadata.namespace.unwrap_or_default(),
+            node: pod.spec.node_name,
+            // Pods using the host network report the node addresses.
+            ips: match pod.spec.host_network {
+                true => Vec::new(),
+                false => pod
+                    .status
+                    .pod_ips
+                    .into_iter()
+                    .filter_map(|a| a.ip)
+                    .collect(),
+            },
+            host_ifindexes: Vec::new(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Service {
+    metadata: Metadata,
+    spec: ServiceSpec,
+    status: ServiceStatus,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ServiceSpec {
+    #[serde(rename = "clusterIPs")]
+    cluster_ips: Vec<String>,
+    #[serde(rename = "externalIPs")]
+    external_ips: Vec<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ServiceStatus {
+    load_balancer: LoadBalancerStatus,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct LoadBalancerStatus {
+    ingress: Vec<Address>,
+}
+
+impl From<Service> for KubeService {
+    fn from(service: Service) -> Self {
+        KubeService {
+            name: service.metadata.name,
+            namespace: service.metadata.namespace.unwrap_or_default(),
+            ips: service
+                .spec
+                .cluster_ips
+                .into_iter()
+                .chain(service.spec.external_ips)
+                .chain(
+                    service
+                        .status
+                        .load_balancer
+                        .ingress
+                        .into_iter()
+                        .filter_map(|a| a.ip),
+                )
+                // Headless services have "None" as a cluster IP.
+                .filter(|ip| ip != "None")
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Node {
+    metadata: Metadata,
+    status: NodeStatus,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct NodeStatus {
+    addresses: Vec<NodeAddress>,
+}
+
+#[derive(Deserialize)]
+struct NodeAddress {
+    #[serde(rename = "type")]
+    kind: String,
+    address: String,
+}
+
+impl From<Node> for KubeNode {
+    fn from(node: Node) -> Self {
+        KubeNode {
+            name: node.metadata.name,
+            ips: node
+                .status
+                .addresses
+                .into_iter()
+                .filter(|a| a.kind == "InternalIP" || a.kind == "ExternalIP")
+                .map(|a| a.address)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kube_objects() {
+        let pods: List<Pod> = serde_json::from_str(
+            r#"{"items":[
+                {"metadata":{"name":"web","namespace":"default"},"spec":{"nodeName":"node1"},"status":{"podIP":"10.244.0.5","podIPs":[{"ip":"10.244.0.5"},{"ip":"fd00::5"}]}},
+                {"metadata":{"name":"kube-proxy","namespace":"kube-system"},"spec":{"nodeName":"node1","hostNetwork":true},"status":{"podIPs":[{"ip":"192.168.0.10"}]}},
+                {"metadata":{"name":"pending","namespace":"default"},"spec":{},"status":{}}
+            ]}"#,
+        )
+        .unwrap();
+        let pods: Vec<KubePod> = pods.items.into_iter().map(KubePod::from).collect();
+        assert_eq!(pods[0].name, "web");
+        assert_eq!(pods[0].namespace, "default");
+        assert_eq!(pods[0].node.as_deref(), Some("node1"));
+        assert_eq!(pods[0].ips, vec!["10.244.0.5", "fd00::5"]);
+        assert!(pods[1].ips.is_empty());
+        assert!(pods[2].node.is_none() && pods[2].ips.is_empty());
+
+        let services: List<Service> = serde_json::from_str(
+            r#"{"items":[
+                {"metadata":{"name":"web","namespace":"default"},"spec":{"clusterIPs":["10.96.0.10"],"externalIPs":["192.168.0.100"]},"status":{"loadBalancer":{"ingress":[{"ip":"172.18.0.1"},{"hostname":"lb.example.com"}]}}},
+                {"metadata":{"name":"headless","namespace":"default"},"spec":{"clusterIPs":["None"]}}
+            ]}"#,
+        )
+        .unwrap();
+        let services: Vec<KubeService> =
+            services.items.into_iter().map(KubeService::from).collect();
+        assert_eq!(
+            services[0].ips,
+            vec!["10.96.0.10", "192.168.0.100", "172.18.0.1"]
+        );
+        assert!(services[1].ips.is_empty());
+
+        let nodes: List<Node> = serde_json::from_str(
+            r#"{"items":[{"metadata":{"name":"node1"},"status":{"addresses":[{"type":"InternalIP","address":"192.168.0.10"},{"type":"Hostname","address":"node1"}]}}]}"#,
+        )
+        .unwrap();
+        let node = KubeNode::from(nodes.items.into_iter().next().unwrap());
+        assert_eq!(node.name, "node1");
+        assert_eq!(node.ips, vec!["192.168.0.10"]);
+    }
+
+    #[test]
+    fn link_peers() {
+        let links = r#"[
+            {"ifindex":1,"ifname":"lo","flags":["LOOPBACK","UP"],"link_type":"loopback"},
+            {"ifindex":2,"link_index":12,"ifname":"eth0","flags":["UP"],"link_type":"ether","link_netnsid":0}
+        ]"#;
+        assert_eq!(parse_link_peers(links).unwrap(), vec![12]);
+        assert!(parse_link_peers("foo").is_err());
+    }
+}
@@ -1,3 +1,6 @@
 //! # Userspace helpers
 
+pub(crate) mod container;
+pub(crate) mod kube;
 pub(crate) mod proc;
+pub(crate) mod symbols;
@@ -10,6 +10,7 @@ use std::{
     fmt, fs,
     io::{BufRead, BufReader, Cursor},
     ops::Bound::{Included, Unbounded},
+    os::unix::fs::FileExt,
     path::{Path, PathBuf},
 };
 
@@ -22,6 +23,8 @@ use byteorder::ReadBytesExt;
 use elf::{endian::AnyEndian, note::Note, ElfStream};
 use log::warn;
 
+use super::container::Container;
+
 /// Integer to represent all pids.
 const PID_ALL: i32 = -1;
 /// The standard ELF Note type for systemtap information.
@@ -193,6 +196,140 @@ impl UsdtNote {
     }
 }
 
+impl UsdtNote {
+    /// Parse the argument description string into a list of arguments.
+    pub(crate) fn parse_args(&self) -> Result<Vec<UsdtArg>> {
+        let mut args = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+
+        // Arguments are separated by spaces, but some architectures (e.g.
+        // aarch64) use spaces inside brackets for memory operands.
+        for (i, c) in self.args.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                ' ' if depth == 0 => {
+                    if i > start {
+                        args.push(self.args[start..i].parse()?);
+                    }
+                    start = i + 1;
+                }
+                _ => (),
+            }
+        }
+        if start < self.args.len() {
+            args.push(self.args[start..].parse()?);
+        }
+
+        Ok(args)
+    }
+}
+
+/// Location of a USDT argument.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum UsdtArgLoc {
+    /// Constant value.
+    Const(i64),
+    /// Value held in a register.
+    Reg(String),
+    /// Value stored in memory, at a register value plus an offset.
+    Mem { reg: String, off: i64 },
+}
+
+/// USDT argument, as described in the note argument description string, e.g.
+/// "-4@%eax", "8@-8(%rbp)", "4@$5" or "8@[sp, 16]".
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct UsdtArg {
+    /// Size of the argument, in bytes.
+    pub(crate) size: u8,
+    /// Is the argument signed?
+    pub(crate) signed: bool,
+    /// Where the argument value can be found.
+    pub(crate) loc: UsdtArgLoc,
+}
+
+impl UsdtArg {
+    /// Cast a raw argument value to the argument size and signedness.
+    pub(crate) fn cast(&self, raw: i64) -> i64 {
+        let shift = 64 - (self.size as u32) * 8;
+        match (shift, self.signed) {
+            (0, _) => raw,
+            (_, true) => (raw << shift) >> shift,
+            (_, false) => ((raw as u64) << shift >> shift) as i64,
+        }
+    }
+
+    fn parse_int(s: &str) -> Result<i64> {
+        let (neg, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let val = match s.strip_prefix("0x") {
+            Some(hex) => i64::from_str_radix(hex, 16)?,
+            None => s.parse::<i64>()?,
+        };
+        Ok(if neg { -val } else { val })
+    }
+}
+
+impl std::str::FromStr for UsdtArg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (size, loc) = s
+            .split_once('@')
+            .ok_or_else(|| anyhow!("Invalid USDT argument '{s}': missing size"))?;
+
+        let size = size.parse::<i8>()?;
+        let signed = size < 0;
+        let size = size.unsigned_abs();
+        if ![1, 2, 4, 8].contains(&size) {
+            bail!("Invalid USDT argument '{s}': unsupported size {size}");
+        }
+
+        let loc = if let Some(val) = loc.strip_prefix('$') {
+            // x86 constant.
+            UsdtArgLoc::Const(Self::parse_int(val)?)
+        } else if let Some(reg) = loc.strip_prefix('%') {
+            // x86 register.
+            UsdtArgLoc::Reg(reg.to_string())
+        } else if let Some(mem) = loc.strip_suffix(')') {
+            // x86 memory operand: [off](%reg)
+            let (off, reg) = mem
+                .split_once("(%")
+                .ok_or_else(|| anyhow!("Invalid USDT argument '{s}'"))?;
+            UsdtArgLoc::Mem {
+                reg: reg.to_string(),
+                off: match off {
+                    "" => 0,
+                    off => Self::parse_int(off)?,
+                },
+            }
+        } else if let Some(mem) = loc.strip_prefix('[').and_then(|m| m.strip_suffix(']')) {
+            // aarch64 memory operand: [reg[, off]]
+            let (reg, off) = match mem.split_once(',') {
+                Some((reg, off)) => (reg.trim(), Self::parse_int(off.trim())?),
+                None => (mem.trim(), 0),
+            };
+            UsdtArgLoc::Mem {
+                reg: reg.to_string(),
+                off,
+            }
+        } else if let Ok(val) = Self::parse_int(loc) {
+            // aarch64 constant.
+            UsdtArgLoc::Const(val)
+        } else if !loc.is_empty() && loc.chars().all(|c| c.is_ascii_alphanumeric()) {
+            // aarch64 register.
+            UsdtArgLoc::Reg(loc.to_string())
+        } else {
+            bail!("Invalid USDT argument '{s}'");
+        };
+
+        Ok(UsdtArg { size, signed, loc })
+    }
+}
+
 /// Allow nice log messages.
 impl fmt::Display for UsdtNote {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -331,6 +468,26 @@ impl Process {
             });
         }
 
+        let (exec_map, mut libs_map) = Self::read_maps(pid, &bin_path)?;
+
+        let pie = is_shared_library(&bin_path);
+        let exec = Binary::new_loaded(bin_path, exec_map)?;
+        // library objects are stored in a BTreeMap indexed by its map's addr_start for fast lookups.
+        let mut libs = BTreeMap::new();
+        for (path, map) in libs_map.drain() {
+            libs.insert(map.addr_start, Binary::new_loaded(path, map)?);
+        }
+        Ok(Process {
+            pid,
+            exec,
+            libs,
+            pie,
+        })
+    }
+
+    /// Read the memory mappings of the executable and of its shared
+    /// libraries (indexed by their path).
+    fn read_maps(pid: i32, bin_path: &Path) -> Result<(Map, HashMap<PathBuf, Map>)> {
         // Process Map objects for both exec and library binaries.
         let map_entries = get_process_maps(pid)?;
         // Temporarily store library maps in a path-indexed HashMap.
@@ -344,7 +501,7 @@ impl Process {
                 addr_end: map_entry.addr_end,
             };
 
-            if path.eq(&bin_path) {
+            if path.as_path() == bin_path {
                 exec_map.extend(&map);
             // Check the path is one of a library as /proc/<pid>/maps contains
             // all mapped files, e.g. /dev/zero.
@@ -353,57 +510,55 @@ impl Process {
             }
         }
 
-        let pie = is_shared_library(&bin_path);
-        let exec = Binary::new_loaded(bin_path, exec_map)?;
-        // library objects are stored in a BTreeMap indexed by its map's addr_start for fast lookups.
-        let mut libs = BTreeMap::new();
-        for (path, map) in libs_map.drain() {
-            libs.insert(map.addr_start, Binary::new_loaded(path, map)?);
-        }
-        Ok(Process {
-            pid,
-            exec,
-            libs,
-            pie,
-        })
+        Ok((exec_map, libs_map))
     }
 
-    /// Create a new Process object with a specific cmd.
-    pub(crate) fn from_cmd(cmd: &str) -> Result<Process> {
-        let mut pid: i32 = PID_ALL;
-        // Look in /proc for a process with this cmd.
-        for entry in Path::new("/proc/").read_dir()? {
-            let entry = entry?;
-            if !entry.path().is_dir()
-                || !entry.path().join("comm").exists()
-                || fs::read_to_string(entry.path().join("comm"))?
-                    .trim()
-                    .ne(cmd)
-            {
-                continue;
-            }
+    /// Refresh the memory mappings of the process, e.g. to find libraries
+    /// loaded (dlopen) or unloaded after the Process object was created. Only
+    /// newly mapped libraries are inspected. Returns true if new libraries
+    /// were found.
+    pub(crate) fn refresh(&mut self) -> Result<bool> {
+        if self.pid == PID_ALL {
+            return Ok(false);
+        }
 
-            // Return a specific error indicating there are more than once process with this
-            // cmd so that the user can decide which one to probe.
-            if pid != PID_ALL {
-                bail!(ProcessError::TooMany);
-            }
-            pid = match entry
-                .file_name()
-                .into_string()
-                .map_err(|s| anyhow!("Unable to convert path into string {:?}", s))?
-                .parse::<i32>()
-            {
-                Ok(pid) => pid,
-                Err(_) => {
-                    continue;
+        let (exec_map, mut libs_map) = Self::read_maps(self.pid, &self.exec.path)?;
+        self.exec.map = exec_map;
+
+        let mut known: HashMap<PathBuf, Binary> = std::mem::take(&mut self.libs)
+            .into_values()
+            .map(|lib| (lib.path.clone(), lib))
+            .collect();
+        let mut new = false;
+        for (path, map) in libs_map.drain() {
+            let lib = match known.remove(&path) {
+                Some(mut lib) => {
+                    lib.map = map;
+                    lib
+                }
+                None => {
+                    new = true;
+                    Binary::new_loaded(path, map)?
                 }
             };
+            self.libs.insert(lib.map.addr_start, lib);
         }
-        if pid == PID_ALL {
-            bail!(ProcessError::NotFound);
+
+        Ok(new)
+    }
+
+    /// Create a new Process object with a specific cmd, optionally running in
+    /// a given container.
+    pub(crate) fn from_cmd(cmd: &str, container: Option<&Container>) -> Result<Process> {
+        let pids = pids_from_cmd(cmd, container)?;
+        match pids.len() {
+            0 => bail!(ProcessError::NotFound),
+            // Return a specific error indicating there are more than once
+            // process with this cmd so that the user can decide which one to
+            // probe.
+            1 => Process::from_pid(pids[0]),
+            _ => bail!(ProcessError::TooMany),
         }
-        Process::from_pid(pid)
     }
 
     /// Create a new Process object that represent all existing and future processes with a
@@ -472,6 +627,83 @@ impl Process {
     }
 }
 
+/// Returns the pids of all the processes running a given cmd, optionally
+/// restricted to the ones running in a given container.
+pub(crate) fn pids_from_cmd(cmd: &str, container: Option<&Container>) -> Result<Vec<i32>> {
+    let mut pids = Vec::new();
+
+    // Look in /proc for processes with this cmd.
+    for entry in Path::new("/proc/").read_dir()? {
+        let entry = entry?;
+        let pid = match entry.file_name().to_str().map(|s| s.parse::<i32>()) {
+            Some(Ok(pid)) => pid,
+            _ => continue,
+        };
+
+        // Processes can go away at any time, ignore errors.
+        match fs::read_to_string(entry.path().join("comm")) {
+            Ok(comm) if comm.trim() == cmd => (),
+            _ => continue,
+        }
+
+        if container.is_none_or(|c| c.contains(pid)) {
+            pids.push(pid);
+        }
+    }
+
+    Ok(pids)
+}
+
+/// Returns the paths of the shared libraries matching a given name (see
+/// `lib_matches`) loaded by the running processes, optionally restricted to
+/// the ones running in a given container. Paths of libraries loaded in a
+/// container are accessed through the process root.
+pub(crate) fn libs_from_name(name: &str, container: Option<&Container>) -> Result<Vec<PathBuf>> {
+    let mut libs = Vec::new();
+
+    for entry in Path::new("/proc/").read_dir()? {
+        let entry = entry?;
+        let pid = match entry.file_name().to_str().map(|s| s.parse::<i32>()) {
+            Some(Ok(pid)) => pid,
+            _ => continue,
+        };
+
+        if container.is_some_and(|c| !c.contains(pid)) {
+            continue;
+        }
+
+        // Processes can go away at any time, ignore errors.
+        let Ok(maps) = get_process_maps(pid) else {
+            continue;
+        };
+        for path in maps
+            .iter()
+            .filter(|m| m.is_file())
+            .map(|m| Path::new(&m.path))
+            .filter(|path| lib_matches(path, name))
+        {
+            let path = match container {
+                Some(_) => entry.path().join("root").join(path.strip_prefix("/")?),
+                None => path.to_path_buf(),
+            };
+            if !libs.contains(&path) && is_shared_library(&path) {
+                libs.push(path);
+            }
+        }
+    }
+
+    Ok(libs)
+}
+
+/// Does a library path match a given name? Names are either the full file
+/// name or its part before the ".so" extension, e.g. "libssl.so.3" and
+/// "libssl" both match "/usr/lib64/libssl.so.3".
+fn lib_matches(path: &Path, name: &str) -> bool {
+    path.file_name()
+        .and_then(|f| f.to_str())
+        .is_some_and(|f| f == name || f.starts_with(&format!("{name}.so")))
+}
+
 /// Check if a path is a shared library.
 ///
 /// There are some difficulties in checking the above:
@@ -544,6 +776,32 @@ impl ProcMapEntry {
     }
 }
 
+/// File backed memory mappings of a process, used to translate addresses in
+/// its virtual memory into offsets in the object files.
+#[derive(Debug, Default)]
+pub(crate) struct ProcMaps(Vec<ProcMapEntry>);
+
+impl ProcMaps {
+    /// Retrieve the memory mappings of a given pid.
+    pub(crate) fn from_pid(pid: i32) -> Result<ProcMaps> {
+        Ok(ProcMaps(
+            get_process_maps(pid)?
+                .into_iter()
+                .filter(|m| m.is_file())
+                .collect(),
+        ))
+    }
+
+    /// Returns the path of the object file an address belongs to and the
+    /// offset of the address in that file.
+    pub(crate) fn resolve(&self, addr: u64) -> Option<(&str, u64)> {
+        self.0
+            .iter()
+            .find(|m| addr >= m.addr_start && addr < m.addr_end)
+            .map(|m| (m.path.as_str(), addr - m.addr_start + m.offset))
+    }
+}
+
 /// Returns the list of ProcMapEntry objects of a given pid.
 fn get_process_maps(pid: i32) -> Result<Vec<ProcMapEntry>> {
     let mut maps = Vec::new();
@@ -559,6 +817,34 @@ fn get_process_maps(pid: i32) -> Result<Vec<ProcMapEntry>> {
     Ok(maps)
 }
 
+/// Read the value of a USDT semaphore in a running process. Semaphores are
+/// given by their link-time address in the object file they are defined in.
+pub(crate) fn read_semaphore(pid: i32, path: &Path, sema_addr: u64) -> Result<u16> {
+    let file = fs::File::open(path)?;
+    let elf = ElfStream::<AnyEndian, _>::open_stream(file)?;
+
+    // Translate the link-time address into an offset in the object file.
+    let offset = elf
+        .segments()
+        .iter()
+        .filter(|phdr| phdr.p_type == elf::abi::PT_LOAD)
+        .find(|phdr| sema_addr >= phdr.p_vaddr && sema_addr < phdr.p_vaddr + phdr.p_filesz)
+        .map(|phdr| sema_addr - phdr.p_vaddr + phdr.p_offset)
+        .ok_or_else(|| anyhow!("Semaphore {sema_addr:#x} is not in a loadable segment"))?;
+
+    // Then look where this offset is mapped in the process.
+    let addr = get_process_maps(pid)?
+        .iter()
+        .filter(|m| Path::new(&m.path) == path)
+        .find(|m| offset >= m.offset && offset < m.offset + (m.addr_end - m.addr_start))
+        .map(|m| offset - m.offset + m.addr_start)
+        .ok_or_else(|| anyhow!("Semaphore {sema_addr:#x} is not mapped in process {pid}"))?;
+
+    let mut val = [0u8; 2];
+    fs::File::open(format!("/proc/{pid}/mem"))?.read_exact_at(&mut val, addr)?;
+    Ok(u16::from_ne_bytes(val))
+}
+
 /// Information of a single thread.
 #[derive(Debug)]
 pub(crate) struct ThreadInfo {
@@ -609,7 +895,7 @@ mod tests {
     };
 
     use super::*;
-    use probe::probe;
+    use probe::{probe, probe_lazy};
 
     #[test]
     fn process_create() -> Result<()> {
@@ -624,7 +910,7 @@ mod tests {
 
     #[test]
     fn process_from_cmd() -> Result<()> {
-        let p = Process::from_cmd("cargo");
+        let p = Process::from_cmd("cargo", None);
         // The test should have been run by cargo so there's at least one process running with that
         // cmd.
         assert!(
@@ -634,7 +920,7 @@ mod tests {
                 || p.is_ok()
         );
 
-        let p = Process::from_cmd("_no_way_a_process_with_this_cmd_exists__");
+        let p = Process::from_cmd("_no_way_a_process_with_this_cmd_exists__", None);
         assert!(
             p.is_err()
                 && p.unwrap_err().downcast_ref::<ProcessError>() == Some(&ProcessError::NotFound)
@@ -673,6 +959,136 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn process_refresh() -> Result<()> {
+        let mut p = Process::from_pid(std::process::id() as i32)?;
+        let libs = p.libs.len();
+        assert!(!p.refresh()?);
+        assert_eq!(p.libs.len(), libs);
+
+        // Simulate libraries being loaded after the process was inspected.
+        p.libs.clear();
+        assert_eq!(p.refresh()?, libs > 0);
+        assert_eq!(p.libs.len(), libs);
+
+        assert!(!Process::all("/bin/sh")?.refresh()?);
+        Ok(())
+    }
+
+    #[test]
+    fn lib_name() -> Result<()> {
+        assert!(lib_matches(Path::new("/usr/lib64/libssl.so.3"), "libssl"));
+        assert!(lib_matches(Path::new("/usr/lib64/libssl.so"), "libssl"));
+        assert!(lib_matches(
+            Path::new("/usr/lib64/libssl.so.3"),
+            "libssl.so.3"
+        ));
+        assert!(!lib_matches(Path::new("/usr/lib64/libssl3.so"), "libssl"));
+        assert!(!lib_matches(Path::new("/usr/lib64/libssl.so.3"), "libss"));
+
+        // The test binary is dynamically linked against the libc.
+        let libs = libs_from_name("libc", None)?;
+        assert!(!libs.is_empty());
+        assert!(libs.iter().all(|l| lib_matches(l, "libc")));
+        assert!(libs_from_name("_no_way_this_lib_exists_", None)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn usdt_semaphore() -> Result<()> {
+        // Lazy probes are gated by a semaphore.
+        probe_lazy!(test_provider, test_semaphore, 1);
+
+        let p = Process::from_pid(std::process::id() as i32)?;
+        let (path, note) = p.get_note("test_provider::test_semaphore")?.unwrap();
+        assert!(note.sema_addr != 0);
+        // Nothing is attached to the probe.
+        assert_eq!(read_semaphore(p.pid(), path, note.sema_addr as u64)?, 0);
+
+        let (path, note) = p.get_note("test_provider::test_function")?.unwrap();
+        assert!(note.sema_addr == 0);
+        assert!(read_semaphore(-1, path, 0x1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn usdt_args() -> Result<()> {
+        let note = |args: &str| UsdtNote {
+            provider: "p".to_string(),
+            name: "n".to_string(),
+            addr: 0,
+            base_addr: 0,
+            sema_addr: 0,
+            args: args.to_string(),
+        };
+
+        let args = note("-4@%eax 8@-8(%rbp) 2@$5 1@(%rdi) 8@0x10(%rsp)").parse_args()?;
+        assert_eq!(
+            args,
+            vec![
+                UsdtArg {
+                    size: 4,
+                    signed: true,
+                    loc: UsdtArgLoc::Reg("eax".to_string())
+                },
+                UsdtArg {
+                    size: 8,
+                    signed: false,
+                    loc: UsdtArgLoc::Mem {
+                        reg: "rbp".to_string(),
+                        off: -8
+                    }
+                },
+                UsdtArg {
+                    size: 2,
+                    signed: false,
+                    loc: UsdtArgLoc::Const(5)
+                },
+                UsdtArg {
+                    size: 1,
+                    signed: false,
+                    loc: UsdtArgLoc::Mem {
+                        reg: "rdi".to_string(),
+                        off: 0
+                    }
+                },
+                UsdtArg {
+                    size: 8,
+                    signed: false,
+                    loc: UsdtArgLoc::Mem {
+                        reg: "rsp".to_string(),
+                        off: 16
+                    }
+                },
+            ]
+        );
+
+        let args = note("-8@x0 4@[sp, 16] 8@[x1] -4@-3").parse_args()?;
+        assert_eq!(args.len(), 4);
+        assert_eq!(args[0].loc, UsdtArgLoc::Reg("x0".to_string()));
+        assert_eq!(
+            args[1].loc,
+            UsdtArgLoc::Mem {
+                reg: "sp".to_string(),
+                off: 16
+            }
+        );
+        assert_eq!(args[3].loc, UsdtArgLoc::Const(-3));
+
+        assert!(note("").parse_args()?.is_empty());
+        assert!(note("%eax").parse_args().is_err());
+        assert!(note("3@%eax").parse_args().is_err());
+        assert!(note("4@(%eax").parse_args().is_err());
+
+        // Casting.
+        assert_eq!(args[0].cast(-1), -1);
+        assert_eq!(note("-4@%eax").parse_args()?[0].cast(0xffffffff), -1);
+        assert_eq!(note("4@%eax").parse_args()?[0].cast(-1), 0xffffffff);
+        assert_eq!(note("-1@%al").parse_args()?[0].cast(0x1ff), -1);
+
+        Ok(())
+    }
+
     #[test]
     fn shared_libs() -> Result<()> {
         let p = Process::from_pid(std::process::id() as i32)?;
@@ -722,4 +1138,39 @@ mod tests {
         done.wait();
         Ok(())
     }
+
+    #[test]
+    fn proc_maps() -> Result<()> {
+        let maps = ProcMaps(
+            [
+                "5594f8dce000-5594f8dd7000 r--p 00000000 00:1f 3526003    /usr/bin/kitty",
+                "5594f8dd7000-5594f8de0000 r-xp 00009000 00:1f 3526003    /usr/bin/kitty",
+                "7ffd2a5e0000-7ffd2a601000 rw-p 00000000 00:00 0          [stack]",
+            ]
+            .iter()
+            .map(|l| ProcMapEntry::from_string(l.to_string()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|m| m.is_file())
+            .collect(),
+        );
+
+        assert_eq!(maps.resolve(0x5594f8dce010), Some(("/usr/bin/kitty", 0x10)));
+        assert_eq!(
+            maps.resolve(0x5594f8dd7100),
+            Some(("/usr/bin/kitty", 0x9100))
+        );
+        assert!(maps.resolve(0x7ffd2a5e0100).is_none());
+        assert!(maps.resolve(0x1000).is_none());
+
+        let maps = ProcMaps::from_pid(std::process::id() as i32)?;
+        let (path, _) = maps
+            .resolve(proc_maps as fn() -> Result<()> as usize as u64)
+            .ok_or_else(|| anyhow!("Could not resolve test function address"))?;
+        assert_eq!(
+            PathBuf::from(path),
+            PathBuf::from("/proc/self/exe").read_link()?
+        );
+        Ok(())
+    }
 }
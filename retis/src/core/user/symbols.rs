@@ -0,0 +1,134 @@
+//! Symbols
+//!
+//! Module providing symbol resolution for userspace object files, using their
+//! ELF symbol tables.
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use elf::{
+    abi::{PT_LOAD, STT_FUNC},
+    endian::AnyEndian,
+    string_table::StringTable,
+    symbol::SymbolTable,
+    ElfStream,
+};
+
+/// Function symbols of an ELF object file.
+#[derive(Debug, Default)]
+pub(crate) struct ElfSymbols {
+    /// Loadable segments, as (file offset, virtual address, size).
+    segments: Vec<(u64, u64, u64)>,
+    /// Function symbols, as (address, size, name) and sorted by address.
+    symbols: Vec<(u64, u64, String)>,
+}
+
+impl ElfSymbols {
+    /// Load the function symbols of an object file. Both the static and the
+    /// dynamic symbol tables are used, as stripped binaries only have the
+    /// latter.
+    pub(crate) fn from_path(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path)?;
+        let mut elf = ElfStream::<AnyEndian, _>::open_stream(file)?;
+
+        let segments = elf
+            .segments()
+            .iter()
+            .filter(|phdr| phdr.p_type == PT_LOAD)
+            .map(|phdr| (phdr.p_offset, phdr.p_vaddr, phdr.p_filesz))
+            .collect();
+
+        let mut symbols = Vec::new();
+        if let Some((symtab, strtab)) = elf.symbol_table()? {
+            Self::functions(&symtab, &strtab, &mut symbols)?;
+        }
+        if let Some((symtab, strtab)) = elf.dynamic_symbol_table()? {
+            Self::functions(&symtab, &strtab, &mut symbols)?;
+        }
+
+        Ok(Self::new(segments, symbols))
+    }
+
+    /// Retrieve the function symbols of a symbol table.
+    fn functions(
+        symtab: &SymbolTable<AnyEndian>,
+        strtab: &StringTable,
+        symbols: &mut Vec<(u64, u64, String)>,
+    ) -> Result<()> {
+        symtab
+            .iter()
+            .filter(|sym| sym.st_symtype() == STT_FUNC && sym.st_value != 0)
+            .try_for_each(|sym| {
+                let name = strtab.get(sym.st_name as usize)?;
+                symbols.push((sym.st_value, sym.st_size, name.to_string()));
+                Ok(())
+            })
+    }
+
+    fn new(segments: Vec<(u64, u64, u64)>, mut symbols: Vec<(u64, u64, String)>) -> Self {
+        symbols.sort();
+        symbols.dedup_by_key(|(addr, _, _)| *addr);
+        ElfSymbols { segments, symbols }
+    }
+
+    /// Translate an offset in the object file into a virtual address, as used
+    /// by the symbol tables.
+    fn vaddr(&self, offset: u64) -> Option<u64> {
+        self.segments
+            .iter()
+            .find(|(off, _, size)| offset >= *off && offset < off + size)
+            .map(|(off, vaddr, _)| offset - off + vaddr)
+    }
+
+    /// Returns the symbol (and the offset in it) an offset in the object file
+    /// belongs to, formatted as "symbol+offset".
+    pub(crate) fn lookup(&self, offset: u64) -> Option<String> {
+        let addr = self.vaddr(offset)?;
+        let (start, size, name) = match self.symbols.partition_point(|(a, _, _)| *a <= addr) {
+            0 => return None,
+            i => &self.symbols[i - 1],
+        };
+
+        // Symbols without a size can't be checked, trust them.
+        if *size != 0 && addr >= start + size {
+            return None;
+        }
+        Some(format!("{name}+{:#x}", addr - start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        let symbols = ElfSymbols::new(
+            vec![(0, 0, 0x1000), (0x1000, 0x401000, 0x2000)],
+            vec![
+                (0x401100, 0x10, "foo".to_string()),
+                (0x401000, 0x100, "main".to_string()),
+                (0x402000, 0, "bar".to_string()),
+            ],
+        );
+
+        assert_eq!(symbols.lookup(0x1010).as_deref(), Some("main+0x10"));
+        assert_eq!(symbols.lookup(0x1100).as_deref(), Some("foo+0x0"));
+        assert!(symbols.lookup(0x1110).is_none());
+        assert_eq!(symbols.lookup(0x2100).as_deref(), Some("bar+0x100"));
+        // Not in a loadable segment.
+        assert!(symbols.lookup(0x4000).is_none());
+        // Before the first symbol.
+        assert!(symbols.lookup(0x10).is_none());
+    }
+
+    #[test]
+    fn own_symbols() {
+        let symbols = ElfSymbols::from_path(Path::new("/proc/self/exe")).unwrap();
+        assert!(!symbols.symbols.is_empty());
+        assert!(symbols
+            .symbols
+            .iter()
+            .any(|(_, _, name)| name.contains("main")));
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    fmt, fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand};
+use log::{debug, warn};
+
+use crate::cli::*;
+
+/// Default path of the control socket.
+pub(crate) const DEFAULT_CTL_SOCKET: &str = "/run/retis/ctl.sock";
+
+/// Requests which can be sent to a running collection.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum CtlRequest {
+    /// Add (or re-enable) a probe.
+    ProbeAdd(String),
+    /// Disable a probe.
+    ProbeDel(String),
+}
+
+impl FromStr for CtlRequest {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words = s.split_whitespace();
+        let req = match (words.next(), words.next(), words.next()) {
+            (Some("probe"), Some("add"), Some(probe)) => CtlRequest::ProbeAdd(probe.to_string()),
+            (Some("probe"), Some("del"), Some(probe)) => CtlRequest::ProbeDel(probe.to_string()),
+            _ => bail!("Invalid request '{}'", s.trim()),
+        };
+
+        if words.next().is_some() {
+            bail!("Invalid request '{}': trailing data", s.trim());
+        }
+        Ok(req)
+    }
+}
+
+impl fmt::Display for CtlRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CtlRequest::ProbeAdd(probe) => write!(f, "probe add {probe}"),
+            CtlRequest::ProbeDel(probe) => write!(f, "probe del {probe}"),
+        }
+    }
+}
+
+/// Server side of the control interface. Requests are handled one at a time
+/// and synchronously, from the collection loop.
+pub(crate) struct CtlServer {
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+impl CtlServer {
+    /// Bind the control socket. A stale socket left by a previous run is
+    /// removed.
+    pub(crate) fn bind(path: &Path) -> Result<Self> {
+        if UnixStream::connect(path).is_ok() {
+            bail!(
+                "Control socket {} is already in use by another instance",
+                path.display()
+            );
+        }
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let listener = UnixListener::bind(path)
+            .map_err(|e| anyhow!("Could not bind control socket {}: {e}", path.display()))?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+        })
+    }
+
+    /// Handle all pending requests using the given handler, without
+    /// blocking. The handler result is sent back to the client.
+    pub(crate) fn poll<F>(&mut self, mut handler: F) -> Result<()>
+    where
+        F: FnMut(CtlRequest) -> Result<String>,
+    {
+        loop {
+            let stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e.into()),
+            };
+
+            if let Err(e) = Self::handle(stream, &mut handler) {
+                warn!("Could not handle control request: {e}");
+            }
+        }
+    }
+
+    fn handle<F>(stream: UnixStream, handler: &mut F) -> Result<()>
+    where
+        F: FnMut(CtlRequest) -> Result<String>,
+    {
+        // Do not let a misbehaving client block the collection.
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+
+        let reply = match line.parse::<CtlRequest>() {
+            Ok(req) => {
+                debug!("Control request: {req}");
+                match handler(req) {
+                    Ok(msg) => format!("ok {msg}"),
+                    Err(e) => format!("error {e}"),
+                }
+            }
+            Err(e) => format!("error {e}"),
+        };
+
+        writeln!(&stream, "{reply}")?;
+        Ok(())
+    }
+}
+
+impl Drop for CtlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Send a request to a running collection and return its reply.
+pub(crate) fn send_request(path: &Path, req: &CtlRequest) -> Result<String> {
+    let stream = UnixStream::connect(path).map_err(|e| {
+        anyhow!(
+            "Could not connect to {} ({e}), is `retis collect --ctl-socket` running?",
+            path.display()
+        )
+    })?;
+    // Requests are handled from the collection loop, which can take a bit
+    // of time to pick them up.
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+
+    writeln!(&stream, "{req}")?;
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+
+    match reply.trim_end().split_once(' ') {
+        Some(("ok", msg)) => Ok(msg.to_string()),
+        Some(("error", msg)) => bail!("{msg}"),
+        _ => bail!("Invalid reply '{}'", reply.trim_end()),
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum CtlSubCommand {
+    /// Manage the probes of a running collection.
+    Probe {
+        #[command(subcommand)]
+        action: CtlProbeAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum CtlProbeAction {
+    /// Add a probe, or re-enable a previously removed one. Uses the same
+    /// format as `retis collect --probe`; USDT probes are not supported.
+    Add { probe: String },
+    /// Remove a probe. Its program stays attached but stops reporting events.
+    /// Probes added by collectors can be removed too.
+    Del { probe: String },
+}
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "ctl",
+    subcommand_required = true,
+    about = "Control a running collection.",
+    long_about = "Control a running collection.
+
+The collection must have been started with `retis collect --ctl-socket`. This allows adding and removing probes while collecting events, without restarting the collection."
+)]
+pub(crate) struct Ctl {
+    #[arg(
+        long,
+        default_value = DEFAULT_CTL_SOCKET,
+        help = "Path of the control socket"
+    )]
+    socket: PathBuf,
+    #[command(subcommand)]
+    command: Option<CtlSubCommand>,
+}
+
+impl SubCommandParserRunner for Ctl {
+    fn run(&mut self, _: &MainConfig) -> Result<()> {
+        let req = match &self.command {
+            Some(CtlSubCommand::Probe { action }) => match action {
+                CtlProbeAction::Add { probe } => CtlRequest::ProbeAdd(probe.clone()),
+                CtlProbeAction::Del { probe } => CtlRequest::ProbeDel(probe.clone()),
+            },
+            None => bail!("No command given"),
+        };
+
+        println!("{}", send_request(&self.socket, &req)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_request() {
+        assert_eq!(
+            "probe add kprobe:ip_rcv\n".parse::<CtlRequest>().unwrap(),
+            CtlRequest::ProbeAdd("kprobe:ip_rcv".to_string())
+        );
+        assert_eq!(
+            "probe del tp:skb:kfree_skb".parse::<CtlRequest>().unwrap(),
+            CtlRequest::ProbeDel("tp:skb:kfree_skb".to_string())
+        );
+
+        let req = CtlRequest::ProbeAdd("kretprobe:ip_*".to_string());
+        assert_eq!(req.to_string().parse::<CtlRequest>().unwrap(), req);
+
+        assert!("".parse::<CtlRequest>().is_err());
+        assert!("probe add".parse::<CtlRequest>().is_err());
+        assert!("probe foo kprobe:ip_rcv".parse::<CtlRequest>().is_err());
+        assert!("probe add kprobe:ip_rcv foo".parse::<CtlRequest>().is_err());
+    }
+
+    #[test]
+    fn server() {
+        let path = std::env::temp_dir().join(format!("retis-ctl-{}.sock", std::process::id()));
+        let mut server = CtlServer::bind(&path).unwrap();
+
+        let client = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                send_request(&path, &CtlRequest::ProbeDel("kprobe:ip_rcv".to_string()))
+            })
+        };
+
+        let mut handled = false;
+        while !handled {
+            server
+                .poll(|req| {
+                    handled = true;
+                    match req {
+                        CtlRequest::ProbeDel(p) => Ok(format!("{p} removed")),
+                        _ => bail!("unexpected request"),
+                    }
+                })
+                .unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(client.join().unwrap().unwrap(), "kprobe:ip_rcv removed");
+        drop(server);
+        assert!(!path.exists());
+    }
+}
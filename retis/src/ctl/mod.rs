@@ -0,0 +1,9 @@
+//! # Ctl
+//!
+//! Provides a runtime control interface to a running collection, over a unix
+//! socket, and the command to interact with it.
+
+// Re-export ctl.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod ctl;
+pub(crate) use ctl::*;
@@ -0,0 +1,497 @@
+//! Captures managed by the daemon. Each capture is a `retis collect` child
+//! process streaming its events to the daemon, which accounts for them and
+//! forwards them to the clients streaming the capture events.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    io::{self, BufRead, BufReader, Write},
+    net::Shutdown,
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use log::{debug, warn};
+use serde::Serialize;
+
+use crate::{
+    agent::{check_remote_args, split_args, stop_collection, wall_clock, ArgValue},
+    ctl::{send_request, CtlRequest},
+};
+
+/// Time given to a collection to start, before giving up.
+const COLLECT_START_TIMEOUT: Duration = Duration::from_secs(120);
+/// Interval at which the capture event socket is checked for the collection
+/// to connect.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time given to subscribers to accept events, before being disconnected.
+const SUBSCRIBER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Collect arguments defining the capture filters, replaced when the filters
+/// of a capture are updated.
+const FILTER_ARGS: &[(&str, ArgValue)] = &[
+    ("-f", ArgValue::Required),
+    ("--filter-packet", ArgValue::Required),
+    ("-m", ArgValue::Required),
+    ("--filter-meta", ArgValue::Required),
+    ("--filter", ArgValue::Required),
+    ("--filter-flow", ArgValue::Required),
+    ("--filter-pid", ArgValue::Required),
+    ("--filter-comm", ArgValue::Required),
+];
+
+/// Statistics of a capture.
+#[derive(Clone, Debug, Default, Serialize)]
+pub(crate) struct CaptureStats {
+    /// Number of events received.
+    pub(crate) events: u64,
+    /// Number of events the collection reported as lost.
+    pub(crate) lost: u64,
+    /// Number of events per probe, e.g. "kprobe:tcp_v4_rcv".
+    pub(crate) probes: BTreeMap<String, u64>,
+}
+
+impl CaptureStats {
+    /// Account for an event, given as its JSON representation.
+    fn add(&mut self, event: &str) {
+        self.events += 1;
+
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(event) else {
+            return;
+        };
+        if let Some(count) = event.pointer("/lost/count").and_then(|c| c.as_u64()) {
+            self.lost += count;
+        }
+        let probe = event.get("kernel").or_else(|| event.get("userspace"));
+        if let Some(probe) = probe {
+            if let (Some(kind), Some(symbol)) = (
+                probe.get("probe_type").and_then(|t| t.as_str()),
+                probe.get("symbol").and_then(|s| s.as_str()),
+            ) {
+                *self.probes.entry(format!("{kind}:{symbol}")).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Capture information, as reported by the API.
+#[derive(Debug, Serialize)]
+pub(crate) struct CaptureInfo {
+    pub(crate) id: u32,
+    /// Collect arguments.
+    pub(crate) args: Vec<String>,
+    /// "running", "restarting" or "stopped".
+    pub(crate) state: String,
+    /// Exit status, or error if restarting failed, once stopped.
+    pub(crate) exit: Option<String>,
+    /// Wall clock time the capture was started at, in nanoseconds since the
+    /// epoch.
+    pub(crate) start_time: i64,
+    /// Number of times the capture was restarted, e.g. to update its filters.
+    pub(crate) restarts: u32,
+    /// Number of clients streaming the capture events.
+    pub(crate) subscribers: usize,
+    pub(crate) stats: CaptureStats,
+}
+
+/// State shared between a capture and the thread reading its events.
+#[derive(Default)]
+struct Shared {
+    stats: Mutex<CaptureStats>,
+    /// Clients streaming the capture events.
+    subscribers: Mutex<Vec<UnixStream>>,
+    /// Startup event of the collection, sent first to new subscribers.
+    startup: Mutex<Option<String>>,
+}
+
+impl Shared {
+    fn close_subscribers(&self) {
+        self.subscribers.lock().unwrap().drain(..).for_each(|s| {
+            let _ = s.shutdown(Shutdown::Both);
+        });
+    }
+}
+
+/// Collection process of a capture, and the thread reading its events.
+struct Collection {
+    child: Child,
+    ctl: PathBuf,
+    /// Set when the collection is stopped on purpose, for the reader not to
+    /// close the subscribers when restarting.
+    detached: Arc<AtomicBool>,
+    /// Set once the collection connected to its event socket.
+    connected: Arc<AtomicBool>,
+    reader: Option<thread::JoinHandle<()>>,
+}
+
+impl Collection {
+    fn start(dir: &Path, id: u32, args: &[String], shared: &Arc<Shared>) -> Result<Self> {
+        let events = dir.join(format!("capture-{id}.events.sock"));
+        let ctl = dir.join(format!("capture-{id}.ctl.sock"));
+        if events.exists() {
+            std::fs::remove_file(&events)?;
+        }
+        let listener = UnixListener::bind(&events)?;
+        listener.set_nonblocking(true)?;
+
+        let child = Command::new(env::current_exe()?)
+            .arg("collect")
+            .args(args)
+            .arg("--out")
+            .arg(format!("unix://{}", events.display()))
+            .arg("--ctl-socket")
+            .arg(&ctl)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()?;
+
+        let detached = Arc::new(AtomicBool::new(false));
+        let connected = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let (shared, detached, connected) = (
+                Arc::clone(shared),
+                Arc::clone(&detached),
+                Arc::clone(&connected),
+            );
+            thread::spawn(move || {
+                if let Err(e) = read_events(&listener, &shared, &detached, &connected) {
+                    warn!("Capture {id}: {e}");
+                }
+                let _ = std::fs::remove_file(&events);
+                if !detached.load(Ordering::Relaxed) {
+                    shared.close_subscribers();
+                }
+            })
+        };
+
+        let mut collection = Self {
+            child,
+            ctl,
+            detached,
+            connected,
+            reader: Some(reader),
+        };
+        if let Err(e) = collection.wait_start() {
+            collection.stop();
+            return Err(e);
+        }
+        Ok(collection)
+    }
+
+    /// Wait for the collection to start, i.e. to connect to its event
+    /// socket.
+    fn wait_start(&mut self) -> Result<()> {
+        let start = Instant::now();
+        while !self.connected.load(Ordering::Relaxed) {
+            if let Some(status) = self.child.try_wait()? {
+                bail!("Collection failed to start ({status})");
+            }
+            if start.elapsed() > COLLECT_START_TIMEOUT {
+                bail!("Collection did not start in time");
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        Ok(())
+    }
+
+    /// Stop the collection and wait for its events to be read.
+    fn stop(&mut self) {
+        self.detached.store(true, Ordering::Relaxed);
+        if let Ok(None) = self.child.try_wait() {
+            stop_collection(self.child.id());
+        }
+        if let Err(e) = self.child.wait() {
+            warn!("Could not wait for the collection to terminate: {e}");
+        }
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Read the events of a collection, until it terminates.
+fn read_events(
+    listener: &UnixListener,
+    shared: &Shared,
+    detached: &AtomicBool,
+    connected: &AtomicBool,
+) -> Result<()> {
+    let stream = loop {
+        match listener.accept() {
+            Ok((stream, _)) => break stream,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if detached.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+    stream.set_nonblocking(false)?;
+    connected.store(true, Ordering::Relaxed);
+
+    let mut first = true;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        // Subscribers are locked first, see `Capture::subscribe`.
+        let mut subscribers = shared.subscribers.lock().unwrap();
+        if first {
+            // Each collection starts with a startup event, only the first
+            // one is kept when restarting.
+            first = false;
+            let mut startup = shared.startup.lock().unwrap();
+            if startup.is_some() {
+                continue;
+            }
+            *startup = Some(line.clone());
+        } else {
+            shared.stats.lock().unwrap().add(&line);
+        }
+
+        // Clients not keeping up are disconnected.
+        subscribers.retain_mut(|s| writeln!(s, "{line}").is_ok());
+    }
+
+    debug!("Collection event stream ended");
+    Ok(())
+}
+
+/// Capture managed by the daemon.
+pub(crate) struct Capture {
+    id: u32,
+    args: Vec<String>,
+    start_time: i64,
+    restarts: u32,
+    dir: PathBuf,
+    /// Unset while restarting, or if restarting failed.
+    collection: Option<Collection>,
+    /// Error which stopped the capture while restarting.
+    error: Option<String>,
+    /// Set once the capture is stopped, possibly while restarting.
+    stopped: bool,
+    shared: Arc<Shared>,
+}
+
+/// Restart of a capture collection, e.g. to update its filters. As starting a
+/// collection can take a while, restarts are run without holding the capture;
+/// see `Capture::restart` and `Capture::restarted`.
+pub(crate) struct Restart {
+    id: u32,
+    args: Vec<String>,
+    dir: PathBuf,
+    old: Collection,
+    new: Option<Result<Collection>>,
+    shared: Arc<Shared>,
+}
+
+impl Restart {
+    /// Stop the previous collection and start the new one.
+    pub(crate) fn run(&mut self) {
+        self.old.stop();
+        self.new = Some(Collection::start(
+            &self.dir,
+            self.id,
+            &self.args,
+            &self.shared,
+        ));
+    }
+}
+
+impl Capture {
+    /// Start a capture, using `retis collect` arguments; only collection
+    /// arguments can be used (see `check_remote_args`). Sockets used to
+    /// communicate with the collection are created in `dir`.
+    pub(crate) fn start(dir: &Path, id: u32, args: Vec<String>) -> Result<Self> {
+        check_remote_args(&args)?;
+
+        let shared = Arc::new(Shared::default());
+        Ok(Self {
+            id,
+            collection: Some(Collection::start(dir, id, &args, &shared)?),
+            args,
+            start_time: wall_clock()?,
+            restarts: 0,
+            dir: dir.to_path_buf(),
+            error: None,
+            stopped: false,
+            shared,
+        })
+    }
+
+    pub(crate) fn info(&mut self) -> CaptureInfo {
+        let (state, exit) = match self.collection.as_mut() {
+            Some(collection) => match collection.child.try_wait() {
+                Ok(None) => ("running", None),
+                Ok(Some(status)) => ("stopped", Some(status.to_string())),
+                Err(e) => ("stopped", Some(e.to_string())),
+            },
+            None if self.stopped || self.error.is_some() => ("stopped", self.error.clone()),
+            None => ("restarting", None),
+        };
+
+        CaptureInfo {
+            id: self.id,
+            args: self.args.clone(),
+            state: state.to_string(),
+            exit,
+            start_time: self.start_time,
+            restarts: self.restarts,
+            subscribers: self.shared.subscribers.lock().unwrap().len(),
+            stats: self.shared.stats.lock().unwrap().clone(),
+        }
+    }
+
+    /// Stream the capture events to a client, as JSON (one event per line,
+    /// starting with the startup event once received). The client is
+    /// disconnected once the capture stops.
+    pub(crate) fn subscribe(&self, mut client: UnixStream) -> Result<()> {
+        // Hold the subscribers lock while sending the startup event, for
+        // the client not to miss events.
+        client.set_write_timeout(Some(SUBSCRIBER_TIMEOUT))?;
+        let mut subscribers = self.shared.subscribers.lock().unwrap();
+        if let Some(startup) = self.shared.startup.lock().unwrap().as_ref() {
+            writeln!(client, "{startup}")?;
+        }
+        subscribers.push(client);
+        Ok(())
+    }
+
+    /// Add or remove a probe, while the capture is running.
+    pub(crate) fn ctl(&self, req: &CtlRequest) -> Result<String> {
+        match &self.collection {
+            Some(collection) => send_request(&collection.ctl, req),
+            None => bail!("Capture is not running"),
+        }
+    }
+
+    /// Prepare replacing the filters of the capture. Filters are set up when
+    /// the collection starts, the capture is restarted using the new ones;
+    /// statistics and subscribers are kept. The returned restart must be run,
+    /// without holding the capture, then handed back to `Capture::restarted`.
+    pub(crate) fn restart(&mut self, filters: Vec<String>) -> Result<Restart> {
+        let (others, filters) = split_args(&filters, FILTER_ARGS);
+        if !others.is_empty() {
+            bail!("Not filter arguments: {}", others.join(" "));
+        }
+        let (args, _) = split_args(&self.args, FILTER_ARGS);
+        let args = args.into_iter().chain(filters).collect::<Vec<_>>();
+        check_remote_args(&args)?;
+
+        let Some(old) = self.collection.take() else {
+            bail!("Capture is not running");
+        };
+        Ok(Restart {
+            id: self.id,
+            args,
+            dir: self.dir.clone(),
+            old,
+            new: None,
+            shared: Arc::clone(&self.shared),
+        })
+    }
+
+    /// Use the collection started by a restart. If starting it failed, the
+    /// capture is stopped.
+    pub(crate) fn restarted(&mut self, mut restart: Restart) -> Result<()> {
+        match restart.new.take() {
+            // The capture was stopped while restarting.
+            Some(Ok(mut collection)) if self.stopped => {
+                collection.stop();
+                bail!("Capture was stopped while restarting");
+            }
+            Some(Ok(collection)) => {
+                self.collection = Some(collection);
+                self.args = restart.args;
+                self.restarts += 1;
+                Ok(())
+            }
+            Some(Err(e)) => {
+                self.error = Some(e.to_string());
+                self.shared.close_subscribers();
+                Err(e)
+            }
+            None => {
+                self.collection = Some(restart.old);
+                bail!("Restart was not run");
+            }
+        }
+    }
+
+    /// Stop the capture and disconnect its subscribers.
+    pub(crate) fn stop(&mut self) {
+        self.stopped = true;
+        if let Some(collection) = self.collection.as_mut() {
+            collection.stop();
+        }
+        self.shared.close_subscribers();
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let mut stats = CaptureStats::default();
+        stats.add(
+            r#"{"common":{"timestamp":1},"kernel":{"probe_type":"kprobe","symbol":"tcp_v4_rcv"}}"#,
+        );
+        stats.add(
+            r#"{"common":{"timestamp":2},"kernel":{"probe_type":"kprobe","symbol":"tcp_v4_rcv"}}"#,
+        );
+        stats.add(r#"{"common":{"timestamp":3},"userspace":{"probe_type":"usdt","symbol":"dpif_recv:recv_upcall"}}"#);
+        stats.add(r#"{"common":{"timestamp":4},"lost":{"count":42}}"#);
+        stats.add("invalid");
+
+        assert_eq!(stats.events, 5);
+        assert_eq!(stats.lost, 42);
+        assert_eq!(
+            stats.probes.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("kprobe:tcp_v4_rcv".to_string(), 2),
+                ("usdt:dpif_recv:recv_upcall".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn filters() {
+        let args = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+
+        let (others, filters) = split_args(
+            &args("-c skb -f tcp --filter-meta x --stack --filter-pid 42"),
+            FILTER_ARGS,
+        );
+        assert_eq!(others, args("-c skb --stack"));
+        assert_eq!(filters, args("-f tcp --filter-meta x --filter-pid 42"));
+    }
+
+    #[test]
+    fn refused_args() {
+        let args = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+        let dir = Path::new("/nonexistent");
+
+        // Arguments managed by the daemon.
+        assert!(Capture::start(dir, 1, args("-c skb --print --ctl-socket /tmp/s")).is_err());
+        // Outputs, including when combined with other short options.
+        assert!(Capture::start(dir, 1, args("-c skb -eo/etc/foo")).is_err());
+        assert!(Capture::start(dir, 1, args("--external-collector /tmp/x")).is_err());
+    }
+}
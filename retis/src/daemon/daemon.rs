@@ -0,0 +1,341 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use log::{info, warn};
+use serde::Deserialize;
+
+use super::{
+    capture::Capture,
+    http::{headers, Request, Response},
+};
+use crate::{agent::private_dir, cli::*, ctl::CtlRequest, helpers::signals::Running};
+
+/// Default unix socket the API listens on.
+const DEFAULT_DAEMON_SOCKET: &str = "/run/retis/daemon.sock";
+/// Interval at which pending connections are checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "daemon",
+    about = "Run captures controlled through an HTTP API.",
+    long_about = "Run captures controlled through an HTTP API.
+
+The daemon exposes a REST API (JSON over HTTP) to start and stop captures, update their probes and filters, fetch their statistics and stream their events. Captures use the same collection arguments as `retis collect` (collectors, probes, filters and their options); arguments accessing files or running commands on the host are refused. See the documentation for the API description.
+
+The API is served on a unix socket only accessible by the user running the daemon, e.g. using 'curl --unix-socket /run/retis/daemon.sock http://localhost/captures'."
+)]
+pub(crate) struct Daemon {
+    #[arg(
+        long,
+        value_name = "PATH",
+        default_value = DEFAULT_DAEMON_SOCKET,
+        help = "Unix socket the API listens on"
+    )]
+    listen: PathBuf,
+}
+
+impl SubCommandParserRunner for Daemon {
+    fn run(&mut self, _: &MainConfig) -> Result<()> {
+        let listener = bind(&self.listen)?;
+        info!("API listening on {}", self.listen.display());
+
+        let api = Arc::new(Api::new()?);
+        let run = Running::new()?;
+        while run.running() {
+            let (stream, _) = match listener.accept() {
+                Ok(client) => client,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let api = Arc::clone(&api);
+            thread::spawn(move || {
+                if let Err(e) = api.handle(stream) {
+                    warn!("API request: {e}");
+                }
+            });
+        }
+
+        // Stop the remaining captures.
+        api.captures.lock().unwrap().clear();
+        let _ = fs::remove_dir_all(&api.dir);
+        let _ = fs::remove_file(&self.listen);
+        Ok(())
+    }
+}
+
+/// Bind the API socket, only accessible by the current user. A stale socket
+/// left by a previous run is removed.
+fn bind(path: &Path) -> Result<UnixListener> {
+    if UnixStream::connect(path).is_ok() {
+        bail!("API socket {} is already in use", path.display());
+    }
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Set the socket permissions when creating it, not to let other users
+    // connect before they are changed. No other thread runs at this point.
+    // Safety: umask has no memory safety implication.
+    let umask = unsafe { libc::umask(0o177) };
+    let listener = UnixListener::bind(path);
+    unsafe { libc::umask(umask) };
+
+    let listener = listener.map_err(|e| anyhow!("Could not listen on {}: {e}", path.display()))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Error reported with a specific HTTP status. Other errors are reported as
+/// bad requests.
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    msg: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Body of requests starting a capture or updating its filters.
+#[derive(Deserialize)]
+struct ArgsRequest {
+    args: Vec<String>,
+}
+
+/// Body of requests adding or removing a probe.
+#[derive(Deserialize)]
+struct ProbeRequest {
+    probe: String,
+}
+
+struct Api {
+    /// Directory holding the sockets used to communicate with the captures.
+    dir: PathBuf,
+    captures: Mutex<BTreeMap<u32, Arc<Mutex<Capture>>>>,
+    next_id: AtomicU32,
+}
+
+impl Api {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            dir: private_dir("retis-daemon")?,
+            captures: Mutex::new(BTreeMap::new()),
+            next_id: AtomicU32::new(1),
+        })
+    }
+
+    /// Handle a single request.
+    fn handle(&self, stream: UnixStream) -> Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let req = Request::read(&mut BufReader::new(&stream));
+        stream.set_read_timeout(None)?;
+
+        let req = match req {
+            Ok(req) => req,
+            Err(e) => {
+                Response::error(400, &e.to_string()).write(&mut &stream)?;
+                return Err(e);
+            }
+        };
+
+        // Events are streamed until the capture stops or the client goes
+        // away, the connection being handed over to the capture.
+        if let ("GET", [captures, id, events]) = (req.method.as_str(), req.path.as_slice()) {
+            if captures == "captures" && events == "events" {
+                return match self.capture(id) {
+                    Ok(capture) => {
+                        write!(&stream, "{}\r\n", headers(200, "application/x-ndjson"))?;
+                        capture.lock().unwrap().subscribe(stream)
+                    }
+                    Err(e) => Self::error(e).write(&mut &stream),
+                };
+            }
+        }
+
+        self.route(&req)
+            .unwrap_or_else(Self::error)
+            .write(&mut &stream)
+    }
+
+    fn route(&self, req: &Request) -> Result<Response> {
+        let path = req.path.iter().map(String::as_str).collect::<Vec<_>>();
+        match (req.method.as_str(), path.as_slice()) {
+            ("GET", ["captures"]) => {
+                let captures = self
+                    .captures
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let infos = captures
+                    .iter()
+                    .map(|c| c.lock().unwrap().info())
+                    .collect::<Vec<_>>();
+                Response::json(200, &infos)
+            }
+            ("POST", ["captures"]) => {
+                let args = req.json::<ArgsRequest>()?.args;
+                let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                info!("Starting capture {id}: {}", args.join(" "));
+
+                let mut capture = Capture::start(&self.dir, id, args)?;
+                let info = capture.info();
+                self.captures
+                    .lock()
+                    .unwrap()
+                    .insert(id, Arc::new(Mutex::new(capture)));
+                Response::json(201, &info)
+            }
+            ("GET", ["captures", id]) => {
+                Response::json(200, &self.capture(id)?.lock().unwrap().info())
+            }
+            ("DELETE", ["captures", id]) => {
+                let capture = self.capture(id)?;
+                self.captures
+                    .lock()
+                    .unwrap()
+                    .retain(|_, c| !Arc::ptr_eq(c, &capture));
+                info!("Stopping capture {id}");
+
+                let mut capture = capture.lock().unwrap();
+                capture.stop();
+                Response::json(200, &capture.info())
+            }
+            ("POST", ["captures", id, "probes"]) => {
+                let probe = req.json::<ProbeRequest>()?.probe;
+                let res = self
+                    .capture(id)?
+                    .lock()
+                    .unwrap()
+                    .ctl(&CtlRequest::ProbeAdd(probe))?;
+                Response::json(200, &serde_json::json!({ "result": res }))
+            }
+            ("DELETE", ["captures", id, "probes"]) => {
+                let probe = req.json::<ProbeRequest>()?.probe;
+                let res = self
+                    .capture(id)?
+                    .lock()
+                    .unwrap()
+                    .ctl(&CtlRequest::ProbeDel(probe))?;
+                Response::json(200, &serde_json::json!({ "result": res }))
+            }
+            ("PUT", ["captures", id, "filters"]) => {
+                let filters = req.json::<ArgsRequest>()?.args;
+                let capture = self.capture(id)?;
+                info!(
+                    "Updating the filters of capture {id}: {}",
+                    filters.join(" ")
+                );
+
+                // Restarting the collection can take a while, don't block
+                // other requests on the capture meanwhile.
+                let mut restart = capture.lock().unwrap().restart(filters)?;
+                restart.run();
+
+                let mut capture = capture.lock().unwrap();
+                capture.restarted(restart)?;
+                Response::json(200, &capture.info())
+            }
+            (_, ["captures"])
+            | (_, ["captures", _])
+            | (_, ["captures", _, "events" | "probes" | "filters"]) => Err(ApiError {
+                status: 405,
+                msg: "Method not allowed".to_string(),
+            }
+            .into()),
+            _ => Err(ApiError {
+                status: 404,
+                msg: "Not found".to_string(),
+            }
+            .into()),
+        }
+    }
+
+    fn capture(&self, id: &str) -> Result<Arc<Mutex<Capture>>> {
+        id.parse::<u32>()
+            .ok()
+            .and_then(|id| self.captures.lock().unwrap().get(&id).cloned())
+            .ok_or_else(|| {
+                ApiError {
+                    status: 404,
+                    msg: format!("Unknown capture {id}"),
+                }
+                .into()
+            })
+    }
+
+    fn error(e: anyhow::Error) -> Response {
+        match e.downcast_ref::<ApiError>() {
+            Some(e) => Response::error(e.status, &e.msg),
+            None => Response::error(400, &e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(raw: &str) -> Request {
+        Request::read(&mut raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn routes() {
+        let api = Api::new().unwrap();
+        let status = |raw: &str| match api.route(&request(raw)) {
+            Ok(res) => res.status,
+            Err(e) => Api::error(e).status,
+        };
+
+        assert_eq!(status("GET /captures HTTP/1.1\r\n\r\n"), 200);
+        assert_eq!(status("GET /captures/1 HTTP/1.1\r\n\r\n"), 404);
+        assert_eq!(status("GET /captures/foo HTTP/1.1\r\n\r\n"), 404);
+        assert_eq!(status("DELETE /captures/1 HTTP/1.1\r\n\r\n"), 404);
+        assert_eq!(status("PATCH /captures HTTP/1.1\r\n\r\n"), 405);
+        assert_eq!(status("GET /captures/1/filters HTTP/1.1\r\n\r\n"), 405);
+        assert_eq!(status("GET /foo HTTP/1.1\r\n\r\n"), 404);
+        assert_eq!(
+            status("POST /captures HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 2\r\n\r\n{}"),
+            400
+        );
+        assert_eq!(
+            status("POST /captures HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: 20\r\n\r\n{\"args\":[\"--print\"]}"),
+            400
+        );
+
+        let res = api
+            .route(&request("GET /captures HTTP/1.1\r\n\r\n"))
+            .unwrap();
+        assert_eq!(res.body, "[]");
+        fs::remove_dir_all(&api.dir).unwrap();
+    }
+}
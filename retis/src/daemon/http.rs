@@ -0,0 +1,189 @@
+//! Minimal HTTP/1.1 support for the daemon API: a single request is handled
+//! per connection and responses always close it.
+
+use std::io::{BufRead, Read, Write};
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+
+/// Maximum size of a request body.
+const MAX_BODY_LEN: usize = 1 << 20;
+
+/// HTTP request.
+#[derive(Debug)]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    /// Path segments, e.g. ["captures", "1"] for /captures/1.
+    pub(crate) path: Vec<String>,
+    /// Media type of the body, without its parameters.
+    pub(crate) content_type: Option<String>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Request {
+    /// Read a request from a client.
+    pub(crate) fn read<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let mut parts = line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+                (method.to_string(), target)
+            }
+            _ => bail!("Invalid request line '{}'", line.trim_end()),
+        };
+        // The query string is not used.
+        let path = target
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+
+        let (mut len, mut content_type) = (0, None);
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 {
+                bail!("Unexpected end of headers");
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    len = value
+                        .trim()
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid Content-Length"))?;
+                } else if name.eq_ignore_ascii_case("content-type") {
+                    content_type = value
+                        .split(';')
+                        .next()
+                        .map(|t| t.trim().to_ascii_lowercase());
+                }
+            }
+        }
+        if len > MAX_BODY_LEN {
+            bail!("Request body too large");
+        }
+
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body)?;
+
+        Ok(Self {
+            method,
+            path,
+            content_type,
+            body,
+        })
+    }
+
+    /// Deserialize the JSON body of the request. The body must be declared as
+    /// JSON, which requests sent by web pages on behalf of the user can't do
+    /// without the API allowing it.
+    pub(crate) fn json<'a, T: serde::Deserialize<'a>>(&'a self) -> Result<T> {
+        if self.content_type.as_deref() != Some("application/json") {
+            bail!("Content-Type must be application/json");
+        }
+        serde_json::from_slice(&self.body).map_err(|e| anyhow!("Invalid request body: {e}"))
+    }
+}
+
+/// HTTP response, with a JSON body.
+#[derive(Debug)]
+pub(crate) struct Response {
+    pub(crate) status: u16,
+    pub(crate) body: String,
+}
+
+impl Response {
+    pub(crate) fn json<T: Serialize>(status: u16, body: &T) -> Result<Self> {
+        Ok(Self {
+            status,
+            body: serde_json::to_string(body)?,
+        })
+    }
+
+    /// Error response, the body being {"error": "<msg>"}.
+    pub(crate) fn error(status: u16, msg: &str) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": msg }).to_string(),
+        }
+    }
+
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write!(
+            writer,
+            "{}Content-Length: {}\r\n\r\n{}",
+            headers(self.status, "application/json"),
+            self.body.len(),
+            self.body
+        )?;
+        Ok(writer.flush()?)
+    }
+}
+
+/// Status line and common headers of a response, without the final empty
+/// line. Streamed responses have no length and end when the connection is
+/// closed.
+pub(crate) fn headers(status: u16, content_type: &str) -> String {
+    let reason = match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    };
+    format!("HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nConnection: close\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request() {
+        let raw = "POST /captures/?foo=bar HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json; charset=utf-8\r\ncontent-length: 15\r\n\r\n{\"args\":[\"-c\"]}";
+        let req = Request::read(&mut raw.as_bytes()).unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, vec!["captures"]);
+        let body: serde_json::Value = req.json().unwrap();
+        assert_eq!(body["args"][0], "-c");
+
+        let raw =
+            "POST /captures HTTP/1.1\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\n{}";
+        let req = Request::read(&mut raw.as_bytes()).unwrap();
+        assert!(req.json::<serde_json::Value>().is_err());
+
+        let req = Request::read(&mut "GET /captures/1/events HTTP/1.0\r\n\r\n".as_bytes()).unwrap();
+        assert_eq!(req.path, vec!["captures", "1", "events"]);
+        assert!(req.body.is_empty());
+
+        assert!(Request::read(&mut "GET /\r\n\r\n".as_bytes()).is_err());
+        assert!(Request::read(&mut "GET / HTTP/1.1\r\nHost: x\r\n".as_bytes()).is_err());
+        assert!(
+            Request::read(&mut "GET / HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}".as_bytes())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn response() {
+        let mut out = Vec::new();
+        Response::error(404, "Unknown capture 3")
+            .write(&mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nConnection: close\r\nContent-Length: 29\r\n\r\n{\"error\":\"Unknown capture 3\"}"
+        );
+    }
+}
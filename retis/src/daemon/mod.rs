@@ -0,0 +1,12 @@
+//! # Daemon
+//!
+//! Runs captures controlled through an HTTP API, for Retis to be embedded in
+//! other tools without wrapping its command line.
+
+// Re-export daemon.rs
+#[allow(clippy::module_inception)]
+pub(crate) mod daemon;
+pub(crate) use daemon::*;
+
+pub(crate) mod capture;
+pub(crate) mod http;
@@ -2,20 +2,64 @@
 use std::{path::PathBuf, str::FromStr};
 
 use anyhow::{anyhow, bail, Result};
+use log::warn;
 use regex::Regex;
 
-use crate::events::file::{rotate::*, *};
+use crate::events::file::{index::EventIndex, rotate::*, *};
 
-/// Convert an str representation of a limit to a `RotationPolicy`.
-/// Accepted values are numbers suffixed with a unit size (MB or GB).
-pub(crate) fn rotation_policy_from_str(limit: &str) -> Result<RotationPolicy> {
-    let re = Regex::new(r"(\d+)(M|G)B")?;
+/// Convert an str representation of a rotation configuration to a
+/// `RotationPolicy` and the number of files to keep, if limited. Accepted
+/// values are a comma separated list of:
+/// - size=<SIZE>: numbers suffixed with a unit size (K, M or G, optionally
+///   followed by B). A <SIZE> alone is also accepted.
+/// - time=<INTERVAL>: numbers suffixed with a time unit (s, m, h or d).
+/// - keep=<N>: number of files to keep.
+pub(crate) fn rotation_from_str(input: &str) -> Result<(RotationPolicy, Option<u32>)> {
+    let mut policy = None;
+    let mut keep = None;
+
+    for param in input.split(',') {
+        let new = match param.split_once('=') {
+            Some(("size", size)) => RotationPolicy::Size {
+                limit: parse_size(size)?,
+            },
+            Some(("time", time)) => RotationPolicy::Time {
+                interval: parse_interval(time)?,
+            },
+            Some(("keep", n)) => {
+                keep = match n.parse::<u32>() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => bail!("Invalid number of files to keep ({n})"),
+                };
+                continue;
+            }
+            Some((key, _)) => bail!("Unknown rotation parameter ({key})"),
+            None => RotationPolicy::Size {
+                limit: parse_size(param)?,
+            },
+        };
+
+        if policy.replace(new).is_some() {
+            bail!("Only a single rotation limit (size or time) can be used");
+        }
+    }
+
+    Ok((
+        policy.ok_or_else(|| anyhow!("No rotation limit given ({input})"))?,
+        keep,
+    ))
+}
+
+/// Parse a size, e.g. "512M" or "64MB".
+pub(crate) fn parse_size(size: &str) -> Result<usize> {
+    let re = Regex::new(r"^(\d+)(K|M|G)B?$")?;
     let matches = re
-        .captures(limit)
-        .ok_or_else(|| anyhow!("Invalid limit format ({limit})"))?;
+        .captures(size)
+        .ok_or_else(|| anyhow!("Invalid limit format ({size})"))?;
 
     // Unwrap as the regex already checked the second group was mandatory.
     let factor = match matches.get(2).unwrap().as_str() {
+        "K" => 1000,
         "M" => 1000 * 1000,
         "G" => 1000 * 1000 * 1000,
         _ => 1,
@@ -28,7 +72,32 @@ pub(crate) fn rotation_policy_from_str(limit: &str) -> Result<RotationPolicy> {
         bail!("Invalid limit value (0)");
     }
 
-    Ok(RotationPolicy::Size { limit })
+    Ok(limit)
+}
+
+/// Parse a time interval, e.g. "30m" or "1h", into seconds.
+pub(crate) fn parse_interval(interval: &str) -> Result<u64> {
+    let re = Regex::new(r"^(\d+)(s|m|h|d)$")?;
+    let matches = re
+        .captures(interval)
+        .ok_or_else(|| anyhow!("Invalid interval format ({interval})"))?;
+
+    // Unwrap as the regex already checked the second group was mandatory.
+    let factor = match matches.get(2).unwrap().as_str() {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => 1,
+    };
+
+    // Unwrap as the regex already checked the first group was mandatory.
+    let interval = u64::from_str(matches.get(1).unwrap().as_str())? * factor;
+
+    if interval == 0 {
+        bail!("Invalid interval value (0)");
+    }
+
+    Ok(interval)
 }
 
 // Custom argument type to represent the input file. This automatically handles
@@ -63,6 +132,63 @@ impl InputDataFile {
             FileEventsFactory::from_path(path_str)
         }
     }
+
+    /// Get the index of the input, if it is a single indexed event file.
+    pub(crate) fn index(&self) -> Result<Option<EventIndex>> {
+        if !self.path.is_file() {
+            return Ok(None);
+        }
+
+        // Split files are read as a set, which is not covered by the index of
+        // a single file.
+        if self.use_rotation
+            && FileEventsFactory::from_path(&self.path)?
+                .startup()
+                .is_some_and(|startup| startup.split_file.is_some())
+        {
+            return Ok(None);
+        }
+
+        Ok(EventIndex::from_file(&self.path).unwrap_or_else(|e| {
+            warn!("Not using the index: {e}");
+            None
+        }))
+    }
+
+    /// Get a factory only reading part of the input, given as (start, end)
+    /// offsets from its index.
+    pub(crate) fn to_factory_range(&self, start: u64, end: u64) -> Result<FileEventsFactory> {
+        FileEventsFactory::from_path_range(&self.path, start, end)
+    }
+}
+
+/// Get a factory reading events from multiple inputs, merged by timestamp (see
+/// `FileEventsFactory::merge`). Deltas, in nanoseconds, are added to the
+/// timestamps of the inputs events, in order. The default input is used if none
+/// is given.
+pub(crate) fn inputs_to_factory(
+    inputs: &[InputDataFile],
+    deltas: &[i64],
+) -> Result<FileEventsFactory> {
+    let default = [InputDataFile::default()];
+    let inputs = match inputs.is_empty() {
+        true => &default[..],
+        false => inputs,
+    };
+    if deltas.len() > inputs.len() {
+        bail!("More time deltas than inputs were given");
+    }
+
+    match inputs {
+        [input] if deltas.is_empty() => input.to_factory(),
+        _ => FileEventsFactory::merge(
+            inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| Ok((input.to_factory()?, deltas.get(i).copied().unwrap_or(0))))
+                .collect::<Result<Vec<_>>>()?,
+        ),
+    }
 }
 
 impl Default for InputDataFile {
@@ -99,3 +225,41 @@ impl FromStr for InputDataFile {
         Ok(input)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation() {
+        let size = |limit| RotationPolicy::Size { limit };
+
+        assert_eq!(rotation_from_str("64MB").unwrap(), (size(64_000_000), None));
+        assert_eq!(
+            rotation_from_str("size=512M,keep=8").unwrap(),
+            (size(512_000_000), Some(8))
+        );
+        assert_eq!(
+            rotation_from_str("size=1GB").unwrap(),
+            (size(1_000_000_000), None)
+        );
+        assert_eq!(
+            rotation_from_str("keep=2,time=1h").unwrap(),
+            (RotationPolicy::Time { interval: 3600 }, Some(2))
+        );
+        assert_eq!(
+            rotation_from_str("time=30s").unwrap(),
+            (RotationPolicy::Time { interval: 30 }, None)
+        );
+
+        assert!(rotation_from_str("").is_err());
+        assert!(rotation_from_str("64").is_err());
+        assert!(rotation_from_str("0MB").is_err());
+        assert!(rotation_from_str("x64MB").is_err());
+        assert!(rotation_from_str("keep=8").is_err());
+        assert!(rotation_from_str("size=64MB,keep=0").is_err());
+        assert!(rotation_from_str("size=64MB,time=1h").is_err());
+        assert!(rotation_from_str("time=1y").is_err());
+        assert!(rotation_from_str("foo=1").is_err());
+    }
+}
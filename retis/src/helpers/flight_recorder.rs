@@ -0,0 +1,340 @@
+/// # Flight recorder
+///
+/// Keeps the last events in memory and only writes them out when a trigger
+/// fires, allowing to run a collection permanently while only storing events
+/// around incidents.
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use log::info;
+
+use super::file_rotate::{parse_interval, parse_size};
+use crate::events::{sink::EventSink, Event};
+
+/// Limit of the events kept in memory by the flight recorder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RecorderLimit {
+    /// Keep up to a given amount of (serialized) events, in bytes.
+    Size(usize),
+    /// Keep the events of the last given duration.
+    Time(Duration),
+}
+
+impl FromStr for RecorderLimit {
+    type Err = anyhow::Error;
+
+    /// Parse a limit, either "size=<SIZE>" or "time=<INTERVAL>" (see
+    /// `parse_size` and `parse_interval`).
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.split_once('=') {
+            Some(("size", size)) => RecorderLimit::Size(parse_size(size)?),
+            Some(("time", time)) => RecorderLimit::Time(Duration::from_secs(parse_interval(time)?)),
+            _ => bail!(
+                "Invalid flight recorder limit ({s}): expected size=<SIZE> or time=<INTERVAL>"
+            ),
+        })
+    }
+}
+
+/// Condition triggering a dump of the events kept by the flight recorder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DumpTrigger {
+    /// SIGUSR2 was received.
+    Signal,
+    /// A packet drop was reported.
+    Drop,
+    /// An event was generated by a given probe, e.g. "tcp_v4_rcv" or
+    /// "kprobe:tcp_v4_rcv".
+    Probe(String),
+}
+
+impl FromStr for DumpTrigger {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.split_once('=') {
+            None if s == "signal" => DumpTrigger::Signal,
+            None if s == "drop" => DumpTrigger::Drop,
+            Some(("probe", probe)) if !probe.is_empty() => DumpTrigger::Probe(probe.to_string()),
+            _ => bail!("Invalid trigger ({s}): expected signal, drop or probe=<PROBE>"),
+        })
+    }
+}
+
+impl DumpTrigger {
+    /// Does an event fire the trigger?
+    pub(crate) fn matches(&self, event: &Event) -> bool {
+        match self {
+            DumpTrigger::Signal => false,
+            DumpTrigger::Drop => event.skb_drop.is_some(),
+            DumpTrigger::Probe(probe) => event.kernel.as_ref().is_some_and(|k| {
+                k.symbol == *probe || format!("{}:{}", k.probe_type, k.symbol) == *probe
+            }),
+        }
+    }
+}
+
+impl fmt::Display for DumpTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DumpTrigger::Signal => write!(f, "SIGUSR2"),
+            DumpTrigger::Drop => write!(f, "packet drops"),
+            DumpTrigger::Probe(probe) => write!(f, "events from {probe}"),
+        }
+    }
+}
+
+/// Writer keeping the last events in memory, up to a `RecorderLimit`, and
+/// only writing them to an inner writer when a dump is requested (see
+/// `RecorderWriter::dump_handle`). A dump is done on the next write or flush.
+///
+/// Each write is expected to be a full event (line).
+pub(crate) struct RecorderWriter {
+    inner: Box<dyn Write + Send>,
+    limit: RecorderLimit,
+    // Events kept in memory, with the time they were written.
+    events: VecDeque<(Instant, Vec<u8>)>,
+    // Size of the events kept in memory.
+    size: usize,
+    // Was a dump requested?
+    dump: Arc<AtomicBool>,
+}
+
+impl RecorderWriter {
+    pub(crate) fn new(inner: Box<dyn Write + Send>, limit: RecorderLimit) -> Self {
+        Self {
+            inner,
+            limit,
+            events: VecDeque::new(),
+            size: 0,
+            dump: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Get a handle to request a dump of the events, by setting it to true.
+    pub(crate) fn dump_handle(&self) -> Arc<AtomicBool> {
+        self.dump.clone()
+    }
+
+    /// Remove the events outside of the limit.
+    fn trim(&mut self) {
+        while let Some((time, event)) = self.events.front() {
+            let expired = match self.limit {
+                RecorderLimit::Size(limit) => self.size > limit,
+                RecorderLimit::Time(limit) => time.elapsed() > limit,
+            };
+            if !expired {
+                break;
+            }
+
+            self.size -= event.len();
+            self.events.pop_front();
+        }
+    }
+
+    /// Write the events kept in memory to the inner writer, if requested.
+    fn dump(&mut self) -> io::Result<()> {
+        if !self.dump.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        self.trim();
+        let count = self.events.len();
+        self.events
+            .drain(..)
+            .try_for_each(|(_, event)| self.inner.write_all(&event))?;
+        self.size = 0;
+
+        info!("Flight recorder: dumped {count} event(s)");
+        Ok(())
+    }
+}
+
+impl Write for RecorderWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.size += buf.len();
+        self.events.push_back((Instant::now(), buf.to_vec()));
+        self.trim();
+
+        self.dump()?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.dump()?;
+        self.inner.flush()
+    }
+}
+
+/// Event sink requesting a dump of the flight recorder, once an event firing
+/// one of the triggers was processed by the inner sink.
+pub(crate) struct TriggerSink {
+    inner: Box<dyn EventSink>,
+    triggers: Vec<DumpTrigger>,
+    dump: Arc<AtomicBool>,
+}
+
+impl TriggerSink {
+    pub(crate) fn new(
+        inner: Box<dyn EventSink>,
+        triggers: Vec<DumpTrigger>,
+        dump: Arc<AtomicBool>,
+    ) -> Self {
+        Self {
+            inner,
+            triggers,
+            dump,
+        }
+    }
+}
+
+impl EventSink for TriggerSink {
+    fn process_one(&mut self, event: &Event) -> Result<()> {
+        self.inner.process_one(event)?;
+
+        if self.triggers.iter().any(|t| t.matches(event)) {
+            self.dump.store(true, Ordering::Relaxed);
+            self.inner.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        events::{KernelEvent, SkbDropEvent},
+        process::display::{PrintEvent, PrintEventFormat},
+    };
+
+    // Writer storing data in a shared buffer, to inspect what was dumped.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn limit() {
+        assert_eq!(
+            RecorderLimit::from_str("size=64M").unwrap(),
+            RecorderLimit::Size(64_000_000)
+        );
+        assert_eq!(
+            RecorderLimit::from_str("time=30s").unwrap(),
+            RecorderLimit::Time(Duration::from_secs(30))
+        );
+        assert!(RecorderLimit::from_str("64M").is_err());
+        assert!(RecorderLimit::from_str("size=0M").is_err());
+        assert!(RecorderLimit::from_str("keep=3").is_err());
+    }
+
+    #[test]
+    fn trigger() {
+        assert_eq!(
+            DumpTrigger::from_str("signal").unwrap(),
+            DumpTrigger::Signal
+        );
+        assert_eq!(DumpTrigger::from_str("drop").unwrap(), DumpTrigger::Drop);
+        assert_eq!(
+            DumpTrigger::from_str("probe=kprobe:tcp_v4_rcv").unwrap(),
+            DumpTrigger::Probe("kprobe:tcp_v4_rcv".to_string())
+        );
+        assert!(DumpTrigger::from_str("probe=").is_err());
+        assert!(DumpTrigger::from_str("foo").is_err());
+
+        let mut event = Event::new();
+        assert!(!DumpTrigger::Drop.matches(&event));
+        event.skb_drop = Some(SkbDropEvent {
+            subsys: None,
+            drop_reason: "NOT_SPECIFIED".to_string(),
+        });
+        assert!(DumpTrigger::Drop.matches(&event));
+
+        event.kernel = Some(KernelEvent {
+            symbol: "tcp_v4_rcv".to_string(),
+            probe_type: "kprobe".to_string(),
+            ..Default::default()
+        });
+        assert!(DumpTrigger::Probe("tcp_v4_rcv".to_string()).matches(&event));
+        assert!(DumpTrigger::Probe("kprobe:tcp_v4_rcv".to_string()).matches(&event));
+        assert!(!DumpTrigger::Probe("kretprobe:tcp_v4_rcv".to_string()).matches(&event));
+        assert!(!DumpTrigger::Signal.matches(&event));
+    }
+
+    #[test]
+    fn recorder() {
+        let out = Shared::default();
+        let mut writer = RecorderWriter::new(Box::new(out.clone()), RecorderLimit::Size(8));
+        let dump = writer.dump_handle();
+
+        for event in [b"ev1\n", b"ev2\n", b"ev3\n"] {
+            writer.write_all(event).unwrap();
+        }
+        writer.flush().unwrap();
+        assert!(out.0.lock().unwrap().is_empty());
+
+        // Only the last events, within the limit, are dumped.
+        dump.store(true, Ordering::Relaxed);
+        writer.flush().unwrap();
+        assert_eq!(out.0.lock().unwrap().as_slice(), b"ev2\nev3\n");
+
+        // Events are dumped on the next write, including the new one.
+        writer.write_all(b"ev4\n").unwrap();
+        dump.store(true, Ordering::Relaxed);
+        writer.write_all(b"ev5\n").unwrap();
+        assert_eq!(out.0.lock().unwrap().as_slice(), b"ev2\nev3\nev4\nev5\n");
+    }
+
+    #[test]
+    fn trigger_sink() {
+        let out = Shared::default();
+        let recorder = RecorderWriter::new(Box::new(out.clone()), RecorderLimit::Size(1024));
+        let dump = recorder.dump_handle();
+        let mut sink = TriggerSink::new(
+            Box::new(PrintEvent::new(Box::new(recorder), PrintEventFormat::Json)),
+            vec![DumpTrigger::Drop],
+            dump,
+        );
+
+        let mut event = Event::new();
+        sink.process_one(&event).unwrap();
+        sink.flush().unwrap();
+        assert!(out.0.lock().unwrap().is_empty());
+
+        // The triggering event is part of the dump.
+        event.skb_drop = Some(SkbDropEvent {
+            subsys: None,
+            drop_reason: "NOT_SPECIFIED".to_string(),
+        });
+        sink.process_one(&event).unwrap();
+        let out = out.0.lock().unwrap();
+        assert_eq!(out.iter().filter(|b| **b == b'\n').count(), 2);
+        assert!(String::from_utf8_lossy(&out).contains("NOT_SPECIFIED"));
+    }
+}
@@ -0,0 +1,95 @@
+//! # Histogram
+//!
+//! Helpers to report latency distributions as power of 2 histograms.
+
+/// Width of the histogram bars.
+const HIST_WIDTH: usize = 40;
+
+/// Get the power of 2 bucket a value belongs to: bucket 0 holds 0 and bucket
+/// `i` holds values in [2^(i-1), 2^i).
+pub(crate) fn log2_bucket(val: u64) -> usize {
+    match val {
+        0 => 0,
+        val => 64 - val.leading_zeros() as usize,
+    }
+}
+
+/// Convert power of 2 buckets (see `log2_bucket`) to a list of their lower
+/// bound and number of samples. Leading and trailing empty buckets are
+/// omitted.
+pub(crate) fn log2_histogram(buckets: &[u64]) -> Vec<(u64, u64)> {
+    let (Some(first), Some(last)) = (
+        buckets.iter().position(|count| *count != 0),
+        buckets.iter().rposition(|count| *count != 0),
+    ) else {
+        return Vec::new();
+    };
+
+    buckets
+        .iter()
+        .enumerate()
+        .take(last + 1)
+        .skip(first)
+        .map(|(i, count)| (if i == 0 { 0 } else { 1 << (i - 1) }, *count))
+        .collect()
+}
+
+/// Render a histogram of durations, as returned by `log2_histogram`, one line
+/// per bucket.
+pub(crate) fn histogram_lines(hist: &[(u64, u64)]) -> Vec<String> {
+    let max = hist.iter().map(|(_, count)| *count).max().unwrap_or(1);
+    let ranges: Vec<_> = hist
+        .iter()
+        .map(|(low, _)| {
+            let high = if *low == 0 { 1 } else { low * 2 };
+            format!("[{}, {})", format_ns(*low), format_ns(high))
+        })
+        .collect();
+    let width = ranges.iter().map(|r| r.len()).max().unwrap_or(0);
+
+    ranges
+        .iter()
+        .zip(hist.iter())
+        .map(|(range, (_, count))| {
+            let bar = "@".repeat((count * HIST_WIDTH as u64).div_ceil(max) as usize);
+            format!("{range:<width$} {count:>8} |{bar:<HIST_WIDTH$}|")
+        })
+        .collect()
+}
+
+/// Format a duration in nanoseconds using the most appropriate unit.
+pub(crate) fn format_ns(ns: u64) -> String {
+    match ns {
+        0..1_000 => format!("{ns}ns"),
+        1_000..1_000_000 => format!("{:.1}us", ns as f64 / 1e3),
+        1_000_000..1_000_000_000 => format!("{:.1}ms", ns as f64 / 1e6),
+        _ => format!("{:.1}s", ns as f64 / 1e9),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram() {
+        assert_eq!(log2_bucket(0), 0);
+        assert_eq!(log2_bucket(1), 1);
+        assert_eq!(log2_bucket(5), 3);
+        assert_eq!(log2_bucket(u64::MAX), 64);
+
+        assert_eq!(
+            log2_histogram(&[0, 1, 0, 2, 0, 0]),
+            vec![(1, 1), (2, 0), (4, 2)]
+        );
+        assert!(log2_histogram(&[0, 0]).is_empty());
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_ns(999), "999ns");
+        assert_eq!(format_ns(1_500), "1.5us");
+        assert_eq!(format_ns(2_000_000), "2.0ms");
+        assert_eq!(format_ns(3_000_000_000), "3.0s");
+    }
+}
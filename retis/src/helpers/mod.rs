@@ -1,6 +1,10 @@
 pub(crate) mod bimap;
 pub(crate) mod file_rotate;
+pub(crate) mod flight_recorder;
+pub(crate) mod histogram;
 pub(crate) mod logger;
 pub(crate) mod net;
 pub(crate) mod pager;
 pub(crate) mod signals;
+pub(crate) mod stream;
+pub(crate) mod until;
@@ -0,0 +1,376 @@
+/// # Writer streaming events to a remote consumer
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, Write},
+    net::{TcpStream, ToSocketAddrs},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use log::{info, warn};
+
+use crate::events::{file::rotate::startup_event, helpers::time::TimeSpec, CollectionInfo};
+
+/// Maximum amount of data buffered while the consumer is not reachable, or
+/// not keeping up. Events are dropped once the limit is reached.
+const STREAM_BUFFER_MAX: usize = 64 * 1024 * 1024;
+/// Minimum interval between two connection attempts.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// Timeout used when connecting, and when flushing the remaining data.
+const STREAM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Remote consumer of a stream of events.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum StreamTarget {
+    /// TCP endpoint, as host:port.
+    Tcp(String),
+    /// Unix socket path.
+    Unix(PathBuf),
+}
+
+impl StreamTarget {
+    /// Parses a stream target given as an URL, e.g. "tcp://host:port" or
+    /// "unix:///path". Returns None if the input is not an URL.
+    pub(crate) fn from_url(url: &str) -> Result<Option<Self>> {
+        let target = if let Some(addr) = url.strip_prefix("tcp://") {
+            match addr.rsplit_once(':') {
+                Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+                    StreamTarget::Tcp(addr.to_string())
+                }
+                _ => bail!("Invalid TCP target {url}: expected tcp://HOST:PORT"),
+            }
+        } else if let Some(path) = url.strip_prefix("unix://") {
+            if !path.starts_with('/') {
+                bail!("Invalid unix target {url}: expected unix:///PATH");
+            }
+            StreamTarget::Unix(PathBuf::from(path))
+        } else if url.contains("://") {
+            bail!("Unsupported output {url}: only tcp:// and unix:// are supported");
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(target))
+    }
+
+    fn connect(&self) -> io::Result<Stream> {
+        let stream = match self {
+            StreamTarget::Tcp(addr) => {
+                let mut res = Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "Could not resolve address",
+                ));
+                for addr in addr.to_socket_addrs()? {
+                    res = TcpStream::connect_timeout(&addr, STREAM_TIMEOUT);
+                    if res.is_ok() {
+                        break;
+                    }
+                }
+                Stream::Tcp(res?)
+            }
+            StreamTarget::Unix(path) => Stream::Unix(UnixStream::connect(path)?),
+        };
+
+        stream.set_nonblocking(true)?;
+        Ok(stream)
+    }
+}
+
+impl fmt::Display for StreamTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamTarget::Tcp(addr) => write!(f, "tcp://{addr}"),
+            StreamTarget::Unix(path) => write!(f, "unix://{}", path.display()),
+        }
+    }
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        match self {
+            Stream::Tcp(s) => s.set_nonblocking(nonblocking),
+            Stream::Unix(s) => s.set_nonblocking(nonblocking),
+        }
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Stream::Tcp(s) => s.set_write_timeout(timeout),
+            Stream::Unix(s) => s.set_write_timeout(timeout),
+        }
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(s) => s.write(buf),
+            Stream::Unix(s) => s.write(buf),
+        }
+    }
+}
+
+/// Writer streaming events to a remote consumer, over TCP or a unix socket.
+/// Each write is expected to be a full event (line). A startup event is sent
+/// first on each new connection, so the stream can be decoded on its own.
+///
+/// Writes never block the collection: events are buffered while the consumer
+/// is not reachable (or does not keep up) and connecting is retried, at most
+/// every RECONNECT_INTERVAL, when new events are written. Events are dropped
+/// once STREAM_BUFFER_MAX is reached.
+pub(crate) struct StreamWriter {
+    target: StreamTarget,
+    stream: Option<Stream>,
+    // Data not sent yet.
+    buf: VecDeque<u8>,
+    // Is the beginning of the buffer in the middle of a line, e.g. after
+    // losing the connection while sending an event?
+    partial: bool,
+    // Startup event, sent first on each connection.
+    startup: Vec<u8>,
+    last_attempt: Option<Instant>,
+    // Number of events dropped as the buffer was full or as they were
+    // partially sent before losing the connection.
+    dropped: u64,
+}
+
+impl StreamWriter {
+    /// Create a new `StreamWriter`; connecting is done lazily.
+    pub(crate) fn new(
+        target: StreamTarget,
+        cmdline: &str,
+        monotonic_offset: TimeSpec,
+        collection: Option<CollectionInfo>,
+    ) -> Result<Self> {
+        let mut startup = serde_json::to_vec(&startup_event(
+            cmdline,
+            monotonic_offset,
+            collection.as_ref(),
+            0,
+            None,
+        )?)?;
+        startup.push(b'\n');
+
+        let mut writer = Self {
+            target,
+            stream: None,
+            buf: VecDeque::new(),
+            partial: false,
+            startup,
+            last_attempt: None,
+            dropped: 0,
+        };
+
+        if let Err(e) = writer.connect() {
+            warn!(
+                "Could not connect to {}, retrying later: {e}",
+                writer.target
+            );
+        }
+        Ok(writer)
+    }
+
+    fn connect(&mut self) -> Result<()> {
+        self.last_attempt = Some(Instant::now());
+        let stream = self.target.connect()?;
+
+        // Skip the remaining part of an event we partially sent on the
+        // previous connection.
+        if self.partial {
+            match self.buf.iter().position(|b| *b == b'\n') {
+                Some(pos) => {
+                    self.buf.drain(..=pos);
+                }
+                None => self.buf.clear(),
+            }
+            self.partial = false;
+        }
+
+        self.startup
+            .iter()
+            .rev()
+            .for_each(|b| self.buf.push_front(*b));
+        self.stream = Some(stream);
+
+        info!("Streaming events to {}", self.target);
+        Ok(())
+    }
+
+    /// Send as much buffered data as possible, without blocking. Connection
+    /// errors are not fatal.
+    fn send(&mut self) {
+        if self.stream.is_none() {
+            if self
+                .last_attempt
+                .is_some_and(|last| last.elapsed() < RECONNECT_INTERVAL)
+            {
+                return;
+            }
+            if self.connect().is_err() {
+                return;
+            }
+        }
+
+        let Some(mut stream) = self.stream.take() else {
+            return;
+        };
+        match self.send_buf(&mut stream) {
+            Ok(()) => self.stream = Some(stream),
+            Err(e) => warn!("Lost connection to {}: {e}", self.target),
+        }
+    }
+
+    fn send_buf(&mut self, stream: &mut Stream) -> io::Result<()> {
+        while !self.buf.is_empty() {
+            let (data, _) = self.buf.as_slices();
+            match stream.write(data) {
+                Ok(0) => {
+                    self.lost();
+                    return Err(io::Error::from(io::ErrorKind::WriteZero));
+                }
+                Ok(n) => {
+                    self.partial = data[n - 1] != b'\n';
+                    self.buf.drain(..n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.lost();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Account for an event partially sent before losing the connection.
+    fn lost(&mut self) {
+        if self.partial {
+            self.dropped += 1;
+        }
+    }
+}
+
+impl Write for StreamWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + buf.len() > STREAM_BUFFER_MAX {
+            self.dropped += 1;
+        } else {
+            self.buf.extend(buf);
+        }
+
+        self.send();
+        Ok(buf.len())
+    }
+
+    /// Try sending all buffered data, blocking for up to STREAM_TIMEOUT.
+    fn flush(&mut self) -> io::Result<()> {
+        if self.stream.is_none() {
+            self.last_attempt = None;
+        }
+        self.send();
+
+        if let Some(stream) = self.stream.as_ref() {
+            stream.set_nonblocking(false)?;
+            stream.set_write_timeout(Some(STREAM_TIMEOUT))?;
+            self.send();
+            if let Some(stream) = self.stream.as_ref() {
+                stream.set_nonblocking(true)?;
+            }
+        }
+
+        if !self.buf.is_empty() {
+            warn!(
+                "Could not send {} byte(s) to {}",
+                self.buf.len(),
+                self.target
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StreamWriter {
+    fn drop(&mut self) {
+        if self.dropped > 0 {
+            warn!(
+                "{} event(s) could not be streamed to {}",
+                self.dropped, self.target
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        os::unix::net::UnixListener,
+    };
+
+    use super::*;
+
+    #[test]
+    fn from_url() {
+        assert_eq!(StreamTarget::from_url("retis.data").unwrap(), None);
+        assert_eq!(
+            StreamTarget::from_url("tcp://localhost:9000").unwrap(),
+            Some(StreamTarget::Tcp("localhost:9000".to_string()))
+        );
+        assert_eq!(
+            StreamTarget::from_url("tcp://[::1]:9000").unwrap(),
+            Some(StreamTarget::Tcp("[::1]:9000".to_string()))
+        );
+        assert_eq!(
+            StreamTarget::from_url("unix:///run/retis.sock").unwrap(),
+            Some(StreamTarget::Unix(PathBuf::from("/run/retis.sock")))
+        );
+
+        assert!(StreamTarget::from_url("tcp://localhost").is_err());
+        assert!(StreamTarget::from_url("tcp://:9000").is_err());
+        assert!(StreamTarget::from_url("tcp://localhost:foo").is_err());
+        assert!(StreamTarget::from_url("unix://retis.sock").is_err());
+        assert!(StreamTarget::from_url("udp://localhost:9000").is_err());
+
+        let target = StreamTarget::from_url("tcp://localhost:9000")
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.to_string(), "tcp://localhost:9000");
+    }
+
+    #[test]
+    fn stream() {
+        let path = std::env::temp_dir().join(format!("retis-stream-{}.sock", std::process::id()));
+        let target = StreamTarget::Unix(path.clone());
+
+        // Events are buffered until the consumer is reachable.
+        let mut writer =
+            StreamWriter::new(target, "retis collect", TimeSpec::new(0, 0), None).unwrap();
+        assert!(writer.stream.is_none());
+        writer.write_all(b"{\"event\":1}\n").unwrap();
+
+        let listener = UnixListener::bind(&path).unwrap();
+        writer.flush().unwrap();
+        writer.write_all(b"{\"event\":2}\n").unwrap();
+        writer.flush().unwrap();
+
+        let (conn, _) = listener.accept().unwrap();
+        let lines: Vec<String> = BufReader::new(conn)
+            .lines()
+            .take(3)
+            .map(|l| l.unwrap())
+            .collect();
+        assert!(lines[0].contains("startup"));
+        assert_eq!(lines[1], "{\"event\":1}");
+        assert_eq!(lines[2], "{\"event\":2}");
+
+        drop(writer);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
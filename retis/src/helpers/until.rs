@@ -0,0 +1,294 @@
+/// # Stop conditions
+///
+/// Conditions evaluated on the live event stream of a collection, ending it
+/// once one of them is met (see `retis collect --until`).
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use pcap::{BpfProgram, Capture, Linktype};
+use regex::Regex;
+
+use super::file_rotate::parse_interval;
+use crate::events::*;
+
+/// Event counter a condition can be evaluated on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Counter {
+    /// Events reported by probes.
+    Events,
+    /// Packet drops.
+    Drops,
+}
+
+/// Condition ending a collection.
+#[derive(Clone, Debug)]
+pub(crate) enum StopCondition {
+    /// A counter reached a given value: "<COUNTER> > N" or "<COUNTER> >= N".
+    Count { counter: Counter, min: u64 },
+    /// The collection ran for a given duration: "duration <INTERVAL>".
+    Duration(Duration),
+    /// An event whose packet matches a packet filter was reported, using the
+    /// same syntax as `--filter-packet` (pcap-filter): "event matches
+    /// <FILTER>".
+    Matches(String),
+    /// An event matching a regular expression was reported, using its single
+    /// line text representation: "event matches-regex <REGEX>".
+    MatchesRegex(Regex),
+}
+
+/// Compile a packet filter, to be matched against the packets of events (which
+/// always start with an Ethernet header).
+fn compile_filter(filter: &str) -> Result<BpfProgram> {
+    Capture::dead(Linktype::ETHERNET)?
+        .compile(filter, true)
+        .map_err(|e| anyhow!("Invalid packet filter ({filter}): {e}"))
+}
+
+impl FromStr for StopCondition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (word, rest) = s.split_at(
+            s.find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(s.len()),
+        );
+        let rest = rest.trim_start();
+
+        Ok(match word {
+            "duration" => StopCondition::Duration(Duration::from_secs(parse_interval(rest)?)),
+            "event" => match (
+                rest.strip_prefix("matches-regex"),
+                rest.strip_prefix("matches"),
+            ) {
+                (Some(regex), _) if regex.starts_with(char::is_whitespace) => {
+                    StopCondition::MatchesRegex(Regex::new(regex.trim())?)
+                }
+                (None, Some(filter)) if filter.starts_with(char::is_whitespace) => {
+                    compile_filter(filter.trim())?;
+                    StopCondition::Matches(filter.trim().to_string())
+                }
+                _ => bail!(
+                    "Invalid condition ({s}): expected event matches <FILTER> or event matches-regex <REGEX>"
+                ),
+            },
+            "events" | "drops" => {
+                let counter = match word {
+                    "events" => Counter::Events,
+                    _ => Counter::Drops,
+                };
+                // Check ">=" first, as ">" is a prefix of it.
+                let (value, inclusive) = match rest.strip_prefix(">=") {
+                    Some(value) => (value, true),
+                    None => match rest.strip_prefix('>') {
+                        Some(value) => (value, false),
+                        None => bail!("Invalid condition ({s}): expected {word} > N or {word} >= N"),
+                    },
+                };
+                let value = value
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|e| anyhow!("Invalid condition ({s}): {e}"))?;
+
+                let min = match inclusive {
+                    true => value,
+                    false => value.saturating_add(1),
+                };
+                if min == 0 {
+                    bail!("Invalid condition ({s}): always met");
+                }
+                StopCondition::Count { counter, min }
+            }
+            _ => bail!(
+                "Invalid condition ({s}): expected drops > N, events > N, duration <INTERVAL>, event matches <FILTER> or event matches-regex <REGEX>"
+            ),
+        })
+    }
+}
+
+impl fmt::Display for StopCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopCondition::Count { counter, min } => {
+                let counter = match counter {
+                    Counter::Events => "events",
+                    Counter::Drops => "drops",
+                };
+                write!(f, "{counter} >= {min}")
+            }
+            StopCondition::Duration(duration) => write!(f, "duration {}s", duration.as_secs()),
+            StopCondition::Matches(filter) => write!(f, "event matches {filter}"),
+            StopCondition::MatchesRegex(regex) => write!(f, "event matches-regex {regex}"),
+        }
+    }
+}
+
+/// Evaluates a set of stop conditions, any of them ending the collection.
+pub(crate) struct StopConditions {
+    conditions: Vec<StopCondition>,
+    /// Compiled packet filters, for each condition.
+    filters: Vec<Option<BpfProgram>>,
+    start: Instant,
+    events: u64,
+    drops: u64,
+    /// Format used to match events against regular expressions.
+    format: DisplayFormat,
+}
+
+impl StopConditions {
+    pub(crate) fn new(conditions: Vec<StopCondition>) -> Result<Self> {
+        let filters = conditions
+            .iter()
+            .map(|c| match c {
+                StopCondition::Matches(filter) => compile_filter(filter).map(Some),
+                _ => Ok(None),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            conditions,
+            filters,
+            start: Instant::now(),
+            events: 0,
+            drops: 0,
+            format: DisplayFormat::new(),
+        })
+    }
+
+    /// Account for an event reported by a probe and return the condition it
+    /// met, if any.
+    pub(crate) fn process(&mut self, event: &Event) -> Option<&StopCondition> {
+        self.events += 1;
+        if event.skb_drop.is_some() {
+            self.drops += 1;
+        }
+
+        let mut text = None;
+        self.conditions
+            .iter()
+            .zip(self.filters.iter())
+            .find(|(c, filter)| match c {
+                StopCondition::Count { counter, min } => match counter {
+                    Counter::Events => self.events >= *min,
+                    Counter::Drops => self.drops >= *min,
+                },
+                StopCondition::Duration(_) => false,
+                StopCondition::Matches(_) => match (filter, &event.packet) {
+                    (Some(filter), Some(packet)) => filter.filter(&packet.data.0),
+                    _ => false,
+                },
+                StopCondition::MatchesRegex(regex) => {
+                    regex.is_match(text.get_or_insert_with(|| {
+                        format!("{}", event.display(&self.format, &FormatterConf::new()))
+                    }))
+                }
+            })
+            .map(|(c, _)| c)
+    }
+
+    /// Return the duration condition met, if any.
+    pub(crate) fn expired(&self) -> Option<&StopCondition> {
+        self.conditions.iter().find(|c| match c {
+            StopCondition::Duration(duration) => self.start.elapsed() >= *duration,
+            _ => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drop_event() -> Event {
+        Event {
+            skb_drop: Some(SkbDropEvent {
+                subsys: None,
+                drop_reason: "NOT_SPECIFIED".to_string(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_conditions() {
+        let parse = |s: &str| StopCondition::from_str(s).map(|c| c.to_string());
+
+        assert_eq!(parse("drops > 100").unwrap(), "drops >= 101");
+        assert_eq!(parse("drops>=100").unwrap(), "drops >= 100");
+        assert_eq!(parse(" events > 0 ").unwrap(), "events >= 1");
+        assert_eq!(parse("duration 5m").unwrap(), "duration 300s");
+        assert_eq!(
+            parse("event matches tcp port 443").unwrap(),
+            "event matches tcp port 443"
+        );
+        assert_eq!(
+            parse("event matches-regex tcp_v4_rcv.*dport 443").unwrap(),
+            "event matches-regex tcp_v4_rcv.*dport 443"
+        );
+
+        assert!(parse("drops >= 0").is_err());
+        assert!(parse("drops < 100").is_err());
+        assert!(parse("drops > foo").is_err());
+        assert!(parse("duration 5y").is_err());
+        assert!(parse("event matches").is_err());
+        assert!(parse("event matches tcp port foo").is_err());
+        assert!(parse("event matches-regex").is_err());
+        assert!(parse("event matches-regex (").is_err());
+        assert!(parse("event matchesfoo").is_err());
+        assert!(parse("event foo").is_err());
+        assert!(parse("foo").is_err());
+    }
+
+    #[test]
+    fn conditions() {
+        let mut until = StopConditions::new(vec![
+            "drops > 1".parse().unwrap(),
+            "event matches-regex ^foo$".parse().unwrap(),
+        ])
+        .unwrap();
+
+        assert!(until.process(&drop_event()).is_none());
+        assert!(until.process(&Event::default()).is_none());
+        assert_eq!(
+            until.process(&drop_event()).map(|c| c.to_string()),
+            Some("drops >= 2".to_string())
+        );
+        assert!(until.expired().is_none());
+
+        let mut until = StopConditions::new(vec!["duration 1s".parse().unwrap()]).unwrap();
+        assert!(until.process(&drop_event()).is_none());
+        until.start -= Duration::from_secs(1);
+        assert!(until.expired().is_some());
+    }
+
+    #[test]
+    fn packet_filter() {
+        // Ethernet, IPv4 and TCP headers, from port 1234 to port 443.
+        let mut data = vec![0; 54];
+        data[12..14].copy_from_slice(&[0x08, 0x00]);
+        data[14] = 0x45;
+        data[23] = 6;
+        data[34..38].copy_from_slice(&[0x04, 0xd2, 0x01, 0xbb]);
+        let event = Event {
+            packet: Some(PacketEvent {
+                len: 54,
+                capture_len: 54,
+                data: RawPacket(data),
+                vlan_accel: None,
+            }),
+            ..Default::default()
+        };
+
+        let mut until =
+            StopConditions::new(vec!["event matches udp port 443".parse().unwrap()]).unwrap();
+        assert!(until.process(&event).is_none());
+        let mut until =
+            StopConditions::new(vec!["event matches tcp dst port 443".parse().unwrap()]).unwrap();
+        assert!(until.process(&Event::default()).is_none());
+        assert!(until.process(&event).is_some());
+    }
+}
@@ -1,9 +1,12 @@
 use anyhow::Result;
 
+mod agent;
 mod bindings;
 mod cli;
 mod collect;
 mod core;
+mod ctl;
+mod daemon;
 mod generate;
 mod helpers;
 mod inspect;
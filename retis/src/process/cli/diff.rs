@@ -0,0 +1,289 @@
+//! # Diff
+//!
+//! Diff is a post-processing command comparing two captures, e.g. collected on
+//! a working and on a failing setup.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+use clap::Parser;
+
+use super::stats::GroupBy;
+use crate::{
+    cli::*,
+    events::{file::FileType, *},
+    helpers::{file_rotate::InputDataFile, signals::Running},
+    process::{series::EventSorter, tracking::AddTracking},
+};
+
+/// Number of events buffered while sorting them into series, see `retis sort
+/// --max-buffer`.
+const SORT_BUFFER: usize = 1000;
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "diff",
+    about = "Compare two captures.",
+    long_about = "Compare two captures.
+
+Compares the per-probe number of events, drop reasons, conntrack states and packet paths (the list of probes packets went through) of two captures, e.g. collected on a working and on a failing setup, and reports the differences. Entries only found in the first capture are prefixed with '-', entries only found in the second one with '+' and entries found in both but with a different count with '~'."
+)]
+pub(crate) struct Diff {
+    #[arg(help = "First event file to compare, e.g. from a working setup")]
+    pub(super) a: InputDataFile,
+    #[arg(help = "Second event file to compare, e.g. from a failing setup")]
+    pub(super) b: InputDataFile,
+    #[arg(
+        long,
+        help = "Also report the entries with the same count in both captures"
+    )]
+    pub(super) all: bool,
+}
+
+/// Category of the compared entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Category {
+    Probe,
+    DropReason,
+    CtState,
+    Path,
+}
+
+impl Category {
+    fn name(&self) -> &'static str {
+        match self {
+            Category::Probe => "Probes",
+            Category::DropReason => "Drop reasons",
+            Category::CtState => "Conntrack states",
+            Category::Path => "Packet paths",
+        }
+    }
+}
+
+/// Summary of a capture, used for comparison.
+#[derive(Default)]
+struct CaptureSummary {
+    counts: HashMap<(Category, String), u64>,
+}
+
+impl CaptureSummary {
+    /// Summarize an event file.
+    fn from_input(input: &InputDataFile, run: &Running) -> Result<Self> {
+        let mut summary = Self::default();
+        let mut factory = input.to_factory()?;
+
+        match factory.file_type() {
+            FileType::Series => {
+                while run.running() {
+                    match factory.next_series()? {
+                        Some(series) => summary.add_series(&series),
+                        None => break,
+                    }
+                }
+            }
+            FileType::Event => {
+                let mut sorter = EventSorter::new();
+                let mut tracker = AddTracking::new();
+
+                while run.running() {
+                    match factory.next_event()? {
+                        Some(mut event) => {
+                            tracker.process_one(&mut event)?;
+                            sorter.add(event);
+
+                            while sorter.len() >= SORT_BUFFER {
+                                match sorter.pop_oldest()? {
+                                    Some(series) => summary.add_series(&series),
+                                    None => break,
+                                }
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                while let Some(series) = sorter.pop_oldest()? {
+                    summary.add_series(&series);
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    fn add_series(&mut self, series: &EventSeries) {
+        let mut path = Vec::new();
+
+        series
+            .events
+            .iter()
+            .filter(|e| e.startup.is_none())
+            .for_each(|event| {
+                [
+                    (Category::Probe, GroupBy::Probe),
+                    (Category::DropReason, GroupBy::DropReason),
+                    (Category::CtState, GroupBy::CtState),
+                ]
+                .iter()
+                .for_each(|(category, key)| {
+                    if let Some(val) = key.value(event) {
+                        *self.counts.entry((*category, val)).or_insert(0) += 1;
+                    }
+                });
+
+                if let Some(kernel) = &event.kernel {
+                    path.push(kernel.symbol.clone());
+                } else if let Some(user) = &event.userspace {
+                    path.push(user.symbol.clone());
+                }
+            });
+
+        if !path.is_empty() {
+            *self
+                .counts
+                .entry((Category::Path, path.join(" > ")))
+                .or_insert(0) += 1;
+        }
+    }
+}
+
+/// Difference of an entry between two captures.
+#[derive(Debug, PartialEq, Eq)]
+struct DiffEntry {
+    category: Category,
+    key: String,
+    a: u64,
+    b: u64,
+}
+
+impl DiffEntry {
+    fn marker(&self) -> char {
+        match (self.a, self.b) {
+            (0, _) => '+',
+            (_, 0) => '-',
+            (a, b) if a != b => '~',
+            _ => ' ',
+        }
+    }
+}
+
+/// Compare two capture summaries. Entries are sorted by category, then by
+/// decreasing difference.
+fn diff(a: &CaptureSummary, b: &CaptureSummary, all: bool) -> Vec<DiffEntry> {
+    let keys: BTreeSet<_> = a.counts.keys().chain(b.counts.keys()).collect();
+
+    let mut entries: Vec<_> = keys
+        .into_iter()
+        .map(|entry| DiffEntry {
+            category: entry.0,
+            key: entry.1.clone(),
+            a: a.counts.get(entry).copied().unwrap_or(0),
+            b: b.counts.get(entry).copied().unwrap_or(0),
+        })
+        .filter(|e| all || e.a != e.b)
+        .collect();
+
+    entries.sort_by(|x, y| {
+        x.category
+            .cmp(&y.category)
+            .then_with(|| y.a.abs_diff(y.b).cmp(&x.a.abs_diff(x.b)))
+            .then_with(|| x.key.cmp(&y.key))
+    });
+    entries
+}
+
+impl SubCommandParserRunner for Diff {
+    fn run(&mut self, _: &MainConfig) -> Result<()> {
+        let run = Running::new()?;
+
+        let a = CaptureSummary::from_input(&self.a, &run)?;
+        let b = CaptureSummary::from_input(&self.b, &run)?;
+
+        let entries = diff(&a, &b, self.all);
+        if entries.is_empty() {
+            println!("No difference found");
+            return Ok(());
+        }
+
+        println!("--- {}", self.a.path.display());
+        println!("+++ {}", self.b.path.display());
+
+        let mut category = None;
+        for entry in entries.iter() {
+            if category != Some(entry.category) {
+                println!("\n{}:", entry.category.name());
+                category = Some(entry.category);
+            }
+
+            let delta = entry.b as i128 - entry.a as i128;
+            println!(
+                "{} {:>8} {:>8} {:>+9}  {}",
+                entry.marker(),
+                entry.a,
+                entry.b,
+                delta,
+                entry.key
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(symbols: &[&str], drop: bool) -> EventSeries {
+        let mut events: Vec<_> = symbols
+            .iter()
+            .map(|symbol| Event {
+                kernel: Some(KernelEvent {
+                    symbol: symbol.to_string(),
+                    probe_type: "kprobe".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .collect();
+        if drop {
+            events.last_mut().unwrap().skb_drop = Some(SkbDropEvent {
+                subsys: None,
+                drop_reason: "NETFILTER_DROP".to_string(),
+            });
+        }
+        EventSeries { events }
+    }
+
+    #[test]
+    fn capture_diff() {
+        let mut a = CaptureSummary::default();
+        a.add_series(&series(&["ip_rcv", "tcp_v4_rcv"], false));
+        a.add_series(&series(&["ip_rcv", "tcp_v4_rcv"], false));
+
+        let mut b = CaptureSummary::default();
+        b.add_series(&series(&["ip_rcv", "tcp_v4_rcv"], false));
+        b.add_series(&series(&["ip_rcv", "kfree_skb"], true));
+
+        let entries = diff(&a, &b, false);
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|e| (e.marker(), e.category, e.key.as_str(), e.a, e.b))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ('+', Category::Probe, "kprobe/kfree_skb", 0, 1),
+                ('~', Category::Probe, "kprobe/tcp_v4_rcv", 2, 1),
+                ('+', Category::DropReason, "NETFILTER_DROP", 0, 1),
+                ('+', Category::Path, "ip_rcv > kfree_skb", 0, 1),
+                ('~', Category::Path, "ip_rcv > tcp_v4_rcv", 2, 1),
+            ]
+        );
+
+        // Unchanged entries.
+        let entries = diff(&a, &b, true);
+        assert!(entries
+            .iter()
+            .any(|e| e.key == "kprobe/ip_rcv" && e.marker() == ' '));
+        assert!(diff(&a, &a, false).is_empty());
+    }
+}
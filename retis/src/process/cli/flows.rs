@@ -0,0 +1,367 @@
+//! # Flows
+//!
+//! Flows is a post-processing command aggregating the packets of a capture by
+//! flow and printing the resulting flow table.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+
+use crate::{
+    cli::*,
+    events::{file::FileType, helpers::time::format_date_time, *},
+    helpers::{file_rotate::InputDataFile, signals::Running},
+    process::{
+        cli::stats::{print_table, GroupBy},
+        tracking::AddTracking,
+    },
+};
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "flows",
+    about = "Print the flow table of a capture.",
+    long_about = "Print the flow table of a capture.
+
+Packets are aggregated by flow (5-tuple) and the following is reported for each flow: its number of packets and bytes, the time it was first and last seen, the interfaces its packets were seen on (in order) and the conntrack states, drop reasons and ICMP errors reported for its packets. Packets are identified using their tracking information (the skb-tracking collector should be used), each event being considered a packet otherwise. Events without packet section are attributed to the flow of their packet, if known. ICMP errors reported by the icmp-error collector are attributed to the flow of the packet which triggered them.
+
+Flows are sorted by decreasing traffic."
+)]
+pub(crate) struct Flows {
+    #[arg(help = InputDataFile::help())]
+    pub(super) input: Option<InputDataFile>,
+    #[arg(
+        short,
+        long,
+        help = "Aggregate both directions of flows (conversations) instead of reporting them separately"
+    )]
+    pub(super) bidirectional: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = FlowSort::Bytes,
+        help = "Metric flows are sorted by"
+    )]
+    pub(super) sort_by: FlowSort,
+    #[arg(long, help = "Print the time as UTC")]
+    pub(super) utc: bool,
+}
+
+/// Metric flows can be sorted by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum FlowSort {
+    #[default]
+    Bytes,
+    Packets,
+}
+
+impl SubCommandParserRunner for Flows {
+    fn run(&mut self, _: &MainConfig) -> Result<()> {
+        let run = Running::new()?;
+        let mut factory = self.input.clone().unwrap_or_default().to_factory()?;
+        let mut flows = FlowTable::new(self.bidirectional);
+        let mut tracker = AddTracking::new();
+
+        let format = DisplayFormat::new().time_format(if self.utc {
+            TimeFormat::UtcDate
+        } else {
+            TimeFormat::MonotonicTimestamp
+        });
+        let format = match factory.startup() {
+            Some(startup) => format.monotonic_offset(startup.clock_monotonic_offset),
+            None => format,
+        };
+
+        match factory.file_type() {
+            FileType::Event => {
+                while run.running() {
+                    match factory.next_event()? {
+                        Some(mut event) => {
+                            if event.startup.is_none() {
+                                tracker.process_one(&mut event)?;
+                            }
+                            flows.process_event(&event);
+                        }
+                        None => break,
+                    }
+                }
+            }
+            FileType::Series => {
+                while run.running() {
+                    match factory.next_series()? {
+                        // Series already contain the tracking information.
+                        Some(series) => series.events.iter().for_each(|e| flows.process_event(e)),
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        print_table(&flows.table(self.sort_by, &format));
+        Ok(())
+    }
+}
+
+/// Statistics of a flow.
+#[derive(Default)]
+struct FlowStats {
+    packets: u64,
+    bytes: u64,
+    /// Timestamps of the first and last events of the flow.
+    first: u64,
+    last: u64,
+    /// Interfaces packets were seen on, in order of appearance.
+    path: Vec<String>,
+    /// Conntrack states, in order of appearance.
+    ct: Vec<String>,
+    /// Number of drops, by reason.
+    drops: Vec<(String, u64)>,
+    /// Number of ICMP errors triggered, by error.
+    icmp_errors: Vec<(String, u64)>,
+}
+
+/// Aggregates packets by flow.
+struct FlowTable {
+    /// Aggregate both directions of flows.
+    bidirectional: bool,
+    flows: HashMap<PacketFlow, FlowStats>,
+    /// Flow of packets, by tracking id.
+    packets: HashMap<u128, PacketFlow>,
+}
+
+impl FlowTable {
+    fn new(bidirectional: bool) -> Self {
+        Self {
+            bidirectional,
+            flows: HashMap::new(),
+            packets: HashMap::new(),
+        }
+    }
+
+    /// Key of a flow in the table. Both directions of a flow share the same
+    /// key when aggregating them.
+    fn key(&self, flow: PacketFlow) -> PacketFlow {
+        if !self.bidirectional {
+            return flow;
+        }
+
+        let reverse = PacketFlow {
+            src: flow.dst,
+            dst: flow.src,
+            protocol: flow.protocol,
+            ports: flow.ports.map(|(sport, dport)| (dport, sport)),
+        };
+        flow.min(reverse)
+    }
+
+    fn process_event(&mut self, event: &Event) {
+        if event.startup.is_some() {
+            return;
+        }
+
+        let id = match (&event.tracking, &event.skb_tracking) {
+            (Some(tracking), _) => Some(tracking.skb.tracking_id()),
+            (None, Some(skb)) => Some(skb.tracking_id()),
+            _ => None,
+        };
+        let packet = event.packet.as_ref();
+        let flow = packet
+            .and_then(|p| p.data.flow())
+            // ICMP errors embed the header of the packet which triggered them.
+            .or_else(|| event.icmp_error.as_ref().and_then(|e| e.flow()))
+            .map(|f| self.key(f));
+
+        let (flow, new_packet) = match (flow, id) {
+            (Some(flow), Some(id)) => {
+                let new = self.packets.insert(id, flow.clone()).as_ref() != Some(&flow);
+                (flow, new)
+            }
+            (Some(flow), None) => (flow, true),
+            (None, Some(id)) => match self.packets.get(&id) {
+                Some(flow) => (flow.clone(), false),
+                None => return,
+            },
+            (None, None) => return,
+        };
+
+        let timestamp = event.common.as_ref().map(|c| c.timestamp).unwrap_or(0);
+        let stats = self.flows.entry(flow).or_insert_with(|| FlowStats {
+            first: timestamp,
+            last: timestamp,
+            ..Default::default()
+        });
+
+        if new_packet {
+            stats.packets += 1;
+            stats.bytes += packet.map(|p| p.len as u64).unwrap_or(0);
+        }
+        stats.first = stats.first.min(timestamp);
+        stats.last = stats.last.max(timestamp);
+
+        if let Some(iface) = GroupBy::Iface.value(event) {
+            if !stats.path.contains(&iface) {
+                stats.path.push(iface);
+            }
+        }
+        if let Some(state) = GroupBy::CtState.value(event) {
+            if !stats.ct.contains(&state) {
+                stats.ct.push(state);
+            }
+        }
+        if let Some(reason) = GroupBy::DropReason.value(event) {
+            Self::count(&mut stats.drops, reason);
+        }
+        if let Some(error) = &event.icmp_error {
+            Self::count(&mut stats.icmp_errors, error.error.clone());
+        }
+    }
+
+    /// Increment the counter of a value, keeping values in order of
+    /// appearance.
+    fn count(counters: &mut Vec<(String, u64)>, value: String) {
+        match counters.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, count)) => *count += 1,
+            None => counters.push((value, 1)),
+        }
+    }
+
+    /// Get the flow table, sorted by decreasing traffic, with a header line.
+    fn table(&self, sort: FlowSort, format: &DisplayFormat) -> Vec<Vec<String>> {
+        let mut flows: Vec<_> = self.flows.iter().collect();
+        flows.sort_by(|(a_flow, a), (b_flow, b)| {
+            let (a_val, b_val) = match sort {
+                FlowSort::Bytes => ((a.bytes, a.packets), (b.bytes, b.packets)),
+                FlowSort::Packets => ((a.packets, a.bytes), (b.packets, b.bytes)),
+            };
+            b_val.cmp(&a_val).then_with(|| a_flow.cmp(b_flow))
+        });
+
+        let list = |values: &[String]| match values.is_empty() {
+            true => "-".to_string(),
+            false => values.join(","),
+        };
+        let counters = |counters: &[(String, u64)]| {
+            list(
+                &counters
+                    .iter()
+                    .map(|(value, count)| format!("{value}:{count}"))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let time = |ts| format_date_time(format.time_format, ts, format.monotonic_offset);
+
+        let mut table = vec![[
+            "flow", "packets", "bytes", "first", "last", "path", "ct", "drops", "icmp",
+        ]
+        .iter()
+        .map(|h| h.to_string())
+        .collect()];
+        flows.into_iter().for_each(|(flow, stats)| {
+            let mut flow = flow.to_string();
+            if self.bidirectional {
+                flow = flow.replacen(" > ", " <> ", 1);
+            }
+            table.push(vec![
+                flow,
+                stats.packets.to_string(),
+                stats.bytes.to_string(),
+                time(stats.first),
+                time(stats.last),
+                match stats.path.is_empty() {
+                    true => "-".to_string(),
+                    false => stats.path.join(" > "),
+                },
+                list(&stats.ct),
+                counters(&stats.drops),
+                counters(&stats.icmp_errors),
+            ]);
+        });
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::file::FileEventsFactory;
+
+    fn flow_table(bidirectional: bool, sort: FlowSort) -> Vec<Vec<String>> {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        let mut flows = FlowTable::new(bidirectional);
+        while let Some(event) = factory.next_event().unwrap() {
+            flows.process_event(&event);
+        }
+        flows.table(sort, &DisplayFormat::new())
+    }
+
+    #[test]
+    fn flows() {
+        let table = flow_table(false, FlowSort::Bytes);
+        assert_eq!(
+            table,
+            vec![
+                vec!["flow", "packets", "bytes", "first", "last", "path", "ct", "drops", "icmp"],
+                vec![
+                    "192.168.125.10 > 192.168.125.11 ICMP",
+                    "3",
+                    "294",
+                    "30419169061793",
+                    "30420178384515",
+                    "veth-ns01 > veth-ns01-ovs > veth-ns02-ovs > veth-ns02",
+                    "new,established",
+                    "-",
+                    "-",
+                ],
+                vec![
+                    "192.168.125.11 > 192.168.125.10 ICMP",
+                    "3",
+                    "294",
+                    "30419169353765",
+                    "30420178622051",
+                    "veth-ns02 > veth-ns02-ovs > veth-ns01-ovs > veth-ns01",
+                    "reply",
+                    "NO_SOCKET:2",
+                    "-",
+                ],
+            ]
+        );
+
+        let table = flow_table(true, FlowSort::Packets);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[1][0], "192.168.125.10 <> 192.168.125.11 ICMP");
+        assert_eq!(
+            &table[1][1..5],
+            &["6", "588", "30419169061793", "30420178622051"]
+        );
+        assert_eq!(table[1][6], "new,reply,established");
+        assert_eq!(table[1][7], "NO_SOCKET:2");
+    }
+    #[test]
+    fn flows_icmp_errors() {
+        let mut flows = FlowTable::new(false);
+        let error = |error: &str| Event {
+            icmp_error: Some(IcmpErrorEvent {
+                error: error.to_string(),
+                orig: IcmpErrorOrig {
+                    src: "10.0.0.1".to_string(),
+                    dst: "10.0.0.2".to_string(),
+                    protocol: 17,
+                    sport: Some(4242),
+                    dport: Some(53),
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        flows.process_event(&error("port-unreachable"));
+        flows.process_event(&error("port-unreachable"));
+        flows.process_event(&error("frag-needed"));
+
+        let table = flows.table(FlowSort::Bytes, &DisplayFormat::new());
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[1][0], "10.0.0.1.4242 > 10.0.0.2.53 UDP");
+        assert_eq!(table[1][8], "port-unreachable:2,frag-needed:1");
+    }
+}
@@ -0,0 +1,297 @@
+//! # Grep
+//!
+//! Grep is a post-processing command printing the events matching a regular
+//! expression, optionally with the surrounding events of the same packet.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, stdout, ErrorKind},
+};
+
+use anyhow::Result;
+use clap::Parser;
+use regex::{Regex, RegexBuilder};
+
+use crate::{
+    cli::*,
+    events::{file::*, sink::EventSink, *},
+    helpers::{file_rotate::InputDataFile, signals::Running},
+    process::{display::*, symbolize::UserSymbolizer, tracking::AddTracking},
+};
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "grep",
+    about = "Print stored events matching a pattern.",
+    long_about = "Print stored events matching a pattern.
+
+Events are matched against a regular expression, either using their text representation (as displayed by 'retis print') or the value of selected fields. Surrounding events of the same packet (using the tracking information of events) can be printed alongside matching ones."
+)]
+pub(crate) struct Grep {
+    #[arg(help = "Regular expression events are matched against")]
+    pub(super) pattern: String,
+    #[arg(help = InputDataFile::help())]
+    pub(super) input: Option<InputDataFile>,
+    #[arg(
+        short,
+        long,
+        value_name = "FIELD",
+        help = "Match the pattern against the value of an event field rather than against the whole event, e.g. 'kernel.symbol' or 'skb-drop.drop_reason'. Fields are given using their path in the JSON representation of events, separated by dots. Can be used multiple times, events being matched if any of the fields matches"
+    )]
+    pub(super) field: Vec<String>,
+    #[arg(short, long, help = "Perform a case insensitive matching")]
+    pub(super) ignore_case: bool,
+    #[arg(
+        short = 'A',
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        help = "Print the N events of the same packet following matching events"
+    )]
+    pub(super) after_context: usize,
+    #[arg(
+        short = 'B',
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        help = "Print the N events of the same packet preceding matching events"
+    )]
+    pub(super) before_context: usize,
+    #[arg(long, help = "Format used when printing an event")]
+    #[clap(value_enum, default_value_t=CliDisplayFormat::MultiLine)]
+    pub(super) format: CliDisplayFormat,
+    #[arg(long, help = "Print the time as UTC")]
+    pub(super) utc: bool,
+    #[arg(short = 'e', help = "Print link-layer information from the packet")]
+    pub(super) print_ll: bool,
+}
+
+impl SubCommandParserRunner for Grep {
+    fn run(&mut self, main_config: &MainConfig) -> Result<()> {
+        let run = Running::new()?;
+        let mut factory = self.input.clone().unwrap_or_default().to_factory()?;
+
+        let format = DisplayFormat::new()
+            .multiline(self.format == CliDisplayFormat::MultiLine)
+            .time_format(if self.utc {
+                TimeFormat::UtcDate
+            } else {
+                TimeFormat::MonotonicTimestamp
+            })
+            .print_ll(self.print_ll)
+            .colors(main_config.colors);
+        let format = match factory.startup() {
+            Some(startup) => format.monotonic_offset(startup.clock_monotonic_offset),
+            None => format,
+        };
+
+        let mut grep = EventGrep::new(
+            RegexBuilder::new(&self.pattern)
+                .case_insensitive(self.ignore_case)
+                .build()?,
+            self.field.clone(),
+            self.before_context,
+            self.after_context,
+        );
+        let mut output = PrintEvent::new(Box::new(stdout()), PrintEventFormat::Text(format));
+        let mut symbolizer = UserSymbolizer::new();
+        let mut tracker = AddTracking::new();
+
+        let mut process = |mut event: Event| -> Result<()> {
+            if event.startup.is_some() {
+                return Ok(());
+            }
+
+            symbolizer.process_one(&mut event);
+            // Series already contain the tracking information.
+            if event.tracking.is_none() {
+                tracker.process_one(&mut event)?;
+            }
+
+            for event in grep.process(event, &format)? {
+                output.process_one(&event)?;
+            }
+            Ok(())
+        };
+
+        while run.running() {
+            let res = match factory.file_type() {
+                FileType::Event => match factory.next_event()? {
+                    Some(event) => process(event),
+                    None => break,
+                },
+                FileType::Series => match factory.next_series()? {
+                    Some(series) => series.events.into_iter().try_for_each(&mut process),
+                    None => break,
+                },
+            };
+
+            if let Err(e) = res {
+                match e.downcast_ref::<io::Error>() {
+                    Some(io_error) if io_error.kind() == ErrorKind::BrokenPipe => break,
+                    _ => return Err(e),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Matches events and keeps track of the surrounding ones of the same packet.
+struct EventGrep {
+    regex: Regex,
+    /// Fields to match, as JSON pointers. The whole event is matched if empty.
+    fields: Vec<String>,
+    before: usize,
+    after: usize,
+    /// Last non-matching events of packets, by tracking id.
+    before_buf: HashMap<u128, VecDeque<Event>>,
+    /// Number of events still to print after a match, by tracking id.
+    after_left: HashMap<u128, usize>,
+}
+
+impl EventGrep {
+    fn new(regex: Regex, fields: Vec<String>, before: usize, after: usize) -> Self {
+        Self {
+            regex,
+            fields: fields
+                .iter()
+                .map(|f| format!("/{}", f.replace('.', "/")))
+                .collect(),
+            before,
+            after,
+            before_buf: HashMap::new(),
+            after_left: HashMap::new(),
+        }
+    }
+
+    /// Does an event match? Events are matched using their text without
+    /// colors, for the result not to depend on the output being a terminal.
+    fn matches(&self, event: &Event, format: &DisplayFormat) -> Result<bool> {
+        if self.fields.is_empty() {
+            let format = format.colors(None);
+            let text = format!("{}", event.display(&format, &FormatterConf::new()));
+            return Ok(self.regex.is_match(&text));
+        }
+
+        let json = serde_json::to_value(event)?;
+        Ok(self
+            .fields
+            .iter()
+            .filter_map(|field| json.pointer(field))
+            .any(|val| match val.as_str() {
+                Some(val) => self.regex.is_match(val),
+                None => self.regex.is_match(&val.to_string()),
+            }))
+    }
+
+    /// Process an event, returning the events to print, in order.
+    fn process(&mut self, event: Event, format: &DisplayFormat) -> Result<Vec<Event>> {
+        let id = event.tracking.as_ref().map(|t| t.skb.tracking_id());
+
+        if self.matches(&event, format)? {
+            let mut events = Vec::new();
+            if let Some(id) = id {
+                if let Some(before) = self.before_buf.remove(&id) {
+                    events.extend(before);
+                }
+                if self.after > 0 {
+                    self.after_left.insert(id, self.after);
+                }
+            }
+            events.push(event);
+            return Ok(events);
+        }
+
+        let Some(id) = id else {
+            return Ok(Vec::new());
+        };
+
+        if let Some(left) = self.after_left.get_mut(&id) {
+            *left -= 1;
+            if *left == 0 {
+                self.after_left.remove(&id);
+            }
+            return Ok(vec![event]);
+        }
+
+        if self.before > 0 {
+            let buf = self.before_buf.entry(id).or_default();
+            if buf.len() == self.before {
+                buf.pop_front();
+            }
+            buf.push_back(event);
+        }
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grep(pattern: &str, fields: &[&str], before: usize, after: usize) -> Vec<u64> {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        let mut grep = EventGrep::new(
+            Regex::new(pattern).unwrap(),
+            fields.iter().map(|f| f.to_string()).collect(),
+            before,
+            after,
+        );
+        let mut tracker = AddTracking::new();
+        // Colors must not change what matches.
+        let format = DisplayFormat::new().colors(Some(ColorTheme::Dark));
+
+        let mut timestamps = Vec::new();
+        while let Some(mut event) = factory.next_event().unwrap() {
+            if event.startup.is_some() {
+                continue;
+            }
+            tracker.process_one(&mut event).unwrap();
+            grep.process(event, &format)
+                .unwrap()
+                .iter()
+                .for_each(|e| timestamps.push(e.common.as_ref().unwrap().timestamp));
+        }
+        timestamps
+    }
+
+    #[test]
+    fn event_grep() {
+        // Text matching.
+        assert_eq!(
+            grep(r"\[tp\] skb:kfree_skb", &[], 0, 0),
+            vec![30419169457160, 30420178673616]
+        );
+        assert_eq!(grep("KFREE_SKB", &[], 0, 0), Vec::<u64>::new());
+
+        // Field matching.
+        assert_eq!(
+            grep("^skb:kfree_skb$", &["kernel.symbol"], 0, 0),
+            vec![30419169457160, 30420178673616]
+        );
+        assert_eq!(
+            grep(".", &["skb-drop.drop_reason", "unknown.field"], 0, 0),
+            vec![30419169457160, 30420178673616]
+        );
+
+        // Context.
+        assert_eq!(
+            grep("kfree_skb", &["kernel.symbol"], 1, 0),
+            vec![
+                30419169449874,
+                30419169457160,
+                30420178658015,
+                30420178673616
+            ]
+        );
+        assert_eq!(
+            grep("30420178384515", &["skb-tracking.timestamp"], 0, 0).len(),
+            5
+        );
+        let after = grep("^30420178384515$", &["common.timestamp"], 0, 2);
+        assert_eq!(after, vec![30420178384515, 30420178449538, 30420178548014]);
+    }
+}
@@ -0,0 +1,327 @@
+//! # Import
+//!
+//! Converts pcap and pcapng files into Retis events, for externally captured
+//! traffic to be processed by the Retis post-processing commands.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use log::{info, warn};
+use pcap_file::{
+    pcap::PcapReader,
+    pcapng::{
+        blocks::{interface_description::InterfaceDescriptionOption, Block},
+        PcapNgReader,
+    },
+    DataLink,
+};
+
+use crate::{
+    cli::*,
+    events::{
+        file::{rotate::RotateWriter, EventFormat},
+        helpers::time::TimeSpec,
+        sink::EventSink,
+        *,
+    },
+    helpers::signals::Running,
+    process::display::{PrintEvent, PrintEventFormat},
+};
+
+/// Pcapng files start with a section header block, whose type is fixed.
+const PCAPNG_MAGIC: [u8; 4] = [0x0a, 0x0d, 0x0d, 0x0a];
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "import-pcap",
+    about = "Convert a pcap or pcapng file into Retis events.",
+    long_about = "Convert a pcap or pcapng file into Retis events.
+
+Each packet of the capture is converted into an event containing a packet section (and a device one when the interface name is known), so the Retis post-processing commands can be used on externally captured traffic. Event timestamps are the packet timestamps, use --utc when printing the events to display them as dates."
+)]
+pub(crate) struct ImportPcap {
+    #[arg(help = "Pcap or pcapng file to import")]
+    pub(super) input: PathBuf,
+    #[arg(
+        short,
+        long,
+        default_value = "retis.data",
+        help = "Write the events to a file"
+    )]
+    pub(super) out: PathBuf,
+}
+
+impl SubCommandParserRunner for ImportPcap {
+    fn run(&mut self, main_config: &MainConfig) -> Result<()> {
+        let run = Running::new()?;
+        let input = File::open(&self.input)
+            .or_else(|e| bail!("Could not open '{}': {e}", self.input.display()))?;
+
+        // Packet timestamps are wall-clock ones, no offset is needed to
+        // convert them.
+        let writer = RotateWriter::new(
+            &self.out,
+            None,
+            false,
+            EventFormat::Json,
+            &main_config.cmdline,
+            TimeSpec::new(0, 0),
+            None,
+        )
+        .or_else(|e| bail!("Could not create or open '{}': {e}", self.out.display()))?;
+        let mut sink = PrintEvent::new(Box::new(writer), PrintEventFormat::Json);
+
+        let mut importer = PcapImporter::default();
+        importer.import(input, &run, |event| sink.process_one(&event))?;
+        sink.flush()?;
+
+        importer.report_stats();
+        Ok(())
+    }
+}
+
+/// Converts packets from pcap and pcapng files into events.
+#[derive(Default)]
+struct PcapImporter {
+    /// Pcapng interfaces, with their link type and name (if any), by id.
+    ifaces: Vec<(DataLink, Option<String>)>,
+    /// Number of packets imported.
+    imported: u64,
+    /// Number of packets skipped because of an unsupported link type.
+    unsupported: u64,
+}
+
+impl PcapImporter {
+    /// Import all packets of a pcap or pcapng file.
+    fn import<R, F>(&mut self, input: R, run: &Running, mut callback: F) -> Result<()>
+    where
+        R: Read,
+        F: FnMut(Event) -> Result<()>,
+    {
+        let mut input = BufReader::new(input);
+
+        if input.fill_buf()?.starts_with(&PCAPNG_MAGIC) {
+            let mut reader = PcapNgReader::new(input)?;
+            while run.running() {
+                let Some(block) = reader.next_block() else {
+                    break;
+                };
+
+                match block? {
+                    Block::InterfaceDescription(idb) => {
+                        let name = idb.options.iter().find_map(|opt| match opt {
+                            InterfaceDescriptionOption::IfName(name) => Some(name.to_string()),
+                            _ => None,
+                        });
+                        self.ifaces.push((idb.linktype, name));
+                    }
+                    Block::EnhancedPacket(epb) => {
+                        let Some((linktype, name)) = self.ifaces.get(epb.interface_id as usize)
+                        else {
+                            bail!("Packet refers to unknown interface {}", epb.interface_id);
+                        };
+                        let (linktype, name) = (*linktype, name.clone());
+
+                        if let Some(event) =
+                            self.event(linktype, epb.timestamp, epb.original_len, &epb.data, name)
+                        {
+                            callback(event)?;
+                        }
+                    }
+                    Block::SimplePacket(spb) => {
+                        // Simple packets have no timestamp and always refer
+                        // to the first interface.
+                        let Some((linktype, name)) = self.ifaces.first() else {
+                            bail!("Packet found before any interface description");
+                        };
+                        let (linktype, name) = (*linktype, name.clone());
+
+                        if let Some(event) =
+                            self.event(linktype, Duration::ZERO, spb.original_len, &spb.data, name)
+                        {
+                            callback(event)?;
+                        }
+                    }
+                    // Interfaces are scoped to a section.
+                    Block::SectionHeader(_) => self.ifaces.clear(),
+                    _ => (),
+                }
+            }
+        } else {
+            let mut reader = PcapReader::new(input)?;
+            let linktype = reader.header().datalink;
+            while run.running() {
+                let Some(packet) = reader.next_packet() else {
+                    break;
+                };
+                let packet = packet?;
+
+                if let Some(event) = self.event(
+                    linktype,
+                    packet.timestamp,
+                    packet.orig_len,
+                    &packet.data,
+                    None,
+                ) {
+                    callback(event)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Convert a packet into an event. Returns None if the link type of the
+    /// packet is not supported.
+    fn event(
+        &mut self,
+        linktype: DataLink,
+        timestamp: Duration,
+        len: u32,
+        data: &[u8],
+        dev: Option<String>,
+    ) -> Option<Event> {
+        let (len, data) = match linktype {
+            DataLink::ETHERNET => (len, data.to_vec()),
+            // Raw IP packets: fake an Ethernet header, as the collector does
+            // for packets without a valid MAC header.
+            DataLink::RAW | DataLink::IPV4 | DataLink::IPV6 => {
+                let etype: u16 = match data.first().map(|b| b >> 4) {
+                    Some(4) => 0x0800,
+                    Some(6) => 0x86dd,
+                    _ => {
+                        self.unsupported += 1;
+                        return None;
+                    }
+                };
+
+                let mut raw = vec![0; 12];
+                raw.extend_from_slice(&etype.to_be_bytes());
+                raw.extend_from_slice(data);
+                (len.saturating_add(14), raw)
+            }
+            _ => {
+                self.unsupported += 1;
+                return None;
+            }
+        };
+
+        self.imported += 1;
+        Some(Event {
+            common: Some(CommonEvent {
+                timestamp: timestamp.as_nanos() as u64,
+                ..Default::default()
+            }),
+            dev: dev.map(|name| DevEvent {
+                name,
+                ifindex: 0,
+                rx_ifindex: None,
+            }),
+            packet: Some(PacketEvent {
+                // The original length can't be lower than the captured one.
+                len: len.max(data.len() as u32),
+                capture_len: data.len() as u32,
+                data: RawPacket(data),
+                vlan_accel: None,
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Report import statistics. Should be called after importing was
+    /// completed.
+    fn report_stats(&self) {
+        info!("{} packet(s) were imported", self.imported);
+
+        if self.unsupported != 0 {
+            warn!(
+                "{} packet(s) were skipped because of an unsupported link type",
+                self.unsupported
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pcap_file::pcap::{PcapHeader, PcapPacket, PcapWriter};
+
+    use super::*;
+
+    // ICMP echo request, without its Ethernet header.
+    const IP_PACKET: [u8; 28] = [
+        69, 0, 0, 28, 163, 249, 64, 0, 64, 1, 27, 73, 192, 168, 125, 10, 192, 168, 125, 11, 8, 0,
+        247, 255, 0, 0, 0, 0,
+    ];
+
+    fn import(datalink: DataLink, data: &[u8]) -> (PcapImporter, Vec<Event>) {
+        let mut writer = PcapWriter::with_header(
+            Vec::new(),
+            PcapHeader {
+                datalink,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        writer
+            .write_packet(&PcapPacket::new(
+                Duration::new(1742339565, 860103000),
+                data.len() as u32 + 10,
+                data,
+            ))
+            .unwrap();
+
+        let mut importer = PcapImporter::default();
+        let mut events = Vec::new();
+        importer
+            .import(
+                writer.into_writer().as_slice(),
+                &Running::ignore_signals(),
+                |event| {
+                    events.push(event);
+                    Ok(())
+                },
+            )
+            .unwrap();
+        (importer, events)
+    }
+
+    #[test]
+    fn import_pcap() {
+        let mut eth = vec![250, 92, 189, 142, 204, 1, 166, 194, 17, 113, 89, 69, 8, 0];
+        eth.extend_from_slice(&IP_PACKET);
+
+        let (importer, events) = import(DataLink::ETHERNET, &eth);
+        assert_eq!(importer.imported, 1);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].common.as_ref().unwrap().timestamp,
+            1742339565860103000
+        );
+        assert!(events[0].dev.is_none());
+        let packet = events[0].packet.as_ref().unwrap();
+        assert_eq!(packet.len, 52);
+        assert_eq!(packet.capture_len, 42);
+        assert_eq!(packet.data.0, eth);
+
+        // Raw IP packets get a fake Ethernet header.
+        let (_, events) = import(DataLink::RAW, &IP_PACKET);
+        let packet = events[0].packet.as_ref().unwrap();
+        assert_eq!(packet.len, 52);
+        assert_eq!(
+            &packet.data.0[..14],
+            &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 0]
+        );
+        assert_eq!(&packet.data.0[14..], &IP_PACKET);
+
+        let (importer, events) = import(DataLink::LINUX_SLL, &IP_PACKET);
+        assert!(events.is_empty());
+        assert_eq!(importer.unsupported, 1);
+    }
+}
@@ -0,0 +1,289 @@
+//! # Latency
+//!
+//! Latency is a post-processing command computing the latency of packets
+//! between two probes, using the skb tracking information of events.
+
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::{
+    cli::*,
+    events::{file::FileType, *},
+    helpers::{file_rotate::InputDataFile, histogram::*, signals::Running},
+};
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "latency",
+    about = "Compute the latency of packets between two probes.",
+    long_about = "Compute the latency of packets between two probes.
+
+Events of the same packet are paired using their skb tracking information (the skb-tracking collector must have been used), the latency being the time between the first event of a packet generated by the --from probe and the first following one generated by the --to probe. The latency distribution is then reported as percentiles and as an histogram."
+)]
+pub(crate) struct Latency {
+    #[arg(help = InputDataFile::help())]
+    pub(super) input: Option<InputDataFile>,
+    #[arg(
+        long,
+        value_name = "PROBE",
+        help = "Probe starting the measurement, as [TYPE:]TARGET using the same syntax as 'retis collect --probe', e.g. 'kprobe:ip_rcv' or 'tp:net:net_dev_xmit'. If TYPE is omitted, any probe type matches"
+    )]
+    pub(super) from: ProbePoint,
+    #[arg(
+        long,
+        value_name = "PROBE",
+        help = "Probe ending the measurement, see --from"
+    )]
+    pub(super) to: ProbePoint,
+}
+
+/// Probe events are generated by, as found in their kernel section.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ProbePoint {
+    /// Probe type, as reported in events (e.g. "kprobe"). Any type matches if
+    /// None.
+    probe_type: Option<String>,
+    symbol: String,
+}
+
+impl FromStr for ProbePoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (probe_type, symbol) = match s.split_once(':') {
+            Some(("kprobe" | "k", symbol)) => (Some("kprobe"), symbol),
+            Some(("kretprobe" | "kr", symbol)) => (Some("kretprobe"), symbol),
+            Some(("raw_tracepoint" | "tp", symbol)) => (Some("raw_tracepoint"), symbol),
+            _ => (None, s),
+        };
+        if symbol.is_empty() {
+            bail!("Invalid probe ({s}): no target");
+        }
+
+        Ok(Self {
+            probe_type: probe_type.map(String::from),
+            symbol: symbol.to_string(),
+        })
+    }
+}
+
+impl ProbePoint {
+    /// Was an event generated by the probe?
+    fn matches(&self, event: &Event) -> bool {
+        event.kernel.as_ref().is_some_and(|k| {
+            k.symbol == self.symbol
+                && self
+                    .probe_type
+                    .as_ref()
+                    .is_none_or(|probe_type| k.probe_type == *probe_type)
+        })
+    }
+}
+
+impl SubCommandParserRunner for Latency {
+    fn run(&mut self, _: &MainConfig) -> Result<()> {
+        let run = Running::new()?;
+        let mut factory = self.input.clone().unwrap_or_default().to_factory()?;
+        let mut latency = LatencyProcessor::new(self.from.clone(), self.to.clone());
+
+        match factory.file_type() {
+            FileType::Event => {
+                while run.running() {
+                    match factory.next_event()? {
+                        Some(event) => latency.process_event(&event),
+                        None => break,
+                    }
+                }
+            }
+            FileType::Series => {
+                while run.running() {
+                    match factory.next_series()? {
+                        Some(series) => series
+                            .events
+                            .iter()
+                            .for_each(|event| latency.process_event(event)),
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        latency.print();
+        Ok(())
+    }
+}
+
+/// Pairs events of the same packets and computes their latency.
+struct LatencyProcessor {
+    from: ProbePoint,
+    to: ProbePoint,
+    // Timestamp of the --from event of packets, by tracking id.
+    pending: HashMap<u128, u64>,
+    // Latencies, in nanoseconds.
+    samples: Vec<u64>,
+}
+
+impl LatencyProcessor {
+    fn new(from: ProbePoint, to: ProbePoint) -> Self {
+        Self {
+            from,
+            to,
+            pending: HashMap::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn process_event(&mut self, event: &Event) {
+        let (Some(tracking), Some(common)) = (&event.skb_tracking, &event.common) else {
+            return;
+        };
+        let id = tracking.tracking_id();
+
+        // Check the end probe first, for the latency to be 0 if both probes
+        // are the same.
+        if self.to.matches(event) {
+            if let Some(start) = self.pending.remove(&id) {
+                self.samples.push(common.timestamp.saturating_sub(start));
+                return;
+            }
+        }
+        if self.from.matches(event) {
+            self.pending.entry(id).or_insert(common.timestamp);
+        }
+    }
+
+    /// Get the latency at a given percentile, out of sorted samples.
+    fn percentile(samples: &[u64], percentile: f64) -> u64 {
+        let rank = ((percentile / 100.0) * samples.len() as f64).ceil() as usize;
+        samples[rank.clamp(1, samples.len()) - 1]
+    }
+
+    /// Get the histogram of the samples, as power of 2 buckets with their
+    /// lower bound and number of samples.
+    fn histogram(samples: &[u64]) -> Vec<(u64, u64)> {
+        let mut buckets = Vec::new();
+        samples.iter().for_each(|val| {
+            let i = log2_bucket(*val);
+            if buckets.len() <= i {
+                buckets.resize(i + 1, 0);
+            }
+            buckets[i] += 1;
+        });
+
+        log2_histogram(&buckets)
+    }
+
+    fn print(&mut self) {
+        println!(
+            "{} packet(s) seen from {} to {}",
+            self.samples.len(),
+            self.from.symbol,
+            self.to.symbol
+        );
+        if !self.pending.is_empty() {
+            println!(
+                "{} packet(s) seen from {} only",
+                self.pending.len(),
+                self.from.symbol
+            );
+        }
+        if self.samples.is_empty() {
+            return;
+        }
+
+        self.samples.sort_unstable();
+        let samples = &self.samples;
+        let mean = samples.iter().map(|s| *s as u128).sum::<u128>() / samples.len() as u128;
+
+        println!("\nmin {}", format_ns(samples[0]));
+        println!("mean {}", format_ns(mean as u64));
+        for p in [50.0, 90.0, 99.0, 99.9] {
+            println!("p{p} {}", format_ns(Self::percentile(samples, p)));
+        }
+        println!("max {}", format_ns(samples[samples.len() - 1]));
+
+        println!();
+        histogram_lines(&Self::histogram(samples))
+            .iter()
+            .for_each(|line| println!("{line}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(probe: &str, id: u64, ts: u64) -> Event {
+        let (probe_type, symbol) = probe.split_once(':').unwrap();
+        Event {
+            common: Some(CommonEvent {
+                timestamp: ts,
+                ..Default::default()
+            }),
+            kernel: Some(KernelEvent {
+                symbol: symbol.to_string(),
+                probe_type: probe_type.to_string(),
+                ..Default::default()
+            }),
+            skb_tracking: Some(SkbTrackingEvent {
+                orig_head: id,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn probe_point() {
+        let probe = ProbePoint::from_str("tp:net:net_dev_xmit").unwrap();
+        assert_eq!(probe.probe_type.as_deref(), Some("raw_tracepoint"));
+        assert_eq!(probe.symbol, "net:net_dev_xmit");
+        assert!(probe.matches(&event("raw_tracepoint:net:net_dev_xmit", 0, 0)));
+        assert!(!probe.matches(&event("kprobe:net:net_dev_xmit", 0, 0)));
+
+        let probe = ProbePoint::from_str("ip_rcv").unwrap();
+        assert!(probe.probe_type.is_none());
+        assert!(probe.matches(&event("kprobe:ip_rcv", 0, 0)));
+
+        assert!(ProbePoint::from_str("kprobe:").is_err());
+    }
+
+    #[test]
+    fn latency() {
+        let mut latency = LatencyProcessor::new(
+            ProbePoint::from_str("kprobe:ip_rcv").unwrap(),
+            ProbePoint::from_str("tp:net:net_dev_xmit").unwrap(),
+        );
+
+        [
+            event("kprobe:ip_rcv", 1, 100),
+            event("kprobe:ip_rcv", 2, 150),
+            // Only the first start event of a packet is used.
+            event("kprobe:ip_rcv", 1, 200),
+            event("kprobe:ip_local_deliver", 1, 250),
+            event("raw_tracepoint:net:net_dev_xmit", 1, 1100),
+            event("raw_tracepoint:net:net_dev_xmit", 2, 160),
+            // Packet 1 was already measured.
+            event("raw_tracepoint:net:net_dev_xmit", 1, 5000),
+            event("kprobe:ip_rcv", 3, 300),
+        ]
+        .iter()
+        .for_each(|e| latency.process_event(e));
+
+        assert_eq!(latency.samples, vec![1000, 10]);
+        assert_eq!(latency.pending.len(), 1);
+
+        let samples: Vec<u64> = (1..=100).collect();
+        assert_eq!(LatencyProcessor::percentile(&samples, 50.0), 50);
+        assert_eq!(LatencyProcessor::percentile(&samples, 99.9), 100);
+        assert_eq!(LatencyProcessor::percentile(&[7], 50.0), 7);
+
+        assert_eq!(
+            LatencyProcessor::histogram(&[0, 3, 3, 9]),
+            vec![(0, 1), (1, 0), (2, 2), (4, 0), (8, 1)]
+        );
+        assert_eq!(LatencyProcessor::histogram(&[5]), vec![(4, 1)]);
+    }
+}
@@ -2,6 +2,18 @@
 //!
 //! Provides cli commands to perform some post-processing.
 
+pub(crate) mod diff;
+pub(crate) use diff::*;
+
+pub(crate) mod grep;
+pub(crate) use grep::*;
+
+pub(crate) mod import;
+pub(crate) use import::*;
+
+pub(crate) mod latency;
+pub(crate) use latency::*;
+
 pub(crate) mod pcap;
 pub(crate) use self::pcap::*;
 
@@ -21,3 +33,12 @@ pub(crate) use schema::*;
 
 pub(crate) mod stats;
 pub(crate) use stats::*;
+
+pub(crate) mod watch;
+pub(crate) use watch::*;
+
+pub(crate) mod scrub;
+pub(crate) use scrub::*;
+
+pub(crate) mod flows;
+pub(crate) use flows::*;
@@ -50,8 +50,11 @@ struct EventParser {
     /// to a pcapng file, packet blocks can refer to it by its id.
     /// This map holds the internal cache of known interface names and their
     /// ids. Note we don't really use actual network interfaces, instead we
-    /// create fake interfaces based on probing points ({type}/{name}).
+    /// create fake interfaces based on probing points ({type}/{name}), and
+    /// optionally on the network device ({type}/{name}@{device}).
     ifaces: HashMap<String, u32>,
+    /// Use one interface per probing point and network device.
+    per_device: bool,
     /// Statistics.
     stats: EventParserStats,
     /// Time offset
@@ -117,9 +120,10 @@ enum SchemaBlockError {
 
 impl EventParser {
     /// Creates a new EventParser from a PcapNgWriter<W: Write>.
-    fn new() -> Self {
+    fn new(per_device: bool) -> Self {
         Self {
             ifaces: HashMap::new(),
+            per_device,
             stats: EventParserStats::default(),
             ts_off: None,
             wrote_header: false,
@@ -129,13 +133,27 @@ impl EventParser {
     /// Extract interface information adding any necessary block and updating internal cache
     /// accordingly.
     fn process_interface(&mut self, event: &Event, blocks: &mut Vec<Block<'_>>) -> Result<u32> {
-        let iface = if let Some(kernel) = &event.kernel {
+        let probe = if let Some(kernel) = &event.kernel {
             format!("{}/{}", kernel.probe_type, kernel.symbol)
         } else {
             bail!("only events with kernel sections are currently supported");
         };
+        let device = match &event.dev {
+            Some(dev) if self.per_device => Some(match dev.name.is_empty() {
+                true => dev.ifindex.to_string(),
+                false => dev.name.clone(),
+            }),
+            _ => None,
+        };
+
+        let (iface, desc) = match device {
+            Some(device) => (
+                format!("{probe}@{device}"),
+                format!("Fake interface for probe {probe} on device {device}"),
+            ),
+            None => (probe.clone(), format!("Fake interface for probe {probe}")),
+        };
         let key = iface.clone();
-        let desc = format!("Fake interface for probe {iface}");
 
         // If we see this iface for the first time, add a description block.
         let id = match self.ifaces.contains_key(&key) {
@@ -174,6 +192,17 @@ impl EventParser {
 
         self.stats.processed += 1;
 
+        // Short comment, giving the main information about the event at a
+        // glance, followed by the full event.
+        let mut summary = String::new();
+        if let Some(kernel) = &event.kernel {
+            summary.push_str(&format!("probe={}/{} ", kernel.probe_type, kernel.symbol));
+        }
+        summary.push_str(&format!("timestamp={}", common.timestamp));
+        if let Some(tracking) = &event.skb_tracking {
+            summary.push_str(&format!(" tracking_id=#{:x}", tracking.tracking_id()));
+        }
+
         let comment = format!(
             "{}",
             event.display(&DisplayFormat::new().multiline(true), &FormatterConf::new())
@@ -202,9 +231,11 @@ impl EventParser {
                 timestamp: Duration::from_nanos(i64::from(
                     TimeSpec::new(0, common.timestamp as i64) + self.ts_off.unwrap_or_default(),
                 ) as u64),
-                original_len: packet.len,
+                // The original length can't be lower than the captured one.
+                original_len: packet.len.max(packet.data.0.len() as u32),
                 data: Cow::Borrowed(&packet.data.0),
                 options: vec![
+                    EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned(summary))),
                     EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned(comment))),
                     EnhancedPacketOption::Common(CommonOption::CustomUtf8Copiable(
                         CustomUtf8Option {
@@ -252,6 +283,11 @@ pub(crate) struct Pcap {
         help = "Filter events from this probe. Probes should follow the [TYPE:]TARGET pattern. See `retis collect --help` for more details on the probe format. Use `retis stats` to get a list of probes."
     )]
     pub(super) probe: Option<String>,
+    #[arg(
+        long,
+        help = "Use one interface per probe and network device (when known) in the generated PCAP, instead of one per probe"
+    )]
+    pub(super) per_device: bool,
 }
 
 impl SubCommandParserRunner for Pcap {
@@ -295,7 +331,7 @@ impl SubCommandParserRunner for Pcap {
         handle_events(
             &self.input.clone().unwrap_or_default(),
             &filter,
-            &mut EventParser::new(),
+            &mut EventParser::new(self.per_device),
             write_block,
         )?;
         Ok(())
@@ -396,6 +432,9 @@ mod tests {
                         timestamp: Duration::from_nanos(1742339565860167909),
                         original_len: 98,
                         options: vec![
+                            EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned(
+                                "probe=kretprobe/ovs_dp_upcall timestamp=30419169125909 tracking_id=#1baa83c42ba1ffff8e95c3b67c00".to_string(),
+                            ))),
                             EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned("30419169125909 (6) [ping] 11330 [kr] ovs_dp_upcall #1baa83c42ba1ffff8e95c3b67c00 (skb ffff8e95d3009100)\n  192.168.125.10 > 192.168.125.11 tos 0x0 ttl 64 id 41977 off 0 [DF] len 84 proto ICMP (1) type 8 code 0\n  ns 0x1/4026531840 if 10 (veth-ns01-ovs) rxif 10\n  skb [csum none hash 0x7e2c5976 len 98 priority 0 users 1 dataref 1]\n  upcall_ret (6/30419169098548) ret 0".to_string()))),
                             EnhancedPacketOption::Common(CommonOption::CustomUtf8Copiable(
                                 CustomUtf8Option {
@@ -419,6 +458,9 @@ mod tests {
                         timestamp: Duration::from_nanos(1742339565860414774),
                         original_len: 98,
                         options: vec![
+                            EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned(
+                                "probe=kretprobe/ovs_dp_upcall timestamp=30419169372774 tracking_id=#1baa83c8a025ffff8e95c3b67c00".to_string(),
+                            ))),
                             EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned("30419169372774 (6) [handler8] 985/995 [kr] ovs_dp_upcall #1baa83c8a025ffff8e95c3b67c00 (skb ffff8e95d3009200)\n  192.168.125.11 > 192.168.125.10 tos 0x0 ttl 64 id 19491 off 0 len 84 proto ICMP (1) type 0 code 0\n  ns 0x1/4026531840 if 12 (veth-ns02-ovs) rxif 12\n  skb [csum none hash 0x7e2c5976 len 98 priority 0 users 1 dataref 1]\n  upcall_ret (6/30419169364667) ret 0".to_string()
                             ))),
                             EnhancedPacketOption::Common(CommonOption::CustomUtf8Copiable(
@@ -470,6 +512,9 @@ mod tests {
                         timestamp: Duration::from_nanos(1742339565860103793),
                         original_len: 98,
                         options: vec![
+                            EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned(
+                                "probe=raw_tracepoint/net:net_dev_start_xmit timestamp=30419169061793 tracking_id=#1baa83c42ba1ffff8e95c3b67c00".to_string(),
+                            ))),
                             EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned("30419169061793 (6) [ping] 11330 [tp] net:net_dev_start_xmit #1baa83c42ba1ffff8e95c3b67c00 (skb ffff8e95d3009100)\n  192.168.125.10 > 192.168.125.11 tos 0x0 ttl 64 id 41977 off 0 [DF] len 84 proto ICMP (1) type 8 code 0\n  ns 0x3/4026532741 if 11 (veth-ns01)\n  skb [csum none len 98 priority 0 users 1 dataref 1]\n  ct_state NEW status 0x8 icmp orig [192.168.125.10 > 192.168.125.11 type 8 code 0 id 29004] reply [192.168.125.11 > 192.168.125.10 type 0 code 0 id 29004] zone 0 mark 0".to_string()))),
                             EnhancedPacketOption::Common(CommonOption::CustomUtf8Copiable(CustomUtf8Option {
                                 pen: RETIS_PEN,
@@ -504,6 +549,9 @@ mod tests {
                         timestamp: Duration::from_nanos(1742339565860124348),
                         original_len: 98,
                         options: vec![
+                            EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned(
+                                "probe=raw_tracepoint/net:netif_receive_skb timestamp=30419169082348 tracking_id=#1baa83c42ba1ffff8e95c3b67c00".to_string(),
+                            ))),
                             EnhancedPacketOption::Common(CommonOption::Comment(Cow::Owned("30419169082348 (6) [ping] 11330 [tp] net:netif_receive_skb #1baa83c42ba1ffff8e95c3b67c00 (skb ffff8e95d3009100)\n  192.168.125.10 > 192.168.125.11 tos 0x0 ttl 64 id 41977 off 0 [DF] len 84 proto ICMP (1) type 8 code 0\n  ns 0x1/4026531840 if 10 (veth-ns01-ovs)\n  skb [csum none len 84 priority 0 users 1 dataref 1]".to_string()
                             ))),
                             EnhancedPacketOption::Common(CommonOption::CustomUtf8Copiable(CustomUtf8Option {
@@ -565,7 +613,7 @@ mod tests {
             match handle_events(
                 &file_path.parse().expect("Could not parse input file"),
                 &filter,
-                &mut EventParser::new(),
+                &mut EventParser::new(false),
                 write_blocks,
             ) {
                 Ok(v) => match expected_res {
@@ -597,4 +645,51 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_per_device() {
+        let filter =
+            |r#type: &str, name: &str| -> bool { r#type == "kretprobe" && name == "ovs_dp_upcall" };
+
+        let mut blocks = Vec::<Block>::new();
+        handle_events(
+            &"test_data/test_events_packets.json"
+                .parse()
+                .expect("Could not parse input file"),
+            &filter,
+            &mut EventParser::new(true),
+            |b: &Block| -> Result<()> {
+                blocks.push(b.clone().into_owned());
+                Ok(())
+            },
+        )
+        .expect("Failed to handle events");
+
+        let ifaces: Vec<_> = blocks
+            .iter()
+            .filter_map(|b| match b {
+                Block::InterfaceDescription(idb) => match &idb.options[0] {
+                    InterfaceDescriptionOption::IfName(name) => Some(name.to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            ifaces,
+            vec![
+                "kretprobe/ovs_dp_upcall@veth-ns01-ovs",
+                "kretprobe/ovs_dp_upcall@veth-ns02-ovs",
+            ]
+        );
+
+        let ids: Vec<_> = blocks
+            .iter()
+            .filter_map(|b| match b {
+                Block::EnhancedPacket(epb) => Some(epb.interface_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, vec![0, 1]);
+    }
 }
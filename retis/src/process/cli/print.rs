@@ -3,71 +3,255 @@
 //! Print is a simple post-processing command that just parses events and prints them back to
 //! stdout
 
-use std::io::{self, stdout, ErrorKind};
+use std::{
+    io::{self, stdout, ErrorKind},
+    thread,
+    time::Duration,
+};
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
 
 use crate::{
     cli::*,
-    events::{file::*, *},
-    helpers::{file_rotate::InputDataFile, signals::Running},
-    process::display::*,
+    events::{file::*, sink::EventSink, *},
+    helpers::{
+        file_rotate::{inputs_to_factory, InputDataFile},
+        signals::Running,
+    },
+    process::{
+        collapse::PrintCollapsed,
+        containers::ContainerMapper,
+        display::*,
+        fields::{Field, PrintFields, PrintTemplate, Template},
+        kube::KubeMapper,
+        netns::NetnsTracker,
+        selection::EventSelection,
+        symbolize::UserSymbolizer,
+    },
 };
 
+/// Interval at which a followed input is checked for new events.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Output format of the print command.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum PrintFormat {
+    SingleLine,
+    #[default]
+    MultiLine,
+    /// Comma-separated values, only supported with --fields.
+    Csv,
+}
+
 #[derive(Parser, Debug, Default)]
 #[command(name = "print", about = "Print stored events to stdout.")]
 pub(crate) struct Print {
     #[arg(help = InputDataFile::help())]
-    pub(super) input: Option<InputDataFile>,
+    pub(super) input: Vec<InputDataFile>,
+    #[arg(
+        long,
+        value_name = "NS",
+        allow_negative_numbers = true,
+        help = "Time delta, in nanoseconds, added to the events of an input when reading multiple inputs (e.g. files collected on different hosts), whose events are merged by timestamp. Timestamps are converted to the clock of the first input, the delta compensating for remaining clock differences between hosts. Can be used multiple times, each delta applying to the input at the same position."
+    )]
+    pub(super) delta: Vec<i64>,
     #[arg(long, help = "Format used when printing an event")]
-    #[clap(value_enum, default_value_t=CliDisplayFormat::MultiLine)]
-    pub(super) format: CliDisplayFormat,
+    #[clap(value_enum, default_value_t=PrintFormat::MultiLine)]
+    pub(super) format: PrintFormat,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "FIELDS",
+        help = "Only print the given fields of events (comma separated), as aligned columns or as CSV with --format csv. Fields are either one of ts, probe, tracking_id, iface, src, dst, proto, sport, dport, len, mss, wscale and sack, or the path of a field in the JSON representation of events, separated by dots (e.g. 'skb.meta.len' or 'ct.state'). E.g. '--fields ts,probe,src,dst,len'"
+    )]
+    pub(super) fields: Vec<Field>,
+    #[arg(
+        long,
+        conflicts_with = "fields",
+        help = "Print events following a template, one line per event. Fields are given as {FIELD}, using the same names as --fields, and braces can be printed by doubling them. E.g. '--template \"{ts} {kernel.symbol} {src} > {dst}\"'"
+    )]
+    pub(super) template: Option<Template>,
+    #[arg(
+        long,
+        conflicts_with_all = ["fields", "template"],
+        help = "Collapse consecutive identical events (same probe and flow, with no change in other fields such as the drop reason or the conntrack state) into a single one, printed with its number of repeats and the time of the last one. Useful to shorten traces of e.g. keepalives"
+    )]
+    pub(super) collapse: bool,
     #[arg(long, help = "Print the time as UTC")]
     pub(super) utc: bool,
     #[arg(short = 'e', help = "Print link-layer information from the packet")]
     pub(super) print_ll: bool,
+    #[arg(
+        long,
+        help = "Only print the collection information (Retis version, machine, probes, filters, etc) and exit"
+    )]
+    pub(super) header: bool,
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["header", "since", "until", "tracking_id", "first", "last"],
+        help = "Keep reading events appended to the input, e.g. while it is being written by 'retis collect -o', similarly to 'tail -f'. Stops on termination signals (e.g. Ctrl-C)"
+    )]
+    pub(super) follow: bool,
+    #[command(flatten)]
+    pub(super) selection: EventSelection,
 }
 
 impl SubCommandParserRunner for Print {
-    fn run(&mut self, _: &MainConfig) -> Result<()> {
+    fn run(&mut self, main_config: &MainConfig) -> Result<()> {
         // Create running instance that will handle signal termination.
         let run = Running::new()?;
 
         // Create event factory.
-        let mut factory = self.input.clone().unwrap_or_default().to_factory()?;
+        let mut factory = inputs_to_factory(&self.input, &self.delta)?;
 
         // Format.
         let format = DisplayFormat::new()
-            .multiline(self.format == CliDisplayFormat::MultiLine)
+            .multiline(self.format == PrintFormat::MultiLine)
             .time_format(if self.utc {
                 TimeFormat::UtcDate
             } else {
                 TimeFormat::MonotonicTimestamp
             })
-            .print_ll(self.print_ll);
+            .print_ll(self.print_ll)
+            .colors(main_config.colors);
+
+        if self.header {
+            return print_header(factory.startup(), format);
+        }
+        if self.format == PrintFormat::Csv && self.fields.is_empty() {
+            bail!("CSV output requires --fields");
+        }
+        if self.collapse && matches!(factory.file_type(), FileType::Series) {
+            bail!("--collapse cannot be used with sorted events");
+        }
+        if self.follow {
+            if matches!(factory.file_type(), FileType::Series) {
+                bail!("--follow cannot be used with sorted events");
+            }
+            factory.follow()?;
+        }
+
+        let mut selector = self.selection.selector(factory.startup())?;
+        if self.selection.selects() {
+            if matches!(factory.file_type(), FileType::Series) {
+                bail!("--since, --until, --tracking-id, --first and --last cannot be used with sorted events");
+            }
+
+            // Only read the part of the input containing the selected events,
+            // if possible. The startup event might not be read, retrieve the
+            // monotonic offset from the factory instead.
+            // This is only done for a single input, as merged inputs are
+            // read as a whole.
+            let input = match self.input.as_slice() {
+                [] => Some(InputDataFile::default()),
+                [input] if self.delta.is_empty() => Some(input.clone()),
+                _ => None,
+            };
+            if let Some(input) = input {
+                if let Some((start, end)) = selector.input_range(&input)? {
+                    factory = input.to_factory_range(start, end)?;
+                }
+            }
+        }
+        let format = match factory.startup() {
+            Some(startup) => format.monotonic_offset(startup.clock_monotonic_offset),
+            None => format,
+        };
+
+        let mut symbolizer = UserSymbolizer::new();
+        let mut netns = NetnsTracker::new();
+        let containers = ContainerMapper::new(factory.startup());
+        let kube = KubeMapper::new(factory.startup());
+
+        // Printer for selected fields of events, or following a template,
+        // including events of series.
+        let fields_output: Option<Box<dyn EventSink>> = match &self.template {
+            Some(template) => Some(Box::new(PrintTemplate::new(
+                Box::new(stdout()),
+                template.clone(),
+                format,
+            ))),
+            None if !self.fields.is_empty() => Some(Box::new(PrintFields::new(
+                Box::new(stdout()),
+                self.fields.clone(),
+                format,
+                self.format == PrintFormat::Csv,
+            ))),
+            None => None,
+        };
 
         match factory.file_type() {
             FileType::Event => {
                 // Formatter & printer for events.
-                let mut event_output =
-                    PrintEvent::new(Box::new(stdout()), PrintEventFormat::Text(format));
+                let mut event_output: Box<dyn EventSink> = match fields_output {
+                    Some(output) => output,
+                    None if self.collapse => {
+                        Box::new(PrintCollapsed::new(Box::new(stdout()), format))
+                    }
+                    None => Box::new(PrintEvent::new(
+                        Box::new(stdout()),
+                        PrintEventFormat::Text(format),
+                    )),
+                };
 
                 while run.running() {
-                    match factory.next_event()? {
-                        Some(event) => {
+                    match selector.next_event(&mut factory)? {
+                        Some(mut event) => {
+                            symbolizer.process_one(&mut event);
+                            netns.process_one(&mut event);
+                            containers.process_one(&mut event);
+                            kube.process_one(&mut event);
                             if let Err(e) = event_output.process_one(&event) {
                                 match e.downcast_ref::<io::Error>() {
                                     Some(io_error) if io_error.kind() == ErrorKind::BrokenPipe => {
-                                        break
+                                        return Ok(())
                                     }
                                     _ => return Err(e),
                                 }
                             }
                         }
+                        // Wait for new events to be written, after printing
+                        // the current ones.
+                        None if self.follow => {
+                            flush_output(event_output.as_mut())?;
+                            thread::sleep(FOLLOW_INTERVAL);
+                        }
                         None => break,
                     }
                 }
+
+                flush_output(event_output.as_mut())?;
+            }
+            FileType::Series if fields_output.is_some() => {
+                // Unwrap as we just checked it was Some.
+                let mut output = fields_output.unwrap();
+
+                while run.running() {
+                    match factory.next_series()? {
+                        Some(mut series) => {
+                            for event in series.events.iter_mut() {
+                                symbolizer.process_one(event);
+                                containers.process_one(event);
+                                kube.process_one(event);
+                                if let Err(e) = output.process_one(event) {
+                                    match e.downcast_ref::<io::Error>() {
+                                        Some(io_error)
+                                            if io_error.kind() == ErrorKind::BrokenPipe =>
+                                        {
+                                            return Ok(())
+                                        }
+                                        _ => return Err(e),
+                                    }
+                                }
+                            }
+                        }
+                        None => break,
+                    }
+                }
+
+                flush_output(output.as_mut())?;
             }
             FileType::Series => {
                 // Formatter & printer for series.
@@ -76,7 +260,12 @@ impl SubCommandParserRunner for Print {
 
                 while run.running() {
                     match factory.next_series()? {
-                        Some(series) => {
+                        Some(mut series) => {
+                            series.events.iter_mut().for_each(|event| {
+                                symbolizer.process_one(event);
+                                containers.process_one(event);
+                                kube.process_one(event);
+                            });
                             if let Err(e) = series_output.process_one(&series) {
                                 match e.downcast_ref::<io::Error>() {
                                     Some(io_error) if io_error.kind() == ErrorKind::BrokenPipe => {
@@ -95,3 +284,14 @@ impl SubCommandParserRunner for Print {
         Ok(())
     }
 }
+
+/// Flush an output, ignoring broken pipes (e.g. when piped to `head`).
+fn flush_output(output: &mut dyn EventSink) -> Result<()> {
+    if let Err(e) = output.flush() {
+        match e.downcast_ref::<io::Error>() {
+            Some(io_error) if io_error.kind() == ErrorKind::BrokenPipe => (),
+            _ => return Err(e),
+        }
+    }
+    Ok(())
+}
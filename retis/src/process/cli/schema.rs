@@ -2,23 +2,68 @@
 //!
 //! Print the json-schema definition of the retis event file
 
+use std::{fs, path::PathBuf};
+
 use anyhow::Result;
 use clap::Parser;
 use schemars::schema_for;
 
-use crate::{cli::*, events::Event};
+use crate::{
+    cli::*,
+    events::{file::proto, Event, EventSeries},
+};
 
 #[derive(Parser, Debug, Default)]
 #[command(
     name = "schema",
-    about = "Print the json-schema of event files produced by retis"
+    about = "Print the json-schema of event files produced by retis",
+    long_about = "Print the json-schema of event files produced by retis.
+
+The schema describes all the event sections, their fields, types and enums, for downstream tooling to validate event files and to generate code. The Retis version the schema was generated from is reported in the \"x-retis-version\" keyword, to be compared with the version found in the startup event of event files."
 )]
-pub(crate) struct PrintSchema {}
+pub(crate) struct PrintSchema {
+    #[arg(
+        long,
+        help = "Print the schema of sorted event files (see `retis sort`), made of event series, instead of the schema of events"
+    )]
+    pub(super) sorted: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "sorted",
+        help = "Print the protobuf definition of the messages used by protobuf event files (see `retis collect --out-format`), generated from the schema of events"
+    )]
+    pub(super) proto: bool,
+
+    #[arg(short, long, help = "Write the schema to a file instead of stdout")]
+    pub(super) out: Option<PathBuf>,
+}
 
 impl SubCommandParserRunner for PrintSchema {
     fn run(&mut self, _: &MainConfig) -> Result<()> {
-        let schema = schema_for!(Event);
-        print!("{}", serde_json::to_string_pretty(&schema)?);
+        if self.proto {
+            return self.write(proto::schema()?.to_proto());
+        }
+
+        let mut schema = match self.sorted {
+            true => schema_for!(EventSeries),
+            false => schema_for!(Event),
+        };
+        schema.insert(
+            "x-retis-version".to_string(),
+            option_env!("RELEASE_VERSION").unwrap_or("unspec").into(),
+        );
+
+        self.write(serde_json::to_string_pretty(&schema)?)
+    }
+}
+
+impl PrintSchema {
+    fn write(&self, schema: String) -> Result<()> {
+        match &self.out {
+            Some(out) => fs::write(out, schema)?,
+            None => print!("{schema}"),
+        }
         Ok(())
     }
 }
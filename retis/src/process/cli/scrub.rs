@@ -0,0 +1,937 @@
+//! # Scrub
+//!
+//! Scrub is a post-processing command rewriting an event file so it can be
+//! shared without leaking addresses, interface names or packet payloads.
+
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::BufWriter,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::{
+    cli::*,
+    events::{file::*, sink::EventSink, *},
+    helpers::{file_rotate::InputDataFile, signals::Running},
+    process::display::*,
+};
+
+/// First pseudonym used for IPv4 addresses (198.18.0.0/15 is reserved for
+/// benchmarking).
+const IPV4_BASE: Ipv4Addr = Ipv4Addr::new(198, 18, 0, 0);
+/// First pseudonym used for IPv6 addresses (documentation prefix).
+const IPV6_BASE: Ipv6Addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0);
+/// First pseudonym used for IPv6 link-local addresses.
+const IPV6_LL_BASE: Ipv6Addr = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0);
+
+const ETH_HLEN: usize = 14;
+const ETH_P_IP: u16 = 0x0800;
+const ETH_P_ARP: u16 = 0x0806;
+const ETH_P_IPV6: u16 = 0x86dd;
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "scrub",
+    about = "Anonymize stored events so they can be shared.",
+    long_about = "Anonymize stored events so they can be shared.
+
+Events are rewritten to a new file in which IP and MAC addresses are replaced by pseudonyms (from the 198.18.0.0/15 and 2001:db8::/32 ranges for IP addresses, and locally administered MAC addresses), and interface names by 'devN' ones. Pseudonyms are consistent across the file: a given address is always replaced by the same pseudonym, so packets and connections can still be followed. Special addresses (unspecified, loopback, broadcast and multicast ones) are kept.
+
+Addresses are rewritten in packets (Ethernet, ARP, IPv4 and IPv6 headers), conntrack tuples, OpenvSwitch flows and in the command line, filters and Kubernetes objects of the collection. Container ids, and container, pod, namespace and node names are replaced by pseudonyms as well. Packets are truncated after their L4 header (or after their last known header) unless --keep-payload is used, their checksums other than the IPv4 header one are not updated."
+)]
+pub(crate) struct Scrub {
+    #[arg(help = InputDataFile::help())]
+    pub(super) input: Option<InputDataFile>,
+    #[arg(short, long, help = "Write the scrubbed events to a file")]
+    pub(super) out: PathBuf,
+    #[arg(
+        long,
+        help = "Do not truncate packets after their headers. Payloads are kept as-is and can contain sensitive information"
+    )]
+    pub(super) keep_payload: bool,
+}
+
+impl SubCommandParserRunner for Scrub {
+    fn run(&mut self, _: &MainConfig) -> Result<()> {
+        let run = Running::new()?;
+        let input = self.input.clone().unwrap_or_default();
+        let mut factory = input.to_factory()?;
+
+        if factory.is_compat() {
+            bail!("Scrubbing events is not supported (input event format is too old)");
+        }
+
+        // Best-effort detection of the same file being used for the input and
+        // the output, see the sort command.
+        let out = match self.out.canonicalize() {
+            Ok(out) => out,
+            Err(_) => self.out.clone(),
+        };
+        if input.path.canonicalize().is_ok_and(|input| out.eq(&input)) {
+            bail!("Cannot scrub a file in-place. Please specify an output file that's different to the input one.");
+        }
+
+        let writer = Box::new(BufWriter::new(
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&out)
+                .or_else(|_| bail!("Could not create or open '{}'", out.display()))?,
+        ));
+
+        let mut scrubber = Scrubber::new(!self.keep_payload);
+        match factory.file_type() {
+            FileType::Event => {
+                let mut output = PrintEvent::new(writer, PrintEventFormat::Json);
+                while run.running() {
+                    match factory.next_event()? {
+                        Some(mut event) => {
+                            scrubber.event(&mut event);
+                            output.process_one(&event)?;
+                        }
+                        None => break,
+                    }
+                }
+                output.flush()?;
+            }
+            FileType::Series => {
+                let mut output = PrintSeries::new(writer, PrintEventFormat::Json);
+                while run.running() {
+                    match factory.next_series()? {
+                        Some(mut series) => {
+                            series.events.iter_mut().for_each(|e| scrubber.event(e));
+                            output.process_one(&series)?;
+                        }
+                        None => break,
+                    }
+                }
+                output.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces addresses and interface names of events by consistent pseudonyms,
+/// and truncates packet payloads.
+#[derive(Default)]
+struct Scrubber {
+    /// Truncate packets after their headers.
+    truncate: bool,
+    ips: HashMap<IpAddr, IpAddr>,
+    macs: HashMap<[u8; 6], [u8; 6]>,
+    ifnames: HashMap<String, String>,
+    /// Container, pod, namespace and node names.
+    names: HashMap<String, String>,
+    /// Container ids.
+    ids: HashMap<String, String>,
+    /// Number of IPv4 and IPv6 pseudonyms allocated.
+    ipv4_count: u32,
+    ipv6_count: u128,
+}
+
+impl Scrubber {
+    fn new(truncate: bool) -> Self {
+        Self {
+            truncate,
+            ..Default::default()
+        }
+    }
+
+    /// Scrub an event.
+    fn event(&mut self, event: &mut Event) {
+        if let Some(startup) = event.startup.as_mut() {
+            startup.cmdline = self.text(&startup.cmdline);
+            if let Some(collection) = startup.collection.as_mut() {
+                collection.filters = collection.filters.iter().map(|f| self.text(f)).collect();
+
+                for container in collection.containers.iter_mut().flatten() {
+                    container.id = self.id(&container.id);
+                    container.name = self.name(&container.name);
+                    container.pod = container.pod.as_ref().map(|p| self.name(p));
+                    container.pod_namespace =
+                        container.pod_namespace.as_ref().map(|n| self.name(n));
+                }
+
+                // Keep the Kubernetes addresses consistent with the scrubbed
+                // packets.
+                if let Some(kube) = collection.kube.as_mut() {
+                    for pod in kube.pods.iter_mut() {
+                        pod.name = self.name(&pod.name);
+                        pod.namespace = self.name(&pod.namespace);
+                        pod.node = pod.node.as_ref().map(|n| self.name(n));
+                    }
+                    for service in kube.services.iter_mut() {
+                        service.name = self.name(&service.name);
+                        service.namespace = self.name(&service.namespace);
+                    }
+                    for node in kube.nodes.iter_mut() {
+                        node.name = self.name(&node.name);
+                    }
+
+                    let ips = kube
+                        .pods
+                        .iter_mut()
+                        .map(|pod| &mut pod.ips)
+                        .chain(kube.services.iter_mut().map(|service| &mut service.ips))
+                        .chain(kube.nodes.iter_mut().map(|node| &mut node.ips));
+                    for ips in ips {
+                        *ips = ips.iter().map(|ip| self.text(ip)).collect();
+                    }
+                }
+            }
+        }
+
+        if let Some(packet) = event.packet.as_mut() {
+            let end = self.packet(&mut packet.data.0);
+            if self.truncate {
+                packet.data.0.truncate(end);
+                packet.capture_len = packet.data.0.len() as u32;
+            }
+        }
+
+        if let Some(dev) = event.dev.as_mut() {
+            dev.name = self.ifname(&dev.name);
+        }
+
+        // Container names include their pod and namespace, if any.
+        if let Some(netns) = event.netns.as_mut() {
+            netns.container = netns.container.as_ref().map(|c| self.path(c));
+        }
+        if let Some(sock) = event.sock.as_mut() {
+            sock.container = sock.container.as_ref().map(|c| self.path(c));
+        }
+
+        if let Some(kube) = event.kube.as_mut() {
+            for endpoint in [&mut kube.src, &mut kube.dst, &mut kube.iface]
+                .into_iter()
+                .flatten()
+            {
+                endpoint.name = self.name(&endpoint.name);
+                endpoint.namespace = endpoint.namespace.as_ref().map(|n| self.name(n));
+            }
+        }
+
+        if let Some(ct) = event.ct.as_mut() {
+            self.ct(&mut ct.base);
+            if let Some(parent) = ct.parent.as_mut() {
+                self.ct(parent);
+            }
+        }
+
+        if let Some(ct) = event.ct_lifecycle.as_mut() {
+            self.ct(&mut ct.conn);
+            if let Some(parent) = ct.parent.as_mut() {
+                self.ct(parent);
+            }
+        }
+
+        match event.ovs.as_mut() {
+            Some(OvsEvent::DpLookup { flow_lookup }) => {
+                flow_lookup.dpflow = self.text(&flow_lookup.dpflow);
+                flow_lookup.ofpflows = flow_lookup.ofpflows.iter().map(|f| self.text(f)).collect();
+            }
+            Some(OvsEvent::FlowUpdate { flow_update }) => {
+                flow_update.dpflow = self.text(&flow_update.dpflow);
+                flow_update.ofpflows = flow_update.ofpflows.iter().map(|f| self.text(f)).collect();
+            }
+            Some(OvsEvent::Action { action_execute }) => match action_execute.action.as_mut() {
+                Some(OvsAction::Ct {
+                    ct: OvsActionCt { nat: Some(nat), .. },
+                }) => {
+                    nat.min_addr = nat.min_addr.as_ref().map(|a| self.text(a));
+                    nat.max_addr = nat.max_addr.as_ref().map(|a| self.text(a));
+                }
+                Some(OvsAction::Set {
+                    set:
+                        OvsActionSet {
+                            tunnel: Some(tunnel),
+                        },
+                }) => {
+                    tunnel.src = self.text(&tunnel.src);
+                    tunnel.dst = self.text(&tunnel.dst);
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+
+        if let Some(detrace) = event.ovs_detrace.as_mut() {
+            detrace.dpflow = self.text(&detrace.dpflow);
+            detrace.ofpflows = detrace.ofpflows.iter().map(|f| self.text(f)).collect();
+        }
+
+        if let Some(sa) = event.xfrm.as_mut().and_then(|x| x.sa.as_mut()) {
+            sa.src = self.text(&sa.src);
+            sa.dst = self.text(&sa.dst);
+        }
+
+        if let Some(tunnel) = event.tunnel.as_mut() {
+            tunnel.src = self.text(&tunnel.src);
+            tunnel.dst = self.text(&tunnel.dst);
+        }
+
+        if let Some(tcp) = event.tcp.as_mut() {
+            tcp.saddr = self.text(&tcp.saddr);
+            tcp.daddr = self.text(&tcp.daddr);
+        }
+
+        if let Some(bridge) = event.bridge.as_mut() {
+            bridge.bridge = bridge.bridge.as_ref().map(|b| self.ifname(b));
+            if let Some(port) = bridge.port.as_mut() {
+                port.name = self.ifname(&port.name);
+            }
+            bridge.mac = bridge.mac.as_ref().map(|m| self.text(m));
+        }
+
+        if let Some(lag) = event.lag.as_mut() {
+            lag.master = self.ifname(&lag.master);
+            lag.port = lag.port.as_ref().map(|p| self.ifname(p));
+            lag.old_port = lag.old_port.as_ref().map(|p| self.ifname(p));
+        }
+
+        if let Some(peer) = event.wireguard.as_mut().and_then(|wg| wg.peer.as_mut()) {
+            peer.endpoint = peer.endpoint.as_ref().map(|e| self.text(e));
+        }
+
+        if let Some(dev) = event.ns_lifecycle.as_mut().and_then(|ns| ns.dev.as_mut()) {
+            dev.name = self.ifname(&dev.name);
+        }
+
+        if let Some(error) = event.icmp_error.as_mut() {
+            error.orig.src = self.text(&error.orig.src);
+            error.orig.dst = self.text(&error.orig.dst);
+        }
+
+        if let Some(filter) = event.offload.as_mut().and_then(|o| o.filter.as_mut()) {
+            filter.dev = filter.dev.as_ref().map(|d| self.ifname(d));
+        }
+
+        if let Some(pool) = event.mem.as_mut().and_then(|m| m.page_pool.as_mut()) {
+            pool.dev = pool.dev.as_ref().map(|d| self.ifname(d));
+        }
+
+        if let Some(xsk) = event.xsk.as_mut() {
+            xsk.dev = xsk.dev.as_ref().map(|d| self.ifname(d));
+        }
+
+        if let Some(mptcp) = event.mptcp.as_mut() {
+            if let Some(subflow) = mptcp.subflow.as_mut() {
+                subflow.saddr = self.text(&subflow.saddr);
+                subflow.daddr = self.text(&subflow.daddr);
+            }
+            if let Some(addr) = mptcp.addr.as_mut() {
+                addr.addr = self.text(&addr.addr);
+            }
+        }
+
+        if let Some(route) = event.route.as_mut() {
+            route.saddr = self.text(&route.saddr);
+            route.daddr = self.text(&route.daddr);
+            route.prefix = route.prefix.as_ref().map(|p| self.text(p));
+            route.gw = route.gw.as_ref().map(|gw| self.text(gw));
+            route.dev = route.dev.as_ref().map(|d| self.ifname(d));
+        }
+
+        if let Some(neigh) = event.neigh.as_mut() {
+            neigh.addr = self.text(&neigh.addr);
+            neigh.lladdr = neigh.lladdr.as_ref().map(|l| self.text(l));
+            neigh.dev = neigh.dev.as_ref().map(|d| self.ifname(d));
+        }
+
+        if let Some(external) = event.external.as_mut() {
+            external
+                .sections
+                .iter_mut()
+                .flat_map(|s| s.fields.iter_mut())
+                .filter(|f| matches!(f.kind.as_str(), "ipv4" | "ipv6" | "mac" | "str"))
+                .for_each(|f| f.value = self.text(&f.value));
+        }
+    }
+
+    /// Pseudonym of an IP address.
+    fn ip(&mut self, ip: IpAddr) -> IpAddr {
+        let keep = match ip {
+            IpAddr::V4(ip) => {
+                ip.is_unspecified() || ip.is_loopback() || ip.is_broadcast() || ip.is_multicast()
+            }
+            IpAddr::V6(ip) => ip.is_unspecified() || ip.is_loopback() || ip.is_multicast(),
+        };
+        if keep {
+            return ip;
+        }
+
+        if let Some(pseudo) = self.ips.get(&ip) {
+            return *pseudo;
+        }
+
+        let pseudo = match ip {
+            IpAddr::V4(_) => {
+                self.ipv4_count += 1;
+                IpAddr::V4(Ipv4Addr::from(u32::from(IPV4_BASE) + self.ipv4_count))
+            }
+            IpAddr::V6(ip) => {
+                self.ipv6_count += 1;
+                // Keep link-local addresses in their range, as their scope
+                // matters.
+                let base = match ip.segments()[0] & 0xffc0 == 0xfe80 {
+                    true => IPV6_LL_BASE,
+                    false => IPV6_BASE,
+                };
+                IpAddr::V6(Ipv6Addr::from(u128::from(base) + self.ipv6_count))
+            }
+        };
+        self.ips.insert(ip, pseudo);
+        pseudo
+    }
+
+    /// Pseudonym of a MAC address.
+    fn mac(&mut self, mac: [u8; 6]) -> [u8; 6] {
+        // Keep the null, broadcast and multicast addresses.
+        if mac == [0; 6] || mac[0] & 1 != 0 {
+            return mac;
+        }
+
+        let count = self.macs.len() as u32 + 1;
+        *self.macs.entry(mac).or_insert_with(|| {
+            let [_, a, b, c] = count.to_be_bytes();
+            [0x02, 0, 0, a, b, c]
+        })
+    }
+
+    /// Pseudonym of an interface name.
+    fn ifname(&mut self, name: &str) -> String {
+        if name.is_empty() || name == "lo" {
+            return name.to_string();
+        }
+
+        let count = self.ifnames.len();
+        self.ifnames
+            .entry(name.to_string())
+            .or_insert_with(|| format!("dev{count}"))
+            .clone()
+    }
+
+    /// Pseudonym of a container, pod, namespace or node name.
+    fn name(&mut self, name: &str) -> String {
+        if name.is_empty() {
+            return name.to_string();
+        }
+
+        let count = self.names.len();
+        self.names
+            .entry(name.to_string())
+            .or_insert_with(|| format!("name{count}"))
+            .clone()
+    }
+
+    /// Pseudonym of a "namespace/pod/container" like name, each part being
+    /// replaced on its own.
+    fn path(&mut self, path: &str) -> String {
+        path.split('/')
+            .map(|part| self.name(part))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Pseudonym of a container id, keeping its length.
+    fn id(&mut self, id: &str) -> String {
+        let count = self.ids.len() + 1;
+        self.ids
+            .entry(id.to_string())
+            .or_insert_with(|| format!("{count:0len$x}", len = id.len()))
+            .clone()
+    }
+
+    /// Replace the IP and MAC addresses found in a text.
+    fn text(&mut self, text: &str) -> String {
+        let is_addr = |c: char| c.is_ascii_hexdigit() || c == ':' || c == '.';
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find(is_addr) {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find(|c| !is_addr(c)).unwrap_or(rest.len());
+            let (token, tail) = rest.split_at(end);
+
+            // Punctuation can follow addresses.
+            let trimmed = token.trim_end_matches(['.', ':']);
+            // So can ports, e.g. "10.0.0.1:80". IPv6 addresses use brackets
+            // in such cases, which are not part of the token.
+            let (addr, port) = match trimmed.rsplit_once(':') {
+                Some((host, port))
+                    if host.parse::<Ipv4Addr>().is_ok()
+                        && !port.is_empty()
+                        && port.bytes().all(|b| b.is_ascii_digit()) =>
+                {
+                    (host, &trimmed[host.len()..])
+                }
+                _ => (trimmed, ""),
+            };
+            match addr.parse::<IpAddr>() {
+                Ok(ip) => out.push_str(&self.ip(ip).to_string()),
+                Err(_) => match parse_mac(addr) {
+                    Some(mac) => out.push_str(&format_mac(&self.mac(mac))),
+                    None => out.push_str(addr),
+                },
+            }
+            out.push_str(port);
+            out.push_str(&token[trimmed.len()..]);
+            rest = tail;
+        }
+
+        out.push_str(rest);
+        out
+    }
+
+    /// Scrub the addresses of a conntrack connection.
+    fn ct(&mut self, conn: &mut CtConnEvent) {
+        for ip in [&mut conn.orig.ip, &mut conn.reply.ip] {
+            ip.src = self.text(&ip.src);
+            ip.dst = self.text(&ip.dst);
+        }
+        if let Some(nat) = conn.nat.as_mut() {
+            for mangle in [nat.src.as_mut(), nat.dst.as_mut()].into_iter().flatten() {
+                mangle.pre_addr = self.text(&mangle.pre_addr);
+                mangle.post_addr = self.text(&mangle.post_addr);
+            }
+        }
+    }
+
+    /// Replace the addresses of an Ethernet packet in place. Returns the
+    /// offset of its payload, that is the end of its L4 header or of the last
+    /// header known (and valid).
+    fn packet(&mut self, data: &mut [u8]) -> usize {
+        if data.len() < ETH_HLEN {
+            return 0;
+        }
+        self.mac_at(data, 0);
+        self.mac_at(data, 6);
+
+        let mut etype = u16::from_be_bytes([data[12], data[13]]);
+        let mut off = ETH_HLEN;
+        // VLAN tags.
+        while matches!(etype, 0x8100 | 0x88a8 | 0x9100) {
+            if data.len() < off + 4 {
+                return off;
+            }
+            etype = u16::from_be_bytes([data[off + 2], data[off + 3]]);
+            off += 4;
+        }
+
+        match etype {
+            ETH_P_IP => self.ipv4(data, off),
+            ETH_P_IPV6 => self.ipv6(data, off),
+            ETH_P_ARP => self.arp(data, off),
+            _ => off,
+        }
+    }
+
+    fn ipv4(&mut self, data: &mut [u8], off: usize) -> usize {
+        let ihl = match data.get(off) {
+            Some(b) => (b & 0xf) as usize * 4,
+            None => return off,
+        };
+        if ihl < 20 || data.len() < off + ihl {
+            return off;
+        }
+
+        self.ip_at(data, off + 12, 4);
+        self.ip_at(data, off + 16, 4);
+
+        data[off + 10..off + 12].fill(0);
+        let csum = ipv4_checksum(&data[off..off + ihl]);
+        data[off + 10..off + 12].copy_from_slice(&csum.to_be_bytes());
+
+        // Only the first fragment contains the L4 header.
+        if u16::from_be_bytes([data[off + 6], data[off + 7]]) & 0x1fff != 0 {
+            return off + ihl;
+        }
+        l4_end(data, data[off + 9], off + ihl)
+    }
+
+    fn ipv6(&mut self, data: &mut [u8], off: usize) -> usize {
+        if data.len() < off + 40 {
+            return off;
+        }
+
+        self.ip_at(data, off + 8, 16);
+        self.ip_at(data, off + 24, 16);
+        l4_end(data, data[off + 6], off + 40)
+    }
+
+    fn arp(&mut self, data: &mut [u8], off: usize) -> usize {
+        // Only Ethernet/IPv4 ARP packets are supported.
+        if data.len() < off + 28 || data[off + 4..off + 6] != [6, 4] {
+            return off;
+        }
+
+        self.mac_at(data, off + 8);
+        self.ip_at(data, off + 14, 4);
+        self.mac_at(data, off + 18);
+        self.ip_at(data, off + 24, 4);
+        off + 28
+    }
+
+    fn mac_at(&mut self, data: &mut [u8], off: usize) {
+        let mut mac = [0; 6];
+        mac.copy_from_slice(&data[off..off + 6]);
+        data[off..off + 6].copy_from_slice(&self.mac(mac));
+    }
+
+    fn ip_at(&mut self, data: &mut [u8], off: usize, len: usize) {
+        let bytes = &mut data[off..off + len];
+        let ip = match len {
+            4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(&*bytes).unwrap())),
+            _ => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(&*bytes).unwrap())),
+        };
+        match self.ip(ip) {
+            IpAddr::V4(ip) => bytes.copy_from_slice(&ip.octets()),
+            IpAddr::V6(ip) => bytes.copy_from_slice(&ip.octets()),
+        }
+    }
+}
+
+/// End of the L4 header starting at `off`, if known and valid. Returns `off`
+/// otherwise.
+fn l4_end(data: &[u8], protocol: u8, off: usize) -> usize {
+    let len = match protocol {
+        // TCP.
+        6 => data.get(off + 12).map(|b| (b >> 4) as usize * 4),
+        // UDP, ICMP & ICMPv6.
+        17 | 1 | 58 => Some(8),
+        // SCTP common header.
+        132 => Some(12),
+        _ => None,
+    };
+
+    match len {
+        Some(len) if data.len() >= off + len => off + len,
+        _ => off,
+    }
+}
+
+fn ipv4_checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header
+        .chunks(2)
+        .map(|c| u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)]) as u32)
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+fn parse_mac(text: &str) -> Option<[u8; 6]> {
+    let mut mac = [0; 6];
+    let mut parts = text.split(':');
+    for byte in mac.iter_mut() {
+        let part = parts.next()?;
+        if part.len() != 2 {
+            return None;
+        }
+        *byte = u8::from_str_radix(part, 16).ok()?;
+    }
+    parts.next().is_none().then_some(mac)
+}
+
+fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrub_text() {
+        let mut scrubber = Scrubber::new(true);
+        assert_eq!(
+            scrubber.text("retis collect -f 'host 10.0.0.1 or host 10.0.0.2'"),
+            "retis collect -f 'host 198.18.0.1 or host 198.18.0.2'"
+        );
+        assert_eq!(
+            scrubber.text("ipv4(src=10.0.0.2,dst=255.255.255.255),eth(src=aa:bb:cc:dd:ee:ff)"),
+            "ipv4(src=198.18.0.2,dst=255.255.255.255),eth(src=02:00:00:00:00:01)"
+        );
+        assert_eq!(
+            scrubber.text("2001:abcd::1 and fe80::1234, ::1."),
+            "2001:db8::1 and fe80::2, ::1."
+        );
+        assert_eq!(
+            scrubber.text("deadbeef 12:34:56 1.2"),
+            "deadbeef 12:34:56 1.2"
+        );
+        assert_eq!(
+            scrubber.text("flow: 10.0.0.1:1234 <-> 10.0.0.2:80/tcp"),
+            "flow: 198.18.0.1:1234 <-> 198.18.0.2:80/tcp"
+        );
+        assert_eq!(
+            scrubber.text("-o tcp://192.0.2.1:9000 [2001:abcd::1]:80 10.0.0.1:"),
+            "-o tcp://198.18.0.3:9000 [2001:db8::1]:80 198.18.0.1:"
+        );
+    }
+
+    #[test]
+    fn scrub_names() {
+        let mut scrubber = Scrubber::new(true);
+        let mut event = Event {
+            netns: Some(NetnsEvent {
+                cookie: None,
+                inum: 4026532000,
+                generation: None,
+                container: Some("default/web/nginx".to_string()),
+            }),
+            kube: Some(KubeEvent {
+                src: Some(KubeEndpoint {
+                    kind: "pod".to_string(),
+                    namespace: Some("default".to_string()),
+                    name: "web".to_string(),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        scrubber.event(&mut event);
+
+        assert_eq!(
+            event.netns.as_ref().unwrap().container.as_deref(),
+            Some("name0/name1/name2")
+        );
+        let src = event.kube.as_ref().unwrap().src.as_ref().unwrap();
+        assert_eq!(src.namespace.as_deref(), Some("name0"));
+        assert_eq!(src.name, "name1");
+
+        assert_eq!(scrubber.id("3f2ab7c9"), "00000001");
+        assert_eq!(scrubber.id("9c7ba2f3"), "00000002");
+        assert_eq!(scrubber.id("3f2ab7c9"), "00000001");
+    }
+
+    #[test]
+    fn scrub_xfrm() {
+        let mut scrubber = Scrubber::new(true);
+        let mut event = Event {
+            xfrm: Some(XfrmEvent {
+                op: "input".to_string(),
+                sa: Some(XfrmSa {
+                    mode: "tunnel".to_string(),
+                    src: "10.0.0.1".to_string(),
+                    dst: "2001:abcd::1".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        scrubber.event(&mut event);
+
+        let sa = event.xfrm.as_ref().unwrap().sa.as_ref().unwrap();
+        assert_eq!(sa.src, "198.18.0.1");
+        assert_eq!(sa.dst, "2001:db8::1");
+    }
+
+    #[test]
+    fn scrub_tunnel() {
+        let mut scrubber = Scrubber::new(true);
+        let mut event = Event {
+            tunnel: Some(TunnelEvent {
+                id: 42,
+                src: "10.0.0.1".to_string(),
+                dst: "10.0.0.2".to_string(),
+                dport: Some(4789),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        scrubber.event(&mut event);
+
+        let tunnel = event.tunnel.as_ref().unwrap();
+        assert_eq!(tunnel.src, "198.18.0.1");
+        assert_eq!(tunnel.dst, "198.18.0.2");
+    }
+
+    #[test]
+    fn scrub_tcp() {
+        let mut scrubber = Scrubber::new(true);
+        let mut event = Event {
+            tcp: Some(TcpEvent {
+                op: "retransmit".to_string(),
+                saddr: "10.0.0.1".to_string(),
+                daddr: "10.0.0.2".to_string(),
+                sport: 1234,
+                dport: 80,
+                state: "ESTABLISHED".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        scrubber.event(&mut event);
+
+        let tcp = event.tcp.as_ref().unwrap();
+        assert_eq!(tcp.saddr, "198.18.0.1");
+        assert_eq!(tcp.daddr, "198.18.0.2");
+    }
+
+    #[test]
+    fn scrub_bridge() {
+        let mut scrubber = Scrubber::new(true);
+        let mut event = Event {
+            bridge: Some(BridgeEvent {
+                op: "learn".to_string(),
+                bridge: Some("br0".to_string()),
+                port: Some(BridgePort {
+                    name: "eth0".to_string(),
+                    no: 1,
+                    state: "forwarding".to_string(),
+                }),
+                mac: Some("aa:bb:cc:dd:ee:fe".to_string()),
+                vid: Some(10),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        scrubber.event(&mut event);
+
+        let bridge = event.bridge.as_ref().unwrap();
+        assert_eq!(bridge.bridge.as_deref(), Some("dev0"));
+        assert_eq!(bridge.port.as_ref().unwrap().name, "dev1");
+        assert_eq!(bridge.mac.as_deref(), Some("02:00:00:00:00:01"));
+    }
+
+    #[test]
+    fn scrub_lag() {
+        let mut scrubber = Scrubber::new(true);
+        let mut event = Event {
+            lag: Some(LagEvent {
+                op: "failover".to_string(),
+                kind: "bond".to_string(),
+                master: "bond0".to_string(),
+                mode: Some("active-backup".to_string()),
+                port: Some("eth1".to_string()),
+                old_port: Some("eth0".to_string()),
+            }),
+            ..Default::default()
+        };
+        scrubber.event(&mut event);
+
+        let lag = event.lag.as_ref().unwrap();
+        assert_eq!(lag.master, "dev0");
+        assert_eq!(lag.port.as_deref(), Some("dev1"));
+        assert_eq!(lag.old_port.as_deref(), Some("dev2"));
+        assert_eq!(lag.mode.as_deref(), Some("active-backup"));
+    }
+
+    #[test]
+    fn scrub_wireguard() {
+        let mut scrubber = Scrubber::new(true);
+        let mut event = Event {
+            wireguard: Some(WireguardEvent {
+                op: "send".to_string(),
+                peer: Some(WireguardPeer {
+                    id: 1,
+                    key: "xTIBA5rb".to_string(),
+                    endpoint: Some("[2001:abcd::1]:51820".to_string()),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        scrubber.event(&mut event);
+
+        let peer = event.wireguard.as_ref().unwrap().peer.as_ref().unwrap();
+        assert_eq!(peer.endpoint.as_deref(), Some("[2001:db8::1]:51820"));
+    }
+
+    #[test]
+    fn scrub_ovs_set() {
+        let mut scrubber = Scrubber::new(true);
+        let mut event = Event {
+            ovs: Some(OvsEvent::Action {
+                action_execute: ActionEvent {
+                    action: Some(OvsAction::Set {
+                        set: OvsActionSet {
+                            tunnel: Some(OvsActionSetTunnel {
+                                id: 42,
+                                src: "172.16.0.1".to_string(),
+                                dst: "172.16.0.2".to_string(),
+                                dport: Some(4789),
+                                ttl: 64,
+                                ..Default::default()
+                            }),
+                        },
+                    }),
+                    ..Default::default()
+                },
+            }),
+            ..Default::default()
+        };
+        scrubber.event(&mut event);
+
+        let Some(OvsEvent::Action { action_execute }) = event.ovs.as_ref() else {
+            panic!("not an action event");
+        };
+        let Some(OvsAction::Set { set }) = action_execute.action.as_ref() else {
+            panic!("not a set action");
+        };
+        let tunnel = set.tunnel.as_ref().unwrap();
+        assert_eq!(tunnel.src, "198.18.0.1");
+        assert_eq!(tunnel.dst, "198.18.0.2");
+    }
+
+    #[test]
+    fn scrub_events() {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        let mut scrubber = Scrubber::new(true);
+
+        let mut events = Vec::new();
+        while let Some(mut event) = factory.next_event().unwrap() {
+            scrubber.event(&mut event);
+            events.push(event);
+        }
+
+        let event = &events[1];
+        let packet = event.packet.as_ref().unwrap();
+        assert_eq!(packet.len, 98);
+        assert_eq!(packet.capture_len, 42);
+        assert_eq!(packet.data.0.len(), 42);
+        // Ethernet addresses.
+        assert_eq!(&packet.data.0[..12], &[2, 0, 0, 0, 0, 1, 2, 0, 0, 0, 0, 2]);
+        // Valid IPv4 header checksum.
+        assert_eq!(ipv4_checksum(&packet.data.0[14..34]), 0);
+
+        let flow = packet.data.flow().unwrap();
+        assert_eq!(flow.src.to_string(), "198.18.0.1");
+        assert_eq!(flow.dst.to_string(), "198.18.0.2");
+        assert_eq!(event.dev.as_ref().unwrap().name, "dev0");
+
+        // Conntrack tuples use the same pseudonyms.
+        let ct = &event.ct.as_ref().unwrap().base;
+        assert_eq!(ct.orig.ip.src, "198.18.0.1");
+        assert_eq!(ct.orig.ip.dst, "198.18.0.2");
+        assert_eq!(ct.reply.ip.src, "198.18.0.2");
+
+        // Names are consistent across events.
+        let names: Vec<_> = events
+            .iter()
+            .filter_map(|e| e.dev.as_ref().map(|d| d.name.as_str()))
+            .collect();
+        assert!(names
+            .iter()
+            .all(|n| ["dev0", "dev1", "dev2", "dev3"].contains(n)));
+        assert!(!serde_json::to_string(&events)
+            .unwrap()
+            .contains("192.168.125."));
+    }
+}
@@ -1,6 +1,7 @@
 //! # Sort
 //!
-//! Sort rearranges the events so they are grouped by skb tracking id (or OVS queue_id if present)
+//! Sort rearranges the events so they are grouped by skb tracking id (or OVS queue_id if present),
+//! or following other sort keys.
 
 use std::{
     fs::OpenOptions,
@@ -14,8 +15,21 @@ use clap::Parser;
 use crate::{
     cli::*,
     events::{file::*, *},
-    helpers::{file_rotate::InputDataFile, signals::Running},
-    process::{display::*, series::EventSorter, tracking::AddTracking},
+    helpers::{
+        file_rotate::{inputs_to_factory, InputDataFile},
+        signals::Running,
+    },
+    process::{
+        containers::ContainerMapper,
+        display::*,
+        graph::{GraphFormat, PathGraph},
+        kube::KubeMapper,
+        netns::NetnsTracker,
+        selection::EventSelection,
+        series::{EventSorter, SortKey},
+        symbolize::UserSymbolizer,
+        tracking::AddTracking,
+    },
 };
 
 /// The default size of the sorting buffer
@@ -27,11 +41,31 @@ const DEFAULT_BUFFER: usize = 1000;
     about = "Sort stored events in series based on tracking id.",
     long_about = "Sort stored events in series based on tracking id.
 
-Reads events and arranges them by tracking id. The output is a number of \"event sets\". An event set is a list of events that share the same tracking id (i.e: belong to the same packet)."
+Reads events and arranges them by tracking id. The output is a number of \"event sets\". An event set is a list of events that share the same tracking id (i.e: belong to the same packet).
+
+Events can be arranged following other keys using --by: events sharing the same tracking id, flow and/or probe (depending on the keys used) are grouped in a set, and sets are ordered following the keys in the order they are given. Ties are broken using the timestamp of the first event of sets, then the order events were read, for the output to be deterministic. Events of a set are kept in the order they were read."
 )]
 pub(crate) struct Sort {
     #[arg(help = InputDataFile::help())]
-    pub(super) input: Option<InputDataFile>,
+    pub(super) input: Vec<InputDataFile>,
+
+    #[arg(
+        long,
+        value_name = "NS",
+        allow_negative_numbers = true,
+        help = "Time delta, in nanoseconds, added to the events of an input when reading multiple inputs (e.g. files collected on different hosts), whose events are merged by timestamp. Timestamps are converted to the clock of the first input, the delta compensating for remaining clock differences between hosts. Can be used multiple times, each delta applying to the input at the same position."
+    )]
+    pub(super) delta: Vec<i64>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "KEYS",
+        default_value = "tracking-id",
+        help = "Keys (comma separated) used to group events in sets and to order the sets. All keys but 'time' group events, e.g. '--by flow,time' groups events by flow and orders the sets by flow while '--by time,flow' orders them by the time of their first event. Using only 'time' orders events by timestamp, each in its own set. Ordering is only guaranteed within the buffer (see --max-buffer)"
+    )]
+    pub(super) by: Vec<SortKey>,
 
     #[arg(
         long,
@@ -65,27 +99,97 @@ A value of zero means the buffer can grow endlessly."
     #[arg(long, help = "Print the time as UTC")]
     pub(super) utc: bool,
 
+    #[arg(
+        long,
+        help = "Print the journey of each packet on a single line instead of its events: the probes it went through, the interfaces it was seen on and the verdicts it got. This makes it easy to spot where packets diverge in the stack"
+    )]
+    pub(super) journeys: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        conflicts_with_all = ["journeys", "print"],
+        help = "Print the paths of packets across probes, aggregated as a graph, instead of the events. Nodes are probes (with their number of events) and edges are weighted by the number of packets going from a probe to the next one"
+    )]
+    pub(super) graph: Option<GraphFormat>,
+
     #[arg(short = 'e', help = "Print link-layer information from the packet")]
     pub(super) print_ll: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "out",
+        help = "Only print the collection information (Retis version, machine, probes, filters, etc) and exit"
+    )]
+    pub(super) header: bool,
+
+    #[command(flatten)]
+    pub(super) selection: EventSelection,
 }
 
 impl SubCommandParserRunner for Sort {
-    fn run(&mut self, _: &MainConfig) -> Result<()> {
+    fn run(&mut self, main_config: &MainConfig) -> Result<()> {
         // Create running instance that will handle signal termination.
         let run = Running::new()?;
 
         // Create event factory.
-        let input = self.input.clone().unwrap_or_default();
-        let mut factory = input.to_factory()?;
+        let mut factory = inputs_to_factory(&self.input, &self.delta)?;
+
+        if self.header {
+            return print_header(
+                factory.startup(),
+                DisplayFormat::new()
+                    .multiline(self.format == CliDisplayFormat::MultiLine)
+                    .time_format(if self.utc {
+                        TimeFormat::UtcDate
+                    } else {
+                        TimeFormat::MonotonicTimestamp
+                    })
+                    .colors(main_config.colors),
+            );
+        }
 
         if matches!(factory.file_type(), FileType::Series) {
+            // Sorted files can still be rendered as a graph.
+            if let Some(format) = self.graph {
+                let mut graph = PathGraph::new();
+                while run.running() {
+                    match factory.next_series()? {
+                        Some(series) => graph.add(&series),
+                        None => break,
+                    }
+                }
+                print!("{}", graph.render(format)?);
+                return Ok(());
+            }
+
             log::info!("File already sorted");
             return Ok(());
         }
 
-        let mut series = EventSorter::new();
+        // Only read the part of the input containing the selected events, if
+        // possible (single input with an index).
+        let mut selector = self.selection.selector(factory.startup())?;
+        let input = match self.input.as_slice() {
+            [] => Some(InputDataFile::default()),
+            [input] if self.delta.is_empty() => Some(input.clone()),
+            _ => None,
+        };
+        if let Some(input) = input {
+            if let Some((start, end)) = selector.input_range(&input)? {
+                factory = input.to_factory_range(start, end)?;
+            }
+        }
+
+        let mut series = EventSorter::with_keys(&self.by);
         let mut tracker = AddTracking::new();
+        let mut symbolizer = UserSymbolizer::new();
+        let mut netns = NetnsTracker::new();
+        let containers = ContainerMapper::new(factory.startup());
+        let kube = KubeMapper::new(factory.startup());
         let mut printers = Vec::new();
+        let mut graph = self.graph.map(|_| PathGraph::new());
 
         if let Some(out) = &self.out {
             if factory.is_compat() {
@@ -98,17 +202,22 @@ impl SubCommandParserRunner for Sort {
             //
             // Due to the default input file logic and the range format, we
             // only due this check best-effort.
-            if let Ok(input) = &input.path.canonicalize() {
-                let out = match out.canonicalize() {
-                    Ok(out) => out,
-                    // If the file doesn't exist we can't use fs::canonicalize() but it is not needed
-                    // as that means it is not the input file.
-                    Err(_) => out.to_path_buf(),
-                };
-
-                if out.eq(input) {
-                    bail!("Cannot sort a file in-place. Please specify an output file that's different to the input one.");
-                }
+            let out = match out.canonicalize() {
+                Ok(out) => out,
+                // If the file doesn't exist we can't use fs::canonicalize() but it is not needed
+                // as that means it is not the input file.
+                Err(_) => out.to_path_buf(),
+            };
+            let inputs = match self.input.is_empty() {
+                true => vec![InputDataFile::default()],
+                false => self.input.clone(),
+            };
+            if inputs
+                .iter()
+                .filter_map(|input| input.path.canonicalize().ok())
+                .any(|input| out.eq(&input))
+            {
+                bail!("Cannot sort a file in-place. Please specify an output file that's different to the input one.");
             }
 
             printers.push(PrintSeries::new(
@@ -124,7 +233,7 @@ impl SubCommandParserRunner for Sort {
             ));
         }
 
-        if self.out.is_none() || self.print {
+        if (self.out.is_none() || self.print) && graph.is_none() {
             let format = DisplayFormat::new()
                 .multiline(self.format == CliDisplayFormat::MultiLine)
                 .time_format(if self.utc {
@@ -132,17 +241,33 @@ impl SubCommandParserRunner for Sort {
                 } else {
                     TimeFormat::MonotonicTimestamp
                 })
-                .print_ll(self.print_ll);
+                .print_ll(self.print_ll)
+                .colors(main_config.colors);
 
             printers.push(PrintSeries::new(
                 Box::new(stdout()),
-                PrintEventFormat::Text(format),
+                match self.journeys {
+                    true => PrintEventFormat::Journey(format),
+                    false => PrintEventFormat::Text(format),
+                },
             ));
         }
 
         while run.running() {
-            match factory.next_event()? {
+            match selector.next_event(&mut factory)? {
                 Some(mut event) => {
+                    // Resolve user stack symbols
+                    symbolizer.process_one(&mut event);
+
+                    // Identify reused netns inums
+                    netns.process_one(&mut event);
+
+                    // Map netns and cgroups to containers
+                    containers.process_one(&mut event);
+
+                    // Map addresses and interfaces to Kubernetes objects
+                    kube.process_one(&mut event);
+
                     // Add tracking information
                     tracker.process_one(&mut event)?;
 
@@ -155,6 +280,9 @@ impl SubCommandParserRunner for Sort {
                             // Flush the oldest series
                             match series.pop_oldest()? {
                                 Some(series) => {
+                                    if let Some(graph) = graph.as_mut() {
+                                        graph.add(&series);
+                                    }
                                     for p in printers.iter_mut() {
                                         if let Err(e) = p.process_one(&series) {
                                             match e.downcast_ref::<io::Error>() {
@@ -180,6 +308,9 @@ impl SubCommandParserRunner for Sort {
         while series.len() > 0 {
             match series.pop_oldest()? {
                 Some(series) => {
+                    if let Some(graph) = graph.as_mut() {
+                        graph.add(&series);
+                    }
                     for p in printers.iter_mut() {
                         if let Err(e) = p.process_one(&series) {
                             match e.downcast_ref::<io::Error>() {
@@ -197,6 +328,10 @@ impl SubCommandParserRunner for Sort {
 
         // Flush writers
         printers.iter_mut().try_for_each(|p| p.flush())?;
+
+        if let (Some(graph), Some(format)) = (graph, self.graph) {
+            print!("{}", graph.render(format)?);
+        }
         Ok(())
     }
 }
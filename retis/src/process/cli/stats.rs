@@ -5,7 +5,7 @@
 use std::collections::HashMap;
 
 use anyhow::{anyhow, bail, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use log::warn;
 
 use crate::{
@@ -19,11 +19,73 @@ use crate::{
 pub(crate) struct Stats {
     #[arg(help = InputDataFile::help())]
     pub(super) input: Option<InputDataFile>,
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Aggregate the events by the given keys (comma separated) and print the number of events and bytes of each group, e.g. '--group-by probe,drop-reason'"
+    )]
+    pub(super) group_by: Vec<GroupBy>,
+}
+
+/// Key events can be aggregated by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum GroupBy {
+    /// Probe the event was generated by.
+    Probe,
+    /// Network interface.
+    Iface,
+    /// Packet 5-tuple.
+    Flow,
+    /// Drop reason.
+    DropReason,
+    /// Conntrack state.
+    CtState,
+}
+
+impl GroupBy {
+    /// Get the value of the key for a given event, if any.
+    pub(super) fn value(&self, event: &Event) -> Option<String> {
+        match self {
+            GroupBy::Probe => probe_name(event),
+            GroupBy::Iface => event.dev.as_ref().map(|dev| match dev.name.is_empty() {
+                true => dev.ifindex.to_string(),
+                false => dev.name.clone(),
+            }),
+            GroupBy::Flow => event
+                .packet
+                .as_ref()
+                .and_then(|p| p.data.flow())
+                .map(|flow| flow.to_string()),
+            GroupBy::DropReason => event.skb_drop.as_ref().map(|drop| match &drop.subsys {
+                Some(subsys) => format!("{subsys}/{}", drop.drop_reason),
+                None => drop.drop_reason.clone(),
+            }),
+            GroupBy::CtState => event.ct.as_ref().and_then(|ct| {
+                serde_json::to_value(&ct.state)
+                    .ok()
+                    .and_then(|state| state.as_str().map(String::from))
+            }),
+        }
+    }
+}
+
+/// Name of the probe an event was generated by.
+fn probe_name(event: &Event) -> Option<String> {
+    if let Some(kernel) = &event.kernel {
+        Some(format!("{}/{}", kernel.probe_type, kernel.symbol))
+    } else {
+        event
+            .userspace
+            .as_ref()
+            .map(|user| format!("{}/{}", user.probe_type, user.symbol))
+    }
 }
 
 impl SubCommandParserRunner for Stats {
     fn run(&mut self, _: &MainConfig) -> Result<()> {
         let mut stats = StatsProcessor::new();
+        let mut groups = GroupStats::new(&self.group_by);
 
         let run = Running::new()?;
 
@@ -35,20 +97,116 @@ impl SubCommandParserRunner for Stats {
                 while run.running() {
                     match factory.next_event()? {
                         None => break,
-                        Some(event) => stats.process_event(&event)?,
+                        Some(event) => {
+                            groups.process_event(&event);
+                            stats.process_event(&event)?;
+                        }
                     }
                 }
             }
             FileType::Series => {
                 while run.running() {
                     match factory.next_series()? {
-                        Some(series) => stats.process_series(&series)?,
+                        Some(series) => {
+                            series.events.iter().for_each(|e| groups.process_event(e));
+                            stats.process_series(&series)?;
+                        }
                         None => break,
                     }
                 }
             }
         }
-        stats.print()
+        stats.print()?;
+        groups.print();
+        Ok(())
+    }
+}
+
+/// Number of events and bytes of a group of events.
+#[derive(Default)]
+struct GroupCounters {
+    events: u64,
+    bytes: u64,
+}
+
+/// Aggregates events by a set of keys.
+struct GroupStats {
+    keys: Vec<GroupBy>,
+    groups: HashMap<Vec<String>, GroupCounters>,
+}
+
+impl GroupStats {
+    fn new(keys: &[GroupBy]) -> Self {
+        Self {
+            keys: keys.to_vec(),
+            groups: HashMap::new(),
+        }
+    }
+
+    fn process_event(&mut self, event: &Event) {
+        if self.keys.is_empty() || event.startup.is_some() {
+            return;
+        }
+
+        let key = self
+            .keys
+            .iter()
+            .map(|k| k.value(event).unwrap_or_else(|| "-".to_string()))
+            .collect();
+        let group = self.groups.entry(key).or_default();
+        group.events += 1;
+        group.bytes += event.packet.as_ref().map(|p| p.len as u64).unwrap_or(0);
+    }
+
+    /// Get the table of the groups, sorted by decreasing number of events,
+    /// with a header line.
+    fn table(&self) -> Vec<Vec<String>> {
+        let mut groups: Vec<_> = self.groups.iter().collect();
+        groups.sort_by(|a, b| b.1.events.cmp(&a.1.events).then_with(|| a.0.cmp(b.0)));
+
+        let mut header: Vec<_> = self
+            .keys
+            .iter()
+            .filter_map(|k| k.to_possible_value().map(|v| v.get_name().to_string()))
+            .collect();
+        header.extend(["events".to_string(), "bytes".to_string()]);
+
+        let mut table = vec![header];
+        groups.into_iter().for_each(|(key, counters)| {
+            let mut row = key.clone();
+            row.extend([counters.events.to_string(), counters.bytes.to_string()]);
+            table.push(row);
+        });
+        table
+    }
+
+    fn print(&self) {
+        if self.keys.is_empty() {
+            return;
+        }
+
+        println!();
+        print_table(&self.table());
+    }
+}
+
+/// Print a table, aligning its columns.
+pub(super) fn print_table(table: &[Vec<String>]) {
+    let Some(header) = table.first() else {
+        return;
+    };
+    let widths: Vec<_> = (0..header.len())
+        .map(|i| table.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect();
+
+    for row in table.iter() {
+        let line = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(val, width)| format!("{val:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
     }
 }
 
@@ -152,12 +310,9 @@ impl FileStats {
             bail!("Invalid event: no common section")
         };
 
-        let probe_name = if let Some(kernel) = &event.kernel {
-            format!("{}/{}", kernel.probe_type, kernel.symbol)
-        } else if let Some(user) = &event.userspace {
-            format!("{}/{}", user.probe_type, user.symbol)
-        } else {
-            bail!("Invalid event: no kernel or userspace section")
+        let probe_name = match probe_name(event) {
+            Some(name) => name,
+            None => bail!("Invalid event: no kernel or userspace section"),
         };
 
         let stat = self.probes.entry(probe_name).or_insert(0);
@@ -221,3 +376,37 @@ impl FileStats {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::file::FileEventsFactory;
+
+    #[test]
+    fn group_stats() {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        let mut groups = GroupStats::new(&[GroupBy::Probe, GroupBy::CtState]);
+        let mut events = 0;
+        while let Some(event) = factory.next_event().unwrap() {
+            if event.startup.is_none() {
+                events += 1;
+            }
+            groups.process_event(&event);
+        }
+
+        let table = groups.table();
+        assert_eq!(table[0], vec!["probe", "ct-state", "events", "bytes"]);
+        assert_eq!(
+            table[1..]
+                .iter()
+                .map(|row| row[2].parse::<u64>().unwrap())
+                .sum::<u64>(),
+            events
+        );
+        // Sorted by decreasing number of events.
+        assert!(table[1..]
+            .windows(2)
+            .all(|w| w[0][2].parse::<u64>().unwrap() >= w[1][2].parse::<u64>().unwrap()));
+    }
+}
@@ -0,0 +1,900 @@
+//! # Watch
+//!
+//! Watch is an interactive terminal viewer of events, either streamed live by
+//! a running collection or stored in a file.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    fs,
+    io::{self, stdin, stdout, BufRead, BufReader, IsTerminal, Stdout, Write},
+    mem::MaybeUninit,
+    net::TcpListener,
+    os::unix::net::UnixListener,
+    path::PathBuf,
+    str::FromStr,
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use regex::{Regex, RegexBuilder};
+
+use super::stats::GroupBy;
+use crate::{
+    cli::*,
+    events::{file::FileType, *},
+    helpers::{file_rotate::InputDataFile, signals::Running, stream::StreamTarget},
+    process::tracking::AddTracking,
+};
+
+/// Interval at which the viewer is refreshed, and probe rates computed.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+/// Interval over which the per-probe rates are computed.
+const RATE_INTERVAL: Duration = Duration::from_secs(1);
+/// Interval at which a followed file is checked for new events.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(200);
+/// Maximum number of events handled between two refreshes of the viewer.
+const MAX_BATCH: usize = 10000;
+/// Width of the probe rate sidebar, only displayed if the terminal is at
+/// least twice as wide.
+const SIDEBAR_WIDTH: usize = 40;
+
+#[derive(Parser, Debug, Default)]
+#[command(
+    name = "watch",
+    about = "Interactively watch events in the terminal.",
+    long_about = "Interactively watch events in the terminal.
+
+Events are displayed in a terminal user interface, either live or from a file, with a scrollback, incremental filtering and per-probe event rates. To watch events live, give a stream endpoint as the input and make the collection stream its events to it, e.g. 'retis watch tcp://127.0.0.1:9000' and 'retis collect -o tcp://127.0.0.1:9000'.
+
+Keys: 'q' quits, space pauses and resumes the display, '/' edits the filter (a regular expression matched against displayed events, case insensitive unless it contains uppercase characters; Enter validates it and Escape clears it), arrows, page up/down, 'g' and 'G' scroll through events."
+)]
+pub(crate) struct Watch {
+    #[arg(
+        help = "Event file to read events from (see 'retis print --help'), or stream endpoint to listen on for events streamed by 'retis collect -o', as tcp://HOST:PORT or unix:///PATH [default: 'retis.data']"
+    )]
+    pub(super) input: Option<String>,
+    #[arg(
+        short,
+        long,
+        help = "Keep reading events appended to the input file, e.g. while it is being written by 'retis collect -o'"
+    )]
+    pub(super) follow: bool,
+    #[arg(
+        long,
+        default_value_t = 10000,
+        help = "Maximum number of events kept in the scrollback"
+    )]
+    pub(super) scrollback: usize,
+    #[arg(long, help = "Print the time as UTC")]
+    pub(super) utc: bool,
+    #[arg(short = 'e', help = "Print link-layer information from the packet")]
+    pub(super) print_ll: bool,
+}
+
+impl SubCommandParserRunner for Watch {
+    fn run(&mut self, _: &MainConfig) -> Result<()> {
+        let run = Running::new()?;
+
+        // Set up the input before the terminal, for errors to be reported
+        // normally.
+        let input = self.input.as_deref().unwrap_or("retis.data");
+        let source = match StreamTarget::from_url(input)? {
+            Some(StreamTarget::Tcp(addr)) => Source::Tcp(
+                TcpListener::bind(&addr)
+                    .or_else(|e| bail!("Could not listen on tcp://{addr}: {e}"))?,
+            ),
+            Some(StreamTarget::Unix(path)) => Source::Unix(
+                UnixListener::bind(&path)
+                    .or_else(|e| bail!("Could not listen on unix://{}: {e}", path.display()))?,
+                path,
+            ),
+            None => match self.input {
+                Some(_) => Source::File(InputDataFile::from_str(input).map_err(|e| anyhow!(e))?),
+                None => Source::File(InputDataFile::default()),
+            },
+        };
+        let cleanup = match &source {
+            Source::Unix(_, path) => Some(path.clone()),
+            _ => None,
+        };
+
+        let format = DisplayFormat::new()
+            .multiline(false)
+            .time_format(if self.utc {
+                TimeFormat::UtcDate
+            } else {
+                TimeFormat::MonotonicTimestamp
+            })
+            .print_ll(self.print_ll);
+
+        let mut term = Terminal::new()?;
+
+        let (tx, rx) = mpsc::sync_channel(MAX_BATCH);
+        let follow = self.follow;
+        let reader_run = run.clone();
+        thread::spawn(move || {
+            let mut reader = EventReader::new(format, tx);
+            if let Err(e) = reader.read(source, follow, &reader_run) {
+                reader.info(format!("Error: {e}"));
+            }
+        });
+
+        let res = watch(&mut term, &rx, &run, self.scrollback);
+
+        drop(term);
+        if let Some(path) = cleanup {
+            let _ = fs::remove_file(path);
+        }
+        res
+    }
+}
+
+/// Main loop of the viewer.
+fn watch(
+    term: &mut Terminal,
+    rx: &Receiver<Message>,
+    run: &Running,
+    scrollback: usize,
+) -> Result<()> {
+    let mut state = WatchState::new(scrollback, Instant::now());
+    let mut dirty = true;
+
+    while run.running() {
+        let mut batch = 0;
+        while batch < MAX_BATCH {
+            match rx.try_recv() {
+                Ok(Message::Event(entry)) => state.push(entry),
+                Ok(Message::Info(info)) => state.info = info,
+                Err(_) => break,
+            }
+            batch += 1;
+            dirty = true;
+        }
+
+        if state.rates.tick(Instant::now()) {
+            dirty = true;
+        }
+
+        if dirty {
+            let (width, height) = term.size();
+            term.draw(&state.render(width, height))?;
+            dirty = false;
+        }
+
+        // Do not wait for keys if events are pending.
+        let timeout = match batch {
+            MAX_BATCH => Duration::ZERO,
+            _ => REFRESH_INTERVAL,
+        };
+        for key in term.read_keys(timeout)? {
+            if !state.handle_key(key) {
+                return Ok(());
+            }
+            dirty = true;
+        }
+    }
+
+    Ok(())
+}
+
+/// Input of the viewer.
+enum Source {
+    File(InputDataFile),
+    Tcp(TcpListener),
+    Unix(UnixListener, PathBuf),
+}
+
+/// Messages sent by the event reader to the viewer.
+enum Message {
+    Event(WatchEntry),
+    Info(String),
+}
+
+/// Reads events from the input and sends them to the viewer.
+struct EventReader {
+    format: DisplayFormat,
+    tracker: AddTracking,
+    tx: SyncSender<Message>,
+}
+
+impl EventReader {
+    fn new(format: DisplayFormat, tx: SyncSender<Message>) -> Self {
+        Self {
+            format,
+            tracker: AddTracking::new(),
+            tx,
+        }
+    }
+
+    fn info(&self, info: String) {
+        let _ = self.tx.send(Message::Info(info));
+    }
+
+    /// Read all events from the input, until the end of the file (when not
+    /// following it) or until the viewer exits.
+    fn read(&mut self, source: Source, follow: bool, run: &Running) -> Result<()> {
+        match source {
+            Source::File(input) => {
+                let mut factory = input.to_factory()?;
+                if follow {
+                    factory.follow()?;
+                }
+                self.info(format!("Reading {}", input.path.display()));
+
+                while run.running() {
+                    let events = match factory.file_type() {
+                        FileType::Event => factory.next_event()?.map(|e| vec![e]),
+                        FileType::Series => factory.next_series()?.map(|s| s.events),
+                    };
+
+                    match events {
+                        Some(events) => {
+                            for event in events {
+                                if !self.send(event)? {
+                                    return Ok(());
+                                }
+                            }
+                        }
+                        None if follow => thread::sleep(FOLLOW_INTERVAL),
+                        None => break,
+                    }
+                }
+                self.info(format!("End of {}", input.path.display()));
+            }
+            Source::Tcp(listener) => {
+                self.info(format!("Listening on tcp://{}", listener.local_addr()?));
+                for conn in listener.incoming() {
+                    let conn = conn?;
+                    self.info(format!("Receiving events from {}", conn.peer_addr()?));
+                    if !self.read_stream(BufReader::new(conn), run)? {
+                        return Ok(());
+                    }
+                }
+            }
+            Source::Unix(listener, path) => {
+                self.info(format!("Listening on unix://{}", path.display()));
+                for conn in listener.incoming() {
+                    let conn = conn?;
+                    self.info("Receiving events".to_string());
+                    if !self.read_stream(BufReader::new(conn), run)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read events from a collection stream, one JSON event per line. Returns
+    /// false if the viewer exited.
+    fn read_stream<R: BufRead>(&mut self, conn: R, run: &Running) -> Result<bool> {
+        for line in conn.lines() {
+            if !run.running() {
+                return Ok(false);
+            }
+
+            // Streams are sent by a collection of the same version.
+            let event: Event = serde_json::from_str(&line?)?;
+            if !self.send(event)? {
+                return Ok(false);
+            }
+        }
+
+        self.info("Collection disconnected, waiting for a new one".to_string());
+        Ok(true)
+    }
+
+    /// Send an event to the viewer. Returns false if the viewer exited.
+    fn send(&mut self, mut event: Event) -> Result<bool> {
+        if let Some(startup) = &event.startup {
+            self.format = self.format.monotonic_offset(startup.clock_monotonic_offset);
+            return Ok(true);
+        }
+
+        // Series already contain the tracking information.
+        if event.tracking.is_none() {
+            self.tracker.process_one(&mut event)?;
+        }
+
+        Ok(self
+            .tx
+            .send(Message::Event(WatchEntry::new(&event, &self.format)))
+            .is_ok())
+    }
+}
+
+/// Event, as displayed in the viewer.
+struct WatchEntry {
+    probe: Option<String>,
+    text: String,
+}
+
+impl WatchEntry {
+    fn new(event: &Event, format: &DisplayFormat) -> Self {
+        Self {
+            probe: GroupBy::Probe.value(event),
+            text: format!("{}", event.display(format, &FormatterConf::new())),
+        }
+    }
+}
+
+/// Per-probe event counters.
+#[derive(Default)]
+struct ProbeStats {
+    total: u64,
+    /// Number of events in the current interval.
+    current: u64,
+    /// Events per second, over the last full interval.
+    rate: f64,
+}
+
+/// Per-probe event rates.
+struct ProbeRates {
+    probes: HashMap<String, ProbeStats>,
+    /// Start of the current interval.
+    start: Instant,
+}
+
+impl ProbeRates {
+    fn new(now: Instant) -> Self {
+        Self {
+            probes: HashMap::new(),
+            start: now,
+        }
+    }
+
+    fn add(&mut self, probe: &str) {
+        let stats = self.probes.entry(probe.to_string()).or_default();
+        stats.total += 1;
+        stats.current += 1;
+    }
+
+    /// Compute the rates if the current interval is over. Returns true if
+    /// they were.
+    fn tick(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed < RATE_INTERVAL {
+            return false;
+        }
+
+        self.probes.values_mut().for_each(|stats| {
+            stats.rate = stats.current as f64 / elapsed.as_secs_f64();
+            stats.current = 0;
+        });
+        self.start = now;
+        true
+    }
+
+    /// Probes sorted by decreasing rate, then decreasing total.
+    fn sorted(&self) -> Vec<(&String, &ProbeStats)> {
+        let mut probes: Vec<_> = self.probes.iter().collect();
+        probes.sort_by(|(a_name, a), (b_name, b)| {
+            b.rate
+                .total_cmp(&a.rate)
+                .then_with(|| b.total.cmp(&a.total))
+                .then_with(|| a_name.cmp(b_name))
+        });
+        probes
+    }
+}
+
+/// Key pressed by the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Esc,
+    CtrlC,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+}
+
+/// Parse the keys from raw terminal input. Unknown escape sequences are
+/// ignored.
+fn parse_keys(input: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+
+    while i < input.len() {
+        match input[i] {
+            0x03 => keys.push(Key::CtrlC),
+            b'\r' | b'\n' => keys.push(Key::Enter),
+            0x08 | 0x7f => keys.push(Key::Backspace),
+            0x1b => match input.get(i + 1) {
+                Some(b'[') | Some(b'O') => {
+                    // Control sequences end with a byte in the 0x40-0x7e
+                    // range, possibly after numeric parameters.
+                    let Some(end) = input[i + 2..]
+                        .iter()
+                        .position(|b| (0x40..=0x7e).contains(b))
+                        .map(|pos| i + 2 + pos)
+                    else {
+                        break;
+                    };
+
+                    let key = match (&input[i + 2..end], input[end]) {
+                        (b"", b'A') => Some(Key::Up),
+                        (b"", b'B') => Some(Key::Down),
+                        (b"", b'H') | (b"1" | b"7", b'~') => Some(Key::Home),
+                        (b"", b'F') | (b"4" | b"8", b'~') => Some(Key::End),
+                        (b"5", b'~') => Some(Key::PageUp),
+                        (b"6", b'~') => Some(Key::PageDown),
+                        _ => None,
+                    };
+                    keys.extend(key);
+                    i = end;
+                }
+                _ => keys.push(Key::Esc),
+            },
+            b if b < 0x20 => (),
+            b => {
+                let len = match b {
+                    0xf0..=0xff => 4,
+                    0xe0..=0xef => 3,
+                    0xc0..=0xdf => 2,
+                    _ => 1,
+                };
+                if let Some(c) = input
+                    .get(i..i + len)
+                    .and_then(|c| std::str::from_utf8(c).ok())
+                    .and_then(|c| c.chars().next())
+                {
+                    keys.push(Key::Char(c));
+                }
+                i += len - 1;
+            }
+        }
+        i += 1;
+    }
+    keys
+}
+
+/// State of the viewer.
+struct WatchState {
+    /// Scrollback.
+    entries: VecDeque<WatchEntry>,
+    /// Events received while paused.
+    pending: VecDeque<WatchEntry>,
+    scrollback: usize,
+    paused: bool,
+    /// Number of matching events hidden below the view.
+    offset: usize,
+    /// Filter, as typed by the user.
+    filter: String,
+    /// Compiled filter, None if empty or invalid.
+    regex: Option<Regex>,
+    /// Is the filter being edited?
+    editing: bool,
+    rates: ProbeRates,
+    /// Information about the input.
+    info: String,
+    /// Number of event rows, as of the last rendering.
+    rows: usize,
+}
+
+impl WatchState {
+    fn new(scrollback: usize, now: Instant) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            pending: VecDeque::new(),
+            scrollback: scrollback.max(1),
+            paused: false,
+            offset: 0,
+            filter: String::new(),
+            regex: None,
+            editing: false,
+            rates: ProbeRates::new(now),
+            info: String::new(),
+            rows: 1,
+        }
+    }
+
+    fn push(&mut self, entry: WatchEntry) {
+        if let Some(probe) = &entry.probe {
+            self.rates.add(probe);
+        }
+
+        let entries = match self.paused {
+            true => &mut self.pending,
+            false => &mut self.entries,
+        };
+        entries.push_back(entry);
+        if entries.len() > self.scrollback {
+            entries.pop_front();
+        }
+    }
+
+    fn matches(&self, entry: &WatchEntry) -> bool {
+        match &self.regex {
+            Some(regex) => regex.is_match(&entry.text),
+            None => true,
+        }
+    }
+
+    fn set_filter(&mut self, filter: String) {
+        // Smart case: the filter is case insensitive unless it contains
+        // uppercase characters.
+        self.regex = match filter.is_empty() {
+            true => None,
+            false => RegexBuilder::new(&filter)
+                .case_insensitive(!filter.chars().any(char::is_uppercase))
+                .build()
+                .ok(),
+        };
+        self.filter = filter;
+        self.offset = 0;
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            self.entries.extend(self.pending.drain(..));
+            let excess = self.entries.len().saturating_sub(self.scrollback);
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// Scroll up (positive) or down (negative). Scrolling up pauses the
+    /// display, for the view not to move.
+    fn scroll(&mut self, lines: isize) {
+        if lines > 0 {
+            self.set_paused(true);
+        }
+        self.offset = self.offset.saturating_add_signed(lines);
+    }
+
+    /// Handle a key. Returns false if the viewer should exit.
+    fn handle_key(&mut self, key: Key) -> bool {
+        let page = self.rows.max(1) as isize;
+
+        if self.editing {
+            match key {
+                Key::CtrlC => return false,
+                Key::Char(c) => {
+                    let mut filter = self.filter.clone();
+                    filter.push(c);
+                    self.set_filter(filter);
+                }
+                Key::Backspace => {
+                    let mut filter = self.filter.clone();
+                    filter.pop();
+                    self.set_filter(filter);
+                }
+                Key::Enter => self.editing = false,
+                Key::Esc => {
+                    self.set_filter(String::new());
+                    self.editing = false;
+                }
+                _ => (),
+            }
+            return true;
+        }
+
+        match key {
+            Key::Char('q') | Key::CtrlC => return false,
+            Key::Char(' ') => self.set_paused(!self.paused),
+            Key::Char('/') => self.editing = true,
+            Key::Esc => self.set_filter(String::new()),
+            Key::Up | Key::Char('k') => self.scroll(1),
+            Key::Down | Key::Char('j') => self.scroll(-1),
+            Key::PageUp => self.scroll(page),
+            Key::PageDown => self.scroll(-page),
+            Key::Home | Key::Char('g') => self.scroll(isize::MAX),
+            Key::End | Key::Char('G') => {
+                self.offset = 0;
+                self.set_paused(false);
+            }
+            _ => (),
+        }
+        true
+    }
+
+    /// Render the viewer, as a list of terminal lines.
+    fn render(&mut self, width: usize, height: usize) -> Vec<String> {
+        let sidebar = match width >= 2 * SIDEBAR_WIDTH {
+            true => SIDEBAR_WIDTH,
+            false => 0,
+        };
+        let main = width - sidebar;
+        self.rows = height.saturating_sub(1);
+
+        // Matching events to display, from the bottom.
+        let matching: Vec<_> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|e| self.matches(e))
+            .collect();
+        self.offset = self.offset.min(matching.len().saturating_sub(self.rows));
+        let mut events: Vec<_> = matching
+            .iter()
+            .skip(self.offset)
+            .take(self.rows)
+            .map(|e| e.text.as_str())
+            .collect();
+        events.reverse();
+
+        let mut rates = vec![truncate(" Probe rates (events/s)", sidebar)];
+        rates.extend(self.rates.sorted().iter().map(|(name, stats)| {
+            truncate(
+                &format!(" {:>9.1} {:>9} {name}", stats.rate, stats.total),
+                sidebar,
+            )
+        }));
+
+        let mut lines: Vec<_> = (0..self.rows)
+            .map(|row| {
+                let mut line = truncate(events.get(row).copied().unwrap_or_default(), main);
+                if sidebar > 0 {
+                    line.push('│');
+                    line.push_str(rates.get(row).map(String::as_str).unwrap_or_default());
+                }
+                line
+            })
+            .collect();
+
+        // Status bar.
+        let mut status = format!(" {} events", self.entries.len());
+        if !self.filter.is_empty() || self.editing {
+            let _ = write!(status, " | filter: /{}", self.filter);
+            if self.regex.is_none() && !self.filter.is_empty() {
+                status.push_str(" (invalid)");
+            }
+        }
+        if self.paused {
+            let _ = write!(status, " | PAUSED ({} new)", self.pending.len());
+        }
+        if !self.info.is_empty() {
+            let _ = write!(status, " | {}", self.info);
+        }
+        status.push_str(match self.editing {
+            true => " | enter:validate esc:clear",
+            false => " | q:quit space:pause /:filter",
+        });
+        lines.push(format!("\x1b[7m{}\x1b[0m", truncate(&status, width)));
+
+        lines
+    }
+}
+
+/// Truncate or pad a line to a given width.
+fn truncate(line: &str, width: usize) -> String {
+    let mut line: String = line.chars().take(width).collect();
+    let len = line.chars().count();
+    line.push_str(&" ".repeat(width - len));
+    line
+}
+
+/// Terminal in raw mode, using the alternate screen. The terminal is restored
+/// when dropped.
+struct Terminal {
+    orig: libc::termios,
+    out: Stdout,
+}
+
+impl Terminal {
+    fn new() -> Result<Self> {
+        if !stdin().is_terminal() || !stdout().is_terminal() {
+            bail!("Events can only be watched from a terminal");
+        }
+
+        let mut orig = MaybeUninit::<libc::termios>::uninit();
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, orig.as_mut_ptr()) } < 0 {
+            bail!(
+                "Could not get the terminal attributes: {}",
+                io::Error::last_os_error()
+            );
+        }
+        let orig = unsafe { orig.assume_init() };
+
+        let mut raw = orig;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } < 0 {
+            bail!(
+                "Could not set the terminal in raw mode: {}",
+                io::Error::last_os_error()
+            );
+        }
+
+        let mut term = Self {
+            orig,
+            out: stdout(),
+        };
+        // Switch to the alternate screen and hide the cursor.
+        term.out.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        term.out.flush()?;
+        Ok(term)
+    }
+
+    /// Get the terminal size, as (width, height).
+    fn size(&self) -> (usize, usize) {
+        let mut size = MaybeUninit::<libc::winsize>::zeroed();
+        match unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, size.as_mut_ptr()) } {
+            0 => {
+                let size = unsafe { size.assume_init() };
+                match (size.ws_col, size.ws_row) {
+                    (0, _) | (_, 0) => (80, 24),
+                    (width, height) => (width as usize, height as usize),
+                }
+            }
+            _ => (80, 24),
+        }
+    }
+
+    fn draw(&mut self, lines: &[String]) -> Result<()> {
+        let mut screen = String::new();
+        for (row, line) in lines.iter().enumerate() {
+            let _ = write!(screen, "\x1b[{};1H{line}\x1b[K", row + 1);
+        }
+        self.out.write_all(screen.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+
+    /// Wait up to a given timeout for keys to be pressed.
+    fn read_keys(&self, timeout: Duration) -> Result<Vec<Key>> {
+        let mut fds = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // Interruptions are reported as errors and handled as timeouts.
+        if unsafe { libc::poll(&mut fds, 1, timeout.as_millis() as libc::c_int) } <= 0 {
+            return Ok(Vec::new());
+        }
+
+        // Read directly from the file descriptor, as buffered data would not
+        // be reported by poll.
+        let mut buf = [0; 256];
+        let len = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+        if len < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock => Ok(Vec::new()),
+                _ => Err(err.into()),
+            };
+        }
+
+        Ok(parse_keys(&buf[..len as usize]))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // Show the cursor and leave the alternate screen.
+        let _ = self.out.write_all(b"\x1b[?25h\x1b[?1049l");
+        let _ = self.out.flush();
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.orig) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(probe: &str, text: &str) -> WatchEntry {
+        WatchEntry {
+            probe: Some(probe.to_string()),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(
+            parse_keys(b"q /a\x1b[A\x1b[B\x1b[5~\x1b[6~\x1bOH\x1b[4~\x1b\r\x7f\x03"),
+            vec![
+                Key::Char('q'),
+                Key::Char(' '),
+                Key::Char('/'),
+                Key::Char('a'),
+                Key::Up,
+                Key::Down,
+                Key::PageUp,
+                Key::PageDown,
+                Key::Home,
+                Key::End,
+                Key::Esc,
+                Key::Enter,
+                Key::Backspace,
+                Key::CtrlC,
+            ]
+        );
+        // Unknown sequences and UTF-8.
+        assert_eq!(
+            parse_keys("\x1b[1;5C\x1b[15~é".as_bytes()),
+            vec![Key::Char('é')]
+        );
+    }
+
+    #[test]
+    fn rates() {
+        let now = Instant::now();
+        let mut rates = ProbeRates::new(now);
+        (0..10).for_each(|_| rates.add("kprobe/ip_rcv"));
+        (0..20).for_each(|_| rates.add("tp/skb:kfree_skb"));
+
+        assert!(!rates.tick(now + Duration::from_millis(500)));
+        assert!(rates.tick(now + Duration::from_secs(2)));
+        rates.add("kprobe/ip_rcv");
+
+        let sorted: Vec<_> = rates
+            .sorted()
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats.rate, stats.total))
+            .collect();
+        assert_eq!(
+            sorted,
+            vec![("tp/skb:kfree_skb", 10.0, 20), ("kprobe/ip_rcv", 5.0, 11)]
+        );
+    }
+
+    #[test]
+    fn viewer() {
+        let mut state = WatchState::new(3, Instant::now());
+        state.push(entry("kprobe/ip_rcv", "1 ip_rcv"));
+        state.push(entry("kprobe/tcp_v4_rcv", "2 tcp_v4_rcv"));
+        state.push(entry("kprobe/ip_rcv", "3 ip_rcv"));
+        state.push(entry("kprobe/ip_rcv", "4 ip_rcv"));
+
+        // Scrollback.
+        let lines = state.render(20, 4);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "2 tcp_v4_rcv        ");
+        assert_eq!(lines[2], "4 ip_rcv            ");
+        assert!(lines[3].starts_with("\x1b[7m 3 events"));
+
+        let lines = state.render(20, 3);
+        assert_eq!(lines[0], "3 ip_rcv            ");
+        assert!(state.handle_key(Key::Up));
+        assert!(state.paused);
+        let lines = state.render(20, 3);
+        assert_eq!(lines[0], "2 tcp_v4_rcv        ");
+
+        // Pause.
+        state.push(entry("kprobe/ip_rcv", "5 ip_rcv"));
+        assert_eq!(state.render(20, 3)[1], "3 ip_rcv            ");
+        assert!(state.handle_key(Key::End));
+        assert!(!state.paused);
+        assert_eq!(state.render(20, 3)[1], "5 ip_rcv            ");
+
+        // Incremental filtering, case sensitive as it contains uppercase
+        // characters.
+        for key in parse_keys(b"/TCP") {
+            assert!(state.handle_key(key));
+        }
+        assert!(state.editing);
+        assert_eq!(state.render(20, 3)[1], " ".repeat(20));
+        for key in parse_keys(b"\x7f\x7f\x7f(") {
+            assert!(state.handle_key(key));
+        }
+        assert!(state.regex.is_none());
+        assert!(state.render(60, 3)[2].contains("filter: /( (invalid)"));
+        for key in parse_keys(b"\x7f[35]\r") {
+            assert!(state.handle_key(key));
+        }
+        assert!(!state.editing);
+        assert_eq!(state.filter, "[35]");
+        let lines = state.render(20, 3);
+        assert_eq!(lines[0], "3 ip_rcv            ");
+        assert_eq!(lines[1], "5 ip_rcv            ");
+        state.handle_key(Key::Esc);
+        assert!(state.filter.is_empty());
+
+        // Sidebar.
+        let lines = state.render(90, 4);
+        assert!(lines[0].ends_with(&format!("│{:<40}", " Probe rates (events/s)")));
+        assert!(lines[1].contains("        4 kprobe/ip_rcv"));
+
+        assert!(!state.handle_key(Key::Char('q')));
+    }
+}
@@ -0,0 +1,235 @@
+//! # Collapse
+//!
+//! Collapsing of consecutive identical events (e.g. keepalives) into a single
+//! one, with a repeat count.
+
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::events::{helpers::time::format_date_time, sink::EventSink, *};
+
+/// Sections of events changing between otherwise identical events, which are
+/// ignored when comparing them. Packets are compared using their flow.
+const VOLATILE_SECTIONS: [&str; 5] = ["common", "tracking", "skb-tracking", "skb", "packet"];
+
+/// Event collapsed with its following identical ones.
+pub(crate) struct CollapsedEvent {
+    /// First event.
+    pub(crate) event: Event,
+    /// Number of identical events, including the first one.
+    pub(crate) count: u64,
+    /// Timestamp of the last identical event.
+    pub(crate) last: u64,
+}
+
+/// Collapses consecutive identical events: events of the same probe, on the
+/// same flow, whose other fields (drop reason, conntrack state, device, etc)
+/// do not change.
+#[derive(Default)]
+pub(crate) struct EventCollapser {
+    /// Current event, with the key it is compared with.
+    current: Option<(serde_json::Value, CollapsedEvent)>,
+}
+
+impl EventCollapser {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Key identical events share.
+    fn key(event: &Event) -> Result<serde_json::Value> {
+        let mut key = serde_json::to_value(event)?;
+        if let Some(sections) = key.as_object_mut() {
+            VOLATILE_SECTIONS.iter().for_each(|s| {
+                sections.remove(*s);
+            });
+
+            if let Some(flow) = event.packet.as_ref().and_then(|p| p.data.flow()) {
+                sections.insert("flow".to_string(), flow.to_string().into());
+            }
+        }
+        Ok(key)
+    }
+
+    /// Add an event. Returns the previous collapsed event if this one is not
+    /// identical to it.
+    pub(crate) fn add(&mut self, event: &Event) -> Result<Option<CollapsedEvent>> {
+        let key = Self::key(event)?;
+        let timestamp = event
+            .common
+            .as_ref()
+            .map(|c| c.timestamp)
+            .unwrap_or_default();
+
+        if let Some((current, collapsed)) = self.current.as_mut() {
+            if *current == key {
+                collapsed.count += 1;
+                collapsed.last = timestamp;
+                return Ok(None);
+            }
+        }
+
+        Ok(self
+            .current
+            .replace((
+                key,
+                CollapsedEvent {
+                    event: event.clone(),
+                    count: 1,
+                    last: timestamp,
+                },
+            ))
+            .map(|(_, collapsed)| collapsed))
+    }
+
+    /// Get the current collapsed event, if any.
+    pub(crate) fn take(&mut self) -> Option<CollapsedEvent> {
+        self.current.take().map(|(_, collapsed)| collapsed)
+    }
+}
+
+/// Prints events in their text representation, collapsing identical ones.
+/// Repeated events are printed once, with the number of repeats and the time
+/// of the last one.
+pub(crate) struct PrintCollapsed {
+    writer: Box<dyn Write + Send>,
+    format: DisplayFormat,
+    collapser: EventCollapser,
+}
+
+impl PrintCollapsed {
+    pub(crate) fn new(writer: Box<dyn Write + Send>, format: DisplayFormat) -> Self {
+        Self {
+            writer,
+            format,
+            collapser: EventCollapser::new(),
+        }
+    }
+
+    /// Text representation of a collapsed event.
+    fn text(&self, collapsed: &CollapsedEvent) -> String {
+        let mut event = format!(
+            "{}",
+            collapsed.event.display(&self.format, &FormatterConf::new())
+        );
+        if event.is_empty() {
+            return event;
+        }
+
+        // Report repeats at the end of the first line.
+        if collapsed.count > 1 {
+            let repeats = format!(
+                " [repeated {} times, last at {}]",
+                collapsed.count,
+                format_date_time(
+                    self.format.time_format,
+                    collapsed.last,
+                    self.format.monotonic_offset
+                )
+            );
+            let pos = event.find('\n').unwrap_or(event.len());
+            event.insert_str(pos, &repeats);
+        }
+
+        event.push('\n');
+        if self.format.multiline {
+            event.push('\n');
+        }
+        event
+    }
+
+    fn write(&mut self, collapsed: CollapsedEvent) -> Result<()> {
+        let event = self.text(&collapsed);
+        Ok(self.writer.write_all(event.as_bytes())?)
+    }
+}
+
+impl EventSink for PrintCollapsed {
+    fn process_one(&mut self, e: &Event) -> Result<()> {
+        if let Some(startup) = &e.startup {
+            self.format.monotonic_offset = Some(startup.clock_monotonic_offset);
+            return Ok(());
+        }
+
+        if let Some(collapsed) = self.collapser.add(e)? {
+            self.write(collapsed)?;
+        }
+        Ok(())
+    }
+
+    /// Print the pending collapsed event, and flush the writer.
+    fn flush(&mut self) -> Result<()> {
+        if let Some(collapsed) = self.collapser.take() {
+            self.write(collapsed)?;
+        }
+        Ok(self.writer.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::file::FileEventsFactory;
+
+    #[test]
+    fn collapse() {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        // Skip the startup event.
+        factory.next_event().unwrap();
+        let event = factory.next_event().unwrap().unwrap();
+
+        // Same probe and flow, different timestamp and tracking information.
+        let mut repeat = event.clone();
+        repeat.common.as_mut().unwrap().timestamp += 1000;
+        repeat.skb_tracking.as_mut().unwrap().timestamp += 1000;
+
+        // Different conntrack state.
+        let mut other = repeat.clone();
+        other.ct = None;
+
+        let mut collapser = EventCollapser::new();
+        assert!(collapser.add(&event).unwrap().is_none());
+        assert!(collapser.add(&repeat).unwrap().is_none());
+        assert!(collapser.add(&repeat).unwrap().is_none());
+
+        let collapsed = collapser.add(&other).unwrap().unwrap();
+        assert_eq!(collapsed.count, 3);
+        assert_eq!(collapsed.last, 30419169062793);
+        assert_eq!(
+            collapsed.event.common.as_ref().unwrap().timestamp,
+            30419169061793
+        );
+
+        let collapsed = collapser.take().unwrap();
+        assert_eq!(collapsed.count, 1);
+        assert!(collapser.take().is_none());
+    }
+
+    #[test]
+    fn print_collapsed() {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        factory.next_event().unwrap();
+        let event = factory.next_event().unwrap().unwrap();
+
+        let output = PrintCollapsed::new(Box::new(Vec::new()), DisplayFormat::new());
+        let mut collapsed = CollapsedEvent {
+            event,
+            count: 1,
+            last: 30419169061793,
+        };
+        assert!(!output.text(&collapsed).contains("repeated"));
+
+        collapsed.count = 3;
+        collapsed.last = 30419169062793;
+        let text = output.text(&collapsed);
+        let mut lines = text.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with(" [repeated 3 times, last at 30419169062793]"));
+        assert!(lines.all(|l| !l.contains("repeated")));
+    }
+}
@@ -0,0 +1,147 @@
+//! Containers processor.
+//!
+//! When container enrichment was enabled at collection time, the containers
+//! known to the container runtimes are stored in the startup event. This
+//! processor uses them to annotate the netns and socket information of events
+//! with the container (or pod) they belong to.
+
+use std::collections::HashMap;
+
+use crate::events::*;
+
+/// ContainerMapper annotates events with container names, using the netns and
+/// cgroup ids they report.
+#[derive(Default)]
+pub(crate) struct ContainerMapper {
+    /// Netns inums to container (or pod) names.
+    netns: HashMap<u32, String>,
+    /// Cgroup ids to container names.
+    cgroups: HashMap<u64, String>,
+}
+
+impl ContainerMapper {
+    /// Build a mapper from the containers found in the startup event, if any.
+    pub(crate) fn new(startup: Option<&StartupEvent>) -> Self {
+        let mut mapper = Self::default();
+
+        let containers = startup
+            .and_then(|startup| startup.collection.as_ref())
+            .and_then(|collection| collection.containers.as_ref());
+        for container in containers.into_iter().flatten() {
+            if let Some(netns) = container.netns {
+                mapper
+                    .netns
+                    .entry(netns)
+                    .or_insert_with(|| container.netns_name());
+            }
+            if let Some(cgroup_id) = container.cgroup_id {
+                mapper.cgroups.insert(cgroup_id, container.full_name());
+            }
+        }
+
+        mapper
+    }
+
+    /// Process one event, annotating its netns and socket information.
+    pub(crate) fn process_one(&self, event: &mut Event) {
+        if let Some(netns) = event.netns.as_mut() {
+            // Inums can be reused once a netns is deleted, only trust the
+            // mapping for the netns seen at startup.
+            if netns.generation.is_none() {
+                netns.container = self.netns.get(&netns.inum).cloned();
+            }
+        }
+
+        if let Some(sock) = event.sock.as_mut() {
+            sock.container = sock.cgroup_id.and_then(|id| self.cgroups.get(&id)).cloned();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::helpers::time::TimeSpec;
+
+    #[test]
+    fn map_containers() {
+        let container = |name: &str, pod: Option<&str>, netns, cgroup_id| ContainerInfo {
+            id: name.to_string(),
+            name: name.to_string(),
+            runtime: "crictl".to_string(),
+            pod: pod.map(String::from),
+            pod_namespace: pod.map(|_| "default".to_string()),
+            pid: None,
+            netns: Some(netns),
+            cgroup_id: Some(cgroup_id),
+        };
+
+        let startup = StartupEvent {
+            retis_version: String::new(),
+            cmdline: String::new(),
+            clock_monotonic_offset: TimeSpec::new(0, 0),
+            machine: MachineInfo {
+                kernel_release: String::new(),
+                kernel_version: String::new(),
+                hardware_name: String::new(),
+                kernel_build_id: None,
+            },
+            collection: Some(CollectionInfo {
+                start_time: 0,
+                probes: Vec::new(),
+                filters: Vec::new(),
+                containers: Some(vec![
+                    container("nginx", Some("frontend"), 42, 1000),
+                    container("sidecar", Some("frontend"), 42, 1001),
+                    container("db", None, 43, 1002),
+                ]),
+                kube: None,
+            }),
+            split_file: None,
+            section_versions: None,
+        };
+        let mapper = ContainerMapper::new(Some(&startup));
+
+        let event = |inum, generation, cgroup_id| Event {
+            netns: Some(NetnsEvent {
+                cookie: None,
+                inum,
+                generation,
+                container: None,
+            }),
+            sock: Some(SockEvent {
+                cgroup_id,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut e = event(42, None, Some(1001));
+        mapper.process_one(&mut e);
+        assert_eq!(
+            e.netns.unwrap().container.as_deref(),
+            Some("default/frontend")
+        );
+        assert_eq!(
+            e.sock.unwrap().container.as_deref(),
+            Some("default/frontend/sidecar")
+        );
+
+        let mut e = event(43, None, Some(1002));
+        mapper.process_one(&mut e);
+        assert_eq!(e.netns.unwrap().container.as_deref(), Some("db"));
+        assert_eq!(e.sock.unwrap().container.as_deref(), Some("db"));
+
+        // Reused inum, unknown cgroup.
+        let mut e = event(43, Some(1), Some(1));
+        mapper.process_one(&mut e);
+        assert!(e.netns.unwrap().container.is_none());
+        assert!(e.sock.unwrap().container.is_none());
+
+        // No container information.
+        let mapper = ContainerMapper::new(None);
+        let mut e = event(42, None, None);
+        mapper.process_one(&mut e);
+        assert!(e.netns.unwrap().container.is_none());
+    }
+}
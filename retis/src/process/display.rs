@@ -1,8 +1,8 @@
-use std::io::Write;
+use std::io::{stdout, Write};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 
-use crate::events::*;
+use crate::events::{helpers::time::format_date_time, sink::EventSink, *};
 
 /// Select the format to follow when printing events with `PrintEvent`.
 pub(crate) enum PrintEventFormat {
@@ -11,21 +11,56 @@ pub(crate) enum PrintEventFormat {
     Text(DisplayFormat),
     /// Json: display the event as JSON.
     Json,
+    /// Protobuf: write the event as a length-delimited protobuf message (see
+    /// `events::file::proto`).
+    Protobuf,
+    /// Journey(format): display event series as a single line describing the
+    /// path of the packet in the stack (see `journey`). Only applies to event
+    /// series.
+    Journey(DisplayFormat),
+}
+
+impl From<file::EventFormat> for PrintEventFormat {
+    fn from(format: file::EventFormat) -> Self {
+        match format {
+            file::EventFormat::Json => PrintEventFormat::Json,
+            file::EventFormat::Protobuf => PrintEventFormat::Protobuf,
+        }
+    }
+}
+
+/// Print the collection information found in the header of an event file
+/// (its startup event) to stdout.
+pub(crate) fn print_header(
+    startup: Option<&StartupEvent>,
+    mut format: DisplayFormat,
+) -> Result<()> {
+    let startup = startup.ok_or_else(|| anyhow!("No collection information found"))?;
+    format.monotonic_offset = Some(startup.clock_monotonic_offset);
+
+    writeln!(
+        stdout(),
+        "{}",
+        startup.display(&format, &FormatterConf::new())
+    )?;
+    Ok(())
 }
 
 /// Handles event individually and write to a `Write`.
 pub(crate) struct PrintEvent {
-    writer: Box<dyn Write>,
+    writer: Box<dyn Write + Send>,
     format: PrintEventFormat,
 }
 
 impl PrintEvent {
-    pub(crate) fn new(writer: Box<dyn Write>, format: PrintEventFormat) -> Self {
+    pub(crate) fn new(writer: Box<dyn Write + Send>, format: PrintEventFormat) -> Self {
         Self { writer, format }
     }
+}
 
+impl EventSink for PrintEvent {
     /// Process events one by one (format & print).
-    pub(crate) fn process_one(&mut self, e: &Event) -> Result<()> {
+    fn process_one(&mut self, e: &Event) -> Result<()> {
         match self.format {
             PrintEventFormat::Text(ref mut format) => {
                 if let Some(startup) = &e.startup {
@@ -46,13 +81,17 @@ impl PrintEvent {
                 event.push(b'\n');
                 self.writer.write_all(&event)?;
             }
+            PrintEventFormat::Protobuf => {
+                self.writer.write_all(&file::proto::encode(e)?)?;
+            }
+            PrintEventFormat::Journey(_) => bail!("Journeys can only be printed for event series"),
         }
 
         Ok(())
     }
 
     /// Flush underlying writers.
-    pub(crate) fn flush(&mut self) -> Result<()> {
+    fn flush(&mut self) -> Result<()> {
         Ok(self.writer.flush()?)
     }
 }
@@ -103,6 +142,19 @@ impl PrintSeries {
                 event.push(b'\n');
                 self.writer.write_all(&event)?;
             }
+            PrintEventFormat::Protobuf => bail!("Event series can't be written as protobuf"),
+            PrintEventFormat::Journey(ref mut format) => {
+                for event in series.events.iter() {
+                    if let Some(startup) = &event.startup {
+                        format.monotonic_offset = Some(startup.clock_monotonic_offset);
+                    }
+                }
+
+                if let Some(mut journey) = journey(series, format) {
+                    journey.push('\n');
+                    self.writer.write_all(journey.as_bytes())?;
+                }
+            }
         }
 
         Ok(())
@@ -113,3 +165,124 @@ impl PrintSeries {
         Ok(self.writer.flush()?)
     }
 }
+
+/// Get the journey of a packet out of its series of events, as a single line:
+/// the probes it went through, along with the interfaces it was seen on and the
+/// verdicts it got. Returns None if the series has no packet event.
+pub(crate) fn journey(series: &EventSeries, format: &DisplayFormat) -> Option<String> {
+    let mut events = series
+        .events
+        .iter()
+        .filter(|e| e.startup.is_none())
+        .peekable();
+    let first = events.peek()?;
+
+    let mut journey = String::new();
+    if let Some(common) = &first.common {
+        journey.push_str(&format_date_time(
+            format.time_format,
+            common.timestamp,
+            format.monotonic_offset,
+        ));
+    }
+    if let Some(tracking) = &first.tracking {
+        journey.push_str(&format!(" #{:x}", tracking.skb.tracking_id()));
+    }
+
+    let mut iface = None;
+    let steps = events
+        .map(|event| {
+            let mut step = match (&event.kernel, &event.userspace) {
+                (Some(kernel), _) => kernel.symbol.clone(),
+                (_, Some(user)) => user.symbol.clone(),
+                _ => "?".to_string(),
+            };
+
+            // Only report the interface when it changes.
+            if let Some(dev) = &event.dev {
+                let name = match dev.name.is_empty() {
+                    true => dev.ifindex.to_string(),
+                    false => dev.name.clone(),
+                };
+                if iface.as_ref() != Some(&name) {
+                    step.push_str(&format!(" @{name}"));
+                    iface = Some(name);
+                }
+            }
+
+            if let Some(nft) = &event.nft {
+                step.push_str(&format!(" (nft {})", nft.verdict));
+            }
+            if let Some(verdict) = event.nf.as_ref().and_then(|nf| nf.verdict.as_ref()) {
+                step.push_str(&format!(" (nf {verdict})"));
+            }
+            if let Some(drop) = &event.skb_drop {
+                match &drop.subsys {
+                    Some(subsys) => {
+                        step.push_str(&format!(" (drop {subsys}/{})", drop.drop_reason))
+                    }
+                    None => step.push_str(&format!(" (drop {})", drop.drop_reason)),
+                }
+            }
+            step
+        })
+        .collect::<Vec<_>>();
+
+    journey.push_str(&format!(" ({} events): {}", steps.len(), steps.join(" > ")));
+    Some(journey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn series_journey() {
+        let event = |symbol: &str, ts: u64| Event {
+            common: Some(CommonEvent {
+                timestamp: ts,
+                ..Default::default()
+            }),
+            kernel: Some(KernelEvent {
+                symbol: symbol.to_string(),
+                probe_type: "kprobe".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut events = vec![
+            event("ip_rcv", 10),
+            event("nft_do_chain", 20),
+            event("nf_hook_slow", 25),
+            event("kfree_skb", 30),
+        ];
+        events[0].dev = Some(DevEvent {
+            name: "eth0".to_string(),
+            ifindex: 2,
+            rx_ifindex: None,
+        });
+        events[1].dev = events[0].dev.clone();
+        events[1].nft = Some(NftEvent {
+            verdict: "drop".to_string(),
+            ..Default::default()
+        });
+        events[2].nf = Some(NfEvent {
+            family: "ipv4".to_string(),
+            hook: "prerouting".to_string(),
+            verdict: Some("drop".to_string()),
+            err: Some(-1),
+        });
+        events[3].skb_drop = Some(SkbDropEvent {
+            subsys: None,
+            drop_reason: "NETFILTER_DROP".to_string(),
+        });
+
+        let format = DisplayFormat::new();
+        assert_eq!(
+            journey(&EventSeries { events }, &format).unwrap(),
+            "10 (4 events): ip_rcv @eth0 > nft_do_chain (nft drop) > nf_hook_slow (nf drop) > kfree_skb (drop NETFILTER_DROP)"
+        );
+        assert!(journey(&EventSeries::default(), &format).is_none());
+    }
+}
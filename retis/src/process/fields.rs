@@ -0,0 +1,494 @@
+//! # Fields
+//!
+//! Extraction of selected fields out of events, and printing of those as
+//! columns, CSV or following a user-provided template.
+
+use std::{io::Write, str::FromStr};
+
+use anyhow::{bail, Result};
+
+use crate::events::{helpers::time::format_date_time, sink::EventSink, *};
+
+/// Number of rows buffered to compute the width of the columns, when printing
+/// aligned columns.
+const ALIGN_ROWS: usize = 100;
+
+/// Field extracted from events.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Field {
+    /// Name of the field, as given by the user.
+    name: String,
+    kind: FieldKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum FieldKind {
+    /// Event timestamp, following the time format.
+    Timestamp,
+    /// Probe the event was generated by.
+    Probe,
+    /// Packet tracking id.
+    TrackingId,
+    /// Network interface.
+    Iface,
+    /// Source IP address of the packet.
+    Src,
+    /// Destination IP address of the packet.
+    Dst,
+    /// L4 protocol of the packet.
+    Proto,
+    /// Source port of the packet.
+    Sport,
+    /// Destination port of the packet.
+    Dport,
+    /// Length of the packet.
+    Len,
+    /// TCP maximum segment size option.
+    Mss,
+    /// TCP window scale option.
+    Wscale,
+    /// TCP SACK blocks.
+    Sack,
+    /// Any field of the JSON representation of events, as a JSON pointer.
+    Json(String),
+}
+
+impl FromStr for Field {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let kind = match s {
+            "" => bail!("Empty field name"),
+            "ts" => FieldKind::Timestamp,
+            "probe" => FieldKind::Probe,
+            "tracking_id" => FieldKind::TrackingId,
+            "iface" => FieldKind::Iface,
+            "src" => FieldKind::Src,
+            "dst" => FieldKind::Dst,
+            "proto" => FieldKind::Proto,
+            "sport" => FieldKind::Sport,
+            "dport" => FieldKind::Dport,
+            "len" => FieldKind::Len,
+            "mss" => FieldKind::Mss,
+            "wscale" => FieldKind::Wscale,
+            "sack" => FieldKind::Sack,
+            path => FieldKind::Json(format!("/{}", path.replace('.', "/"))),
+        };
+
+        Ok(Self {
+            name: s.to_string(),
+            kind,
+        })
+    }
+}
+
+impl Field {
+    /// Is the field extracted from the JSON representation of events?
+    fn is_json(&self) -> bool {
+        matches!(self.kind, FieldKind::Json(_))
+    }
+
+    /// Get the value of the field for a given event, if any.
+    fn value(
+        &self,
+        event: &Event,
+        json: Option<&serde_json::Value>,
+        format: &DisplayFormat,
+    ) -> Option<String> {
+        let flow = || event.packet.as_ref().and_then(|p| p.data.flow());
+        let tcp_options = || event.packet.as_ref().and_then(|p| p.data.tcp_options());
+
+        match &self.kind {
+            FieldKind::Timestamp => event.common.as_ref().map(|common| {
+                format_date_time(
+                    format.time_format,
+                    common.timestamp,
+                    format.monotonic_offset,
+                )
+            }),
+            FieldKind::Probe => match (&event.kernel, &event.userspace) {
+                (Some(kernel), _) => Some(format!("{}/{}", kernel.probe_type, kernel.symbol)),
+                (_, Some(user)) => Some(format!("{}/{}", user.probe_type, user.symbol)),
+                _ => None,
+            },
+            FieldKind::TrackingId => event
+                .skb_tracking
+                .as_ref()
+                .map(|t| format!("#{:x}", t.tracking_id())),
+            FieldKind::Iface => event.dev.as_ref().map(|dev| match dev.name.is_empty() {
+                true => dev.ifindex.to_string(),
+                false => dev.name.clone(),
+            }),
+            FieldKind::Src => flow().map(|flow| flow.src.to_string()),
+            FieldKind::Dst => flow().map(|flow| flow.dst.to_string()),
+            FieldKind::Proto => flow().map(|flow| flow.protocol_name()),
+            FieldKind::Sport => flow().and_then(|flow| flow.ports).map(|p| p.0.to_string()),
+            FieldKind::Dport => flow().and_then(|flow| flow.ports).map(|p| p.1.to_string()),
+            FieldKind::Len => event.packet.as_ref().map(|p| p.len.to_string()),
+            FieldKind::Mss => tcp_options()
+                .and_then(|opts| opts.mss)
+                .map(|mss| mss.to_string()),
+            FieldKind::Wscale => tcp_options()
+                .and_then(|opts| opts.wscale)
+                .map(|wscale| wscale.to_string()),
+            FieldKind::Sack => tcp_options()
+                .filter(|opts| !opts.sack.is_empty())
+                .map(|opts| {
+                    opts.sack
+                        .iter()
+                        .map(|(left, right)| format!("{left}:{right}"))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                }),
+            FieldKind::Json(pointer) => {
+                json.and_then(|json| json.pointer(pointer))
+                    .map(|val| match val.as_str() {
+                        Some(val) => val.to_string(),
+                        None => val.to_string(),
+                    })
+            }
+        }
+    }
+}
+
+/// Handles printing selected fields of events to a `Write`, either as aligned
+/// columns or as CSV.
+pub(crate) struct PrintFields {
+    writer: Box<dyn Write + Send>,
+    fields: Vec<Field>,
+    format: DisplayFormat,
+    csv: bool,
+    /// Was the header written?
+    wrote_header: bool,
+    /// Rows buffered to compute the width of the columns.
+    rows: Vec<Vec<String>>,
+    widths: Vec<usize>,
+}
+
+impl PrintFields {
+    pub(crate) fn new(
+        writer: Box<dyn Write + Send>,
+        fields: Vec<Field>,
+        format: DisplayFormat,
+        csv: bool,
+    ) -> Self {
+        let widths = fields.iter().map(|f| f.name.len()).collect();
+        Self {
+            writer,
+            fields,
+            format,
+            csv,
+            wrote_header: false,
+            rows: Vec::new(),
+            widths,
+        }
+    }
+
+    /// Get the values of the fields for a given event. Missing values are
+    /// empty in CSV and reported as '-' otherwise.
+    fn row(&self, event: &Event) -> Result<Vec<String>> {
+        let json = match self.fields.iter().any(|f| f.is_json()) {
+            true => Some(serde_json::to_value(event)?),
+            false => None,
+        };
+
+        Ok(self
+            .fields
+            .iter()
+            .map(|f| {
+                f.value(event, json.as_ref(), &self.format)
+                    .unwrap_or_else(|| match self.csv {
+                        true => String::new(),
+                        false => "-".to_string(),
+                    })
+            })
+            .collect())
+    }
+
+    /// Format a row as CSV.
+    fn csv_line(row: &[String]) -> String {
+        row.iter()
+            .map(|val| match val.contains([',', '"', '\n', '\r']) {
+                true => format!("\"{}\"", val.replace('"', "\"\"")),
+                false => val.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Format a row as aligned columns. The last column is not padded.
+    fn columns_line(row: &[String], widths: &[usize]) -> String {
+        let mut line = row
+            .iter()
+            .zip(widths)
+            .map(|(val, width)| format!("{val:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        line.truncate(line.trim_end().len());
+        line
+    }
+
+    fn write_row(&mut self, row: &[String]) -> Result<()> {
+        let mut line = match self.csv {
+            true => Self::csv_line(row),
+            false => Self::columns_line(row, &self.widths),
+        };
+        line.push('\n');
+        Ok(self.writer.write_all(line.as_bytes())?)
+    }
+
+    /// Write the header and the buffered rows.
+    fn write_buffered(&mut self) -> Result<()> {
+        if !self.wrote_header {
+            let header: Vec<_> = self.fields.iter().map(|f| f.name.clone()).collect();
+            self.write_row(&header)?;
+            self.wrote_header = true;
+        }
+
+        for row in std::mem::take(&mut self.rows) {
+            self.write_row(&row)?;
+        }
+        Ok(())
+    }
+}
+
+impl EventSink for PrintFields {
+    fn process_one(&mut self, e: &Event) -> Result<()> {
+        if let Some(startup) = &e.startup {
+            self.format.monotonic_offset = Some(startup.clock_monotonic_offset);
+            return Ok(());
+        }
+
+        let row = self.row(e)?;
+        if self.csv {
+            self.write_buffered()?;
+            return self.write_row(&row);
+        }
+
+        // Columns can only grow once the header was written, later rows
+        // might not be aligned with the first ones.
+        self.widths
+            .iter_mut()
+            .zip(row.iter())
+            .for_each(|(width, val)| *width = (*width).max(val.len()));
+
+        self.rows.push(row);
+        if self.wrote_header || self.rows.len() >= ALIGN_ROWS {
+            self.write_buffered()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.write_buffered()?;
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Output template, made of literal text and of fields given as `{FIELD}`
+/// (see `Field`). Braces are escaped by doubling them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Field(Field),
+}
+
+impl FromStr for Template {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => bail!("Unterminated field in template ({s})"),
+                            Some(c) => name.push(c),
+                        }
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(TemplatePart::Field(Field::from_str(name.trim())?));
+                }
+                '}' => bail!("Unmatched '}}' in template ({s}), use '}}}}' to print it"),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(text));
+        }
+
+        Ok(Self { parts })
+    }
+}
+
+impl Template {
+    /// Render the template for a given event. Missing fields are rendered as
+    /// '-'.
+    fn render(&self, event: &Event, format: &DisplayFormat) -> Result<String> {
+        let json = match self.parts.iter().any(|p| match p {
+            TemplatePart::Field(f) => f.is_json(),
+            _ => false,
+        }) {
+            true => Some(serde_json::to_value(event)?),
+            false => None,
+        };
+
+        let mut out = String::new();
+        self.parts.iter().for_each(|part| match part {
+            TemplatePart::Text(text) => out.push_str(text),
+            TemplatePart::Field(field) => match field.value(event, json.as_ref(), format) {
+                Some(val) => out.push_str(&val),
+                None => out.push('-'),
+            },
+        });
+        Ok(out)
+    }
+}
+
+/// Handles printing events following a template to a `Write`, one line per
+/// event.
+pub(crate) struct PrintTemplate {
+    writer: Box<dyn Write + Send>,
+    template: Template,
+    format: DisplayFormat,
+}
+
+impl PrintTemplate {
+    pub(crate) fn new(
+        writer: Box<dyn Write + Send>,
+        template: Template,
+        format: DisplayFormat,
+    ) -> Self {
+        Self {
+            writer,
+            template,
+            format,
+        }
+    }
+}
+
+impl EventSink for PrintTemplate {
+    fn process_one(&mut self, e: &Event) -> Result<()> {
+        if let Some(startup) = &e.startup {
+            self.format.monotonic_offset = Some(startup.clock_monotonic_offset);
+            return Ok(());
+        }
+
+        let mut line = self.template.render(e, &self.format)?;
+        line.push('\n');
+        Ok(self.writer.write_all(line.as_bytes())?)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::file::FileEventsFactory;
+
+    #[test]
+    fn fields() {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        // Skip the startup event.
+        factory.next_event().unwrap();
+        let event = factory.next_event().unwrap().unwrap();
+
+        let fields: Vec<Field> = [
+            "ts",
+            "probe",
+            "tracking_id",
+            "iface",
+            "src",
+            "dst",
+            "proto",
+            "sport",
+            "len",
+            "ct.state",
+            "skb.meta.len",
+            "unknown",
+        ]
+        .iter()
+        .map(|f| Field::from_str(f).unwrap())
+        .collect();
+        assert!(Field::from_str("").is_err());
+
+        let printer = PrintFields::new(Box::new(Vec::new()), fields, DisplayFormat::new(), false);
+        assert_eq!(
+            printer.row(&event).unwrap(),
+            vec![
+                "30419169061793",
+                "raw_tracepoint/net:net_dev_start_xmit",
+                "#1baa83c42ba1ffff8e95c3b67c00",
+                "veth-ns01",
+                "192.168.125.10",
+                "192.168.125.11",
+                "ICMP",
+                "-",
+                "98",
+                "new",
+                "98",
+                "-",
+            ]
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let row = vec!["a".to_string(), "b,c".to_string(), "d\"e".to_string()];
+        assert_eq!(PrintFields::csv_line(&row), "a,\"b,c\",\"d\"\"e\"");
+        assert_eq!(
+            PrintFields::columns_line(&row, &[3, 4, 4]),
+            "a    b,c   d\"e"
+        );
+    }
+
+    #[test]
+    fn template() {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        factory.next_event().unwrap();
+        let event = factory.next_event().unwrap().unwrap();
+
+        let template =
+            Template::from_str("{ts} {kernel.symbol} {src}->{dst} {{{ct.state}}} {sport}").unwrap();
+        assert_eq!(
+            template.render(&event, &DisplayFormat::new()).unwrap(),
+            "30419169061793 net:net_dev_start_xmit 192.168.125.10->192.168.125.11 {new} -"
+        );
+        assert_eq!(
+            Template::from_str("no field").unwrap().parts,
+            vec![TemplatePart::Text("no field".to_string())]
+        );
+
+        assert!(Template::from_str("{ts").is_err());
+        assert!(Template::from_str("ts}").is_err());
+        assert!(Template::from_str("{}").is_err());
+        assert!(Template::from_str("{a{b}").is_err());
+    }
+}
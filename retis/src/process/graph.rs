@@ -0,0 +1,159 @@
+//! # Graph
+//!
+//! Aggregates the paths of packets across probes into a graph, which can be
+//! rendered for visualization.
+
+use std::{collections::HashMap, fmt::Write};
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::events::*;
+
+/// Format of a rendered graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum GraphFormat {
+    /// Graphviz dot.
+    Dot,
+    /// Mermaid flowchart.
+    Mermaid,
+}
+
+/// Graph of the paths of packets across probes. Nodes are probes and edges
+/// are weighted by the number of packets going from a probe to the next one.
+#[derive(Default)]
+pub(crate) struct PathGraph {
+    // Nodes, in order of appearance, with their number of events.
+    nodes: Vec<(String, u64)>,
+    // Node indexes, by name.
+    ids: HashMap<String, usize>,
+    // Number of packets, by (from, to) node indexes.
+    edges: HashMap<(usize, usize), u64>,
+}
+
+impl PathGraph {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn node(&mut self, name: &str) -> usize {
+        match self.ids.get(name) {
+            Some(id) => *id,
+            None => {
+                self.nodes.push((name.to_string(), 0));
+                self.ids.insert(name.to_string(), self.nodes.len() - 1);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Add the path of a packet, from its series of events.
+    pub(crate) fn add(&mut self, series: &EventSeries) {
+        let mut prev = None;
+        series
+            .events
+            .iter()
+            .filter_map(|e| match (&e.kernel, &e.userspace) {
+                (Some(kernel), _) => Some(&kernel.symbol),
+                (_, Some(user)) => Some(&user.symbol),
+                _ => None,
+            })
+            .for_each(|name| {
+                let id = self.node(name);
+                self.nodes[id].1 += 1;
+                if let Some(prev) = prev {
+                    *self.edges.entry((prev, id)).or_insert(0) += 1;
+                }
+                prev = Some(id);
+            });
+    }
+
+    /// Render the graph in a given format.
+    pub(crate) fn render(&self, format: GraphFormat) -> Result<String> {
+        let mut edges: Vec<_> = self.edges.iter().collect();
+        edges.sort();
+
+        let mut out = String::new();
+        match format {
+            GraphFormat::Dot => {
+                writeln!(out, "digraph retis {{")?;
+                for (id, (name, count)) in self.nodes.iter().enumerate() {
+                    let name = name.replace('"', "\\\"");
+                    writeln!(out, "  n{id} [label=\"{name} ({count})\"];")?;
+                }
+                for ((from, to), count) in edges {
+                    writeln!(out, "  n{from} -> n{to} [label=\"{count}\"];")?;
+                }
+                writeln!(out, "}}")?;
+            }
+            GraphFormat::Mermaid => {
+                writeln!(out, "flowchart LR")?;
+                for (id, (name, count)) in self.nodes.iter().enumerate() {
+                    let name = name.replace('"', "#quot;");
+                    writeln!(out, "  n{id}[\"{name} ({count})\"]")?;
+                }
+                for ((from, to), count) in edges {
+                    writeln!(out, "  n{from} -->|{count}| n{to}")?;
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(symbols: &[&str]) -> EventSeries {
+        EventSeries {
+            events: symbols
+                .iter()
+                .map(|symbol| Event {
+                    kernel: Some(KernelEvent {
+                        symbol: symbol.to_string(),
+                        probe_type: "kprobe".to_string(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn path_graph() {
+        let mut graph = PathGraph::new();
+        graph.add(&series(&["ip_rcv", "ip_local_deliver", "tcp_v4_rcv"]));
+        graph.add(&series(&["ip_rcv", "kfree_skb"]));
+        graph.add(&series(&["ip_rcv", "ip_local_deliver"]));
+        // Startup series.
+        graph.add(&EventSeries::default());
+
+        assert_eq!(
+            graph.render(GraphFormat::Dot).unwrap(),
+            r#"digraph retis {
+  n0 [label="ip_rcv (3)"];
+  n1 [label="ip_local_deliver (2)"];
+  n2 [label="tcp_v4_rcv (1)"];
+  n3 [label="kfree_skb (1)"];
+  n0 -> n1 [label="2"];
+  n0 -> n3 [label="1"];
+  n1 -> n2 [label="1"];
+}
+"#
+        );
+        assert_eq!(
+            graph.render(GraphFormat::Mermaid).unwrap(),
+            r#"flowchart LR
+  n0["ip_rcv (3)"]
+  n1["ip_local_deliver (2)"]
+  n2["tcp_v4_rcv (1)"]
+  n3["kfree_skb (1)"]
+  n0 -->|2| n1
+  n0 -->|1| n3
+  n1 -->|1| n2
+"#
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Kubernetes processor.
+//!
+//! When the Kubernetes mode was enabled at collection time, the pods, services
+//! and nodes known to the API server are stored in the startup event. This
+//! processor uses them to annotate events with the Kubernetes objects their
+//! packet addresses and interface belong to.
+
+use std::{collections::HashMap, net::IpAddr};
+
+use crate::events::*;
+
+/// KubeMapper adds a Kubernetes section to events whose packet addresses or
+/// interface belong to known Kubernetes objects.
+#[derive(Default)]
+pub(crate) struct KubeMapper {
+    /// Addresses to objects.
+    addrs: HashMap<IpAddr, KubeEndpoint>,
+    /// Host side ifindexes of pod interfaces to pods.
+    ifaces: HashMap<u32, KubeEndpoint>,
+    /// Netns inum of the host.
+    host_netns: Option<u32>,
+}
+
+impl KubeMapper {
+    /// Build a mapper from the Kubernetes objects found in the startup event,
+    /// if any.
+    pub(crate) fn new(startup: Option<&StartupEvent>) -> Self {
+        let mut mapper = Self::default();
+
+        let kube = match startup
+            .and_then(|startup| startup.collection.as_ref())
+            .and_then(|collection| collection.kube.as_ref())
+        {
+            Some(kube) => kube,
+            None => return mapper,
+        };
+        mapper.host_netns = kube.host_netns;
+
+        // Nodes first, so pods and services take precedence in case of
+        // overlapping addresses.
+        kube.nodes.iter().for_each(|node| {
+            let endpoint = KubeEndpoint {
+                kind: "node".to_string(),
+                namespace: None,
+                name: node.name.clone(),
+            };
+            mapper.add_addrs(&node.ips, &endpoint);
+        });
+        kube.services.iter().for_each(|service| {
+            let endpoint = KubeEndpoint {
+                kind: "service".to_string(),
+                namespace: Some(service.namespace.clone()),
+                name: service.name.clone(),
+            };
+            mapper.add_addrs(&service.ips, &endpoint);
+        });
+        kube.pods.iter().for_each(|pod| {
+            let endpoint = KubeEndpoint {
+                kind: "pod".to_string(),
+                namespace: Some(pod.namespace.clone()),
+                name: pod.name.clone(),
+            };
+            mapper.add_addrs(&pod.ips, &endpoint);
+            pod.host_ifindexes.iter().for_each(|ifindex| {
+                mapper.ifaces.insert(*ifindex, endpoint.clone());
+            });
+        });
+
+        mapper
+    }
+
+    fn add_addrs(&mut self, ips: &[String], endpoint: &KubeEndpoint) {
+        ips.iter().filter_map(|ip| ip.parse().ok()).for_each(|ip| {
+            self.addrs.insert(ip, endpoint.clone());
+        });
+    }
+
+    /// Process one event, adding a Kubernetes section if any of its packet
+    /// addresses or its interface is known.
+    pub(crate) fn process_one(&self, event: &mut Event) {
+        if self.addrs.is_empty() && self.ifaces.is_empty() {
+            return;
+        }
+
+        let mut kube = KubeEvent::default();
+
+        if let Some(flow) = event.packet.as_ref().and_then(|p| p.data.flow()) {
+            kube.src = self.addrs.get(&flow.src).cloned();
+            kube.dst = self.addrs.get(&flow.dst).cloned();
+        }
+
+        // Ifindexes are only unique in a given netns, only look up interfaces
+        // in the host one.
+        if let (Some(dev), Some(netns)) = (&event.dev, &event.netns) {
+            if self.host_netns == Some(netns.inum) && netns.generation.is_none() {
+                kube.iface = self.ifaces.get(&dev.ifindex).cloned();
+            }
+        }
+
+        if kube.src.is_some() || kube.dst.is_some() || kube.iface.is_some() {
+            event.kube = Some(kube);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{file::FileEventsFactory, helpers::time::TimeSpec};
+
+    fn startup(kube: Option<KubeInfo>) -> StartupEvent {
+        StartupEvent {
+            retis_version: String::new(),
+            cmdline: String::new(),
+            clock_monotonic_offset: TimeSpec::new(0, 0),
+            machine: MachineInfo {
+                kernel_release: String::new(),
+                kernel_version: String::new(),
+                hardware_name: String::new(),
+                kernel_build_id: None,
+            },
+            collection: Some(CollectionInfo {
+                start_time: 0,
+                probes: Vec::new(),
+                filters: Vec::new(),
+                containers: None,
+                kube,
+            }),
+            split_file: None,
+            section_versions: None,
+        }
+    }
+
+    fn name(endpoint: &Option<KubeEndpoint>) -> Option<String> {
+        endpoint.as_ref().map(|e| e.to_string())
+    }
+
+    #[test]
+    fn map_kube() {
+        let mapper = KubeMapper::new(Some(&startup(Some(KubeInfo {
+            host_netns: Some(1),
+            pods: vec![KubePod {
+                name: "web".to_string(),
+                namespace: "default".to_string(),
+                ips: vec!["192.168.125.10".to_string()],
+                host_ifindexes: vec![12],
+                ..Default::default()
+            }],
+            services: vec![KubeService {
+                name: "db".to_string(),
+                namespace: "default".to_string(),
+                ips: vec!["192.168.125.11".to_string()],
+            }],
+            nodes: vec![KubeNode {
+                name: "node1".to_string(),
+                ips: vec!["192.168.0.10".to_string()],
+            }],
+        }))));
+
+        // Packet addresses.
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        // Skip the startup event.
+        factory.next_event().unwrap();
+        let mut event = factory.next_event().unwrap().unwrap();
+        mapper.process_one(&mut event);
+        let kube = event.kube.unwrap();
+        assert_eq!(name(&kube.src).as_deref(), Some("pod default/web"));
+        assert_eq!(name(&kube.dst).as_deref(), Some("svc default/db"));
+        assert!(kube.iface.is_none());
+
+        let event = |inum, ifindex| Event {
+            netns: Some(NetnsEvent {
+                cookie: None,
+                inum,
+                generation: None,
+                container: None,
+            }),
+            dev: Some(DevEvent {
+                ifindex,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // Interface in the host netns.
+        let mut e = event(1, 12);
+        mapper.process_one(&mut e);
+        let kube = e.kube.unwrap();
+        assert_eq!(name(&kube.iface).as_deref(), Some("pod default/web"));
+        assert!(kube.src.is_none() && kube.dst.is_none());
+
+        // Same ifindex, in another netns.
+        let mut e = event(2, 12);
+        mapper.process_one(&mut e);
+        assert!(e.kube.is_none());
+
+        let node = mapper.addrs.get(&"192.168.0.10".parse::<IpAddr>().unwrap());
+        assert_eq!(node.map(|n| n.to_string()).as_deref(), Some("node node1"));
+
+        // No Kubernetes information.
+        for mapper in [KubeMapper::new(None), KubeMapper::new(Some(&startup(None)))] {
+            let mut e = event(1, 12);
+            mapper.process_one(&mut e);
+            assert!(e.kube.is_none());
+        }
+    }
+}
@@ -4,6 +4,14 @@
 
 pub(crate) mod cli;
 
+pub(crate) mod collapse;
+pub(crate) mod containers;
 pub(crate) mod display;
+pub(crate) mod fields;
+pub(crate) mod graph;
+pub(crate) mod kube;
+pub(crate) mod netns;
+pub(crate) mod selection;
 pub(crate) mod series;
+pub(crate) mod symbolize;
 pub(crate) mod tracking;
@@ -0,0 +1,103 @@
+//! Netns processor.
+//!
+//! Netns inums can be reused once a netns is deleted. Using the netns lifecycle
+//! events, this processor annotates netns information with the generation of
+//! their inum, so netns are identified in a stable way during the collection.
+//! Netns cookies are never reused, but are not available on older kernels.
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use crate::events::*;
+
+/// NetnsTracker annotates netns information with the generation of their inum.
+#[derive(Default)]
+pub(crate) struct NetnsTracker {
+    /// Known inums, and their current generation.
+    inums: HashMap<u32, u32>,
+}
+
+impl NetnsTracker {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process one event, annotating its netns information.
+    pub(crate) fn process_one(&mut self, event: &mut Event) {
+        if let Some(lifecycle) = event.ns_lifecycle.as_mut() {
+            let inum = lifecycle.netns.inum;
+            if lifecycle.op == "create" {
+                match self.inums.entry(inum) {
+                    // The inum was used by a previous netns.
+                    Entry::Occupied(mut entry) => *entry.get_mut() += 1,
+                    Entry::Vacant(entry) => {
+                        entry.insert(0);
+                    }
+                }
+            }
+
+            lifecycle.netns.generation = self.generation(inum);
+            if let Some(target) = lifecycle.target.as_mut() {
+                target.generation = self.generation(target.inum);
+            }
+        }
+
+        if let Some(netns) = event.netns.as_mut() {
+            netns.generation = self.generation(netns.inum);
+        }
+    }
+
+    /// Get the generation of an inum, if it was reused. Inums seen for the
+    /// first time are recorded.
+    fn generation(&mut self, inum: u32) -> Option<u32> {
+        Some(*self.inums.entry(inum).or_insert(0)).filter(|generation| *generation > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(op: Option<&str>, inum: u32) -> Event {
+        Event {
+            ns_lifecycle: op.map(|op| NsLifecycleEvent {
+                op: op.to_string(),
+                netns: NsLifecycleNetns {
+                    inum,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            netns: Some(NetnsEvent {
+                cookie: None,
+                inum,
+                generation: None,
+                container: None,
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn netns_generation() {
+        let mut tracker = NetnsTracker::new();
+        let mut generation = |op, inum| {
+            let mut event = event(op, inum);
+            tracker.process_one(&mut event);
+            event.netns.unwrap().generation
+        };
+
+        // Inums seen before their creation is reported.
+        assert_eq!(generation(None, 1), None);
+        assert_eq!(generation(Some("delete"), 1), None);
+        assert_eq!(generation(Some("create"), 1), Some(1));
+        assert_eq!(generation(None, 1), Some(1));
+
+        // New inums.
+        assert_eq!(generation(Some("create"), 2), None);
+        assert_eq!(generation(None, 2), None);
+        assert_eq!(generation(Some("delete"), 2), None);
+        assert_eq!(generation(Some("create"), 2), Some(1));
+        assert_eq!(generation(Some("delete"), 2), Some(1));
+        assert_eq!(generation(Some("create"), 2), Some(2));
+    }
+}
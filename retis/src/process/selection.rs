@@ -0,0 +1,348 @@
+//! # Selection
+//!
+//! Selection of the events to process in post-processing commands, by time
+//! range, packet or count.
+
+use std::{collections::VecDeque, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use clap::Args;
+use log::debug;
+use regex::Regex;
+
+use crate::{
+    events::{file::FileEventsFactory, *},
+    helpers::file_rotate::InputDataFile,
+};
+
+/// Bound of a time range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TimeBound {
+    /// CLOCK_MONOTONIC timestamp, in nanoseconds.
+    Absolute(u64),
+    /// Time since the collection start, in nanoseconds.
+    Relative(u64),
+}
+
+impl FromStr for TimeBound {
+    type Err = anyhow::Error;
+
+    /// Parse a time bound, either a timestamp ("<NS>") or a time relative to
+    /// the collection start ("+<N><UNIT>", with UNIT being one of ns, us, ms,
+    /// s, m or h).
+    fn from_str(s: &str) -> Result<Self> {
+        let Some(rel) = s.strip_prefix('+') else {
+            return Ok(TimeBound::Absolute(
+                s.parse()
+                    .map_err(|e| anyhow!("Invalid timestamp ({s}): {e}"))?,
+            ));
+        };
+
+        let re = Regex::new(r"^(\d+)(ns|us|ms|s|m|h)$")?;
+        let matches = re.captures(rel).ok_or_else(|| {
+            anyhow!("Invalid relative time ({s}): expected +<N><UNIT> with UNIT one of ns, us, ms, s, m or h")
+        })?;
+
+        // Unwrap as the regex already checked both groups were mandatory.
+        let factor = match matches.get(2).unwrap().as_str() {
+            "us" => 1_000,
+            "ms" => 1_000_000,
+            "s" => 1_000_000_000,
+            "m" => 60 * 1_000_000_000,
+            "h" => 60 * 60 * 1_000_000_000,
+            _ => 1,
+        };
+        let time = u64::from_str(matches.get(1).unwrap().as_str())?
+            .checked_mul(factor)
+            .ok_or_else(|| anyhow!("Relative time is too large ({s})"))?;
+
+        Ok(TimeBound::Relative(time))
+    }
+}
+
+impl TimeBound {
+    /// Get the bound as a CLOCK_MONOTONIC timestamp, given the collection
+    /// information.
+    fn resolve(&self, startup: Option<&StartupEvent>) -> Result<u64> {
+        Ok(match self {
+            TimeBound::Absolute(ts) => *ts,
+            TimeBound::Relative(time) => {
+                match startup.and_then(|s| s.collection.as_ref()) {
+                    Some(collection) => collection.start_time.saturating_add(*time),
+                    None => bail!(
+                        "Relative times require the collection start time, which is not reported by the input"
+                    ),
+                }
+            }
+        })
+    }
+}
+
+/// Event selection arguments, shared by post-processing commands.
+#[derive(Args, Debug, Default)]
+pub(crate) struct EventSelection {
+    #[arg(
+        long,
+        visible_alias = "from",
+        value_name = "TIME",
+        help = "Only process events with a timestamp greater or equal to TIME. TIME is either a timestamp (CLOCK_MONOTONIC, in nanoseconds, as displayed without --utc) or a time relative to the collection start, as +<N><UNIT> with UNIT one of ns, us, ms, s, m or h (e.g. '+90s')"
+    )]
+    pub(crate) since: Option<TimeBound>,
+    #[arg(
+        long,
+        visible_alias = "to",
+        value_name = "TIME",
+        help = "Only process events with a timestamp lower or equal to TIME, see --since"
+    )]
+    pub(crate) until: Option<TimeBound>,
+    #[arg(
+        long,
+        value_parser = parse_tracking_id,
+        help = "Only process events of the packet with the given tracking id, as displayed (e.g. '#1dc34f0c2c4ffff8c71dcc5f400')"
+    )]
+    pub(crate) tracking_id: Option<u128>,
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with = "last",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Only process the first N events (after the other selections)"
+    )]
+    pub(crate) first: Option<u64>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Only process the last N events (after the other selections). Those are only processed once the whole input was read"
+    )]
+    pub(crate) last: Option<u64>,
+}
+
+fn parse_tracking_id(id: &str) -> Result<u128, String> {
+    let id = id.strip_prefix('#').unwrap_or(id);
+    u128::from_str_radix(id, 16).map_err(|e| format!("Invalid tracking id ({e})"))
+}
+
+impl EventSelection {
+    /// Are events being selected?
+    pub(crate) fn selects(&self) -> bool {
+        self.since.is_some()
+            || self.until.is_some()
+            || self.tracking_id.is_some()
+            || self.first.is_some()
+            || self.last.is_some()
+    }
+
+    /// Get a selector for the events of an input, given its collection
+    /// information.
+    pub(crate) fn selector(&self, startup: Option<&StartupEvent>) -> Result<EventSelector> {
+        Ok(EventSelector {
+            since: self.since.map(|s| s.resolve(startup)).transpose()?,
+            until: self.until.map(|u| u.resolve(startup)).transpose()?,
+            tracking_id: self.tracking_id,
+            first: self.first,
+            last: self.last,
+            count: 0,
+            kept: VecDeque::new(),
+            eof: false,
+        })
+    }
+}
+
+/// Selects events read from a `FileEventsFactory`, following an
+/// `EventSelection`. Startup events are always selected.
+pub(crate) struct EventSelector {
+    since: Option<u64>,
+    until: Option<u64>,
+    tracking_id: Option<u128>,
+    first: Option<u64>,
+    last: Option<u64>,
+    // Number of events selected so far.
+    count: u64,
+    // Last selected events, when only the last ones are processed.
+    kept: VecDeque<Event>,
+    // Was the end of the input reached?
+    eof: bool,
+}
+
+impl EventSelector {
+    /// Does an event match the time range and tracking id selection?
+    fn matches(&self, event: &Event) -> bool {
+        let ts = event
+            .common
+            .as_ref()
+            .map(|c| c.timestamp)
+            .unwrap_or_default();
+        if self.since.is_some_and(|since| ts < since) || self.until.is_some_and(|until| ts > until)
+        {
+            return false;
+        }
+
+        match self.tracking_id {
+            Some(id) => event
+                .skb_tracking
+                .as_ref()
+                .is_some_and(|t| t.tracking_id() == id),
+            None => true,
+        }
+    }
+
+    /// Get the next selected event.
+    pub(crate) fn next_event(&mut self, factory: &mut FileEventsFactory) -> Result<Option<Event>> {
+        loop {
+            if self.eof || self.first.is_some_and(|first| self.count >= first) {
+                return Ok(self.kept.pop_front());
+            }
+
+            // The end of the input is only final when keeping the last
+            // events, for followed inputs to be read again.
+            let Some(event) = factory.next_event()? else {
+                if self.last.is_none() {
+                    return Ok(None);
+                }
+                self.eof = true;
+                continue;
+            };
+            if event.startup.is_some() {
+                return Ok(Some(event));
+            }
+            if !self.matches(&event) {
+                continue;
+            }
+
+            self.count += 1;
+            match self.last {
+                Some(last) => {
+                    if self.kept.len() as u64 == last {
+                        self.kept.pop_front();
+                    }
+                    self.kept.push_back(event);
+                }
+                None => return Ok(Some(event)),
+            }
+        }
+    }
+
+    /// Get the part of an input containing the selected events using its
+    /// index, if any. The range is empty if no event can match.
+    pub(crate) fn input_range(&self, input: &InputDataFile) -> Result<Option<(u64, u64)>> {
+        if self.since.is_none() && self.until.is_none() && self.tracking_id.is_none() {
+            return Ok(None);
+        }
+
+        let Some(index) = input.index()? else {
+            debug!("No index found, reading the whole input");
+            return Ok(None);
+        };
+
+        let mut range = (0, index.end);
+        if self.since.is_some() || self.until.is_some() {
+            match index.time_range(self.since.unwrap_or(0), self.until.unwrap_or(u64::MAX)) {
+                Some((start, end)) => range = (range.0.max(start), range.1.min(end)),
+                None => return Ok(Some((0, 0))),
+            }
+        }
+        if let Some(id) = self.tracking_id {
+            match index.tracking_range(id) {
+                Some((start, end)) => range = (range.0.max(start), range.1.min(end)),
+                None => return Ok(Some((0, 0))),
+            }
+        }
+
+        Ok(Some((range.0, range.1.max(range.0))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::helpers::time::TimeSpec;
+
+    #[test]
+    fn time_bound() {
+        assert_eq!(
+            TimeBound::from_str("30415793489711").unwrap(),
+            TimeBound::Absolute(30415793489711)
+        );
+        assert_eq!(
+            TimeBound::from_str("+1500ms").unwrap(),
+            TimeBound::Relative(1_500_000_000)
+        );
+        assert_eq!(
+            TimeBound::from_str("+2m").unwrap(),
+            TimeBound::Relative(120_000_000_000)
+        );
+        assert!(TimeBound::from_str("+2").is_err());
+        assert!(TimeBound::from_str("2s").is_err());
+        assert!(TimeBound::from_str("+99999999999h").is_err());
+
+        let mut startup = StartupEvent {
+            retis_version: String::new(),
+            cmdline: String::new(),
+            clock_monotonic_offset: TimeSpec::new(0, 0),
+            machine: MachineInfo {
+                kernel_release: String::new(),
+                kernel_version: String::new(),
+                hardware_name: String::new(),
+                kernel_build_id: None,
+            },
+            collection: None,
+            split_file: None,
+            section_versions: None,
+        };
+        assert!(TimeBound::Relative(10).resolve(Some(&startup)).is_err());
+        assert_eq!(TimeBound::Absolute(10).resolve(None).unwrap(), 10);
+
+        startup.collection = Some(CollectionInfo {
+            start_time: 1000,
+            probes: Vec::new(),
+            filters: Vec::new(),
+            containers: None,
+            kube: None,
+        });
+        assert_eq!(
+            TimeBound::Relative(10).resolve(Some(&startup)).unwrap(),
+            1010
+        );
+    }
+
+    fn timestamps(selection: EventSelection) -> Vec<u64> {
+        let mut factory =
+            FileEventsFactory::from_path("test_data/test_events_packets.json").unwrap();
+        let mut selector = selection.selector(factory.startup()).unwrap();
+
+        let mut timestamps = Vec::new();
+        while let Some(event) = selector.next_event(&mut factory).unwrap() {
+            if event.startup.is_none() {
+                timestamps.push(event.common.unwrap().timestamp);
+            }
+        }
+        timestamps
+    }
+
+    #[test]
+    fn selector() {
+        let all = timestamps(EventSelection::default());
+        assert!(all.len() > 4);
+
+        let first = timestamps(EventSelection {
+            first: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(first, all[..2]);
+
+        let last = timestamps(EventSelection {
+            last: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(last, all[all.len() - 2..]);
+
+        let range = timestamps(EventSelection {
+            since: Some(TimeBound::Absolute(all[1])),
+            until: Some(TimeBound::Absolute(all[3])),
+            ..Default::default()
+        });
+        assert!(range.iter().all(|ts| *ts >= all[1] && *ts <= all[3]));
+        assert!(range.contains(&all[1]) && range.contains(&all[3]));
+    }
+}
@@ -3,28 +3,89 @@
 //! EventSeries is a collection of sorted Events.
 //!
 //! Events can be added to EventSeries in any order and it will internally arrange them based on
-//! their TrackingInfo.
+//! sort keys, by default their TrackingInfo.
 
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap};
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use clap::ValueEnum;
 
 use crate::events::*;
 
-#[derive(Default)]
+/// Key events can be sorted by. Events sharing the same values for all the
+/// keys but `Time` are grouped in a series.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum SortKey {
+    /// Timestamp of the first event of a series.
+    Time,
+    /// Packet the event is about, using the tracking information.
+    TrackingId,
+    /// Packet 5-tuple.
+    Flow,
+    /// Probe the event was generated by.
+    Probe,
+}
+
+/// Values of the grouping keys of an event. Unused keys are None.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SeriesGroup {
+    tracking_id: Option<u128>,
+    flow: Option<String>,
+    probe: Option<String>,
+}
+
+/// Value of a key used to order series.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum OrderValue {
+    Time(u64),
+    /// Timestamp and original head of the packet, as in `TrackingInfo`.
+    /// Untracked events use their own timestamp.
+    Tracking(u64, u64),
+    Text(Option<String>),
+}
+
+/// Order of a series, following the sort keys. Ties are broken using the
+/// timestamp of the first event, then the order events were added.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesOrder {
+    values: Vec<OrderValue>,
+    timestamp: u64,
+    seq: u64,
+}
+
 pub(crate) struct EventSorter {
-    series: BTreeMap<TrackingInfo, Vec<Event>>,
-    untracked: VecDeque<Event>,
+    keys: Vec<SortKey>,
+    /// Series, in order, with their group if any. Events not having values
+    /// for all grouping keys are in their own series.
+    series: BTreeMap<SeriesOrder, (Option<SeriesGroup>, Vec<Event>)>,
+    /// Order of the series, by group.
+    groups: HashMap<SeriesGroup, SeriesOrder>,
+    /// Number of events added so far.
+    seq: u64,
     n_events: usize,
     flow_info: HashMap<FlowId, OvsFlowInfoEvent>,
 }
 
+impl Default for EventSorter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EventSorter {
-    /// Creates a empty EventSorter.
+    /// Creates a empty EventSorter, grouping events by tracking id.
     pub(crate) fn new() -> Self {
+        Self::with_keys(&[SortKey::TrackingId])
+    }
+
+    /// Creates a empty EventSorter, grouping and ordering events following
+    /// the given keys.
+    pub(crate) fn with_keys(keys: &[SortKey]) -> Self {
         EventSorter {
+            keys: keys.to_vec(),
             series: BTreeMap::new(),
-            untracked: VecDeque::new(),
+            groups: HashMap::new(),
+            seq: 0,
             n_events: 0,
             flow_info: HashMap::new(),
         }
@@ -35,15 +96,94 @@ impl EventSorter {
         self.n_events
     }
 
-    fn enrich_ovs_lookup(&mut self, ovs: &mut OvsEvent) {
-        if let OvsEvent::DpLookup {
-            flow_lookup: lookup,
-        } = ovs
-        {
-            if let Some(info) = self.flow_info.get(&lookup.flow_id()) {
-                lookup.dpflow = info.dpflow.clone();
-                lookup.ofpflows = info.ofpflows.clone();
+    fn enrich_ovs_flow(&mut self, ovs: &mut OvsEvent) {
+        match ovs {
+            OvsEvent::DpLookup {
+                flow_lookup: lookup,
+            } => {
+                if let Some(info) = self.flow_info.get(&lookup.flow_id()) {
+                    lookup.dpflow = info.dpflow.clone();
+                    lookup.ofpflows = info.ofpflows.clone();
+                }
             }
+            OvsEvent::FlowUpdate { flow_update } => {
+                if let Some(info) = self.flow_info.get(&flow_update.flow_id()) {
+                    flow_update.dpflow = info.dpflow.clone();
+                    flow_update.ofpflows = info.ofpflows.clone();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn flow(event: &Event) -> Option<String> {
+        event
+            .packet
+            .as_ref()
+            .and_then(|p| p.data.flow())
+            .map(|flow| flow.to_string())
+    }
+
+    fn probe(event: &Event) -> Option<String> {
+        match (&event.kernel, &event.userspace) {
+            (Some(kernel), _) => Some(format!("{}/{}", kernel.probe_type, kernel.symbol)),
+            (_, Some(user)) => Some(format!("{}/{}", user.probe_type, user.symbol)),
+            _ => None,
+        }
+    }
+
+    /// Get the group of an event, if it has values for all the grouping
+    /// keys.
+    fn group(&self, event: &Event) -> Option<SeriesGroup> {
+        let mut group = SeriesGroup {
+            tracking_id: None,
+            flow: None,
+            probe: None,
+        };
+
+        for key in self.keys.iter() {
+            match key {
+                SortKey::Time => (),
+                SortKey::TrackingId => {
+                    group.tracking_id = Some(event.tracking.as_ref()?.skb.tracking_id())
+                }
+                SortKey::Flow => group.flow = Some(Self::flow(event)?),
+                SortKey::Probe => group.probe = Some(Self::probe(event)?),
+            }
+        }
+
+        match group.tracking_id.is_some() || group.flow.is_some() || group.probe.is_some() {
+            true => Some(group),
+            false => None,
+        }
+    }
+
+    /// Get the order of a new series, starting with a given event.
+    fn order(&self, event: &Event) -> SeriesOrder {
+        let timestamp = event
+            .common
+            .as_ref()
+            .map(|c| c.timestamp)
+            .unwrap_or_default();
+
+        SeriesOrder {
+            values: self
+                .keys
+                .iter()
+                .map(|key| match key {
+                    SortKey::Time => OrderValue::Time(timestamp),
+                    SortKey::TrackingId => match &event.tracking {
+                        Some(tracking) => {
+                            OrderValue::Tracking(tracking.skb.timestamp, tracking.skb.orig_head)
+                        }
+                        None => OrderValue::Tracking(timestamp, 0),
+                    },
+                    SortKey::Flow => OrderValue::Text(Self::flow(event)),
+                    SortKey::Probe => OrderValue::Text(Self::probe(event)),
+                })
+                .collect(),
+            timestamp,
+            seq: self.seq,
         }
     }
 
@@ -55,82 +195,145 @@ impl EventSorter {
                 .insert(flow_info.flow_id(), flow_info.clone());
         }
 
-        match &event.tracking {
-            Some(track) => match self.series.get_mut(track) {
-                Some(series) => {
-                    series.push(event);
-                }
-                None => {
-                    self.series.insert(track.clone(), vec![event]);
-                }
-            },
+        let group = self.group(&event);
+        let order = match group.as_ref().and_then(|g| self.groups.get(g)) {
+            Some(order) => order.clone(),
             None => {
-                self.untracked.push_back(event);
+                let order = self.order(&event);
+                if let Some(group) = &group {
+                    self.groups.insert(group.clone(), order.clone());
+                }
+                order
             }
-        }
+        };
+
+        self.series
+            .entry(order)
+            .or_insert_with(|| (group, Vec::new()))
+            .1
+            .push(event);
+        self.seq += 1;
         self.n_events += 1;
     }
 
-    /// Removes and returns Events of the oldest series in a Vector.
+    /// Removes and returns Events of the oldest series (the first one
+    /// following the sort keys) in a Vector.
     pub(crate) fn pop_oldest(&mut self) -> Result<Option<EventSeries>> {
-        Ok(if self.n_events == 0 {
-            None
-        } else if self.untracked.is_empty() {
-            self.pop_oldest_series()
-        } else if self.series.is_empty() {
-            self.pop_oldest_untracked().map(|e| vec![e])
-        } else {
-            // Pop whatever is oldest
-            // It's safe to unwrap because we've already checked both series and untracked are
-            // non-empty.
-            if self.series.iter().next().unwrap().0.skb.timestamp
-                < self
-                    .untracked
-                    .front()
-                    .unwrap()
-                    .common
-                    .as_ref()
-                    .map(|c| c.timestamp)
-                    .ok_or_else(|| anyhow!("malformed event: no common section"))?
-            {
-                self.pop_oldest_series()
-            } else {
-                self.pop_oldest_untracked().map(|e| vec![e])
-            }
+        let Some((_, (group, mut events))) = self.series.pop_first() else {
+            return Ok(None);
+        };
+        if let Some(group) = group {
+            self.groups.remove(&group);
         }
-        .map(|v| EventSeries { events: v }))
+        self.n_events -= events.len();
+
+        // Enrich flow lookups and updates at dequeue time to catch
+        // FlowInfoEvents that came after them.
+        events
+            .iter_mut()
+            .filter_map(|e| e.ovs.as_mut())
+            .for_each(|o| self.enrich_ovs_flow(o));
+
+        Ok(Some(EventSeries { events }))
     }
+}
 
-    fn pop_oldest_untracked(&mut self) -> Option<Event> {
-        match self.untracked.pop_front() {
-            Some(e) => {
-                self.n_events -= 1;
-                Some(e)
-            }
-            None => None,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp: u64, symbol: &str, tracking: Option<(u64, u64)>) -> Event {
+        Event {
+            common: Some(CommonEvent {
+                timestamp,
+                ..Default::default()
+            }),
+            kernel: Some(KernelEvent {
+                symbol: symbol.to_string(),
+                probe_type: "kprobe".to_string(),
+                ..Default::default()
+            }),
+            tracking: tracking.map(|(timestamp, orig_head)| TrackingInfo {
+                skb: SkbTrackingEvent {
+                    orig_head,
+                    timestamp,
+                    skb: 0,
+                },
+                idx: 0,
+            }),
+            ..Default::default()
         }
     }
 
-    fn pop_oldest_series(&mut self) -> Option<Vec<Event>> {
-        // TODO: Use pop_first when it's stable to avoid having to clone the key.
-        match self.series.iter().next() {
-            Some((key, _)) => {
-                let key = key.clone();
-                match self.series.remove(&key) {
-                    Some(mut series) => {
-                        self.n_events -= series.len();
-                        // Enrich flow lookups at dequeue time to catch FlowInfoEvents that came
-                        // after the Lookup one.
-                        series
-                            .iter_mut()
-                            .filter_map(|e| e.ovs.as_mut())
-                            .for_each(|o| self.enrich_ovs_lookup(o));
-                        Some(series)
-                    }
-                    None => None,
-                }
-            }
-            None => None,
+    fn events() -> Vec<Event> {
+        vec![
+            event(5, "ip_rcv", Some((3, 1))),
+            event(2, "ip_rcv", Some((1, 2))),
+            event(4, "kfree_skb", None),
+            event(6, "kfree_skb", Some((3, 1))),
+            event(7, "ip_rcv", Some((1, 2))),
+            event(3, "ip_rcv", None),
+        ]
+    }
+
+    fn timestamps(sorter: &mut EventSorter) -> Vec<Vec<u64>> {
+        let mut series = Vec::new();
+        while let Some(s) = sorter.pop_oldest().unwrap() {
+            series.push(
+                s.events
+                    .iter()
+                    .map(|e| e.common.as_ref().unwrap().timestamp)
+                    .collect(),
+            );
         }
+        assert_eq!(sorter.len(), 0);
+        series
+    }
+
+    fn sort(keys: &[SortKey]) -> Vec<Vec<u64>> {
+        let mut sorter = EventSorter::with_keys(keys);
+        events().into_iter().for_each(|e| sorter.add(e));
+        assert_eq!(sorter.len(), 6);
+        timestamps(&mut sorter)
+    }
+
+    #[test]
+    fn sort_keys() {
+        use SortKey::*;
+
+        // Untracked events are ordered with packets by timestamp.
+        assert_eq!(
+            sort(&[TrackingId]),
+            vec![vec![2, 7], vec![3], vec![5, 6], vec![4]]
+        );
+        assert_eq!(
+            sort(&[Time]),
+            vec![vec![2], vec![3], vec![4], vec![5], vec![6], vec![7]]
+        );
+        assert_eq!(sort(&[Probe]), vec![vec![5, 2, 7, 3], vec![4, 6]]);
+        assert_eq!(sort(&[Time, Probe]), vec![vec![4, 6], vec![5, 2, 7, 3]]);
+        assert_eq!(
+            sort(&[TrackingId, Probe]),
+            vec![vec![2, 7], vec![3], vec![5], vec![6], vec![4]]
+        );
+
+        // Ties are broken using the order events were added.
+        let mut sorter = EventSorter::with_keys(&[Time]);
+        sorter.add(event(1, "z", None));
+        sorter.add(event(1, "a", None));
+        let symbols: Vec<_> = (0..2)
+            .map(|_| {
+                let series = sorter.pop_oldest().unwrap().unwrap();
+                series.events[0].kernel.as_ref().unwrap().symbol.clone()
+            })
+            .collect();
+        assert_eq!(symbols, vec!["z", "a"]);
+
+        // Events added after their series was popped are in a new one.
+        let mut sorter = EventSorter::new();
+        sorter.add(event(5, "ip_rcv", Some((3, 1))));
+        assert_eq!(timestamps(&mut sorter), vec![vec![5]]);
+        sorter.add(event(6, "kfree_skb", Some((3, 1))));
+        assert_eq!(timestamps(&mut sorter), vec![vec![6]]);
     }
 }
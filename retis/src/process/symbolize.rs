@@ -0,0 +1,48 @@
+//! Symbolization processor.
+//!
+//! User stack traces are resolved to object files and offsets at collection
+//! time. Symbols are resolved at post-processing time using the object files
+//! symbol tables, which requires them to be available on the machine.
+
+use std::{collections::HashMap, path::Path};
+
+use log::debug;
+
+use crate::{core::user::symbols::ElfSymbols, events::*};
+
+/// UserSymbolizer resolves the symbols of user stack frames.
+#[derive(Default)]
+pub(crate) struct UserSymbolizer {
+    /// Cache of object file path -> symbols, if they could be loaded.
+    objects: HashMap<String, Option<ElfSymbols>>,
+}
+
+impl UserSymbolizer {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process one event, resolving the symbols of its user stack frames.
+    pub(crate) fn process_one(&mut self, event: &mut Event) {
+        let Some(user_stack) = event.user_stack.as_mut() else {
+            return;
+        };
+
+        user_stack
+            .frames
+            .iter_mut()
+            .filter(|frame| frame.symbol.is_none())
+            .for_each(|frame| {
+                let (Some(path), Some(offset)) = (&frame.path, frame.offset) else {
+                    return;
+                };
+
+                let symbols = self.objects.entry(path.clone()).or_insert_with(|| {
+                    ElfSymbols::from_path(Path::new(path))
+                        .map_err(|e| debug!("Could not load symbols from {path}: {e}"))
+                        .ok()
+                });
+                frame.symbol = symbols.as_ref().and_then(|s| s.lookup(offset));
+            });
+    }
+}
@@ -137,6 +137,15 @@ impl AddTracking {
                 } => {
                     self.process_skb(event)?;
                 }
+                FlowUpdate { flow_update } => {
+                    // Only flows installed for a tracked upcall can be linked
+                    // to a packet.
+                    if let Some(queue_id) = flow_update.queue_id {
+                        let info = self.lookup_ovs_queue(queue_id)?;
+                        info.lock().unwrap().idx += 1;
+                        Self::insert_info(event, &info)?;
+                    }
+                }
             }
         } else {
             // It's not an OVS event, try skb-only tracking.
@@ -178,6 +187,16 @@ impl AddTracking {
                 }
             };
             Self::insert_info(event, &info)?;
+
+            // Skbs created out of this one (copies, expansions) belong to the
+            // same packet journey: make their tracking id point to this
+            // series.
+            if let Some(relation) = &event.skb_relation {
+                self.skb_tracking
+                    .entry(relation.child.tracking_id())
+                    .or_insert_with(|| info.clone());
+            }
+
             Ok(Some(info))
         } else {
             Ok(None)
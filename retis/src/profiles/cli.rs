@@ -65,11 +65,11 @@ impl ProfileCmd {
                         println!("{}:", entry.to_str().unwrap_or("unknown"));
                     }
                     for profile in profiles.drain(..) {
-                        println!(
-                            "  {: <20} {}",
-                            profile.name,
-                            profile.about.unwrap_or(String::new()),
-                        );
+                        let name = match profile.aliases.is_empty() {
+                            true => profile.name,
+                            false => format!("{} ({})", profile.name, profile.aliases.join(", ")),
+                        };
+                        println!("  {: <20} {}", name, profile.about.unwrap_or(String::new()));
                     }
                 }
                 Err(err) => {
@@ -123,6 +123,9 @@ fn default_name() -> String {
 pub(crate) struct Profile {
     /// Name of the profile.
     pub(crate) name: String,
+    /// Alternative names the profile can be referred to.
+    #[serde(default = "Vec::new")]
+    pub(crate) aliases: Vec<String>,
     #[allow(dead_code)]
     /// Version of the Profile API that is being used.
     pub(crate) version: ApiVersion,
@@ -142,6 +145,29 @@ impl Profile {
         ApiVersion::parse(API_VERSION_STR)
     }
 
+    /// Can the profile be referred to using a given name?
+    pub(crate) fn is_named(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|a| a == name)
+    }
+
+    /// Load a profile given either its path or its name; an additional custom
+    /// profiles directory can be provided.
+    pub(crate) fn lookup(name: &Path, custom_dir: Option<&PathBuf>) -> Result<Profile> {
+        match read_to_string(name) {
+            Ok(s) => match Profile::from_str(&s) {
+                Ok(profile) => Ok(profile),
+                Err(e) => bail!("Could not import profile: {e}"),
+            },
+            _ => {
+                let name = match name.to_str() {
+                    Some(name) => name,
+                    None => bail!("Invalid profile name ({})", name.display()),
+                };
+                Profile::find(name, custom_dir)
+            }
+        }
+    }
+
     /// Find a profile; an additional custom profiles directory can be provided.
     pub(crate) fn find(name: &str, custom_dir: Option<&PathBuf>) -> Result<Profile> {
         for path in get_profile_paths(custom_dir)
@@ -172,7 +198,7 @@ impl Profile {
             match Profile::from_file(entry.clone()) {
                 Ok(mut profiles) => {
                     for profile in profiles.drain(..) {
-                        if profile.name.eq(name) {
+                        if profile.is_named(name) {
                             // If we already found a profile this means we
                             // have a name conflict.
                             if found.is_some() {
@@ -257,66 +283,80 @@ impl Profile {
         Ok(None)
     }
 
-    /// Generate cli arguments from a profile. The result is a list of arguments that can be
-    /// concatenated to the ones provided by the user.
-    pub(crate) fn cli_args(&self, subcommand: &str) -> Result<Vec<OsString>> {
-        let mut result = Vec::new();
-        let args = match subcommand {
-            "collect" => {
-                let collect = match self.match_collect()? {
-                    None => {
-                        warn!(
-                            "None of the collect profiles defined in {} were selected",
-                            self.name
-                        );
-                        return Ok(result);
-                    }
-                    Some(collect) => collect,
-                };
-                if collect.name == default_name() {
-                    info!("Applying profile {}", self.name);
-                } else {
-                    info!("Applying profile {}: {}", self.name, collect.name);
+    /// Evaluate the profiles of a subcommand and return the arguments of the
+    /// one that matches, if any.
+    pub(crate) fn subcommand_args(
+        &self,
+        subcommand: &str,
+    ) -> Result<Option<&BTreeMap<String, ArgValue>>> {
+        Ok(match subcommand {
+            "collect" => match self.match_collect()? {
+                None => {
+                    warn!(
+                        "None of the collect profiles defined in {} were selected",
+                        self.name
+                    );
+                    None
                 }
-                &collect.args
-            }
-            "pcap" => {
-                let pcap = match self.match_pcap()? {
-                    None => {
-                        warn!(
-                            "None of the pcap profiles defined in {} were selected",
-                            self.name
-                        );
-                        return Ok(result);
+                Some(collect) => {
+                    if collect.name == default_name() {
+                        info!("Applying profile {}", self.name);
+                    } else {
+                        info!("Applying profile {}: {}", self.name, collect.name);
                     }
-                    Some(pcap) => pcap,
-                };
-                info!("Applying profile {}: {}", self.name, pcap.name);
-                &pcap.args
-            }
-            _ => bail!("Subcommand {subcommand} does not support profile enhancement"),
-        };
-
-        args.iter()
-            .map(|(k, v)| (format!("--{}", &k.replace('_', "-")), v))
-            .for_each(|(k, v)| match v {
-                ArgValue::Single(s) => {
-                    result.push(k.into());
-                    result.push(s.into())
+                    Some(&collect.args)
                 }
-                ArgValue::Sequence(sec) => {
-                    for value in sec.iter() {
-                        result.push(k.clone().into());
-                        result.push(value.into())
-                    }
+            },
+            "pcap" => match self.match_pcap()? {
+                None => {
+                    warn!(
+                        "None of the pcap profiles defined in {} were selected",
+                        self.name
+                    );
+                    None
                 }
-                ArgValue::Flag => result.push(k.into()),
-            });
+                Some(pcap) => {
+                    info!("Applying profile {}: {}", self.name, pcap.name);
+                    Some(&pcap.args)
+                }
+            },
+            _ => bail!("Subcommand {subcommand} does not support profile enhancement"),
+        })
+    }
 
-        Ok(result)
+    /// Generate cli arguments from a profile. The result is a list of arguments that can be
+    /// concatenated to the ones provided by the user.
+    pub(crate) fn cli_args(&self, subcommand: &str) -> Result<Vec<OsString>> {
+        Ok(match self.subcommand_args(subcommand)? {
+            Some(args) => args_to_cli(args.iter()),
+            None => Vec::new(),
+        })
     }
 }
 
+/// Convert profile arguments to cli arguments.
+pub(crate) fn args_to_cli<'a, I>(args: I) -> Vec<OsString>
+where
+    I: Iterator<Item = (&'a String, &'a ArgValue)>,
+{
+    let mut result = Vec::new();
+    args.map(|(k, v)| (format!("--{}", &k.replace('_', "-")), v))
+        .for_each(|(k, v)| match v {
+            ArgValue::Single(s) => {
+                result.push(k.into());
+                result.push(s.into())
+            }
+            ArgValue::Sequence(sec) => {
+                for value in sec.iter() {
+                    result.push(k.clone().into());
+                    result.push(value.into())
+                }
+            }
+            ArgValue::Flag => result.push(k.into()),
+        });
+    result
+}
+
 /// Return the list of paths to be used for profile lookup.
 pub(super) fn get_profile_paths(custom: Option<&PathBuf>) -> Vec<PathBuf> {
     // Paths are inspected in order so keep them ordered by (descending) priority.
@@ -348,6 +388,41 @@ mod tests {
         assert_eq!(p.version, ApiVersion::parse("1.0").unwrap());
     }
 
+    #[test]
+    fn aliases() {
+        let p = Profile::from_str(
+            r#"
+version: 1.0
+name: test
+aliases:
+  - test-alias
+"#,
+        )
+        .unwrap();
+        assert!(p.is_named("test"));
+        assert!(p.is_named("test-alias"));
+        assert!(!p.is_named("foo"));
+
+        let p = Profile::from_str(
+            r#"
+version: 1.0
+name: test
+"#,
+        )
+        .unwrap();
+        assert!(p.aliases.is_empty());
+        assert!(p.is_named("test"));
+    }
+
+    #[test]
+    fn builtin_profiles() {
+        for name in ["dropmon", "drop-monitor", "routing", "ovs-upcall"] {
+            assert!(Profile::find_from(Path::new("profiles/"), name)
+                .unwrap()
+                .is_some());
+        }
+    }
+
     #[test]
     fn collect_when_version() {
         fn version_cond(s: &'static str) -> VersionCondition {
@@ -34,7 +34,7 @@ pub(crate) struct ApiVersion {
 
 /// Possible Version compatibility results.
 #[derive(Debug, PartialEq)]
-pub(super) enum ApiVersionSupport {
+pub(crate) enum ApiVersionSupport {
     Full,
     Partial,
     NotSupported,
@@ -48,7 +48,7 @@ impl ApiVersion {
 
     /// Assuming `self` is retis' supported API version, return whether parsing the ApiVersion
     /// referenced by `profile` is supported.
-    pub(super) fn supports(&self, profile: &ApiVersion) -> Result<ApiVersionSupport> {
+    pub(crate) fn supports(&self, profile: &ApiVersion) -> Result<ApiVersionSupport> {
         if self.major == profile.major {
             if self.minor >= profile.minor {
                 Ok(ApiVersionSupport::Full)